@@ -0,0 +1 @@
+ALTER TABLE mint_projects DROP COLUMN cip68_ref_addr;
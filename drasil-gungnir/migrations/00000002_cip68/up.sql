@@ -0,0 +1 @@
+ALTER TABLE mint_projects ADD COLUMN cip68_ref_addr VARCHAR;
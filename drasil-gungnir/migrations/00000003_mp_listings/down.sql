@@ -0,0 +1,2 @@
+DROP TABLE mp_listing_events;
+DROP TABLE mp_listings;
@@ -0,0 +1,40 @@
+    CREATE TABLE mp_listings (
+        id BIGSERIAL PRIMARY KEY,
+        contract_addr VARCHAR(140) NOT NULL,
+        utxo VARCHAR(80) NOT NULL,
+        policy_id VARCHAR(56) NOT NULL,
+        tokenname VARCHAR(64) NOT NULL,
+        seller_pkh VARCHAR(56) NOT NULL,
+        price BIGINT NOT NULL,
+        royalties_rate BIGINT NOT NULL,
+        royalties_pkh VARCHAR(56),
+        status VARCHAR(20) NOT NULL,
+        buyer_addr VARCHAR(140),
+        listed_slot BIGINT NOT NULL,
+        last_slot BIGINT NOT NULL,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+    );
+
+    CREATE TABLE mp_listing_events (
+        id BIGSERIAL PRIMARY KEY,
+        listing_id BIGINT NOT NULL REFERENCES mp_listings(id) ON DELETE CASCADE,
+        event VARCHAR(20) NOT NULL,
+        txhash VARCHAR(120) NOT NULL,
+        utxo VARCHAR(80) NOT NULL,
+        price BIGINT NOT NULL,
+        buyer_addr VARCHAR(140),
+        slot BIGINT NOT NULL,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+    );
+
+    CREATE TRIGGER set_timestamp
+    BEFORE UPDATE ON mp_listings
+    FOR EACH ROW
+    EXECUTE PROCEDURE trigger_set_timestamp();
+
+    ALTER TABLE mp_listings ADD CONSTRAINT unique_mp_listing_utxo UNIQUE(utxo);
+    CREATE INDEX mp_listings_policy_status ON mp_listings(policy_id, status);
+    CREATE INDEX mp_listings_seller ON mp_listings(seller_pkh);
+    CREATE INDEX mp_listing_events_listing ON mp_listing_events(listing_id);
+    CREATE INDEX mp_listing_events_slot ON mp_listing_events(slot);
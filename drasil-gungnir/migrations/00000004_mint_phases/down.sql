@@ -0,0 +1,2 @@
+ALTER TABLE mint_rewards DROP COLUMN phase_id;
+DROP TABLE mint_phases;
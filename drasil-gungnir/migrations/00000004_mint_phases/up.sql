@@ -0,0 +1,24 @@
+    CREATE TABLE mint_phases (
+        id BIGSERIAL PRIMARY KEY,
+        project_id BIGINT NOT NULL REFERENCES mint_projects(id) ON DELETE CASCADE,
+        phase_name VARCHAR(64) NOT NULL,
+        start_slot BIGINT NOT NULL,
+        end_slot BIGINT,
+        price BIGINT NOT NULL,
+        pay_addr VARCHAR(140),
+        max_per_wallet INT,
+        whitelist_id BIGINT,
+        gate_policy VARCHAR(56),
+        created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        UNIQUE (project_id, phase_name)
+    );
+
+    CREATE TRIGGER set_timestamp
+    BEFORE UPDATE ON mint_phases
+    FOR EACH ROW
+    EXECUTE PROCEDURE trigger_set_timestamp();
+
+    CREATE INDEX mint_phases_project ON mint_phases(project_id, start_slot);
+
+    ALTER TABLE mint_rewards ADD COLUMN phase_id BIGINT REFERENCES mint_phases(id) ON DELETE SET NULL;
@@ -0,0 +1,6 @@
+DROP INDEX mint_rewards_project_pay;
+DROP INDEX mint_rewards_project_stake;
+ALTER TABLE mint_rewards DROP COLUMN wl_entry;
+ALTER TABLE mint_rewards DROP COLUMN stake_addr;
+ALTER TABLE mint_projects DROP COLUMN max_mint_p_wl_entry;
+ALTER TABLE mint_projects DROP COLUMN max_mint_p_stake;
@@ -0,0 +1,8 @@
+    ALTER TABLE mint_projects ADD COLUMN max_mint_p_stake INT;
+    ALTER TABLE mint_projects ADD COLUMN max_mint_p_wl_entry INT;
+
+    ALTER TABLE mint_rewards ADD COLUMN stake_addr VARCHAR;
+    ALTER TABLE mint_rewards ADD COLUMN wl_entry BIGINT;
+
+    CREATE INDEX mint_rewards_project_stake ON mint_rewards(project_id, stake_addr);
+    CREATE INDEX mint_rewards_project_pay ON mint_rewards(project_id, pay_addr);
@@ -0,0 +1,6 @@
+DROP TABLE nft_reveals;
+ALTER TABLE mint_projects DROP COLUMN revealed_at;
+ALTER TABLE mint_projects DROP COLUMN reveal_provenance;
+ALTER TABLE mint_projects DROP COLUMN reveal_seed;
+ALTER TABLE mint_projects DROP COLUMN reveal_commitment;
+ALTER TABLE mint_projects DROP COLUMN reveal_placeholder;
@@ -0,0 +1,22 @@
+    ALTER TABLE mint_projects ADD COLUMN reveal_placeholder TEXT;
+    ALTER TABLE mint_projects ADD COLUMN reveal_commitment VARCHAR(64);
+    ALTER TABLE mint_projects ADD COLUMN reveal_seed VARCHAR(64);
+    ALTER TABLE mint_projects ADD COLUMN reveal_provenance VARCHAR(64);
+    ALTER TABLE mint_projects ADD COLUMN revealed_at TIMESTAMPTZ;
+
+    CREATE TABLE nft_reveals (
+        project_id BIGINT NOT NULL REFERENCES mint_projects(id) ON DELETE CASCADE,
+        asset_name_b BYTEA NOT NULL,
+        pool_index INT NOT NULL,
+        metadata TEXT NOT NULL,
+        needs_update BOOLEAN NOT NULL,
+        update_tx VARCHAR(64),
+        created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        PRIMARY KEY (project_id, asset_name_b)
+    );
+
+    CREATE TRIGGER set_timestamp
+    BEFORE UPDATE ON nft_reveals
+    FOR EACH ROW
+    EXECUTE PROCEDURE trigger_set_timestamp();
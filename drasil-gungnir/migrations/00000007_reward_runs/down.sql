@@ -0,0 +1,2 @@
+DROP TABLE reward_run_entries;
+DROP TABLE reward_runs;
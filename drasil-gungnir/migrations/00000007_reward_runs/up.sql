@@ -0,0 +1,35 @@
+    CREATE TABLE reward_runs (
+        id BIGSERIAL PRIMARY KEY,
+        contract_id BIGINT NOT NULL,
+        user_id BIGINT NOT NULL,
+        fingerprint VARCHAR(100) NOT NULL,
+        epoch BIGINT NOT NULL,
+        inputs_hash VARCHAR(64) NOT NULL,
+        total_earned NUMERIC NOT NULL,
+        entries INT NOT NULL,
+        status VARCHAR(16) NOT NULL,
+        replaces BIGINT REFERENCES reward_runs(id),
+        created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+    );
+
+    CREATE TABLE reward_run_entries (
+        id BIGSERIAL PRIMARY KEY,
+        run_id BIGINT NOT NULL REFERENCES reward_runs(id) ON DELETE CASCADE,
+        stake_addr VARCHAR(100) NOT NULL,
+        payment_addr VARCHAR(200) NOT NULL,
+        earned NUMERIC NOT NULL,
+        tot_earned_before NUMERIC NOT NULL,
+        tot_earned NUMERIC NOT NULL,
+        new_entry BOOLEAN NOT NULL,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+    );
+
+    CREATE TRIGGER set_timestamp
+    BEFORE UPDATE ON reward_runs
+    FOR EACH ROW
+    EXECUTE PROCEDURE trigger_set_timestamp();
+
+    CREATE UNIQUE INDEX reward_runs_applied ON reward_runs(contract_id, user_id, fingerprint, epoch) WHERE status = 'applied';
+    CREATE INDEX reward_runs_token_epoch ON reward_runs(contract_id, user_id, fingerprint, epoch);
+    CREATE INDEX reward_run_entries_run ON reward_run_entries(run_id);
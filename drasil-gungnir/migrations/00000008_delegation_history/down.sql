@@ -0,0 +1,2 @@
+DROP TABLE delegation_history;
+ALTER TABLE token_whitelist DROP COLUMN loyalty_schedule;
@@ -0,0 +1,21 @@
+    ALTER TABLE token_whitelist ADD COLUMN loyalty_schedule JSONB;
+
+    CREATE TABLE delegation_history (
+        id BIGSERIAL PRIMARY KEY,
+        stake_addr VARCHAR(100) NOT NULL,
+        pool_id VARCHAR(100) NOT NULL,
+        first_epoch BIGINT NOT NULL,
+        last_epoch BIGINT NOT NULL,
+        streak BIGINT NOT NULL,
+        total_epochs BIGINT NOT NULL,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+    );
+
+    CREATE TRIGGER set_timestamp
+    BEFORE UPDATE ON delegation_history
+    FOR EACH ROW
+    EXECUTE PROCEDURE trigger_set_timestamp();
+
+    CREATE UNIQUE INDEX delegation_history_stake_pool ON delegation_history(stake_addr, pool_id);
+    CREATE INDEX delegation_history_pool ON delegation_history(pool_id);
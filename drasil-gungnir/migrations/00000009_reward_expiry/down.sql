@@ -0,0 +1,2 @@
+ALTER TABLE rewards DROP COLUMN tot_expired;
+ALTER TABLE token_whitelist DROP COLUMN expiry_epochs;
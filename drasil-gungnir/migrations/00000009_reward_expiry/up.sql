@@ -0,0 +1,2 @@
+    ALTER TABLE token_whitelist ADD COLUMN expiry_epochs BIGINT;
+    ALTER TABLE rewards ADD COLUMN tot_expired NUMERIC NOT NULL DEFAULT 0;
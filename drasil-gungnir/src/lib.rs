@@ -1,6 +1,7 @@
 #[macro_use]
 extern crate diesel;
 pub mod error;
+pub mod marketplace;
 pub mod minting;
 pub mod rewards;
 pub mod schema;
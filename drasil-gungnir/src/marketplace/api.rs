@@ -0,0 +1,324 @@
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+
+use super::models::*;
+use crate::error::RWDError;
+use crate::schema::{mp_listing_events, mp_listings};
+use crate::*;
+
+const DEFAULT_PER_PAGE: i64 = 50;
+const MAX_PER_PAGE: i64 = 500;
+/// Highest page whose offset (`page * per_page`) cannot overflow
+const MAX_PAGE: i64 = i64::MAX / MAX_PER_PAGE;
+
+fn paging(page: Option<i64>, per_page: Option<i64>) -> (i64, i64) {
+    (
+        page.unwrap_or(0).clamp(0, MAX_PAGE),
+        per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE),
+    )
+}
+
+fn status_after(event: &str) -> &'static str {
+    match event {
+        EVENT_BUY => LISTING_SOLD,
+        EVENT_CANCEL => LISTING_CANCELLED,
+        _ => LISTING_LISTED,
+    }
+}
+
+impl MpListing {
+    pub fn find(id_in: i64) -> Result<MpListing, RWDError> {
+        let conn = &mut establish_connection()?;
+        let result = mp_listings::table
+            .filter(mp_listings::id.eq(id_in))
+            .first::<MpListing>(conn)?;
+        Ok(result)
+    }
+
+    /// Open listing locked in the utxo `utxo_in` ('txhash#index')
+    pub fn find_open_by_utxo(utxo_in: &String) -> Result<Option<MpListing>, RWDError> {
+        let conn = &mut establish_connection()?;
+        let result = mp_listings::table
+            .filter(mp_listings::utxo.eq(utxo_in))
+            .filter(mp_listings::status.eq(LISTING_LISTED))
+            .first::<MpListing>(conn)
+            .optional()?;
+        Ok(result)
+    }
+
+    /// Stores a new listing together with its 'list' event
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_listing(
+        contract_addr: &String,
+        utxo: &String,
+        policy_id: &String,
+        tokenname: &String,
+        seller_pkh: &String,
+        price: &i64,
+        royalties_rate: &i64,
+        royalties_pkh: Option<&String>,
+        txhash: &String,
+        slot: &i64,
+    ) -> Result<MpListing, RWDError> {
+        let conn = &mut establish_connection()?;
+        let new_listing = MpListingNew {
+            contract_addr,
+            utxo,
+            policy_id,
+            tokenname,
+            seller_pkh,
+            price,
+            royalties_rate,
+            royalties_pkh,
+            status: LISTING_LISTED,
+            listed_slot: slot,
+            last_slot: slot,
+        };
+        conn.transaction(|conn| {
+            let listing = diesel::insert_into(mp_listings::table)
+                .values(&new_listing)
+                .get_result::<MpListing>(conn)?;
+            listing.add_event(conn, EVENT_LIST, txhash, utxo, price, None, slot)?;
+            Ok(listing)
+        })
+    }
+
+    /// The listed token was relisted into `utxo_in` with the price `price_in`
+    pub fn update_listing(
+        &self,
+        utxo_in: &String,
+        price_in: &i64,
+        txhash: &String,
+        slot: &i64,
+    ) -> Result<MpListing, RWDError> {
+        let conn = &mut establish_connection()?;
+        conn.transaction(|conn| {
+            let listing = diesel::update(mp_listings::table.find(self.id))
+                .set((
+                    mp_listings::utxo.eq(utxo_in),
+                    mp_listings::price.eq(price_in),
+                    mp_listings::last_slot.eq(slot),
+                ))
+                .get_result::<MpListing>(conn)?;
+            listing.add_event(conn, EVENT_UPDATE, txhash, utxo_in, price_in, None, slot)?;
+            Ok(listing)
+        })
+    }
+
+    /// Closes the listing with a 'buy' or 'cancel' event
+    pub fn close_listing(
+        &self,
+        event: &str,
+        buyer_addr_in: Option<&String>,
+        txhash: &String,
+        slot: &i64,
+    ) -> Result<MpListing, RWDError> {
+        if event != EVENT_BUY && event != EVENT_CANCEL {
+            return Err(RWDError::new(&format!(
+                "a listing can not be closed by a '{event}' event"
+            )));
+        }
+        let conn = &mut establish_connection()?;
+        conn.transaction(|conn| {
+            let listing = diesel::update(mp_listings::table.find(self.id))
+                .set((
+                    mp_listings::status.eq(status_after(event)),
+                    mp_listings::buyer_addr.eq(buyer_addr_in),
+                    mp_listings::last_slot.eq(slot),
+                ))
+                .get_result::<MpListing>(conn)?;
+            listing.add_event(
+                conn,
+                event,
+                txhash,
+                &self.utxo,
+                &self.price,
+                buyer_addr_in,
+                slot,
+            )?;
+            Ok(listing)
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn add_event(
+        &self,
+        conn: &mut PgConnection,
+        event: &str,
+        txhash: &String,
+        utxo: &String,
+        price: &i64,
+        buyer_addr: Option<&String>,
+        slot: &i64,
+    ) -> Result<MpListingEvent, RWDError> {
+        let new_event = MpListingEventNew {
+            listing_id: &self.id,
+            event,
+            txhash,
+            utxo,
+            price,
+            buyer_addr,
+            slot,
+        };
+        Ok(diesel::insert_into(mp_listing_events::table)
+            .values(&new_event)
+            .get_result::<MpListingEvent>(conn)?)
+    }
+
+    /// Listings matching `filter`, the most recently changed first
+    pub fn query(filter: &MpListingFilter) -> Result<MpPage<MpListing>, RWDError> {
+        let conn = &mut establish_connection()?;
+        let (page, per_page) = paging(filter.page, filter.per_page);
+
+        let filtered = || {
+            let mut q = mp_listings::table.into_boxed();
+            if let Some(addr) = &filter.contract_addr {
+                q = q.filter(mp_listings::contract_addr.eq(addr));
+            }
+            if let Some(policy) = &filter.policy_id {
+                q = q.filter(mp_listings::policy_id.eq(policy));
+            }
+            if let Some(seller) = &filter.seller_pkh {
+                q = q.filter(mp_listings::seller_pkh.eq(seller));
+            }
+            if let Some(min) = filter.min_price {
+                q = q.filter(mp_listings::price.ge(min));
+            }
+            if let Some(max) = filter.max_price {
+                q = q.filter(mp_listings::price.le(max));
+            }
+            if let Some(status) = &filter.status {
+                q = q.filter(mp_listings::status.eq(status));
+            }
+            q
+        };
+
+        let total = filtered().count().get_result::<i64>(conn)?;
+        let items = filtered()
+            .order((mp_listings::last_slot.desc(), mp_listings::id.desc()))
+            .limit(per_page)
+            .offset(page * per_page)
+            .load::<MpListing>(conn)?;
+        Ok(MpPage {
+            items,
+            page,
+            per_page,
+            total,
+        })
+    }
+
+    /// Reverts all listing events after `slot`, listings without remaining
+    /// events are removed. Returns the number of reverted events.
+    pub fn rollback(slot_in: i64) -> Result<usize, RWDError> {
+        let conn = &mut establish_connection()?;
+        conn.transaction(|conn| {
+            let reverted = diesel::delete(
+                mp_listing_events::table.filter(mp_listing_events::slot.gt(slot_in)),
+            )
+            .get_results::<MpListingEvent>(conn)?;
+
+            let mut listing_ids = reverted.iter().map(|e| e.listing_id).collect::<Vec<_>>();
+            listing_ids.sort_unstable();
+            listing_ids.dedup();
+
+            for listing_id in listing_ids {
+                let last = mp_listing_events::table
+                    .filter(mp_listing_events::listing_id.eq(listing_id))
+                    .order((mp_listing_events::slot.desc(), mp_listing_events::id.desc()))
+                    .first::<MpListingEvent>(conn)
+                    .optional()?;
+                match last {
+                    Some(last) => {
+                        diesel::update(mp_listings::table.find(listing_id))
+                            .set((
+                                mp_listings::status.eq(status_after(&last.event)),
+                                mp_listings::utxo.eq(&last.utxo),
+                                mp_listings::price.eq(last.price),
+                                mp_listings::buyer_addr.eq(&last.buyer_addr),
+                                mp_listings::last_slot.eq(last.slot),
+                            ))
+                            .execute(conn)?;
+                    }
+                    None => {
+                        diesel::delete(mp_listings::table.find(listing_id)).execute(conn)?;
+                    }
+                }
+            }
+            Ok(reverted.len())
+        })
+    }
+}
+
+impl MpListingEvent {
+    /// History of a listing at the marketplace contract `contract_addr_in` in chain order
+    pub fn history(
+        listing_id_in: i64,
+        contract_addr_in: &String,
+    ) -> Result<Vec<MpListingEvent>, RWDError> {
+        let conn = &mut establish_connection()?;
+        let result = mp_listing_events::table
+            .inner_join(mp_listings::table)
+            .filter(mp_listing_events::listing_id.eq(listing_id_in))
+            .filter(mp_listings::contract_addr.eq(contract_addr_in))
+            .order((mp_listing_events::slot.asc(), mp_listing_events::id.asc()))
+            .select(mp_listing_events::all_columns)
+            .load::<MpListingEvent>(conn)?;
+        Ok(result)
+    }
+
+    /// Purchases at the marketplace contract `contract_addr_in`, the latest first,
+    /// optionally only of the policy `policy_id_in`
+    pub fn sales(
+        contract_addr_in: &String,
+        policy_id_in: Option<&String>,
+        page: Option<i64>,
+        per_page: Option<i64>,
+    ) -> Result<MpPage<MpSale>, RWDError> {
+        let conn = &mut establish_connection()?;
+        let (page, per_page) = paging(page, per_page);
+
+        let filtered = || {
+            let mut q = mp_listing_events::table
+                .inner_join(mp_listings::table)
+                .filter(mp_listing_events::event.eq(EVENT_BUY))
+                .filter(mp_listings::contract_addr.eq(contract_addr_in))
+                .into_boxed();
+            if let Some(policy) = policy_id_in {
+                q = q.filter(mp_listings::policy_id.eq(policy));
+            }
+            q
+        };
+
+        let total = filtered().count().get_result::<i64>(conn)?;
+        let items = filtered()
+            .order((mp_listing_events::slot.desc(), mp_listing_events::id.desc()))
+            .limit(per_page)
+            .offset(page * per_page)
+            .load::<(MpListingEvent, MpListing)>(conn)?
+            .into_iter()
+            .map(|(event, listing)| MpSale { listing, event })
+            .collect();
+        Ok(MpPage {
+            items,
+            page,
+            per_page,
+            total,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paging_is_clamped() {
+        assert_eq!(paging(None, None), (0, DEFAULT_PER_PAGE));
+        assert_eq!(paging(Some(-3), Some(0)), (0, 1));
+        assert_eq!(paging(Some(2), Some(10_000)), (2, MAX_PER_PAGE));
+        for per_page in [None, Some(1), Some(MAX_PER_PAGE), Some(i64::MAX)] {
+            let (page, per_page) = paging(Some(i64::MAX), per_page);
+            assert!(page.checked_mul(per_page).is_some());
+        }
+    }
+}
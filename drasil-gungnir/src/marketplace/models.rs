@@ -0,0 +1,118 @@
+use chrono::serde::ts_seconds::serialize as to_ts;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::schema::{mp_listing_events, mp_listings};
+
+/// Status of a listing which can still be bought
+pub const LISTING_LISTED: &str = "listed";
+/// Status of a listing which was bought
+pub const LISTING_SOLD: &str = "sold";
+/// Status of a listing which was cancelled by the seller
+pub const LISTING_CANCELLED: &str = "cancelled";
+
+/// Token was locked at a marketplace contract
+pub const EVENT_LIST: &str = "list";
+/// Listing was relisted by the seller, e.g. with a new price
+pub const EVENT_UPDATE: &str = "update";
+/// Listing was bought
+pub const EVENT_BUY: &str = "buy";
+/// Listing was cancelled by the seller
+pub const EVENT_CANCEL: &str = "cancel";
+
+#[derive(Queryable, Identifiable, Debug, Clone, Serialize, Deserialize)]
+#[diesel(table_name = mp_listings)]
+pub struct MpListing {
+    pub id: i64,
+    pub contract_addr: String,
+    /// Utxo ('txhash#index') currently holding the listed token
+    pub utxo: String,
+    pub policy_id: String,
+    pub tokenname: String,
+    pub seller_pkh: String,
+    pub price: i64,
+    pub royalties_rate: i64,
+    pub royalties_pkh: Option<String>,
+    pub status: String,
+    pub buyer_addr: Option<String>,
+    pub listed_slot: i64,
+    pub last_slot: i64,
+    #[serde(serialize_with = "to_ts")]
+    pub created_at: DateTime<Utc>,
+    #[serde(serialize_with = "to_ts")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = mp_listings)]
+pub struct MpListingNew<'a> {
+    pub contract_addr: &'a String,
+    pub utxo: &'a String,
+    pub policy_id: &'a String,
+    pub tokenname: &'a String,
+    pub seller_pkh: &'a String,
+    pub price: &'a i64,
+    pub royalties_rate: &'a i64,
+    pub royalties_pkh: Option<&'a String>,
+    pub status: &'a str,
+    pub listed_slot: &'a i64,
+    pub last_slot: &'a i64,
+}
+
+#[derive(Queryable, Identifiable, Associations, Debug, Clone, Serialize, Deserialize)]
+#[diesel(belongs_to(MpListing, foreign_key = listing_id))]
+#[diesel(table_name = mp_listing_events)]
+pub struct MpListingEvent {
+    pub id: i64,
+    pub listing_id: i64,
+    pub event: String,
+    pub txhash: String,
+    pub utxo: String,
+    pub price: i64,
+    pub buyer_addr: Option<String>,
+    pub slot: i64,
+    #[serde(serialize_with = "to_ts")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = mp_listing_events)]
+pub struct MpListingEventNew<'a> {
+    pub listing_id: &'a i64,
+    pub event: &'a str,
+    pub txhash: &'a String,
+    pub utxo: &'a String,
+    pub price: &'a i64,
+    pub buyer_addr: Option<&'a String>,
+    pub slot: &'a i64,
+}
+
+/// Filter for listing queries, unset fields are not filtered
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MpListingFilter {
+    /// Address of the marketplace contract the listings are locked at
+    pub contract_addr: Option<String>,
+    pub policy_id: Option<String>,
+    pub seller_pkh: Option<String>,
+    pub min_price: Option<i64>,
+    pub max_price: Option<i64>,
+    pub status: Option<String>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+/// One page of a query result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MpPage<T> {
+    pub items: Vec<T>,
+    pub page: i64,
+    pub per_page: i64,
+    pub total: i64,
+}
+
+/// A purchase of a listing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MpSale {
+    pub listing: MpListing,
+    pub event: MpListingEvent,
+}
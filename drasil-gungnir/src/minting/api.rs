@@ -4,7 +4,10 @@ use diesel::pg::PgConnection;
 use diesel::prelude::*;
 use diesel::sql_types::{Bool, Bytea, Int8, Nullable, Timestamptz, Varchar};
 
-use super::models::{MintProject, MintProjectNew, MintReward, MintRewardNew, Nft};
+use super::models::{
+    ClaimLimit, ClaimResult, MintPhase, MintPhaseNew, MintProject, MintProjectNew, MintReward,
+    MintRewardNew, Nft, NftClaim, NftReveal, NftRevealNew,
+};
 use crate::error::RWDError;
 use crate::schema::*;
 use crate::*;
@@ -54,6 +57,9 @@ impl MintProject {
         max_mint_p_addr: Option<&'a i32>,
         nft_table_name: &'a String,
         active: &'a bool,
+        cip68_ref_addr: Option<&'a String>,
+        max_mint_p_stake: Option<&'a i32>,
+        max_mint_p_wl_entry: Option<&'a i32>,
     ) -> Result<MintProject, RWDError> {
         let conn = &mut establish_connection()?;
         let new_entry = MintProjectNew {
@@ -73,6 +79,9 @@ impl MintProject {
             max_mint_p_addr,
             nft_table_name,
             active,
+            cip68_ref_addr,
+            max_mint_p_stake,
+            max_mint_p_wl_entry,
         };
         log::debug!("try to insert mint project into db...");
         let q = diesel::insert_into(mint_projects::table)
@@ -87,6 +96,96 @@ impl MintProject {
 
         Ok(result)
     }
+
+    pub fn get_nft_table_names() -> Result<Vec<String>, RWDError> {
+        let conn = &mut establish_connection()?;
+        let result = mint_projects::table
+            .select(mint_projects::nft_table_name)
+            .distinct()
+            .load::<String>(conn)?;
+        Ok(result)
+    }
+
+    /// Switches the project to reveal mode, the NFTs are minted with
+    /// `placeholder` until the reveal assigns their metadata
+    pub fn enable_reveal(
+        &self,
+        placeholder: &String,
+        seed: &String,
+    ) -> Result<MintProject, RWDError> {
+        if self.active || self.reveal_placeholder.is_some() {
+            return Err(RWDError::new(
+                "reveal mode can only be enabled on an inactive project without reveal",
+            ));
+        }
+        let result = diesel::update(mint_projects::table.find(self.id))
+            .set((
+                mint_projects::reveal_placeholder.eq(placeholder),
+                mint_projects::reveal_seed.eq(seed),
+            ))
+            .get_result::<MintProject>(&mut establish_connection()?)?;
+        Ok(result)
+    }
+
+    /// Fixes the metadata pool of a reveal project with the commitment to its
+    /// seed and the `provenance` of the pool, the pool can not change afterwards
+    pub fn commit_reveal(
+        &self,
+        provenance: &String,
+        commitment: &String,
+    ) -> Result<MintProject, RWDError> {
+        if self.active || self.reveal_placeholder.is_none() || self.reveal_commitment.is_some() {
+            return Err(RWDError::new(
+                "only an inactive reveal project without commitment can be committed",
+            ));
+        }
+        let result = diesel::update(
+            mint_projects::table
+                .find(self.id)
+                .filter(mint_projects::reveal_commitment.is_null()),
+        )
+        .set((
+            mint_projects::reveal_commitment.eq(commitment),
+            mint_projects::reveal_provenance.eq(provenance),
+        ))
+        .get_result::<MintProject>(&mut establish_connection()?)?;
+        Ok(result)
+    }
+
+    /// The project mints placeholder metadata as it was not revealed yet
+    pub fn reveal_pending(&self) -> bool {
+        self.reveal_placeholder.is_some() && self.revealed_at.is_none()
+    }
+
+    /// A reveal project can not mint before its metadata pool is committed
+    pub fn reveal_uncommitted(&self) -> bool {
+        self.reveal_placeholder.is_some() && self.reveal_commitment.is_none()
+    }
+
+    /// Stores the metadata the reveal assigned to each NFT of the project
+    pub fn reveal(&self, reveals: &[NftRevealNew]) -> Result<MintProject, RWDError> {
+        if !self.reveal_pending() || self.reveal_uncommitted() {
+            return Err(RWDError::new(
+                "mint project has no committed pending reveal",
+            ));
+        }
+        let conn = &mut establish_connection()?;
+        conn.transaction(|conn| {
+            let project = diesel::update(
+                mint_projects::table
+                    .find(self.id)
+                    .filter(mint_projects::revealed_at.is_null()),
+            )
+            .set(mint_projects::revealed_at.eq(Utc::now()))
+            .get_result::<MintProject>(conn)?;
+            for chunk in reveals.chunks(1000) {
+                diesel::insert_into(nft_reveals::table)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+            Ok(project)
+        })
+    }
 }
 
 /// Ugly Helper Type to manage the dynamic tables
@@ -176,6 +275,146 @@ impl Nft {
         Ok(client)
     }
 
+    /// Fails if the metadata pool of the project is fixed by a reveal commitment
+    async fn check_pool_open(
+        client: &tokio_postgres::Client,
+        project_id: &i64,
+    ) -> Result<(), RWDError> {
+        let committed = client
+            .query(
+                "SELECT id FROM mint_projects WHERE id = $1 AND reveal_commitment IS NOT NULL",
+                &[project_id],
+            )
+            .await?;
+        if !committed.is_empty() {
+            return Err(RWDError::new(
+                "the NFTs of a mint project with a reveal commitment can not change",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Claims a random unminted NFT for `claim` and creates its mint reward.
+    /// Claims of a project are serialized by an advisory lock and the limits
+    /// are checked in the same transaction, so concurrent claims of one wallet
+    /// can not exceed them. `value_of` serializes the value of an asset name.
+    pub async fn claim_nft_within_limits(
+        project: &MintProject,
+        claim: &NftClaim,
+        value_of: impl Fn(&[u8]) -> Result<Vec<u8>, RWDError>,
+    ) -> Result<ClaimResult, RWDError> {
+        let mut client = Nft::db_client().await?;
+        let tx = client.transaction().await?;
+        tx.execute("SELECT pg_advisory_xact_lock($1)", &[&project.id])
+            .await?;
+
+        let phase_id = claim.phase.as_ref().map(|p| p.id);
+        if let Some(limit) = Nft::claim_limits(&tx, project, claim)
+            .await?
+            .into_iter()
+            .find(|l| l.reached())
+        {
+            return Ok(ClaimResult::LimitReached(limit));
+        }
+
+        let table_name = &project.nft_table_name;
+        let rows = tx
+            .query(
+                &format!(
+                    "SELECT asset_name_b FROM {table_name} WHERE project_id = $1 AND claim_addr IS NULL AND tx_hash IS NULL AND minted = false ORDER BY random() LIMIT 1 FOR UPDATE"
+                ),
+                &[&project.id],
+            )
+            .await?;
+        let asset_name_b: Vec<u8> = match rows.first() {
+            Some(row) => row.get(0),
+            None => return Ok(ClaimResult::SoldOut),
+        };
+        tx.execute(
+            &format!(
+                "UPDATE {table_name} SET claim_addr = $1 WHERE project_id = $2 AND asset_name_b = $3"
+            ),
+            &[&claim.pay_addr, &project.id, &asset_name_b],
+        )
+        .await?;
+        let value = value_of(&asset_name_b)?;
+        let row = tx
+            .query_one(
+                "INSERT INTO mint_rewards (project_id, pay_addr, nft_ids, v_nfts_b, processed, minted, phase_id, stake_addr, wl_entry) VALUES ($1, $2, $3, $4, false, false, $5, $6, $7) RETURNING id",
+                &[
+                    &project.id,
+                    &claim.pay_addr,
+                    &vec![asset_name_b],
+                    &vec![value],
+                    &phase_id,
+                    &claim.stake_addr,
+                    &claim.wl_entry,
+                ],
+            )
+            .await?;
+        tx.commit().await?;
+
+        Ok(ClaimResult::Claimed(MintReward::get_mintreward_by_id(
+            row.get(0),
+        )?))
+    }
+
+    /// Claims of a wallet against each limit of the project and its phase
+    pub async fn claim_limits(
+        client: &impl tokio_postgres::GenericClient,
+        project: &MintProject,
+        claim: &NftClaim,
+    ) -> Result<Vec<ClaimLimit>, RWDError> {
+        let claimed = "SELECT COALESCE(SUM(cardinality(nft_ids)), 0)::BIGINT FROM mint_rewards WHERE project_id = $1 AND";
+        let mut limits = Vec::<(&str, i32, i64)>::new();
+        if let Some(max) = project.max_mint_p_addr {
+            let row = client
+                .query_one(
+                    &format!("{claimed} pay_addr = $2"),
+                    &[&project.id, &claim.pay_addr],
+                )
+                .await?;
+            limits.push(("payment address", max, row.get(0)));
+        }
+        if let (Some(max), Some(stake_addr)) = (project.max_mint_p_stake, &claim.stake_addr) {
+            let row = client
+                .query_one(
+                    &format!("{claimed} stake_addr = $2"),
+                    &[&project.id, stake_addr],
+                )
+                .await?;
+            limits.push(("stake address", max, row.get(0)));
+        }
+        if let (Some(max), Some(wl_entry)) = (project.max_mint_p_wl_entry, &claim.wl_entry) {
+            let row = client
+                .query_one(
+                    &format!("{claimed} wl_entry = $2"),
+                    &[&project.id, wl_entry],
+                )
+                .await?;
+            limits.push(("whitelist entry", max, row.get(0)));
+        }
+        if let Some(phase) = &claim.phase {
+            if let Some(max) = phase.max_per_wallet {
+                let row = client
+                    .query_one(
+                        &format!("{claimed} phase_id = $2 AND (pay_addr = $3 OR stake_addr = $4)"),
+                        &[&project.id, &phase.id, &claim.pay_addr, &claim.stake_addr],
+                    )
+                    .await?;
+                limits.push(("phase", max, row.get(0)));
+            }
+        }
+        Ok(limits
+            .into_iter()
+            .map(|(limit, max, claimed)| ClaimLimit {
+                limit: limit.to_string(),
+                claimed,
+                max,
+            })
+            .collect())
+    }
+
     fn diesel_nft_table_definition(
         t: &'_ str,
     ) -> std::result::Result<(diesel_dynamic_schema::Table<&'_ str>, HNft), error::RWDError> {
@@ -214,6 +453,19 @@ impl Nft {
         Ok((table, t_clmns))
     }
 
+    /// Metadata to mint the NFT with, the placeholder of a project with a
+    /// pending reveal or the metadata the reveal assigned
+    pub fn mint_metadata(&self, project: &MintProject) -> Result<Option<String>, RWDError> {
+        if project.reveal_pending() {
+            return Ok(project.reveal_placeholder.clone());
+        }
+        if project.revealed_at.is_some() {
+            let reveal = NftReveal::get_reveal(self.project_id, &self.asset_name_b)?;
+            return Ok(Some(reveal.metadata));
+        }
+        Ok(self.metadata.clone())
+    }
+
     pub fn get_nfts_by_pid(
         conn: &mut PgConnection,
         pid_in: i64,
@@ -486,6 +738,8 @@ impl Nft {
             }
         });
 
+        Nft::check_pool_open(&client, project_id).await?;
+
         let insert_query = format!("INSERT INTO {table_name}
         (project_id, asset_name_b, asset_name, fingerprint, nft_id, file_name, ipfs_hash, metadata, claim_addr, minted, confirmed)
         VALUES
@@ -613,6 +867,7 @@ impl Nft {
         metadata: &'a String,
     ) -> Result<(), RWDError> {
         let client = Nft::db_client().await?;
+        Nft::check_pool_open(&client, pid_in).await?;
         let update_query = format!(
             "UPDATE {table_name} SET metadata = $1 WHERE project_id=$2 AND asset_name_b=$3 AND minted = false",
         );
@@ -628,6 +883,41 @@ impl Nft {
         Ok(())
     }
 
+    // Runs the update built by `query` for each NFT table with `txhash_in` bound as $1
+    fn update_by_txhash(
+        txhash_in: &String,
+        query: impl Fn(&str) -> String,
+    ) -> Result<usize, RWDError> {
+        let conn = &mut establish_connection()?;
+        let mut updated = 0;
+        for table_name in MintProject::get_nft_table_names()? {
+            updated += diesel::sql_query(query(&table_name))
+                .bind::<Varchar, _>(txhash_in)
+                .execute(conn)?;
+        }
+        Ok(updated)
+    }
+
+    /// Sets the NFTs minted in `txhash_in` to confirmed or back to unconfirmed
+    /// after a rollback, returns the number of changed NFTs
+    pub fn set_tx_confirmed(txhash_in: &String, confirmed_in: bool) -> Result<usize, RWDError> {
+        Nft::update_by_txhash(txhash_in, |table_name| {
+            format!(
+                "UPDATE {table_name} SET confirmed = {confirmed_in} WHERE tx_hash = $1 AND minted = true"
+            )
+        })
+    }
+
+    /// Makes the NFTs of a transaction which can not be included anymore
+    /// available for minting again
+    pub fn release_tx(txhash_in: &String) -> Result<usize, RWDError> {
+        Nft::update_by_txhash(txhash_in, |table_name| {
+            format!(
+                "UPDATE {table_name} SET minted = false, tx_hash = NULL, confirmed = false WHERE tx_hash = $1"
+            )
+        })
+    }
+
     /*
     TODO:
       On Mint-Project creation a user needs to set if a mint project will contain double IPFS images / filenames.
@@ -756,6 +1046,9 @@ impl MintReward {
             v_nfts_b,
             processed: &false,
             minted: &false,
+            phase_id: None,
+            stake_addr: None,
+            wl_entry: None,
         };
         log::debug!("try to insert mint reward into db...");
         let q = diesel::insert_into(mint_rewards::table)
@@ -830,10 +1123,206 @@ impl MintReward {
     }
 }
 
+impl MintPhase {
+    pub fn get_phase_by_id(id_in: i64) -> Result<MintPhase, RWDError> {
+        let result = mint_phases::table
+            .filter(mint_phases::id.eq(id_in))
+            .first::<MintPhase>(&mut establish_connection()?)?;
+        Ok(result)
+    }
+
+    /// Sale phases of a mint project ordered by their start
+    pub fn get_phases(project_id_in: i64) -> Result<Vec<MintPhase>, RWDError> {
+        let result = mint_phases::table
+            .filter(mint_phases::project_id.eq(project_id_in))
+            .order((mint_phases::start_slot.asc(), mint_phases::id.asc()))
+            .load::<MintPhase>(&mut establish_connection()?)?;
+        Ok(result)
+    }
+
+    /// The phase of a mint project open at `slot`, the latest started one if
+    /// phases overlap
+    pub fn get_active_phase(project_id_in: i64, slot: i64) -> Result<Option<MintPhase>, RWDError> {
+        Ok(MintPhase::active_phase(
+            MintPhase::get_phases(project_id_in)?,
+            slot,
+        ))
+    }
+
+    /// The phase of `phases`, ordered by their start, open at `slot`
+    pub fn active_phase(phases: Vec<MintPhase>, slot: i64) -> Option<MintPhase> {
+        phases.into_iter().filter(|p| p.is_active(slot)).last()
+    }
+
+    pub fn is_active(&self, slot: i64) -> bool {
+        self.start_slot <= slot && self.end_slot.map_or(true, |end| slot < end)
+    }
+
+    /// Price in lovelace of `nfts` claimed in this phase
+    pub fn price_of(&self, nfts: usize) -> u64 {
+        self.price as u64 * nfts as u64
+    }
+
+    /// Checks the window and price of a new phase, a phase without price is free
+    pub fn validate(
+        start_slot: &i64,
+        end_slot: Option<&i64>,
+        price: &i64,
+        pay_addr: Option<&String>,
+    ) -> Result<(), RWDError> {
+        if *price < 0 {
+            return Err(RWDError::new(
+                "the price of a mint phase cannot be negative",
+            ));
+        }
+        if *price > 0 && pay_addr.is_none() {
+            return Err(RWDError::new(
+                "a mint phase with a price needs an address receiving it",
+            ));
+        }
+        if let Some(end) = end_slot {
+            if end <= start_slot {
+                return Err(RWDError::new("a mint phase must end after its start"));
+            }
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_phase<'a>(
+        project_id: &'a i64,
+        phase_name: &'a String,
+        start_slot: &'a i64,
+        end_slot: Option<&'a i64>,
+        price: &'a i64,
+        pay_addr: Option<&'a String>,
+        max_per_wallet: Option<&'a i32>,
+        whitelist_id: Option<&'a i64>,
+        gate_policy: Option<&'a String>,
+    ) -> Result<MintPhase, RWDError> {
+        MintPhase::validate(start_slot, end_slot, price, pay_addr)?;
+        let new_entry = MintPhaseNew {
+            project_id,
+            phase_name,
+            start_slot,
+            end_slot,
+            price,
+            pay_addr,
+            max_per_wallet,
+            whitelist_id,
+            gate_policy,
+        };
+        let result = diesel::insert_into(mint_phases::table)
+            .values(&new_entry)
+            .get_result::<MintPhase>(&mut establish_connection()?)?;
+        Ok(result)
+    }
+
+    pub fn remove_phase(project_id_in: i64, id_in: i64) -> Result<usize, RWDError> {
+        let result = diesel::delete(
+            mint_phases::table
+                .filter(mint_phases::project_id.eq(project_id_in))
+                .filter(mint_phases::id.eq(id_in)),
+        )
+        .execute(&mut establish_connection()?)?;
+        Ok(result)
+    }
+}
+
+impl NftReveal {
+    pub fn get_reveal(project_id_in: i64, asset_name_b_in: &[u8]) -> Result<NftReveal, RWDError> {
+        let result = nft_reveals::table
+            .find((project_id_in, asset_name_b_in))
+            .first::<NftReveal>(&mut establish_connection()?)?;
+        Ok(result)
+    }
+
+    /// Reveals of a mint project ordered by asset name
+    pub fn get_reveals(project_id_in: i64) -> Result<Vec<NftReveal>, RWDError> {
+        let result = nft_reveals::table
+            .filter(nft_reveals::project_id.eq(project_id_in))
+            .order(nft_reveals::asset_name_b.asc())
+            .load::<NftReveal>(&mut establish_connection()?)?;
+        Ok(result)
+    }
+
+    /// Reveals of NFTs claimed with the placeholder whose metadata was not
+    /// updated yet
+    pub fn get_pending_updates(project_id_in: i64) -> Result<Vec<NftReveal>, RWDError> {
+        let result = nft_reveals::table
+            .filter(nft_reveals::project_id.eq(project_id_in))
+            .filter(nft_reveals::needs_update.eq(true))
+            .filter(nft_reveals::update_tx.is_null())
+            .order(nft_reveals::asset_name_b.asc())
+            .load::<NftReveal>(&mut establish_connection()?)?;
+        Ok(result)
+    }
+
+    pub fn set_update_tx(&self, txhash_in: &String) -> Result<NftReveal, RWDError> {
+        let result = diesel::update(nft_reveals::table.find((self.project_id, &self.asset_name_b)))
+            .set(nft_reveals::update_tx.eq(txhash_in))
+            .get_result::<NftReveal>(&mut establish_connection()?)?;
+        Ok(result)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn phase(id: i64, start_slot: i64, end_slot: Option<i64>) -> MintPhase {
+        MintPhase {
+            id,
+            project_id: 1,
+            phase_name: format!("phase {id}"),
+            start_slot,
+            end_slot,
+            price: 5_000_000,
+            pay_addr: Some("addr_test1vz".to_string()),
+            max_per_wallet: None,
+            whitelist_id: None,
+            gate_policy: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn phase_window_at_claim() {
+        let phases = vec![phase(1, 100, Some(200)), phase(2, 150, None)];
+        assert!(MintPhase::active_phase(phases.clone(), 99).is_none());
+        assert_eq!(MintPhase::active_phase(phases.clone(), 100).unwrap().id, 1);
+        // overlapping phases, the latest started one is open
+        assert_eq!(MintPhase::active_phase(phases.clone(), 150).unwrap().id, 2);
+        assert_eq!(MintPhase::active_phase(phases, 10_000).unwrap().id, 2);
+
+        let presale = phase(1, 100, Some(200));
+        assert!(presale.is_active(199));
+        assert!(!presale.is_active(200));
+        assert!(MintPhase::active_phase(vec![presale], 200).is_none());
+    }
+
+    #[test]
+    fn phase_price_at_mint() {
+        // the price is charged from the phase of the reward, also once it closed
+        let closed = phase(1, 100, Some(200));
+        assert!(!closed.is_active(300));
+        assert_eq!(closed.price_of(3), 15_000_000);
+        let mut free = phase(2, 100, None);
+        free.price = 0;
+        assert_eq!(free.price_of(3), 0);
+    }
+
+    #[test]
+    fn phase_validation() {
+        let addr = "addr_test1vz".to_string();
+        assert!(MintPhase::validate(&100, Some(&200), &5_000_000, Some(&addr)).is_ok());
+        assert!(MintPhase::validate(&100, None, &0, None).is_ok());
+        assert!(MintPhase::validate(&100, None, &-1, Some(&addr)).is_err());
+        assert!(MintPhase::validate(&100, None, &5_000_000, None).is_err());
+        assert!(MintPhase::validate(&100, Some(&100), &0, None).is_err());
+    }
+
     #[tokio::test]
     async fn test_nft_functions() {
         std::env::set_var(
@@ -872,6 +1361,9 @@ mod tests {
             Some(&1),
             &"test_table".to_string(),
             &true,
+            None,
+            None,
+            None,
         )
         .unwrap();
 
@@ -1035,4 +1527,92 @@ mod tests {
         let all_confirmed = Nft::get_all_confirmed(99991, "test_table").unwrap();
         assert_eq!(all_confirmed.len(), 1);
     }
+
+    #[tokio::test]
+    #[ignore = "needs a rewards database in REWARDS_DB_URL"]
+    async fn concurrent_claims_within_limits() {
+        let table = "test_limit_table".to_string();
+        let client = Nft::db_client().await.unwrap();
+        client
+            .batch_execute(&format!("DROP TABLE IF EXISTS {table};"))
+            .await
+            .unwrap();
+        Nft::create_nft_table(&table).await.unwrap();
+
+        let project = MintProject::create_mintproject(
+            &"LimitProject".to_string(),
+            &0,
+            &98,
+            None,
+            &Utc::now(),
+            None,
+            &"CIP25Metadata".to_string(),
+            None,
+            None,
+            &"Limit Collection".to_string(),
+            &"Test0r".to_string(),
+            &"A Test Project".to_string(),
+            None,
+            None,
+            &table,
+            &true,
+            None,
+            Some(&2),
+            None,
+        )
+        .unwrap();
+        for i in 0..6 {
+            let name = format!("LimitAsset{i}");
+            Nft::create_nft(
+                &table,
+                &project.id,
+                &name.as_bytes().to_vec(),
+                &name,
+                &format!("fingerprint{i}"),
+                &format!("{i:04}"),
+                None,
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+
+        // a bot rotating payment addresses under one stake key
+        let claims = (0..6).map(|i| {
+            let project = project.clone();
+            tokio::spawn(async move {
+                let claim = NftClaim {
+                    pay_addr: format!("addr_test_rotating_{i}"),
+                    stake_addr: Some("stake_test_bot".to_string()),
+                    wl_entry: None,
+                    phase: None,
+                };
+                Nft::claim_nft_within_limits(&project, &claim, |name| Ok(name.to_vec())).await
+            })
+        });
+        let mut claimed = 0;
+        for claim in claims.collect::<Vec<_>>() {
+            match claim.await.unwrap().unwrap() {
+                ClaimResult::Claimed(_) => claimed += 1,
+                ClaimResult::LimitReached(limit) => {
+                    assert_eq!(limit.limit, "stake address");
+                    assert_eq!((limit.claimed, limit.max), (2, 2));
+                }
+                ClaimResult::SoldOut => panic!("the collection is not sold out"),
+            }
+        }
+        assert_eq!(claimed, 2);
+
+        let conn = &mut establish_connection().unwrap();
+        diesel::delete(mint_rewards::table.filter(mint_rewards::project_id.eq(project.id)))
+            .execute(conn)
+            .unwrap();
+        MintProject::remove_mintproject(conn, &project.id).unwrap();
+        client
+            .batch_execute(&format!("DROP TABLE IF EXISTS {table};"))
+            .await
+            .unwrap();
+    }
 }
@@ -2,7 +2,7 @@ use chrono::serde::ts_seconds::serialize as to_ts;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::schema::{mint_projects, mint_rewards, nft_table};
+use crate::schema::{mint_phases, mint_projects, mint_rewards, nft_reveals, nft_table};
 
 #[derive(Queryable, Identifiable, Debug, Clone, Serialize, Deserialize)]
 #[diesel(table_name = mint_projects)]
@@ -27,6 +27,22 @@ pub struct MintProject {
     pub active: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Address the CIP-68 reference tokens are locked at, CIP-25 is minted if unset
+    pub cip68_ref_addr: Option<String>,
+    /// Maximum NFTs all addresses of one stake key can claim
+    pub max_mint_p_stake: Option<i32>,
+    /// Maximum NFTs one whitelist entry can claim
+    pub max_mint_p_wl_entry: Option<i32>,
+    /// Metadata minted until the reveal, reveal mode is off if unset
+    pub reveal_placeholder: Option<String>,
+    /// Hash of the reveal seed, committed at project creation
+    pub reveal_commitment: Option<String>,
+    /// Kept secret until the reveal, published in the reveal proof
+    #[serde(skip_serializing)]
+    pub reveal_seed: Option<String>,
+    /// Hash of the metadata pool the reveal assigned
+    pub reveal_provenance: Option<String>,
+    pub revealed_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Insertable, Debug, Clone)]
@@ -48,6 +64,9 @@ pub struct MintProjectNew<'a> {
     pub max_mint_p_addr: Option<&'a i32>,
     pub nft_table_name: &'a String,
     pub active: &'a bool,
+    pub cip68_ref_addr: Option<&'a String>,
+    pub max_mint_p_stake: Option<&'a i32>,
+    pub max_mint_p_wl_entry: Option<&'a i32>,
 }
 
 #[derive(Queryable, Debug, Clone, Serialize, Deserialize, QueryableByName)]
@@ -108,6 +127,12 @@ pub struct MintReward {
     pub created_at: DateTime<Utc>,
     #[serde(serialize_with = "to_ts")]
     pub updated_at: DateTime<Utc>,
+    /// Sale phase the reward was claimed in
+    pub phase_id: Option<i64>,
+    /// Stake address of the claiming wallet
+    pub stake_addr: Option<String>,
+    /// Whitelist entry the reward was claimed with
+    pub wl_entry: Option<i64>,
 }
 
 #[derive(Insertable, Debug, Clone)]
@@ -119,4 +144,109 @@ pub struct MintRewardNew<'a> {
     pub v_nfts_b: Vec<&'a Vec<u8>>, // serialized clib::utils::Value
     pub processed: &'a bool,
     pub minted: &'a bool,
+    pub phase_id: Option<&'a i64>,
+    pub stake_addr: Option<&'a String>,
+    pub wl_entry: Option<&'a i64>,
+}
+
+/// Sale phase of a mint project, e.g. an allowlist presale or the public sale
+#[derive(Queryable, Identifiable, Debug, Clone, Serialize, Deserialize)]
+#[diesel(table_name = mint_phases)]
+pub struct MintPhase {
+    pub id: i64,
+    pub project_id: i64,
+    pub phase_name: String,
+    pub start_slot: i64,
+    /// The phase is open ended if unset
+    pub end_slot: Option<i64>,
+    /// Price per NFT in lovelace
+    pub price: i64,
+    /// Address receiving the price, required if the price is not zero
+    pub pay_addr: Option<String>,
+    pub max_per_wallet: Option<i32>,
+    /// Only addresses on this whitelist can claim in this phase
+    pub whitelist_id: Option<i64>,
+    /// Only wallets holding a token of this policy can claim in this phase
+    pub gate_policy: Option<String>,
+    #[serde(serialize_with = "to_ts")]
+    pub created_at: DateTime<Utc>,
+    #[serde(serialize_with = "to_ts")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = mint_phases)]
+pub struct MintPhaseNew<'a> {
+    pub project_id: &'a i64,
+    pub phase_name: &'a String,
+    pub start_slot: &'a i64,
+    pub end_slot: Option<&'a i64>,
+    pub price: &'a i64,
+    pub pay_addr: Option<&'a String>,
+    pub max_per_wallet: Option<&'a i32>,
+    pub whitelist_id: Option<&'a i64>,
+    pub gate_policy: Option<&'a String>,
+}
+
+/// Final metadata the reveal of a mint project assigned to an NFT
+#[derive(Queryable, Identifiable, Debug, Clone, Serialize, Deserialize)]
+#[diesel(table_name = nft_reveals)]
+#[diesel(primary_key(project_id, asset_name_b))]
+pub struct NftReveal {
+    pub project_id: i64,
+    pub asset_name_b: Vec<u8>,
+    /// Position of the metadata in the pool ordered by asset name
+    pub pool_index: i32,
+    pub metadata: String,
+    /// The NFT was claimed before the reveal and carries the placeholder
+    pub needs_update: bool,
+    /// Transaction updating the metadata of the minted NFT
+    pub update_tx: Option<String>,
+    #[serde(serialize_with = "to_ts")]
+    pub created_at: DateTime<Utc>,
+    #[serde(serialize_with = "to_ts")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = nft_reveals)]
+pub struct NftRevealNew {
+    pub project_id: i64,
+    pub asset_name_b: Vec<u8>,
+    pub pool_index: i32,
+    pub metadata: String,
+    pub needs_update: bool,
+}
+
+/// Wallet claiming an NFT of a mint project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NftClaim {
+    pub pay_addr: String,
+    pub stake_addr: Option<String>,
+    /// Whitelist entry the wallet claims with
+    pub wl_entry: Option<i64>,
+    /// Sale phase the claim is made in
+    pub phase: Option<MintPhase>,
+}
+
+/// A limit of a mint project and how much of it a wallet used
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClaimLimit {
+    /// 'payment address', 'stake address', 'whitelist entry' or 'phase'
+    pub limit: String,
+    pub claimed: i64,
+    pub max: i32,
+}
+
+impl ClaimLimit {
+    pub fn reached(&self) -> bool {
+        self.claimed >= self.max as i64
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ClaimResult {
+    Claimed(MintReward),
+    LimitReached(ClaimLimit),
+    SoldOut,
 }
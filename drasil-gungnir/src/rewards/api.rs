@@ -2,6 +2,13 @@ use super::*;
 use crate::error::RWDError;
 use crate::schema::*;
 use bigdecimal::{FromPrimitive, ToPrimitive};
+use std::collections::HashMap;
+
+/// `invalid_descr` of claims whose transaction was rolled back, they are
+/// restored if the transaction is included again
+pub const CLAIM_ROLLED_BACK: &str = "transaction rolled back";
+/// `invalid_descr` of claims whose transaction expired without being included
+pub const CLAIM_EXPIRED: &str = "transaction expired";
 
 impl Rewards {
     pub fn get_rewards_stake_addr(
@@ -69,12 +76,14 @@ impl Rewards {
         user_id_in: i64,
     ) -> Result<Vec<Rewards>, RWDError> {
         use crate::schema::rewards::dsl::*;
+        let lovelace = BigDecimal::from_i32(1000000).unwrap();
         let result = rewards
             .filter(payment_addr.eq(&payment_addr_in))
             .filter(fingerprint.eq(&fingerprint_in))
             .filter(contract_id.eq(&contract_id_in))
             .filter(user_id.eq(&user_id_in))
-            .filter(tot_earned.gt(tot_claimed))
+            // expired rewards cannot be claimed anymore
+            .filter((tot_earned - tot_expired).gt(tot_claimed * lovelace))
             .load::<Rewards>(conn)?;
 
         let mut res = Vec::<Rewards>::new();
@@ -88,7 +97,7 @@ impl Rewards {
             );
             match claim_sum {
                 Ok(cs) => {
-                    if cs < r.tot_earned.to_i128().unwrap() {
+                    if r.claimable_after(cs) {
                         res.push(r);
                     }
                 }
@@ -146,12 +155,7 @@ impl Rewards {
 
             let sum: BigDecimal = twl_rewards.iter().fold(
                 bigdecimal::FromPrimitive::from_u64(0).unwrap(),
-                |acc: BigDecimal, n| {
-                    acc + (std::ops::Div::<BigDecimal>::div(
-                        n.tot_earned.clone(),
-                        bigdecimal::FromPrimitive::from_u64(1000000).unwrap(),
-                    )) - n.tot_claimed.clone()
-                },
+                |acc: BigDecimal, n| acc + n.unclaimed(),
             );
             out.push((i.contract_id, i.fingerprint.clone().unwrap(), sum))
         }
@@ -175,8 +179,8 @@ impl Rewards {
             .filter(fingerprint.eq(&fingerprint_in))
             .filter(contract_id.eq(&contract_id_in))
             .filter(user_id.eq(&user_id_in))
-            .select((tot_earned, tot_claimed, payment_addr))
-            .first::<(BigDecimal, BigDecimal, String)>(conn)?;
+            .select((tot_earned, tot_claimed, payment_addr, tot_expired))
+            .first::<(BigDecimal, BigDecimal, String, BigDecimal)>(conn)?;
         log::info!("found rewards");
         if *payment_addr_in != result.2 {
             return Err(RWDError::new(
@@ -202,7 +206,8 @@ impl Rewards {
         };
         log::info!("found claims");
         let lovelace = BigDecimal::from_i32(1000000).unwrap();
-        match ((result.0 / lovelace) - result.1.clone()).to_i128() {
+        // expired rewards cannot be claimed anymore
+        match (((result.0 - result.3) / lovelace) - result.1.clone()).to_i128() {
             Some(dif) => {
                 if claim_sum != result.1.to_i128().unwrap() {
                     return Err(RWDError::new(
@@ -310,6 +315,24 @@ impl Rewards {
         }
     }
 
+    fn add_claimed(
+        conn: &mut PgConnection,
+        claim: &Claimed,
+        amt: BigDecimal,
+    ) -> Result<(), RWDError> {
+        use crate::schema::rewards::dsl::*;
+        diesel::update(
+            rewards
+                .filter(stake_addr.eq(&claim.stake_addr))
+                .filter(fingerprint.eq(&claim.fingerprint))
+                .filter(contract_id.eq(&claim.contract_id))
+                .filter(user_id.eq(&claim.user_id)),
+        )
+        .set(tot_claimed.eq(tot_claimed + amt))
+        .execute(conn)?;
+        Ok(())
+    }
+
     pub fn get_tot_rewards_to_deliver(
         conn: &mut PgConnection,
         contract_id_in: i64,
@@ -319,12 +342,129 @@ impl Rewards {
         let ret = rewards
             .filter(contract_id.eq(&contract_id_in))
             .filter(user_id.eq(&user_id_in))
-            .select((tot_earned, tot_claimed))
-            .load::<(BigDecimal, BigDecimal)>(conn)?;
+            .select((tot_earned, tot_claimed, tot_expired))
+            .load::<(BigDecimal, BigDecimal, BigDecimal)>(conn)?;
         let lovelace = BigDecimal::from_i32(1000000).unwrap();
-        let open_amt = ret.iter().map(|(x, y)| (x / &lovelace) - y).sum();
+        let open_amt = ret.iter().map(|(x, y, e)| ((x - e) / &lovelace) - y).sum();
         Ok(open_amt)
     }
+
+    /// Whole tokens which were earned, did not expire and are not claimed yet
+    pub fn unclaimed(&self) -> BigDecimal {
+        let lovelace = BigDecimal::from_i32(1000000).unwrap();
+        (&self.tot_earned - &self.tot_expired) / lovelace - &self.tot_claimed
+    }
+
+    /// Whether tokens are left to claim after `claim_sum` tokens were claimed,
+    /// expired rewards cannot be claimed anymore
+    pub fn claimable_after(&self, claim_sum: i128) -> bool {
+        let lovelace = BigDecimal::from_i32(1000000).unwrap();
+        BigDecimal::from_i128(claim_sum).map_or(false, |cs| {
+            cs < (&self.tot_earned - &self.tot_expired) / lovelace
+        })
+    }
+
+    /// Expired amount of these rewards if the epochs up to `cutoff` expire, with
+    /// `recent` earned in applied reward runs after `cutoff`
+    ///
+    /// Claims are counted against the oldest rewards first, rewards without a
+    /// reward run count as earned in `last_calc_epoch`. `None` if the expired
+    /// amount does not grow.
+    pub fn expiry(&self, cutoff: i64, recent: Option<&BigDecimal>) -> Option<BigDecimal> {
+        let zero = BigDecimal::from_i32(0).unwrap();
+        let old = if self.last_calc_epoch <= cutoff {
+            self.tot_earned.clone()
+        } else {
+            match recent {
+                Some(e) => (&self.tot_earned - e).max(zero.clone()),
+                None => zero.clone(),
+            }
+        };
+        let lovelace = BigDecimal::from_i32(1000000).unwrap();
+        let tot_expired = (old - &self.tot_claimed * lovelace).max(zero);
+        (tot_expired > self.tot_expired).then_some(tot_expired)
+    }
+
+    /// Expires the rewards of a token which were earned `expiry_epochs` or more
+    /// epochs before `current_epoch` and are still unclaimed
+    ///
+    /// Claims are counted against the oldest rewards first. Rewards without a
+    /// reward run in the ledger count as earned in the last calculated epoch
+    /// of the stake address. The expired amount of a stake address only grows.
+    /// With `dry_run` nothing is written.
+    pub fn expire_rewards(
+        conn: &mut PgConnection,
+        twl: &TokenWhitelist,
+        current_epoch: i64,
+        dry_run: bool,
+    ) -> Result<Vec<RewardExpiry>, RWDError> {
+        let (expiry_epochs, fingerprint_in) = match (twl.expiry_epochs, &twl.fingerprint) {
+            (Some(e), Some(f)) => (e, f),
+            _ => return Ok(Vec::new()),
+        };
+        let cutoff = current_epoch - expiry_epochs;
+        let zero = BigDecimal::from_i32(0).unwrap();
+
+        conn.transaction::<_, RWDError, _>(|conn| {
+            let rwds =
+                Rewards::get_token_rewards(conn, twl.contract_id, twl.user_id, fingerprint_in)?;
+            // rewards earned after the cutoff which did not expire yet
+            let recent = reward_run_entries::table
+                .inner_join(reward_runs::table)
+                .filter(reward_runs::contract_id.eq(twl.contract_id))
+                .filter(reward_runs::user_id.eq(twl.user_id))
+                .filter(reward_runs::fingerprint.eq(fingerprint_in))
+                .filter(reward_runs::status.eq(RUN_APPLIED))
+                .filter(reward_runs::epoch.gt(cutoff))
+                .select((reward_run_entries::stake_addr, reward_run_entries::earned))
+                .load::<(String, BigDecimal)>(conn)?
+                .into_iter()
+                .fold(
+                    HashMap::<String, BigDecimal>::new(),
+                    |mut acc, (addr, e)| {
+                        *acc.entry(addr).or_insert_with(|| zero.clone()) += e;
+                        acc
+                    },
+                );
+
+            let mut expired = Vec::<RewardExpiry>::new();
+            for r in rwds {
+                let tot_expired = match r.expiry(cutoff, recent.get(&r.stake_addr)) {
+                    Some(e) => e,
+                    None => continue,
+                };
+                if !dry_run {
+                    diesel::update(rewards::table.find(r.id))
+                        .set(rewards::tot_expired.eq(&tot_expired))
+                        .execute(conn)?;
+                }
+                expired.push(RewardExpiry {
+                    stake_addr: r.stake_addr,
+                    fingerprint: r.fingerprint,
+                    contract_id: r.contract_id,
+                    user_id: r.user_id,
+                    tot_expired_before: r.tot_expired,
+                    tot_expired,
+                });
+            }
+            Ok(expired)
+        })
+    }
+
+    /// All rewards of a token on a contract
+    pub fn get_token_rewards(
+        conn: &mut PgConnection,
+        contract_id_in: i64,
+        user_id_in: i64,
+        fingerprint_in: &String,
+    ) -> Result<Vec<Rewards>, RWDError> {
+        use crate::schema::rewards::dsl::*;
+        Ok(rewards
+            .filter(contract_id.eq(&contract_id_in))
+            .filter(user_id.eq(&user_id_in))
+            .filter(fingerprint.eq(fingerprint_in))
+            .load::<Rewards>(conn)?)
+    }
 }
 
 impl Claimed {
@@ -448,6 +588,51 @@ impl Claimed {
         Ok(contract)
     }
 
+    /// Invalidates the valid claims of a transaction and gives the claimed
+    /// amounts back to the rewards
+    pub fn invalidate_tx_claims(
+        conn: &mut PgConnection,
+        txhash_in: &String,
+        reason: &str,
+    ) -> Result<Vec<Claimed>, RWDError> {
+        use crate::schema::claimed::dsl::*;
+        conn.transaction(|conn| {
+            let claims = diesel::update(
+                claimed
+                    .filter(txhash.eq(txhash_in))
+                    .filter(invalid.is_null()),
+            )
+            .set((invalid.eq(Some(true)), invalid_descr.eq(Some(reason))))
+            .get_results::<Claimed>(conn)?;
+            for claim in &claims {
+                Rewards::add_claimed(conn, claim, -claim.amount.clone())?;
+            }
+            Ok(claims)
+        })
+    }
+
+    /// Validates the claims of a rolled back transaction again once it is
+    /// included in a block
+    pub fn restore_tx_claims(
+        conn: &mut PgConnection,
+        txhash_in: &String,
+    ) -> Result<Vec<Claimed>, RWDError> {
+        use crate::schema::claimed::dsl::*;
+        conn.transaction(|conn| {
+            let claims = diesel::update(
+                claimed
+                    .filter(txhash.eq(txhash_in))
+                    .filter(invalid_descr.eq(CLAIM_ROLLED_BACK)),
+            )
+            .set((invalid.eq(None::<bool>), invalid_descr.eq(None::<String>)))
+            .get_results::<Claimed>(conn)?;
+            for claim in &claims {
+                Rewards::add_claimed(conn, claim, claim.amount.clone())?;
+            }
+            Ok(claims)
+        })
+    }
+
     pub fn get_stat_count_all_tx_on_contr(
         contract_id_in: i64,
         user_id_in: i64,
@@ -938,6 +1123,152 @@ impl TokenWhitelist {
 
         Ok(result)
     }
+
+    /// Reward multipliers by delegation streak configured for the token
+    pub fn loyalty_schedule(&self) -> Result<Vec<LoyaltyStep>, RWDError> {
+        match &self.loyalty_schedule {
+            Some(schedule) => Ok(serde_json::from_value(schedule.clone())?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    pub fn set_expiry_epochs(
+        conn: &mut PgConnection,
+        id_in: i64,
+        expiry_epochs_in: Option<i64>,
+    ) -> Result<TokenWhitelist, RWDError> {
+        use crate::schema::token_whitelist::dsl::*;
+        Ok(diesel::update(token_whitelist.find(id_in))
+            .set(expiry_epochs.eq(expiry_epochs_in))
+            .get_result::<TokenWhitelist>(conn)?)
+    }
+
+    pub fn set_loyalty_schedule(
+        conn: &mut PgConnection,
+        id_in: i64,
+        schedule: Option<&[LoyaltyStep]>,
+    ) -> Result<TokenWhitelist, RWDError> {
+        use crate::schema::token_whitelist::dsl::*;
+        let value = match schedule {
+            Some(s) => Some(serde_json::to_value(s)?),
+            None => None,
+        };
+        Ok(diesel::update(token_whitelist.find(id_in))
+            .set(loyalty_schedule.eq(value))
+            .get_result::<TokenWhitelist>(conn)?)
+    }
+}
+
+impl DelegationHistory {
+    /// Delegation history of all stake addresses seen on a pool
+    pub fn get_pool_history(
+        conn: &mut PgConnection,
+        pool_id_in: &str,
+    ) -> Result<Vec<DelegationHistory>, RWDError> {
+        Ok(delegation_history::table
+            .filter(delegation_history::pool_id.eq(pool_id_in))
+            .load::<DelegationHistory>(conn)?)
+    }
+
+    /// `true` if the delegators of a pool were tracked before
+    pub fn is_tracked(conn: &mut PgConnection, pool_id_in: &str) -> Result<bool, RWDError> {
+        Ok(delegation_history::table
+            .filter(delegation_history::pool_id.eq(pool_id_in))
+            .select(delegation_history::id)
+            .first::<i64>(conn)
+            .optional()?
+            .is_some())
+    }
+
+    /// Delegation history of a stake address on all tracked pools
+    pub fn get_stake_history(
+        conn: &mut PgConnection,
+        stake_addr_in: &str,
+    ) -> Result<Vec<DelegationHistory>, RWDError> {
+        Ok(delegation_history::table
+            .filter(delegation_history::stake_addr.eq(stake_addr_in))
+            .order(delegation_history::last_epoch.desc())
+            .load::<DelegationHistory>(conn)?)
+    }
+
+    /// Tracks the delegators of a pool in `epoch` and returns their streaks in it
+    ///
+    /// Stake addresses seen on the pool for the first time start with their
+    /// streak from `seeds`, or 1 if they have none. Stake addresses which are not
+    /// delegated to the pool anymore lose their streak, so a redelegation starts
+    /// over. Epochs need to be tracked in order, a skipped epoch resets all
+    /// streaks of the pool. With `dry_run` nothing is written.
+    pub fn track_epoch(
+        conn: &mut PgConnection,
+        pool_id_in: &str,
+        epoch: i64,
+        delegators: &[String],
+        seeds: &HashMap<String, i64>,
+        dry_run: bool,
+    ) -> Result<HashMap<String, i64>, RWDError> {
+        let known = DelegationHistory::get_pool_history(conn, pool_id_in)?
+            .into_iter()
+            .map(|h| (h.stake_addr.clone(), DelegationStreak::from(h)))
+            .collect::<HashMap<_, _>>();
+
+        let mut streaks = HashMap::<String, i64>::new();
+        let mut changed = Vec::<DelegationStreak>::new();
+        for addr in delegators {
+            let seed = seeds.get(addr).copied().unwrap_or(1);
+            let streak = match known.get(addr) {
+                // epoch was tracked before, e.g. on a recalculation
+                Some(d) if epoch <= d.last_epoch => match d.streak_in(epoch) {
+                    0 => seed.max(1),
+                    s => s,
+                },
+                Some(d) => {
+                    let next = d.advance(epoch);
+                    let streak = next.streak;
+                    changed.push(next);
+                    streak
+                }
+                None => {
+                    let new = DelegationStreak::new(addr, pool_id_in, epoch, seed);
+                    let streak = new.streak;
+                    changed.push(new);
+                    streak
+                }
+            };
+            streaks.insert(addr.clone(), streak);
+        }
+
+        if dry_run {
+            return Ok(streaks);
+        }
+        conn.transaction::<_, RWDError, _>(|conn| {
+            use diesel::upsert::excluded;
+            for chunk in changed.chunks(1000) {
+                diesel::insert_into(delegation_history::table)
+                    .values(chunk)
+                    .on_conflict((delegation_history::stake_addr, delegation_history::pool_id))
+                    .do_update()
+                    .set((
+                        delegation_history::first_epoch
+                            .eq(excluded(delegation_history::first_epoch)),
+                        delegation_history::last_epoch.eq(excluded(delegation_history::last_epoch)),
+                        delegation_history::streak.eq(excluded(delegation_history::streak)),
+                        delegation_history::total_epochs
+                            .eq(excluded(delegation_history::total_epochs)),
+                    ))
+                    .execute(conn)?;
+            }
+            diesel::update(
+                delegation_history::table
+                    .filter(delegation_history::pool_id.eq(pool_id_in))
+                    .filter(delegation_history::last_epoch.lt(epoch))
+                    .filter(delegation_history::streak.gt(0)),
+            )
+            .set(delegation_history::streak.eq(0))
+            .execute(conn)?;
+            Ok(())
+        })?;
+        Ok(streaks)
+    }
 }
 
 impl AirDropWhitelist {
@@ -1446,3 +1777,626 @@ impl Discount {
         self.fingerprint.as_ref()
     }
 }
+
+impl RewardRun {
+    /// The applied run of a token on a contract for an epoch
+    pub fn get_applied_run(
+        conn: &mut PgConnection,
+        key: &RewardRunKey,
+    ) -> Result<Option<RewardRun>, RWDError> {
+        Ok(reward_runs::table
+            .filter(reward_runs::contract_id.eq(key.contract_id))
+            .filter(reward_runs::user_id.eq(key.user_id))
+            .filter(reward_runs::fingerprint.eq(&key.fingerprint))
+            .filter(reward_runs::epoch.eq(key.epoch))
+            .filter(reward_runs::status.eq(RUN_APPLIED))
+            .first::<RewardRun>(conn)
+            .optional()?)
+    }
+
+    /// All runs of a token on a contract, latest epoch first
+    pub fn get_runs(
+        conn: &mut PgConnection,
+        contract_id_in: i64,
+        user_id_in: i64,
+        fingerprint_in: &String,
+    ) -> Result<Vec<RewardRun>, RWDError> {
+        Ok(reward_runs::table
+            .filter(reward_runs::contract_id.eq(contract_id_in))
+            .filter(reward_runs::user_id.eq(user_id_in))
+            .filter(reward_runs::fingerprint.eq(fingerprint_in))
+            .order((reward_runs::epoch.desc(), reward_runs::id.desc()))
+            .load::<RewardRun>(conn)?)
+    }
+
+    /// What a stake address earns in the run of `key`, `None` if its rewards of
+    /// the epoch were already calculated
+    ///
+    /// Returns the payment address of the rewards of the stake address if it has
+    /// any. With `no_acc` rewards do not accumulate, nothing is earned as long as
+    /// earlier rewards are not claimed.
+    pub fn earned_by(
+        conn: &mut PgConnection,
+        key: &RewardRunKey,
+        stake_addr: &String,
+        token_earned: &BigDecimal,
+        no_acc: bool,
+        recalculate: bool,
+    ) -> Result<Option<(Option<String>, BigDecimal)>, RWDError> {
+        let rewards = Rewards::get_rewards_per_token(
+            conn,
+            stake_addr,
+            key.contract_id,
+            key.user_id,
+            &key.fingerprint,
+        )?;
+        let r = match rewards.as_slice() {
+            [] => return Ok(Some((None, token_earned.clone()))),
+            [r] => r,
+            _ => {
+                return Err(RWDError::new(
+                    "More than one reward entry found on the same contract for the same token",
+                ))
+            }
+        };
+        if r.last_calc_epoch >= key.epoch && !recalculate {
+            return Ok(None);
+        }
+        let mut earned = token_earned.clone();
+        if no_acc {
+            let mut available = Rewards::get_available_rewards(
+                conn,
+                &r.stake_addr,
+                &r.payment_addr,
+                &r.fingerprint,
+                r.contract_id,
+                r.user_id,
+                token_earned.to_i128().unwrap(),
+            )?;
+            if recalculate {
+                // the rewards of the replaced run are reversed before the new ones are added
+                let replaced = RewardRunEntry::get_applied_earned(conn, key, stake_addr)?;
+                available -= (replaced / BigDecimal::from_i32(1000000).unwrap())
+                    .to_i128()
+                    .unwrap();
+            }
+            if available != -token_earned.to_i128().unwrap() {
+                earned = BigDecimal::from_i32(0).unwrap();
+            }
+        }
+        Ok(Some((Some(r.payment_addr.clone()), earned)))
+    }
+
+    /// Records calculated rewards as one reward run per token and epoch
+    ///
+    /// Each run is applied to the rewards table, or only reported with `dry_run`.
+    /// Epochs which already have an applied run are skipped unless `recalculate`
+    /// is set, which replaces the applied run.
+    pub fn record(
+        conn: &mut PgConnection,
+        runs: &[RewardRunInput],
+        dry_run: bool,
+        recalculate: bool,
+    ) -> Result<Vec<(RewardRunKey, Vec<RewardDiff>)>, RWDError> {
+        let mut diffs = Vec::new();
+        for run in runs {
+            match (RewardRun::get_applied_run(conn, &run.key)?, recalculate) {
+                (Some(applied), false) => {
+                    log::info!(
+                        "Reward run {} already applied for {:?}",
+                        applied.id,
+                        run.key
+                    );
+                    continue;
+                }
+                (None, true) => {
+                    log::warn!("No applied reward run to recalculate for {:?}", run.key);
+                    continue;
+                }
+                _ => {}
+            }
+
+            let diff = if dry_run {
+                RewardRun::dry_run(conn, &run.key, &run.earned, recalculate)?
+            } else {
+                let (applied, diff) =
+                    RewardRun::apply(conn, &run.key, &run.inputs_hash, &run.earned, recalculate)?;
+                log::info!("Applied reward run {} for {:?}", applied.id, run.key);
+                diff
+            };
+            diffs.push((run.key.clone(), diff));
+        }
+        Ok(diffs)
+    }
+
+    /// Changes to the rewards table a run of `earned` would cause
+    ///
+    /// With `recalculate` the applied run of the epoch is reversed first, without
+    /// it the epoch must not have an applied run yet. Nothing is written.
+    pub fn dry_run(
+        conn: &mut PgConnection,
+        key: &RewardRunKey,
+        earned: &[RewardEarned],
+        recalculate: bool,
+    ) -> Result<Vec<RewardDiff>, RWDError> {
+        let previous = RewardRun::replaced_run(conn, key, recalculate)?;
+        RewardRun::diff(conn, key, earned, previous.as_ref())
+    }
+
+    /// Adds `earned` to the rewards table and records the run in the ledger
+    ///
+    /// With `recalculate` the applied run of the epoch is reversed and replaced
+    /// by the new run, the rewards of a stake address must not drop below
+    /// what it already claimed.
+    pub fn apply(
+        conn: &mut PgConnection,
+        key: &RewardRunKey,
+        inputs_hash: &String,
+        earned: &[RewardEarned],
+        recalculate: bool,
+    ) -> Result<(RewardRun, Vec<RewardDiff>), RWDError> {
+        conn.transaction(|conn| {
+            let previous = RewardRun::replaced_run(conn, key, recalculate)?;
+            let diff = RewardRun::diff(conn, key, earned, previous.as_ref())?;
+            if let Some(previous) = &previous {
+                diesel::update(reward_runs::table.find(previous.id))
+                    .set(reward_runs::status.eq(RUN_REVERSED))
+                    .execute(conn)?;
+            }
+
+            for d in &diff {
+                let existing = Rewards::get_rewards_per_token(
+                    conn,
+                    &d.stake_addr,
+                    key.contract_id,
+                    key.user_id,
+                    &key.fingerprint,
+                )?;
+                match existing.first() {
+                    Some(r) => {
+                        diesel::update(rewards::table.find(r.id))
+                            .set((
+                                rewards::tot_earned.eq(&d.tot_earned),
+                                rewards::last_calc_epoch.eq(r.last_calc_epoch.max(key.epoch)),
+                            ))
+                            .execute(conn)?;
+                    }
+                    None => {
+                        Rewards::create_rewards(
+                            conn,
+                            &d.stake_addr,
+                            &d.payment_addr,
+                            &key.fingerprint,
+                            &key.contract_id,
+                            &key.user_id,
+                            &d.tot_earned,
+                            &BigDecimal::from_i32(0).unwrap(),
+                            &false,
+                            &key.epoch,
+                        )?;
+                    }
+                }
+            }
+
+            let total_earned = earned
+                .iter()
+                .fold(BigDecimal::from_i32(0).unwrap(), |acc, e| acc + &e.earned);
+            let run = diesel::insert_into(reward_runs::table)
+                .values(&RewardRunNew {
+                    contract_id: &key.contract_id,
+                    user_id: &key.user_id,
+                    fingerprint: &key.fingerprint,
+                    epoch: &key.epoch,
+                    inputs_hash,
+                    total_earned: &total_earned,
+                    entries: &(earned.len() as i32),
+                    status: RUN_APPLIED,
+                    replaces: previous.as_ref().map(|p| &p.id),
+                })
+                .get_result::<RewardRun>(conn)?;
+
+            let entries = diff
+                .iter()
+                .map(|d| RewardRunEntryNew {
+                    run_id: run.id,
+                    stake_addr: d.stake_addr.clone(),
+                    payment_addr: d.payment_addr.clone(),
+                    earned: d.earned.clone(),
+                    tot_earned_before: d.tot_earned_before.clone(),
+                    tot_earned: d.tot_earned.clone(),
+                    new_entry: d.new_entry,
+                })
+                .collect::<Vec<_>>();
+            for chunk in entries.chunks(1000) {
+                diesel::insert_into(reward_run_entries::table)
+                    .values(chunk)
+                    .execute(conn)?;
+            }
+            Ok((run, diff))
+        })
+    }
+
+    fn replaced_run(
+        conn: &mut PgConnection,
+        key: &RewardRunKey,
+        recalculate: bool,
+    ) -> Result<Option<RewardRun>, RWDError> {
+        match (RewardRun::get_applied_run(conn, key)?, recalculate) {
+            (Some(run), true) => Ok(Some(run)),
+            (None, false) => Ok(None),
+            (Some(_), false) => Err(RWDError::new(
+                "Rewards of this epoch were already applied, recalculate to replace them",
+            )),
+            (None, true) => Err(RWDError::new(
+                "No applied reward run found for this epoch to recalculate",
+            )),
+        }
+    }
+
+    fn diff(
+        conn: &mut PgConnection,
+        key: &RewardRunKey,
+        earned: &[RewardEarned],
+        previous: Option<&RewardRun>,
+    ) -> Result<Vec<RewardDiff>, RWDError> {
+        let zero = BigDecimal::from_i32(0).unwrap();
+        let mut reversed = match previous {
+            Some(run) => RewardRunEntry::get_entries(conn, run.id)?,
+            None => Vec::new(),
+        };
+
+        let mut diff = Vec::<RewardDiff>::new();
+        for e in earned {
+            let reverse = match reversed.iter().position(|r| r.stake_addr == e.stake_addr) {
+                Some(i) => reversed.swap_remove(i).earned,
+                None => zero.clone(),
+            };
+            diff.push(RewardRun::diff_entry(conn, key, e, &reverse)?);
+        }
+        // Stake addresses which earned in the replaced run but not anymore
+        for r in reversed {
+            let e = RewardEarned {
+                stake_addr: r.stake_addr,
+                payment_addr: r.payment_addr,
+                earned: zero.clone(),
+            };
+            diff.push(RewardRun::diff_entry(conn, key, &e, &r.earned)?);
+        }
+        Ok(diff)
+    }
+
+    fn diff_entry(
+        conn: &mut PgConnection,
+        key: &RewardRunKey,
+        earned: &RewardEarned,
+        reverse: &BigDecimal,
+    ) -> Result<RewardDiff, RWDError> {
+        let existing = Rewards::get_rewards_per_token(
+            conn,
+            &earned.stake_addr,
+            key.contract_id,
+            key.user_id,
+            &key.fingerprint,
+        )?;
+        RewardDiff::new(existing.first(), earned, reverse)
+    }
+}
+
+impl RewardDiff {
+    /// Change of the `existing` rewards of a stake address when `reverse` is
+    /// taken back from them and `earned` is added
+    pub fn new(
+        existing: Option<&Rewards>,
+        earned: &RewardEarned,
+        reverse: &BigDecimal,
+    ) -> Result<RewardDiff, RWDError> {
+        match existing {
+            Some(r) => {
+                let tot_earned = &r.tot_earned - reverse + &earned.earned;
+                // claims are counted in whole tokens, earnings in their smallest unit
+                let lovelace = BigDecimal::from_i32(1000000).unwrap();
+                if (&tot_earned - &r.tot_expired) / lovelace < r.tot_claimed {
+                    return Err(RWDError::new(&format!(
+                        "Recalculation would drop the rewards of {} below the claimed and expired amount",
+                        earned.stake_addr
+                    )));
+                }
+                Ok(RewardDiff {
+                    stake_addr: earned.stake_addr.clone(),
+                    payment_addr: r.payment_addr.clone(),
+                    earned: earned.earned.clone(),
+                    tot_earned_before: r.tot_earned.clone(),
+                    tot_earned,
+                    new_entry: false,
+                })
+            }
+            None => Ok(RewardDiff {
+                stake_addr: earned.stake_addr.clone(),
+                payment_addr: earned.payment_addr.clone(),
+                earned: earned.earned.clone(),
+                tot_earned_before: BigDecimal::from_i32(0).unwrap(),
+                tot_earned: earned.earned.clone(),
+                new_entry: true,
+            }),
+        }
+    }
+}
+
+impl RewardRunEntry {
+    pub fn get_entries(
+        conn: &mut PgConnection,
+        run_id_in: i64,
+    ) -> Result<Vec<RewardRunEntry>, RWDError> {
+        Ok(reward_run_entries::table
+            .filter(reward_run_entries::run_id.eq(run_id_in))
+            .order(reward_run_entries::id.asc())
+            .load::<RewardRunEntry>(conn)?)
+    }
+
+    /// What a stake address earned in the applied run of a token for an epoch
+    pub fn get_applied_earned(
+        conn: &mut PgConnection,
+        key: &RewardRunKey,
+        stake_addr_in: &String,
+    ) -> Result<BigDecimal, RWDError> {
+        Ok(reward_run_entries::table
+            .inner_join(reward_runs::table)
+            .filter(reward_runs::contract_id.eq(key.contract_id))
+            .filter(reward_runs::user_id.eq(key.user_id))
+            .filter(reward_runs::fingerprint.eq(&key.fingerprint))
+            .filter(reward_runs::epoch.eq(key.epoch))
+            .filter(reward_runs::status.eq(RUN_APPLIED))
+            .filter(reward_run_entries::stake_addr.eq(stake_addr_in))
+            .select(reward_run_entries::earned)
+            .first::<BigDecimal>(conn)
+            .optional()?
+            .unwrap_or_else(|| BigDecimal::from_i32(0).unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn earned(stake_addr: &str, amount: i64) -> RewardEarned {
+        RewardEarned {
+            stake_addr: stake_addr.to_string(),
+            payment_addr: format!("addr_{stake_addr}"),
+            earned: BigDecimal::from_i64(amount).unwrap(),
+        }
+    }
+
+    fn existing_rewards(tot_earned: i64, tot_claimed: i64) -> Rewards {
+        Rewards {
+            id: 1,
+            stake_addr: "stake_test_a".to_string(),
+            payment_addr: "addr_existing".to_string(),
+            fingerprint: "asset1test".to_string(),
+            contract_id: 1,
+            user_id: 1,
+            tot_earned: BigDecimal::from_i64(tot_earned).unwrap(),
+            tot_claimed: BigDecimal::from_i64(tot_claimed).unwrap(),
+            oneshot: false,
+            last_calc_epoch: 300,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            tot_expired: BigDecimal::from_i32(0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn reward_diff_of_new_and_existing_rewards() {
+        let zero = BigDecimal::from_i32(0).unwrap();
+        let d = RewardDiff::new(None, &earned("stake_test_a", 5000000), &zero).unwrap();
+        assert!(d.new_entry);
+        assert_eq!(d.payment_addr, "addr_stake_test_a");
+        assert_eq!(d.tot_earned, BigDecimal::from_i64(5000000).unwrap());
+
+        let existing = existing_rewards(7000000, 0);
+        let d = RewardDiff::new(Some(&existing), &earned("stake_test_a", 5000000), &zero).unwrap();
+        assert!(!d.new_entry);
+        assert_eq!(d.payment_addr, "addr_existing");
+        assert_eq!(d.tot_earned_before, BigDecimal::from_i64(7000000).unwrap());
+        assert_eq!(d.tot_earned, BigDecimal::from_i64(12000000).unwrap());
+    }
+
+    #[test]
+    fn reward_diff_of_recalculation() {
+        // 3 tokens of the replaced run are reversed, 1 token is earned instead
+        let reverse = BigDecimal::from_i64(3000000).unwrap();
+        let existing = existing_rewards(7000000, 4);
+        let d =
+            RewardDiff::new(Some(&existing), &earned("stake_test_a", 1000000), &reverse).unwrap();
+        assert_eq!(d.tot_earned, BigDecimal::from_i64(5000000).unwrap());
+
+        // the stake address claimed more than it would keep
+        let existing = existing_rewards(7000000, 6);
+        assert!(
+            RewardDiff::new(Some(&existing), &earned("stake_test_a", 1000000), &reverse).is_err()
+        );
+        // dropping to exactly the claimed amount is fine
+        let d =
+            RewardDiff::new(Some(&existing), &earned("stake_test_a", 2000000), &reverse).unwrap();
+        assert_eq!(d.tot_earned, BigDecimal::from_i64(6000000).unwrap());
+    }
+
+    #[test]
+    fn reward_diff_keeps_expired_rewards() {
+        // 2 of 7 tokens expired and 4 were claimed, 1 token is left to take back
+        let existing = Rewards {
+            tot_expired: BigDecimal::from_i64(2000000).unwrap(),
+            ..existing_rewards(7000000, 4)
+        };
+        let reverse = BigDecimal::from_i64(2000000).unwrap();
+        assert!(RewardDiff::new(Some(&existing), &earned("stake_test_a", 0), &reverse).is_err());
+        let d =
+            RewardDiff::new(Some(&existing), &earned("stake_test_a", 1000000), &reverse).unwrap();
+        assert_eq!(d.tot_earned, BigDecimal::from_i64(6000000).unwrap());
+    }
+
+    #[test]
+    fn expiry_of_rewards() {
+        let tokens = |t: i64| BigDecimal::from_i64(t * 1000000).unwrap();
+        // 10 tokens earned up to epoch 300, expiring everything up to epoch 305
+        let r = existing_rewards(10000000, 0);
+        assert_eq!(r.expiry(305, None), Some(tokens(10)));
+        // nothing expires before the cutoff reaches the last calculated epoch
+        assert_eq!(r.expiry(299, None), None);
+
+        // claimed before the expiry, the claim is counted against the old rewards
+        let r = existing_rewards(10000000, 4);
+        assert_eq!(r.expiry(305, None), Some(tokens(6)));
+        let r = existing_rewards(10000000, 10);
+        assert_eq!(r.expiry(305, None), None);
+
+        // 3 of the tokens were earned in a reward run after the cutoff
+        let r = Rewards {
+            last_calc_epoch: 310,
+            ..existing_rewards(10000000, 2)
+        };
+        assert_eq!(r.expiry(305, Some(&tokens(3))), Some(tokens(5)));
+        // claims larger than the old rewards leave nothing to expire
+        let r = Rewards {
+            last_calc_epoch: 310,
+            ..existing_rewards(10000000, 8)
+        };
+        assert_eq!(r.expiry(305, Some(&tokens(3))), None);
+
+        // legacy rewards without reward runs count as earned in the last calculated epoch
+        let r = Rewards {
+            last_calc_epoch: 310,
+            ..existing_rewards(10000000, 0)
+        };
+        assert_eq!(r.expiry(305, None), None);
+        assert_eq!(r.expiry(310, None), Some(tokens(10)));
+    }
+
+    #[test]
+    fn expired_rewards_only_grow() {
+        let tokens = |t: i64| BigDecimal::from_i64(t * 1000000).unwrap();
+        let mut r = existing_rewards(10000000, 0);
+        r.tot_expired = r.expiry(305, None).unwrap();
+        assert_eq!(r.unclaimed(), BigDecimal::from_i32(0).unwrap());
+        // a claim after the expiry does not give expired rewards back
+        r.tot_claimed = BigDecimal::from_i32(4).unwrap();
+        assert_eq!(r.expiry(305, None), None);
+        assert_eq!(r.tot_expired, tokens(10));
+
+        // rewards earned after the expiry stay claimable until they expire themselves
+        r.tot_claimed = BigDecimal::from_i32(0).unwrap();
+        r.tot_earned = tokens(13);
+        r.last_calc_epoch = 310;
+        assert_eq!(r.unclaimed(), BigDecimal::from_i32(3).unwrap());
+        assert_eq!(r.expiry(305, Some(&tokens(3))), None);
+        assert_eq!(r.expiry(310, None), Some(tokens(13)));
+    }
+
+    #[test]
+    fn expired_rewards_are_not_claimable() {
+        let r = existing_rewards(10000000, 4);
+        assert!(r.claimable_after(4));
+        assert!(r.claimable_after(9));
+        assert!(!r.claimable_after(10));
+
+        // 5 of the 10 tokens expired
+        let r = Rewards {
+            tot_expired: BigDecimal::from_i64(5000000).unwrap(),
+            ..r
+        };
+        assert!(r.claimable_after(4));
+        assert!(!r.claimable_after(5));
+        assert!(!r.claimable_after(9));
+    }
+
+    #[test]
+    #[ignore = "needs a rewards database in REWARDS_DB_URL"]
+    fn apply_and_recalculate_reward_runs() {
+        let conn = &mut establish_connection().unwrap();
+        conn.begin_test_transaction().unwrap();
+        let key = RewardRunKey {
+            contract_id: 987654,
+            user_id: 987654,
+            fingerprint: "asset1rewardruntest".to_string(),
+            epoch: 300,
+        };
+        let total = |conn: &mut PgConnection, stake_addr: &str| {
+            Rewards::get_rewards_per_token(
+                conn,
+                &stake_addr.to_string(),
+                key.contract_id,
+                key.user_id,
+                &key.fingerprint,
+            )
+            .unwrap()
+            .first()
+            .map(|r| r.tot_earned.clone())
+        };
+
+        let first = vec![
+            earned("stake_test_a", 5000000),
+            earned("stake_test_b", 3000000),
+        ];
+        let (run, diff) =
+            RewardRun::apply(conn, &key, &"first".to_string(), &first, false).unwrap();
+        assert_eq!(run.status, RUN_APPLIED);
+        assert_eq!(run.entries, 2);
+        assert!(diff.iter().all(|d| d.new_entry));
+        assert_eq!(total(conn, "stake_test_a"), Some(first[0].earned.clone()));
+
+        // an applied epoch is only replaced by a recalculation
+        assert!(RewardRun::apply(conn, &key, &"again".to_string(), &first, false).is_err());
+        let input = RewardRunInput {
+            key: key.clone(),
+            inputs_hash: "again".to_string(),
+            earned: first.clone(),
+        };
+        assert!(RewardRun::record(conn, &[input], false, false)
+            .unwrap()
+            .is_empty());
+
+        // stake_test_b does not earn anymore, a dry run changes nothing
+        let second = vec![earned("stake_test_a", 2000000)];
+        let diff = RewardRun::dry_run(conn, &key, &second, true).unwrap();
+        assert_eq!(diff.len(), 2);
+        assert_eq!(
+            diff.iter()
+                .find(|d| d.stake_addr == "stake_test_b")
+                .unwrap()
+                .tot_earned,
+            BigDecimal::from_i32(0).unwrap()
+        );
+        assert_eq!(total(conn, "stake_test_a"), Some(first[0].earned.clone()));
+
+        let (replacing, _) =
+            RewardRun::apply(conn, &key, &"second".to_string(), &second, true).unwrap();
+        assert_eq!(replacing.replaces, Some(run.id));
+        let runs =
+            RewardRun::get_runs(conn, key.contract_id, key.user_id, &key.fingerprint).unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(
+            runs.iter().find(|r| r.id == run.id).unwrap().status,
+            RUN_REVERSED
+        );
+        assert_eq!(total(conn, "stake_test_a"), Some(second[0].earned.clone()));
+        assert_eq!(
+            total(conn, "stake_test_b"),
+            Some(BigDecimal::from_i32(0).unwrap())
+        );
+
+        // a recalculation must not take back claimed rewards
+        let a = &Rewards::get_rewards_per_token(
+            conn,
+            &"stake_test_a".to_string(),
+            key.contract_id,
+            key.user_id,
+            &key.fingerprint,
+        )
+        .unwrap()[0];
+        diesel::update(rewards::table.find(a.id))
+            .set(rewards::tot_claimed.eq(BigDecimal::from_i32(2).unwrap()))
+            .execute(conn)
+            .unwrap();
+        let third = vec![earned("stake_test_a", 1000000)];
+        assert!(RewardRun::apply(conn, &key, &"third".to_string(), &third, true).is_err());
+    }
+}
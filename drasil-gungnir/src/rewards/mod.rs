@@ -18,8 +18,8 @@ use chrono::{DateTime, Utc};
 use std::fmt;
 
 use crate::schema::{
-    airdrop_parameter, airdrop_whitelist, claimed, discount, rewards, token_whitelist, whitelist,
-    wladdresses, wlalloc,
+    airdrop_parameter, airdrop_whitelist, claimed, delegation_history, discount,
+    reward_run_entries, reward_runs, rewards, token_whitelist, whitelist, wladdresses, wlalloc,
 };
 
 pub fn establish_connection() -> Result<PgConnection, RWDError> {
@@ -124,6 +124,8 @@ pub struct Rewards {
     pub created_at: DateTime<Utc>,
     #[serde(serialize_with = "to_ts")]
     pub updated_at: DateTime<Utc>,
+    /// Part of `tot_earned` which expired unclaimed, in the unit of `tot_earned`
+    pub tot_expired: BigDecimal,
 }
 
 #[derive(Insertable, Debug, Clone)]
@@ -189,6 +191,9 @@ pub struct TokenWhitelist {
     pub modificator_equ: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub loyalty_schedule: Option<serde_json::Value>,
+    /// Epochs after which unclaimed rewards expire, `None` if they never expire
+    pub expiry_epochs: Option<i64>,
 }
 
 #[derive(Insertable, Debug, Clone)]
@@ -375,3 +380,281 @@ pub struct DiscountNew<'a> {
     pub fingerprint: Option<&'a String>,
     pub metadata_path: &'a Vec<String>,
 }
+
+/// Status of a reward run whose rewards were added to the rewards table
+pub const RUN_APPLIED: &str = "applied";
+/// Status of an applied reward run which was replaced by a recalculation
+pub const RUN_REVERSED: &str = "reversed";
+
+/// Ledger entry of one reward calculation of a token on a contract for an epoch
+#[derive(Queryable, Identifiable, Debug, Clone, Serialize, Deserialize)]
+#[diesel(table_name = reward_runs)]
+pub struct RewardRun {
+    pub id: i64,
+    pub contract_id: i64,
+    pub user_id: i64,
+    pub fingerprint: String,
+    pub epoch: i64,
+    pub inputs_hash: String,
+    pub total_earned: BigDecimal,
+    pub entries: i32,
+    pub status: String,
+    pub replaces: Option<i64>,
+    #[serde(serialize_with = "to_ts")]
+    pub created_at: DateTime<Utc>,
+    #[serde(serialize_with = "to_ts")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = reward_runs)]
+pub struct RewardRunNew<'a> {
+    pub contract_id: &'a i64,
+    pub user_id: &'a i64,
+    pub fingerprint: &'a String,
+    pub epoch: &'a i64,
+    pub inputs_hash: &'a String,
+    pub total_earned: &'a BigDecimal,
+    pub entries: &'a i32,
+    pub status: &'a str,
+    pub replaces: Option<&'a i64>,
+}
+
+/// Reward of one stake address within a reward run
+#[derive(Queryable, Identifiable, Debug, Clone, Serialize, Deserialize)]
+#[diesel(table_name = reward_run_entries)]
+pub struct RewardRunEntry {
+    pub id: i64,
+    pub run_id: i64,
+    pub stake_addr: String,
+    pub payment_addr: String,
+    pub earned: BigDecimal,
+    pub tot_earned_before: BigDecimal,
+    pub tot_earned: BigDecimal,
+    pub new_entry: bool,
+    #[serde(serialize_with = "to_ts")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = reward_run_entries)]
+pub struct RewardRunEntryNew {
+    pub run_id: i64,
+    pub stake_addr: String,
+    pub payment_addr: String,
+    pub earned: BigDecimal,
+    pub tot_earned_before: BigDecimal,
+    pub tot_earned: BigDecimal,
+    pub new_entry: bool,
+}
+
+/// Token, contract and epoch a reward run is calculated for
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RewardRunKey {
+    pub contract_id: i64,
+    pub user_id: i64,
+    pub fingerprint: String,
+    pub epoch: i64,
+}
+
+/// Reward a stake address earned in a reward calculation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RewardEarned {
+    pub stake_addr: String,
+    pub payment_addr: String,
+    pub earned: BigDecimal,
+}
+
+/// Rewards calculated for a token on a contract in an epoch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RewardRunInput {
+    pub key: RewardRunKey,
+    /// Hash of the calculation data the rewards were calculated from
+    pub inputs_hash: String,
+    pub earned: Vec<RewardEarned>,
+}
+
+/// Change of the rewards of a stake address caused by a reward run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RewardDiff {
+    pub stake_addr: String,
+    pub payment_addr: String,
+    pub earned: BigDecimal,
+    pub tot_earned_before: BigDecimal,
+    pub tot_earned: BigDecimal,
+    pub new_entry: bool,
+}
+
+/// Rewards of a stake address which expired in an expiry run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RewardExpiry {
+    pub stake_addr: String,
+    pub fingerprint: String,
+    pub contract_id: i64,
+    pub user_id: i64,
+    pub tot_expired_before: BigDecimal,
+    pub tot_expired: BigDecimal,
+}
+
+/// Reward multiplier applied once a delegator reached `epochs` consecutive epochs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoyaltyStep {
+    pub epochs: i64,
+    pub multiplier: f64,
+}
+
+/// Multiplier of the longest step of `schedule` reached by `streak` epochs, 1 if none
+pub fn loyalty_multiplier(schedule: &[LoyaltyStep], streak: i64) -> f64 {
+    schedule
+        .iter()
+        .filter(|s| s.epochs <= streak)
+        .max_by_key(|s| s.epochs)
+        .map(|s| s.multiplier)
+        .unwrap_or(1.0)
+}
+
+/// Checks that every step of `schedule` needs at least one epoch and has a positive multiplier
+pub fn validate_loyalty_schedule(schedule: &[LoyaltyStep]) -> Result<(), RWDError> {
+    if schedule
+        .iter()
+        .any(|s| s.epochs < 1 || s.multiplier.is_nan() || s.multiplier <= 0.0)
+    {
+        return Err(RWDError::new(
+            "Loyalty schedule needs positive epochs and multipliers",
+        ));
+    }
+    Ok(())
+}
+
+/// Delegation of a stake address to a whitelisted pool over the calculated epochs
+#[derive(Queryable, Identifiable, Debug, Clone, Serialize, Deserialize)]
+#[diesel(table_name = delegation_history)]
+pub struct DelegationHistory {
+    pub id: i64,
+    pub stake_addr: String,
+    pub pool_id: String,
+    pub first_epoch: i64,
+    pub last_epoch: i64,
+    pub streak: i64,
+    pub total_epochs: i64,
+    #[serde(serialize_with = "to_ts")]
+    pub created_at: DateTime<Utc>,
+    #[serde(serialize_with = "to_ts")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Insertable, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[diesel(table_name = delegation_history)]
+pub struct DelegationStreak {
+    pub stake_addr: String,
+    pub pool_id: String,
+    pub first_epoch: i64,
+    pub last_epoch: i64,
+    /// Consecutive epochs up to `last_epoch`, 0 once the stake address left the pool
+    pub streak: i64,
+    pub total_epochs: i64,
+}
+
+impl DelegationStreak {
+    /// First delegation of a stake address seen in `epoch`, already `streak` epochs long
+    pub fn new(stake_addr: &str, pool_id: &str, epoch: i64, streak: i64) -> DelegationStreak {
+        let streak = streak.max(1);
+        DelegationStreak {
+            stake_addr: stake_addr.to_owned(),
+            pool_id: pool_id.to_owned(),
+            first_epoch: epoch - streak + 1,
+            last_epoch: epoch,
+            streak,
+            total_epochs: streak,
+        }
+    }
+
+    /// The delegation after the stake address was delegated to the pool in `epoch`
+    ///
+    /// The streak restarts if the stake address was not delegated to the pool in
+    /// the epoch before. Epochs which were already tracked change nothing.
+    pub fn advance(&self, epoch: i64) -> DelegationStreak {
+        let mut next = self.clone();
+        if epoch <= self.last_epoch {
+            return next;
+        }
+        next.streak = if epoch == self.last_epoch + 1 {
+            self.streak + 1
+        } else {
+            1
+        };
+        next.last_epoch = epoch;
+        next.total_epochs += 1;
+        next
+    }
+
+    /// Consecutive epochs up to `epoch`, 0 if unknown from the tracked epochs
+    pub fn streak_in(&self, epoch: i64) -> i64 {
+        let behind = self.last_epoch - epoch;
+        if behind >= 0 && behind < self.streak {
+            self.streak - behind
+        } else {
+            0
+        }
+    }
+}
+
+impl From<DelegationHistory> for DelegationStreak {
+    fn from(h: DelegationHistory) -> Self {
+        DelegationStreak {
+            stake_addr: h.stake_addr,
+            pool_id: h.pool_id,
+            first_epoch: h.first_epoch,
+            last_epoch: h.last_epoch,
+            streak: h.streak,
+            total_epochs: h.total_epochs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delegation_streaks_restart_after_a_gap() {
+        let d = DelegationStreak::new("stake_test1", "pool1", 300, 3);
+        assert_eq!((d.first_epoch, d.streak, d.total_epochs), (298, 3, 3));
+
+        let d = d.advance(301).advance(302);
+        assert_eq!((d.streak, d.total_epochs), (5, 5));
+        // tracking an epoch again changes nothing
+        assert_eq!(d.advance(302), d);
+        assert_eq!(d.streak_in(300), 3);
+        assert_eq!(d.streak_in(297), 0);
+
+        let d = d.advance(305);
+        assert_eq!((d.first_epoch, d.streak, d.total_epochs), (298, 1, 6));
+        assert_eq!(d.streak_in(302), 0);
+
+        let schedule = vec![
+            LoyaltyStep {
+                epochs: 10,
+                multiplier: 1.25,
+            },
+            LoyaltyStep {
+                epochs: 20,
+                multiplier: 1.5,
+            },
+        ];
+        assert_eq!(loyalty_multiplier(&schedule, 9), 1.0);
+        assert_eq!(loyalty_multiplier(&schedule, 10), 1.25);
+        assert_eq!(loyalty_multiplier(&schedule, 42), 1.5);
+    }
+
+    #[test]
+    fn loyalty_schedules_need_positive_steps() {
+        let step = |epochs, multiplier| LoyaltyStep { epochs, multiplier };
+        assert!(validate_loyalty_schedule(&[]).is_ok());
+        assert!(validate_loyalty_schedule(&[step(1, 0.5), step(10, 1.25)]).is_ok());
+        assert!(validate_loyalty_schedule(&[step(0, 1.25)]).is_err());
+        assert!(validate_loyalty_schedule(&[step(-3, 1.25)]).is_err());
+        assert!(validate_loyalty_schedule(&[step(10, 0.0)]).is_err());
+        assert!(validate_loyalty_schedule(&[step(10, f64::NAN)]).is_err());
+    }
+}
@@ -67,6 +67,7 @@ table! {
         last_calc_epoch -> Int8,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        tot_expired -> Numeric,
     }
 }
 
@@ -88,6 +89,8 @@ table! {
         modificator_equ -> Nullable<Text>,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        loyalty_schedule -> Nullable<Jsonb>,
+        expiry_epochs -> Nullable<Int8>,
     }
 }
 
@@ -141,6 +144,14 @@ table! {
         active -> Bool,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        cip68_ref_addr -> Nullable<Varchar>,
+        max_mint_p_stake -> Nullable<Int4>,
+        max_mint_p_wl_entry -> Nullable<Int4>,
+        reveal_placeholder -> Nullable<Text>,
+        reveal_commitment -> Nullable<Varchar>,
+        reveal_seed -> Nullable<Varchar>,
+        reveal_provenance -> Nullable<Varchar>,
+        revealed_at -> Nullable<Timestamptz>,
     }
 }
 
@@ -174,6 +185,39 @@ table! {
         minted -> Bool,
         created_at -> Timestamptz,
         updated_at -> Timestamptz,
+        phase_id -> Nullable<Int8>,
+        stake_addr -> Nullable<Varchar>,
+        wl_entry -> Nullable<Int8>,
+    }
+}
+
+table! {
+    mint_phases (id) {
+        id -> Int8,
+        project_id -> Int8,
+        phase_name -> Varchar,
+        start_slot -> Int8,
+        end_slot -> Nullable<Int8>,
+        price -> Int8,
+        pay_addr -> Nullable<Varchar>,
+        max_per_wallet -> Nullable<Int4>,
+        whitelist_id -> Nullable<Int8>,
+        gate_policy -> Nullable<Varchar>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+table! {
+    nft_reveals (project_id, asset_name_b) {
+        project_id -> Int8,
+        asset_name_b -> Bytea,
+        pool_index -> Int4,
+        metadata -> Text,
+        needs_update -> Bool,
+        update_tx -> Nullable<Varchar>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
     }
 }
 
@@ -190,11 +234,97 @@ table! {
     }
 }
 
+table! {
+    mp_listings (id) {
+        id -> Int8,
+        contract_addr -> Varchar,
+        utxo -> Varchar,
+        policy_id -> Varchar,
+        tokenname -> Varchar,
+        seller_pkh -> Varchar,
+        price -> Int8,
+        royalties_rate -> Int8,
+        royalties_pkh -> Nullable<Varchar>,
+        status -> Varchar,
+        buyer_addr -> Nullable<Varchar>,
+        listed_slot -> Int8,
+        last_slot -> Int8,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+table! {
+    mp_listing_events (id) {
+        id -> Int8,
+        listing_id -> Int8,
+        event -> Varchar,
+        txhash -> Varchar,
+        utxo -> Varchar,
+        price -> Int8,
+        buyer_addr -> Nullable<Varchar>,
+        slot -> Int8,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    delegation_history (id) {
+        id -> Int8,
+        stake_addr -> Varchar,
+        pool_id -> Varchar,
+        first_epoch -> Int8,
+        last_epoch -> Int8,
+        streak -> Int8,
+        total_epochs -> Int8,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+table! {
+    reward_runs (id) {
+        id -> Int8,
+        contract_id -> Int8,
+        user_id -> Int8,
+        fingerprint -> Varchar,
+        epoch -> Int8,
+        inputs_hash -> Varchar,
+        total_earned -> Numeric,
+        entries -> Int4,
+        status -> Varchar,
+        replaces -> Nullable<Int8>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+table! {
+    reward_run_entries (id) {
+        id -> Int8,
+        run_id -> Int8,
+        stake_addr -> Varchar,
+        payment_addr -> Varchar,
+        earned -> Numeric,
+        tot_earned_before -> Numeric,
+        tot_earned -> Numeric,
+        new_entry -> Bool,
+        created_at -> Timestamptz,
+    }
+}
+
+joinable!(mp_listing_events -> mp_listings (listing_id));
+joinable!(nft_reveals -> mint_projects (project_id));
+joinable!(reward_run_entries -> reward_runs (run_id));
+
 allow_tables_to_appear_in_same_query!(
     airdrop_parameter,
     airdrop_whitelist,
     claimed,
+    delegation_history,
     rewards,
+    reward_runs,
+    reward_run_entries,
     token_whitelist,
     wladdresses,
     wlalloc,
@@ -202,4 +332,8 @@ allow_tables_to_appear_in_same_query!(
     mint_projects,
     nft_table,
     mint_rewards,
+    mint_phases,
+    nft_reveals,
+    mp_listings,
+    mp_listing_events,
 );
@@ -0,0 +1,2 @@
+DROP TABLE auction_events;
+DROP TABLE auctions;
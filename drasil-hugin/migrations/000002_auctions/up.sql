@@ -0,0 +1,42 @@
+    CREATE TABLE auctions (
+        id BIGSERIAL PRIMARY KEY,
+        user_id BIGINT NOT NULL,
+        contract_id BIGINT NOT NULL,
+        kind VARCHAR(10) NOT NULL,
+        policy_id VARCHAR(56) NOT NULL,
+        tokenname VARCHAR(64) NOT NULL,
+        seller_addr VARCHAR(120) NOT NULL,
+        start_price BIGINT NOT NULL,
+        end_price BIGINT NOT NULL,
+        increment BIGINT NOT NULL,
+        deadline TIMESTAMPTZ NOT NULL,
+        utxo VARCHAR(80) NOT NULL,
+        highest_bid BIGINT,
+        highest_bidder VARCHAR(120),
+        status VARCHAR(20) NOT NULL,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+    );
+
+    CREATE TABLE auction_events (
+        id BIGSERIAL PRIMARY KEY,
+        auction_id BIGINT NOT NULL REFERENCES auctions(id) ON DELETE CASCADE,
+        event VARCHAR(20) NOT NULL,
+        txhash VARCHAR(120) NOT NULL,
+        utxo VARCHAR(80) NOT NULL,
+        highest_bid BIGINT,
+        highest_bidder VARCHAR(120),
+        status VARCHAR(20) NOT NULL,
+        confirmed BOOLEAN NOT NULL DEFAULT FALSE,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+    );
+
+    CREATE TRIGGER set_timestamp
+    BEFORE UPDATE ON auctions
+    FOR EACH ROW
+    EXECUTE PROCEDURE trigger_set_timestamp();
+
+    ALTER TABLE auctions ADD CONSTRAINT unique_auction_utxo UNIQUE(utxo);
+    CREATE INDEX auctions_status_deadline ON auctions(status, deadline);
+    CREATE INDEX auction_events_auction ON auction_events(auction_id);
+    CREATE INDEX auction_events_txhash ON auction_events(txhash);
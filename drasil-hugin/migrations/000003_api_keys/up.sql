@@ -0,0 +1,25 @@
+    CREATE TABLE api_keys (
+        id BIGSERIAL PRIMARY KEY,
+        user_id BIGINT NOT NULL,
+        key_id VARCHAR(64) NOT NULL,
+        token_hash VARCHAR(64) NOT NULL,
+        name VARCHAR(100) NOT NULL,
+        contract_ids BIGINT[] NOT NULL DEFAULT '{}',
+        permissions TEXT[] NOT NULL DEFAULT '{}',
+        networks BIGINT[] NOT NULL DEFAULT '{}',
+        origins TEXT[] NOT NULL DEFAULT '{}',
+        ips TEXT[] NOT NULL DEFAULT '{}',
+        expires_at TIMESTAMPTZ NOT NULL,
+        revoked_at TIMESTAMPTZ,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+    );
+
+    CREATE TRIGGER set_timestamp
+    BEFORE UPDATE ON api_keys
+    FOR EACH ROW
+    EXECUTE PROCEDURE trigger_set_timestamp();
+
+    ALTER TABLE api_keys ADD CONSTRAINT unique_api_key_id UNIQUE(key_id);
+    ALTER TABLE api_keys ADD CONSTRAINT unique_api_key_token UNIQUE(token_hash);
+    CREATE INDEX api_keys_user_id ON api_keys(user_id);
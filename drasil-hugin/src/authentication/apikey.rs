@@ -0,0 +1,450 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use warp::http::header::{HeaderMap, HeaderValue, ORIGIN};
+
+use crate::datamodel::models::{Operation, TXPWrapper};
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Permissions {
+    RewardClaimRequest,
+    RewardClaimCreateContract,
+    StandardTransactionsDeleigate,
+    Marketplace,
+    Standard,
+    Minting,
+}
+
+impl Permissions {
+    pub fn str_to_role(role: &str) -> Permissions {
+        match role {
+            "1" => Permissions::RewardClaimRequest,
+            "2" => Permissions::RewardClaimCreateContract,
+            "3" => Permissions::StandardTransactionsDeleigate,
+            "4" => Permissions::Marketplace,
+            "5" => Permissions::Minting,
+            _ => Permissions::Standard,
+        }
+    }
+
+    /// Permission an API key needs to build a transaction for the operation
+    pub fn for_operation(op: &Operation) -> Permissions {
+        match op {
+            Operation::SpoRewardClaim { .. } => Permissions::RewardClaimRequest,
+            Operation::CPO { .. } => Permissions::RewardClaimCreateContract,
+            Operation::Marketplace { .. }
+            | Operation::NftVendor { .. }
+            | Operation::NftShop { .. }
+            | Operation::NftOffer { .. }
+            | Operation::CollectionBid { .. }
+            | Operation::OfferAccept { .. }
+            | Operation::OfferCancel { .. }
+            | Operation::Auction { .. }
+            | Operation::AuctionBid { .. }
+            | Operation::AuctionSettle { .. }
+            | Operation::AuctionCancel { .. } => Permissions::Marketplace,
+            Operation::Minter { .. }
+            | Operation::NftCollectionMinter { .. }
+            | Operation::TokenMinter { .. }
+            | Operation::ClApiOneShotMint { .. }
+            | Operation::ClApiCip68Update { .. } => Permissions::Minting,
+            Operation::StakeDelegation { .. }
+            | Operation::StakeDeregistration { .. }
+            | Operation::RewardWithdrawal { .. }
+            | Operation::DRep { .. }
+            | Operation::VoteDelegation { .. }
+            | Operation::GovernanceVote { .. } => Permissions::StandardTransactionsDeleigate,
+            Operation::StdTx { .. } => Permissions::Standard,
+        }
+    }
+}
+
+impl fmt::Display for Permissions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Permissions::RewardClaimRequest => write!(f, "1"),
+            Permissions::RewardClaimCreateContract => write!(f, "2"),
+            Permissions::StandardTransactionsDeleigate => write!(f, "3"),
+            Permissions::Marketplace => write!(f, "4"),
+            Permissions::Minting => write!(f, "5"),
+            Permissions::Standard => write!(f, "0"),
+        }
+    }
+}
+
+/// Restrictions of a scoped API key, an empty list does not restrict
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ApiScope {
+    /// Contract ids the key can use
+    #[serde(default)]
+    pub contracts: Vec<i64>,
+    /// Operation types the key can build transactions for
+    #[serde(default)]
+    pub permissions: Vec<Permissions>,
+    /// Network ids the key can build transactions on, 0 testnet and 1 mainnet
+    #[serde(default)]
+    pub networks: Vec<u64>,
+    /// Values of the `Origin` header the key is accepted from
+    #[serde(default)]
+    pub origins: Vec<String>,
+    /// Client ip addresses the key is accepted from
+    #[serde(default)]
+    pub ips: Vec<String>,
+}
+
+/// Claims of the client facing JWTs
+///
+/// Account tokens only carry `sub` and `exp`, scoped API keys carry their key id
+/// in `jti` and their scope.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ApiClaims {
+    pub sub: String,
+    pub exp: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<ApiScope>,
+}
+
+impl ApiClaims {
+    /// Checks the origin and client ip of the request if the token is a scoped API key,
+    /// `remote` is the peer address of the connection
+    pub fn check_request(
+        &self,
+        headers: &HeaderMap<HeaderValue>,
+        remote: Option<SocketAddr>,
+    ) -> Result<(), String> {
+        match &self.scope {
+            Some(scope) => scope.allows_request(headers, client_ip(headers, remote)),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks a request reading data if the token is a scoped API key, `contracts`
+    /// is `None` if the request reads the data of all contracts of the user
+    pub fn check_access(
+        &self,
+        permission: &Permissions,
+        contracts: Option<&[i64]>,
+    ) -> Result<(), String> {
+        match &self.scope {
+            Some(scope) => scope.allows_access(permission, contracts),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks a client api payload if the token is a scoped API key
+    pub fn check_payload(&self, payload: &TXPWrapper) -> Result<(), String> {
+        match &self.scope {
+            Some(scope) => scope.allows_payload(payload),
+            None => Ok(()),
+        }
+    }
+}
+
+impl ApiScope {
+    pub fn allows(
+        &self,
+        permission: &Permissions,
+        contracts: &[i64],
+        network: Option<u64>,
+    ) -> Result<(), String> {
+        if !self.permissions.is_empty() && !self.permissions.contains(permission) {
+            return Err(format!("API key does not permit {permission:?}"));
+        }
+        if !self.contracts.is_empty() {
+            if let Some(c) = contracts.iter().find(|c| !self.contracts.contains(c)) {
+                return Err(format!("API key does not permit contract {c}"));
+            }
+        }
+        if let Some(n) = network {
+            if !self.networks.is_empty() && !self.networks.contains(&n) {
+                return Err(format!("API key does not permit network {n}"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks a request reading data, `contracts` is `None` if it reads the data of
+    /// all contracts of the user, which keys restricted to contracts cannot
+    pub fn allows_access(
+        &self,
+        permission: &Permissions,
+        contracts: Option<&[i64]>,
+    ) -> Result<(), String> {
+        match contracts {
+            Some(contracts) => self.allows(permission, contracts, None),
+            None => {
+                self.allows_all_contracts()?;
+                self.allows(permission, &[], None)
+            }
+        }
+    }
+
+    /// Keys restricted to contracts cannot use anything not tied to one of them
+    fn allows_all_contracts(&self) -> Result<(), String> {
+        if self.contracts.is_empty() {
+            Ok(())
+        } else {
+            Err("API key only permits the data and operations of its contracts".to_string())
+        }
+    }
+
+    /// Checks the `Origin` header and the client ip of a request against the allowlists
+    pub fn allows_request(
+        &self,
+        headers: &HeaderMap<HeaderValue>,
+        client: Option<IpAddr>,
+    ) -> Result<(), String> {
+        if !self.origins.is_empty() {
+            let origin = headers.get(ORIGIN).and_then(|o| o.to_str().ok());
+            if !origin.map_or(false, |o| self.origins.iter().any(|a| a == o)) {
+                return Err("API key does not permit this origin".to_string());
+            }
+        }
+        if !self.ips.is_empty() {
+            let allowed = client.map_or(false, |ip| {
+                self.ips
+                    .iter()
+                    .any(|a| a.parse::<IpAddr>().map_or(false, |a| a == ip))
+            });
+            if !allowed {
+                return Err("API key does not permit this ip address".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks operation type, contracts and network of a client api payload
+    pub fn allows_payload(&self, payload: &TXPWrapper) -> Result<(), String> {
+        match payload {
+            TXPWrapper::TransactionPattern(txp) => {
+                let mut contracts = txp
+                    .contract_id()
+                    .map(|c| vec![c as i64])
+                    .unwrap_or_default();
+                let permission = match txp.operation() {
+                    Some(op) => {
+                        contracts.extend(operation_contracts(&op));
+                        Permissions::for_operation(&op)
+                    }
+                    None => Permissions::Standard,
+                };
+                if contracts.is_empty() {
+                    self.allows_all_contracts()?;
+                }
+                self.allows(&permission, &contracts, Some(txp.network()))
+            }
+            TXPWrapper::OneShotMinter(p) => {
+                self.allows_all_contracts()?;
+                self.allows(&Permissions::Minting, &[], Some(p.network()))
+            }
+            TXPWrapper::Cip68Update(p) => {
+                self.allows_all_contracts()?;
+                self.allows(&Permissions::Minting, &[], Some(p.network()))
+            }
+            // finalizes a transaction which was built with the key
+            TXPWrapper::Signature(_) | TXPWrapper::WalletTransaction() => Ok(()),
+        }
+    }
+}
+
+/// Contract ids an operation refers to in its payload
+fn operation_contracts(op: &Operation) -> Vec<i64> {
+    match op {
+        Operation::SpoRewardClaim { rewards, .. } => {
+            rewards.iter().map(|r| r.get_contract_id()).collect()
+        }
+        Operation::Minter { contract_id, .. } => vec![*contract_id],
+        _ => Vec::new(),
+    }
+}
+
+/// Number of proxies in front of the service which append to `X-Forwarded-For`
+fn trusted_proxies() -> usize {
+    std::env::var("TRUSTED_PROXIES")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Client ip of a request, see [`forwarded_ip`]
+fn client_ip(headers: &HeaderMap<HeaderValue>, remote: Option<SocketAddr>) -> Option<IpAddr> {
+    forwarded_ip(headers, remote, trusted_proxies())
+}
+
+/// Client ip as seen by the outermost of `proxies` trusted proxies
+///
+/// Each proxy appends the address it received the request from to
+/// `X-Forwarded-For`, entries left of the ones the trusted proxies appended are
+/// set by the client. Without trusted proxies the peer address of the
+/// connection is the client.
+fn forwarded_ip(
+    headers: &HeaderMap<HeaderValue>,
+    remote: Option<SocketAddr>,
+    proxies: usize,
+) -> Option<IpAddr> {
+    if proxies == 0 {
+        return remote.map(|r| r.ip());
+    }
+    headers
+        .get("x-forwarded-for")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.rsplit(',').nth(proxies - 1))
+        .and_then(|ip| ip.trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datamodel::models::{OneShotMintPayload, TransactionPattern};
+
+    #[test]
+    fn empty_scope_allows_everything() {
+        let scope = ApiScope::default();
+        assert!(scope
+            .allows(&Permissions::Marketplace, &[3], Some(1))
+            .is_ok());
+        assert!(scope.allows_request(&HeaderMap::new(), None).is_ok());
+    }
+
+    #[test]
+    fn scope_restricts_operations_contracts_and_networks() {
+        let scope = ApiScope {
+            contracts: vec![7],
+            permissions: vec![Permissions::RewardClaimRequest],
+            networks: vec![0],
+            ..Default::default()
+        };
+        assert!(scope
+            .allows(&Permissions::RewardClaimRequest, &[7], Some(0))
+            .is_ok());
+        assert!(scope
+            .allows(&Permissions::RewardClaimRequest, &[], Some(0))
+            .is_ok());
+        assert!(scope
+            .allows(&Permissions::Marketplace, &[7], Some(0))
+            .is_err());
+        assert!(scope
+            .allows(&Permissions::RewardClaimRequest, &[7, 8], Some(0))
+            .is_err());
+        assert!(scope
+            .allows(&Permissions::RewardClaimRequest, &[7], Some(1))
+            .is_err());
+    }
+
+    #[test]
+    fn access_to_contract_data() {
+        let scope = ApiScope {
+            contracts: vec![7],
+            permissions: vec![Permissions::RewardClaimRequest],
+            ..Default::default()
+        };
+        assert!(scope
+            .allows_access(&Permissions::RewardClaimRequest, Some(&[7]))
+            .is_ok());
+        assert!(scope
+            .allows_access(&Permissions::RewardClaimRequest, Some(&[]))
+            .is_ok());
+        assert!(scope
+            .allows_access(&Permissions::RewardClaimRequest, Some(&[8]))
+            .is_err());
+        assert!(scope
+            .allows_access(&Permissions::RewardClaimRequest, None)
+            .is_err());
+        assert!(scope
+            .allows_access(&Permissions::Marketplace, Some(&[]))
+            .is_err());
+        assert!(ApiScope::default()
+            .allows_access(&Permissions::Marketplace, None)
+            .is_ok());
+    }
+
+    #[test]
+    fn contract_scoped_keys_cannot_run_untied_operations() {
+        let scope = ApiScope {
+            contracts: vec![7],
+            ..Default::default()
+        };
+        let stdtx = TXPWrapper::TransactionPattern(Box::new(TransactionPattern::new_empty(
+            1,
+            &Operation::StdTx {
+                transfers: Vec::new(),
+                wallet_addresses: None,
+            },
+            0,
+        )));
+        assert!(scope.allows_payload(&stdtx).is_err());
+        assert!(ApiScope::default().allows_payload(&stdtx).is_ok());
+
+        let oneshot: OneShotMintPayload = serde_json::from_str(
+            r#"{"tokennames":["T"],"amounts":[1],"metadata":{"assets":[],"other":null,"version":"1.0"},"receiver":"","network":0}"#,
+        )
+        .unwrap();
+        let oneshot = TXPWrapper::OneShotMinter(oneshot);
+        assert!(scope.allows_payload(&oneshot).is_err());
+        assert!(ApiScope::default().allows_payload(&oneshot).is_ok());
+
+        let minter = TXPWrapper::TransactionPattern(Box::new(TransactionPattern::new_empty(
+            1,
+            &Operation::Minter {
+                mint_tokens: None,
+                receiver_stake_addr: None,
+                receiver_payment_addr: String::new(),
+                mint_metadata: None,
+                auto_mint: None,
+                contract_id: 7,
+            },
+            0,
+        )));
+        assert!(scope.allows_payload(&minter).is_ok());
+    }
+
+    #[test]
+    fn allowlists_check_origin_and_client_ip() {
+        let scope = ApiScope {
+            origins: vec!["https://dapp.example".to_string()],
+            ips: vec!["10.0.0.1".to_string()],
+            ..Default::default()
+        };
+        let allowed: SocketAddr = "10.0.0.1:443".parse().unwrap();
+        let other: SocketAddr = "10.0.0.2:443".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(ORIGIN, HeaderValue::from_static("https://dapp.example"));
+        assert!(scope
+            .allows_request(&headers, forwarded_ip(&headers, Some(allowed), 0))
+            .is_ok());
+        assert!(scope
+            .allows_request(&headers, forwarded_ip(&headers, Some(other), 0))
+            .is_err());
+
+        // headers set by the client are not trusted without proxies
+        headers.insert("x-real-ip", HeaderValue::from_static("10.0.0.1"));
+        headers.insert("x-forwarded-for", HeaderValue::from_static("10.0.0.1"));
+        assert!(scope
+            .allows_request(&headers, forwarded_ip(&headers, Some(other), 0))
+            .is_err());
+
+        // behind one proxy only the hop it appended counts
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("10.0.0.1, 10.0.0.2"),
+        );
+        assert!(scope
+            .allows_request(&headers, forwarded_ip(&headers, Some(allowed), 1))
+            .is_err());
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("172.16.0.2, 10.0.0.1"),
+        );
+        assert!(scope
+            .allows_request(&headers, forwarded_ip(&headers, Some(other), 1))
+            .is_ok());
+        assert!(forwarded_ip(&headers, Some(other), 3).is_none());
+
+        headers.insert(ORIGIN, HeaderValue::from_static("https://other.example"));
+        assert!(scope
+            .allows_request(&headers, forwarded_ip(&headers, Some(allowed), 0))
+            .is_err());
+    }
+}
@@ -1,28 +1,42 @@
 use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
-use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
 use std::str;
+use std::sync::OnceLock;
 use warp::http::header::{HeaderMap, HeaderValue, AUTHORIZATION};
 use warp::{reject, Rejection};
 
+use super::apikey::ApiClaims;
 use super::error::Error;
+use crate::client::ClientPool;
 use crate::{
-    OneShotMintPayload, Signature, TXPWrapper, TransactionPattern, WalletTransactionPattern,
+    Cip68UpdatePayload, OneShotMintPayload, Signature, TXPWrapper, TransactionPattern, VerifyUser,
+    WalletTransactionPattern,
 };
 
 const BEARER: &str = "Bearer ";
 
-#[derive(Debug, Deserialize, Serialize)]
-struct ApiClaims {
-    sub: String,
-    exp: usize,
+// Connections to odin shared by all requests verifying API keys
+static ODIN: OnceLock<ClientPool> = OnceLock::new();
+const DEFAULT_ODIN_CONNECTIONS: usize = 4;
+
+fn odin() -> Result<&'static ClientPool, Error> {
+    if let Some(pool) = ODIN.get() {
+        return Ok(pool);
+    }
+    let addr =
+        std::env::var("ODIN_URL").map_err(|e| Error::ImproperlyConfigError(e.to_string()))?;
+    let size = std::env::var("ODIN_CONNECTIONS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_ODIN_CONNECTIONS);
+    Ok(ODIN.get_or_init(|| ClientPool::new(addr, size)))
 }
 
 pub async fn authorize(
     headers: HeaderMap<HeaderValue>,
+    remote: Option<SocketAddr>,
     body: bytes::Bytes,
 ) -> Result<(u64, TXPWrapper), Rejection> {
-    let publ =
-        std::env::var("JWT_PUB_KEY").map_err(|e| Error::ImproperlyConfigError(e.to_string()))?;
     log::info!("checking login data ...");
     let b = body.to_vec();
 
@@ -36,6 +50,8 @@ pub async fn authorize(
         TXPWrapper::TransactionPattern(Box::new(wal.into_txp()))
     } else if let Ok(_wal) = serde_json::from_str::<OneShotMintPayload>(str_slice) {
         TXPWrapper::OneShotMinter(serde_json::from_str::<OneShotMintPayload>(str_slice).unwrap())
+    } else if let Ok(upd) = serde_json::from_str::<Cip68UpdatePayload>(str_slice) {
+        TXPWrapper::Cip68Update(upd)
     } else {
         log::debug!(
             "txp_out: {:?}",
@@ -43,8 +59,30 @@ pub async fn authorize(
         );
         return Err(reject::not_found());
     };
+    let (user_id, claims) = verify(&headers, remote).await?;
+    claims
+        .check_payload(&txp_out)
+        .map_err(|e| reject::custom(Error::Forbidden(e)))?;
+    log::debug!("Authentication successful: User_id: {user_id:?}; txp: {txp_out:?}");
+    Ok((user_id, txp_out))
+}
+
+/// Checks the bearer token of requests without a body and returns the user id
+pub async fn authorize_header(
+    headers: HeaderMap<HeaderValue>,
+    remote: Option<SocketAddr>,
+) -> Result<u64, Rejection> {
+    Ok(verify(&headers, remote).await?.0)
+}
+
+async fn verify(
+    headers: &HeaderMap<HeaderValue>,
+    remote: Option<SocketAddr>,
+) -> Result<(u64, ApiClaims), Rejection> {
+    let publ =
+        std::env::var("JWT_PUB_KEY").map_err(|e| Error::ImproperlyConfigError(e.to_string()))?;
     let publ = publ.into_bytes();
-    match jwt_from_header(&headers) {
+    match jwt_from_header(headers) {
         Ok(jwt) => {
             let decoded = decode::<ApiClaims>(
                 &jwt,
@@ -54,6 +92,21 @@ pub async fn authorize(
             .map_err(Error::JWTTokenError)?;
             log::info!("lookup user data ...");
             let user_id: u64 = decoded.claims.sub.parse().map_err(Error::ParseIntError)?;
+            decoded
+                .claims
+                .check_request(headers, remote)
+                .map_err(|e| reject::custom(Error::Forbidden(e)))?;
+            // Scoped API keys can be revoked individually
+            if decoded.claims.jti.is_some() {
+                let client = odin()?
+                    .get()
+                    .await
+                    .map_err(|e| Error::Custom(e.to_string()))?;
+                client
+                    .build_cmd::<VerifyUser>(VerifyUser::new(user_id, jwt))
+                    .await
+                    .map_err(|_| reject::custom(Error::Forbidden("API key revoked".to_string())))?;
+            }
             // Deactivates User Identification, only API token validity checked
             // This code will make sure that the API token is correctly registered with this user
             //
@@ -66,8 +119,7 @@ pub async fn authorize(
             //        return Err(reject::custom(Error::JWTTokenError));
             //    }
             //};
-            log::debug!("Authentication successful: User_id: {user_id:?}; txp: {txp_out:?}");
-            Ok((user_id, txp_out))
+            Ok((user_id, decoded.claims))
         }
 
         Err(e) => {
@@ -8,6 +8,8 @@ pub enum Error {
     NoAuthHeaderError,
     #[error("invalid auth header")]
     InvalidAuthHeaderError,
+    #[error("forbidden: {0}")]
+    Forbidden(String),
     #[error("internal error: {:?}", self)]
     Custom(String),
     #[error("{0}")]
@@ -1,48 +1,297 @@
-use crate::protocol::{Connection, Frame, IntoFrame};
-use bc::Options;
-use bincode as bc;
+//! Client for the odin protocol
+//!
+//! A [`Client`] is a cheap handle to one long-lived connection. All clones of
+//! a client share the connection, their commands are pipelined and the
+//! responses are matched to the waiting callers by request id. A
+//! [`ClientPool`] keeps a fixed number of such connections to one odin
+//! instance and replaces connections which were closed. Connections use
+//! mutual TLS if a [`TlsConfig`] is given or configured in the environment.
+use crate::protocol::{handshake, Connection, Encoding, Frame, IntoFrame, TlsConfig};
+use std::collections::BTreeMap;
 use std::io::{Error, ErrorKind};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::{TcpStream, ToSocketAddrs};
+use tokio::sync::{mpsc, oneshot, Mutex};
 
+/// Time a command may take before the client stops waiting for its response
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+// Requests which can be queued for one connection before callers have to wait
+const MAX_QUEUED_REQUESTS: usize = 64;
+
+type Responder = oneshot::Sender<crate::Result<Frame>>;
+
+struct Request {
+    id: u64,
+    frame: Frame,
+    responder: Responder,
+}
+
+#[derive(Clone)]
 pub struct Client {
-    pub connection: Connection,
+    requests: mpsc::Sender<Request>,
+    next_id: Arc<AtomicU64>,
+    version: u64,
+    encoding: Encoding,
+    timeout: Duration,
+}
+
+pub async fn connect<T: ToSocketAddrs + ToString>(addr: T) -> crate::Result<Client> {
+    connect_with(addr, Encoding::default()).await
 }
 
-pub async fn connect<T: ToSocketAddrs>(addr: T) -> crate::Result<Client> {
+/// Connects and negotiates the protocol version and payload encoding. Uses
+/// TLS if it is configured in the environment, see [`TlsConfig::from_env`].
+pub async fn connect_with<T: ToSocketAddrs + ToString>(
+    addr: T,
+    encoding: Encoding,
+) -> crate::Result<Client> {
+    let tls = TlsConfig::from_env()?;
+    connect_tls(addr, encoding, tls.as_ref()).await
+}
+
+/// Connects over TLS if `tls` is given, plain TCP otherwise
+pub async fn connect_tls<T: ToSocketAddrs + ToString>(
+    addr: T,
+    encoding: Encoding,
+    tls: Option<&TlsConfig>,
+) -> crate::Result<Client> {
+    let server_name = addr.to_string();
     let socket = TcpStream::connect(addr).await?;
-    let connection = Connection::new(socket);
+    let mut connection = match tls {
+        Some(tls) => {
+            let stream = tls
+                .connector()?
+                .connect(tls.server_name(&server_name)?, socket)
+                .await?;
+            Connection::from_stream(stream)
+        }
+        None => Connection::new(socket),
+    };
+    connection.handshake(encoding).await?;
 
-    Ok(Client { connection })
+    let (requests, receiver) = mpsc::channel(MAX_QUEUED_REQUESTS);
+    let client = Client {
+        requests,
+        next_id: Arc::new(AtomicU64::new(0)),
+        version: connection.version(),
+        encoding: connection.encoding(),
+        timeout: DEFAULT_TIMEOUT,
+    };
+    tokio::spawn(run_connection(connection, receiver));
+
+    Ok(client)
 }
 
 impl Client {
-    pub async fn build_cmd<T: IntoFrame>(&mut self, cmd: T) -> crate::Result<String> {
-        let frame = cmd.into_frame();
+    pub fn with_timeout(mut self, timeout: Duration) -> Client {
+        self.timeout = timeout;
+        self
+    }
+
+    /// True if the connection of this client is gone
+    pub fn is_closed(&self) -> bool {
+        self.requests.is_closed()
+    }
+
+    pub async fn build_cmd<T: IntoFrame>(&self, cmd: T) -> crate::Result<String> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let frame = cmd.into_frame(self.encoding);
+        let frame = if self.version > 0 {
+            handshake::request(id, frame)
+        } else {
+            frame
+        };
         log::debug!("Send frame: {:?}", frame);
-        self.connection.write_frame(&frame).await?;
+        let (responder, response) = oneshot::channel();
+        self.requests
+            .send(Request {
+                id,
+                frame,
+                responder,
+            })
+            .await
+            .map_err(|_| connection_closed("connection to odin is closed"))?;
+
         log::debug!("Read response ...");
-        match self.read_response().await? {
+        let response = match tokio::time::timeout(self.timeout, response).await {
+            Ok(Ok(response)) => response?,
+            Ok(Err(_)) => return Err(connection_closed("connection aborted by server")),
+            Err(_) => {
+                return Err(Error::new(
+                    ErrorKind::TimedOut,
+                    format!("no response to request {id} within {:?}", self.timeout),
+                )
+                .into())
+            }
+        };
+        log::debug!("{:?}", response);
+
+        match response {
             Frame::Simple(response) => Ok(response),
-            Frame::Bulk(data) => Ok(bc::DefaultOptions::new()
-                .with_varint_encoding()
-                .deserialize::<String>(&data)?),
+            Frame::Bulk(data) => self.encoding.decode::<String>(&data),
             frame => Err(frame.to_error()),
         }
     }
+}
 
-    async fn read_response(&mut self) -> crate::Result<Frame> {
-        let response = self.connection.read_frame().await?;
+fn connection_closed(reason: &str) -> crate::Error {
+    Error::new(ErrorKind::ConnectionReset, reason.to_string()).into()
+}
 
-        log::debug!("{:?}", response);
+/// Writes the queued requests and hands each response to the caller waiting
+/// for it. Stops when all client handles are dropped or the connection fails.
+async fn run_connection(mut connection: Connection, mut requests: mpsc::Receiver<Request>) {
+    let version = connection.version();
+    let mut pending = BTreeMap::<u64, Responder>::new();
 
-        match response {
-            Some(Frame::Error(msg)) => Err(msg.into()),
-            Some(frame) => Ok(frame),
-            None => {
-                let err = Error::new(ErrorKind::ConnectionReset, "connection aborted by server");
+    let reason = loop {
+        tokio::select! {
+            request = requests.recv() => match request {
+                Some(request) => {
+                    if let Err(err) = connection.write_frame(&request.frame).await {
+                        let _ = request.responder.send(Err(err.into()));
+                        break "could not write to odin".to_string();
+                    }
+                    pending.insert(request.id, request.responder);
+                }
+                None => return,
+            },
+            response = connection.read_frame() => match response {
+                Ok(Some(frame)) => {
+                    // odin answers in order, responses without envelope belong to
+                    // the oldest request
+                    let (responder, frame) = match handshake::open_response(frame) {
+                        Ok((id, frame)) => (pending.remove(&id), frame),
+                        Err(frame) => (pending.pop_first().map(|(_, r)| r), frame),
+                    };
+                    match responder {
+                        Some(responder) => {
+                            let _ = responder.send(match frame {
+                                Frame::Error(msg) if version > 0 => {
+                                    Err(Frame::into_protocol_error(&msg))
+                                }
+                                Frame::Error(msg) => Err(msg.into()),
+                                frame => Ok(frame),
+                            });
+                        }
+                        // the caller gave up waiting
+                        None => log::debug!("dropped response: {:?}", frame),
+                    }
+                }
+                Ok(None) => break "connection aborted by server".to_string(),
+                Err(err) => break err.to_string(),
+            },
+        }
+    };
 
-                Err(err.into())
-            }
+    log::warn!("odin connection closed: {}", reason);
+    for (_, responder) in pending {
+        let _ = responder.send(Err(connection_closed(&reason)));
+    }
+}
+
+/// Fixed number of connections to one odin instance, handed out round robin
+pub struct ClientPool {
+    addr: String,
+    encoding: Encoding,
+    timeout: Duration,
+    tls: Option<TlsConfig>,
+    clients: Vec<Mutex<Option<Client>>>,
+    next: AtomicUsize,
+}
+
+impl ClientPool {
+    /// Connections are opened on first use
+    pub fn new(addr: impl Into<String>, size: usize) -> ClientPool {
+        ClientPool {
+            addr: addr.into(),
+            encoding: Encoding::default(),
+            timeout: DEFAULT_TIMEOUT,
+            tls: None,
+            clients: (0..size.max(1)).map(|_| Mutex::new(None)).collect(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn with_encoding(mut self, encoding: Encoding) -> ClientPool {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> ClientPool {
+        self.timeout = timeout;
+        self
+    }
+
+    /// TLS settings for the connections, without them the environment decides
+    pub fn with_tls(mut self, tls: TlsConfig) -> ClientPool {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Client of the next connection, reconnects if the connection was closed
+    pub async fn get(&self) -> crate::Result<Client> {
+        let slot = self.next.fetch_add(1, Ordering::Relaxed) % self.clients.len();
+        let mut slot = self.clients[slot].lock().await;
+        if let Some(client) = slot.as_ref().filter(|c| !c.is_closed()) {
+            return Ok(client.clone());
         }
+        let client = match &self.tls {
+            Some(tls) => connect_tls(self.addr.as_str(), self.encoding, Some(tls)).await?,
+            None => connect_with(self.addr.as_str(), self.encoding).await?,
+        }
+        .with_timeout(self.timeout);
+        *slot = Some(client.clone());
+        Ok(client)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::Hello;
+    use crate::{Parse, VerifyUser};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn pipelined_responses_are_matched_by_id() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut connection = Connection::new(socket);
+            let hello = connection.read_frame().await.unwrap().unwrap();
+            let hello = Hello::from_frame(&hello).unwrap().unwrap();
+            let hello = hello.negotiate().unwrap();
+            connection.write_frame(&hello.into_frame()).await.unwrap();
+
+            let mut tokens = Vec::new();
+            for _ in 0..2 {
+                let frame = connection.read_frame().await.unwrap().unwrap();
+                let (id, cmd) = handshake::open_request(frame).unwrap();
+                let mut parse = Parse::new(cmd, hello.encoding).unwrap();
+                parse.next_string().unwrap();
+                parse.next_int().unwrap();
+                tokens.push((id, parse.next_value::<String>().unwrap()));
+            }
+            // answer out of order
+            for (id, token) in tokens.into_iter().rev() {
+                let response = Frame::Bulk(hello.encoding.encode(&token).unwrap());
+                connection
+                    .write_frame(&handshake::response(id, response))
+                    .await
+                    .unwrap();
+            }
+        });
+
+        let client = connect_tls(addr, Encoding::Json, None).await.unwrap();
+        let (a, b) = tokio::join!(
+            client.build_cmd(VerifyUser::new(1, "a".to_string())),
+            client.build_cmd(VerifyUser::new(2, "b".to_string()))
+        );
+        assert_eq!(a.unwrap(), "a");
+        assert_eq!(b.unwrap(), "b");
     }
 }
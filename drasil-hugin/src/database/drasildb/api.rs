@@ -819,3 +819,240 @@ impl TBCaPaymentHash {
         Ok(())
     }
 }
+
+impl TBAuction {
+    pub fn find(id_in: &i64) -> Result<Self, SystemDBError> {
+        let result = auctions::table
+            .find(id_in)
+            .first::<TBAuction>(&mut establish_connection()?)?;
+        Ok(result)
+    }
+
+    pub fn find_by_utxo(utxo_in: &str) -> Result<Self, SystemDBError> {
+        let result = auctions::table
+            .filter(auctions::utxo.eq(utxo_in))
+            .first::<TBAuction>(&mut establish_connection()?)?;
+        Ok(result)
+    }
+
+    /// Auctions of a user which have not ended yet, the next deadline first
+    pub fn get_live(user_id_in: &i64) -> Result<Vec<Self>, SystemDBError> {
+        let result = auctions::table
+            .filter(auctions::user_id.eq(user_id_in))
+            .filter(auctions::status.eq(AUCTION_LIVE))
+            .filter(auctions::deadline.gt(Utc::now()))
+            .order(auctions::deadline.asc())
+            .load::<TBAuction>(&mut establish_connection()?)?;
+        Ok(result)
+    }
+
+    /// Stores a started auction, it stays pending until geri confirms its transaction
+    pub fn create(auction: &TBAuctionNew<'_>, txhash: &str) -> Result<Self, SystemDBError> {
+        let conn = &mut establish_connection()?;
+        conn.transaction(|conn| {
+            let result = diesel::insert_into(auctions::table)
+                .values(auction)
+                .get_result::<TBAuction>(conn)?;
+            result.add_event(
+                conn,
+                AUCTION_EVENT_START,
+                txhash,
+                auction.utxo,
+                None,
+                None,
+                AUCTION_LIVE,
+            )?;
+            Ok(result)
+        })
+    }
+
+    /// Records a new highest bid of a submitted transaction, the auction moves to `utxo_in`
+    pub fn record_bid(
+        &self,
+        txhash: &str,
+        utxo_in: &str,
+        bid: &i64,
+        bidder: &str,
+    ) -> Result<TBAuctionEvent, SystemDBError> {
+        self.add_event(
+            &mut establish_connection()?,
+            AUCTION_EVENT_BID,
+            txhash,
+            utxo_in,
+            Some(bid),
+            Some(bidder),
+            AUCTION_LIVE,
+        )
+    }
+
+    /// Records the settlement or cancellation of a submitted transaction
+    pub fn record_close(&self, event: &str, txhash: &str) -> Result<TBAuctionEvent, SystemDBError> {
+        let status = match event {
+            AUCTION_EVENT_SETTLE => AUCTION_SETTLED,
+            AUCTION_EVENT_CANCEL => AUCTION_CANCELLED,
+            _ => {
+                return Err(SystemDBError::Custom(format!(
+                    "an auction can not be closed by a '{event}' event"
+                )))
+            }
+        };
+        self.add_event(
+            &mut establish_connection()?,
+            event,
+            txhash,
+            &self.utxo,
+            self.highest_bid.as_ref(),
+            self.highest_bidder.as_deref(),
+            status,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn add_event(
+        &self,
+        conn: &mut PgConnection,
+        event: &str,
+        txhash: &str,
+        utxo: &str,
+        highest_bid: Option<&i64>,
+        highest_bidder: Option<&str>,
+        status: &str,
+    ) -> Result<TBAuctionEvent, SystemDBError> {
+        let new_event = TBAuctionEventNew {
+            auction_id: &self.id,
+            event,
+            txhash,
+            utxo,
+            highest_bid,
+            highest_bidder,
+            status,
+        };
+        Ok(diesel::insert_into(auction_events::table)
+            .values(&new_event)
+            .get_result::<TBAuctionEvent>(conn)?)
+    }
+
+    /// Applies the auction events of a transaction included in a block
+    pub fn confirm_tx(txhash: &str) -> Result<usize, SystemDBError> {
+        Self::set_tx_confirmed(txhash, true)
+    }
+
+    /// Takes back the auction events of a rolled back transaction until it is included again
+    pub fn revert_tx(txhash: &str) -> Result<usize, SystemDBError> {
+        Self::set_tx_confirmed(txhash, false)
+    }
+
+    /// Drops the auction events of an expired transaction, auctions it started are removed
+    pub fn release_tx(txhash: &str) -> Result<usize, SystemDBError> {
+        let conn = &mut establish_connection()?;
+        conn.transaction(|conn| {
+            let released =
+                diesel::delete(auction_events::table.filter(auction_events::txhash.eq(txhash)))
+                    .get_results::<TBAuctionEvent>(conn)?;
+            Self::refresh(conn, &released)?;
+            Ok(released.len())
+        })
+    }
+
+    fn set_tx_confirmed(txhash: &str, confirmed_in: bool) -> Result<usize, SystemDBError> {
+        let conn = &mut establish_connection()?;
+        conn.transaction(|conn| {
+            let changed =
+                diesel::update(auction_events::table.filter(auction_events::txhash.eq(txhash)))
+                    .set(auction_events::confirmed.eq(confirmed_in))
+                    .get_results::<TBAuctionEvent>(conn)?;
+            Self::refresh(conn, &changed)?;
+            Ok(changed.len())
+        })
+    }
+
+    /// Sets the auctions of `changed` to the state of their last confirmed event
+    fn refresh(conn: &mut PgConnection, changed: &[TBAuctionEvent]) -> Result<(), SystemDBError> {
+        let mut auction_ids = changed.iter().map(|e| e.auction_id).collect::<Vec<_>>();
+        auction_ids.sort_unstable();
+        auction_ids.dedup();
+
+        for auction_id in auction_ids {
+            let events = auction_events::table
+                .filter(auction_events::auction_id.eq(auction_id))
+                .order(auction_events::id.asc())
+                .load::<TBAuctionEvent>(conn)?;
+            if events.is_empty() {
+                diesel::delete(auctions::table.find(auction_id)).execute(conn)?;
+                continue;
+            }
+            match TBAuctionEvent::current(&events) {
+                Some(current) => {
+                    diesel::update(auctions::table.find(auction_id))
+                        .set((
+                            auctions::utxo.eq(&current.utxo),
+                            auctions::highest_bid.eq(current.highest_bid),
+                            auctions::highest_bidder.eq(&current.highest_bidder),
+                            auctions::status.eq(&current.status),
+                        ))
+                        .execute(conn)?;
+                }
+                None => {
+                    diesel::update(auctions::table.find(auction_id))
+                        .set(auctions::status.eq(AUCTION_PENDING))
+                        .execute(conn)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TBAuctionEvent {
+    /// The last confirmed of the chronologically ordered `events`
+    pub fn current(events: &[TBAuctionEvent]) -> Option<&TBAuctionEvent> {
+        events.iter().rev().find(|e| e.confirmed)
+    }
+}
+
+impl TBApiKey {
+    /// Hash of an API key as it is stored
+    pub fn hash_token(token: &str) -> String {
+        hex::encode(sha2::Sha256::digest(token.as_bytes()))
+    }
+
+    pub fn create(key: &TBApiKeyNew<'_>) -> Result<Self, SystemDBError> {
+        let result = diesel::insert_into(api_keys::table)
+            .values(key)
+            .get_result::<TBApiKey>(&mut establish_connection()?)?;
+        Ok(result)
+    }
+
+    pub fn get_user_keys(user_id_in: &i64) -> Result<Vec<Self>, SystemDBError> {
+        let result = api_keys::table
+            .filter(api_keys::user_id.eq(user_id_in))
+            .order(api_keys::created_at.desc())
+            .load::<TBApiKey>(&mut establish_connection()?)?;
+        Ok(result)
+    }
+
+    pub fn find_by_token(token: &str) -> Result<Option<Self>, SystemDBError> {
+        let result = api_keys::table
+            .filter(api_keys::token_hash.eq(TBApiKey::hash_token(token)))
+            .first::<TBApiKey>(&mut establish_connection()?)
+            .optional()?;
+        Ok(result)
+    }
+
+    /// Revokes a key of the user, revoked keys stay revoked
+    pub fn revoke(user_id_in: &i64, key_id_in: &str) -> Result<Self, SystemDBError> {
+        let result = diesel::update(
+            api_keys::table
+                .filter(api_keys::user_id.eq(user_id_in))
+                .filter(api_keys::key_id.eq(key_id_in))
+                .filter(api_keys::revoked_at.is_null()),
+        )
+        .set(api_keys::revoked_at.eq(Some(Utc::now())))
+        .get_result::<TBApiKey>(&mut establish_connection()?)?;
+        Ok(result)
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none() && self.expires_at > Utc::now()
+    }
+}
@@ -3,8 +3,8 @@
 pub mod api;
 pub mod error;
 use crate::schema::{
-    ca_payment, ca_payment_hash, contracts, drasil_user, email_verification_token, multisig_keyloc,
-    multisigs,
+    api_keys, auction_events, auctions, ca_payment, ca_payment_hash, contracts, drasil_user,
+    email_verification_token, multisig_keyloc, multisigs,
 };
 use chrono::{DateTime, Utc};
 use diesel::pg::PgConnection;
@@ -259,3 +259,121 @@ pub struct TBCaPaymentHashNew<'a> {
     pub payment_id: &'a i64,
     pub payment_hash: &'a str,
 }
+
+/// Started auction whose transaction is not on chain yet
+pub const AUCTION_PENDING: &str = "pending";
+pub const AUCTION_LIVE: &str = "live";
+pub const AUCTION_SETTLED: &str = "settled";
+pub const AUCTION_CANCELLED: &str = "cancelled";
+
+/// Off-chain state of an auction, `utxo` is the current auction output
+#[derive(
+    serde::Deserialize, serde::Serialize, Queryable, Identifiable, PartialEq, Eq, Debug, Clone,
+)]
+#[diesel(table_name = auctions)]
+pub struct TBAuction {
+    pub id: i64,
+    pub user_id: i64,
+    pub contract_id: i64,
+    pub kind: String,
+    pub policy_id: String,
+    pub tokenname: String,
+    pub seller_addr: String,
+    pub start_price: i64,
+    pub end_price: i64,
+    pub increment: i64,
+    pub deadline: DateTime<Utc>,
+    pub utxo: String,
+    pub highest_bid: Option<i64>,
+    pub highest_bidder: Option<String>,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = auctions)]
+pub struct TBAuctionNew<'a> {
+    pub user_id: &'a i64,
+    pub contract_id: &'a i64,
+    pub kind: &'a str,
+    pub policy_id: &'a str,
+    pub tokenname: &'a str,
+    pub seller_addr: &'a str,
+    pub start_price: &'a i64,
+    pub end_price: &'a i64,
+    pub increment: &'a i64,
+    pub deadline: &'a DateTime<Utc>,
+    pub utxo: &'a str,
+    pub status: &'a str,
+}
+
+pub const AUCTION_EVENT_START: &str = "start";
+pub const AUCTION_EVENT_BID: &str = "bid";
+pub const AUCTION_EVENT_SETTLE: &str = "settle";
+pub const AUCTION_EVENT_CANCEL: &str = "cancel";
+
+/// Change of an auction by a submitted transaction and the auction state after
+/// it, the auction shows the state of its last confirmed event
+#[derive(serde::Serialize, Queryable, Identifiable, PartialEq, Eq, Debug, Clone)]
+#[diesel(table_name = auction_events)]
+pub struct TBAuctionEvent {
+    pub id: i64,
+    pub auction_id: i64,
+    pub event: String,
+    pub txhash: String,
+    pub utxo: String,
+    pub highest_bid: Option<i64>,
+    pub highest_bidder: Option<String>,
+    pub status: String,
+    pub confirmed: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = auction_events)]
+pub struct TBAuctionEventNew<'a> {
+    pub auction_id: &'a i64,
+    pub event: &'a str,
+    pub txhash: &'a str,
+    pub utxo: &'a str,
+    pub highest_bid: Option<&'a i64>,
+    pub highest_bidder: Option<&'a str>,
+    pub status: &'a str,
+}
+
+/// Scoped API key of a user, the key itself is only stored as hash
+#[derive(serde::Serialize, Queryable, Identifiable, PartialEq, Eq, Debug, Clone)]
+#[diesel(table_name = api_keys)]
+pub struct TBApiKey {
+    pub id: i64,
+    pub user_id: i64,
+    pub key_id: String,
+    #[serde(skip)]
+    pub token_hash: String,
+    pub name: String,
+    pub contract_ids: Vec<i64>,
+    pub permissions: Vec<String>,
+    pub networks: Vec<i64>,
+    pub origins: Vec<String>,
+    pub ips: Vec<String>,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Insertable, Debug, Clone)]
+#[diesel(table_name = api_keys)]
+pub struct TBApiKeyNew<'a> {
+    pub user_id: &'a i64,
+    pub key_id: &'a str,
+    pub token_hash: &'a str,
+    pub name: &'a str,
+    pub contract_ids: &'a Vec<i64>,
+    pub permissions: &'a Vec<String>,
+    pub networks: &'a Vec<i64>,
+    pub origins: &'a Vec<String>,
+    pub ips: &'a Vec<String>,
+    pub expires_at: &'a DateTime<Utc>,
+}
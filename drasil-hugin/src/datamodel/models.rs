@@ -22,6 +22,8 @@ pub enum ContractType {
     WmtStaking,
     WmEnRegistration,
     DrasilAPILiquidity,
+    Auction,
+    MarketplaceOffer,
 }
 
 #[derive(
@@ -32,6 +34,20 @@ pub enum MarketplaceActions {
     Buy,
     Cancel,
     Update,
+    Offer,
+    Bid,
+    AcceptOffer,
+    CancelOffer,
+}
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, PartialEq, Eq, EnumVariantNames, Display, EnumString,
+)]
+pub enum AuctionActions {
+    StartAuction,
+    BidAuction,
+    SettleAuction,
+    CancelAuction,
 }
 
 #[derive(
@@ -42,6 +58,7 @@ pub enum MultiSigType {
     Mint,
     NftCollectionMinter,
     ClAPIOneShotMint,
+    ClAPICip68Update,
     TestRewards,
     UTxOpti,
     Other,
@@ -68,6 +85,11 @@ pub enum StdTxType {
     StandardTx,
     DeregisterStake,
     RewardWithdrawal,
+    RegisterDRep,
+    UpdateDRep,
+    RetireDRep,
+    DelegateVote,
+    CastVote,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -84,6 +106,7 @@ impl Signature {
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum ContractAction {
     MarketplaceActions(MarketplaceActions),
+    AuctionActions(AuctionActions),
 }
 
 impl ContractAction {}
@@ -96,6 +119,14 @@ impl FromStr for ContractAction {
             "buy" => ContractAction::MarketplaceActions(MarketplaceActions::Buy),
             "cancel" => ContractAction::MarketplaceActions(MarketplaceActions::Cancel),
             "update" => ContractAction::MarketplaceActions(MarketplaceActions::Update),
+            "offer" => ContractAction::MarketplaceActions(MarketplaceActions::Offer),
+            "bid" => ContractAction::MarketplaceActions(MarketplaceActions::Bid),
+            "acceptoffer" => ContractAction::MarketplaceActions(MarketplaceActions::AcceptOffer),
+            "canceloffer" => ContractAction::MarketplaceActions(MarketplaceActions::CancelOffer),
+            "startauction" => ContractAction::AuctionActions(AuctionActions::StartAuction),
+            "bidauction" => ContractAction::AuctionActions(AuctionActions::BidAuction),
+            "settleauction" => ContractAction::AuctionActions(AuctionActions::SettleAuction),
+            "cancelauction" => ContractAction::AuctionActions(AuctionActions::CancelAuction),
             _ => {
                 return Err(Error::new(
                     std::io::ErrorKind::InvalidData,
@@ -111,6 +142,7 @@ impl ToString for ContractAction {
     fn to_string(&self) -> String {
         match &self {
             ContractAction::MarketplaceActions(action) => action.to_string().to_lowercase(),
+            ContractAction::AuctionActions(action) => action.to_string().to_lowercase(),
         }
     }
 }
@@ -195,6 +227,7 @@ pub enum TXPWrapper {
     TransactionPattern(Box<TransactionPattern>),
     Signature(Signature),
     OneShotMinter(OneShotMintPayload),
+    Cip68Update(Cip68UpdatePayload),
     WalletTransaction(),
 }
 
@@ -421,6 +454,7 @@ impl TransactionPattern {
     }
 }
 
+/// Marketplace operations take no royalties, those come from the CIP-27 token of the policy
 #[derive(Serialize, Deserialize, Debug, Clone, EnumIs)]
 pub enum Operation {
     SpoRewardClaim {
@@ -429,10 +463,10 @@ pub enum Operation {
         recipient_payment_addr: String,
     },
     NftVendor {},
+    /// Listing takes one token, buying takes one token per listing in the cart
     Marketplace {
         tokens: Vec<Token>,
-        royalties_addr: Option<String>,
-        royalties_rate: Option<f32>,
+        /// Price of a listing, for purchases the maximum the buyer pays for all listings
         selling_price: u64,
         wallet_addresses: Option<Vec<String>>,
     },
@@ -454,13 +488,52 @@ pub enum Operation {
     TokenMinter {},
     NftOffer {
         token: Token,
+        /// Ignored, whoever holds the token can accept the offer
         token_owner_addr: String,
         metadata: Vec<String>,
-        royalties_addr: Option<String>,
-        royalties_rate: Option<f32>,
         offer_price: u64,
+        wallet_addresses: Option<Vec<String>>,
+    },
+    /// Offer on any token of a policy
+    CollectionBid {
+        policy_id: String,
+        bid_price: u64,
+        wallet_addresses: Option<Vec<String>>,
+    },
+    /// Sells `token` into the offer or collection bid locked in `offer_utxo` ('txhash#index')
+    OfferAccept {
+        offer_utxo: String,
+        token: Token,
+        wallet_addresses: Option<Vec<String>>,
+    },
+    OfferCancel {
+        offer_utxo: String,
+        wallet_addresses: Option<Vec<String>>,
+    },
+    /// Starts an auction, `end_price` is required for dutch and `increment` for english auctions
+    Auction {
+        token: Token,
+        kind: drasil_murin::txbuilder::auction::AuctionKind,
+        start_price: u64,
+        end_price: Option<u64>,
+        increment: Option<u64>,
+        deadline: DateTime<Utc>,
+        wallet_addresses: Option<Vec<String>>,
+    },
+    /// Bids on the auction locked in `auction_utxo` ('txhash#index'), `bid` is ignored for dutch auctions
+    AuctionBid {
+        auction_utxo: String,
+        bid: Option<u64>,
+        wallet_addresses: Option<Vec<String>>,
+    },
+    AuctionSettle {
+        auction_utxo: String,
+        wallet_addresses: Option<Vec<String>>,
+    },
+    AuctionCancel {
+        auction_utxo: String,
+        wallet_addresses: Option<Vec<String>>,
     },
-    Auction {},
     StakeDelegation {
         poolhash: String,
         addresses: Option<Vec<String>>,
@@ -484,6 +557,28 @@ pub enum Operation {
         amounts: Vec<u64>,
         metadata: drasil_murin::minter::Cip25Metadata,
         receiver: String,
+        cip68: Option<drasil_murin::minter::cip68::Cip68Config>,
+    },
+    ClApiCip68Update {
+        policy_id: String,
+        tokenname: String,
+        metadata: drasil_murin::minter::AssetMetadata,
+        version: Option<u64>,
+    },
+    DRep {
+        drep_key: String,
+        anchor: Option<AnchorHandle>,
+        deposit: Option<u64>,
+        payment_addresses: Option<Vec<String>>,
+    },
+    VoteDelegation {
+        drep: String,
+        addresses: Option<Vec<String>>,
+    },
+    GovernanceVote {
+        drep_key: String,
+        votes: Vec<VoteHandle>,
+        payment_addresses: Option<Vec<String>>,
     },
 }
 
@@ -494,13 +589,12 @@ impl Operation {
     ) -> Result<drasil_murin::txbuilder::marketplace::MpTxData, drasil_murin::error::MurinError>
     {
         use drasil_murin::error::MurinError;
+        use drasil_murin::minter::cip27::Cip27Royalty;
         use drasil_murin::txbuilder::marketplace::MpTxData;
 
         match self {
             Operation::Marketplace {
                 tokens,
-                royalties_addr,
-                royalties_rate,
                 selling_price,
                 ..
             } => {
@@ -515,17 +609,99 @@ impl Operation {
                     None,
                 )?;
 
+                let policy = match assets.first() {
+                    Some(asset) => asset.0.clone(),
+                    None => return Err(MurinError::new("no tokens provided")),
+                };
+                if assets.iter().any(|a| a.0 != policy) {
+                    return Err(MurinError::new(
+                        "tokens of different policies cannot be traded together",
+                    ));
+                }
+
                 let mut mptx = MpTxData::new(assets, token_utxos, *selling_price);
 
-                if let Some(royaddr) = royalties_addr {
-                    mptx.set_royalties_address(wallet::decode_address_from_bytes(royaddr).await?);
+                // The seller must not choose the royalties, they are declared on chain
+                if let Some(metadata) = drasil_mimir::chain_data()
+                    .royalty_metadata(&hex::encode(policy.to_bytes()))
+                    .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?
+                {
+                    mptx.set_royalties(&Cip27Royalty::from_json(&metadata)?)?;
                 }
 
-                if let Some(royrate) = royalties_rate {
-                    mptx.set_royalties_rate(*royrate);
+                Ok(mptx)
+            }
+            _ => Err(MurinError::new(
+                "provided wrong specfic parameter for this contract",
+            )),
+        }
+    }
+
+    /// Listings holding the tokens to buy, with the royalties declared for their policies
+    pub async fn into_sweep(
+        &self,
+        avail_inputs: drasil_murin::TransactionUnspentOutputs,
+    ) -> Result<Vec<drasil_murin::txbuilder::marketplace::MpTxData>, drasil_murin::error::MurinError>
+    {
+        use drasil_murin::error::MurinError;
+        use drasil_murin::minter::cip27::Cip27Royalty;
+        use drasil_murin::txbuilder::marketplace::MpTxData;
+
+        match self {
+            Operation::Marketplace {
+                tokens,
+                selling_price,
+                ..
+            } => {
+                let mut listings = Vec::<MpTxData>::new();
+                let mut royalties = HashMap::<String, Option<Cip27Royalty>>::new();
+                for asset in Token::for_all_into_asset(tokens)? {
+                    let utxos = drasil_murin::txbuilder::find_token_utxos_na(
+                        &avail_inputs,
+                        vec![asset.clone()],
+                        None,
+                    )?;
+                    if utxos.is_empty() {
+                        return Err(MurinError::new(&format!(
+                            "token '{}.{}' is not listed",
+                            hex::encode(asset.0.to_bytes()),
+                            hex::encode(asset.1.name())
+                        )));
+                    }
+                    let utxo = utxos.get(0);
+                    // Tokens listed together are bought together
+                    if listings
+                        .iter()
+                        .any(|l| l.get_token_utxos().contains_tx(&utxo))
+                    {
+                        continue;
+                    }
+                    let mut listing = MpTxData::from_listing_utxo(&utxo)?;
+
+                    let policy = hex::encode(asset.0.to_bytes());
+                    if !royalties.contains_key(&policy) {
+                        let royalty = match drasil_mimir::chain_data()
+                            .royalty_metadata(&policy)
+                            .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?
+                        {
+                            Some(metadata) => Some(Cip27Royalty::from_json(&metadata)?),
+                            None => None,
+                        };
+                        royalties.insert(policy.clone(), royalty);
+                    }
+                    if let Some(royalty) = &royalties[&policy] {
+                        listing.set_royalties(royalty)?;
+                    }
+                    listings.push(listing);
                 }
 
-                Ok(mptx)
+                let total = listings.iter().map(|l| l.get_price()).sum::<u64>();
+                if total > *selling_price {
+                    return Err(MurinError::new(&format!(
+                        "the listings cost {total} lovelace, more than the provided maximum of {selling_price}"
+                    )));
+                }
+                Ok(listings)
             }
             _ => Err(MurinError::new(
                 "provided wrong specfic parameter for this contract",
@@ -533,6 +709,153 @@ impl Operation {
         }
     }
 
+    pub async fn into_offer(
+        &self,
+        avail_inputs: drasil_murin::TransactionUnspentOutputs,
+    ) -> Result<drasil_murin::txbuilder::marketplace::MpOfferTxData, drasil_murin::error::MurinError>
+    {
+        use drasil_murin::error::MurinError;
+        use drasil_murin::minter::cip27::Cip27Royalty;
+        use drasil_murin::txbuilder::marketplace::MpOfferTxData;
+
+        let find_offer = |offer_utxo: &String| -> Result<MpOfferTxData, MurinError> {
+            let split: Vec<&str> = offer_utxo.split('#').collect();
+            if split.len() != 2 {
+                return Err(MurinError::new(&format!(
+                    "offer utxo '{offer_utxo}' is not of the form 'txhash#index'"
+                )));
+            }
+            let index = avail_inputs
+                .find_utxo_by_txhash(&split[0].to_string(), split[1].parse::<u32>()?)
+                .ok_or_else(|| {
+                    MurinError::new(&format!("offer '{offer_utxo}' not found at the contract"))
+                })?;
+            MpOfferTxData::from_offer_utxo(&avail_inputs.get(index))
+        };
+
+        let mut offer = match self {
+            Operation::NftOffer {
+                token, offer_price, ..
+            } => {
+                let asset = token.into_asset()?;
+                MpOfferTxData::new(asset.0, Some(asset.1), *offer_price)
+            }
+            Operation::CollectionBid {
+                policy_id,
+                bid_price,
+                ..
+            } => MpOfferTxData::new(
+                drasil_murin::cardano::string_to_policy(policy_id)?,
+                None,
+                *bid_price,
+            ),
+            Operation::OfferAccept {
+                offer_utxo, token, ..
+            } => {
+                let mut offer = find_offer(offer_utxo)?;
+                offer.set_token(token.into_asset()?);
+                offer
+            }
+            Operation::OfferCancel { offer_utxo, .. } => return find_offer(offer_utxo),
+            _ => {
+                return Err(MurinError::new(
+                    "provided wrong specfic parameter for this contract",
+                ))
+            }
+        };
+
+        // Buyers must pay the royalties declared on chain
+        if let Some(metadata) = drasil_mimir::chain_data()
+            .royalty_metadata(&hex::encode(offer.get_policy_id().to_bytes()))
+            .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?
+        {
+            offer.set_royalties(&Cip27Royalty::from_json(&metadata)?)?;
+        }
+
+        Ok(offer)
+    }
+
+    pub async fn into_auction(
+        &self,
+        avail_inputs: drasil_murin::TransactionUnspentOutputs,
+    ) -> Result<drasil_murin::txbuilder::auction::AuctionTxData, drasil_murin::error::MurinError>
+    {
+        use drasil_murin::error::MurinError;
+        use drasil_murin::minter::cip27::Cip27Royalty;
+        use drasil_murin::txbuilder::auction::{AuctionKind, AuctionStep, AuctionTxData};
+
+        let find_auction =
+            |auction_utxo: &String, step: AuctionStep| -> Result<AuctionTxData, MurinError> {
+                let split: Vec<&str> = auction_utxo.split('#').collect();
+                if split.len() != 2 {
+                    return Err(MurinError::new(&format!(
+                        "auction utxo '{auction_utxo}' is not of the form 'txhash#index'"
+                    )));
+                }
+                let index = avail_inputs
+                    .find_utxo_by_txhash(&split[0].to_string(), split[1].parse::<u32>()?)
+                    .ok_or_else(|| {
+                        MurinError::new(&format!(
+                            "auction '{auction_utxo}' not found at the contract"
+                        ))
+                    })?;
+                AuctionTxData::from_auction_utxo(step, &avail_inputs.get(index))
+            };
+
+        let mut auction = match self {
+            Operation::Auction {
+                token,
+                kind,
+                start_price,
+                end_price,
+                increment,
+                deadline,
+                ..
+            } => AuctionTxData::new_start(
+                *kind,
+                token.into_asset()?,
+                *start_price,
+                end_price.unwrap_or(0),
+                increment.unwrap_or(0),
+                deadline.timestamp_millis() as u64,
+            )?,
+            Operation::AuctionBid {
+                auction_utxo, bid, ..
+            } => {
+                let mut auction = find_auction(auction_utxo, AuctionStep::Bid)?;
+                if auction.get_kind() == AuctionKind::English {
+                    auction.set_bid(bid.ok_or_else(|| MurinError::new("no bid provided"))?);
+                }
+                auction
+            }
+            Operation::AuctionSettle { auction_utxo, .. } => {
+                find_auction(auction_utxo, AuctionStep::Settle)?
+            }
+            Operation::AuctionCancel { auction_utxo, .. } => {
+                return find_auction(auction_utxo, AuctionStep::Cancel)
+            }
+            _ => {
+                return Err(MurinError::new(
+                    "provided wrong specfic parameter for this contract",
+                ))
+            }
+        };
+
+        // Royalties are declared on chain and paid on every sale
+        let policy = auction
+            .get_token()
+            .map(|t| hex::encode(t.0.to_bytes()))
+            .ok_or_else(|| MurinError::new("no token provided for the auction"))?;
+        if let Some(metadata) = drasil_mimir::chain_data()
+            .royalty_metadata(&policy)
+            .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?
+        {
+            auction.set_royalties(&Cip27Royalty::from_json(&metadata)?)?;
+        }
+
+        Ok(auction)
+    }
+
     pub async fn into_rwd(
         &self,
     ) -> Result<drasil_murin::txbuilder::rwdist::RWDTxData, drasil_murin::error::MurinError> {
@@ -716,6 +1039,7 @@ impl Operation {
                 amounts,
                 metadata,
                 receiver,
+                cip68,
             } => {
                 log::debug!("Try to parse OneShotType");
                 let mut assets = Vec::<drasil_murin::txbuilder::MintTokenAsset>::new();
@@ -726,7 +1050,7 @@ impl Operation {
                     assets.push((None, tn, amt))
                 }
                 let payment_addr = wallet::address_from_string(receiver).await?;
-                Ok(MinterTxData::new(
+                let mut minttxd = MinterTxData::new(
                     assets,
                     None,
                     payment_addr,
@@ -735,7 +1059,11 @@ impl Operation {
                     None,
                     None,
                     -1,
-                ))
+                );
+                if let Some(cip68) = cip68 {
+                    minttxd.set_cip68(cip68.to_owned());
+                }
+                Ok(minttxd)
             }
             _ => Err(MurinError::new(
                 "provided wrong specfic parameter for this contract",
@@ -789,6 +1117,75 @@ impl Operation {
         }
     }
 
+    pub async fn into_drep(
+        &self,
+        action: drasil_murin::txbuilder::stdtx::DRepAction,
+    ) -> Result<drasil_murin::txbuilder::stdtx::DRepTxData, drasil_murin::error::MurinError> {
+        use drasil_murin::error::MurinError;
+        use drasil_murin::txbuilder::stdtx::DRepTxData;
+        match self {
+            Operation::DRep {
+                drep_key,
+                anchor,
+                deposit,
+                ..
+            } => {
+                let anchor = match anchor {
+                    Some(a) => Some(a.to_anchor()?),
+                    None => None,
+                };
+                let mut drep = DRepTxData::new(action, drep_key, anchor)?;
+                if let Some(d) = deposit {
+                    drep.set_deposit(*d);
+                }
+                Ok(drep)
+            }
+            _ => Err(MurinError::new(
+                "provided wrong specfic parameter for this transaction",
+            )),
+        }
+    }
+
+    pub async fn into_vote_delegation(
+        &self,
+    ) -> Result<drasil_murin::txbuilder::stdtx::VoteDelegTxData, drasil_murin::error::MurinError>
+    {
+        use drasil_murin::error::MurinError;
+        use drasil_murin::txbuilder::stdtx::VoteDelegTxData;
+        match self {
+            Operation::VoteDelegation { drep, .. } => Ok(VoteDelegTxData::new(drep)?),
+            _ => Err(MurinError::new(
+                "provided wrong specfic parameter for this transaction",
+            )),
+        }
+    }
+
+    pub async fn into_governance_vote(
+        &self,
+    ) -> Result<drasil_murin::txbuilder::stdtx::VoteTxData, drasil_murin::error::MurinError> {
+        use drasil_murin::conway::{drep_key_hash, Voter};
+        use drasil_murin::error::MurinError;
+        use drasil_murin::txbuilder::stdtx::VoteTxData;
+        match self {
+            Operation::GovernanceVote {
+                drep_key, votes, ..
+            } => {
+                let mut vtxd = VoteTxData::new(Voter::DRepKeyHash(drep_key_hash(drep_key)?));
+                for v in votes {
+                    let anchor = match &v.anchor {
+                        Some(a) => Some(a.to_anchor()?),
+                        None => None,
+                    };
+                    vtxd.add_vote(&v.gov_action_id, &v.vote, anchor)?;
+                }
+                Ok(vtxd)
+            }
+            _ => Err(MurinError::new(
+                "provided wrong specfic parameter for this transaction",
+            )),
+        }
+    }
+
     pub async fn into_cpo(
         &self,
     ) -> Result<drasil_murin::txbuilder::CPO, drasil_murin::error::MurinError> {
@@ -919,6 +1316,7 @@ pub struct OneShotMintPayload {
     metadata: drasil_murin::minter::Cip25Metadata,
     receiver: String,
     network: u8,
+    cip68: Option<drasil_murin::minter::cip68::Cip68Config>,
 }
 
 impl OneShotMintPayload {
@@ -928,6 +1326,7 @@ impl OneShotMintPayload {
             amounts: self.amounts.to_owned(),
             metadata: self.metadata.to_owned(),
             receiver: self.receiver.to_owned(),
+            cip68: self.cip68.to_owned(),
         }
     }
 
@@ -952,6 +1351,31 @@ impl OneShotMintPayload {
     }
 }
 
+/// New metadata for a CIP-68 asset, its reference token must be held by the liquidity wallet
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Cip68UpdatePayload {
+    policy_id: String,
+    tokenname: String,
+    metadata: drasil_murin::minter::AssetMetadata,
+    version: Option<u64>,
+    network: u8,
+}
+
+impl Cip68UpdatePayload {
+    pub fn into_script_spec(&self) -> Operation {
+        Operation::ClApiCip68Update {
+            policy_id: self.policy_id.to_owned(),
+            tokenname: self.tokenname.to_owned(),
+            metadata: self.metadata.to_owned(),
+            version: self.version,
+        }
+    }
+
+    pub fn network(&self) -> u64 {
+        self.network as u64
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OneShotReturn {
     policy_id: String,
@@ -1038,8 +1462,10 @@ pub struct RewardHandle {
     pub fingerprint: String,
     pub policy: String,
     pub tokenname: String,
+    /// Earned tokens without the expired ones
     pub tot_earned: i128,
     pub tot_claimed: i128,
+    pub tot_expired: i128,
     pub last_calc_epoch: i64,
 }
 
@@ -1051,11 +1477,15 @@ impl RewardHandle {
             fingerprint: ti.fingerprint.clone().unwrap(),
             policy: ti.policy.clone(),
             tokenname: ti.tokenname.clone().unwrap(),
-            tot_earned: (rwd.tot_earned.clone() / &BigDecimal::from_i32(1000000).unwrap())
+            tot_earned: ((rwd.tot_earned.clone() - &rwd.tot_expired)
+                / &BigDecimal::from_i32(1000000).unwrap())
                 .to_i128()
                 .unwrap(),
 
             tot_claimed: rwd.tot_claimed.clone().to_i128().unwrap(),
+            tot_expired: (rwd.tot_expired.clone() / &BigDecimal::from_i32(1000000).unwrap())
+                .to_i128()
+                .unwrap(),
             last_calc_epoch: rwd.last_calc_epoch,
         }
     }
@@ -1122,6 +1552,27 @@ pub struct TransferHandle {
     pub message: Option<String>,
 }
 
+/// Off-chain metadata of a DRep or a vote, `data_hash` is the hex encoded blake2b-256 hash of the document
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct AnchorHandle {
+    pub url: String,
+    pub data_hash: String,
+}
+
+impl AnchorHandle {
+    pub fn to_anchor(&self) -> Result<drasil_murin::conway::Anchor, drasil_murin::MurinError> {
+        drasil_murin::conway::Anchor::new(&self.url, &self.data_hash)
+    }
+}
+
+/// A vote on the governance action `gov_action_id` ('<txhash>#<index>'), `vote` is one of 'yes', 'no' or 'abstain'
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct VoteHandle {
+    pub gov_action_id: String,
+    pub vote: String,
+    pub anchor: Option<AnchorHandle>,
+}
+
 #[cfg(test)]
 mod tests {
     use drasil_murin::clib::crypto::Ed25519KeyHash;
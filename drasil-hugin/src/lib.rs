@@ -3,6 +3,7 @@ extern crate diesel;
 pub mod protocol;
 
 pub use crate::protocol::cmd::*;
+pub use crate::protocol::codec::Encoding;
 pub use crate::protocol::connection::*;
 pub use crate::protocol::frame::*;
 use crate::protocol::parse::*;
@@ -20,7 +21,7 @@ pub mod datamodel;
 pub use crate::datamodel::models::*;
 
 pub mod client;
-pub use crate::client::Client;
+pub use crate::client::{Client, ClientPool};
 
 pub mod schema;
 pub use schema::*;
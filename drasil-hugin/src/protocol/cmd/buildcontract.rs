@@ -1,13 +1,11 @@
 use std::str::FromStr;
 
-use bc::Options;
-use bincode as bc;
 use bytes::Bytes;
 use drasil_murin::MurinError;
 
 use crate::datamodel::{ContractAction, ContractType, TransactionPattern};
 use crate::Parse;
-use crate::{Connection, Frame, IntoFrame};
+use crate::{Connection, Encoding, ErrorCode, Frame, IntoFrame};
 
 #[derive(Debug, Clone)]
 pub struct BuildContract {
@@ -53,24 +51,18 @@ impl BuildContract {
             .next_int()
             .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
 
-        let ctype = parse
-            .next_bytes()
+        let ctype: ContractType = parse
+            .next_value()
             .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
-        let ctype: ContractType = bc::DefaultOptions::new()
-            .with_varint_encoding()
-            .deserialize(&ctype)?;
 
         let action = parse
             .next_string()
             .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
         let action = ContractAction::from_str(&action)?;
 
-        let txpattern = parse
-            .next_bytes()
+        let txpattern: TransactionPattern = parse
+            .next_value()
             .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
-        let txpattern: TransactionPattern = bc::DefaultOptions::new()
-            .with_varint_encoding()
-            .deserialize(&txpattern)?;
 
         Ok(BuildContract {
             customer_id,
@@ -81,60 +73,54 @@ impl BuildContract {
     }
 
     pub async fn apply(self, dst: &mut Connection) -> crate::Result<()> {
-        let mut response = Frame::Simple("OK".to_string());
-
         if let Err(e) = super::check_txpattern(&self.transaction_pattern()).await {
-            log::debug!("{:?}", response);
-            response = Frame::Simple(e.to_string());
-            dst.write_frame(&response).await?;
+            log::debug!("{:?}", e);
+            dst.write_error(ErrorCode::InvalidData, &e.to_string())
+                .await?;
             return Err(MurinError::ProtocolCommandErrorInvalidData);
         }
 
-        let ret: String;
-        match self.ctype {
+        let ret = match self.ctype {
             ContractType::MarketPlace => {
-                ret = crate::protocol::smartcontract::nft_marketplace::handle_marketplace(self)
-                    .await
-                    .unwrap_or_else(|err| err.to_string());
+                crate::protocol::smartcontract::nft_marketplace::handle_marketplace(self).await
+            }
+            ContractType::Auction => {
+                crate::protocol::smartcontract::auction::handle_auction(self).await
             }
             _ => {
-                return Err(
-                    format!("ERROR this contract Type does not exists {:?}'", self.ctype).into(),
-                );
+                let msg = format!("ERROR this contract Type does not exists {:?}'", self.ctype);
+                dst.write_error(ErrorCode::InvalidCommand, &msg).await?;
+                return Err(msg.into());
             }
-        }
+        };
 
-        response = Frame::Bulk(Bytes::from(
-            bc::DefaultOptions::new()
-                .with_varint_encoding()
-                .serialize(&ret.to_string())?,
-        ));
-        log::debug!("{:?}", response);
-        dst.write_frame(&response).await?;
+        match ret {
+            Ok(ret) => {
+                let response = dst.encode(&ret)?;
+                log::debug!("{:?}", response);
+                dst.write_frame(&response).await?;
+            }
+            Err(err) => {
+                let code = ErrorCode::of(&err).unwrap_or(ErrorCode::BuildFailed);
+                dst.write_error(code, &err.to_string()).await?;
+            }
+        }
         Ok(())
     }
 }
 
 impl IntoFrame for BuildContract {
-    fn into_frame(self) -> Frame {
+    fn into_frame(self, encoding: Encoding) -> Frame {
         let mut frame = Frame::array();
         frame.push_bulk(Bytes::from("bct".as_bytes()));
 
         frame.push_int(self.customer_id);
 
-        let ctype_b = bc::DefaultOptions::new()
-            .with_varint_encoding()
-            .serialize(&self.ctype)
-            .unwrap();
-        frame.push_bulk(Bytes::from(ctype_b));
+        frame.push_bulk(encoding.encode(&self.ctype).unwrap());
 
         frame.push_bulk(Bytes::from(self.action().to_string().into_bytes()));
 
-        let txpattern_b = bc::DefaultOptions::new()
-            .with_varint_encoding()
-            .serialize(&self.txpattern)
-            .unwrap();
-        frame.push_bulk(Bytes::from(txpattern_b));
+        frame.push_bulk(encoding.encode(&self.txpattern).unwrap());
 
         frame
     }
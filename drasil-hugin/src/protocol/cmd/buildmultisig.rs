@@ -1,10 +1,8 @@
 use crate::datamodel::{MultiSigType, TransactionPattern};
 use crate::protocol::multisig;
 use crate::Parse;
-use crate::{Connection, Frame, IntoFrame};
+use crate::{Connection, Encoding, ErrorCode, Frame, IntoFrame};
 
-use bc::Options;
-use bincode as bc;
 use bytes::Bytes;
 use drasil_murin::MurinError;
 
@@ -44,18 +42,12 @@ impl BuildMultiSig {
         let customer_id = parse
             .next_int()
             .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
-        let mtype = parse
-            .next_bytes()
+        let mtype: MultiSigType = parse
+            .next_value()
             .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
-        let mtype: MultiSigType = bc::DefaultOptions::new()
-            .with_varint_encoding()
-            .deserialize(&mtype)?;
-        let txpattern = parse
-            .next_bytes()
+        let txpattern: TransactionPattern = parse
+            .next_value()
             .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
-        let txpattern: TransactionPattern = bc::DefaultOptions::new()
-            .with_varint_encoding()
-            .deserialize(&txpattern)?;
         Ok(BuildMultiSig {
             customer_id,
             mtype,
@@ -64,87 +56,63 @@ impl BuildMultiSig {
     }
 
     pub async fn apply(self, dst: &mut Connection) -> crate::Result<()> {
-        let mut response =
-            Frame::Simple("ERROR: Could not build multisignature transaction".to_string());
         if self.multisig_type() != MultiSigType::ClAPIOneShotMint
+            && self.multisig_type() != MultiSigType::ClAPICip68Update
             && self.multisig_type() != MultiSigType::CustomerPayout
         {
             if let Err(e) = super::check_txpattern(&self.transaction_pattern()).await {
-                log::debug!("{:?}", response);
-                response = Frame::Simple(e.to_string());
-                dst.write_frame(&response).await?;
+                log::debug!("{:?}", e);
+                dst.write_error(ErrorCode::InvalidData, &e.to_string())
+                    .await?;
                 return Err(drasil_murin::MurinError::ProtocolCommandErrorInvalidData);
             }
             log::debug!("Transaction pattern check okay!");
         }
 
-        let mut ret = String::new();
-        match self.multisig_type() {
-            MultiSigType::SpoRewardClaim => {
-                ret = match multisig::handle_rewardclaim(&self).await {
-                    Ok(s) => s,
-                    Err(e) => e.to_string(),
-                };
-            }
-            MultiSigType::Mint => {
-                ret = match multisig::handle_collection_mint(&self).await {
-                    Ok(s) => s,
-                    Err(e) => e.to_string(),
-                };
-            }
+        let ret = match self.multisig_type() {
+            MultiSigType::SpoRewardClaim => multisig::handle_rewardclaim(&self).await,
+            MultiSigType::Mint => multisig::handle_collection_mint(&self).await,
             MultiSigType::NftCollectionMinter => {
                 log::debug!("NftCollectionMinter");
-                ret = match multisig::handle_collection_mint(&self).await {
-                    Ok(s) => s,
-                    Err(e) => e.to_string(),
-                };
+                multisig::handle_collection_mint(&self).await
             }
             MultiSigType::ClAPIOneShotMint => {
                 log::debug!("ClAPIOneShotMint");
-                ret = match multisig::handle_onehshot_mint(&self).await {
-                    Ok(s) => s,
-                    Err(e) => e.to_string(),
-                };
+                multisig::handle_onehshot_mint(&self).await
             }
-            MultiSigType::CustomerPayout => {
-                ret = match multisig::handle_customer_payout(&self).await {
-                    Ok(s) => s,
-                    Err(e) => e.to_string(),
-                };
+            MultiSigType::ClAPICip68Update => multisig::handle_cip68_update(&self).await,
+            MultiSigType::CustomerPayout => multisig::handle_customer_payout(&self).await,
+            _ => Err(MurinError::new(
+                "ERROR: Could not build multisignature transaction",
+            )),
+        };
+
+        match ret {
+            Ok(ret) => {
+                let response = dst.encode(&ret)?;
+                log::debug!("{:?}", response);
+                dst.write_frame(&response).await?;
+            }
+            Err(err) => {
+                let code = ErrorCode::of(&err).unwrap_or(ErrorCode::BuildFailed);
+                dst.write_error(code, &err.to_string()).await?;
             }
-            _ => {}
         }
 
-        response = Frame::Bulk(Bytes::from(
-            bc::DefaultOptions::new()
-                .with_varint_encoding()
-                .serialize(&ret)?,
-        ));
-        log::debug!("{:?}", response);
-        dst.write_frame(&response).await?;
-
         Ok(())
     }
 }
 
 impl IntoFrame for BuildMultiSig {
-    fn into_frame(self) -> Frame {
+    fn into_frame(self, encoding: Encoding) -> Frame {
         let mut frame = Frame::array();
         frame.push_bulk(Bytes::from("bms".as_bytes()));
 
         frame.push_int(self.customer_id);
 
-        let mtype_b = bc::DefaultOptions::new()
-            .with_varint_encoding()
-            .serialize(&self.mtype)
-            .unwrap();
-        frame.push_bulk(Bytes::from(mtype_b));
-
-        let txpattern_b = bc::DefaultOptions::new()
-            .with_varint_encoding()
-            .serialize(&self.txpattern)
-            .unwrap();
-        frame.push_bulk(Bytes::from(txpattern_b));
+        frame.push_bulk(encoding.encode(&self.mtype).unwrap());
+
+        frame.push_bulk(encoding.encode(&self.txpattern).unwrap());
 
         frame
     }
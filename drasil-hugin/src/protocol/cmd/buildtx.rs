@@ -1,10 +1,8 @@
 use crate::datamodel::{StdTxType, TransactionPattern};
 use crate::protocol::stdtx;
 use crate::Parse; // CmdError
-use crate::{Connection, Frame, IntoFrame};
+use crate::{Connection, Encoding, ErrorCode, Frame, IntoFrame};
 
-use bc::Options;
-use bincode as bc;
 use bytes::Bytes;
 use drasil_murin::MurinError;
 
@@ -39,17 +37,13 @@ impl BuildStdTx {
     pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<BuildStdTx> {
         let customer_id = parse.next_int().map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
         log::debug!("customer_id: {}", customer_id);
-        let txtype = parse.next_bytes().map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
-        log::debug!("txtype: {:?}", txtype);
-        let txtype: StdTxType = bc::DefaultOptions::new()
-            .with_varint_encoding()
-            .deserialize(&txtype)?;
+        let txtype: StdTxType = parse
+            .next_value()
+            .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
         debug!("txtype: {:?}", txtype);
-        let txpattern = parse.next_bytes().map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
-        log::debug!("txpattern: {:?}", txpattern);
-        let txpattern: TransactionPattern = bc::DefaultOptions::new()
-            .with_varint_encoding()
-            .deserialize(&txpattern)?;
+        let txpattern: TransactionPattern = parse
+            .next_value()
+            .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
         log::debug!("txpattern: {:?}", txpattern);
         Ok(BuildStdTx {
             customer_id,
@@ -60,21 +54,26 @@ impl BuildStdTx {
 
     pub async fn apply(self, dst: &mut Connection) -> crate::Result<()> {
         let ret = match self.tx_type() {
-            StdTxType::DelegateStake => stdtx::handle_stake_delegation(&self).await
-                .unwrap_or_else(|err| err.to_string()),
-            StdTxType::DeregisterStake => stdtx::handle_stake_deregistration(&self).await
-                .unwrap_or_else(|err| err.to_string()),
-            StdTxType::StandardTx => stdtx::handle_stx(&self).await
-                .unwrap_or_else(|err| err.to_string()),
-            StdTxType::RewardWithdrawal => stdtx::handle_reward_withdrawal(&self).await
-                .unwrap_or_else(|err| err.to_string()),
+            StdTxType::DelegateStake => stdtx::handle_stake_delegation(&self).await,
+            StdTxType::DeregisterStake => stdtx::handle_stake_deregistration(&self).await,
+            StdTxType::StandardTx => stdtx::handle_stx(&self).await,
+            StdTxType::RewardWithdrawal => stdtx::handle_reward_withdrawal(&self).await,
+            StdTxType::RegisterDRep | StdTxType::UpdateDRep | StdTxType::RetireDRep => {
+                stdtx::handle_drep(&self).await
+            }
+            StdTxType::DelegateVote => stdtx::handle_vote_delegation(&self).await,
+            StdTxType::CastVote => stdtx::handle_governance_vote(&self).await,
+        };
+        let ret = match ret {
+            Ok(ret) => ret,
+            Err(err) => {
+                let code = ErrorCode::of(&err).unwrap_or(ErrorCode::BuildFailed);
+                dst.write_error(code, &err.to_string()).await?;
+                return Ok(());
+            }
         };
-        log::debug!("Return String before parsing into BC:\n{:?}", ret);
-        let response = Frame::Bulk(Bytes::from(
-            bc::DefaultOptions::new()
-                .with_varint_encoding()
-                .serialize(&ret)?,
-        ));
+        log::debug!("Return String before encoding:\n{:?}", ret);
+        let response = dst.encode(&ret)?;
         log::debug!("Response before writing into Frame{:?}", response);
         dst.write_frame(&response).await?;
 
@@ -83,23 +82,15 @@ impl BuildStdTx {
 }
 
 impl IntoFrame for BuildStdTx {
-    fn into_frame(self) -> Frame {
+    fn into_frame(self, encoding: Encoding) -> Frame {
         let mut frame = Frame::array();
         frame.push_bulk(Bytes::from("stx".as_bytes()));
 
         frame.push_int(self.customer_id);
 
-        let mtype_b = bc::DefaultOptions::new()
-            .with_varint_encoding()
-            .serialize(&self.txtype)
-            .unwrap();
-        frame.push_bulk(Bytes::from(mtype_b));
+        frame.push_bulk(encoding.encode(&self.txtype).unwrap());
 
-        let txpattern_b = bc::DefaultOptions::new()
-            .with_varint_encoding()
-            .serialize(&self.txpattern)
-            .unwrap();
-        frame.push_bulk(Bytes::from(txpattern_b));
+        frame.push_bulk(encoding.encode(&self.txpattern).unwrap());
         log::debug!("Stx IntoFrame: {:?}", frame);
 
         frame
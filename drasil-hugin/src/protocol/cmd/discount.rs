@@ -67,13 +67,15 @@ pub fn discount(utxos: TransactionUnspentOutputs, cid: i64, uid: i64) -> i64 {
         }
     }
     log::debug!("\nAvail Tokens2: {avail_token:?}");
+    let chain = drasil_mimir::chain_data();
     let mut metadata = Vec::<TokenInfoMint>::new();
     for t in avail_token.iter() {
         log::debug!("\nToken iter: {:?}\n", t);
-        let m = drasil_mimir::get_mint_metadata(
-            &make_fingerprint(&hex::encode(t.0.to_bytes()), &hex::encode(t.1.name())).unwrap(),
-        )
-        .unwrap();
+        let m = chain
+            .mint_metadata(
+                &make_fingerprint(&hex::encode(t.0.to_bytes()), &hex::encode(t.1.name())).unwrap(),
+            )
+            .unwrap();
         log::debug!("\nMetadata iter: {:?}\n", m);
         metadata.push(m);
     }
@@ -1,9 +1,7 @@
 use crate::datamodel::ContractType;
 use crate::Parse;
-use crate::{Connection, Frame, IntoFrame};
+use crate::{Connection, Encoding, ErrorCode, Frame, IntoFrame};
 
-use bc::Options;
-use bincode as bc;
 use bytes::Bytes;
 use drasil_murin::MurinError;
 
@@ -16,17 +14,13 @@ pub struct FinalizeContract {
 }
 
 impl IntoFrame for FinalizeContract {
-    fn into_frame(self) -> Frame {
+    fn into_frame(self, encoding: Encoding) -> Frame {
         let mut frame = Frame::array();
         frame.push_bulk(Bytes::from("fct".as_bytes()));
 
         frame.push_int(self.customer_id);
 
-        let ctype_b = bc::DefaultOptions::new()
-            .with_varint_encoding()
-            .serialize(&self.ctype)
-            .unwrap();
-        frame.push_bulk(Bytes::from(ctype_b));
+        frame.push_bulk(encoding.encode(&self.ctype).unwrap());
 
         frame.push_bulk(Bytes::from(self.get_tx_id().into_bytes()));
 
@@ -72,12 +66,9 @@ impl FinalizeContract {
             .next_int()
             .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
 
-        let ctype = parse
-            .next_bytes()
+        let ctype: ContractType = parse
+            .next_value()
             .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
-        let ctype: ContractType = bc::DefaultOptions::new()
-            .with_varint_encoding()
-            .deserialize(&ctype)?;
 
         let tx_id = parse
             .next_string()
@@ -98,56 +89,159 @@ impl FinalizeContract {
         let raw_tx = drasil_murin::utxomngr::txmind::read_raw_tx(&self.get_tx_id())?;
 
         let response = match self.ctype {
-            ContractType::MarketPlace => match self.finalize_marketplace(raw_tx).await {
-                Ok(r) => r,
-                Err(e) => Frame::Error(format!(
+            ContractType::MarketPlace => self.finalize_marketplace(raw_tx).await.map_err(|e| {
+                format!(
                     "Error: Finalizing Marketplace Transaction failed: {:?}",
                     e.to_string()
-                )),
-            },
-            ContractType::WmEnRegistration => match self.finalize_general(raw_tx).await {
-                Ok(r) => r,
-                Err(e) => Frame::Error(format!(
+                )
+            }),
+            ContractType::Auction => self.finalize_auction(raw_tx).await.map_err(|e| {
+                format!(
+                    "Error: Finalizing Auction Transaction failed: {:?}",
+                    e.to_string()
+                )
+            }),
+            ContractType::WmEnRegistration => self.finalize_general(raw_tx).await.map_err(|e| {
+                format!(
                     "Error: Finalizing EarthNode Registration Transaction failed: {:?}",
                     e.to_string()
-                )),
-            },
-            ContractType::WmtStaking => match self.finalize_general(raw_tx).await {
-                Ok(r) => r,
-                Err(e) => Frame::Error(format!(
+                )
+            }),
+            ContractType::WmtStaking => self.finalize_general(raw_tx).await.map_err(|e| {
+                format!(
                     "Error: Finalizing WmtStaking Transaction failed: {:?}",
                     e.to_string()
-                )),
-            },
-            _ => Frame::Error(format!(
-                "This contract Type does not exists {:?}",
-                self.ctype
-            )),
+                )
+            }),
+            _ => {
+                let msg = format!("This contract Type does not exists {:?}", self.ctype);
+                dst.write_error(ErrorCode::InvalidCommand, &msg).await?;
+                return Ok(());
+            }
         };
-        log::debug!("{:?}", response);
-        dst.write_frame(&response).await?;
+        match response {
+            Ok(response) => {
+                let response = dst.encode(&response)?;
+                log::debug!("{:?}", response);
+                dst.write_frame(&response).await?;
+            }
+            Err(msg) => dst.write_error(ErrorCode::FinalizeFailed, &msg).await?,
+        }
         Ok(())
     }
 
-    async fn finalize_marketplace(&self, raw_tx: drasil_murin::RawTx) -> crate::Result<Frame> {
+    async fn finalize_marketplace(&self, raw_tx: drasil_murin::RawTx) -> crate::Result<String> {
         use drasil_murin::txbuilder::finalize::finalize;
         let response = finalize(&self.get_signature(), raw_tx).await?;
         info!("Response: {}", response);
-        Ok(Frame::Bulk(Bytes::from(
-            bc::DefaultOptions::new()
-                .with_varint_encoding()
-                .serialize(&response)?,
-        )))
+        Ok(response)
     }
 
-    async fn finalize_general(&self, raw_tx: drasil_murin::RawTx) -> crate::Result<Frame> {
+    async fn finalize_general(&self, raw_tx: drasil_murin::RawTx) -> crate::Result<String> {
         use drasil_murin::txbuilder::finalize::finalize;
         let response = finalize(&self.get_signature(), raw_tx).await?;
         info!("Response: {}", response);
-        Ok(Frame::Bulk(Bytes::from(
-            bc::DefaultOptions::new()
-                .with_varint_encoding()
-                .serialize(&response)?,
-        )))
+        Ok(response)
+    }
+
+    async fn finalize_auction(&self, raw_tx: drasil_murin::RawTx) -> crate::Result<String> {
+        use drasil_murin::txbuilder::finalize::finalize;
+        let response = finalize(&self.get_signature(), raw_tx.clone()).await?;
+        info!("Response: {}", response);
+        // The transaction is submitted, failing to track it must not fail the request
+        if let Err(e) = record_auction(&raw_tx, &response) {
+            log::error!("Could not record auction state of '{}': {:?}", response, e);
+        }
+        Ok(response)
+    }
+}
+
+/// Records the auction change of a submitted auction transaction in the system
+/// database, geri applies it once the transaction is confirmed on chain
+fn record_auction(raw_tx: &drasil_murin::RawTx, tx_hash: &str) -> crate::Result<()> {
+    use crate::database::{
+        TBAuction, TBAuctionNew, AUCTION_EVENT_CANCEL, AUCTION_EVENT_SETTLE, AUCTION_PENDING,
+    };
+    use chrono::TimeZone;
+    use drasil_murin::txbuilder::auction::{
+        decode_auction_datum, AuctionDatum, AuctionKind, AuctionStep, AuctionTxData,
+    };
+    use std::str::FromStr;
+
+    let auction = AuctionTxData::from_str(raw_tx.get_tx_specific_rawdata())?;
+    let txbody =
+        drasil_murin::clib::TransactionBody::from_bytes(hex::decode(raw_tx.get_txbody())?)?;
+
+    // The output at the auction script carrying the auction datum, if any
+    let auction_output =
+        || -> Option<(String, drasil_murin::clib::address::Address, AuctionDatum)> {
+            let outputs = txbody.outputs();
+            (0..outputs.len()).find_map(|i| {
+                let txo = outputs.get(i);
+                let datum = decode_auction_datum(&txo.plutus_data()?).ok()?;
+                Some((format!("{tx_hash}#{i}"), txo.address(), datum))
+            })
+        };
+    let previous_auction = || -> crate::Result<TBAuction> {
+        let input = auction.get_auction_utxos().get(0).input();
+        let utxo = format!(
+            "{}#{}",
+            hex::encode(input.transaction_id().to_bytes()),
+            input.index()
+        );
+        Ok(TBAuction::find_by_utxo(&utxo)?)
+    };
+
+    match (auction.get_step(), auction.get_kind()) {
+        (AuctionStep::Start, _) => {
+            let (utxo, sc_address, datum) =
+                auction_output().ok_or("no auction output in the transaction")?;
+            let network =
+                drasil_murin::cardano::get_network_from_address(&sc_address.to_bech32(None)?)?;
+            let deadline = chrono::Utc
+                .timestamp_millis_opt(datum.deadline as i64)
+                .single()
+                .ok_or("invalid auction deadline")?;
+            let contract_id = raw_tx.get_contract_id()?[0];
+            TBAuction::create(
+                &TBAuctionNew {
+                    user_id: &raw_tx.get_user_id()?,
+                    contract_id: &contract_id,
+                    kind: &datum.kind.to_string(),
+                    policy_id: &hex::encode(datum.policy_id.to_bytes()),
+                    tokenname: &hex::encode(datum.token_name.name()),
+                    seller_addr: &datum.seller_address(&network).to_bech32(None)?,
+                    start_price: &(datum.start_price as i64),
+                    end_price: &(datum.end_price as i64),
+                    increment: &(datum.increment as i64),
+                    deadline: &deadline,
+                    utxo: &utxo,
+                    status: AUCTION_PENDING,
+                },
+                tx_hash,
+            )?;
+        }
+        (AuctionStep::Bid, AuctionKind::English) => {
+            let (utxo, sc_address, datum) =
+                auction_output().ok_or("no auction output in the transaction")?;
+            let network =
+                drasil_murin::cardano::get_network_from_address(&sc_address.to_bech32(None)?)?;
+            let bidder = datum
+                .bidder_address(&network)
+                .ok_or("no bidder in the auction datum")?;
+            previous_auction()?.record_bid(
+                tx_hash,
+                &utxo,
+                &(datum.bid as i64),
+                &bidder.to_bech32(None)?,
+            )?;
+        }
+        (AuctionStep::Bid, AuctionKind::Dutch) | (AuctionStep::Settle, _) => {
+            previous_auction()?.record_close(AUCTION_EVENT_SETTLE, tx_hash)?;
+        }
+        (AuctionStep::Cancel, _) => {
+            previous_auction()?.record_close(AUCTION_EVENT_CANCEL, tx_hash)?;
+        }
     }
+    Ok(())
 }
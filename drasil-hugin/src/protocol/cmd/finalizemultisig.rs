@@ -1,9 +1,7 @@
 use crate::datamodel::MultiSigType;
-use crate::{Connection, Frame, IntoFrame};
+use crate::{Connection, Encoding, ErrorCode, Frame, IntoFrame};
 use crate::{Parse, TBContracts};
 
-use bc::Options;
-use bincode as bc;
 use bytes::Bytes;
 use drasil_gungnir::minting::models::{MintProject, MintReward, Nft};
 use drasil_murin::minter::models::{CMintHandle, ColMinterTxData};
@@ -19,17 +17,13 @@ pub struct FinalizeMultiSig {
 }
 
 impl IntoFrame for FinalizeMultiSig {
-    fn into_frame(self) -> Frame {
+    fn into_frame(self, encoding: Encoding) -> Frame {
         let mut frame = Frame::array();
         frame.push_bulk(Bytes::from("fms".as_bytes()));
 
         frame.push_int(self.customer_id);
 
-        let mtype_b = bc::DefaultOptions::new()
-            .with_varint_encoding()
-            .serialize(&self.mtype)
-            .unwrap();
-        frame.push_bulk(Bytes::from(mtype_b));
+        frame.push_bulk(encoding.encode(&self.mtype).unwrap());
 
         frame.push_bulk(Bytes::from(self.get_tx_id().into_bytes()));
 
@@ -75,12 +69,9 @@ impl FinalizeMultiSig {
             .next_int()
             .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
 
-        let mtype = parse
-            .next_bytes()
+        let mtype: MultiSigType = parse
+            .next_value()
             .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
-        let mtype: MultiSigType = bc::DefaultOptions::new()
-            .with_varint_encoding()
-            .deserialize(&mtype)?;
 
         let tx_id = parse
             .next_string()
@@ -98,7 +89,6 @@ impl FinalizeMultiSig {
     }
 
     pub async fn apply(self, dst: &mut Connection) -> crate::Result<()> {
-        let response: Frame;
         let raw_tx = drasil_murin::utxomngr::txmind::read_raw_tx(&self.get_tx_id())?;
 
         let mut ret = String::new();
@@ -108,7 +98,7 @@ impl FinalizeMultiSig {
                 if let Err(e) =
                     drasil_murin::rwdist::RWDTxData::from_str(raw_tx.get_tx_specific_rawdata())
                 {
-                    return Err(format!("ERROR Invalid Transaction Data, this is not a reward distribution transaction, {:?}",e.to_string()).into());
+                    return Err(ErrorCode::InvalidData.error(format!("ERROR Invalid Transaction Data, this is not a reward distribution transaction, {:?}",e.to_string())));
                 };
 
                 let tx_data = drasil_murin::TxData::from_str(raw_tx.get_txrawdata())?;
@@ -156,7 +146,7 @@ impl FinalizeMultiSig {
                 if let Err(e) = drasil_murin::minter::models::ColMinterTxData::from_str(
                     raw_tx.get_tx_specific_rawdata(),
                 ) {
-                    return Err(format!("ERROR Invalid Transaction Data, this is not a collection minter transaction, {:?}",e.to_string()).into());
+                    return Err(ErrorCode::InvalidData.error(format!("ERROR Invalid Transaction Data, this is not a collection minter transaction, {:?}",e.to_string())));
                 };
                 drasil_murin::TxData::from_str(raw_tx.get_txrawdata())?;
                 let mint_data = ColMinterTxData::from_str(raw_tx.get_tx_specific_rawdata())?;
@@ -176,7 +166,8 @@ impl FinalizeMultiSig {
                         let nft = Nft::get_nft_by_assetnameb(p.id, &p.nft_table_name, &a)
                             .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
                         if nft.minted || mrwd.processed || mrwd.minted {
-                            return Err(format!("ERROR mint already processed, {nft:?}").into());
+                            return Err(ErrorCode::InvalidData
+                                .error(format!("ERROR mint already processed, {nft:?}")));
                         }
                     }
                     handles.push((m.to_owned(), mrwd.to_owned(), p.to_owned(), c.to_owned()));
@@ -203,11 +194,10 @@ impl FinalizeMultiSig {
 
             MultiSigType::UTxOpti => {
                 if let Err(e) = crate::Utxopti::from_str(raw_tx.get_tx_specific_rawdata()) {
-                    return Err(format!(
+                    return Err(ErrorCode::InvalidData.error(format!(
                         "ERROR Invalid Transaction Data, this is not UTxOpti transaction, {:?}",
                         e.to_string()
-                    )
-                    .into());
+                    )));
                 };
                 ret = self.finalize_utxopti(raw_tx.clone()).await?;
             }
@@ -216,19 +206,19 @@ impl FinalizeMultiSig {
                 if let Err(e) =
                     drasil_murin::minter::MinterTxData::from_str(raw_tx.get_tx_specific_rawdata())
                 {
-                    return Err(format!(
+                    return Err(ErrorCode::InvalidData.error(format!(
                         "ERROR Invalid Transaction Data, this is not mint transaction, {:?}",
                         e.to_string()
-                    )
-                    .into());
+                    )));
                 };
                 ret = self.finalize_mint(raw_tx.clone()).await?;
             }
 
             _ => {
-                dst.write_frame(&Frame::Error(
-                    "Not a  valid multisig transaction type".to_string(),
-                ))
+                dst.write_error(
+                    ErrorCode::InvalidCommand,
+                    "Not a  valid multisig transaction type",
+                )
                 .await?;
                 return Ok(());
             }
@@ -239,11 +229,7 @@ impl FinalizeMultiSig {
             &drasil_murin::TransactionUnspentOutputs::from_hex(&used_utxos)?,
         )?;
 
-        response = Frame::Bulk(Bytes::from(
-            bc::DefaultOptions::new()
-                .with_varint_encoding()
-                .serialize(&ret)?,
-        ));
+        let response = dst.encode(&ret)?;
         log::debug!("{:?}", response);
         dst.write_frame(&response).await?;
         Ok(())
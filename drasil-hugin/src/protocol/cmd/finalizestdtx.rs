@@ -1,9 +1,7 @@
 use crate::datamodel::StdTxType;
 use crate::Parse;
-use crate::{Connection, Frame, IntoFrame};
+use crate::{Connection, Encoding, ErrorCode, Frame, IntoFrame};
 
-use bc::Options;
-use bincode as bc;
 use bytes::Bytes;
 use drasil_murin::MurinError;
 use std::str::FromStr;
@@ -17,17 +15,13 @@ pub struct FinalizeStdTx {
 }
 
 impl IntoFrame for FinalizeStdTx {
-    fn into_frame(self) -> Frame {
+    fn into_frame(self, encoding: Encoding) -> Frame {
         let mut frame = Frame::array();
         frame.push_bulk(Bytes::from("ftx".as_bytes()));
 
         frame.push_int(self.customer_id);
 
-        let txtype_b = bc::DefaultOptions::new()
-            .with_varint_encoding()
-            .serialize(&self.txtype)
-            .unwrap();
-        frame.push_bulk(Bytes::from(txtype_b));
+        frame.push_bulk(encoding.encode(&self.txtype).unwrap());
 
         frame.push_bulk(Bytes::from(self.get_tx_id().into_bytes()));
 
@@ -72,12 +66,9 @@ impl FinalizeStdTx {
         let customer_id = parse
             .next_int()
             .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
-        let txtype = parse
-            .next_bytes()
+        let txtype: StdTxType = parse
+            .next_value()
             .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
-        let txtype: StdTxType = bc::DefaultOptions::new()
-            .with_varint_encoding()
-            .deserialize(&txtype)?;
         let tx_id = parse
             .next_string()
             .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
@@ -102,35 +93,31 @@ impl FinalizeStdTx {
                 if let Err(e) =
                     drasil_murin::stdtx::DelegTxData::from_str(raw_tx.get_tx_specific_rawdata())
                 {
-                    format!("ERROR Invalid Transaction Data, this is not a delegation transaction, {:?}",e.to_string()).into()
-                } else {
-                    match self.finalize_std_tx(raw_tx.clone()).await {
-                        Ok(response) => response,
-                        Err(e) => {
-                            format!("ERROR in assemble step: {:?}", e.to_string())
-                        }
-                    }
+                    return Err(ErrorCode::InvalidData.error(format!(
+                        "ERROR Invalid Transaction Data, this is not a delegation transaction, {:?}",
+                        e.to_string()
+                    )));
                 }
+                self.finalize_std_tx(raw_tx.clone()).await?
             }
             StdTxType::DeregisterStake => self.finalize_std_tx(raw_tx.clone()).await?,
             StdTxType::StandardTx => {
                 if let Err(e) =
                     drasil_murin::stdtx::StandardTxData::from_str(raw_tx.get_tx_specific_rawdata())
                 {
-                    format!(
+                    return Err(ErrorCode::InvalidData.error(format!(
                         "ERROR Invalid Transaction Data, this is not a standard transaction, {:?}",
                         e.to_string()
-                    )
-                } else {
-                    match self.finalize_std_tx(raw_tx.clone()).await {
-                        Ok(response) => response,
-                        Err(e) => {
-                            format!("ERROR in assemble step: {:?}", e.to_string())
-                        }
-                    }
+                    )));
                 }
+                self.finalize_std_tx(raw_tx.clone()).await?
             }
             StdTxType::RewardWithdrawal => self.finalize_std_tx(raw_tx.clone()).await?,
+            StdTxType::RegisterDRep
+            | StdTxType::UpdateDRep
+            | StdTxType::RetireDRep
+            | StdTxType::DelegateVote
+            | StdTxType::CastVote => self.finalize_std_tx(raw_tx.clone()).await?,
         };
 
         // store used Utxos into utxo manager and store txhash for ovserver
@@ -143,11 +130,7 @@ impl FinalizeStdTx {
         // store tx into permanent storage (drasildb)
         // delete build_tx from redis
 
-        let response = Frame::Bulk(Bytes::from(
-            bc::DefaultOptions::new()
-                .with_varint_encoding()
-                .serialize(&ret)?,
-        ));
+        let response = dst.encode(&ret)?;
         log::debug!("{:?}", response);
         dst.write_frame(&response).await?;
         Ok(())
@@ -7,6 +7,7 @@ mod error;
 mod finalizecontract;
 mod finalizemultisig;
 mod finalizestdtx;
+mod txstatus;
 mod unknown;
 mod verifydata;
 mod verifyuser;
@@ -16,6 +17,7 @@ pub mod buildtx;
 pub use buildtx::BuildStdTx;
 pub(crate) use discount::*;
 pub use error::CmdError;
+pub use txstatus::GetTxStatus;
 pub use unknown::Unknown;
 pub use verifydata::VerifyData;
 pub use verifyuser::VerifyUser;
@@ -24,12 +26,13 @@ use drasil_murin::address::BaseAddress;
 use drasil_murin::{cardano, wallet, MurinError};
 
 use crate::error::SystemDBError;
-use crate::{Connection, Frame, Parse, Shutdown, TransactionPattern};
+use crate::{Connection, Encoding, ErrorCode, Frame, Parse, Shutdown, TransactionPattern};
 pub use finalizemultisig::FinalizeMultiSig;
 pub use finalizestdtx::FinalizeStdTx;
 
 pub trait IntoFrame {
-    fn into_frame(self) -> Frame;
+    /// Command frame with the fields encoded in `encoding`
+    fn into_frame(self, encoding: Encoding) -> Frame;
 }
 
 #[derive(Debug)]
@@ -42,13 +45,14 @@ pub enum Command {
     FinalizeStdTx(FinalizeStdTx),
     VerifyUser(VerifyUser),
     VerifyData(VerifyData),
+    GetTxStatus(GetTxStatus),
     Unknown(Unknown),
 }
 
 impl Command {
-    pub fn from_frame(frame: Frame) -> crate::Result<Command> {
-        let mut parse =
-            Parse::new(frame).map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
+    pub fn from_frame(frame: Frame, encoding: Encoding) -> crate::Result<Command> {
+        let mut parse = Parse::new(frame, encoding)
+            .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
         let command_name = parse
             .next_string()
             .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
@@ -68,6 +72,8 @@ impl Command {
             "ftx" => Command::FinalizeStdTx(FinalizeStdTx::parse_frames(&mut parse)?),
             //GetPubKey
             "vus" => Command::VerifyUser(VerifyUser::parse_frames(&mut parse)?),
+            //GetTxStatus
+            "gts" => Command::GetTxStatus(GetTxStatus::parse_frames(&mut parse)?),
             //GetStakeKey
             "hyd" => {
                 Command::Unknown(Unknown::new(command_name))
@@ -97,18 +103,36 @@ impl Command {
             Command::FinalizeMultiSig(cmd) => cmd.apply(dst).await?,
             Command::FinalizeStdTx(cmd) => cmd.apply(dst).await?,
             Command::VerifyUser(cmd) => cmd.apply(dst).await?,
+            Command::GetTxStatus(cmd) => cmd.apply(dst).await?,
             Command::Unknown(cmd) => cmd.apply(dst).await?,
 
             _ => {
-                let response = Frame::Simple("ERROR command could not be applied".to_string());
-                debug!("{:?}", response);
-                dst.write_frame(&response).await?;
+                dst.write_error(
+                    ErrorCode::InvalidCommand,
+                    "ERROR command could not be applied",
+                )
+                .await?;
             }
         }
 
         Ok(())
     }
 
+    /// Error code reported if applying this command fails without a more specific error
+    pub fn failure_code(&self) -> ErrorCode {
+        match self {
+            Command::BuildContract(_) | Command::BuildMultiSig(_) | Command::BuildStdTx(_) => {
+                ErrorCode::BuildFailed
+            }
+            Command::FinalizeContract(_)
+            | Command::FinalizeMultiSig(_)
+            | Command::FinalizeStdTx(_) => ErrorCode::FinalizeFailed,
+            Command::VerifyUser(_) | Command::VerifyData(_) => ErrorCode::Unauthorized,
+            Command::GetTxStatus(_) => ErrorCode::Internal,
+            Command::Unknown(_) => ErrorCode::UnknownCommand,
+        }
+    }
+
     pub(crate) fn _get_name(&self) -> &str {
         match self {
             Command::BuildContract(_) => "bct",
@@ -119,6 +143,7 @@ impl Command {
             Command::FinalizeStdTx(_) => "ftx",
             Command::VerifyUser(_) => "vus",
             Command::VerifyData(_) => "vd",
+            Command::GetTxStatus(_) => "gts",
             Command::Unknown(_) => "unkw",
         }
     }
@@ -151,8 +176,6 @@ async fn check_txpattern(txp: &TransactionPattern) -> crate::Result<()> {
                 crate::Operation::NftVendor {} => todo!(),
                 crate::Operation::Marketplace {
                     tokens: _,
-                    royalties_addr: _,
-                    royalties_rate: _,
                     selling_price: _,
                     wallet_addresses: wa,
                 } => {
@@ -175,14 +198,41 @@ async fn check_txpattern(txp: &TransactionPattern) -> crate::Result<()> {
                 crate::Operation::NftCollectionMinter { mint_handles: _ } => todo!(),
                 crate::Operation::TokenMinter {} => todo!(),
                 crate::Operation::NftOffer {
-                    token: _,
-                    token_owner_addr: _,
-                    metadata: _,
-                    royalties_addr: _,
-                    royalties_rate: _,
-                    offer_price: _,
-                } => todo!(),
-                crate::Operation::Auction {} => todo!(),
+                    wallet_addresses: wa,
+                    ..
+                }
+                | crate::Operation::CollectionBid {
+                    wallet_addresses: wa,
+                    ..
+                }
+                | crate::Operation::OfferAccept {
+                    wallet_addresses: wa,
+                    ..
+                }
+                | crate::Operation::OfferCancel {
+                    wallet_addresses: wa,
+                    ..
+                }
+                | crate::Operation::Auction {
+                    wallet_addresses: wa,
+                    ..
+                }
+                | crate::Operation::AuctionBid {
+                    wallet_addresses: wa,
+                    ..
+                }
+                | crate::Operation::AuctionSettle {
+                    wallet_addresses: wa,
+                    ..
+                }
+                | crate::Operation::AuctionCancel {
+                    wallet_addresses: wa,
+                    ..
+                } => {
+                    if wa.unwrap_or_default().is_empty() {
+                        return Err("ERROR no wallet addresses provided".into());
+                    }
+                }
                 crate::Operation::StakeDelegation {
                     poolhash: _,
                     addresses: _,
@@ -208,7 +258,22 @@ async fn check_txpattern(txp: &TransactionPattern) -> crate::Result<()> {
                     amounts: _,
                     metadata: _,
                     receiver: _,
+                    cip68: _,
                 } => {}
+                crate::Operation::ClApiCip68Update { .. } => {}
+                crate::Operation::DRep {
+                    payment_addresses: wa,
+                    ..
+                }
+                | crate::Operation::VoteDelegation { addresses: wa, .. }
+                | crate::Operation::GovernanceVote {
+                    payment_addresses: wa,
+                    ..
+                } => {
+                    if wa.unwrap_or_default().is_empty() {
+                        return Err("ERROR no wallet addresses provided".into());
+                    }
+                }
             },
             None => return Err("ERROR no wallet address provided".into()),
         }
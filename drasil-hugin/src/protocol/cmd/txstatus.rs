@@ -0,0 +1,62 @@
+use crate::Parse;
+use crate::{Connection, Encoding, ErrorCode, Frame, IntoFrame};
+
+use bytes::Bytes;
+use drasil_murin::MurinError;
+
+/// Lifecycle state of a transaction built for the user
+#[derive(Debug, Clone)]
+pub struct GetTxStatus {
+    user_id: u64,
+    tx_hash: String,
+}
+
+impl GetTxStatus {
+    pub fn new(user_id: u64, tx_hash: String) -> GetTxStatus {
+        GetTxStatus { user_id, tx_hash }
+    }
+
+    pub fn customer_id(&self) -> u64 {
+        self.user_id
+    }
+
+    pub fn tx_hash(&self) -> String {
+        self.tx_hash.clone()
+    }
+
+    pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<GetTxStatus> {
+        let user_id = parse
+            .next_int()
+            .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
+        let tx_hash: String = parse
+            .next_value()
+            .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
+        Ok(GetTxStatus { user_id, tx_hash })
+    }
+
+    /// Responds with the status as JSON, transactions of other users are not found
+    pub async fn apply(self, dst: &mut Connection) -> crate::Result<()> {
+        let status = drasil_murin::get_tx_status(&self.tx_hash)?
+            .filter(|s| s.user_id == Some(self.user_id as i64))
+            .ok_or_else(|| {
+                ErrorCode::NotFound.error(format!("unknown transaction {}", self.tx_hash))
+            })?;
+
+        let response = dst.encode(&serde_json::to_string(&status)?)?;
+        dst.write_frame(&response).await?;
+        Ok(())
+    }
+}
+
+impl IntoFrame for GetTxStatus {
+    fn into_frame(self, encoding: Encoding) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from("gts".as_bytes()));
+
+        frame.push_int(self.user_id);
+
+        frame.push_bulk(encoding.encode(&self.tx_hash).unwrap());
+
+        frame
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Connection, Frame};
+use crate::{Connection, ErrorCode};
 
 #[derive(Debug, Clone)]
 pub struct Unknown {
@@ -17,9 +17,10 @@ impl Unknown {
     }
 
     pub(crate) async fn apply(self, dst: &mut Connection) -> crate::Result<()> {
-        let response = Frame::Error(format!("ERROR: unknown command '{}'", self.command_name));
+        let response = format!("ERROR: unknown command '{}'", self.command_name);
         log::debug!("{:?}", response);
-        dst.write_frame(&response).await?;
+        dst.write_error(ErrorCode::UnknownCommand, &response)
+            .await?;
         Ok(())
     }
 }
@@ -1,8 +1,6 @@
 use crate::Parse;
-use crate::{Connection, Frame, IntoFrame};
+use crate::{Connection, Encoding, ErrorCode, Frame, IntoFrame};
 
-use bc::Options;
-use bincode as bc;
 use bytes::Bytes;
 use drasil_murin::MurinError;
 
@@ -30,10 +28,9 @@ impl VerifyUser {
 
     pub(crate) fn parse_frames(parse: &mut Parse) -> crate::Result<VerifyUser> {
         let customer_id = parse.next_int().map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
-        let btoken = parse.next_bytes().map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
-        let btoken: String = bc::DefaultOptions::new()
-            .with_varint_encoding()
-            .deserialize(&btoken)?;
+        let btoken: String = parse
+            .next_value()
+            .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
         Ok(VerifyUser {
             user_id: customer_id,
             bearer_token: btoken,
@@ -41,19 +38,28 @@ impl VerifyUser {
     }
 
     /// Apply verify user
+    ///
+    /// Scoped API keys are valid until they expire or are revoked, other tokens
+    /// need to be the current API token of the user.
     pub async fn apply(self, dst: &mut Connection) -> crate::Result<()> {
+        if let Some(key) = crate::database::TBApiKey::find_by_token(&self.bearer_token)
+            .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?
+        {
+            if key.user_id != self.user_id as i64 || !key.is_active() {
+                return Err(ErrorCode::Unauthorized.error("ERROR api key revoked"));
+            }
+            let response = dst.encode(&"true".to_string())?;
+            dst.write_frame(&response).await?;
+            return Ok(());
+        }
         let user = crate::database::TBDrasilUser::get_user_by_user_id(&(self.user_id as i64)).map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
 
         if let Some(token) = user.api_pubkey {
             if token == self.bearer_token {
-                let response = Frame::Bulk(Bytes::from(
-                    bc::DefaultOptions::new()
-                        .with_varint_encoding()
-                        .serialize(&"true".to_string())?,
-                ));
+                let response = dst.encode(&"true".to_string())?;
                 dst.write_frame(&response).await?;
             } else {
-                return Err("ERROR token not valid".to_string().into());
+                return Err(ErrorCode::Unauthorized.error("ERROR token not valid"));
             }
         }
         Ok(())
@@ -61,17 +67,13 @@ impl VerifyUser {
 }
 
 impl IntoFrame for VerifyUser {
-    fn into_frame(self) -> Frame {
+    fn into_frame(self, encoding: Encoding) -> Frame {
         let mut frame = Frame::array();
         frame.push_bulk(Bytes::from("vus".as_bytes()));
 
         frame.push_int(self.user_id);
 
-        let mtype_b = bc::DefaultOptions::new()
-            .with_varint_encoding()
-            .serialize(&self.bearer_token)
-            .unwrap();
-        frame.push_bulk(Bytes::from(mtype_b));
+        frame.push_bulk(encoding.encode(&self.bearer_token).unwrap());
 
         frame
     }
@@ -0,0 +1,62 @@
+//! Payload encodings of the odin protocol
+//!
+//! Command fields and responses are carried as bulk frames. Their content is
+//! encoded with the encoding negotiated in the handshake, connections which did
+//! not perform a handshake use bincode.
+use bc::Options;
+use bincode as bc;
+use bytes::Bytes;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumString};
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Display, EnumString,
+)]
+#[strum(serialize_all = "lowercase")]
+pub enum Encoding {
+    #[default]
+    Bincode,
+    Json,
+}
+
+impl Encoding {
+    pub fn encode<T: Serialize + ?Sized>(&self, value: &T) -> crate::Result<Bytes> {
+        let bytes = match self {
+            Encoding::Bincode => bc::DefaultOptions::new()
+                .with_varint_encoding()
+                .serialize(value)?,
+            Encoding::Json => serde_json::to_vec(value)?,
+        };
+        Ok(Bytes::from(bytes))
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> crate::Result<T> {
+        Ok(match self {
+            Encoding::Bincode => bc::DefaultOptions::new()
+                .with_varint_encoding()
+                .deserialize(bytes)?,
+            Encoding::Json => serde_json::from_slice(bytes)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn roundtrip() {
+        for enc in [Encoding::Bincode, Encoding::Json] {
+            let bytes = enc.encode(&"some response".to_string()).unwrap();
+            assert_eq!(enc.decode::<String>(&bytes).unwrap(), "some response");
+        }
+        assert_eq!(
+            Encoding::Json.encode(&vec![1, 2]).unwrap(),
+            Bytes::from("[1,2]")
+        );
+        assert_eq!(Encoding::from_str("json").unwrap(), Encoding::Json);
+        assert_eq!(Encoding::Bincode.to_string(), "bincode");
+    }
+}
@@ -1,26 +1,169 @@
-use super::frame::Frame;
+use super::codec::Encoding;
+use super::frame::{ErrorCode, Frame};
+use super::handshake::{self, Hello};
 use async_recursion::async_recursion;
 use bytes::{Buf, BytesMut};
 use drasil_murin::MurinError;
+use serde::Serialize;
+use std::fmt::Debug;
 use std::io::Cursor;
 use tokio::io::BufWriter;
-use tokio::io::{self, AsyncReadExt, AsyncWriteExt};
+use tokio::io::{self, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+
+/// Transport of a connection, plain TCP or TLS
+pub trait Stream: AsyncRead + AsyncWrite + Unpin + Send + Debug {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + Debug> Stream for T {}
 
 #[derive(Debug)]
 pub struct Connection {
-    stream: BufWriter<TcpStream>,
+    stream: BufWriter<Box<dyn Stream>>,
     buffer: BytesMut,
+    // negotiated protocol version, 0 without handshake
+    version: u64,
+    encoding: Encoding,
+    // request the next written frame answers
+    request_id: Option<u64>,
+    responded: bool,
+    // a responder hands its answers to the connection the request was read from
+    responses: Option<mpsc::UnboundedSender<Frame>>,
 }
 
 impl Connection {
     pub fn new(stream: TcpStream) -> Connection {
+        Connection::from_stream(stream)
+    }
+
+    pub fn from_stream<S: Stream + 'static>(stream: S) -> Connection {
         Connection {
-            stream: BufWriter::new(stream),
+            stream: BufWriter::new(Box::new(stream)),
             buffer: BytesMut::with_capacity(50 * 1024),
+            version: 0,
+            encoding: Encoding::default(),
+            request_id: None,
+            responded: false,
+            responses: None,
+        }
+    }
+
+    /// Connection which answers the last request read by `read_request` on its own,
+    /// the enveloped answer is sent to `responses` instead of being written.
+    /// Servers use it to process tagged requests of one connection concurrently.
+    pub fn responder(&mut self, responses: mpsc::UnboundedSender<Frame>) -> Connection {
+        let mut responder = Connection::from_stream(Cursor::new(Vec::new()));
+        responder.version = self.version;
+        responder.encoding = self.encoding;
+        responder.request_id = self.request_id.take();
+        responder.responses = Some(responses);
+        responder
+    }
+
+    /// Id of the envelope of the last request read by `read_request`, `None` for
+    /// untagged requests which are answered in order
+    pub fn request_id(&self) -> Option<u64> {
+        self.request_id
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// True if a response was written for the last request read by `read_request`
+    pub fn responded(&self) -> bool {
+        self.responded
+    }
+
+    /// Client side of the version handshake. Servers which do not know the
+    /// handshake answer with an error, the connection stays at version 0 then
+    /// if bincode was requested.
+    pub async fn handshake(&mut self, encoding: Encoding) -> crate::Result<()> {
+        self.write_frame(&Hello::new(encoding).into_frame()).await?;
+        match self.read_frame().await? {
+            Some(frame) => {
+                match Hello::from_frame(&frame) {
+                    Some(hello) => {
+                        let hello = hello?;
+                        self.version = hello.version;
+                        self.encoding = hello.encoding;
+                        Ok(())
+                    }
+                    None => match frame {
+                        Frame::Error(msg) => {
+                            let err = Frame::into_protocol_error(&msg);
+                            if encoding == Encoding::Bincode
+                                && ErrorCode::split(&msg).0 == ErrorCode::Internal
+                            {
+                                log::warn!("server does not support the handshake, using protocol version 0");
+                                Ok(())
+                            } else {
+                                Err(err)
+                            }
+                        }
+                        frame => Err(frame.to_error()),
+                    },
+                }
+            }
+            None => Err("connection closed during handshake".into()),
+        }
+    }
+
+    /// Server side read of the next command. Answers handshakes and unwraps
+    /// request envelopes, the request id is used for the next written frame.
+    pub async fn read_request(&mut self) -> crate::Result<Option<Frame>> {
+        loop {
+            let frame = match self.read_frame().await? {
+                Some(frame) => frame,
+                None => return Ok(None),
+            };
+            self.responded = false;
+            if let Some(hello) = Hello::from_frame(&frame) {
+                match hello.and_then(|h| h.negotiate()) {
+                    Ok(hello) => {
+                        self.version = hello.version;
+                        self.encoding = hello.encoding;
+                        self.write_frame(&hello.into_frame()).await?;
+                    }
+                    Err(e) => {
+                        self.write_frame(&Frame::error(
+                            ErrorCode::UnsupportedVersion,
+                            &e.to_string(),
+                        ))
+                        .await?;
+                    }
+                }
+                continue;
+            }
+            return Ok(Some(match handshake::open_request(frame) {
+                Ok((id, command)) => {
+                    self.request_id = Some(id);
+                    command
+                }
+                Err(frame) => frame,
+            }));
         }
     }
 
+    /// Writes an error answer, typed if the connection negotiated a protocol version
+    pub async fn write_error(&mut self, code: ErrorCode, message: &str) -> io::Result<()> {
+        let frame = if self.version > 0 {
+            Frame::error(code, message)
+        } else {
+            Frame::Error(message.to_string())
+        };
+        self.write_frame(&frame).await
+    }
+
+    /// Bulk frame of a value in the encoding of this connection
+    pub fn encode<T: Serialize + ?Sized>(&self, value: &T) -> crate::Result<Frame> {
+        Ok(Frame::Bulk(self.encoding.encode(value)?))
+    }
+
     pub async fn read_frame(&mut self) -> crate::Result<Option<Frame>> {
         loop {
             log::trace!("new read loop cycle");
@@ -37,7 +180,6 @@ impl Connection {
                     return Err(format!("error connection reset by peer: {:?}", self.buffer).into());
                 }
             }
-            
         }
     }
 
@@ -49,7 +191,8 @@ impl Connection {
                 let len = buf.position() as usize;
                 buf.set_position(0);
 
-                let frame = Frame::parse(&mut buf).map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
+                let frame = Frame::parse(&mut buf)
+                    .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
 
                 self.buffer.advance(len);
                 Ok(Some(frame))
@@ -61,6 +204,20 @@ impl Connection {
 
     pub async fn write_frame(&mut self, frame: &Frame) -> io::Result<()> {
         log::trace!("Frame::write_frame: {:?}", frame);
+        self.responded = true;
+        let enveloped;
+        let frame = match self.request_id.take() {
+            Some(id) => {
+                enveloped = handshake::response(id, frame.clone());
+                &enveloped
+            }
+            None => frame,
+        };
+        if let Some(responses) = &self.responses {
+            return responses
+                .send(frame.clone())
+                .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "connection closed"));
+        }
         match frame {
             Frame::Array(val) => {
                 log::trace!("write_frame ARRAY: {:?}", val.clone());
@@ -70,9 +227,10 @@ impl Connection {
                     self.write_value(entry).await?;
                 }
             }
-            _ =>{ log::trace!("Frame::write_frame: OTHER!!! ");
+            _ => {
+                log::trace!("Frame::write_frame: OTHER!!! ");
                 self.write_value(frame).await?
-            },
+            }
         }
         log::trace!("Frame::write_frame: Flush self.stream");
         self.stream.flush().await
@@ -135,3 +293,40 @@ impl Connection {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn responder_answers_tagged_request() {
+        let (client, server) = tokio::io::duplex(1024);
+        let mut client = Connection::from_stream(client);
+        let mut server = Connection::from_stream(server);
+        client
+            .write_frame(&handshake::request(7, Frame::Simple("ping".to_string())))
+            .await
+            .unwrap();
+
+        match server.read_request().await.unwrap() {
+            Some(Frame::Simple(cmd)) => assert_eq!(cmd, "ping"),
+            frame => panic!("unexpected frame {frame:?}"),
+        }
+        assert_eq!(server.request_id(), Some(7));
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut responder = server.responder(tx);
+        assert_eq!(server.request_id(), None);
+        responder
+            .write_frame(&Frame::Simple("pong".to_string()))
+            .await
+            .unwrap();
+        assert!(responder.responded());
+
+        server.write_frame(&rx.recv().await.unwrap()).await.unwrap();
+        match handshake::open_response(client.read_frame().await.unwrap().unwrap()) {
+            Ok((7, Frame::Simple(answer))) => assert_eq!(answer, "pong"),
+            frame => panic!("unexpected response {frame:?}"),
+        }
+    }
+}
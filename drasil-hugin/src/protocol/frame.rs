@@ -3,7 +3,9 @@ use std::convert::TryInto;
 use std::fmt;
 use std::io::Cursor;
 use std::num::TryFromIntError;
+use std::str::FromStr;
 use std::string::FromUtf8Error;
+use strum::{Display, EnumString};
 
 #[derive(Clone, Debug)]
 pub enum Frame {
@@ -15,6 +17,57 @@ pub enum Frame {
     Array(Vec<Frame>),
 }
 
+/// Machine readable code of an error frame, written as the first word of the error message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    UnknownCommand,
+    InvalidCommand,
+    InvalidData,
+    UnsupportedVersion,
+    UnsupportedEncoding,
+    Unauthorized,
+    NotFound,
+    BuildFailed,
+    FinalizeFailed,
+    SubmissionFailed,
+    TxLimitExceeded,
+    Internal,
+}
+
+impl ErrorCode {
+    /// Splits the message of an error frame into code and text, messages of
+    /// servers without typed errors are returned as `Internal`
+    pub fn split(message: &str) -> (ErrorCode, String) {
+        if let Some((code, text)) = message.split_once(' ') {
+            if let Ok(code) = ErrorCode::from_str(code) {
+                return (code, text.to_string());
+            }
+        }
+        (ErrorCode::Internal, message.to_string())
+    }
+
+    /// Code of errors which carry their kind, `None` for generic errors
+    pub fn of(err: &crate::Error) -> Option<ErrorCode> {
+        use drasil_murin::MurinError;
+        match err {
+            MurinError::ProtocolCommandErrorInvalidData => Some(ErrorCode::InvalidData),
+            MurinError::TxSubmission(_) => Some(ErrorCode::SubmissionFailed),
+            MurinError::TxLimitExceeded { .. } => Some(ErrorCode::TxLimitExceeded),
+            MurinError::ProtocolError { code, .. } => ErrorCode::from_str(code).ok(),
+            _ => None,
+        }
+    }
+
+    /// `ProtocolError` carrying this code
+    pub fn error(self, message: impl Into<String>) -> crate::Error {
+        drasil_murin::MurinError::ProtocolError {
+            code: self.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum Error {
     Incomplete,
@@ -37,6 +90,15 @@ impl Frame {
         }
     }
 
+    pub(crate) fn push_frame(&mut self, frame: Frame) {
+        match self {
+            Frame::Array(vec) => {
+                vec.push(frame);
+            }
+            _ => panic!("not an array frame"),
+        }
+    }
+
     pub(crate) fn push_int(&mut self, value: u64) {
         match self {
             Frame::Array(vec) => {
@@ -132,6 +194,17 @@ impl Frame {
     pub(crate) fn to_error(&self) -> crate::Error {
         format!("unexpected frame: {self}").into()
     }
+
+    /// Typed error frame
+    pub fn error(code: ErrorCode, message: &str) -> Frame {
+        Frame::Error(format!("{code} {message}"))
+    }
+
+    /// Converts an error frame into a `ProtocolError`
+    pub fn into_protocol_error(msg: &str) -> crate::Error {
+        let (code, message) = ErrorCode::split(msg);
+        code.error(message)
+    }
 }
 
 impl PartialEq<&str> for Frame {
@@ -254,4 +327,31 @@ impl fmt::Display for Error {
             Error::Other(err) => err.fmt(fmt),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed_error_frames() {
+        let frame = Frame::error(ErrorCode::BuildFailed, "not enough funds");
+        match &frame {
+            Frame::Error(msg) => {
+                assert_eq!(msg, "BUILD_FAILED not enough funds");
+                assert_eq!(
+                    ErrorCode::split(msg),
+                    (ErrorCode::BuildFailed, "not enough funds".to_string())
+                );
+            }
+            _ => panic!("not an error frame"),
+        }
+        assert_eq!(
+            ErrorCode::split("ERROR: unknown command 'hyd'"),
+            (
+                ErrorCode::Internal,
+                "ERROR: unknown command 'hyd'".to_string()
+            )
+        );
+    }
+}
@@ -0,0 +1,164 @@
+//! Protocol version handshake and request envelopes
+//!
+//! A client opens a connection with `hello <version> <encoding>`, the server
+//! answers with the version and encoding it will use for this connection.
+//! From protocol version 1 on every command is wrapped into
+//! `req <request id> <command>` and answered with `res <request id> <frame>`.
+//! Connections which start without a handshake are served as version 0,
+//! without envelopes and typed errors.
+use super::codec::Encoding;
+use super::frame::Frame;
+use bytes::Bytes;
+use std::str::FromStr;
+
+/// Newest protocol version this build speaks
+pub const PROTOCOL_VERSION: u64 = 1;
+/// Oldest protocol version a handshake can negotiate
+pub const MIN_PROTOCOL_VERSION: u64 = 1;
+
+const HELLO: &str = "hello";
+const REQUEST: &str = "req";
+const RESPONSE: &str = "res";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hello {
+    pub version: u64,
+    pub encoding: Encoding,
+}
+
+impl Hello {
+    pub fn new(encoding: Encoding) -> Hello {
+        Hello {
+            version: PROTOCOL_VERSION,
+            encoding,
+        }
+    }
+
+    pub fn into_frame(self) -> Frame {
+        let mut frame = Frame::array();
+        frame.push_bulk(Bytes::from(HELLO.as_bytes()));
+        frame.push_int(self.version);
+        frame.push_bulk(Bytes::from(self.encoding.to_string().into_bytes()));
+        frame
+    }
+
+    /// `None` if the frame is not a hello frame
+    pub fn from_frame(frame: &Frame) -> Option<crate::Result<Hello>> {
+        match frame {
+            Frame::Array(parts) if parts.first().map_or(false, |p| *p == HELLO) => {
+                Some(match (parts.get(1), parts.get(2), parts.len()) {
+                    (Some(Frame::Integer(version)), Some(Frame::Bulk(enc)), 3) => {
+                        std::str::from_utf8(enc)
+                            .ok()
+                            .and_then(|e| Encoding::from_str(e).ok())
+                            .map(|encoding| Hello {
+                                version: *version,
+                                encoding,
+                            })
+                            .ok_or_else(|| format!("unknown encoding {enc:?}").into())
+                    }
+                    _ => Err("protocol error; invalid hello frame".into()),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Answer of a server to this hello, an error if the versions are incompatible
+    pub fn negotiate(&self) -> crate::Result<Hello> {
+        if self.version < MIN_PROTOCOL_VERSION {
+            return Err(format!(
+                "protocol version {} is not supported, minimum is {}",
+                self.version, MIN_PROTOCOL_VERSION
+            )
+            .into());
+        }
+        Ok(Hello {
+            version: self.version.min(PROTOCOL_VERSION),
+            encoding: self.encoding,
+        })
+    }
+}
+
+fn envelope(kind: &str, id: u64, frame: Frame) -> Frame {
+    let mut envelope = Frame::array();
+    envelope.push_bulk(Bytes::from(kind.as_bytes().to_vec()));
+    envelope.push_int(id);
+    envelope.push_frame(frame);
+    envelope
+}
+
+fn open_envelope(kind: &str, frame: Frame) -> Result<(u64, Frame), Frame> {
+    match frame {
+        Frame::Array(mut parts) if parts.len() == 3 && parts[0] == kind => match parts[1] {
+            Frame::Integer(id) => {
+                let inner = parts.pop().expect("checked length");
+                Ok((id, inner))
+            }
+            _ => Err(Frame::Array(parts)),
+        },
+        frame => Err(frame),
+    }
+}
+
+pub fn request(id: u64, command: Frame) -> Frame {
+    envelope(REQUEST, id, command)
+}
+
+pub fn response(id: u64, frame: Frame) -> Frame {
+    envelope(RESPONSE, id, frame)
+}
+
+/// Request id and command of a request envelope, the frame itself if it is not enveloped
+pub fn open_request(frame: Frame) -> Result<(u64, Frame), Frame> {
+    open_envelope(REQUEST, frame)
+}
+
+/// Request id and content of a response envelope, the frame itself if it is not enveloped
+pub fn open_response(frame: Frame) -> Result<(u64, Frame), Frame> {
+    open_envelope(RESPONSE, frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hello_roundtrip_and_negotiation() {
+        let hello = Hello::new(Encoding::Json);
+        let parsed = Hello::from_frame(&hello.into_frame()).unwrap().unwrap();
+        assert_eq!(parsed, hello);
+        assert_eq!(parsed.negotiate().unwrap().version, PROTOCOL_VERSION);
+
+        let newer = Hello {
+            version: PROTOCOL_VERSION + 1,
+            encoding: Encoding::Bincode,
+        };
+        assert_eq!(newer.negotiate().unwrap().version, PROTOCOL_VERSION);
+        assert!(Hello {
+            version: 0,
+            encoding: Encoding::Bincode
+        }
+        .negotiate()
+        .is_err());
+
+        let mut cmd = Frame::array();
+        cmd.push_bulk(Bytes::from("stx"));
+        assert!(Hello::from_frame(&cmd).is_none());
+    }
+
+    #[test]
+    fn envelopes() {
+        let mut cmd = Frame::array();
+        cmd.push_bulk(Bytes::from("ftx"));
+        let (id, inner) = open_request(request(7, cmd)).unwrap();
+        assert_eq!(id, 7);
+        assert!(matches!(inner, Frame::Array(ref p) if p[0] == "ftx"));
+
+        let (id, inner) = open_response(response(7, Frame::Simple("OK".into()))).unwrap();
+        assert_eq!(id, 7);
+        assert!(inner == "OK");
+
+        assert!(open_response(Frame::Simple("OK".into())).is_err());
+    }
+}
@@ -1,13 +1,19 @@
 pub mod cmd;
+pub mod codec;
 pub mod connection;
 pub mod frame;
+pub mod handshake;
 pub(crate) mod multisig;
 pub mod parse;
 pub mod shutdown;
 pub(crate) mod smartcontract;
 pub(crate) mod stdtx;
+pub mod tls;
 
 pub use cmd::*;
+pub use codec::*;
 pub use connection::*;
 pub use frame::*;
+pub use handshake::{Hello, PROTOCOL_VERSION};
 pub use shutdown::*;
+pub use tls::TlsConfig;
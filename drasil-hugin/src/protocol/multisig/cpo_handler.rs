@@ -54,15 +54,8 @@ pub(crate) async fn handle_customer_payout(bms: &BuildMultiSig) -> crate::Result
     log::debug!("Try to build transaction...");
     // ToDo: Check that payout sum cannot spent liquidity
 
-    let mut dbsync = match drasil_mimir::establish_connection() {
-        Ok(conn) => conn,
-        Err(e) => {
-            return Err(format!(
-                "ERROR could not connect to dbsync: '{:?}'",
-                e.to_string()).into())
-        }
-    };
-    let slot = match drasil_mimir::get_slot(&mut dbsync) {
+    let chain = drasil_mimir::chain_data();
+    let slot = match chain.slot() {
         Ok(s) => s,
         Err(e) => {
             return Err(format!(
@@ -73,7 +66,7 @@ pub(crate) async fn handle_customer_payout(bms: &BuildMultiSig) -> crate::Result
     gtxd.set_current_slot(slot as u64);
     log::info!("DB Sync Slot: {}", slot);
 
-    let utxos = drasil_mimir::get_address_utxos(&contract.address)
+    let utxos = chain.address_utxos(&contract.address)
         .expect("MimirError: cannot find address utxos");
     gtxd.set_inputs(utxos);
 
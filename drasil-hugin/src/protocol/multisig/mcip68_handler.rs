@@ -0,0 +1,125 @@
+use drasil_murin::minter::build_cip68_update::{AtCip68UpdBuilder, AtCip68UpdParams};
+use drasil_murin::minter::cip68::{cip68_datum, datum_version, reference_asset_name};
+use drasil_murin::{wallet, MurinError, PerformTxb};
+
+use crate::datamodel::Operation;
+use crate::drasildb::TBContracts;
+use crate::{BuildMultiSig, ErrorCode};
+
+pub(crate) async fn handle_cip68_update(bms: &BuildMultiSig) -> crate::Result<String> {
+    log::debug!("Entered CIP-68 reference datum update...");
+    let (policy_id, tokenname, metadata, version) = match bms.transaction_pattern().operation() {
+        Some(Operation::ClApiCip68Update {
+            policy_id,
+            tokenname,
+            metadata,
+            version,
+        }) => (policy_id, tokenname, metadata, version),
+        _ => {
+            return Err(
+                format!("ERROR wrong data provided for '{:?}'", bms.multisig_type()).into(),
+            );
+        }
+    };
+    let policy = drasil_murin::PolicyID::from_hex(&policy_id)?;
+    let reference = reference_asset_name(&drasil_murin::cardano::string_to_assetname(
+        &hex::encode(tokenname.as_bytes()),
+    )?)?;
+
+    log::debug!("Check contract...");
+    let contract = TBContracts::get_liquidity_wallet(&bms.customer_id())
+        .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
+    let mut txp = bms.transaction_pattern();
+    txp.set_used_addresses(&[contract.address.clone()]);
+    let mut gtxd = txp.into_txdata().await?;
+
+    let chain = drasil_mimir::chain_data();
+    let slot = chain.slot().map_err(|e| {
+        format!(
+            "ERROR could not determine current slot: '{:?}'",
+            e.to_string()
+        )
+    })?;
+    gtxd.set_current_slot(slot as u64);
+
+    log::debug!("Find reference token...");
+    let inputs = chain
+        .address_utxos(&contract.address)
+        .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
+    let reference_utxos = inputs.find_utxos_containing_asset(&policy, &reference)?;
+    if reference_utxos.is_empty() {
+        return Err(ErrorCode::NotFound.error(format!(
+            "reference token of '{tokenname}' is not held by the liquidity wallet"
+        )));
+    }
+    if drasil_murin::utxomngr::usedutxos::check_any_utxo_used(&reference_utxos)?.is_some() {
+        return Err(format!(
+            "ERROR reference token of '{tokenname}' is locked by a pending transaction"
+        )
+        .into());
+    }
+    let reference_utxo = reference_utxos.get(0);
+    let version = match version {
+        Some(v) => v,
+        None => reference_utxo
+            .output()
+            .plutus_data()
+            .and_then(|d| datum_version(&d))
+            .unwrap_or(1),
+    };
+    let datum = cip68_datum(&metadata, version)?;
+
+    log::debug!("Try to determine additional data...");
+    let keyloc = crate::drasildb::TBMultiSigLoc::get_multisig_keyloc(
+        &contract.contract_id,
+        &contract.user_id,
+        &contract.version,
+    )
+    .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
+    let ident = crate::encryption::mident(
+        &contract.user_id,
+        &contract.contract_id,
+        &contract.version,
+        &contract.address,
+    );
+    let pkvs = crate::encryption::decrypt_pkvs(keyloc.pvks, &ident).await?;
+    gtxd.set_inputs(inputs);
+
+    log::debug!("Try to build transaction...");
+    let liquidity_addr = wallet::address_from_string(&contract.address).await?;
+    let txb_param: AtCip68UpdParams = (&reference_utxo, &datum, &liquidity_addr);
+    let updater = AtCip68UpdBuilder::new(txb_param);
+    let builder = drasil_murin::TxBuilder::new(&gtxd, &pkvs);
+    let bld_tx = builder.build(&updater).await?;
+
+    log::debug!("Try to create Raw Tx...");
+    let tx = drasil_murin::utxomngr::RawTx::new(
+        &bld_tx.get_tx_body(),
+        &bld_tx.get_txwitness(),
+        &bld_tx.get_tx_unsigned(),
+        &bld_tx.get_metadata(),
+        &gtxd.to_string(),
+        &hex::encode(datum.to_bytes()),
+        &bld_tx.get_used_utxos(),
+        &hex::encode(gtxd.get_stake_address().to_bytes()),
+        &(bms.customer_id()),
+        &[contract.contract_id],
+    );
+
+    log::debug!("Finalize...");
+    let used_utxos = tx.get_usedutxos().clone();
+    let txh = drasil_murin::finalize_rwd(
+        &hex::encode(drasil_murin::clib::TransactionWitnessSet::new().to_bytes()),
+        tx,
+        vec![],
+    )
+    .await?;
+
+    log::debug!("Store used utxos...");
+    drasil_murin::utxomngr::usedutxos::store_used_utxos(
+        &txh,
+        &drasil_murin::TransactionUnspentOutputs::from_hex(&used_utxos)?,
+    )?;
+
+    Ok(txh)
+}
@@ -1,8 +1,11 @@
 use std::cmp::Ordering;
 
-use drasil_gungnir::minting::models::MintProject;
+use drasil_gungnir::minting::models::{MintPhase, MintProject};
 use drasil_gungnir::Whitelist;
 use drasil_murin::minter::build_minttx::{AtCMBuilder, AtCMParams};
+use drasil_murin::minter::cip68::{Cip68Config, Cip68TokenKind};
+use drasil_murin::minter::models::PriceCMintHandle;
+use drasil_murin::minter::reveal::assign_metadata;
 use drasil_murin::utils::{from_bignum, to_bignum};
 use drasil_murin::{wallet, MurinError};
 use drasil_murin::{NativeScript, PerformTxb, ServiceFees};
@@ -54,7 +57,9 @@ pub(crate) async fn handle_collection_mint(bms: &BuildMultiSig) -> crate::Result
     let stake_address = minttxd.mint_handles[0].reward_addr()?.to_bech32(None)?;
 
     let first_address = wallet::address_from_string(
-        &drasil_mimir::api::select_addr_of_first_transaction(&stake_address).map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?,
+        &drasil_mimir::chain_data()
+            .first_address_of_stake_address(&stake_address)
+            .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?,
     )
     .await?;
     let mut gtxd = bms.transaction_pattern().into_txdata().await?;
@@ -132,6 +137,7 @@ pub(crate) async fn handle_collection_mint(bms: &BuildMultiSig) -> crate::Result
         }
     }
     log::debug!("Check nft data...");
+    let mut phased = Vec::<(i64, i64, usize)>::new();
     for m in &minttxd.mint_handles {
         let mintreward = drasil_gungnir::minting::models::MintReward::get_mintreward_by_id(m.id);
         match mintreward {
@@ -143,6 +149,9 @@ pub(crate) async fn handle_collection_mint(bms: &BuildMultiSig) -> crate::Result
                 {
                     return Err(format!("ERROR invalid mint reward '{mr:?}'").into());
                 }
+                if let Some(phase_id) = mr.phase_id {
+                    phased.push((m.id, phase_id, mr.nft_ids.len()));
+                }
                 let mut tv = drasil_murin::clib::utils::Value::zero();
                 for nft in &mr.v_nfts_b {
                     let v = drasil_murin::clib::utils::Value::from_bytes(nft.to_owned())?;
@@ -179,8 +188,17 @@ pub(crate) async fn handle_collection_mint(bms: &BuildMultiSig) -> crate::Result
             if nft.minted {
                 return Err(format!("ERROR Nft already minted: '{mh:?}'").into());
             }
-            if let Some(metadata) = &nft.metadata {
-                metadataassets.push(serde_json::from_str(metadata)?)
+            if mp[0].1.reveal_uncommitted() {
+                return Err("ERROR the metadata pool of the reveal project is not committed".into());
+            }
+            let metadata = nft.mint_metadata(&mp[0].1).map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
+            if let Some(metadata) = metadata {
+                let mut asset: drasil_murin::minter::AssetMetadata = serde_json::from_str(&metadata)?;
+                // all NFTs share the placeholder until the reveal
+                if mp[0].1.reveal_pending() {
+                    asset = assign_metadata(&asset, &hex::encode(&nft.asset_name_b), &nft.asset_name);
+                }
+                metadataassets.push(asset)
             }
             nfts.push(nft);
         }
@@ -193,13 +211,7 @@ pub(crate) async fn handle_collection_mint(bms: &BuildMultiSig) -> crate::Result
     };
 
     log::debug!("Try to determine slot...");
-    let mut dbsync = match drasil_mimir::establish_connection() {
-        Ok(conn) => conn,
-        Err(e) => {
-            return Err(format!("ERROR could not connect to dbsync: '{:?}'", e.to_string()).into());
-        }
-    };
-    let slot = match drasil_mimir::get_slot(&mut dbsync) {
+    let slot = match drasil_mimir::chain_data().slot() {
         Ok(s) => s,
         Err(e) => {
             return Err(format!(
@@ -210,6 +222,13 @@ pub(crate) async fn handle_collection_mint(bms: &BuildMultiSig) -> crate::Result
     };
     gtxd.set_current_slot(slot as u64);
 
+    // Projects with a reference address mint CIP-68 pairs
+    let cip68 = mintprojects[0]
+        .1
+        .cip68_ref_addr
+        .as_ref()
+        .map(|addr| Cip68Config::new(addr, Cip68TokenKind::Nft));
+
     let mut pvks = Vec::<String>::new();
     let mut scripts = Vec::<NativeScript>::new();
     let mut contract_ids = Vec::<i64>::new();
@@ -222,10 +241,29 @@ pub(crate) async fn handle_collection_mint(bms: &BuildMultiSig) -> crate::Result
         contract_ids.push(m.2.contract_id);
     }
 
-    // TODO: Prices are not implemented yet
+    // Rewards claimed in a sale phase pay its price, the phase window was checked
+    // with the claim and the phase may have closed since
+    let mut prices = Vec::<PriceCMintHandle>::new();
+    for (handle_id, phase_id, nfts) in phased {
+        let phase = MintPhase::get_phase_by_id(phase_id)
+            .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
+        prices.push(PriceCMintHandle {
+            handle_id,
+            price: to_bignum(phase.price_of(nfts)),
+            seller_address: phase.pay_addr,
+        });
+    }
+    let prices = if prices.is_empty() { None } else { Some(prices) };
 
     log::debug!("Try to build transaction...");
-    let txb_param: AtCMParams = (&scripts, &None, &metadata, &Some(fees), &minttxd);
+    let txb_param: AtCMParams = (
+        &scripts,
+        &prices,
+        &metadata,
+        &Some(fees),
+        &minttxd,
+        &cip68,
+    );
 
     let minter = AtCMBuilder::new(txb_param);
     let builder = drasil_murin::TxBuilder::new(&gtxd, &pvks);
@@ -7,6 +7,9 @@ pub(crate) use mcollection_handler::handle_collection_mint;
 pub(crate) mod moneshot_handler;
 pub(crate) use moneshot_handler::handle_onehshot_mint;
 
+pub(crate) mod mcip68_handler;
+pub(crate) use mcip68_handler::handle_cip68_update;
+
 pub(crate) mod cpo_handler;
 pub(crate) use cpo_handler::handle_customer_payout;
 
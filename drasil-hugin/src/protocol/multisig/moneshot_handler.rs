@@ -1,3 +1,4 @@
+use drasil_murin::minter::cip68::{reference_asset_name, user_asset_name};
 use drasil_murin::MurinError;
 use drasil_murin::{wallet, PerformTxb};
 use serde_json::json;
@@ -8,7 +9,7 @@ use crate::BuildMultiSig;
 
 pub(crate) async fn handle_onehshot_mint(bms: &BuildMultiSig) -> crate::Result<String> {
     log::debug!("Entered Oneshot Minter...");
-    let minttxd = bms
+    let mut minttxd = bms
         .transaction_pattern()
         .operation()
         .unwrap()
@@ -21,15 +22,9 @@ pub(crate) async fn handle_onehshot_mint(bms: &BuildMultiSig) -> crate::Result<S
     log::debug!("Try to create general transaction data...");
     let mut gtxd = txp.into_txdata().await?;
     log::debug!("General TX Data: {:?}", gtxd);
-    log::debug!("Connect to dbsync...");
-    let mut dbsync = match drasil_mimir::establish_connection() {
-        Ok(conn) => conn,
-        Err(e) => {
-            return Err(format!("ERROR could not connect to dbsync: '{:?}'", e.to_string()).into());
-        }
-    };
+    let chain = drasil_mimir::chain_data();
     log::debug!("Get Slot...");
-    let slot = match drasil_mimir::get_slot(&mut dbsync) {
+    let slot = match chain.slot() {
         Ok(s) => s,
         Err(e) => {
             return Err(format!(
@@ -44,11 +39,22 @@ pub(crate) async fn handle_onehshot_mint(bms: &BuildMultiSig) -> crate::Result<S
     log::debug!("Create Oneshot policy...");
     log::debug!("Current Slot: {:?}", slot);
     let oneshotwallet = drasil_murin::wallet::create_wallet();
-    let oneshotpolicy = drasil_murin::minter::create_onshot_policy(&oneshotwallet.3, slot as u64);
+    let oneshotpolicy = drasil_murin::minter::create_onshot_policy(
+        &oneshotwallet.3,
+        slot as u64,
+        &gtxd.get_network(),
+    )?;
 
     log::debug!("Check contract...");
     let contract = TBContracts::get_liquidity_wallet(&bms.customer_id())
         .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
+    // Reference tokens stay with the liquidity wallet unless an address is given
+    if let Some(mut cip68) = minttxd.get_cip68() {
+        if cip68.reference_addr.is_empty() {
+            cip68.reference_addr = contract.address.clone();
+            minttxd.set_cip68(cip68);
+        }
+    }
     log::debug!("Try to determine additional data...");
     let keyloc = crate::drasildb::TBMultiSigLoc::get_multisig_keyloc(
         &contract.contract_id,
@@ -67,7 +73,8 @@ pub(crate) async fn handle_onehshot_mint(bms: &BuildMultiSig) -> crate::Result<S
 
     log::debug!("Set utxos for input...");
     gtxd.set_inputs(
-        drasil_mimir::get_address_utxos(&contract.address)
+        chain
+            .address_utxos(&contract.address)
             .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?,
     );
 
@@ -119,9 +126,21 @@ pub(crate) async fn handle_onehshot_mint(bms: &BuildMultiSig) -> crate::Result<S
     let mut amounts = Vec::<u64>::new();
     let policy_id = hex::encode(ns_script.hash().to_bytes());
 
+    let cip68 = minttxd.get_cip68();
     for t in minttxd.get_mint_tokens() {
-        tokennames.push(hex::encode(t.1.name()));
-        amounts.push(drasil_murin::clib::utils::from_bignum(&t.2));
+        let amount = drasil_murin::clib::utils::from_bignum(&t.2);
+        match &cip68 {
+            Some(cip68) => {
+                tokennames.push(hex::encode(reference_asset_name(&t.1)?.name()));
+                amounts.push(1);
+                tokennames.push(hex::encode(user_asset_name(&t.1, cip68.token_kind)?.name()));
+                amounts.push(amount);
+            }
+            None => {
+                tokennames.push(hex::encode(t.1.name()));
+                amounts.push(amount);
+            }
+        }
     }
 
     let ret = OneShotReturn::new(
@@ -33,13 +33,14 @@ pub(crate) async fn handle_rewardclaim(bms: &BuildMultiSig) -> crate::Result<Str
                     &wallet::address_from_string(&recipient_stake_addr).await?,
                 )? != wallet::stake_keyhash_from_address(
                     &wallet::address_from_string(
-                        &drasil_mimir::api::select_addr_of_first_transaction(
-                            &wallet::decode_address_from_bytes(&recipient_stake_addr)
-                                .await?
-                                .to_bech32(None)
-                                .unwrap(),
-                        )
-                        .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?,
+                        &drasil_mimir::chain_data()
+                            .first_address_of_stake_address(
+                                &wallet::decode_address_from_bytes(&recipient_stake_addr)
+                                    .await?
+                                    .to_bech32(None)
+                                    .unwrap(),
+                            )
+                            .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?,
                     )
                     .await?,
                 )?
@@ -63,13 +64,14 @@ pub(crate) async fn handle_rewardclaim(bms: &BuildMultiSig) -> crate::Result<Str
         .await?;
     rwdtxd.set_payment_addr(
         &wallet::address_from_string(
-            &drasil_mimir::api::select_addr_of_first_transaction(
-                &rwdtxd
-                    .get_stake_addr()
-                    .to_bech32(None)
-                    .expect("ERROR Could not construct bech32 address for stake address"),
-            )
-            .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?,
+            &drasil_mimir::chain_data()
+                .first_address_of_stake_address(
+                    &rwdtxd
+                        .get_stake_addr()
+                        .to_bech32(None)
+                        .expect("ERROR Could not construct bech32 address for stake address"),
+                )
+                .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?,
         )
         .await?,
     );
@@ -205,8 +207,7 @@ pub(crate) async fn handle_rewardclaim(bms: &BuildMultiSig) -> crate::Result<Str
     }
 
     let mut wallets = TransWallets::new();
-    let mut dbsync = drasil_mimir::establish_connection()
-        .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
+    let chain = drasil_mimir::chain_data();
     for c in contract {
         let keyloc = crate::drasildb::TBMultiSigLoc::get_multisig_keyloc(
             &c.contract_id,
@@ -216,7 +217,8 @@ pub(crate) async fn handle_rewardclaim(bms: &BuildMultiSig) -> crate::Result<Str
         .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
         info!("retrieve blockchain data...");
 
-        let wallet_utxos = drasil_mimir::get_address_utxos(&c.address)
+        let wallet_utxos = chain
+            .address_utxos(&c.address)
             .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
         if wallet_utxos.is_empty() {
             // ToDO :
@@ -248,7 +250,8 @@ pub(crate) async fn handle_rewardclaim(bms: &BuildMultiSig) -> crate::Result<Str
     let uw = TransWallet::new(&rwdtxd.get_payment_addr(), &gtxd.get_inputs());
     wallets.add_wallet(&uw);
     log::debug!("Wallets in reward_handler: {:?}", wallets);
-    let slot = drasil_mimir::get_slot(&mut dbsync)
+    let slot = chain
+        .slot()
         .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
     gtxd.set_current_slot(slot as u64);
 
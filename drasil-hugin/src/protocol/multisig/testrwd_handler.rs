@@ -116,16 +116,8 @@ pub(crate) async fn handle_testrewards(bms: &BuildMultiSig) -> crate::Result<Str
     //}
     let ns_script = contract.plutus.clone();
 
-    let mut dbsync = match mimir::establish_connection() {
-        Ok(conn) => conn,
-        Err(e) => {
-            return Err(CmdError::Custom {
-                str: format!("ERROR could not connect to dbsync: '{:?}'", e.to_string()),
-            }
-            .into());
-        }
-    };
-    let slot = match mimir::get_slot(&mut dbsync) {
+    let chain = mimir::chain_data();
+    let slot = match chain.slot() {
         Ok(s) => s,
         Err(e) => {
             return Err(CmdError::Custom {
@@ -140,7 +132,7 @@ pub(crate) async fn handle_testrewards(bms: &BuildMultiSig) -> crate::Result<Str
     gtxd.set_current_slot(slot as u64);
     log::info!("DB Sync Slot: {}", slot);
 
-    let policy_script_utxos = mimir::get_address_utxos(&mut dbsync, &contract.address)?;
+    let policy_script_utxos = chain.address_utxos(&contract.address)?;
 
     gtxd.set_inputs(policy_script_utxos);
 
@@ -209,7 +201,7 @@ pub(crate) async fn handle_testrewards(bms: &BuildMultiSig) -> crate::Result<Str
         sporwc_flz.user_id as i64,
         &fingerprint,
     )?;
-    let current_epoch = mimir::get_epoch(&mut mimir::establish_connection()?)? as i64;
+    let current_epoch = chain.epoch()? as i64;
     println!("Rewards: {:?}", rewards);
     if rewards.len() == 1 && rewards[0].fingerprint == fingerprint {
         let tot_earned = rewards[0].tot_earned.clone()
@@ -240,7 +232,7 @@ pub(crate) async fn handle_testrewards(bms: &BuildMultiSig) -> crate::Result<Str
         let stake_rwd = gungnir::Rewards::create_rewards(
             &mut gconn,
             &gtxd.get_stake_address().to_bech32(None).unwrap(),
-            &mimir::api::select_addr_of_first_transaction(
+            &chain.first_address_of_stake_address(
                 &gtxd
                     .get_stake_address()
                     .to_bech32(None)
@@ -1,11 +1,14 @@
+use crate::protocol::codec::Encoding;
 use crate::Frame;
 
 use bytes::Bytes;
+use serde::de::DeserializeOwned;
 use std::{fmt, str, vec};
 
 #[derive(Debug)]
 pub(crate) struct Parse {
     parts: vec::IntoIter<Frame>,
+    encoding: Encoding,
 }
 
 #[derive(Debug)]
@@ -15,7 +18,7 @@ pub(crate) enum CmdParseError {
 }
 
 impl Parse {
-    pub(crate) fn new(frame: Frame) -> Result<Parse, CmdParseError> {
+    pub(crate) fn new(frame: Frame, encoding: Encoding) -> Result<Parse, CmdParseError> {
         log::trace!("Parse::new, frame: {:?}", frame);
         let array = match frame {
             Frame::Array(array) => array,
@@ -26,6 +29,7 @@ impl Parse {
 
         Ok(Parse {
             parts: array.into_iter(),
+            encoding,
         })
     }
 
@@ -57,6 +61,14 @@ impl Parse {
         }
     }
 
+    /// Decodes the next bulk frame with the encoding of the connection
+    pub(crate) fn next_value<T: DeserializeOwned>(&mut self) -> Result<T, CmdParseError> {
+        let bytes = self.next_bytes()?;
+        self.encoding
+            .decode(&bytes)
+            .map_err(|e| format!("protocol error; could not decode value: {e}").into())
+    }
+
     pub(crate) fn next_int(&mut self) -> Result<u64, CmdParseError> {
         use atoi::atoi;
 
@@ -14,6 +14,14 @@ impl Shutdown {
         }
     }
 
+    /// Another listener for the same shutdown signal
+    pub fn subscribe(&self) -> Shutdown {
+        Shutdown {
+            shutdown: self.shutdown,
+            notify: self.notify.resubscribe(),
+        }
+    }
+
     pub fn is_shutdown(&self) -> bool {
         self.shutdown
     }
@@ -0,0 +1,104 @@
+use super::nft_marketplace::{build_and_store, wallet_txdata};
+use crate::database::TBContracts;
+use crate::datamodel::{AuctionActions, ContractAction, ContractType, Operation};
+use crate::BuildContract;
+use drasil_murin::txbuilder::auction::{
+    AtAuctionBidBuilder, AtAuctionBidParam, AtAuctionCancelBuilder, AtAuctionCancelParam,
+    AtAuctionSettleBuilder, AtAuctionSettleParam, AtAuctionStartBuilder, AtAuctionStartParam,
+};
+use drasil_murin::{wallet, MurinError, PerformTxb};
+
+pub async fn handle_auction(bc: BuildContract) -> crate::Result<String> {
+    let operation = bc
+        .transaction_pattern()
+        .operation()
+        .ok_or("ERROR: No specific contract data supplied")?;
+
+    let wallet_addresses = match (&operation, bc.action()) {
+        (
+            Operation::Auction {
+                wallet_addresses, ..
+            },
+            ContractAction::AuctionActions(AuctionActions::StartAuction),
+        )
+        | (
+            Operation::AuctionBid {
+                wallet_addresses, ..
+            },
+            ContractAction::AuctionActions(AuctionActions::BidAuction),
+        )
+        | (
+            Operation::AuctionSettle {
+                wallet_addresses, ..
+            },
+            ContractAction::AuctionActions(AuctionActions::SettleAuction),
+        )
+        | (
+            Operation::AuctionCancel {
+                wallet_addresses, ..
+            },
+            ContractAction::AuctionActions(AuctionActions::CancelAuction),
+        ) => wallet_addresses.clone(),
+        _ => {
+            return Err(format!("ERROR wrong data provided for '{:?}'", bc.action()).into());
+        }
+    };
+
+    let mut gtxd = wallet_txdata(&bc, wallet_addresses).await?;
+    gtxd.set_user_id(bc.customer_id as i64);
+
+    let contract = TBContracts::get_active_contract_for_user(
+        bc.customer_id as i64,
+        ContractType::Auction.to_string(),
+        None,
+    )
+    .map_err(|e| e.to_string())?;
+
+    let chain = drasil_mimir::chain_data();
+    let smartcontract_inputs = chain
+        .address_utxos(&contract.address)
+        .map_err(|e| e.to_string())?;
+
+    let stake_address = gtxd.get_stake_address();
+    let first_address = wallet::address_from_string(
+        &chain
+            .first_address_of_stake_address(&stake_address.to_bech32(None)?)
+            .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?,
+    )
+    .await?;
+
+    let slot = chain.slot().map_err(|e| e.to_string())?;
+    gtxd.set_current_slot(slot as u64);
+
+    let plutus = drasil_murin::plutus::PlutusScript::from_bytes(hex::decode(&contract.plutus)?)?;
+    let sc_address = drasil_murin::clib::address::Address::from_bech32(&contract.address)?;
+    let auction = operation.into_auction(smartcontract_inputs).await?;
+    let tx_specific = auction.to_string();
+
+    let ret = match bc.action() {
+        ContractAction::AuctionActions(AuctionActions::StartAuction) => {
+            let txb_param: AtAuctionStartParam = (&plutus, &sc_address, &first_address, &auction);
+            let builder = AtAuctionStartBuilder::new(txb_param);
+            build_and_store(&bc, &gtxd, &builder, &tx_specific, contract.contract_id).await?
+        }
+        ContractAction::AuctionActions(AuctionActions::BidAuction) => {
+            let txb_param: AtAuctionBidParam = (&plutus, &sc_address, &first_address, &auction);
+            let builder = AtAuctionBidBuilder::new(txb_param);
+            build_and_store(&bc, &gtxd, &builder, &tx_specific, contract.contract_id).await?
+        }
+        ContractAction::AuctionActions(AuctionActions::SettleAuction) => {
+            let txb_param: AtAuctionSettleParam = (&plutus, &sc_address, &first_address, &auction);
+            let builder = AtAuctionSettleBuilder::new(txb_param);
+            build_and_store(&bc, &gtxd, &builder, &tx_specific, contract.contract_id).await?
+        }
+        ContractAction::AuctionActions(AuctionActions::CancelAuction) => {
+            let txb_param: AtAuctionCancelParam = (&plutus, &sc_address, &first_address, &auction);
+            let builder = AtAuctionCancelBuilder::new(txb_param);
+            build_and_store(&bc, &gtxd, &builder, &tx_specific, contract.contract_id).await?
+        }
+        _ => {
+            return Err(format!("ERROR wrong data provided for '{:?}'", bc.contract_type()).into())
+        }
+    };
+    Ok(ret)
+}
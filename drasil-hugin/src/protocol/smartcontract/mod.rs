@@ -1 +1,2 @@
-pub mod nft_marketplace;
\ No newline at end of file
+pub mod auction;
+pub mod nft_marketplace;
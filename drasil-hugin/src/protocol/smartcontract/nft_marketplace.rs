@@ -1,92 +1,52 @@
 use crate::database::TBContracts;
-use crate::datamodel::{ContractAction, MarketplaceActions, Operation};
+use crate::datamodel::{ContractAction, ContractType, MarketplaceActions, Operation};
 use crate::{create_response, BuildContract};
-use drasil_murin::cardano::get_network_from_address;
-use drasil_murin::wallet::reward_address_from_address;
+use drasil_murin::txbuilder::marketplace::build_sweep;
 use drasil_murin::{
-    wallet, AtMPCancelBuilder, AtMPCancelParam, AtMPListBuilder, AtMPListParam, MurinError,
-    PerformTxb, TransactionUnspentOutputs, TxData,
+    wallet, AtMPAcceptOfferBuilder, AtMPAcceptOfferParam, AtMPCancelBuilder,
+    AtMPCancelOfferBuilder, AtMPCancelOfferParam, AtMPCancelParam, AtMPListBuilder, AtMPListParam,
+    AtMPOfferBuilder, AtMPOfferParam, MurinError, PerformTxb, TxData,
 };
 pub async fn handle_marketplace(bc: BuildContract) -> crate::Result<String> {
-    match bc
+    let wallet_addresses = match bc
         .transaction_pattern()
         .operation()
         .ok_or("ERROR: No specific contract data supplied")?
     {
-        Operation::Marketplace { tokens, .. } => {
+        Operation::Marketplace {
+            tokens,
+            wallet_addresses,
+            ..
+        } => {
             if tokens.is_empty() {
                 return Err(format!("ERROR no asset provided",).into());
             }
-            if tokens.len() != 1 {
+            // Purchases can sweep several listings at once
+            if tokens.len() != 1
+                && *bc.action() != ContractAction::MarketplaceActions(MarketplaceActions::Buy)
+            {
                 return Err(format!("ERROR just one asset at a time is supported",).into());
             }
+            wallet_addresses
         }
+        Operation::NftOffer {
+            wallet_addresses, ..
+        }
+        | Operation::CollectionBid {
+            wallet_addresses, ..
+        }
+        | Operation::OfferAccept {
+            wallet_addresses, ..
+        }
+        | Operation::OfferCancel {
+            wallet_addresses, ..
+        } => wallet_addresses,
         _ => {
             return Err(format!("ERROR wrong data provided for '{:?}'", bc.contract_type()).into());
         }
-    }
-
-    let operation = match bc.txpattern.operation() {
-        Some(d) => match d {
-            crate::Operation::Marketplace {
-                tokens,
-                royalties_addr,
-                royalties_rate,
-                selling_price,
-                wallet_addresses,
-            } => (
-                tokens,
-                royalties_addr,
-                royalties_rate,
-                selling_price,
-                wallet_addresses,
-            ),
-            _ => return Err("Could not deserialze Marketplace Operation".into()),
-        },
-        None => return Err("Could not deserialze Marketplace Operation".into()),
     };
-    let mut gtxd: TxData;
-    match bc.transaction_pattern().into_txdata().await {
-        Ok(data) => gtxd = data,
-        Err(_) => {
-            // We only got wallet addresses, determine the txdata on your own
-            let wallet_addresses = operation.4.unwrap();
-            let wallet_addresses = wallet_addresses.iter().fold(
-                Vec::<drasil_murin::clib::address::Address>::new(),
-                |mut acc, n| {
-                    acc.push(drasil_murin::clib::address::Address::from_bech32(&n).unwrap());
-                    acc
-                },
-            );
 
-            let an_address = &wallet_addresses[0].clone();
-
-            let stake_address = reward_address_from_address(&an_address.clone())?;
-
-            if !wallet_addresses.is_empty() {
-                let wallet_utxos =
-                    wallet_addresses
-                        .iter()
-                        .fold(TransactionUnspentOutputs::new(), |mut acc, n| {
-                            acc.merge(
-                                drasil_mimir::get_address_utxos(&n.to_bech32(None).unwrap())
-                                    .unwrap(),
-                            );
-                            acc
-                        });
-                gtxd = TxData::new(
-                    None,
-                    wallet_addresses,
-                    Some(stake_address),
-                    wallet_utxos,
-                    get_network_from_address(&an_address.to_bech32(None)?)?,
-                    0,
-                )?;
-            } else {
-                return Err("Unable to determine wallet data".into());
-            }
-        }
-    };
+    let mut gtxd = wallet_txdata(&bc, wallet_addresses).await?;
 
     let mut contracts = TBContracts::get_all_contracts_for_user_typed(
         bc.customer_id as i64,
@@ -113,115 +73,214 @@ pub async fn handle_marketplace(bc: BuildContract) -> crate::Result<String> {
         contracts[0].clone()
     };
 
-    let smartcontract_inputs =
-        drasil_mimir::get_address_utxos(&contract.address).map_err(|e| e.to_string())?;
+    // Offers lock ADA at their own validator, never at the listing script
+    let offer_action = matches!(
+        bc.action(),
+        ContractAction::MarketplaceActions(
+            MarketplaceActions::Offer
+                | MarketplaceActions::Bid
+                | MarketplaceActions::AcceptOffer
+                | MarketplaceActions::CancelOffer
+        )
+    );
+    let contract = if offer_action {
+        TBContracts::get_active_contract_for_user(
+            bc.customer_id as i64,
+            ContractType::MarketplaceOffer.to_string(),
+            None,
+        )
+        .map_err(|_| "ERROR offers need an active 'MarketplaceOffer' contract")?
+    } else {
+        contract
+    };
+
+    let chain = drasil_mimir::chain_data();
+    let smartcontract_inputs = chain
+        .address_utxos(&contract.address)
+        .map_err(|e| e.to_string())?;
 
-    // Transform into Marketplace TxData for further processing
-    let mptxd = bc
-        .transaction_pattern()
-        .operation()
-        .unwrap()
-        .into_mp(smartcontract_inputs)
-        .await?;
     gtxd.set_user_id(bc.customer_id as i64);
 
     let stake_address = gtxd.get_stake_address();
     let first_address = wallet::address_from_string(
-        &drasil_mimir::api::select_addr_of_first_transaction(&stake_address.to_bech32(None)?)
+        &chain
+            .first_address_of_stake_address(&stake_address.to_bech32(None)?)
             .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?,
     )
     .await?;
 
-    let contract: TBContracts = TBContracts::get_active_contract_for_user(
-        bc.customer_id as i64,
-        bc.ctype.to_string(),
-        None,
-    )
-    .map_err(|e| e.to_string())?;
+    let contract: TBContracts = if offer_action {
+        contract
+    } else {
+        TBContracts::get_active_contract_for_user(bc.customer_id as i64, bc.ctype.to_string(), None)
+            .map_err(|e| e.to_string())?
+    };
 
-    let mut dbsync = drasil_mimir::establish_connection().map_err(|e| e.to_string())?;
-    let slot = drasil_mimir::get_slot(&mut dbsync).map_err(|e| e.to_string())?;
+    let slot = chain.slot().map_err(|e| e.to_string())?;
     gtxd.set_current_slot(slot as u64);
 
-    let ret: String;
+    let operation = bc.transaction_pattern().operation().unwrap();
+    let plutus = drasil_murin::plutus::PlutusScript::from_bytes(hex::decode(&contract.plutus)?)?;
+    let sc_address = drasil_murin::clib::address::Address::from_bech32(&contract.address)?;
 
-    match bc.action() {
+    let ret = match bc.action() {
         ContractAction::MarketplaceActions(MarketplaceActions::List) => {
-            log::debug!("Try to build transaction...");
-            let txb_param: AtMPListParam = (
-                &drasil_murin::plutus::PlutusScript::from_bytes(hex::decode(contract.plutus)?)?,
-                &drasil_murin::clib::address::Address::from_bech32(&contract.address)?,
-                &first_address,
-                &mptxd,
-            );
-
+            let mptxd = operation.into_mp(smartcontract_inputs).await?;
+            let txb_param: AtMPListParam = (&plutus, &sc_address, &first_address, &mptxd);
             let minter = AtMPListBuilder::new(txb_param);
-            let builder = drasil_murin::TxBuilder::new(&gtxd, &vec![]);
-            let bld_tx = builder.build(&minter).await?;
-
-            log::debug!("Try to create raw tx...");
-
-            let tx = drasil_murin::utxomngr::RawTx::new(
-                &bld_tx.get_tx_body(),
-                &bld_tx.get_txwitness(),
-                &bld_tx.get_tx_unsigned(),
-                &bld_tx.get_metadata(),
-                &gtxd.to_string(),
+            build_and_store(
+                &bc,
+                &gtxd,
+                &minter,
                 &mptxd.to_string(),
-                &bld_tx.get_used_utxos(),
-                &hex::encode(gtxd.get_stake_address().to_bytes()),
-                &(bc.customer_id as i64),
-                &[contract.contract_id],
-            );
-
-            ret = create_response(
-                &bld_tx,
-                &tx,
-                bc.transaction_pattern().wallet_type().as_ref(),
-            )?
-            .to_string();
+                contract.contract_id,
+            )
+            .await?
         }
         ContractAction::MarketplaceActions(MarketplaceActions::Buy) => {
-            ret = "Got MP Buy Transaction".to_string();
+            let listings = operation.into_sweep(smartcontract_inputs).await?;
+            log::debug!("Try to build transaction...");
+            let builder = drasil_murin::TxBuilder::new(&gtxd, &vec![]);
+            let bld_tx =
+                build_sweep(&builder, &plutus, &sc_address, &first_address, &listings).await?;
+            let tx_specific = listings
+                .iter()
+                .map(|l| l.to_string())
+                .collect::<Vec<_>>()
+                .join(";");
+            store_and_respond(&bc, &gtxd, &bld_tx, &tx_specific, contract.contract_id)?
         }
         ContractAction::MarketplaceActions(MarketplaceActions::Cancel) => {
-            log::debug!("Try to build transaction...");
-            let txb_param: AtMPCancelParam = (
-                &drasil_murin::plutus::PlutusScript::from_bytes(hex::decode(contract.plutus)?)?,
-                &drasil_murin::clib::address::Address::from_bech32(&contract.address)?,
-                &first_address,
-                &mptxd,
+            let mptxd = operation.into_mp(smartcontract_inputs).await?;
+            let txb_param: AtMPCancelParam = (&plutus, &sc_address, &first_address, &mptxd);
+            let minter = AtMPCancelBuilder::new(txb_param);
+            build_and_store(
+                &bc,
+                &gtxd,
+                &minter,
+                &mptxd.to_string(),
+                contract.contract_id,
+            )
+            .await?
+        }
+        ContractAction::MarketplaceActions(MarketplaceActions::Update) => {
+            return Err(
+                "ERROR updating a listing is not supported, cancel it and list the token again"
+                    .into(),
             );
+        }
+        ContractAction::MarketplaceActions(
+            action @ (MarketplaceActions::Offer | MarketplaceActions::Bid),
+        ) => {
+            let offer = operation.into_offer(smartcontract_inputs).await?;
+            // Offers name a token, bids are open to any token of the policy
+            if offer.get_token_name().is_some() != (*action == MarketplaceActions::Offer) {
+                return Err(format!("ERROR wrong data provided for '{action}'").into());
+            }
+            let txb_param: AtMPOfferParam = (&plutus, &sc_address, &first_address, &offer);
+            let minter = AtMPOfferBuilder::new(txb_param);
+            build_and_store(
+                &bc,
+                &gtxd,
+                &minter,
+                &offer.to_string(),
+                contract.contract_id,
+            )
+            .await?
+        }
+        ContractAction::MarketplaceActions(MarketplaceActions::AcceptOffer) => {
+            if !operation.is_offer_accept() {
+                return Err("ERROR wrong data provided for 'AcceptOffer'".into());
+            }
+            let offer = operation.into_offer(smartcontract_inputs).await?;
+            let txb_param: AtMPAcceptOfferParam = (&plutus, &sc_address, &first_address, &offer);
+            let minter = AtMPAcceptOfferBuilder::new(txb_param);
+            build_and_store(
+                &bc,
+                &gtxd,
+                &minter,
+                &offer.to_string(),
+                contract.contract_id,
+            )
+            .await?
+        }
+        ContractAction::MarketplaceActions(MarketplaceActions::CancelOffer) => {
+            if !operation.is_offer_cancel() {
+                return Err("ERROR wrong data provided for 'CancelOffer'".into());
+            }
+            let offer = operation.into_offer(smartcontract_inputs).await?;
+            let txb_param: AtMPCancelOfferParam = (&plutus, &sc_address, &first_address, &offer);
+            let minter = AtMPCancelOfferBuilder::new(txb_param);
+            build_and_store(
+                &bc,
+                &gtxd,
+                &minter,
+                &offer.to_string(),
+                contract.contract_id,
+            )
+            .await?
+        }
+        _ => {
+            return Err(format!("ERROR wrong data provided for '{:?}'", bc.action()).into());
+        }
+    };
+    Ok(ret)
+}
 
-            let minter = AtMPCancelBuilder::new(txb_param);
-            let builder = drasil_murin::TxBuilder::new(&gtxd, &vec![]);
-            let bld_tx = builder.build(&minter).await?;
+/// Builds the transaction, stores it as raw transaction and creates the response
+pub(crate) async fn build_and_store<P, A: PerformTxb<P>>(
+    bc: &BuildContract,
+    gtxd: &TxData,
+    minter: &A,
+    tx_specific: &str,
+    contract_id: i64,
+) -> crate::Result<String> {
+    log::debug!("Try to build transaction...");
+    let builder = drasil_murin::TxBuilder::new(gtxd, &vec![]);
+    let bld_tx = builder.build(minter).await?;
+    store_and_respond(bc, gtxd, &bld_tx, tx_specific, contract_id)
+}
 
-            log::debug!("Try to create raw tx...");
+/// Stores the built transaction as raw transaction and creates the response
+fn store_and_respond(
+    bc: &BuildContract,
+    gtxd: &TxData,
+    bld_tx: &drasil_murin::cardano::models::BuildOutput,
+    tx_specific: &str,
+    contract_id: i64,
+) -> crate::Result<String> {
+    log::debug!("Try to create raw tx...");
+    let tx = drasil_murin::utxomngr::RawTx::new(
+        &bld_tx.get_tx_body(),
+        &bld_tx.get_txwitness(),
+        &bld_tx.get_tx_unsigned(),
+        &bld_tx.get_metadata(),
+        &gtxd.to_string(),
+        &tx_specific.to_string(),
+        &bld_tx.get_used_utxos(),
+        &hex::encode(gtxd.get_stake_address().to_bytes()),
+        &(bc.customer_id as i64),
+        &[contract_id],
+    );
 
-            let tx = drasil_murin::utxomngr::RawTx::new(
-                &bld_tx.get_tx_body(),
-                &bld_tx.get_txwitness(),
-                &bld_tx.get_tx_unsigned(),
-                &bld_tx.get_metadata(),
-                &gtxd.to_string(),
-                &mptxd.to_string(),
-                &bld_tx.get_used_utxos(),
-                &hex::encode(gtxd.get_stake_address().to_bytes()),
-                &(bc.customer_id as i64),
-                &[contract.contract_id],
-            );
+    Ok(create_response(bld_tx, &tx, bc.transaction_pattern().wallet_type().as_ref())?.to_string())
+}
 
-            ret = create_response(
-                &bld_tx,
-                &tx,
-                bc.transaction_pattern().wallet_type().as_ref(),
-            )?
-            .to_string();
-        }
-        ContractAction::MarketplaceActions(MarketplaceActions::Update) => {
-            ret = "Got MP Update Transaction".to_string();
+/// Transaction data of the transaction pattern, or of the wallet addresses if it has none
+pub(crate) async fn wallet_txdata(
+    bc: &BuildContract,
+    wallet_addresses: Option<Vec<String>>,
+) -> crate::Result<TxData> {
+    match bc.transaction_pattern().into_txdata().await {
+        Ok(data) => Ok(data),
+        // We only got wallet addresses, determine the txdata on your own
+        Err(_) => {
+            crate::protocol::stdtx::pattern_txdata(
+                bc.transaction_pattern(),
+                bc.customer_id(),
+                &wallet_addresses,
+            )
+            .await
         }
     }
-    Ok(ret)
 }
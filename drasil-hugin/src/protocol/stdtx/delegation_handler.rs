@@ -5,7 +5,6 @@ use drasil_murin::clib;
 use drasil_murin::wallet;
 use drasil_murin::MurinError;
 use drasil_murin::PerformTxb;
-use drasil_murin::TransactionUnspentOutputs;
 
 pub(crate) async fn handle_stake_delegation(bst: &BuildStdTx) -> crate::Result<String> {
     match bst
@@ -32,19 +31,15 @@ pub(crate) async fn handle_stake_delegation(bst: &BuildStdTx) -> crate::Result<S
     };
     // intotxdata only works with the transaction pattern, we also need to make the address pattern acceptable
 
-    let wal_addr = if let Some(addr) = addresses {
-        addr.iter()
-            .fold(Vec::<clib::address::Address>::new(), |mut acc, a| {
-                acc.push(wallet::address_from_string_non_async(a).unwrap());
-                acc
-            })
-    } else {
-        vec![]
-    };
-    let addresses = wal_addr.iter().fold(Vec::<String>::new(), |mut acc, n| {
-        acc.push(n.to_bech32(None).unwrap());
-        acc
-    });
+    let wal_addr = addresses
+        .iter()
+        .flatten()
+        .map(wallet::address_from_string_non_async)
+        .collect::<Result<Vec<clib::address::Address>, _>>()?;
+    let addresses = wal_addr
+        .iter()
+        .map(|a| a.to_bech32(None))
+        .collect::<Result<Vec<_>, _>>()?;
     debug!("stake delegation addresses: {:?}", addresses);
     let mut bst_tmp = bst.transaction_pattern().clone();
     bst_tmp.set_used_addresses(&addresses[..]);
@@ -55,14 +50,9 @@ pub(crate) async fn handle_stake_delegation(bst: &BuildStdTx) -> crate::Result<S
     let mut gtxd = bst_tmp.into_txdata().await?;
     gtxd.set_user_id(bst.customer_id());
 
+    let chain = drasil_mimir::chain_data();
     if !wal_addr.is_empty() {
-        let wallet_utxos = wal_addr
-            .iter()
-            .fold(TransactionUnspentOutputs::new(), |mut acc, n| {
-                acc.merge(drasil_mimir::get_address_utxos(&n.to_bech32(None).unwrap()).unwrap());
-                acc
-            });
-        gtxd.set_inputs(wallet_utxos);
+        gtxd.set_inputs(super::wallet_utxos(chain.as_ref(), &wal_addr)?);
 
         let sa = wallet::reward_address_from_address(&wal_addr[0])?;
         gtxd.set_stake_address(sa);
@@ -70,13 +60,8 @@ pub(crate) async fn handle_stake_delegation(bst: &BuildStdTx) -> crate::Result<S
     }
 
     log::debug!("Try to determine slot...");
-    let mut dbsync = match drasil_mimir::establish_connection() {
-        Ok(conn) => conn,
-        Err(e) => {
-            return Err(format!("ERROR could not connect to dbsync: '{:?}'", e.to_string()).into());
-        }
-    };
-    let slot = drasil_mimir::get_slot(&mut dbsync)
+    let slot = chain
+        .slot()
         .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
     gtxd.set_current_slot(slot as u64);
 
@@ -87,7 +72,8 @@ pub(crate) async fn handle_stake_delegation(bst: &BuildStdTx) -> crate::Result<S
         }
     };
 
-    let registered = drasil_mimir::check_stakeaddr_registered(&bech32_stake_addr)
+    let registered = chain
+        .stake_address_registered(&bech32_stake_addr)
         .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
     delegtxd.set_registered(Some(registered));
 
@@ -158,4 +144,26 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn stake_delegation_returns_chain_data_errors() {
+        let addr1 = "addr_test1qp8cprhse9pnnv7f4l3n6pj0afq2hjm6f7r2205dz0583egaeu9dhacmtx94652q4ym0v9v2mcra0n28d5lrtjqzsgxqgk5t8s".to_string();
+        let script_spec = Operation::StakeDelegation {
+            poolhash: "pool1pt39c4va0aljcgn4jqru0jhtws9q5wj8u0xnajtkgk9g7lxlk2t".to_string(),
+            addresses: Some(vec![addr1]),
+        };
+        let txpattern = TransactionPattern::new_empty(0, &script_spec, 0);
+        let bst = BuildStdTx::new(0, StdTxType::DelegateStake, txpattern);
+
+        // The fixture knows the wallet but has no chain tip, the slot lookup has to fail
+        drasil_mimir::set_chain_data_provider(std::sync::Arc::new(
+            drasil_mimir::provider::FixtureProvider::new(),
+        ));
+        let err = super::handle_stake_delegation(&bst).await.unwrap_err();
+
+        assert!(matches!(
+            err,
+            drasil_murin::MurinError::ProtocolCommandError(_)
+        ));
+    }
 }
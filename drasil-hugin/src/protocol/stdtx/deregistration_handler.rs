@@ -5,7 +5,6 @@ use drasil_murin::clib;
 use drasil_murin::wallet::address_from_string_non_async;
 use drasil_murin::MurinError;
 use drasil_murin::PerformTxb;
-use drasil_murin::TransactionUnspentOutputs;
 
 pub(crate) async fn handle_stake_deregistration(bst: &BuildStdTx) -> crate::Result<String> {
     match bst
@@ -31,19 +30,20 @@ pub(crate) async fn handle_stake_deregistration(bst: &BuildStdTx) -> crate::Resu
         }
     };
     // intotxdata only works with the transaction pattern, we also need to make the address pattern acceptable
-    let wal_addr = if let Some(addr) = addresses {
-        addr.iter()
-            .fold(Vec::<clib::address::Address>::new(), |mut acc, a| {
-                acc.push(address_from_string_non_async(a).unwrap());
-                acc
-            })
-    } else {
-        vec![]
-    };
+    let wal_addr = addresses
+        .iter()
+        .flatten()
+        .map(address_from_string_non_async)
+        .collect::<Result<Vec<clib::address::Address>, _>>()?;
 
     debug!("stake deregistration address: {:?}", addresses);
     let mut bst_tp = bst.transaction_pattern().clone();
-    bst_tp.set_used_addresses(&addresses.clone().unwrap()[..]);
+    bst_tp.set_used_addresses(
+        &wal_addr
+            .iter()
+            .map(|a| a.to_bech32(None))
+            .collect::<Result<Vec<_>, _>>()?,
+    );
 
     debug!(
         "bst.transaction_pattern().stake_addr: {:?}",
@@ -57,14 +57,9 @@ pub(crate) async fn handle_stake_deregistration(bst: &BuildStdTx) -> crate::Resu
 
     gtxd.set_user_id(bst.customer_id());
 
+    let chain = drasil_mimir::chain_data();
     if !wal_addr.is_empty() {
-        let wallet_utxos = wal_addr
-            .iter()
-            .fold(TransactionUnspentOutputs::new(), |mut acc, n| {
-                acc.merge(drasil_mimir::get_address_utxos(&n.to_bech32(None).unwrap()).unwrap());
-                acc
-            });
-        gtxd.set_inputs(wallet_utxos);
+        gtxd.set_inputs(super::wallet_utxos(chain.as_ref(), &wal_addr)?);
 
         let sa = drasil_murin::wallet::reward_address_from_address(&wal_addr[0])?;
         gtxd.set_stake_address(sa);
@@ -72,13 +67,8 @@ pub(crate) async fn handle_stake_deregistration(bst: &BuildStdTx) -> crate::Resu
     }
 
     log::debug!("Try to determine slot...");
-    let mut dbsync = match drasil_mimir::establish_connection() {
-        Ok(conn) => conn,
-        Err(e) => {
-            return Err(format!("ERROR could not connect to dbsync: '{:?}'", e.to_string()).into());
-        }
-    };
-    let current_slot = drasil_mimir::get_slot(&mut dbsync)
+    let current_slot = chain
+        .slot()
         .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
     gtxd.set_current_slot(current_slot as u64);
 
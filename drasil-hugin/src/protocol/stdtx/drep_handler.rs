@@ -0,0 +1,59 @@
+use crate::datamodel::{Operation, StdTxType};
+use crate::protocol::create_response;
+use crate::BuildStdTx;
+use drasil_murin::txbuilder::stdtx::DRepAction;
+use drasil_murin::PerformTxb;
+
+pub(crate) async fn handle_drep(bst: &BuildStdTx) -> crate::Result<String> {
+    let action = match bst.tx_type() {
+        StdTxType::RegisterDRep => DRepAction::Register,
+        StdTxType::UpdateDRep => DRepAction::Update,
+        StdTxType::RetireDRep => DRepAction::Retire,
+        _ => return Err(format!("ERROR '{:?}' is not a DRep transaction", bst.tx_type()).into()),
+    };
+    let op = bst
+        .transaction_pattern()
+        .operation()
+        .ok_or("ERROR: No transaction specific data supplied for DRep transaction")?;
+
+    let (dreptxd, addresses) = match &op {
+        Operation::DRep {
+            payment_addresses, ..
+        } => (op.into_drep(action).await?, payment_addresses),
+        _ => {
+            return Err(format!("ERROR wrong input data provided for '{:?}'", bst.tx_type()).into())
+        }
+    };
+
+    let gtxd = super::wallet_txdata(bst, addresses).await?;
+
+    log::debug!("Try to build transaction...");
+
+    let txb_param: drasil_murin::txbuilder::stdtx::AtDRepParams = &dreptxd;
+    let drep = drasil_murin::txbuilder::stdtx::AtDRepBuilder::new(txb_param);
+    let builder = drasil_murin::TxBuilder::new(&gtxd, &Vec::<String>::new());
+    let bld_tx = builder.build(&drep).await?;
+
+    info!("Build Successful!");
+    let tx = drasil_murin::utxomngr::RawTx::new(
+        &bld_tx.get_tx_body(),
+        &bld_tx.get_txwitness(),
+        &bld_tx.get_tx_unsigned(),
+        &bld_tx.get_metadata(),
+        &gtxd.to_string(),
+        &hex::encode(dreptxd.get_drep_keyhash().to_bytes()),
+        &bld_tx.get_used_utxos(),
+        &hex::encode(gtxd.get_stake_address().to_bytes()),
+        &(bst.customer_id()),
+        &[(-1)],
+    );
+    debug!("RAWTX data: {:?}", tx);
+
+    let ret = create_response(
+        &bld_tx,
+        &tx,
+        bst.transaction_pattern().wallet_type().as_ref(),
+    )?;
+
+    Ok(ret.to_string())
+}
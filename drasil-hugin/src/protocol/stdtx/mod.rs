@@ -2,7 +2,80 @@ pub(crate) mod delegation_handler;
 pub(crate) use delegation_handler::handle_stake_delegation;
 pub(crate) mod deregistration_handler;
 pub(crate) use deregistration_handler::handle_stake_deregistration;
+pub(crate) mod drep_handler;
+pub(crate) use drep_handler::handle_drep;
 pub(crate) mod standard_tx;
 pub(crate) use standard_tx::handle_stx;
+pub(crate) mod vote_delegation_handler;
+pub(crate) use vote_delegation_handler::handle_vote_delegation;
+pub(crate) mod vote_handler;
+pub(crate) use vote_handler::handle_governance_vote;
 pub(crate) mod withdrawal_handler;
 pub(crate) use withdrawal_handler::handle_reward_withdrawal;
+
+use crate::{BuildStdTx, TransactionPattern};
+use drasil_mimir::ChainDataProvider;
+use drasil_murin::clib::address::Address;
+use drasil_murin::wallet;
+use drasil_murin::MurinError;
+use drasil_murin::TransactionUnspentOutputs;
+
+/// Transaction data for a standard transaction paid from the given wallet addresses
+pub(crate) async fn wallet_txdata(
+    bst: &BuildStdTx,
+    addresses: &Option<Vec<String>>,
+) -> crate::Result<drasil_murin::TxData> {
+    pattern_txdata(&bst.transaction_pattern(), bst.customer_id(), addresses).await
+}
+
+/// Transaction data for the transaction pattern paid from the given wallet addresses
+pub(crate) async fn pattern_txdata(
+    txp: &TransactionPattern,
+    customer_id: i64,
+    addresses: &Option<Vec<String>>,
+) -> crate::Result<drasil_murin::TxData> {
+    let wal_addr = match addresses {
+        Some(addr) if !addr.is_empty() => addr
+            .iter()
+            .map(wallet::address_from_string_non_async)
+            .collect::<Result<Vec<_>, _>>()?,
+        _ => return Err("ERROR no wallet addresses provided".into()),
+    };
+    let addresses = wal_addr
+        .iter()
+        .map(|a| a.to_bech32(None))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut txp = txp.clone();
+    txp.set_used_addresses(&addresses[..]);
+    let mut gtxd = txp.into_txdata().await?;
+    gtxd.set_user_id(customer_id);
+
+    let chain = drasil_mimir::chain_data();
+    gtxd.set_inputs(wallet_utxos(chain.as_ref(), &wal_addr)?);
+    gtxd.set_stake_address(wallet::reward_address_from_address(&wal_addr[0])?);
+    gtxd.set_senders_addresses(wal_addr);
+
+    log::debug!("Try to determine slot...");
+    let slot = chain
+        .slot()
+        .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
+    gtxd.set_current_slot(slot as u64);
+    Ok(gtxd)
+}
+
+/// Unspent outputs of all wallet addresses
+pub(crate) fn wallet_utxos(
+    chain: &dyn ChainDataProvider,
+    addresses: &[Address],
+) -> Result<TransactionUnspentOutputs, MurinError> {
+    let mut utxos = TransactionUnspentOutputs::new();
+    for a in addresses {
+        utxos.merge(
+            chain
+                .address_utxos(&a.to_bech32(None)?)
+                .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?,
+        );
+    }
+    Ok(utxos)
+}
@@ -4,7 +4,6 @@ use crate::BuildStdTx;
 
 use drasil_murin::clib::address::Address;
 use drasil_murin::MurinError;
-use drasil_murin::TransactionUnspentOutputs;
 
 use drasil_murin::modules::transfer::models::{TransWallet, TransWallets};
 use drasil_murin::stdtx::build_wallet_asset_transfer::{AtSATBuilder, AtSATParams};
@@ -60,28 +59,22 @@ pub(crate) async fn handle_stx(bss: &BuildStdTx) -> Result<String, MurinError> {
         .into_stdassettx()
         .await?;
 
-    let addresses =
-        std_asset_txd
-            .wallet_addresses
-            .iter()
-            .fold(Vec::<String>::new(), |mut acc, n| {
-                acc.push(n.to_bech32(None).unwrap());
-                acc
-            });
+    let addresses = std_asset_txd
+        .wallet_addresses
+        .iter()
+        .map(|a| a.to_bech32(None))
+        .collect::<Result<Vec<_>, _>>()?;
     bsstp.set_used_addresses(&addresses);
 
     log::debug!("Try to create raw data2...");
     let mut gtxd = bsstp.into_txdata().await?;
     log::debug!("Try to create raw data3...");
+    let chain = drasil_mimir::chain_data();
     if !std_asset_txd.wallet_addresses.is_empty() {
-        let wallet_utxos = std_asset_txd.wallet_addresses.iter().fold(
-            TransactionUnspentOutputs::new(),
-            |mut acc, n| {
-                acc.merge(drasil_mimir::get_address_utxos(&n.to_bech32(None).unwrap()).unwrap());
-                acc
-            },
-        );
-        gtxd.set_inputs(wallet_utxos);
+        gtxd.set_inputs(super::wallet_utxos(
+            chain.as_ref(),
+            &std_asset_txd.wallet_addresses,
+        )?);
 
         let sa = wallet::reward_address_from_address(&std_asset_txd.wallet_addresses[0])?;
         gtxd.set_stake_address(sa);
@@ -89,16 +82,7 @@ pub(crate) async fn handle_stx(bss: &BuildStdTx) -> Result<String, MurinError> {
     }
 
     log::debug!("Try to determine slot...");
-    let mut dbsync = match drasil_mimir::establish_connection() {
-        Ok(conn) => conn,
-        Err(e) => {
-            return Err(MurinError::new(&format!(
-                "ERROR could not connect to dbsync: '{:?}'",
-                e.to_string()
-            )));
-        }
-    };
-    let slot = match drasil_mimir::get_slot(&mut dbsync) {
+    let slot = match chain.slot() {
         Ok(s) => s,
         Err(e) => {
             return Err(MurinError::new(&format!(
@@ -111,14 +95,14 @@ pub(crate) async fn handle_stx(bss: &BuildStdTx) -> Result<String, MurinError> {
 
     let mut wallets = TransWallets::new();
 
-    let first_address_str =
-        drasil_mimir::select_addr_of_first_transaction(&gtxd.get_stake_address().to_bech32(None)?)
-            .map_err(|e| {
-                MurinError::new(&format!(
-                    "Could not get address of first transaction: {:?}",
-                    e.to_string()
-                ))
-            })?;
+    let first_address_str = chain
+        .first_address_of_stake_address(&gtxd.get_stake_address().to_bech32(None)?)
+        .map_err(|e| {
+            MurinError::new(&format!(
+                "Could not get address of first transaction: {:?}",
+                e.to_string()
+            ))
+        })?;
     let first_addr = Address::from_bech32(&first_address_str)?;
 
     let uw = TransWallet::new(&first_addr, &gtxd.get_inputs());
@@ -0,0 +1,65 @@
+use crate::datamodel::Operation;
+use crate::protocol::create_response;
+use crate::BuildStdTx;
+use drasil_murin::MurinError;
+use drasil_murin::PerformTxb;
+
+pub(crate) async fn handle_vote_delegation(bst: &BuildStdTx) -> crate::Result<String> {
+    let op = bst
+        .transaction_pattern()
+        .operation()
+        .ok_or("ERROR: No transaction specific data supplied for vote delegation")?;
+
+    let (mut votedelegtxd, addresses) = match &op {
+        Operation::VoteDelegation { addresses, .. } => {
+            (op.into_vote_delegation().await?, addresses)
+        }
+        _ => {
+            return Err(format!("ERROR wrong input data provided for '{:?}'", bst.tx_type()).into())
+        }
+    };
+
+    let gtxd = super::wallet_txdata(bst, addresses).await?;
+
+    let bech32_stake_addr = match gtxd.get_stake_address().to_bech32(None) {
+        Ok(ba) => ba,
+        Err(e) => {
+            return Err(format!("Could not convert Stake Address;' {e:?}'").into());
+        }
+    };
+    let registered = drasil_mimir::chain_data()
+        .stake_address_registered(&bech32_stake_addr)
+        .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?;
+    votedelegtxd.set_registered(Some(registered));
+    votedelegtxd.set_stake_address(&gtxd.get_stake_address());
+
+    log::debug!("Try to build transaction...");
+
+    let txb_param: drasil_murin::txbuilder::stdtx::AtVoteDelegParams = &votedelegtxd;
+    let vote_deleg = drasil_murin::txbuilder::stdtx::AtVoteDelegBuilder::new(txb_param);
+    let builder = drasil_murin::TxBuilder::new(&gtxd, &Vec::<String>::new());
+    let bld_tx = builder.build(&vote_deleg).await?;
+
+    info!("Build Successful!");
+    let tx = drasil_murin::utxomngr::RawTx::new(
+        &bld_tx.get_tx_body(),
+        &bld_tx.get_txwitness(),
+        &bld_tx.get_tx_unsigned(),
+        &bld_tx.get_metadata(),
+        &gtxd.to_string(),
+        &"".to_string(),
+        &bld_tx.get_used_utxos(),
+        &hex::encode(gtxd.get_stake_address().to_bytes()),
+        &(bst.customer_id()),
+        &[(-1)],
+    );
+    debug!("RAWTX data: {:?}", tx);
+
+    let ret = create_response(
+        &bld_tx,
+        &tx,
+        bst.transaction_pattern().wallet_type().as_ref(),
+    )?;
+
+    Ok(ret.to_string())
+}
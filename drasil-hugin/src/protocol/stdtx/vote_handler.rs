@@ -0,0 +1,52 @@
+use crate::datamodel::Operation;
+use crate::protocol::create_response;
+use crate::BuildStdTx;
+use drasil_murin::PerformTxb;
+
+pub(crate) async fn handle_governance_vote(bst: &BuildStdTx) -> crate::Result<String> {
+    let op = bst
+        .transaction_pattern()
+        .operation()
+        .ok_or("ERROR: No transaction specific data supplied for governance vote")?;
+
+    let (votetxd, addresses) = match &op {
+        Operation::GovernanceVote {
+            payment_addresses, ..
+        } => (op.into_governance_vote().await?, payment_addresses),
+        _ => {
+            return Err(format!("ERROR wrong input data provided for '{:?}'", bst.tx_type()).into())
+        }
+    };
+
+    let gtxd = super::wallet_txdata(bst, addresses).await?;
+
+    log::debug!("Try to build transaction...");
+
+    let txb_param: drasil_murin::txbuilder::stdtx::AtVoteParams = &votetxd;
+    let vote = drasil_murin::txbuilder::stdtx::AtVoteBuilder::new(txb_param);
+    let builder = drasil_murin::TxBuilder::new(&gtxd, &Vec::<String>::new());
+    let bld_tx = builder.build(&vote).await?;
+
+    info!("Build Successful!");
+    let tx = drasil_murin::utxomngr::RawTx::new(
+        &bld_tx.get_tx_body(),
+        &bld_tx.get_txwitness(),
+        &bld_tx.get_tx_unsigned(),
+        &bld_tx.get_metadata(),
+        &gtxd.to_string(),
+        &hex::encode(votetxd.get_voter().key_hash().to_bytes()),
+        &bld_tx.get_used_utxos(),
+        &hex::encode(gtxd.get_stake_address().to_bytes()),
+        &(bst.customer_id()),
+        &[(-1)],
+    );
+    debug!("RAWTX data: {:?}", tx);
+
+    let ret = create_response(
+        &bld_tx,
+        &tx,
+        bst.transaction_pattern().wallet_type().as_ref(),
+    )?;
+
+    Ok(ret.to_string())
+}
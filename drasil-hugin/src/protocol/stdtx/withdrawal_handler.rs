@@ -1,6 +1,6 @@
 use drasil_murin::{MurinError, PerformTxb, RawTx};
 
-use crate::{create_response, BuildStdTx, CmdError, Operation};
+use crate::{create_response, BuildStdTx, Operation};
 
 pub(crate) async fn handle_reward_withdrawal(bst: &BuildStdTx) -> crate::Result<String> {
     let op = bst
@@ -13,14 +13,9 @@ pub(crate) async fn handle_reward_withdrawal(bst: &BuildStdTx) -> crate::Result<
     gtxd.set_user_id(bst.customer_id());
 
     gtxd.set_current_slot(
-        drasil_mimir::get_slot(
-            &mut drasil_mimir::establish_connection()
-                .map_err(|e| CmdError::Custom {
-                    str: format!("ERROR could not connect to dbsync: '{:?}'", e.to_string()),
-                })
-                .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))?,
-        )
-        .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))? as u64,
+        drasil_mimir::chain_data()
+            .slot()
+            .map_err(|e| MurinError::ProtocolCommandError(e.to_string()))? as u64,
     );
 
     let tx_data = &drasil_murin::TxBuilder::new(&gtxd, &Vec::<String>::new());
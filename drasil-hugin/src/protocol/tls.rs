@@ -0,0 +1,155 @@
+//! Mutual TLS between odin and its clients
+//!
+//! TLS is enabled by setting `ODIN_TLS_CA`, `ODIN_TLS_CERT` and `ODIN_TLS_KEY`
+//! to PEM files. Both sides present their certificate and only accept peers
+//! whose certificate is signed by the configured CA. Clients verify the server
+//! certificate against `ODIN_TLS_SERVER_NAME`, or the host of the address they
+//! connect to if it is not set.
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use tokio_rustls::rustls::server::AllowAnyAuthenticatedClient;
+use tokio_rustls::rustls::{
+    Certificate, ClientConfig, PrivateKey, RootCertStore, ServerConfig, ServerName,
+};
+pub use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+const CA_VAR: &str = "ODIN_TLS_CA";
+const CERT_VAR: &str = "ODIN_TLS_CERT";
+const KEY_VAR: &str = "ODIN_TLS_KEY";
+const SERVER_NAME_VAR: &str = "ODIN_TLS_SERVER_NAME";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsConfig {
+    /// CA which signs the certificates of all peers
+    pub ca: PathBuf,
+    /// Own certificate chain
+    pub cert: PathBuf,
+    /// Private key of the own certificate
+    pub key: PathBuf,
+    /// Name the server certificate is verified against
+    pub server_name: Option<String>,
+}
+
+impl TlsConfig {
+    pub fn new(ca: impl Into<PathBuf>, cert: impl Into<PathBuf>, key: impl Into<PathBuf>) -> Self {
+        TlsConfig {
+            ca: ca.into(),
+            cert: cert.into(),
+            key: key.into(),
+            server_name: None,
+        }
+    }
+
+    pub fn with_server_name(mut self, server_name: impl Into<String>) -> Self {
+        self.server_name = Some(server_name.into());
+        self
+    }
+
+    /// `None` if TLS is not configured, an error if it is configured incompletely
+    pub fn from_env() -> crate::Result<Option<TlsConfig>> {
+        let vars = [CA_VAR, CERT_VAR, KEY_VAR].map(|v| std::env::var(v).ok());
+        match vars {
+            [None, None, None] => Ok(None),
+            [Some(ca), Some(cert), Some(key)] => {
+                let mut config = TlsConfig::new(ca, cert, key);
+                config.server_name = std::env::var(SERVER_NAME_VAR).ok();
+                Ok(Some(config))
+            }
+            _ => Err(format!("TLS needs all of {CA_VAR}, {CERT_VAR} and {KEY_VAR}").into()),
+        }
+    }
+
+    /// Acceptor which rejects clients without a certificate signed by the CA
+    pub fn acceptor(&self) -> crate::Result<TlsAcceptor> {
+        let verifier = AllowAnyAuthenticatedClient::new(self.roots()?).boxed();
+        let config = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(load_certs(&self.cert)?, load_key(&self.key)?)
+            .map_err(|e| format!("invalid server certificate: {e}"))?;
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+
+    pub fn connector(&self) -> crate::Result<TlsConnector> {
+        let config = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(self.roots()?)
+            .with_client_auth_cert(load_certs(&self.cert)?, load_key(&self.key)?)
+            .map_err(|e| format!("invalid client certificate: {e}"))?;
+        Ok(TlsConnector::from(Arc::new(config)))
+    }
+
+    /// Name to verify the server certificate of `addr` against
+    pub fn server_name(&self, addr: &str) -> crate::Result<ServerName> {
+        let name = match &self.server_name {
+            Some(name) => name.as_str(),
+            None => host(addr),
+        };
+        ServerName::try_from(name).map_err(|e| format!("invalid server name {name}: {e}").into())
+    }
+
+    fn roots(&self) -> crate::Result<RootCertStore> {
+        let mut roots = RootCertStore::empty();
+        for cert in load_certs(&self.ca)? {
+            roots
+                .add(&cert)
+                .map_err(|e| format!("invalid CA certificate: {e}"))?;
+        }
+        Ok(roots)
+    }
+}
+
+// Host part of a `host:port` address, IPv6 addresses may be bracketed
+fn host(addr: &str) -> &str {
+    let host = match addr.rsplit_once(':') {
+        Some((host, port)) if port.parse::<u16>().is_ok() => host,
+        _ => addr,
+    };
+    host.trim_start_matches('[').trim_end_matches(']')
+}
+
+fn load_certs(path: &Path) -> crate::Result<Vec<Certificate>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let certs = rustls_pemfile::certs(&mut reader)?;
+    if certs.is_empty() {
+        return Err(format!("no certificate found in {}", path.display()).into());
+    }
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+fn load_key(path: &Path) -> crate::Result<PrivateKey> {
+    let mut reader = BufReader::new(File::open(path)?);
+    while let Some(item) = rustls_pemfile::read_one(&mut reader)? {
+        match item {
+            rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::ECKey(key) => return Ok(PrivateKey(key)),
+            _ => {}
+        }
+    }
+    Err(format!("no private key found in {}", path.display()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn server_name_from_address() {
+        assert_eq!(host("drasil-odin-service:6142"), "drasil-odin-service");
+        assert_eq!(host("127.0.0.1:6142"), "127.0.0.1");
+        assert_eq!(host("[::1]:6142"), "::1");
+        assert_eq!(host("odin"), "odin");
+
+        let config = TlsConfig::new("ca.pem", "cert.pem", "key.pem");
+        assert!(config.server_name("odin.local:6142").is_ok());
+        let config = config.with_server_name("odin.internal");
+        assert_eq!(
+            config.server_name("10.0.0.1:6142").unwrap(),
+            ServerName::try_from("odin.internal").unwrap()
+        );
+    }
+}
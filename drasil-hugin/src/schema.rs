@@ -111,6 +111,62 @@ table! {
     }
 }
 
+table! {
+    auctions (id) {
+        id -> Int8,
+        user_id -> Int8,
+        contract_id -> Int8,
+        kind -> Varchar,
+        policy_id -> Varchar,
+        tokenname -> Varchar,
+        seller_addr -> Varchar,
+        start_price -> Int8,
+        end_price -> Int8,
+        increment -> Int8,
+        deadline -> Timestamptz,
+        utxo -> Varchar,
+        highest_bid -> Nullable<Int8>,
+        highest_bidder -> Nullable<Varchar>,
+        status -> Varchar,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
+table! {
+    auction_events (id) {
+        id -> Int8,
+        auction_id -> Int8,
+        event -> Varchar,
+        txhash -> Varchar,
+        utxo -> Varchar,
+        highest_bid -> Nullable<Int8>,
+        highest_bidder -> Nullable<Varchar>,
+        status -> Varchar,
+        confirmed -> Bool,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    api_keys (id) {
+        id -> Int8,
+        user_id -> Int8,
+        key_id -> Varchar,
+        token_hash -> Varchar,
+        name -> Varchar,
+        contract_ids -> Array<Int8>,
+        permissions -> Array<Text>,
+        networks -> Array<Int8>,
+        origins -> Array<Text>,
+        ips -> Array<Text>,
+        expires_at -> Timestamptz,
+        revoked_at -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
 allow_tables_to_appear_in_same_query!(
     contracts,
     drasil_user,
@@ -119,4 +175,7 @@ allow_tables_to_appear_in_same_query!(
     email_verification_token,
     ca_payment,
     ca_payment_hash,
+    auctions,
+    auction_events,
+    api_keys,
 );
@@ -183,6 +183,33 @@ pub fn get_pool_total_stake(
     Ok(tot_stake)
 }
 
+/// get the total active stake of all pools in an epoch
+pub fn get_active_stake(conn: &mut PgConnection, epoch: i32) -> Result<u64, MimirError> {
+    let stake = epoch_stake::table
+        .filter(epoch_stake::epoch_no.eq(epoch))
+        .select(diesel::dsl::sum(epoch_stake::amount))
+        .first::<Option<BigDecimal>>(conn)?;
+
+    Ok(stake.and_then(|s| s.to_u64()).unwrap_or(0))
+}
+
+/// get the number of blocks a pool minted in an epoch
+pub fn get_pool_blocks(
+    conn: &mut PgConnection,
+    pool: &String,
+    epoch: i32,
+) -> Result<u64, MimirError> {
+    let blocks = block::table
+        .inner_join(slot_leader::table.on(slot_leader::id.eq(block::slot_leader_id)))
+        .inner_join(pool_hash::table.on(slot_leader::pool_hash_id.eq(pool_hash::id.nullable())))
+        .filter(pool_hash::view.eq(pool))
+        .filter(block::epoch_no.eq(epoch))
+        .count()
+        .get_result::<i64>(conn)?;
+
+    Ok(blocks as u64)
+}
+
 /// get current epoch number
 pub fn get_epoch(conn: &mut PgConnection) -> Result<i32, MimirError> {
     let epoch = epoch_stake::table
@@ -194,6 +221,35 @@ pub fn get_epoch(conn: &mut PgConnection) -> Result<i32, MimirError> {
     Ok(epoch)
 }
 
+/// get the protocol parameters of the latest epoch
+pub fn get_epoch_param(conn: &mut PgConnection) -> Result<EpochParam, MimirError> {
+    let params = epoch_param::table
+        .order(epoch_param::epoch_no.desc())
+        .first::<EpochParam>(conn)?;
+
+    Ok(params)
+}
+
+/// get the latest epoch for which protocol parameters exist
+pub fn get_epoch_param_epoch(conn: &mut PgConnection) -> Result<i32, MimirError> {
+    let epoch = epoch_param::table
+        .select(epoch_param::epoch_no)
+        .order(epoch_param::epoch_no.desc())
+        .first::<i32>(conn)?;
+
+    Ok(epoch)
+}
+
+/// get the cost models stored in a cost_model entry
+pub fn get_cost_model(conn: &mut PgConnection, id: i64) -> Result<serde_json::Value, MimirError> {
+    let costs = cost_model::table
+        .filter(cost_model::id.eq(id))
+        .select(cost_model::costs)
+        .first::<serde_json::Value>(conn)?;
+
+    Ok(costs)
+}
+
 /// Get fingerprint from dbsync for a given token
 pub fn get_fingerprint(
     conn: &mut PgConnection,
@@ -435,6 +491,27 @@ pub fn get_mint_metadata(fingerprint_in: &str) -> Result<TokenInfoMint, MimirErr
     })
 }
 
+/// Label 777 metadata of the first mint of the CIP-27 royalty token of a policy
+pub fn get_royalty_metadata(policy: &str) -> Result<Option<serde_json::Value>, MimirError> {
+    let metadata = ma_tx_mint::table
+        .inner_join(multi_asset::table.on(multi_asset::id.eq(ma_tx_mint::ident)))
+        .inner_join(tx_metadata::table.on(tx_metadata::tx_id.eq(ma_tx_mint::tx_id)))
+        .inner_join(tx::table.on(ma_tx_mint::tx_id.eq(tx::id)))
+        .inner_join(block::table.on(tx::block_id.eq(block::id)))
+        .filter(multi_asset::policy.eq(hex::decode(policy)?))
+        .filter(multi_asset::name.eq(Vec::<u8>::new()))
+        .filter(ma_tx_mint::quantity.gt(BigDecimal::from(0)))
+        .filter(tx_metadata::key.eq(BigDecimal::from(
+            drasil_murin::minter::cip27::ROYALTY_METADATA_LABEL,
+        )))
+        .order_by(block::slot_no.asc())
+        .select(tx_metadata::json.nullable())
+        .first::<Option<serde_json::Value>>(&mut crate::establish_connection()?)
+        .optional()?;
+
+    Ok(metadata.flatten())
+}
+
 /*
 pub fn lookup_mint_metadata_condition(
     policy  : &String,
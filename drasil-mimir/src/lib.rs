@@ -11,6 +11,11 @@ pub use error::MimirError;
 pub mod api;
 pub use api::*;
 
+pub mod provider;
+pub use provider::{
+    chain_data, init_protocol_parameter_source, set_chain_data_provider, ChainDataProvider,
+};
+
 use diesel::pg::PgConnection;
 use diesel::prelude::*;
 use dotenv::dotenv;
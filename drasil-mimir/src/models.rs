@@ -176,7 +176,7 @@ pub struct Datum {
     pub value: Option<Jsonb>,
 }
 
-#[derive(Queryable, Debug)]
+#[derive(Queryable, Debug, Clone)]
 pub struct DelegationView {
     pub stake_addr: String,
     pub amount: i64,
@@ -249,6 +249,14 @@ pub struct EpochParam {
     pub collateral_percent: Option<i32>,
     pub max_collateral_inputs: Option<i32>,
     pub block_id: i64,
+    pub coins_per_utxo_size: Option<BigDecimal>,
+    pub gov_action_lifetime: Option<BigDecimal>,
+    pub gov_action_deposit: Option<BigDecimal>,
+    pub drep_deposit: Option<BigDecimal>,
+    pub drep_activity: Option<BigDecimal>,
+    pub committee_min_size: Option<BigDecimal>,
+    pub committee_max_term_length: Option<BigDecimal>,
+    pub min_fee_ref_script_cost_per_byte: Option<f64>,
 }
 
 #[derive(Queryable, Debug)]
@@ -258,7 +266,7 @@ pub struct EpochRewardTotalReceived {
     pub amount: BigDecimal,
 }
 
-#[derive(Queryable, Debug)]
+#[derive(Queryable, Debug, Clone)]
 pub struct EpochStakeView {
     pub stake_addr: String,
     pub amount: BigDecimal,
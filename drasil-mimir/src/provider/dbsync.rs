@@ -0,0 +1,108 @@
+use drasil_murin::TransactionUnspentOutputs;
+
+use super::ChainDataProvider;
+use crate::{
+    api, establish_connection, DelegationView, EligableWallet, EpochStakeView, MimirError,
+    TokenInfo, TokenInfoMint,
+};
+
+/// Chain data provider backed by cardano-db-sync, connects via `DBSYNC_DB_URL`
+#[derive(Debug, Clone, Default)]
+pub struct DbSyncProvider;
+
+impl DbSyncProvider {
+    pub fn new() -> Self {
+        DbSyncProvider
+    }
+}
+
+impl ChainDataProvider for DbSyncProvider {
+    fn address_utxos(&self, addr: &str) -> Result<TransactionUnspentOutputs, MimirError> {
+        api::get_address_utxos(&addr.to_string())
+    }
+
+    fn stake_address_utxos(
+        &self,
+        stake_addr: &str,
+    ) -> Result<TransactionUnspentOutputs, MimirError> {
+        api::get_stake_address_utxos(&mut establish_connection()?, &stake_addr.to_string())
+    }
+
+    fn asset_utxos_on_addr(&self, addr: &str) -> Result<TransactionUnspentOutputs, MimirError> {
+        api::get_asset_utxos_on_addr(&mut establish_connection()?, &addr.to_string())
+    }
+
+    fn first_address_of_stake_address(&self, stake_addr: &str) -> Result<String, MimirError> {
+        api::select_addr_of_first_transaction(stake_addr)
+    }
+
+    fn epoch(&self) -> Result<i32, MimirError> {
+        api::get_epoch(&mut establish_connection()?)
+    }
+
+    fn slot(&self) -> Result<i64, MimirError> {
+        api::get_slot(&mut establish_connection()?)
+    }
+
+    fn stake_per_pool(&self, pool: &str, epoch: i32) -> Result<Vec<EpochStakeView>, MimirError> {
+        api::get_tot_stake_per_pool(&mut establish_connection()?, &pool.to_string(), epoch)
+    }
+
+    fn pool_total_stake(&self, pool: &str, epoch: i32) -> Result<u64, MimirError> {
+        api::get_pool_total_stake(&mut establish_connection()?, &pool.to_string(), epoch)
+    }
+
+    fn active_stake(&self, epoch: i32) -> Result<u64, MimirError> {
+        api::get_active_stake(&mut establish_connection()?, epoch)
+    }
+
+    fn pool_blocks(&self, pool: &str, epoch: i32) -> Result<u64, MimirError> {
+        api::get_pool_blocks(&mut establish_connection()?, &pool.to_string(), epoch)
+    }
+
+    fn delegations_per_pool(
+        &self,
+        pool: &str,
+        start_epoch: i64,
+        end_epoch: i64,
+    ) -> Result<Vec<DelegationView>, MimirError> {
+        api::get_delegations_per_pool_for_epochs(
+            &mut establish_connection()?,
+            &pool.to_string(),
+            start_epoch,
+            end_epoch,
+        )
+    }
+
+    fn pool_available(&self, pool_id: &str) -> Result<bool, MimirError> {
+        api::find_avail_pool(&pool_id.to_string())
+    }
+
+    fn stake_address_registered(&self, stake_addr: &str) -> Result<bool, MimirError> {
+        api::check_stakeaddr_registered(stake_addr)
+    }
+
+    fn token_holders(
+        &self,
+        fingerprint: &str,
+        min_amount: Option<&i64>,
+    ) -> Result<Vec<EligableWallet>, MimirError> {
+        api::lookup_token_holders(&fingerprint.to_string(), min_amount)
+    }
+
+    fn nft_token_holders(&self, policy: &str) -> Result<Vec<EligableWallet>, MimirError> {
+        api::lookup_nft_token_holders(&policy.to_string())
+    }
+
+    fn token_info(&self, fingerprint: &str) -> Result<TokenInfo, MimirError> {
+        api::get_token_info(&mut establish_connection()?, &fingerprint.to_string())
+    }
+
+    fn mint_metadata(&self, fingerprint: &str) -> Result<TokenInfoMint, MimirError> {
+        api::get_mint_metadata(fingerprint)
+    }
+
+    fn royalty_metadata(&self, policy: &str) -> Result<Option<serde_json::Value>, MimirError> {
+        api::get_royalty_metadata(policy)
+    }
+}
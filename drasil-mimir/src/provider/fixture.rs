@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+
+use drasil_murin::{TransactionUnspentOutput, TransactionUnspentOutputs};
+
+use super::ChainDataProvider;
+use crate::{DelegationView, EligableWallet, EpochStakeView, MimirError, TokenInfo, TokenInfoMint};
+
+/// In-memory chain data provider to run builders and jobs without dbsync
+///
+/// Unknown addresses resolve to empty UTxO sets and pools without stake to an
+/// empty snapshot, the same way dbsync answers for data it has never seen.
+#[derive(Debug, Clone, Default)]
+pub struct FixtureProvider {
+    epoch: Option<i32>,
+    slot: Option<i64>,
+    address_utxos: HashMap<String, TransactionUnspentOutputs>,
+    stake_address_utxos: HashMap<String, TransactionUnspentOutputs>,
+    first_addresses: HashMap<String, String>,
+    pool_stake: HashMap<(String, i32), Vec<EpochStakeView>>,
+    pool_blocks: HashMap<(String, i32), u64>,
+    delegations: HashMap<String, Vec<DelegationView>>,
+    pools: HashMap<String, bool>,
+    registered: HashMap<String, bool>,
+    token_holders: HashMap<String, Vec<EligableWallet>>,
+    nft_holders: HashMap<String, Vec<EligableWallet>>,
+    token_info: HashMap<String, TokenInfo>,
+    mint_metadata: HashMap<String, TokenInfoMint>,
+    royalties: HashMap<String, serde_json::Value>,
+}
+
+impl FixtureProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_tip(mut self, epoch: i32, slot: i64) -> Self {
+        self.epoch = Some(epoch);
+        self.slot = Some(slot);
+        self
+    }
+
+    pub fn with_address_utxo(mut self, addr: &str, utxo: &TransactionUnspentOutput) -> Self {
+        self.address_utxos
+            .entry(addr.to_string())
+            .or_default()
+            .add(utxo);
+        self
+    }
+
+    pub fn with_stake_address_utxo(
+        mut self,
+        stake_addr: &str,
+        utxo: &TransactionUnspentOutput,
+    ) -> Self {
+        self.stake_address_utxos
+            .entry(stake_addr.to_string())
+            .or_default()
+            .add(utxo);
+        self
+    }
+
+    pub fn with_first_address(mut self, stake_addr: &str, addr: &str) -> Self {
+        self.first_addresses
+            .insert(stake_addr.to_string(), addr.to_string());
+        self
+    }
+
+    pub fn with_pool_stake(mut self, pool: &str, epoch: i32, stake: Vec<EpochStakeView>) -> Self {
+        self.pools.entry(pool.to_string()).or_insert(true);
+        self.pool_stake.insert((pool.to_string(), epoch), stake);
+        self
+    }
+
+    pub fn with_pool_blocks(mut self, pool: &str, epoch: i32, blocks: u64) -> Self {
+        self.pools.entry(pool.to_string()).or_insert(true);
+        self.pool_blocks.insert((pool.to_string(), epoch), blocks);
+        self
+    }
+
+    pub fn with_delegation(mut self, pool: &str, delegation: DelegationView) -> Self {
+        self.pools.entry(pool.to_string()).or_insert(true);
+        self.delegations
+            .entry(pool.to_string())
+            .or_default()
+            .push(delegation);
+        self
+    }
+
+    pub fn with_retired_pool(mut self, pool: &str) -> Self {
+        self.pools.insert(pool.to_string(), false);
+        self
+    }
+
+    pub fn with_registration(mut self, stake_addr: &str, registered: bool) -> Self {
+        self.registered.insert(stake_addr.to_string(), registered);
+        self
+    }
+
+    pub fn with_token_holders(mut self, fingerprint: &str, holders: Vec<EligableWallet>) -> Self {
+        self.token_holders.insert(fingerprint.to_string(), holders);
+        self
+    }
+
+    pub fn with_nft_holders(mut self, policy: &str, holders: Vec<EligableWallet>) -> Self {
+        self.nft_holders.insert(policy.to_string(), holders);
+        self
+    }
+
+    pub fn with_token(mut self, info: TokenInfo, mint: Option<TokenInfoMint>) -> Self {
+        if let Some(m) = mint {
+            self.mint_metadata.insert(info.fingerprint.clone(), m);
+        }
+        self.token_info.insert(info.fingerprint.clone(), info);
+        self
+    }
+
+    pub fn with_royalty(mut self, policy: &str, metadata: serde_json::Value) -> Self {
+        self.royalties.insert(policy.to_string(), metadata);
+        self
+    }
+}
+
+impl ChainDataProvider for FixtureProvider {
+    fn address_utxos(&self, addr: &str) -> Result<TransactionUnspentOutputs, MimirError> {
+        Ok(self.address_utxos.get(addr).cloned().unwrap_or_default())
+    }
+
+    fn stake_address_utxos(
+        &self,
+        stake_addr: &str,
+    ) -> Result<TransactionUnspentOutputs, MimirError> {
+        Ok(self
+            .stake_address_utxos
+            .get(stake_addr)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn asset_utxos_on_addr(&self, addr: &str) -> Result<TransactionUnspentOutputs, MimirError> {
+        Ok(self.address_utxos(addr)?.get_token_only())
+    }
+
+    fn first_address_of_stake_address(&self, stake_addr: &str) -> Result<String, MimirError> {
+        self.first_addresses
+            .get(stake_addr)
+            .cloned()
+            .ok_or(MimirError::DieselError(diesel::result::Error::NotFound))
+    }
+
+    fn epoch(&self) -> Result<i32, MimirError> {
+        self.epoch
+            .ok_or_else(|| MimirError::Custom("fixture has no epoch set".to_string()))
+    }
+
+    fn slot(&self) -> Result<i64, MimirError> {
+        self.slot
+            .ok_or_else(|| MimirError::Custom("fixture has no slot set".to_string()))
+    }
+
+    fn stake_per_pool(&self, pool: &str, epoch: i32) -> Result<Vec<EpochStakeView>, MimirError> {
+        Ok(self
+            .pool_stake
+            .get(&(pool.to_string(), epoch))
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn pool_total_stake(&self, pool: &str, epoch: i32) -> Result<u64, MimirError> {
+        use bigdecimal::ToPrimitive;
+        Ok(self
+            .stake_per_pool(pool, epoch)?
+            .iter()
+            .map(|s| s.amount.to_u64().unwrap_or(0))
+            .sum())
+    }
+
+    fn active_stake(&self, epoch: i32) -> Result<u64, MimirError> {
+        use bigdecimal::ToPrimitive;
+        Ok(self
+            .pool_stake
+            .iter()
+            .filter(|((_, e), _)| *e == epoch)
+            .flat_map(|(_, stake)| stake.iter())
+            .map(|s| s.amount.to_u64().unwrap_or(0))
+            .sum())
+    }
+
+    fn pool_blocks(&self, pool: &str, epoch: i32) -> Result<u64, MimirError> {
+        Ok(self
+            .pool_blocks
+            .get(&(pool.to_string(), epoch))
+            .copied()
+            .unwrap_or(0))
+    }
+
+    fn delegations_per_pool(
+        &self,
+        pool: &str,
+        start_epoch: i64,
+        end_epoch: i64,
+    ) -> Result<Vec<DelegationView>, MimirError> {
+        let mut deleg = self.delegations.get(pool).cloned().unwrap_or_default();
+        deleg.retain(|d| d.active_epoch_no >= start_epoch && d.active_epoch_no <= end_epoch);
+        Ok(deleg)
+    }
+
+    fn pool_available(&self, pool_id: &str) -> Result<bool, MimirError> {
+        self.pools
+            .get(pool_id)
+            .copied()
+            .ok_or(MimirError::DieselError(diesel::result::Error::NotFound))
+    }
+
+    fn stake_address_registered(&self, stake_addr: &str) -> Result<bool, MimirError> {
+        Ok(self.registered.get(stake_addr).copied().unwrap_or(false))
+    }
+
+    fn token_holders(
+        &self,
+        fingerprint: &str,
+        min_amount: Option<&i64>,
+    ) -> Result<Vec<EligableWallet>, MimirError> {
+        let mut holders = self
+            .token_holders
+            .get(fingerprint)
+            .cloned()
+            .unwrap_or_default();
+        if let Some(min) = min_amount {
+            holders.retain(|h| h.hodl_amount as i64 >= *min);
+        }
+        Ok(holders)
+    }
+
+    fn nft_token_holders(&self, policy: &str) -> Result<Vec<EligableWallet>, MimirError> {
+        Ok(self.nft_holders.get(policy).cloned().unwrap_or_default())
+    }
+
+    fn token_info(&self, fingerprint: &str) -> Result<TokenInfo, MimirError> {
+        self.token_info
+            .get(fingerprint)
+            .cloned()
+            .ok_or(MimirError::DieselError(diesel::result::Error::NotFound))
+    }
+
+    fn mint_metadata(&self, fingerprint: &str) -> Result<TokenInfoMint, MimirError> {
+        self.mint_metadata
+            .get(fingerprint)
+            .cloned()
+            .ok_or(MimirError::NotOnChainMetadataFound)
+    }
+
+    fn royalty_metadata(&self, policy: &str) -> Result<Option<serde_json::Value>, MimirError> {
+        Ok(self.royalties.get(policy).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bigdecimal::BigDecimal;
+
+    #[test]
+    fn fixture_pool_stake() {
+        let provider = FixtureProvider::new()
+            .with_tip(420, 100_000)
+            .with_pool_stake(
+                "pool1",
+                418,
+                vec![
+                    EpochStakeView {
+                        stake_addr: "stake1".to_string(),
+                        amount: BigDecimal::from(5_000_000),
+                    },
+                    EpochStakeView {
+                        stake_addr: "stake2".to_string(),
+                        amount: BigDecimal::from(2_000_000),
+                    },
+                ],
+            );
+
+        assert_eq!(provider.epoch().unwrap(), 420);
+        assert_eq!(provider.slot().unwrap(), 100_000);
+        assert_eq!(provider.stake_per_pool("pool1", 418).unwrap().len(), 2);
+        assert!(provider.stake_per_pool("pool1", 419).unwrap().is_empty());
+        assert_eq!(provider.pool_total_stake("pool1", 418).unwrap(), 7_000_000);
+        assert!(provider.pool_available("pool1").unwrap());
+        assert!(provider.pool_available("pool2").is_err());
+    }
+
+    #[test]
+    fn fixture_token_holders() {
+        let holder = |s: &str, a: u64| EligableWallet {
+            stake_address: s.to_string(),
+            hodl_amount: a,
+            assetname: None,
+        };
+        let provider = FixtureProvider::new()
+            .with_token_holders("asset1", vec![holder("stake1", 10), holder("stake2", 1)]);
+
+        assert_eq!(provider.token_holders("asset1", None).unwrap().len(), 2);
+        assert_eq!(provider.token_holders("asset1", Some(&5)).unwrap().len(), 1);
+        assert!(provider.token_holders("asset2", None).unwrap().is_empty());
+        assert!(matches!(
+            provider.mint_metadata("asset1"),
+            Err(MimirError::NotOnChainMetadataFound)
+        ));
+    }
+}
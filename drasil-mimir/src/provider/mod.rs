@@ -0,0 +1,107 @@
+//! Chain data provider abstraction
+//!
+//! All chain queries needed by the transaction builders, handlers and jobs are
+//! expressed through the [`ChainDataProvider`] trait. The default backend is
+//! [`DbSyncProvider`] which queries cardano-db-sync, [`FixtureProvider`] keeps
+//! the data in memory and is meant for tests.
+use std::sync::{Arc, OnceLock, RwLock};
+
+use drasil_murin::TransactionUnspentOutputs;
+
+use crate::{DelegationView, EligableWallet, EpochStakeView, MimirError, TokenInfo, TokenInfoMint};
+
+pub mod dbsync;
+pub use dbsync::DbSyncProvider;
+pub mod fixture;
+pub use fixture::FixtureProvider;
+pub mod pparams;
+pub use pparams::{init_protocol_parameter_source, DbSyncProtocolParameters};
+
+pub trait ChainDataProvider: Send + Sync {
+    /// Unspent outputs sitting on the given address
+    fn address_utxos(&self, addr: &str) -> Result<TransactionUnspentOutputs, MimirError>;
+
+    /// Unspent outputs of all non-script addresses belonging to a stake address
+    fn stake_address_utxos(
+        &self,
+        stake_addr: &str,
+    ) -> Result<TransactionUnspentOutputs, MimirError>;
+
+    /// Unspent outputs on the given address holding native assets
+    fn asset_utxos_on_addr(&self, addr: &str) -> Result<TransactionUnspentOutputs, MimirError>;
+
+    /// First payment address which was seen on chain for a stake address
+    fn first_address_of_stake_address(&self, stake_addr: &str) -> Result<String, MimirError>;
+
+    /// Current epoch number
+    fn epoch(&self) -> Result<i32, MimirError>;
+
+    /// Current slot number
+    fn slot(&self) -> Result<i64, MimirError>;
+
+    /// Stake snapshot of each delegator of a pool in the given epoch
+    fn stake_per_pool(&self, pool: &str, epoch: i32) -> Result<Vec<EpochStakeView>, MimirError>;
+
+    /// Total active stake of a pool in the given epoch
+    fn pool_total_stake(&self, pool: &str, epoch: i32) -> Result<u64, MimirError>;
+
+    /// Total active stake of all pools in the given epoch
+    fn active_stake(&self, epoch: i32) -> Result<u64, MimirError>;
+
+    /// Number of blocks a pool minted in the given epoch
+    fn pool_blocks(&self, pool: &str, epoch: i32) -> Result<u64, MimirError>;
+
+    /// Delegations to a pool which became active within the epoch range
+    fn delegations_per_pool(
+        &self,
+        pool: &str,
+        start_epoch: i64,
+        end_epoch: i64,
+    ) -> Result<Vec<DelegationView>, MimirError>;
+
+    /// `true` if the pool is known and not retired
+    fn pool_available(&self, pool_id: &str) -> Result<bool, MimirError>;
+
+    /// `true` if the stake address is currently registered
+    fn stake_address_registered(&self, stake_addr: &str) -> Result<bool, MimirError>;
+
+    /// Stake addresses holding the token with the given fingerprint
+    fn token_holders(
+        &self,
+        fingerprint: &str,
+        min_amount: Option<&i64>,
+    ) -> Result<Vec<EligableWallet>, MimirError>;
+
+    /// Stake addresses holding any token of the given policy
+    fn nft_token_holders(&self, policy: &str) -> Result<Vec<EligableWallet>, MimirError>;
+
+    /// Policy and name of the token with the given fingerprint
+    fn token_info(&self, fingerprint: &str) -> Result<TokenInfo, MimirError>;
+
+    /// Metadata of the latest mint transaction of a token
+    fn mint_metadata(&self, fingerprint: &str) -> Result<TokenInfoMint, MimirError>;
+
+    /// CIP-27 royalty metadata of a policy, `None` if no royalty token was minted
+    fn royalty_metadata(&self, policy: &str) -> Result<Option<serde_json::Value>, MimirError>;
+}
+
+static PROVIDER: OnceLock<RwLock<Arc<dyn ChainDataProvider>>> = OnceLock::new();
+
+fn provider_slot() -> &'static RwLock<Arc<dyn ChainDataProvider>> {
+    PROVIDER.get_or_init(|| RwLock::new(Arc::new(DbSyncProvider::new())))
+}
+
+/// Returns the chain data provider used by this process, dbsync if none was set.
+pub fn chain_data() -> Arc<dyn ChainDataProvider> {
+    provider_slot()
+        .read()
+        .expect("chain data provider lock poisoned")
+        .clone()
+}
+
+/// Replaces the chain data provider used by this process.
+pub fn set_chain_data_provider(provider: Arc<dyn ChainDataProvider>) {
+    *provider_slot()
+        .write()
+        .expect("chain data provider lock poisoned") = provider;
+}
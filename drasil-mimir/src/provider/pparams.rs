@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use bigdecimal::{BigDecimal, ToPrimitive};
+use drasil_murin::pparams::{
+    set_protocol_parameter_source, CachedSource, CostModels, ExUnitPrice, FileSource,
+    MaxBlockExUnits, MaxTxExUnits, ProtocolParameterSource, ProtocolParameters, ProtocolVersion,
+};
+use drasil_murin::MurinError;
+
+use crate::{api, establish_connection, EpochParam, MimirError};
+
+/// `file` (default) or `dbsync`
+pub const PROTOCOL_PARAMETER_SOURCE: &str = "CARDANO_PROTOCOL_PARAMETER_SOURCE";
+
+/// Protocol parameters of the latest epoch from the dbsync `epoch_param` table
+#[derive(Debug, Clone, Default)]
+pub struct DbSyncProtocolParameters;
+
+impl DbSyncProtocolParameters {
+    pub fn new() -> Self {
+        DbSyncProtocolParameters
+    }
+
+    fn load(&self) -> Result<ProtocolParameters, MimirError> {
+        let conn = &mut establish_connection()?;
+        let ep = api::get_epoch_param(conn)?;
+        let cost_model_id = ep.cost_model_id.ok_or_else(|| {
+            MimirError::Custom(format!("epoch {} has no cost models", ep.epoch_no))
+        })?;
+        let cost_models = CostModels::from_json(&api::get_cost_model(conn, cost_model_id)?)?;
+        to_protocol_parameters(&ep, cost_models)
+    }
+}
+
+impl ProtocolParameterSource for DbSyncProtocolParameters {
+    fn protocol_parameters(&self) -> Result<ProtocolParameters, MurinError> {
+        self.load()
+            .map_err(|e| MurinError::new(&format!("could not load protocol parameters: {e:?}")))
+    }
+
+    fn current_epoch(&self) -> Result<Option<i64>, MurinError> {
+        establish_connection()
+            .and_then(|mut conn| api::get_epoch_param_epoch(&mut conn))
+            .map(|e| Some(e as i64))
+            .map_err(|e| MurinError::new(&format!("could not query current epoch: {e:?}")))
+    }
+}
+
+fn num(field: &str, value: &Option<BigDecimal>) -> Result<u64, MimirError> {
+    value
+        .as_ref()
+        .and_then(|v| v.to_u64())
+        .ok_or_else(|| MimirError::Custom(format!("protocol parameter '{field}' is not set")))
+}
+
+fn opt_num(value: &Option<BigDecimal>) -> Option<u64> {
+    value.as_ref().and_then(|v| v.to_u64())
+}
+
+fn to_protocol_parameters(
+    ep: &EpochParam,
+    cost_models: CostModels,
+) -> Result<ProtocolParameters, MimirError> {
+    // before Babbage the utxo cost was given per 8 byte word
+    let utxo_cost_per_byte = match opt_num(&ep.coins_per_utxo_size) {
+        Some(c) => c,
+        None => num("coins_per_utxo_word", &ep.coins_per_utxo_word)? / 8,
+    };
+    let price = |field: &str, v: Option<f64>| {
+        v.ok_or_else(|| MimirError::Custom(format!("protocol parameter '{field}' is not set")))
+    };
+
+    Ok(ProtocolParameters {
+        utxo_cost_per_byte,
+        tx_fee_per_byte: ep.min_fee_a as u64,
+        tx_fee_fixed: ep.min_fee_b as u64,
+        treasury_cut: ep.treasury_growth_rate as f32,
+        stake_pool_target_num: ep.optimal_pool_count as u64,
+        stake_pool_deposit: ep.pool_deposit.to_i128().unwrap_or_default(),
+        stake_address_deposit: ep.key_deposit.to_u64().unwrap_or_default(),
+        protocol_version: ProtocolVersion {
+            major: ep.protocol_major as u8,
+            minor: ep.protocol_minor as u8,
+        },
+        pool_retire_max_epoch: ep.max_epoch,
+        pool_pledge_influence: ep.influence as f32,
+        monetary_expansion: ep.monetary_expand_rate as f32,
+        min_pool_cost: ep.min_pool_cost.to_i128().unwrap_or_default(),
+        max_value_size: num("max_val_size", &ep.max_val_size)?,
+        max_tx_size: ep.max_tx_size as u64,
+        max_tx_execution_units: MaxTxExUnits {
+            memory: num("max_tx_ex_mem", &ep.max_tx_ex_mem)? as f64,
+            steps: num("max_tx_ex_steps", &ep.max_tx_ex_steps)? as f64,
+        },
+        max_block_header_size: ep.max_bh_size as u64,
+        max_block_execution_units: MaxBlockExUnits {
+            memory: num("max_block_ex_mem", &ep.max_block_ex_mem)? as f64,
+            steps: num("max_block_ex_steps", &ep.max_block_ex_steps)? as f64,
+        },
+        max_block_body_size: ep.max_block_size as u64,
+        execution_unit_prices: ExUnitPrice {
+            priceSteps: price("price_step", ep.price_step)?,
+            priceMemory: price("price_mem", ep.price_mem)?,
+        },
+        collateral_percentage: ep.collateral_percent.unwrap_or_default() as u64,
+        max_collateral_inputs: ep.max_collateral_inputs.unwrap_or_default(),
+        cost_models,
+        min_fee_ref_script_cost_per_byte: ep.min_fee_ref_script_cost_per_byte,
+        drep_deposit: opt_num(&ep.drep_deposit),
+        drep_activity: opt_num(&ep.drep_activity),
+        gov_action_deposit: opt_num(&ep.gov_action_deposit),
+        gov_action_lifetime: opt_num(&ep.gov_action_lifetime),
+        committee_min_size: opt_num(&ep.committee_min_size),
+        committee_max_term_length: opt_num(&ep.committee_max_term_length),
+    })
+}
+
+/// Builds the protocol parameter source selected by `CARDANO_PROTOCOL_PARAMETER_SOURCE`
+pub fn protocol_parameter_source_from_env() -> Arc<dyn ProtocolParameterSource> {
+    match std::env::var(PROTOCOL_PARAMETER_SOURCE).as_deref() {
+        Ok("dbsync") => Arc::new(CachedSource::new(DbSyncProtocolParameters::new())),
+        _ => Arc::new(CachedSource::new(FileSource::from_env())),
+    }
+}
+
+/// Sets the protocol parameter source of this process from the environment
+pub fn init_protocol_parameter_source() {
+    set_protocol_parameter_source(protocol_parameter_source_from_env())
+}
@@ -167,6 +167,14 @@ table! {
         collateral_percent -> Nullable<Int4>,
         max_collateral_inputs -> Nullable<Int4>,
         block_id -> Int8,
+        coins_per_utxo_size -> Nullable<Numeric>,
+        gov_action_lifetime -> Nullable<Numeric>,
+        gov_action_deposit -> Nullable<Numeric>,
+        drep_deposit -> Nullable<Numeric>,
+        drep_activity -> Nullable<Numeric>,
+        committee_min_size -> Nullable<Numeric>,
+        committee_max_term_length -> Nullable<Numeric>,
+        min_fee_ref_script_cost_per_byte -> Nullable<Float8>,
     }
 }
 
@@ -0,0 +1,541 @@
+//! Conway governance types
+//!
+//! cardano-serialization-lib 11 does not know the Conway certificates and the
+//! voting procedures of a transaction body. Similar to `pparams::mp_plutus` we
+//! encode them ourselves and splice them into the serialized body built by the
+//! library, see [`ConwayBodyExtension`].
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use bech32::FromBase32;
+use cardano_serialization_lib as clib;
+use cbor_event::{se::Serializer, Len, Special};
+use clib::address::StakeCredential;
+use clib::crypto::{Ed25519KeyHash, ScriptHash, TransactionHash};
+
+use super::models::BuildOutput;
+use crate::pparams::hash::{blake2b224, blake2b256};
+use crate::MurinError;
+
+// Conway transaction body keys
+const BODY_KEY_CERTIFICATES: u64 = 4;
+const BODY_KEY_VOTING_PROCEDURES: u64 = 19;
+
+/// Off-chain metadata reference of a DRep or a vote
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Anchor {
+    pub url: String,
+    pub data_hash: Vec<u8>,
+}
+
+impl Anchor {
+    pub fn new(url: &str, data_hash: &str) -> Result<Anchor, MurinError> {
+        if url.len() > 128 {
+            return Err(MurinError::new("anchor url exceeds 128 bytes"));
+        }
+        let data_hash = hex::decode(data_hash)?;
+        if data_hash.len() != 32 {
+            return Err(MurinError::new("anchor data hash must be 32 bytes"));
+        }
+        Ok(Anchor {
+            url: url.to_string(),
+            data_hash,
+        })
+    }
+
+    fn serialize(&self, s: &mut Serializer<Vec<u8>>) -> cbor_event::Result<()> {
+        s.write_array(Len::Len(2))?;
+        s.write_text(&self.url)?;
+        s.write_bytes(&self.data_hash)?;
+        Ok(())
+    }
+}
+
+fn serialize_optional_anchor(
+    anchor: &Option<Anchor>,
+    s: &mut Serializer<Vec<u8>>,
+) -> cbor_event::Result<()> {
+    match anchor {
+        Some(a) => a.serialize(s),
+        None => s.write_special(Special::Null).map(|_| ()),
+    }
+}
+
+/// Target of a vote delegation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DRep {
+    KeyHash(Ed25519KeyHash),
+    ScriptHash(ScriptHash),
+    AlwaysAbstain,
+    AlwaysNoConfidence,
+}
+
+impl DRep {
+    fn serialize(&self, s: &mut Serializer<Vec<u8>>) -> cbor_event::Result<()> {
+        match self {
+            DRep::KeyHash(kh) => {
+                s.write_array(Len::Len(2))?;
+                s.write_unsigned_integer(0)?;
+                s.write_bytes(kh.to_bytes())?;
+            }
+            DRep::ScriptHash(sh) => {
+                s.write_array(Len::Len(2))?;
+                s.write_unsigned_integer(1)?;
+                s.write_bytes(sh.to_bytes())?;
+            }
+            DRep::AlwaysAbstain => {
+                s.write_array(Len::Len(1))?;
+                s.write_unsigned_integer(2)?;
+            }
+            DRep::AlwaysNoConfidence => {
+                s.write_array(Len::Len(1))?;
+                s.write_unsigned_integer(3)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses `always_abstain`, `always_no_confidence`, a DRep id in bech32 (CIP-105 and CIP-129)
+/// or a hex encoded key hash
+impl FromStr for DRep {
+    type Err = MurinError;
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        match src {
+            "always_abstain" | "abstain" => return Ok(DRep::AlwaysAbstain),
+            "always_no_confidence" | "no_confidence" => return Ok(DRep::AlwaysNoConfidence),
+            _ => (),
+        }
+        if let Ok(bytes) = hex::decode(src) {
+            return Ok(DRep::KeyHash(Ed25519KeyHash::from_bytes(bytes)?));
+        }
+        let (hrp, bytes) = decode_bech32(src)?;
+        match (hrp.as_str(), bytes.len()) {
+            ("drep", 28) => Ok(DRep::KeyHash(Ed25519KeyHash::from_bytes(bytes)?)),
+            ("drep_script", 28) => Ok(DRep::ScriptHash(ScriptHash::from_bytes(bytes)?)),
+            ("drep", 29) if bytes[0] == 0x22 => Ok(DRep::KeyHash(Ed25519KeyHash::from_bytes(
+                bytes[1..].to_vec(),
+            )?)),
+            ("drep", 29) if bytes[0] == 0x23 => Ok(DRep::ScriptHash(ScriptHash::from_bytes(
+                bytes[1..].to_vec(),
+            )?)),
+            _ => Err(MurinError::new(&format!("invalid DRep id: {src}"))),
+        }
+    }
+}
+
+fn decode_bech32(src: &str) -> Result<(String, Vec<u8>), MurinError> {
+    let b = bech32::Bech32::from_str(src)
+        .map_err(|e| MurinError::new(&format!("invalid bech32 string '{src}': {e}")))?;
+    let bytes = Vec::<u8>::from_base32(b.data())
+        .map_err(|e| MurinError::new(&format!("invalid bech32 data '{src}': {e}")))?;
+    Ok((b.hrp().to_string(), bytes))
+}
+
+/// Key hash of a DRep credential given as DRep id, hex key hash or hex public key (CIP-95 `getPubDRepKey`)
+pub fn drep_key_hash(src: &str) -> Result<Ed25519KeyHash, MurinError> {
+    if let Ok(bytes) = hex::decode(src) {
+        return match bytes.len() {
+            32 => Ok(Ed25519KeyHash::from_bytes(blake2b224(&bytes).to_vec())?),
+            _ => Ok(Ed25519KeyHash::from_bytes(bytes)?),
+        };
+    }
+    match DRep::from_str(src)? {
+        DRep::KeyHash(kh) => Ok(kh),
+        _ => Err(MurinError::new("a DRep key credential is required")),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Vote {
+    No = 0,
+    Yes = 1,
+    Abstain = 2,
+}
+
+impl FromStr for Vote {
+    type Err = MurinError;
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        match src.to_lowercase().as_str() {
+            "no" => Ok(Vote::No),
+            "yes" => Ok(Vote::Yes),
+            "abstain" => Ok(Vote::Abstain),
+            _ => Err(MurinError::new(&format!("invalid vote: {src}"))),
+        }
+    }
+}
+
+/// Governance action identified by the proposing transaction and the proposal index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GovActionId {
+    pub transaction_id: TransactionHash,
+    pub index: u32,
+}
+
+impl GovActionId {
+    fn serialize(&self, s: &mut Serializer<Vec<u8>>) -> cbor_event::Result<()> {
+        s.write_array(Len::Len(2))?;
+        s.write_bytes(self.transaction_id.to_bytes())?;
+        s.write_unsigned_integer(self.index as u64)?;
+        Ok(())
+    }
+}
+
+/// Parses `<transaction hash>#<index>`
+impl FromStr for GovActionId {
+    type Err = MurinError;
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let (tx, idx) = src
+            .split_once('#')
+            .ok_or_else(|| MurinError::new("governance action id must be '<txhash>#<index>'"))?;
+        Ok(GovActionId {
+            transaction_id: TransactionHash::from_bytes(hex::decode(tx)?)?,
+            index: idx.parse::<u32>()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Voter {
+    DRepKeyHash(Ed25519KeyHash),
+    StakePool(Ed25519KeyHash),
+}
+
+impl Voter {
+    fn to_bytes(&self) -> Vec<u8> {
+        let (tag, kh) = match self {
+            Voter::DRepKeyHash(kh) => (2, kh),
+            Voter::StakePool(kh) => (4, kh),
+        };
+        let mut s = Serializer::new_vec();
+        s.write_array(Len::Len(2)).unwrap();
+        s.write_unsigned_integer(tag).unwrap();
+        s.write_bytes(kh.to_bytes()).unwrap();
+        s.finalize()
+    }
+
+    pub fn key_hash(&self) -> Ed25519KeyHash {
+        match self {
+            Voter::DRepKeyHash(kh) | Voter::StakePool(kh) => kh.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VotingProcedure {
+    pub vote: Vote,
+    pub anchor: Option<Anchor>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConwayCertificate {
+    VoteDelegation {
+        stake_credential: StakeCredential,
+        drep: DRep,
+    },
+    /// Registers the stake credential and delegates its votes (`vote_reg_deleg_cert`)
+    VoteRegistrationDelegation {
+        stake_credential: StakeCredential,
+        drep: DRep,
+        deposit: u64,
+    },
+    DRepRegistration {
+        drep_credential: StakeCredential,
+        deposit: u64,
+        anchor: Option<Anchor>,
+    },
+    DRepDeregistration {
+        drep_credential: StakeCredential,
+        refund: u64,
+    },
+    DRepUpdate {
+        drep_credential: StakeCredential,
+        anchor: Option<Anchor>,
+    },
+}
+
+impl ConwayCertificate {
+    fn serialize(&self, s: &mut Serializer<Vec<u8>>) -> cbor_event::Result<()> {
+        match self {
+            ConwayCertificate::VoteDelegation {
+                stake_credential,
+                drep,
+            } => {
+                s.write_array(Len::Len(3))?;
+                s.write_unsigned_integer(9)?;
+                s.write_raw_bytes(&stake_credential.to_bytes())?;
+                drep.serialize(s)?;
+            }
+            ConwayCertificate::VoteRegistrationDelegation {
+                stake_credential,
+                drep,
+                deposit,
+            } => {
+                s.write_array(Len::Len(4))?;
+                s.write_unsigned_integer(12)?;
+                s.write_raw_bytes(&stake_credential.to_bytes())?;
+                drep.serialize(s)?;
+                s.write_unsigned_integer(*deposit)?;
+            }
+            ConwayCertificate::DRepRegistration {
+                drep_credential,
+                deposit,
+                anchor,
+            } => {
+                s.write_array(Len::Len(4))?;
+                s.write_unsigned_integer(16)?;
+                s.write_raw_bytes(&drep_credential.to_bytes())?;
+                s.write_unsigned_integer(*deposit)?;
+                serialize_optional_anchor(anchor, s)?;
+            }
+            ConwayCertificate::DRepDeregistration {
+                drep_credential,
+                refund,
+            } => {
+                s.write_array(Len::Len(3))?;
+                s.write_unsigned_integer(17)?;
+                s.write_raw_bytes(&drep_credential.to_bytes())?;
+                s.write_unsigned_integer(*refund)?;
+            }
+            ConwayCertificate::DRepUpdate {
+                drep_credential,
+                anchor,
+            } => {
+                s.write_array(Len::Len(3))?;
+                s.write_unsigned_integer(18)?;
+                s.write_raw_bytes(&drep_credential.to_bytes())?;
+                serialize_optional_anchor(anchor, s)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Conway certificates and votes which are added to a transaction body built by cardano-serialization-lib
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConwayBodyExtension {
+    certificates: Vec<ConwayCertificate>,
+    votes: Vec<(Voter, GovActionId, VotingProcedure)>,
+}
+
+impl ConwayBodyExtension {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_certificate(&mut self, cert: ConwayCertificate) {
+        self.certificates.push(cert);
+    }
+
+    pub fn add_vote(&mut self, voter: Voter, action: GovActionId, procedure: VotingProcedure) {
+        self.votes.push((voter, action, procedure));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.certificates.is_empty() && self.votes.is_empty()
+    }
+
+    fn entries(&self) -> Result<(u64, Vec<u8>), MurinError> {
+        let mut s = Serializer::new_vec();
+        let mut n = 0;
+        if !self.certificates.is_empty() {
+            s.write_unsigned_integer(BODY_KEY_CERTIFICATES)?;
+            s.write_array(Len::Len(self.certificates.len() as u64))?;
+            for cert in &self.certificates {
+                cert.serialize(&mut s)?;
+            }
+            n += 1;
+        }
+        if !self.votes.is_empty() {
+            let mut by_voter = BTreeMap::<Vec<u8>, Vec<(&GovActionId, &VotingProcedure)>>::new();
+            for (voter, action, procedure) in &self.votes {
+                by_voter
+                    .entry(voter.to_bytes())
+                    .or_default()
+                    .push((action, procedure));
+            }
+            s.write_unsigned_integer(BODY_KEY_VOTING_PROCEDURES)?;
+            s.write_map(Len::Len(by_voter.len() as u64))?;
+            for (voter, procedures) in by_voter {
+                s.write_raw_bytes(&voter)?;
+                s.write_map(Len::Len(procedures.len() as u64))?;
+                for (action, procedure) in procedures {
+                    action.serialize(&mut s)?;
+                    s.write_array(Len::Len(2))?;
+                    s.write_unsigned_integer(procedure.vote as u64)?;
+                    serialize_optional_anchor(&procedure.anchor, &mut s)?;
+                }
+            }
+            n += 1;
+        }
+        Ok((n, s.finalize()))
+    }
+
+    /// Number of bytes the extension adds to a transaction, used for the fee calculation
+    pub fn size(&self) -> Result<usize, MurinError> {
+        // +2 in case the map header of the body grows
+        Ok(self.entries()?.1.len() + 2)
+    }
+
+    /// Serializes the body with the Conway certificates and voting procedures added
+    pub fn extend_body(&self, txbody: &clib::TransactionBody) -> Result<Vec<u8>, MurinError> {
+        if !self.certificates.is_empty() && txbody.certs().is_some() {
+            return Err(MurinError::new(
+                "certificates must either be set on the body or in the Conway extension",
+            ));
+        }
+        let body = txbody.to_bytes();
+        let (len, header_size) = map_header(&body)?;
+        let (n, entries) = self.entries()?;
+
+        let mut s = Serializer::new_vec();
+        s.write_map(Len::Len(len + n))?;
+        s.write_raw_bytes(&body[header_size..])?;
+        s.write_raw_bytes(&entries)?;
+        Ok(s.finalize())
+    }
+
+    /// Like `supporting_functions::tx_output_data` for a body with Conway extensions
+    pub fn tx_output_data(
+        &self,
+        txbody: clib::TransactionBody,
+        txwitness: clib::TransactionWitnessSet,
+        aux_data: Option<clib::metadata::AuxiliaryData>,
+        used_utxos: String,
+        royalties: u64,
+        internal: bool,
+    ) -> Result<BuildOutput, MurinError> {
+        let body = self.extend_body(&txbody)?;
+        let tx = assemble_transaction(&body, &txwitness, aux_data.as_ref())?;
+
+        let hex_aux = match &aux_data {
+            Some(aux) => hex::encode(aux.to_bytes()),
+            None => hex::encode(clib::metadata::AuxiliaryData::new().to_bytes()),
+        };
+
+        Ok(BuildOutput {
+            tx_witness: hex::encode(txwitness.to_bytes()),
+            metadata: hex_aux,
+            tx_body: hex::encode(body),
+            tx_unsigned: hex::encode(tx),
+            used_utxos,
+            royalties,
+            internal_transfer: internal.to_string(),
+        })
+    }
+}
+
+// returns the number of entries and the size of the header of a definite length map
+fn map_header(bytes: &[u8]) -> Result<(u64, usize), MurinError> {
+    let first = *bytes
+        .first()
+        .ok_or_else(|| MurinError::new("empty transaction body"))?;
+    if first >> 5 != 5 {
+        return Err(MurinError::new("transaction body is not a map"));
+    }
+    let size = match first & 0x1f {
+        n @ 0..=23 => return Ok((n as u64, 1)),
+        24 => 1,
+        25 => 2,
+        26 => 4,
+        27 => 8,
+        _ => {
+            return Err(MurinError::new(
+                "indefinite transaction body is not supported",
+            ))
+        }
+    };
+    if bytes.len() <= size {
+        return Err(MurinError::new("invalid transaction body"));
+    }
+    let len = bytes[1..=size]
+        .iter()
+        .fold(0u64, |acc, b| (acc << 8) | *b as u64);
+    Ok((len, size + 1))
+}
+
+/// Assembles a transaction from a serialized body
+pub fn assemble_transaction(
+    body: &[u8],
+    witness: &clib::TransactionWitnessSet,
+    aux_data: Option<&clib::metadata::AuxiliaryData>,
+) -> Result<Vec<u8>, MurinError> {
+    let mut s = Serializer::new_vec();
+    s.write_array(Len::Len(4))?;
+    s.write_raw_bytes(body)?;
+    s.write_raw_bytes(&witness.to_bytes())?;
+    s.write_special(Special::Bool(true))?;
+    match aux_data {
+        Some(aux) => s.write_raw_bytes(&aux.to_bytes())?,
+        None => s.write_special(Special::Null)?,
+    };
+    Ok(s.finalize())
+}
+
+/// Transaction id of a serialized body
+pub fn hash_body(body: &[u8]) -> TransactionHash {
+    TransactionHash::from(blake2b256(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clib::utils::to_bignum;
+
+    fn key_hash() -> Ed25519KeyHash {
+        Ed25519KeyHash::from_bytes(vec![7; 28]).unwrap()
+    }
+
+    #[test]
+    fn parse_drep() {
+        assert_eq!(
+            DRep::from_str("always_abstain").unwrap(),
+            DRep::AlwaysAbstain
+        );
+        assert_eq!(
+            DRep::from_str(&hex::encode([7; 28])).unwrap(),
+            DRep::KeyHash(key_hash())
+        );
+        assert_eq!(drep_key_hash(&hex::encode([7; 28])).unwrap(), key_hash());
+        assert!(DRep::from_str("pool1abc").is_err());
+        assert_eq!(
+            GovActionId::from_str(&format!("{}#3", hex::encode([1; 32])))
+                .unwrap()
+                .index,
+            3
+        );
+    }
+
+    #[test]
+    fn extend_body() {
+        let body = clib::TransactionBody::new_tx_body(
+            &clib::TransactionInputs::new(),
+            &clib::TransactionOutputs::new(),
+            &to_bignum(200000),
+        );
+        let mut ext = ConwayBodyExtension::new();
+        ext.add_certificate(ConwayCertificate::VoteDelegation {
+            stake_credential: StakeCredential::from_keyhash(&key_hash()),
+            drep: DRep::AlwaysAbstain,
+        });
+        ext.add_vote(
+            Voter::DRepKeyHash(key_hash()),
+            GovActionId {
+                transaction_id: TransactionHash::from([1; 32]),
+                index: 0,
+            },
+            VotingProcedure {
+                vote: Vote::Yes,
+                anchor: None,
+            },
+        );
+
+        let extended = ext.extend_body(&body).unwrap();
+        let original = body.to_bytes();
+        assert_eq!(map_header(&original).unwrap(), (3, 1));
+        assert_eq!(map_header(&extended).unwrap(), (5, 1));
+        assert_eq!(&extended[1..original.len()], &original[1..]);
+        assert_eq!(extended.len(), original.len() + ext.size().unwrap() - 2);
+        assert!(ConwayBodyExtension::new().is_empty());
+    }
+}
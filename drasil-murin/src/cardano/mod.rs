@@ -1,13 +1,17 @@
 #![allow(non_snake_case)]
 pub mod cip30;
+pub mod conway;
 pub mod models;
 pub mod pparams;
+pub mod slots;
 pub mod supporting_functions;
 
 pub use crate::MurinError;
 use bech32::{self, ToBase32};
 use cryptoxide::{blake2b::Blake2b, digest::Digest};
 pub use models::*;
+pub use pparams::hash::blake2b256;
+pub use slots::{CardanoNetwork, SlotConfig};
 pub use supporting_functions::*;
 
 use cardano_serialization_lib as clib;
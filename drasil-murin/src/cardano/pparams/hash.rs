@@ -10,7 +10,7 @@ pub(crate) fn blake2b224(data: &[u8]) -> [u8; 28] {
     out
 }
 
-pub(crate) fn blake2b256(data: &[u8]) -> [u8; 32] {
+pub fn blake2b256(data: &[u8]) -> [u8; 32] {
     let mut out = [0; 32];
     Blake2b::blake2b(&mut out, data, &[]);
     out
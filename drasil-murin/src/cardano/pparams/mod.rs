@@ -2,6 +2,11 @@
 pub(crate) mod binary;
 pub(crate) mod hash;
 pub(crate) mod mp_plutus;
+pub mod source;
+pub use source::{
+    protocol_parameter_source, protocol_parameters, set_protocol_parameter_source, CachedSource,
+    FileSource, ProtocolParameterSource,
+};
 
 use crate::pparams::mp_plutus::{CostModel as ClibCostModel, Costmdls, Language};
 use crate::MurinError;
@@ -9,10 +14,20 @@ use cardano_serialization_lib::utils;
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 pub struct CostModels {
-    #[serde(alias = "PlutusScriptV1")]
+    #[serde(alias = "PlutusScriptV1", alias = "PlutusV1")]
     plutus_v1: CostModel,
-    #[serde(alias = "PlutusScriptV2")]
+    #[serde(alias = "PlutusScriptV2", alias = "PlutusV2")]
     plutus_v2: CostModel,
+    #[serde(alias = "PlutusScriptV3", alias = "PlutusV3", default)]
+    plutus_v3: Option<CostModel>,
+}
+
+impl CostModels {
+    /// Parses the cost models from their JSON representation, either the
+    /// cardano-cli format or the `costs` column of the dbsync `cost_model` table
+    pub fn from_json(value: &serde_json::Value) -> Result<CostModels, MurinError> {
+        Ok(serde_json::from_value(value.clone())?)
+    }
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
@@ -21,31 +36,42 @@ pub struct CostModel(serde_json::Value);
 //#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
 //pub struct CostModels(HashMap<ScriptType, CostModel>);
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
 pub struct ExUnitPrice {
     pub priceSteps: f64,
     pub priceMemory: f64,
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
 pub struct MaxTxExUnits {
     pub memory: f64,
     pub steps: f64,
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
 pub struct MaxBlockExUnits {
     pub memory: f64,
     pub steps: f64,
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
 pub struct ProtocolVersion {
-    major: u8,
-    minor: u8,
+    pub major: u8,
+    pub minor: u8,
+}
+
+/// Ledger era derived from the major protocol version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Era {
+    Alonzo,
+    Babbage,
+    Conway,
 }
 
-#[derive(Debug, serde::Serialize, serde::Deserialize)]
+// Conway reference script fees grow by 6/5 for each started tier of 25 KiB
+const REF_SCRIPT_FEE_TIER_SIZE: usize = 25_600;
+
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
 pub struct ProtocolParameters {
     #[serde(alias = "utxoCostPerByte")]
     pub utxo_cost_per_byte: u64,
@@ -91,6 +117,21 @@ pub struct ProtocolParameters {
     pub max_collateral_inputs: i32,
     #[serde(alias = "costModels")]
     pub cost_models: CostModels,
+    // Conway
+    #[serde(alias = "minFeeRefScriptCostPerByte", default)]
+    pub min_fee_ref_script_cost_per_byte: Option<f64>,
+    #[serde(alias = "dRepDeposit", default)]
+    pub drep_deposit: Option<u64>,
+    #[serde(alias = "dRepActivity", default)]
+    pub drep_activity: Option<u64>,
+    #[serde(alias = "govActionDeposit", default)]
+    pub gov_action_deposit: Option<u64>,
+    #[serde(alias = "govActionLifetime", default)]
+    pub gov_action_lifetime: Option<u64>,
+    #[serde(alias = "committeeMinSize", default)]
+    pub committee_min_size: Option<u64>,
+    #[serde(alias = "committeeMaxTermLength", default)]
+    pub committee_max_term_length: Option<u64>,
 }
 
 impl ProtocolParameters {
@@ -102,9 +143,36 @@ impl ProtocolParameters {
         ////////////////////////////////////////////////////////////////////////////////////////////////////////////////
         // Read txFeePerByte from Protocol Parameters JSON
         let pp_data = std::fs::read_to_string(std::path::PathBuf::from(path))?;
-        let pp: ProtocolParameters = serde_json::from_str(&pp_data).unwrap();
+        ProtocolParameters::from_json(&pp_data)
+    }
+
+    pub fn from_json(json: &str) -> Result<ProtocolParameters, MurinError> {
+        Ok(serde_json::from_str(json)?)
+    }
 
-        Ok(pp)
+    pub fn era(&self) -> Era {
+        match self.protocol_version.major {
+            0..=6 => Era::Alonzo,
+            7 | 8 => Era::Babbage,
+            _ => Era::Conway,
+        }
+    }
+
+    /// Fee for the reference scripts used by a transaction, zero before Conway
+    pub fn ref_script_fee(&self, ref_script_size: usize) -> u64 {
+        let mut price = match self.min_fee_ref_script_cost_per_byte {
+            Some(p) if self.era() >= Era::Conway => p,
+            _ => return 0,
+        };
+        let mut fee = 0f64;
+        let mut remaining = ref_script_size;
+        while remaining > 0 {
+            let chunk = remaining.min(REF_SCRIPT_FEE_TIER_SIZE);
+            fee += chunk as f64 * price;
+            remaining -= chunk;
+            price = price * 6.0 / 5.0;
+        }
+        fee.floor() as u64
     }
 
     pub fn get_CostMdls(&self) -> Result<Costmdls, MurinError> {
@@ -112,42 +180,118 @@ impl ProtocolParameters {
 
         let mut v1 = ClibCostModel::empty_model(&Language::new_plutus_v1());
         let mut v2 = ClibCostModel::empty_model(&Language::new_plutus_v2());
-        if self.cost_models.plutus_v1.0.is_object() {
-            for (i, (_key, value)) in self
-                .cost_models
-                .plutus_v1
-                .0
-                .as_object()
-                .unwrap()
-                .iter()
-                .enumerate()
-            {
-                v1.set(
-                    i,
-                    &utils::Int::from_str(&value.as_u64().unwrap().to_string())?,
-                )?;
-            }
+        if Self::set_cost_model(&mut v1, &self.cost_models.plutus_v1)? {
             cstmdls.insert(&v1);
         }
-
-        if self.cost_models.plutus_v2.0.is_object() {
-            for (i, (_key, value)) in self
-                .cost_models
-                .plutus_v2
-                .0
-                .as_object()
-                .unwrap()
-                .iter()
-                .enumerate()
-            {
-                v2.set(
-                    i,
-                    &utils::Int::from_str(&value.as_u64().unwrap().to_string())?,
-                )?;
-            }
+        if Self::set_cost_model(&mut v2, &self.cost_models.plutus_v2)? {
             cstmdls.insert(&v2);
         }
+        // The number of PlutusV3 parameters changes with protocol updates, the model keeps the length of the chain
+        if let Some(costs) = self
+            .cost_models
+            .plutus_v3
+            .as_ref()
+            .map(Self::cost_values)
+            .transpose()?
+            .flatten()
+        {
+            cstmdls.insert(&ClibCostModel::new(&Language::new_plutus_v3(), &costs));
+        }
 
         Ok(cstmdls)
     }
+
+    fn set_cost_model(model: &mut ClibCostModel, costs: &CostModel) -> Result<bool, MurinError> {
+        match Self::cost_values(costs)? {
+            Some(values) => {
+                for (i, cost) in values.iter().enumerate() {
+                    model.set(i, cost)?;
+                }
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    // Cost models are either named (cardano-cli) or plain lists (dbsync, Conway cardano-cli)
+    fn cost_values(costs: &CostModel) -> Result<Option<Vec<utils::Int>>, MurinError> {
+        let values: Vec<&serde_json::Value> = match &costs.0 {
+            serde_json::Value::Object(map) => map.values().collect(),
+            serde_json::Value::Array(list) => list.iter().collect(),
+            _ => return Ok(None),
+        };
+        values
+            .into_iter()
+            .map(|value| {
+                let cost = value.as_i64().ok_or_else(|| {
+                    MurinError::new(&format!("invalid cost model value: {value}"))
+                })?;
+                Ok(utils::Int::from_str(&cost.to_string())?)
+            })
+            .collect::<Result<Vec<_>, MurinError>>()
+            .map(Some)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) const BABBAGE_PARAMS: &str = r#"{
+        "collateralPercentage": 150,
+        "costModels": { "PlutusV1": [205665, 812, 1], "PlutusV2": [205665, 812, 1, 1] },
+        "executionUnitPrices": { "priceMemory": 0.0577, "priceSteps": 0.0000721 },
+        "maxBlockBodySize": 90112,
+        "maxBlockExecutionUnits": { "memory": 62000000, "steps": 20000000000 },
+        "maxBlockHeaderSize": 1100,
+        "maxCollateralInputs": 3,
+        "maxTxExecutionUnits": { "memory": 14000000, "steps": 10000000000 },
+        "maxTxSize": 16384,
+        "maxValueSize": 5000,
+        "minPoolCost": 340000000,
+        "monetaryExpansion": 0.003,
+        "poolPledgeInfluence": 0.3,
+        "poolRetireMaxEpoch": 18,
+        "protocolVersion": { "major": 8, "minor": 0 },
+        "stakeAddressDeposit": 2000000,
+        "stakePoolDeposit": 500000000,
+        "stakePoolTargetNum": 500,
+        "treasuryCut": 0.2,
+        "txFeeFixed": 155381,
+        "txFeePerByte": 44,
+        "utxoCostPerByte": 4310
+    }"#;
+
+    #[test]
+    fn parse_babbage_and_conway() {
+        let pp = ProtocolParameters::from_json(BABBAGE_PARAMS).unwrap();
+        assert_eq!(pp.era(), Era::Babbage);
+        assert_eq!(pp.drep_deposit, None);
+        assert_eq!(pp.ref_script_fee(1000), 0);
+        assert_eq!(pp.get_CostMdls().unwrap().len(), 2);
+
+        let mut conway: serde_json::Value = serde_json::from_str(BABBAGE_PARAMS).unwrap();
+        conway["protocolVersion"]["major"] = 9.into();
+        conway["minFeeRefScriptCostPerByte"] = 15.into();
+        conway["dRepDeposit"] = 500000000.into();
+        conway["govActionDeposit"] = 100000000000u64.into();
+        let pp = ProtocolParameters::from_json(&conway.to_string()).unwrap();
+        assert_eq!(pp.era(), Era::Conway);
+        assert_eq!(pp.drep_deposit, Some(500000000));
+        assert_eq!(pp.gov_action_deposit, Some(100000000000));
+        assert_eq!(pp.ref_script_fee(1000), 15000);
+        // 25600 bytes at 15 and 400 bytes at 18
+        assert_eq!(pp.ref_script_fee(26000), 384000 + 7200);
+        assert_eq!(pp.get_CostMdls().unwrap().len(), 2);
+
+        conway["costModels"]["PlutusV3"] = serde_json::json!([100788, 420, 1, 1, 1000]);
+        let pp = ProtocolParameters::from_json(&conway.to_string()).unwrap();
+        let v3 = pp
+            .get_CostMdls()
+            .unwrap()
+            .get(&Language::new_plutus_v3())
+            .unwrap();
+        assert_eq!(v3.op_costs.len(), 5);
+        assert_eq!(v3.get(4).unwrap().as_i32_or_nothing(), Some(1000));
+    }
 }
@@ -15,11 +15,13 @@ use super::binary::*;
 
 const PLUTUS_V1_COST_MODEL_OP_COUNT: usize = 166;
 const PLUTUS_V2_COST_MODEL_OP_COUNT: usize = 175;
+const PLUTUS_V3_COST_MODEL_OP_COUNT: usize = 297;
 
 fn cost_model_op_count(lang: LanguageKind) -> usize {
     match lang {
         LanguageKind::PlutusV1 => PLUTUS_V1_COST_MODEL_OP_COUNT,
         LanguageKind::PlutusV2 => PLUTUS_V2_COST_MODEL_OP_COUNT,
+        LanguageKind::PlutusV3 => PLUTUS_V3_COST_MODEL_OP_COUNT,
     }
 }
 
@@ -148,9 +150,9 @@ impl Costmdls {
                         .write_bytes(cost_model_serializer.finalize())
                         .unwrap();
                 }
-                LanguageKind::PlutusV2 => {
-                    // For PlutusV2 (language id 1), the language view is the following:
-                    //    * the value of costmdls map at key 1 is encoded as an definite length list.
+                LanguageKind::PlutusV2 | LanguageKind::PlutusV3 => {
+                    // For PlutusV2 (language id 1) and PlutusV3 (language id 2), the language view is the following:
+                    //    * the value of costmdls map at the key is encoded as an definite length list.
                     key.serialize(&mut serializer).unwrap();
                     let cost_model = self.0.get(&key).unwrap();
                     serializer
@@ -173,6 +175,7 @@ impl Costmdls {
 pub enum LanguageKind {
     PlutusV1,
     PlutusV2,
+    PlutusV3,
 }
 
 #[derive(
@@ -191,6 +194,10 @@ impl Language {
         Self(LanguageKind::PlutusV2)
     }
 
+    pub fn new_plutus_v3() -> Self {
+        Self(LanguageKind::PlutusV3)
+    }
+
     pub fn kind(&self) -> LanguageKind {
         self.0
     }
@@ -308,6 +315,7 @@ impl cbor_event::se::Serialize for Language {
         match self.0 {
             LanguageKind::PlutusV1 => serializer.write_unsigned_integer(0u64),
             LanguageKind::PlutusV2 => serializer.write_unsigned_integer(1u64),
+            LanguageKind::PlutusV3 => serializer.write_unsigned_integer(2u64),
         }
     }
 }
@@ -317,6 +325,8 @@ impl Deserialize for Language {
         (|| -> Result<_, DeserializeError> {
             match raw.unsigned_integer()? {
                 0 => Ok(Language::new_plutus_v1()),
+                1 => Ok(Language::new_plutus_v2()),
+                2 => Ok(Language::new_plutus_v3()),
                 _ => Err(DeserializeError::new(
                     "Language",
                     DeserializeFailure::NoVariantMatched.into(),
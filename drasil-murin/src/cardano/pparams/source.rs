@@ -0,0 +1,230 @@
+//! Protocol parameter sources
+//!
+//! Transaction building reads the protocol parameters through the process wide
+//! [`ProtocolParameterSource`] returned by [`protocol_parameter_source`]. The
+//! default is a [`CachedSource`] over the JSON file in `CARDANO_PROTOCOL_PARAMETER_PATH`,
+//! services with access to dbsync can replace it on startup.
+use super::ProtocolParameters;
+use crate::MurinError;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+pub const PROTOCOL_PARAMETER_PATH: &str = "CARDANO_PROTOCOL_PARAMETER_PATH";
+pub const PROTOCOL_PARAMETER_REFRESH_SECS: &str = "CARDANO_PROTOCOL_PARAMETER_REFRESH_SECS";
+const DEFAULT_PROTOCOL_PARAMETER_PATH: &str = "/odin/protocol_parameters_babbage.json";
+const DEFAULT_REFRESH: Duration = Duration::from_secs(60);
+
+pub trait ProtocolParameterSource: Send + Sync {
+    /// Loads the protocol parameters which are valid right now
+    fn protocol_parameters(&self) -> Result<ProtocolParameters, MurinError>;
+
+    /// Current epoch as seen by this source, `None` if the source does not know about epochs
+    fn current_epoch(&self) -> Result<Option<i64>, MurinError> {
+        Ok(None)
+    }
+}
+
+/// Reads the protocol parameters from a JSON file in the format of `cardano-cli query protocol-parameters`
+#[derive(Debug, Clone)]
+pub struct FileSource {
+    path: String,
+}
+
+impl FileSource {
+    pub fn new(path: &str) -> Self {
+        FileSource {
+            path: path.to_string(),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        FileSource::new(
+            &std::env::var(PROTOCOL_PARAMETER_PATH)
+                .unwrap_or_else(|_| DEFAULT_PROTOCOL_PARAMETER_PATH.to_owned()),
+        )
+    }
+}
+
+impl ProtocolParameterSource for FileSource {
+    fn protocol_parameters(&self) -> Result<ProtocolParameters, MurinError> {
+        ProtocolParameters::read_protocol_parameter(&self.path).map_err(|e| {
+            MurinError::new(&format!(
+                "could not read protocol parameters from '{}': {}",
+                self.path, e
+            ))
+        })
+    }
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    params: ProtocolParameters,
+    epoch: Option<i64>,
+    checked: Instant,
+}
+
+/// Keeps the parameters of the inner source in memory and reloads them when the
+/// inner source reports a new epoch. Sources without epoch information are
+/// reloaded after each refresh interval. If a reload fails the last known
+/// parameters stay in use.
+pub struct CachedSource<S: ProtocolParameterSource> {
+    inner: S,
+    refresh: Duration,
+    cache: Mutex<Option<CacheEntry>>,
+}
+
+impl<S: ProtocolParameterSource> CachedSource<S> {
+    pub fn new(inner: S) -> Self {
+        let refresh = std::env::var(PROTOCOL_PARAMETER_REFRESH_SECS)
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_REFRESH);
+        CachedSource {
+            inner,
+            refresh,
+            cache: Mutex::new(None),
+        }
+    }
+
+    /// Interval in which the inner source is asked for a new epoch
+    pub fn with_refresh(mut self, refresh: Duration) -> Self {
+        self.refresh = refresh;
+        self
+    }
+
+    fn load(&self) -> Result<CacheEntry, MurinError> {
+        let epoch = self.inner.current_epoch()?;
+        Ok(CacheEntry {
+            params: self.inner.protocol_parameters()?,
+            epoch,
+            checked: Instant::now(),
+        })
+    }
+}
+
+impl<S: ProtocolParameterSource> ProtocolParameterSource for CachedSource<S> {
+    fn protocol_parameters(&self) -> Result<ProtocolParameters, MurinError> {
+        let mut cache = self
+            .cache
+            .lock()
+            .map_err(|_| MurinError::new("protocol parameter cache poisoned"))?;
+        let entry = match cache.take() {
+            None => self.load()?,
+            Some(entry) if entry.checked.elapsed() < self.refresh => entry,
+            Some(mut entry) => {
+                let reload = match self.inner.current_epoch() {
+                    Ok(Some(epoch)) => entry.epoch != Some(epoch),
+                    Ok(None) => true,
+                    Err(e) => {
+                        warn!(
+                            "could not query current epoch for protocol parameters: {}",
+                            e
+                        );
+                        false
+                    }
+                };
+                if reload {
+                    match self.load() {
+                        Ok(new) => {
+                            info!("reloaded protocol parameters for epoch {:?}", new.epoch);
+                            new
+                        }
+                        Err(e) => {
+                            warn!(
+                                "could not reload protocol parameters, keeping cached ones: {}",
+                                e
+                            );
+                            entry.checked = Instant::now();
+                            entry
+                        }
+                    }
+                } else {
+                    entry.checked = Instant::now();
+                    entry
+                }
+            }
+        };
+        let params = entry.params.clone();
+        *cache = Some(entry);
+        Ok(params)
+    }
+
+    fn current_epoch(&self) -> Result<Option<i64>, MurinError> {
+        self.inner.current_epoch()
+    }
+}
+
+static SOURCE: OnceLock<RwLock<Arc<dyn ProtocolParameterSource>>> = OnceLock::new();
+
+fn source_slot() -> &'static RwLock<Arc<dyn ProtocolParameterSource>> {
+    SOURCE.get_or_init(|| RwLock::new(Arc::new(CachedSource::new(FileSource::from_env()))))
+}
+
+/// Returns the protocol parameter source used by this process, the cached parameter file if none was set.
+pub fn protocol_parameter_source() -> Arc<dyn ProtocolParameterSource> {
+    source_slot()
+        .read()
+        .expect("protocol parameter source lock poisoned")
+        .clone()
+}
+
+/// Replaces the protocol parameter source used by this process.
+pub fn set_protocol_parameter_source(source: Arc<dyn ProtocolParameterSource>) {
+    *source_slot()
+        .write()
+        .expect("protocol parameter source lock poisoned") = source;
+}
+
+/// Current protocol parameters of the process wide source
+pub fn protocol_parameters() -> Result<ProtocolParameters, MurinError> {
+    protocol_parameter_source().protocol_parameters()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+
+    struct CountingSource {
+        epoch: AtomicI64,
+        loads: AtomicUsize,
+    }
+
+    impl ProtocolParameterSource for CountingSource {
+        fn protocol_parameters(&self) -> Result<ProtocolParameters, MurinError> {
+            self.loads.fetch_add(1, Ordering::SeqCst);
+            let mut pp = ProtocolParameters::from_json(super::super::tests::BABBAGE_PARAMS)?;
+            pp.tx_fee_fixed = self.epoch.load(Ordering::SeqCst) as u64;
+            Ok(pp)
+        }
+
+        fn current_epoch(&self) -> Result<Option<i64>, MurinError> {
+            Ok(Some(self.epoch.load(Ordering::SeqCst)))
+        }
+    }
+
+    #[test]
+    fn cached_source_reloads_on_new_epoch() {
+        let source = CachedSource::new(CountingSource {
+            epoch: AtomicI64::new(400),
+            loads: AtomicUsize::new(0),
+        })
+        .with_refresh(Duration::ZERO);
+
+        assert_eq!(source.protocol_parameters().unwrap().tx_fee_fixed, 400);
+        assert_eq!(source.protocol_parameters().unwrap().tx_fee_fixed, 400);
+        assert_eq!(source.inner.loads.load(Ordering::SeqCst), 1);
+
+        source.inner.epoch.store(401, Ordering::SeqCst);
+        assert_eq!(source.protocol_parameters().unwrap().tx_fee_fixed, 401);
+        assert_eq!(source.inner.loads.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn missing_file_is_an_error() {
+        assert!(FileSource::new("/does/not/exist.json")
+            .protocol_parameters()
+            .is_err());
+    }
+}
@@ -0,0 +1,222 @@
+//! Conversion between slots, POSIX time and epochs
+//!
+//! Each network started its current slot length at a different point in time,
+//! a [`SlotConfig`] holds that point and the slot and epoch lengths since then.
+//! Testnet transactions use the network selected by `CARDANO_NETWORK`
+//! (`preprod`, `preview` or `custom`, default `preprod`). A custom network,
+//! e.g. a local devnet, is described by:
+//!
+//! - `CARDANO_SLOT_ZERO_TIME`: POSIX time in milliseconds of the zero slot
+//! - `CARDANO_SLOT_ZERO_SLOT`: first slot with the slot length below, default 0
+//! - `CARDANO_SLOT_LENGTH`: slot length in milliseconds, default 1000
+//! - `CARDANO_SLOT_ZERO_EPOCH`: epoch of the zero slot, default 0
+//! - `CARDANO_EPOCH_LENGTH`: slots per epoch
+//!
+//! Slots before the zero slot (the Byron era) are not supported.
+use std::str::FromStr;
+
+use cardano_serialization_lib::NetworkIdKind;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumString};
+
+use crate::MurinError;
+
+const NETWORK_VAR: &str = "CARDANO_NETWORK";
+/// Share of the slots of an epoch expected to hold a block, the same on all networks
+const ACTIVE_SLOT_COEFF: f64 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum CardanoNetwork {
+    Mainnet,
+    Preprod,
+    Preview,
+    Custom,
+}
+
+impl CardanoNetwork {
+    /// Testnet selected in the environment, preprod if none is set
+    pub fn testnet_from_env() -> Result<CardanoNetwork, MurinError> {
+        match std::env::var(NETWORK_VAR) {
+            Ok(name) => match CardanoNetwork::from_str(&name.to_lowercase()) {
+                Ok(CardanoNetwork::Mainnet) => Err(MurinError::new(&format!(
+                    "{NETWORK_VAR} is mainnet, but a testnet slot configuration was requested"
+                ))),
+                Ok(network) => Ok(network),
+                Err(_) => Err(MurinError::new(&format!(
+                    "unknown network in {NETWORK_VAR}: {name}"
+                ))),
+            },
+            Err(_) => Ok(CardanoNetwork::Preprod),
+        }
+    }
+
+    pub fn slot_config(&self) -> Result<SlotConfig, MurinError> {
+        match self {
+            CardanoNetwork::Mainnet => Ok(SlotConfig::MAINNET),
+            CardanoNetwork::Preprod => Ok(SlotConfig::PREPROD),
+            CardanoNetwork::Preview => Ok(SlotConfig::PREVIEW),
+            CardanoNetwork::Custom => SlotConfig::custom_from_env(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotConfig {
+    /// POSIX time in milliseconds of `zero_slot`
+    pub zero_time: u64,
+    /// First slot with `slot_length`
+    pub zero_slot: u64,
+    /// Slot length in milliseconds
+    pub slot_length: u32,
+    /// Epoch `zero_slot` belongs to
+    pub zero_epoch: u64,
+    /// Slots per epoch
+    pub epoch_length: u64,
+}
+
+impl SlotConfig {
+    /// Shelley hard fork of mainnet
+    pub const MAINNET: SlotConfig = SlotConfig {
+        zero_time: 1596059091000,
+        zero_slot: 4492800,
+        slot_length: 1000,
+        zero_epoch: 208,
+        epoch_length: 432000,
+    };
+
+    /// Shelley hard fork of the preprod testnet
+    pub const PREPROD: SlotConfig = SlotConfig {
+        zero_time: 1655769600000,
+        zero_slot: 86400,
+        slot_length: 1000,
+        zero_epoch: 4,
+        epoch_length: 432000,
+    };
+
+    /// Genesis of the preview testnet, which has no Byron era
+    pub const PREVIEW: SlotConfig = SlotConfig {
+        zero_time: 1666656000000,
+        zero_slot: 0,
+        slot_length: 1000,
+        zero_epoch: 0,
+        epoch_length: 86400,
+    };
+
+    /// Slot configuration of the network transactions with `network` id are built for
+    pub fn for_network(network: &NetworkIdKind) -> Result<SlotConfig, MurinError> {
+        match network {
+            NetworkIdKind::Mainnet => Ok(SlotConfig::MAINNET),
+            NetworkIdKind::Testnet => CardanoNetwork::testnet_from_env()?.slot_config(),
+        }
+    }
+
+    pub fn custom_from_env() -> Result<SlotConfig, MurinError> {
+        fn var<T: FromStr>(name: &str, default: Option<T>) -> Result<T, MurinError> {
+            match std::env::var(name) {
+                Ok(v) => v
+                    .parse()
+                    .map_err(|_| MurinError::new(&format!("invalid value in {name}: {v}"))),
+                Err(_) => default.ok_or_else(|| {
+                    MurinError::new(&format!("{name} is required for a custom network"))
+                }),
+            }
+        }
+        let config = SlotConfig {
+            zero_time: var("CARDANO_SLOT_ZERO_TIME", None)?,
+            zero_slot: var("CARDANO_SLOT_ZERO_SLOT", Some(0))?,
+            slot_length: var("CARDANO_SLOT_LENGTH", Some(1000))?,
+            zero_epoch: var("CARDANO_SLOT_ZERO_EPOCH", Some(0))?,
+            epoch_length: var("CARDANO_EPOCH_LENGTH", None)?,
+        };
+        if config.slot_length == 0 || config.epoch_length == 0 {
+            return Err(MurinError::new(
+                "slot and epoch length of a custom network must not be zero",
+            ));
+        }
+        Ok(config)
+    }
+
+    /// POSIX time in milliseconds at the start of `slot`
+    pub fn slot_to_posix(&self, slot: u64) -> u64 {
+        self.zero_time + slot.saturating_sub(self.zero_slot) * self.slot_length as u64
+    }
+
+    /// Slot containing the POSIX time `posix_ms`
+    pub fn posix_to_slot(&self, posix_ms: u64) -> u64 {
+        self.zero_slot + posix_ms.saturating_sub(self.zero_time) / self.slot_length as u64
+    }
+
+    pub fn slot_to_time(&self, slot: u64) -> DateTime<Utc> {
+        Utc.timestamp_millis_opt(self.slot_to_posix(slot) as i64)
+            .unwrap()
+    }
+
+    pub fn time_to_slot(&self, time: &DateTime<Utc>) -> u64 {
+        self.posix_to_slot(time.timestamp_millis().max(0) as u64)
+    }
+
+    pub fn slot_to_epoch(&self, slot: u64) -> u64 {
+        self.zero_epoch + slot.saturating_sub(self.zero_slot) / self.epoch_length
+    }
+
+    pub fn epoch_first_slot(&self, epoch: u64) -> u64 {
+        self.zero_slot + epoch.saturating_sub(self.zero_epoch) * self.epoch_length
+    }
+
+    /// Blocks expected to be minted in an epoch
+    pub fn blocks_per_epoch(&self) -> f64 {
+        self.epoch_length as f64 * ACTIVE_SLOT_COEFF
+    }
+
+    /// Number of slots in `seconds`
+    pub fn slots_in(&self, seconds: u64) -> u64 {
+        seconds * 1000 / self.slot_length as u64
+    }
+
+    /// (zero_time, zero_slot, slot_length) as expected by the UPLC evaluator
+    pub fn uplc(&self) -> (u64, u64, u32) {
+        (self.zero_time, self.zero_slot, self.slot_length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slot_conversions() {
+        let mainnet = SlotConfig::MAINNET;
+        let date = Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap();
+        // formerly hardcoded for mainnet
+        let slot = (date.timestamp() - 1596491091 + 4924800) as u64;
+        assert_eq!(mainnet.time_to_slot(&date), slot);
+        assert_eq!(mainnet.slot_to_time(slot), date);
+        assert_eq!(mainnet.slot_to_epoch(4924800), 209);
+        assert_eq!(mainnet.epoch_first_slot(209), 4924800);
+
+        let preprod = SlotConfig::PREPROD;
+        assert_eq!(preprod.slot_to_time(86400).timestamp(), 1655769600);
+        assert_eq!(preprod.time_to_slot(&date), 86400 + 1672531200 - 1655769600);
+        assert_eq!(preprod.slot_to_epoch(86400 + 432000), 5);
+
+        let preview = SlotConfig::PREVIEW;
+        assert_eq!(preview.time_to_slot(&date), 1672531200 - 1666656000);
+        assert_eq!(preview.slot_to_epoch(86400 * 3 + 5), 3);
+        assert_eq!(preview.epoch_first_slot(3), 86400 * 3);
+        assert_eq!(mainnet.blocks_per_epoch(), 21600.0);
+        assert_eq!(preview.blocks_per_epoch(), 4320.0);
+
+        let devnet = SlotConfig {
+            zero_time: 1700000000000,
+            zero_slot: 0,
+            slot_length: 100,
+            zero_epoch: 0,
+            epoch_length: 500,
+        };
+        assert_eq!(devnet.slots_in(60), 600);
+        assert_eq!(devnet.posix_to_slot(1700000001050), 10);
+        assert_eq!(devnet.slot_to_epoch(1200), 2);
+    }
+}
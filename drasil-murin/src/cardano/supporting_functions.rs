@@ -168,12 +168,14 @@ pub fn _ccli_query_utxos_address(
     None
 }
 
-pub fn get_ttl_tx(net: &cardano_serialization_lib::NetworkIdKind) -> u64 {
-    if *net == cardano_serialization_lib::NetworkIdKind::Testnet {
+/// Validity of a transaction in slots, 30 minutes on testnets and 2 hours on mainnet
+pub fn get_ttl_tx(net: &cardano_serialization_lib::NetworkIdKind) -> Result<u64, MurinError> {
+    let seconds = if *net == cardano_serialization_lib::NetworkIdKind::Testnet {
         1800
     } else {
         7200
-    }
+    };
+    Ok(super::SlotConfig::for_network(net)?.slots_in(seconds))
 }
 
 pub fn _query_utxos_by_address_from_cli(
@@ -1177,7 +1179,7 @@ pub fn create_ada_tx(
         &dummy,
     )?;
 
-    let slot = in_current_slot + 3000;
+    let slot = in_current_slot + super::SlotConfig::for_network(network)?.slots_in(3000);
     let mut txbody = clib::TransactionBody::new_tx_body(&txins, &txouts_fin, fee);
     txbody.set_ttl(&cutils::to_bignum(slot));
 
@@ -12,6 +12,17 @@ pub enum MurinError {
     ProtocolCommandErrorInvalidData,
     #[error("frame check: could not get decimal")]
     ProtocolCommandErrorCouldNotGetDecimal,
+    #[error("transaction submission failed: {0}")]
+    TxSubmission(crate::txbuilder::submit::SubmitReport),
+    #[error("{code}: {message}")]
+    ProtocolError { code: String, message: String },
+    /// The transaction exceeds the size or execution unit limits of the protocol,
+    /// `split_off` lists the inputs ('txhash#index') which must go into another transaction
+    #[error("{message}")]
+    TxLimitExceeded {
+        message: String,
+        split_off: Vec<String>,
+    },
     //#[error("{:}}",)]
     //Error(#[from] &str),
 }
@@ -4,7 +4,9 @@ pub mod txbuilder;
 pub mod utxomngr;
 
 pub use cardano::cip30;
+pub use cardano::conway;
 pub use cardano::pparams;
+pub use cardano::slots;
 pub use error::MurinError;
 pub use txbuilder::*;
 
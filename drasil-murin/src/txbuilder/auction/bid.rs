@@ -0,0 +1,234 @@
+use cardano_serialization_lib as clib;
+use cardano_serialization_lib::{address as caddr, utils as cutils};
+use clib::plutus::PlutusScript;
+use models::Tokens;
+
+use super::*;
+use crate::cardano::{models, supporting_functions, SlotConfig};
+use crate::error::MurinError;
+use crate::modules::txtools::utxo_handling::combine_wallet_outputs;
+
+/// Bids on an auction. English auctions get a new auction output carrying the
+/// bid and the previous bid is refunded, Dutch auctions are bought at the
+/// current price.
+#[derive(Debug, Clone)]
+pub struct AtAuctionBidBuilder {
+    pub contract: PlutusScript,
+    pub sc_address: caddr::Address,
+    pub owner: caddr::Address,
+    pub auction: AuctionTxData,
+}
+
+pub type AtAuctionBidParam<'a> = (
+    &'a PlutusScript,
+    &'a caddr::Address,
+    &'a caddr::Address,
+    &'a AuctionTxData,
+);
+
+impl<'a> PerformTxb<AtAuctionBidParam<'a>> for AtAuctionBidBuilder {
+    fn new(t: AtAuctionBidParam) -> Self {
+        AtAuctionBidBuilder {
+            contract: t.0.clone(),
+            sc_address: t.1.clone(),
+            owner: t.2.clone(),
+            auction: t.3.clone(),
+        }
+    }
+
+    fn script_utxos(&self) -> models::TransactionUnspentOutputs {
+        self.auction.get_auction_utxos()
+    }
+
+    fn perform_txb(
+        &self,
+        fee: &clib::utils::BigNum,
+        gtxd: &TxData,
+        _pvks: &[String],
+        fcrun: bool,
+    ) -> std::result::Result<TxBO, MurinError> {
+        if fcrun {
+            info!("--------------------------------------------------------------------------------------------------------");
+            info!("-----------------------------------------Fee Calculation------------------------------------------------");
+            info!("---------------------------------------------------------------------------------------------------------\n");
+        } else {
+            info!("--------------------------------------------------------------------------------------------------------");
+            info!("-----------------------------------------Build Transaction----------------------------------------------");
+            info!("--------------------------------------------------------------------------------------------------------\n");
+        }
+
+        /////////////////////////////////////////////////////////////////////////////////////////////////////
+        //Restore Datum
+        /////////////////////////////////////////////////////////////////////////////////////////////////////
+        let (auction_utxo, datum) = restore_auction(&self.auction.get_auction_utxos())?;
+        let network = gtxd.get_network();
+        let slot_config = SlotConfig::for_network(&network)?;
+        let current_slot = gtxd.get_current_slot();
+        let now = slot_config.slot_to_posix(current_slot);
+        if now >= datum.deadline {
+            return Err(MurinError::new("Error: the auction has already ended"));
+        }
+        let (bidder, bidder_stake) = address_keys(&self.owner)?;
+        let locked = cutils::from_bignum(&auction_utxo.output().amount().coin());
+
+        //////////////////////////////////////////////////////////////////////////////////////////////////////
+        //Add Inputs and Outputs
+        ///////////////////////////////////////////////////////////////////////////////////////////////////////
+        let mut txouts = clib::TransactionOutputs::new();
+        let price = match datum.kind {
+            AuctionKind::English => {
+                let bid = self
+                    .auction
+                    .get_bid()
+                    .ok_or_else(|| MurinError::new("Error: no bid provided"))?;
+                if bid < datum.min_bid() {
+                    return Err(MurinError::new(&format!(
+                        "Error: the bid must be at least {} lovelace",
+                        datum.min_bid()
+                    )));
+                }
+                let mut new_datum = datum.clone();
+                new_datum.bidder = Some(bidder.clone());
+                new_datum.bidder_stake = bidder_stake;
+                new_datum.bid = bid;
+
+                let mut value = token_value(&datum.policy_id, &datum.token_name);
+                value.set_coin(&to_bignum(bid));
+                let mut txo = TransactionOutput::new(&self.sc_address, &value);
+                txo.set_plutus_data(&encode_auction_datum(&new_datum));
+                if cutils::from_bignum(&min_ada_for_utxo(&txo)?.amount().coin()) > bid {
+                    return Err(MurinError::new(
+                        "Error: the bid is below the minimum Ada of the auction output",
+                    ));
+                }
+                txouts.add(&txo);
+
+                // The previous bid, or the deposit of the seller, goes back
+                let refund_addr = datum
+                    .bidder_address(&network)
+                    .unwrap_or_else(|| datum.seller_address(&network));
+                txouts.add(&min_ada_for_utxo(&TransactionOutput::new(
+                    &refund_addr,
+                    &cutils::Value::new(&to_bignum(locked)),
+                ))?);
+                bid
+            }
+            AuctionKind::Dutch => {
+                let price = datum.dutch_price(now);
+                txouts.add(&min_ada_for_utxo(&TransactionOutput::new(
+                    &self.owner,
+                    &token_value(&datum.policy_id, &datum.token_name),
+                ))?);
+                add_sale_outputs(
+                    &mut txouts,
+                    &datum,
+                    price,
+                    locked,
+                    self.auction.get_royalties_address().as_ref(),
+                    &network,
+                )?;
+                price
+            }
+        };
+
+        let mut input_txuos = gtxd.clone().get_inputs();
+
+        // Check if some utxos in inputs are in use and remove them
+        if let Some(used_utxos) = crate::utxomngr::usedutxos::check_any_utxo_used(&input_txuos)? {
+            input_txuos.remove_used_utxos(used_utxos);
+        }
+
+        let collateral_input_txuo = gtxd.get_collateral();
+        info!("\nCollateral Input: {:?}", collateral_input_txuo);
+
+        // Balance TX
+        debug!("Before Balance: Transaction Inputs: {:?}", input_txuos);
+        debug!("Before Balance: Transaction Outputs: {:?}", txouts);
+
+        let mut fee_paid = false;
+        let mut first_run = true;
+        let mut txos_paid = false;
+        let mut tbb_values = cutils::Value::new(&cutils::to_bignum(0u64));
+        let mut acc = cutils::Value::new(&cutils::to_bignum(0u64));
+
+        let mut needed_value = cutils::Value::new(&to_bignum(price));
+        needed_value.set_coin(&needed_value.coin().checked_add(&fee.clone()).unwrap());
+        let security = cutils::to_bignum(
+            cutils::from_bignum(&needed_value.coin()) / 100 * 10 + (2 * models::MIN_ADA),
+        ); // 10% Security for min utxo etc.
+        needed_value.set_coin(&needed_value.coin().checked_add(&security).unwrap());
+
+        let (mut txins, mut input_txuos) =
+            input_selection(None, &mut needed_value, &input_txuos, None, None)?;
+        let saved_input_txuos = input_txuos.clone();
+
+        txins.add(&auction_utxo.input());
+        input_txuos.add(&auction_utxo);
+
+        let vkey_counter =
+            supporting_functions::get_vkey_count(&input_txuos, collateral_input_txuo.as_ref());
+
+        let txouts_fin = supporting_functions::balance_tx(
+            &mut input_txuos,
+            &Tokens::new(),
+            &mut txouts,
+            None,
+            fee,
+            &mut fee_paid,
+            &mut first_run,
+            &mut txos_paid,
+            &mut tbb_values,
+            &self.owner,
+            &self.owner,
+            &mut acc,
+            None,
+            &fcrun,
+        )?;
+        let txouts_fin = combine_wallet_outputs(&txouts_fin);
+
+        // The script sees the bid time as the lower bound and needs the upper bound before the deadline
+        let ttl = std::cmp::min(
+            current_slot + slot_config.slots_in(3000),
+            slot_config.posix_to_slot(datum.deadline),
+        );
+        let mut txbody = clib::TransactionBody::new_tx_body(&txins, &txouts_fin, fee);
+        txbody.set_ttl(&cutils::to_bignum(ttl));
+        txbody.set_validity_start_interval_bignum(&cutils::to_bignum(current_slot));
+
+        //////////////////////////////////////////////////////////////////////////////////////////////////////
+        //Required Signer
+        //
+        ///////////////////////////////////////////////////////////////////////////////////////////////////////
+        let mut req_signers = clib::Ed25519KeyHashes::new();
+        req_signers.add(&bidder);
+        txbody.set_required_signers(&req_signers);
+
+        // Collateral Input
+        let mut col_inputs = clib::TransactionInputs::new();
+        if let Some(collateral) = collateral_input_txuo {
+            col_inputs.add(&collateral.input());
+            txbody.set_collateral(&col_inputs);
+        };
+        if txbody.collateral().is_none() {
+            return Err(MurinError::new("Error: No collateral provided"));
+        }
+
+        let txwitness = spend_script_witness(
+            &mut txbody,
+            &auction_utxo,
+            &self.contract,
+            AUCTION_BID_REDEEMER,
+        )?;
+
+        info!("--------------------Iteration Ended------------------------------");
+        info!("Vkey Counter at End: {:?}", vkey_counter);
+        Ok((
+            txbody,
+            txwitness,
+            None,
+            saved_input_txuos,
+            vkey_counter,
+            true,
+        ))
+    }
+}
@@ -0,0 +1,781 @@
+//! Timed auctions
+//!
+//! An auction locks a single token together with an [`AuctionDatum`] at the
+//! auction script. English auctions ascend from a reserve price, every bid
+//! replaces the auction output and refunds the previous bidder, after the
+//! deadline the highest bidder gets the token and the seller the bid. Dutch
+//! auctions descend linearly from the start to the end price until the
+//! deadline, the first bidder buys the token at the current price.
+//!
+//! # Validator
+//!
+//! The `Auction` contract has to implement this interface, the builders only
+//! produce transactions it accepts.
+//!
+//! The datum is `Constr 0` with the 15 fields of [`AuctionDatum`] in
+//! declaration order, see [`encode_auction_datum`]. Key hashes are bytes, an
+//! absent key is empty bytes, the kind is `0` for English and `1` for Dutch,
+//! times are POSIX milliseconds and the royalties rate is in promille.
+//!
+//! - [`AUCTION_BID_REDEEMER`]: the transaction is signed by the bidder and its
+//!   validity interval ends before `deadline`. English auctions must continue
+//!   with one output at the script holding the token, the bid and the datum with
+//!   only bidder, bidder stake and bid changed to a bid of at least
+//!   [`AuctionDatum::min_bid`], and refund the locked Ada to the previous bidder,
+//!   or the seller without bid. Dutch auctions must pay at least
+//!   [`AuctionDatum::dutch_price`] at the lower bound of the validity interval.
+//! - [`AUCTION_SETTLE_REDEEMER`]: the validity interval starts at or after
+//!   `deadline`. The token goes to the bidder and the bid to the seller,
+//!   without bid the whole output goes back to the seller.
+//! - [`AUCTION_CANCEL_REDEEMER`]: the transaction is signed by the seller and
+//!   the datum has no bidder.
+//!
+//! Every sale pays at least `price / 1000 * royalties_rate` to `royalties_pkh`
+//! and the remainder to the seller.
+use cardano_serialization_lib as clib;
+use cardano_serialization_lib::{address as caddr, utils as cutils};
+use clib::crypto::Ed25519KeyHash;
+use clib::plutus;
+use clib::{AssetName, PolicyID};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use strum::{Display, EnumString};
+
+pub mod bid;
+pub mod cancel;
+pub mod settle;
+pub mod start;
+
+use self::minter::cip27::Cip27Royalty;
+use super::scripts::{
+    bytes_field, int_field, key_address, key_field, royalty_terms, spend_script_witness,
+};
+
+use super::*;
+pub use bid::*;
+pub use cancel::*;
+pub use settle::*;
+pub use start::*;
+
+/// Redeemer of a bid, for Dutch auctions the bid buys the token
+pub const AUCTION_BID_REDEEMER: u64 = 0;
+/// Redeemer of the settlement after the deadline
+pub const AUCTION_SETTLE_REDEEMER: u64 = 1;
+/// Redeemer of the seller withdrawing an auction without bids
+pub const AUCTION_CANCEL_REDEEMER: u64 = 2;
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Display, EnumString, Default,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum AuctionKind {
+    /// Ascending price, the highest bid wins at the deadline
+    #[default]
+    English,
+    /// Descending price, the first bid wins
+    Dutch,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Display, EnumString)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum AuctionStep {
+    Start,
+    Bid,
+    Settle,
+    Cancel,
+}
+
+#[derive(Debug, Clone)]
+pub struct AuctionTxData {
+    step: AuctionStep,
+    kind: AuctionKind,
+    token: Option<TokenAsset>,
+    start_price: u64,
+    end_price: u64,
+    increment: u64,
+    deadline: u64,
+    bid: Option<u64>,
+    auction_utxos: TransactionUnspentOutputs,
+    royalties_addr: Option<caddr::Address>,
+    royalties_rate: Option<f32>,
+}
+
+impl ToString for AuctionTxData {
+    fn to_string(&self) -> String {
+        let s_token = match &self.token {
+            Some(t) => {
+                hex::encode(t.0.to_bytes())
+                    + "?"
+                    + &hex::encode(t.1.to_bytes())
+                    + "?"
+                    + &hex::encode(t.2.to_bytes())
+            }
+            None => "NoData".to_string(),
+        };
+        let s_bid = match self.bid {
+            Some(b) => b.to_string(),
+            None => "NoData".to_string(),
+        };
+        let s_auction_utxos = match self.auction_utxos.to_hex() {
+            Ok(s) if !self.auction_utxos.is_empty() => s,
+            _ => "NoData".to_string(),
+        };
+        let s_royaddr = match &self.royalties_addr {
+            Some(a) => hex::encode(a.to_bytes()),
+            None => "NoData".to_string(),
+        };
+        let s_royrate = match self.royalties_rate {
+            Some(r) => r.to_string(),
+            None => "NoData".to_string(),
+        };
+
+        [
+            self.step.to_string(),
+            self.kind.to_string(),
+            s_token,
+            self.start_price.to_string(),
+            self.end_price.to_string(),
+            self.increment.to_string(),
+            self.deadline.to_string(),
+            s_bid,
+            s_auction_utxos,
+            s_royaddr,
+            s_royrate,
+        ]
+        .join("|")
+    }
+}
+
+impl std::str::FromStr for AuctionTxData {
+    type Err = MurinError;
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let slice: Vec<&str> = src.split('|').collect();
+        if slice.len() != 11 {
+            return Err(MurinError::new(&format!(
+                "Error the provided string '{src}' cannot be parsed into 'AuctionTxData' "
+            )));
+        }
+        let strum_err = |e: strum::ParseError| MurinError::new(&e.to_string());
+        let token = match slice[2] {
+            "NoData" => None,
+            _ => {
+                let t: Vec<&str> = slice[2].split('?').collect();
+                if t.len() != 3 {
+                    return Err(MurinError::new("Error: invalid token in auction data"));
+                }
+                Some((
+                    PolicyID::from_bytes(hex::decode(t[0])?)?,
+                    AssetName::from_bytes(hex::decode(t[1])?)?,
+                    cutils::BigNum::from_bytes(hex::decode(t[2])?)?,
+                ))
+            }
+        };
+        let bid = match slice[7] {
+            "NoData" => None,
+            _ => Some(slice[7].parse::<u64>()?),
+        };
+        let auction_utxos = match slice[8] {
+            "NoData" => TransactionUnspentOutputs::new(),
+            _ => TransactionUnspentOutputs::from_hex(slice[8])?,
+        };
+        let royalties_addr = match slice[9] {
+            "NoData" => None,
+            _ => Some(caddr::Address::from_bytes(hex::decode(slice[9])?)?),
+        };
+        let royalties_rate = match slice[10] {
+            "NoData" => None,
+            _ => Some(slice[10].parse::<f32>()?),
+        };
+        Ok(AuctionTxData {
+            step: AuctionStep::from_str(slice[0]).map_err(strum_err)?,
+            kind: AuctionKind::from_str(slice[1]).map_err(strum_err)?,
+            token,
+            start_price: slice[3].parse::<u64>()?,
+            end_price: slice[4].parse::<u64>()?,
+            increment: slice[5].parse::<u64>()?,
+            deadline: slice[6].parse::<u64>()?,
+            bid,
+            auction_utxos,
+            royalties_addr,
+            royalties_rate,
+        })
+    }
+}
+
+impl AuctionTxData {
+    /// Parameters of a new auction, `deadline` is a POSIX time in milliseconds.
+    /// English auctions start at the reserve `start_price`, Dutch auctions
+    /// descend from `start_price` to `end_price`.
+    pub fn new_start(
+        kind: AuctionKind,
+        token: TokenAsset,
+        start_price: u64,
+        end_price: u64,
+        increment: u64,
+        deadline: u64,
+    ) -> Result<AuctionTxData, MurinError> {
+        match kind {
+            AuctionKind::English if increment == 0 => {
+                return Err(MurinError::new(
+                    "Error: english auctions need a bid increment",
+                ));
+            }
+            AuctionKind::Dutch if end_price >= start_price => {
+                return Err(MurinError::new(
+                    "Error: the end price of a dutch auction must be below its start price",
+                ));
+            }
+            _ => {}
+        }
+        if token.2 != cutils::to_bignum(1) {
+            return Err(MurinError::new(
+                "Error: only single tokens can be auctioned",
+            ));
+        }
+        Ok(AuctionTxData {
+            step: AuctionStep::Start,
+            kind,
+            token: Some(token),
+            start_price,
+            end_price: if kind == AuctionKind::Dutch {
+                end_price
+            } else {
+                0
+            },
+            increment: if kind == AuctionKind::English {
+                increment
+            } else {
+                0
+            },
+            deadline,
+            bid: None,
+            auction_utxos: TransactionUnspentOutputs::new(),
+            royalties_addr: None,
+            royalties_rate: None,
+        })
+    }
+
+    /// Restores the auction data of `step` from the datum of an auction script UTxO
+    pub fn from_auction_utxo(
+        step: AuctionStep,
+        utxo: &TransactionUnspentOutput,
+    ) -> Result<AuctionTxData, MurinError> {
+        let mut auction_utxos = TransactionUnspentOutputs::new();
+        auction_utxos.add(utxo);
+        let (_, datum) = restore_auction(&auction_utxos)?;
+        Ok(AuctionTxData {
+            step,
+            kind: datum.kind,
+            token: Some((datum.policy_id, datum.token_name, cutils::to_bignum(1))),
+            start_price: datum.start_price,
+            end_price: datum.end_price,
+            increment: datum.increment,
+            deadline: datum.deadline,
+            bid: None,
+            auction_utxos,
+            royalties_addr: None,
+            royalties_rate: None,
+        })
+    }
+
+    /// The bid of an english auction, Dutch auctions are bought at the current price
+    pub fn set_bid(&mut self, bid: u64) {
+        self.bid = Some(bid);
+    }
+
+    /// Sets the royalties declared by the CIP-27 token of the policy
+    pub fn set_royalties(&mut self, royalty: &Cip27Royalty) -> Result<(), MurinError> {
+        self.royalties_addr = Some(royalty.get_addr()?);
+        self.royalties_rate = Some(royalty.rate);
+        Ok(())
+    }
+
+    pub fn get_step(&self) -> AuctionStep {
+        self.step
+    }
+
+    pub fn get_kind(&self) -> AuctionKind {
+        self.kind
+    }
+
+    pub fn get_token(&self) -> Option<&TokenAsset> {
+        self.token.as_ref()
+    }
+
+    pub fn get_start_price(&self) -> u64 {
+        self.start_price
+    }
+
+    pub fn get_end_price(&self) -> u64 {
+        self.end_price
+    }
+
+    pub fn get_increment(&self) -> u64 {
+        self.increment
+    }
+
+    pub fn get_deadline(&self) -> u64 {
+        self.deadline
+    }
+
+    pub fn get_bid(&self) -> Option<u64> {
+        self.bid
+    }
+
+    pub fn get_auction_utxos(&self) -> TransactionUnspentOutputs {
+        self.auction_utxos.clone()
+    }
+
+    pub fn get_royalties_address(&self) -> Option<caddr::Address> {
+        self.royalties_addr.clone()
+    }
+
+    pub fn get_royalties_rate(&self) -> Option<f32> {
+        self.royalties_rate
+    }
+
+    /// Royalties in promille and the key hash they are paid to
+    fn royalties(&self) -> Result<(u64, Option<Ed25519KeyHash>), MurinError> {
+        royalty_terms(self.royalties_addr.as_ref(), self.royalties_rate)
+    }
+}
+
+/// Datum of an auction, the bidder fields are empty as long as there is no bid
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuctionDatum {
+    pub seller: Ed25519KeyHash,
+    pub seller_stake: Option<Ed25519KeyHash>,
+    pub policy_id: PolicyID,
+    pub token_name: AssetName,
+    pub kind: AuctionKind,
+    pub start_price: u64,
+    pub end_price: u64,
+    /// POSIX time in milliseconds
+    pub start: u64,
+    /// POSIX time in milliseconds
+    pub deadline: u64,
+    pub increment: u64,
+    pub royalties_rate: u64,
+    pub royalties_pkh: Option<Ed25519KeyHash>,
+    pub bidder: Option<Ed25519KeyHash>,
+    pub bidder_stake: Option<Ed25519KeyHash>,
+    pub bid: u64,
+}
+
+impl AuctionDatum {
+    /// Lowest acceptable bid of an english auction
+    pub fn min_bid(&self) -> u64 {
+        match self.bidder {
+            Some(_) => self.bid + self.increment,
+            None => self.start_price,
+        }
+    }
+
+    /// Price of a dutch auction at `posix_ms`
+    pub fn dutch_price(&self, posix_ms: u64) -> u64 {
+        if posix_ms <= self.start || self.deadline <= self.start {
+            return self.start_price;
+        }
+        if posix_ms >= self.deadline {
+            return self.end_price;
+        }
+        let drop = (self.start_price - self.end_price) as u128 * (posix_ms - self.start) as u128
+            / (self.deadline - self.start) as u128;
+        self.start_price - drop as u64
+    }
+
+    pub fn seller_address(&self, network: &clib::NetworkIdKind) -> caddr::Address {
+        key_address(network, &self.seller, self.seller_stake.as_ref())
+    }
+
+    pub fn bidder_address(&self, network: &clib::NetworkIdKind) -> Option<caddr::Address> {
+        self.bidder
+            .as_ref()
+            .map(|b| key_address(network, b, self.bidder_stake.as_ref()))
+    }
+}
+
+/// Payment and optional stake key hash of a wallet address
+fn address_keys(
+    addr: &caddr::Address,
+) -> Result<(Ed25519KeyHash, Option<Ed25519KeyHash>), MurinError> {
+    if let Some(base) = caddr::BaseAddress::from_address(addr) {
+        if let Some(payment) = base.payment_cred().to_keyhash() {
+            return Ok((payment, base.stake_cred().to_keyhash()));
+        }
+    }
+    if let Some(enterprise) = caddr::EnterpriseAddress::from_address(addr) {
+        if let Some(payment) = enterprise.payment_cred().to_keyhash() {
+            return Ok((payment, None));
+        }
+    }
+    Err(MurinError::new(
+        "Error: auctions need a wallet address with a payment key",
+    ))
+}
+
+pub fn encode_auction_datum(auction: &AuctionDatum) -> plutus::PlutusData {
+    let mut fields = plutus::PlutusList::new();
+    fields.add(&key_field(Some(&auction.seller)));
+    fields.add(&key_field(auction.seller_stake.as_ref()));
+    fields.add(&bytes_field(auction.policy_id.to_bytes()));
+    fields.add(&bytes_field(auction.token_name.name()));
+    fields.add(&int_field(match auction.kind {
+        AuctionKind::English => 0,
+        AuctionKind::Dutch => 1,
+    }));
+    fields.add(&int_field(auction.start_price));
+    fields.add(&int_field(auction.end_price));
+    fields.add(&int_field(auction.start));
+    fields.add(&int_field(auction.deadline));
+    fields.add(&int_field(auction.increment));
+    fields.add(&int_field(auction.royalties_rate));
+    fields.add(&key_field(auction.royalties_pkh.as_ref()));
+    fields.add(&key_field(auction.bidder.as_ref()));
+    fields.add(&key_field(auction.bidder_stake.as_ref()));
+    fields.add(&int_field(auction.bid));
+    plutus::PlutusData::new_constr_plutus_data(&plutus::ConstrPlutusData::new(
+        &cutils::to_bignum(0),
+        &fields,
+    ))
+}
+
+pub fn decode_auction_datum(datum: &plutus::PlutusData) -> Result<AuctionDatum, MurinError> {
+    let err = || MurinError::new("Error: datum is not an auction");
+    let constr = datum.as_constr_plutus_data().ok_or_else(err)?;
+    let fields = constr.data();
+    if cutils::from_bignum(&constr.alternative()) != 0 || fields.len() != 15 {
+        return Err(err());
+    }
+    let int = |i: usize| -> Result<u64, MurinError> {
+        fields
+            .get(i)
+            .as_integer()
+            .and_then(|n| n.as_u64())
+            .map(|n| cutils::from_bignum(&n))
+            .ok_or_else(err)
+    };
+    let bytes = |i: usize| fields.get(i).as_bytes().ok_or_else(err);
+    let keyhash = |i: usize| -> Result<Option<Ed25519KeyHash>, MurinError> {
+        let b = bytes(i)?;
+        if b.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(Ed25519KeyHash::from_bytes(b)?))
+        }
+    };
+
+    Ok(AuctionDatum {
+        seller: keyhash(0)?.ok_or_else(err)?,
+        seller_stake: keyhash(1)?,
+        policy_id: PolicyID::from_bytes(bytes(2)?)?,
+        token_name: AssetName::new(bytes(3)?)?,
+        kind: match int(4)? {
+            0 => AuctionKind::English,
+            1 => AuctionKind::Dutch,
+            _ => return Err(err()),
+        },
+        start_price: int(5)?,
+        end_price: int(6)?,
+        start: int(7)?,
+        deadline: int(8)?,
+        increment: int(9)?,
+        royalties_rate: int(10)?,
+        royalties_pkh: keyhash(11)?,
+        bidder: keyhash(12)?,
+        bidder_stake: keyhash(13)?,
+        bid: int(14)?,
+    })
+}
+
+/// The auction datum of the single script UTxO in `auction_utxos`
+fn restore_auction(
+    auction_utxos: &TransactionUnspentOutputs,
+) -> Result<(TransactionUnspentOutput, AuctionDatum), MurinError> {
+    if auction_utxos.len() != 1 {
+        return Err(MurinError::new(
+            "Error: exactly one auction can be handled at a time",
+        ));
+    }
+    let utxo = auction_utxos.get(0);
+    let datum = utxo
+        .output()
+        .plutus_data()
+        .ok_or_else(|| MurinError::new("Error: auction utxo has no inline datum"))?;
+    Ok((utxo, decode_auction_datum(&datum)?))
+}
+
+/// Value of the auctioned token without Ada
+fn token_value(policy_id: &PolicyID, token_name: &AssetName) -> cutils::Value {
+    let mut value = cutils::Value::zero();
+    let mut ma = clib::MultiAsset::new();
+    let mut assets = clib::Assets::new();
+    assets.insert(token_name, &cutils::to_bignum(1));
+    ma.insert(policy_id, &assets);
+    value.set_multiasset(&ma);
+    value
+}
+
+/// Pays the price of a sold token and the `deposit` locked with it to the
+/// seller, and the royalties of the datum
+fn add_sale_outputs(
+    txouts: &mut clib::TransactionOutputs,
+    datum: &AuctionDatum,
+    price: u64,
+    deposit: u64,
+    royalties_addr: Option<&caddr::Address>,
+    network: &clib::NetworkIdKind,
+) -> Result<(), MurinError> {
+    let royalties = price / 1000 * datum.royalties_rate;
+    if royalties > 0 {
+        let addr = royalties_addr
+            .ok_or_else(|| MurinError::new("royalties declared without an address"))?;
+        if Some(supporting_functions::get_payment_keyhash(addr)) != datum.royalties_pkh {
+            return Err(MurinError::new(
+                "Error: the auction does not pay the declared royalties",
+            ));
+        }
+        txouts.add(&min_ada_for_utxo(&TransactionOutput::new(
+            addr,
+            &cutils::Value::new(&to_bignum(royalties)),
+        ))?);
+    }
+    txouts.add(&min_ada_for_utxo(&TransactionOutput::new(
+        &datum.seller_address(network),
+        &cutils::Value::new(&to_bignum(price - royalties + deposit)),
+    ))?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pparams::{ProtocolParameterSource, ProtocolParameters};
+    use std::sync::Arc;
+
+    struct TestSource;
+
+    impl ProtocolParameterSource for TestSource {
+        fn protocol_parameters(&self) -> Result<ProtocolParameters, MurinError> {
+            ProtocolParameters::from_json(crate::pparams::tests::BABBAGE_PARAMS)
+        }
+    }
+
+    fn seller_address() -> caddr::Address {
+        caddr::Address::from_bech32("addr_test1qp8cprhse9pnnv7f4l3n6pj0afq2hjm6f7r2205dz0583egaeu9dhacmtx94652q4ym0v9v2mcra0n28d5lrtjqzsgxqgk5t8s").unwrap()
+    }
+
+    fn dutch_auction() -> AuctionDatum {
+        let (seller, seller_stake) = address_keys(&seller_address()).unwrap();
+        AuctionDatum {
+            seller,
+            seller_stake,
+            policy_id: PolicyID::from_bytes(
+                hex::decode("5a3f5bb4bf1a22f0a3a9a9d8e6e5b7a1d9d2d1c8b7a6f5e4d3c2b1a0").unwrap(),
+            )
+            .unwrap(),
+            token_name: AssetName::new(b"Drasil01".to_vec()).unwrap(),
+            kind: AuctionKind::Dutch,
+            start_price: 100_000_000,
+            end_price: 20_000_000,
+            start: 1_000_000,
+            deadline: 2_000_000,
+            increment: 0,
+            royalties_rate: 0,
+            royalties_pkh: None,
+            bidder: None,
+            bidder_stake: None,
+            bid: 0,
+        }
+    }
+
+    #[test]
+    fn auction_datum_and_prices() {
+        let addr = seller_address();
+        let mut datum = dutch_auction();
+        assert_eq!(
+            decode_auction_datum(&encode_auction_datum(&datum)).unwrap(),
+            datum
+        );
+        assert_eq!(
+            datum
+                .seller_address(&clib::NetworkIdKind::Testnet)
+                .to_bytes(),
+            addr.to_bytes()
+        );
+        assert_eq!(datum.dutch_price(0), 100_000_000);
+        assert_eq!(datum.dutch_price(1_500_000), 60_000_000);
+        assert_eq!(datum.dutch_price(3_000_000), 20_000_000);
+
+        datum.kind = AuctionKind::English;
+        datum.increment = 1_000_000;
+        assert_eq!(datum.min_bid(), 100_000_000);
+        datum.bidder = Some(datum.seller.clone());
+        datum.bid = 120_000_000;
+        assert_eq!(datum.min_bid(), 121_000_000);
+
+        let start = AuctionTxData::new_start(
+            AuctionKind::English,
+            (
+                datum.policy_id.clone(),
+                datum.token_name.clone(),
+                cutils::to_bignum(1),
+            ),
+            100_000_000,
+            0,
+            1_000_000,
+            2_000_000,
+        )
+        .unwrap();
+        assert_eq!(
+            AuctionTxData::from_str(&start.to_string())
+                .unwrap()
+                .to_string(),
+            start.to_string()
+        );
+    }
+
+    #[test]
+    fn auction_datum_layout() {
+        let mut datum = dutch_auction();
+        let royalties = caddr::EnterpriseAddress::new(
+            0,
+            &caddr::StakeCredential::from_keyhash(
+                &Ed25519KeyHash::from_bytes(vec![7; 28]).unwrap(),
+            ),
+        )
+        .to_address();
+        datum.royalties_rate = 25;
+        datum.royalties_pkh = Some(supporting_functions::get_payment_keyhash(&royalties));
+
+        let encoded = encode_auction_datum(&datum);
+        let constr = encoded.as_constr_plutus_data().unwrap();
+        let fields = constr.data();
+        assert_eq!(cutils::from_bignum(&constr.alternative()), 0);
+        assert_eq!(fields.len(), 15);
+        assert_eq!(fields.get(0).as_bytes().unwrap(), datum.seller.to_bytes());
+        assert_eq!(
+            fields.get(4).as_integer().unwrap().as_u64(),
+            Some(to_bignum(1))
+        );
+        assert_eq!(
+            fields.get(8).as_integer().unwrap().as_u64(),
+            Some(to_bignum(2_000_000))
+        );
+        assert_eq!(
+            fields.get(10).as_integer().unwrap().as_u64(),
+            Some(to_bignum(25))
+        );
+        assert_eq!(fields.get(11).as_bytes().unwrap(), vec![7; 28]);
+        // absent bidder and bidder stake are empty bytes
+        assert!(fields.get(12).as_bytes().unwrap().is_empty());
+        assert!(fields.get(13).as_bytes().unwrap().is_empty());
+
+        let other = plutus::PlutusData::new_constr_plutus_data(&plutus::ConstrPlutusData::new(
+            &cutils::to_bignum(1),
+            &fields,
+        ));
+        assert!(decode_auction_datum(&other).is_err());
+    }
+
+    #[test]
+    fn sale_outputs_pay_royalties_and_seller() {
+        crate::pparams::set_protocol_parameter_source(Arc::new(TestSource));
+        let royalties = caddr::EnterpriseAddress::new(
+            0,
+            &caddr::StakeCredential::from_keyhash(
+                &Ed25519KeyHash::from_bytes(vec![7; 28]).unwrap(),
+            ),
+        )
+        .to_address();
+        let mut datum = dutch_auction();
+        datum.royalties_rate = 50;
+        datum.royalties_pkh = Some(supporting_functions::get_payment_keyhash(&royalties));
+        let network = clib::NetworkIdKind::Testnet;
+
+        let mut txouts = clib::TransactionOutputs::new();
+        add_sale_outputs(
+            &mut txouts,
+            &datum,
+            100_000_000,
+            2_000_000,
+            Some(&royalties),
+            &network,
+        )
+        .unwrap();
+        assert_eq!(txouts.len(), 2);
+        assert_eq!(txouts.get(0).address().to_bytes(), royalties.to_bytes());
+        assert_eq!(txouts.get(0).amount().coin(), to_bignum(5_000_000));
+        assert_eq!(
+            txouts.get(1).address().to_bytes(),
+            seller_address().to_bytes()
+        );
+        assert_eq!(txouts.get(1).amount().coin(), to_bignum(97_000_000));
+
+        // royalties to another key than the datum declares are rejected
+        let mut txouts = clib::TransactionOutputs::new();
+        assert!(add_sale_outputs(
+            &mut txouts,
+            &datum,
+            100_000_000,
+            0,
+            Some(&seller_address()),
+            &network,
+        )
+        .is_err());
+        assert!(add_sale_outputs(&mut txouts, &datum, 100_000_000, 0, None, &network).is_err());
+    }
+
+    #[test]
+    fn redeemer_spends_the_auction_input() {
+        crate::pparams::set_protocol_parameter_source(Arc::new(TestSource));
+        let wallet_input = clib::TransactionInput::new(
+            &clib::crypto::TransactionHash::from_bytes(vec![1; 32]).unwrap(),
+            0,
+        );
+        let auction_utxo = TransactionUnspentOutput::new(
+            &clib::TransactionInput::new(
+                &clib::crypto::TransactionHash::from_bytes(vec![2; 32]).unwrap(),
+                1,
+            ),
+            &TransactionOutput::new(
+                &seller_address(),
+                &cutils::Value::new(&to_bignum(2_000_000)),
+            ),
+        );
+        let mut txins = clib::TransactionInputs::new();
+        txins.add(&auction_utxo.input());
+        txins.add(&wallet_input);
+        let mut txbody = clib::TransactionBody::new_tx_body(
+            &txins,
+            &clib::TransactionOutputs::new(),
+            &to_bignum(200_000),
+        );
+        let contract = plutus::PlutusScript::new_v2(vec![0x4e, 0x4d, 0x01, 0x00]);
+
+        let witness = spend_script_witness(
+            &mut txbody,
+            &auction_utxo,
+            &contract,
+            AUCTION_SETTLE_REDEEMER,
+        )
+        .unwrap();
+        let redeemers = witness.redeemers().unwrap();
+        assert_eq!(redeemers.len(), 1);
+        // inputs are sorted, the auction input is the second one
+        assert_eq!(redeemers.get(0).index(), to_bignum(1));
+        assert_eq!(
+            redeemers
+                .get(0)
+                .data()
+                .as_constr_plutus_data()
+                .unwrap()
+                .alternative(),
+            to_bignum(AUCTION_SETTLE_REDEEMER)
+        );
+        assert_eq!(witness.plutus_scripts().unwrap().len(), 1);
+        assert!(txbody.script_data_hash().is_some());
+    }
+}
@@ -0,0 +1,186 @@
+use cardano_serialization_lib as clib;
+use cardano_serialization_lib::{address as caddr, utils as cutils};
+use clib::plutus::PlutusScript;
+use models::Tokens;
+
+use super::*;
+use crate::cardano::{models, supporting_functions, SlotConfig};
+use crate::error::MurinError;
+use crate::modules::txtools::utxo_handling::combine_wallet_outputs;
+
+/// Settles an auction after its deadline, the token goes to the highest bidder
+/// and the bid to the seller. Without bids the token goes back to the seller.
+/// Anyone can settle, the settling wallet pays the fee.
+#[derive(Debug, Clone)]
+pub struct AtAuctionSettleBuilder {
+    pub contract: PlutusScript,
+    pub sc_address: caddr::Address,
+    pub owner: caddr::Address,
+    pub auction: AuctionTxData,
+}
+
+pub type AtAuctionSettleParam<'a> = (
+    &'a PlutusScript,
+    &'a caddr::Address,
+    &'a caddr::Address,
+    &'a AuctionTxData,
+);
+
+impl<'a> PerformTxb<AtAuctionSettleParam<'a>> for AtAuctionSettleBuilder {
+    fn new(t: AtAuctionSettleParam) -> Self {
+        AtAuctionSettleBuilder {
+            contract: t.0.clone(),
+            sc_address: t.1.clone(),
+            owner: t.2.clone(),
+            auction: t.3.clone(),
+        }
+    }
+
+    fn script_utxos(&self) -> models::TransactionUnspentOutputs {
+        self.auction.get_auction_utxos()
+    }
+
+    fn perform_txb(
+        &self,
+        fee: &clib::utils::BigNum,
+        gtxd: &TxData,
+        _pvks: &[String],
+        fcrun: bool,
+    ) -> std::result::Result<TxBO, MurinError> {
+        if fcrun {
+            info!("--------------------------------------------------------------------------------------------------------");
+            info!("-----------------------------------------Fee Calculation------------------------------------------------");
+            info!("---------------------------------------------------------------------------------------------------------\n");
+        } else {
+            info!("--------------------------------------------------------------------------------------------------------");
+            info!("-----------------------------------------Build Transaction----------------------------------------------");
+            info!("--------------------------------------------------------------------------------------------------------\n");
+        }
+
+        /////////////////////////////////////////////////////////////////////////////////////////////////////
+        //Restore Datum
+        /////////////////////////////////////////////////////////////////////////////////////////////////////
+        let (auction_utxo, datum) = restore_auction(&self.auction.get_auction_utxos())?;
+        let network = gtxd.get_network();
+        let slot_config = SlotConfig::for_network(&network)?;
+        let current_slot = gtxd.get_current_slot();
+        if slot_config.slot_to_posix(current_slot) < datum.deadline {
+            return Err(MurinError::new("Error: the auction has not ended yet"));
+        }
+
+        //////////////////////////////////////////////////////////////////////////////////////////////////////
+        //Add Inputs and Outputs
+        ///////////////////////////////////////////////////////////////////////////////////////////////////////
+        let mut txouts = clib::TransactionOutputs::new();
+        match datum.bidder_address(&network) {
+            Some(winner) => {
+                txouts.add(&min_ada_for_utxo(&TransactionOutput::new(
+                    &winner,
+                    &token_value(&datum.policy_id, &datum.token_name),
+                ))?);
+                add_sale_outputs(
+                    &mut txouts,
+                    &datum,
+                    datum.bid,
+                    0,
+                    self.auction.get_royalties_address().as_ref(),
+                    &network,
+                )?;
+            }
+            None => {
+                txouts.add(&min_ada_for_utxo(&TransactionOutput::new(
+                    &datum.seller_address(&network),
+                    &auction_utxo.output().amount(),
+                ))?);
+            }
+        }
+
+        let mut input_txuos = gtxd.clone().get_inputs();
+
+        // Check if some utxos in inputs are in use and remove them
+        if let Some(used_utxos) = crate::utxomngr::usedutxos::check_any_utxo_used(&input_txuos)? {
+            input_txuos.remove_used_utxos(used_utxos);
+        }
+
+        let collateral_input_txuo = gtxd.get_collateral();
+        info!("\nCollateral Input: {:?}", collateral_input_txuo);
+
+        // Balance TX
+        debug!("Before Balance: Transaction Inputs: {:?}", input_txuos);
+        debug!("Before Balance: Transaction Outputs: {:?}", txouts);
+
+        let mut fee_paid = false;
+        let mut first_run = true;
+        let mut txos_paid = false;
+        let mut tbb_values = cutils::Value::new(&cutils::to_bignum(0u64));
+        let mut acc = cutils::Value::new(&cutils::to_bignum(0u64));
+
+        // The auction utxo pays the outputs, the wallet covers the fee and the min Ada of the token output
+        let mut needed_value = cutils::Value::new(&fee.clone());
+        let security = cutils::to_bignum(2 * models::MIN_ADA);
+        needed_value.set_coin(&needed_value.coin().checked_add(&security).unwrap());
+
+        let (mut txins, mut input_txuos) =
+            input_selection(None, &mut needed_value, &input_txuos, None, None)?;
+        let saved_input_txuos = input_txuos.clone();
+
+        txins.add(&auction_utxo.input());
+        input_txuos.add(&auction_utxo);
+
+        let vkey_counter =
+            supporting_functions::get_vkey_count(&input_txuos, collateral_input_txuo.as_ref());
+
+        let txouts_fin = supporting_functions::balance_tx(
+            &mut input_txuos,
+            &Tokens::new(),
+            &mut txouts,
+            None,
+            fee,
+            &mut fee_paid,
+            &mut first_run,
+            &mut txos_paid,
+            &mut tbb_values,
+            &self.owner,
+            &self.owner,
+            &mut acc,
+            None,
+            &fcrun,
+        )?;
+        let txouts_fin = combine_wallet_outputs(&txouts_fin);
+
+        // The script sees the settlement time as the lower bound, it must be after the deadline
+        let mut txbody = clib::TransactionBody::new_tx_body(&txins, &txouts_fin, fee);
+        txbody.set_ttl(&cutils::to_bignum(
+            current_slot + slot_config.slots_in(3000),
+        ));
+        txbody.set_validity_start_interval_bignum(&cutils::to_bignum(current_slot));
+
+        // Collateral Input
+        let mut col_inputs = clib::TransactionInputs::new();
+        if let Some(collateral) = collateral_input_txuo {
+            col_inputs.add(&collateral.input());
+            txbody.set_collateral(&col_inputs);
+        };
+        if txbody.collateral().is_none() {
+            return Err(MurinError::new("Error: No collateral provided"));
+        }
+
+        let txwitness = spend_script_witness(
+            &mut txbody,
+            &auction_utxo,
+            &self.contract,
+            AUCTION_SETTLE_REDEEMER,
+        )?;
+
+        info!("--------------------Iteration Ended------------------------------");
+        info!("Vkey Counter at End: {:?}", vkey_counter);
+        Ok((
+            txbody,
+            txwitness,
+            None,
+            saved_input_txuos,
+            vkey_counter,
+            true,
+        ))
+    }
+}
@@ -0,0 +1,177 @@
+//! Plutus script execution unit evaluation
+//!
+//! After the dummy build of a script transaction the redeemers still carry a
+//! placeholder budget. The functions in this module run the scripts with the
+//! UPLC machine against the cost models of the protocol parameters, so the fee
+//! of the final build only pays for the budget the scripts actually consume.
+use super::TxBO;
+use crate::cardano::{SlotConfig, TransactionUnspentOutputs};
+use crate::error::MurinError;
+use crate::pparams::mp_plutus::{Costmdls, Language};
+use crate::pparams::ProtocolParameters;
+use cardano_serialization_lib as clib;
+use clib::plutus::{ExUnits, Redeemer, Redeemers};
+use clib::utils::{from_bignum, to_bignum};
+use clib::NetworkIdKind;
+
+/// Safety margin in percent added on top of the evaluated execution units,
+/// protects against small differences between the dummy and the final build.
+pub const EX_UNITS_MARGIN_PERCENT: u64 = 10;
+
+/// Evaluates all redeemers of the transaction and returns them with the
+/// execution units the scripts consumed plus `EX_UNITS_MARGIN_PERCENT`.
+///
+/// `utxos` must contain every input, reference input and collateral of the transaction.
+pub fn evaluate_ex_units(
+    tx: &clib::Transaction,
+    utxos: &TransactionUnspentOutputs,
+    protocol_parameters: &ProtocolParameters,
+    network: &NetworkIdKind,
+) -> Result<Redeemers, MurinError> {
+    let resolved = utxos
+        .0
+        .iter()
+        .map(|u| (u.input().to_bytes(), u.output().to_bytes()))
+        .collect::<Vec<_>>();
+    let cost_models = protocol_parameters.get_CostMdls()?.to_bytes();
+    let budget = (
+        protocol_parameters.max_tx_execution_units.steps as u64,
+        protocol_parameters.max_tx_execution_units.memory as u64,
+    );
+
+    let evaluated = uplc::tx::eval_phase_two_raw(
+        &tx.to_bytes(),
+        &resolved,
+        &cost_models,
+        budget,
+        SlotConfig::for_network(network)?.uplc(),
+        false,
+        |_| (),
+    )
+    .map_err(|e| MurinError::new(&format!("script evaluation failed: {e:?}")))?;
+
+    let mut redeemers = Redeemers::new();
+    for bytes in evaluated {
+        let red = Redeemer::from_bytes(bytes)?;
+        redeemers.add(&Redeemer::new(
+            &red.tag(),
+            &red.index(),
+            &red.data(),
+            &with_margin(&red.ex_units()),
+        ));
+    }
+    Ok(redeemers)
+}
+
+fn with_margin(ex_units: &ExUnits) -> ExUnits {
+    let add = |v: u64| v + v * EX_UNITS_MARGIN_PERCENT / 100;
+    ExUnits::new(
+        &to_bignum(add(from_bignum(&ex_units.mem()))),
+        &to_bignum(add(from_bignum(&ex_units.steps()))),
+    )
+}
+
+/// Sum of the execution units of all redeemers as (mem, steps)
+pub fn total_ex_units(redeemers: &Redeemers) -> (u64, u64) {
+    (0..redeemers.len())
+        .map(|i| redeemers.get(i).ex_units())
+        .fold((0, 0), |(mem, steps), e| {
+            (mem + from_bignum(&e.mem()), steps + from_bignum(&e.steps()))
+        })
+}
+
+/// Replaces the execution units of the redeemers in the witness set with the
+/// evaluated ones and recalculates the script data hash of the body.
+pub fn apply_ex_units(
+    tx: &mut TxBO,
+    evaluated: &Redeemers,
+    protocol_parameters: &ProtocolParameters,
+) -> Result<(), MurinError> {
+    let redeemers = match tx.1.redeemers() {
+        Some(r) => r,
+        None => return Ok(()),
+    };
+
+    let mut updated = Redeemers::new();
+    for i in 0..redeemers.len() {
+        let red = redeemers.get(i);
+        let ex_units = (0..evaluated.len())
+            .map(|j| evaluated.get(j))
+            .find(|e| e.tag() == red.tag() && e.index() == red.index())
+            .ok_or_else(|| {
+                MurinError::new(&format!(
+                    "no evaluated execution units for redeemer {:?}/{}",
+                    red.tag().kind(),
+                    from_bignum(&red.index())
+                ))
+            })?
+            .ex_units();
+        updated.add(&Redeemer::new(
+            &red.tag(),
+            &red.index(),
+            &red.data(),
+            &ex_units,
+        ));
+    }
+    tx.1.set_redeemers(&updated);
+
+    let all_cost_models = protocol_parameters.get_CostMdls()?;
+    let mut cost_models = Costmdls::new();
+    for lang in script_languages(&tx.1) {
+        if let Some(cm) = all_cost_models.get(&lang) {
+            cost_models.insert(&cm);
+        }
+    }
+    let scriptdatahash =
+        crate::pparams::hash::hash_script_data(&updated, &cost_models, tx.1.plutus_data());
+    tx.0.set_script_data_hash(&scriptdatahash);
+    Ok(())
+}
+
+// Scripts used via reference inputs are not visible in the witness set, we assume PlutusV2 for those
+fn script_languages(witness: &clib::TransactionWitnessSet) -> Vec<Language> {
+    let mut langs = Vec::new();
+    if let Some(scripts) = witness.plutus_scripts() {
+        for i in 0..scripts.len() {
+            let lang = match scripts.get(i).language_version().kind() {
+                clib::plutus::LanguageKind::PlutusV1 => Language::new_plutus_v1(),
+                clib::plutus::LanguageKind::PlutusV2 => Language::new_plutus_v2(),
+            };
+            if !langs.contains(&lang) {
+                langs.push(lang);
+            }
+        }
+    }
+    if langs.is_empty() {
+        langs.push(Language::new_plutus_v2());
+    }
+    langs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clib::plutus::{PlutusData, PlutusList, RedeemerTag};
+
+    fn redeemer(index: u64, mem: u64, steps: u64) -> Redeemer {
+        Redeemer::new(
+            &RedeemerTag::new_spend(),
+            &to_bignum(index),
+            &PlutusData::new_list(&PlutusList::new()),
+            &ExUnits::new(&to_bignum(mem), &to_bignum(steps)),
+        )
+    }
+
+    #[test]
+    fn margin_and_total() {
+        let e = with_margin(&ExUnits::new(&to_bignum(1000), &to_bignum(50000)));
+        assert_eq!(from_bignum(&e.mem()), 1100);
+        assert_eq!(from_bignum(&e.steps()), 55000);
+
+        let mut redeemers = Redeemers::new();
+        redeemers.add(&redeemer(0, 1000, 20000));
+        redeemers.add(&redeemer(2, 500, 30000));
+        assert_eq!(total_ex_units(&redeemers), (1500, 50000));
+        assert_eq!(total_ex_units(&Redeemers::new()), (0, 0));
+    }
+}
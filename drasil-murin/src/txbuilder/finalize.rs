@@ -28,15 +28,29 @@ pub async fn finalize(signature: &String, raw_tx: RawTx) -> Result<String, Murin
         Some(tx_aux.clone())
     };
 
-    let tx_body = clib::TransactionBody::from_bytes(hex::decode(raw_tx.get_txbody())?)?;
+    let body_bytes = hex::decode(raw_tx.get_txbody())?;
 
     let mut tx_witness_stored =
         clib::TransactionWitnessSet::from_bytes(hex::decode(raw_tx.get_txwitness())?)?;
 
     let vkeys = tx_witness_signature.vkeys().unwrap();
     tx_witness_stored.set_vkeys(&vkeys);
-    let fin_tx =
-        hex::encode(clib::Transaction::new(&tx_body, &tx_witness_stored, aux_data).to_bytes());
-    let tx_hash = hex::encode(cutils::hash_transaction(&tx_body).to_bytes());
+
+    // Bodies with Conway certificates or votes can not be decoded by cardano-serialization-lib
+    let (fin_tx, tx_hash) = match clib::TransactionBody::from_bytes(body_bytes.clone()) {
+        Ok(tx_body) => (
+            hex::encode(clib::Transaction::new(&tx_body, &tx_witness_stored, aux_data).to_bytes()),
+            hex::encode(cutils::hash_transaction(&tx_body).to_bytes()),
+        ),
+        Err(_) => (
+            hex::encode(crate::conway::assemble_transaction(
+                &body_bytes,
+                &tx_witness_stored,
+                aux_data.as_ref(),
+            )?),
+            hex::encode(crate::conway::hash_body(&body_bytes).to_bytes()),
+        ),
+    };
+    track_tx_state(&tx_hash, crate::utxomngr::TxState::Signed);
     create_and_submit_cbor_tx(fin_tx, tx_hash).await
 }
@@ -0,0 +1,222 @@
+use crate::cardano::{models, SlotConfig};
+use crate::error::MurinError;
+use crate::marketplace::*;
+use crate::modules::txtools::utxo_handling::combine_wallet_outputs;
+use cardano_serialization_lib as clib;
+use cardano_serialization_lib::{address as caddr, utils as cutils};
+use clib::plutus::PlutusScript;
+use models::Tokens;
+
+/// Sells a token of the holder into an offer or collection bid, the token goes
+/// to the buyer, the royalties to the declared address and the price to the holder.
+#[derive(Debug, Clone)]
+pub struct AtMPAcceptOfferBuilder {
+    pub contract: PlutusScript,
+    pub sc_address: caddr::Address,
+    pub owner: caddr::Address,
+    pub offer: MpOfferTxData,
+}
+
+pub type AtMPAcceptOfferParam<'a> = (
+    &'a PlutusScript,
+    &'a caddr::Address,
+    &'a caddr::Address,
+    &'a MpOfferTxData,
+);
+
+impl<'a> super::PerformTxb<AtMPAcceptOfferParam<'a>> for AtMPAcceptOfferBuilder {
+    fn new(t: AtMPAcceptOfferParam) -> Self {
+        AtMPAcceptOfferBuilder {
+            contract: t.0.clone(),
+            sc_address: t.1.clone(),
+            owner: t.2.clone(),
+            offer: t.3.clone(),
+        }
+    }
+
+    fn script_utxos(&self) -> models::TransactionUnspentOutputs {
+        self.offer.get_offer_utxos()
+    }
+
+    fn perform_txb(
+        &self,
+        fee: &clib::utils::BigNum,
+        gtxd: &TxData,
+        _pvks: &[String],
+        fcrun: bool,
+    ) -> std::result::Result<TxBO, MurinError> {
+        if fcrun {
+            info!("--------------------------------------------------------------------------------------------------------");
+            info!("-----------------------------------------Fee Calculation------------------------------------------------");
+            info!("---------------------------------------------------------------------------------------------------------\n");
+        } else {
+            info!("--------------------------------------------------------------------------------------------------------");
+            info!("-----------------------------------------Build Transaction----------------------------------------------");
+            info!("--------------------------------------------------------------------------------------------------------\n");
+        }
+
+        /////////////////////////////////////////////////////////////////////////////////////////////////////
+        //Restore Datum
+        /////////////////////////////////////////////////////////////////////////////////////////////////////
+        let (offer_utxo, datum) = restore_offer(&self.offer.get_offer_utxos())?;
+
+        let token = self
+            .offer
+            .get_token()
+            .ok_or_else(|| MurinError::new("Error: no token provided to accept the offer"))?;
+        if token.0 != datum.policy_id {
+            return Err(MurinError::new(
+                "Error: the token does not belong to the policy of the offer",
+            ));
+        }
+        match &datum.token_name {
+            Some(name) if *name != token.1 => {
+                return Err(MurinError::new("Error: the offer is for a different token"));
+            }
+            // A collection bid must not buy the CIP-27 royalty token
+            None if token.1.name().is_empty() => {
+                return Err(MurinError::new(
+                    "Error: the royalty token cannot be sold into a bid",
+                ));
+            }
+            _ => {}
+        }
+
+        // Royalties are resolved from the CIP-27 token of the policy
+        let (roy_rate, roy_pkh) = self.offer.royalties()?;
+        if datum.royalties_rate < roy_rate || (roy_rate > 0 && datum.royalties_pkh != roy_pkh) {
+            return Err(MurinError::new(
+                "Error: the offer does not pay the declared royalties",
+            ));
+        }
+        let unpaid_royalties: u64 = datum.price / 1000 * roy_rate;
+
+        //////////////////////////////////////////////////////////////////////////////////////////////////////
+        //Add Inputs and Outputs
+        ///////////////////////////////////////////////////////////////////////////////////////////////////////
+        let mut txouts = clib::TransactionOutputs::new();
+        let mut token_val = cutils::Value::zero();
+        let mut token_ma = clib::MultiAsset::new();
+        let mut token_assets = clib::Assets::new();
+        token_assets.insert(&token.1, &cutils::to_bignum(1));
+        token_ma.insert(&token.0, &token_assets);
+        token_val.set_multiasset(&token_ma);
+        txouts.add(&min_ada_for_utxo(&TransactionOutput::new(
+            &datum.buyer_address(&gtxd.get_network()),
+            &token_val,
+        ))?);
+
+        if unpaid_royalties > 0 {
+            txouts.add(&min_ada_for_utxo(&TransactionOutput::new(
+                &self.offer.get_royalties_address().unwrap(),
+                &cutils::Value::new(&to_bignum(unpaid_royalties)),
+            ))?);
+        }
+
+        let mut input_txuos = gtxd.clone().get_inputs();
+
+        // Check if some utxos in inputs are in use and remove them
+        if let Some(used_utxos) = crate::utxomngr::usedutxos::check_any_utxo_used(&input_txuos)? {
+            input_txuos.remove_used_utxos(used_utxos);
+        }
+
+        let token_utxos = input_txuos.find_utxos_containing_asset(&token.0, &token.1)?;
+        if token_utxos.is_empty() {
+            return Err(MurinError::new(
+                "Error: the token to sell is not held by the wallet",
+            ));
+        }
+
+        let collateral_input_txuo = gtxd.get_collateral();
+        info!("\nCollateral Input: {:?}", collateral_input_txuo);
+
+        // Balance TX
+        debug!("Before Balance: Transaction Inputs: {:?}", input_txuos);
+        debug!("Before Balance: Transaction Outputs: {:?}", txouts);
+
+        let mut fee_paid = false;
+        let mut first_run = true;
+        let mut txos_paid = false;
+        let mut tbb_values = cutils::Value::new(&cutils::to_bignum(0u64));
+        let mut acc = cutils::Value::new(&cutils::to_bignum(0u64));
+
+        // The offer pays the outputs, the wallet only needs to cover fee and collateral
+        let mut needed_value = cutils::Value::new(&fee.clone());
+        let security = cutils::to_bignum(2 * models::MIN_ADA);
+        needed_value.set_coin(&needed_value.coin().checked_add(&security).unwrap());
+
+        let (mut txins, mut input_txuos) = input_selection(
+            Some(&token_utxos),
+            &mut needed_value,
+            &input_txuos,
+            None,
+            None,
+        )?;
+        let saved_input_txuos = input_txuos.clone();
+
+        txins.add(&offer_utxo.input());
+        input_txuos.add(&offer_utxo);
+
+        let vkey_counter =
+            supporting_functions::get_vkey_count(&input_txuos, collateral_input_txuo.as_ref());
+
+        let txouts_fin = supporting_functions::balance_tx(
+            &mut input_txuos,
+            &Tokens::new(),
+            &mut txouts,
+            None,
+            fee,
+            &mut fee_paid,
+            &mut first_run,
+            &mut txos_paid,
+            &mut tbb_values,
+            &self.owner,
+            &self.owner,
+            &mut acc,
+            None,
+            &fcrun,
+        )?;
+        let txouts_fin = combine_wallet_outputs(&txouts_fin);
+
+        let slot =
+            gtxd.get_current_slot() + SlotConfig::for_network(&gtxd.get_network())?.slots_in(3000);
+        let mut txbody = clib::TransactionBody::new_tx_body(&txins, &txouts_fin, fee);
+        txbody.set_ttl(&cutils::to_bignum(slot));
+
+        //////////////////////////////////////////////////////////////////////////////////////////////////////
+        //Required Signer
+        //
+        ///////////////////////////////////////////////////////////////////////////////////////////////////////
+        let mut req_signers = clib::Ed25519KeyHashes::new();
+        req_signers.add(&supporting_functions::get_payment_keyhash(&self.owner));
+        txbody.set_required_signers(&req_signers);
+
+        // Collateral Input
+        let mut col_inputs = clib::TransactionInputs::new();
+        if let Some(collateral) = collateral_input_txuo {
+            col_inputs.add(&collateral.input());
+            txbody.set_collateral(&col_inputs);
+        };
+        if txbody.collateral().is_none() {
+            return Err(MurinError::new("Error: No collateral provided"));
+        }
+
+        let txwitness = spend_script_witness(
+            &mut txbody,
+            &offer_utxo,
+            &self.contract,
+            ACCEPT_OFFER_REDEEMER,
+        )?;
+
+        info!("--------------------Iteration Ended------------------------------");
+        info!("Vkey Counter at End: {:?}", vkey_counter);
+        Ok((
+            txbody,
+            txwitness,
+            None,
+            saved_input_txuos,
+            vkey_counter,
+            true,
+        ))
+    }
+}
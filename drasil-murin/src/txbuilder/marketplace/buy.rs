@@ -1,25 +1,27 @@
-use crate::cardano::models;
+use crate::cardano::{models, BuildOutput, SlotConfig};
 use crate::error::MurinError;
 use crate::marketplace::*;
 use crate::modules::txtools::utxo_handling::combine_wallet_outputs;
 use cardano_serialization_lib as clib;
 use cardano_serialization_lib::{address as caddr, utils as cutils};
-use clib::plutus::{ExUnits, PlutusScript, PlutusScripts};
+use clib::plutus::PlutusScript;
 use models::Tokens;
 
+/// Buys one or several listings in one transaction, each seller and royalty
+/// address is paid and all bought tokens go to the buyer
 #[derive(Debug, Clone)]
 pub struct AtMPBuyBuilder {
     pub contract: PlutusScript,
     pub sc_address: caddr::Address,
     pub owner: caddr::Address,
-    pub mptxd: MpTxData,
+    pub listings: Vec<MpTxData>,
 }
 
 pub type AtMPBuyParam<'a> = (
     &'a PlutusScript,
     &'a caddr::Address,
     &'a caddr::Address,
-    &'a MpTxData,
+    &'a [MpTxData],
 );
 
 impl<'a> super::PerformTxb<AtMPBuyParam<'a>> for AtMPBuyBuilder {
@@ -28,10 +30,19 @@ impl<'a> super::PerformTxb<AtMPBuyParam<'a>> for AtMPBuyBuilder {
             contract: t.0.clone(),
             sc_address: t.1.clone(),
             owner: t.2.clone(),
-            mptxd: t.3.clone(),
+            listings: t.3.to_vec(),
         }
     }
 
+    fn script_utxos(&self) -> models::TransactionUnspentOutputs {
+        self.listings
+            .iter()
+            .fold(TransactionUnspentOutputs::new(), |mut acc, l| {
+                acc.merge(l.get_token_utxos());
+                acc
+            })
+    }
+
     fn perform_txb(
         &self,
         fee: &clib::utils::BigNum,
@@ -49,37 +60,64 @@ impl<'a> super::PerformTxb<AtMPBuyParam<'a>> for AtMPBuyBuilder {
             info!("--------------------------------------------------------------------------------------------------------\n");
         }
 
-        // Is copy of Cancel change to Buy functionallity.
-        todo!();
+        if self.listings.is_empty() {
+            return Err(MurinError::new("Error: no listings to buy"));
+        }
+        let network = gtxd.get_network();
 
         /////////////////////////////////////////////////////////////////////////////////////////////////////
-        //Restore Datum
+        //Restore Datums
+        //Add Inputs and Outputs
         /////////////////////////////////////////////////////////////////////////////////////////////////////
-        let contract_utxos = self.mptxd.token_utxos.clone();
-        let mut datums = Vec::<MarketPlaceDatum>::new();
-        for utxo in contract_utxos.0.iter() {
-            if let Some(data) = utxo.output().plutus_data() {
-                datums.push(decode_mp_datum(&data.to_bytes())?);
+        let mut txouts = clib::TransactionOutputs::new();
+        let mut bought = cutils::Value::new(&to_bignum(0));
+        let mut total_price = 0u64;
+        let script_utxos = self.script_utxos();
+        for listing in self.listings.iter() {
+            let utxos = listing.get_token_utxos();
+            if utxos.len() != 1 {
+                return Err(MurinError::new(
+                    "Error: every listing must consist of exactly one script utxo",
+                ));
+            }
+            let utxo = utxos.get(0);
+            let data = utxo
+                .output()
+                .plutus_data()
+                .ok_or_else(|| MurinError::new("Error: listing utxo has no inline datum"))?;
+            let datum = decode_mp_datum(&data.to_bytes())?;
+            // Royalties are resolved from the CIP-27 token of the policy
+            check_datum_royalties(&datum, listing)?;
+
+            let royalties = datum.price / 1000 * datum.royalties_rate;
+            if royalties > 0 {
+                let royalties_addr = listing
+                    .get_royalties_address()
+                    .ok_or_else(|| MurinError::new("royalties declared without an address"))?;
+                txouts.add(&min_ada_for_utxo(&TransactionOutput::new(
+                    &royalties_addr,
+                    &cutils::Value::new(&to_bignum(royalties)),
+                ))?);
             }
-        }
-
-        if datums.len() != 1 {
-            return Err("More than one datum found in contract utxos, currently only one trade at a time is supported".into());
-        }
-
-        //////////////////////////////////////////////////////////////////////////////////////////////////////
-        //Add Inputs and Outputs
-        ///////////////////////////////////////////////////////////////////////////////////////////////////////
 
-        let mut txouts = clib::TransactionOutputs::new();
-        let script_utxos = self.mptxd.token_utxos.clone();
-        for utxo in script_utxos.0.iter() {
-            let trade_utxo_value = utxo.output().amount();
+            // The seller gets the price and the Ada locked with the token
+            let locked = utxo.output().amount();
+            let mut seller_value = cutils::Value::new(&to_bignum(datum.price - royalties));
+            seller_value.set_coin(&seller_value.coin().checked_add(&locked.coin())?);
             txouts.add(&min_ada_for_utxo(&TransactionOutput::new(
-                &self.owner,
-                &trade_utxo_value,
+                &datum.seller_address(&network),
+                &seller_value,
             ))?);
+
+            let mut tokens = locked.clone();
+            tokens.set_coin(&to_bignum(0));
+            bought = bought.checked_add(&tokens)?;
+            total_price += datum.price;
         }
+        txouts.add(&min_ada_for_utxo(&TransactionOutput::new(
+            &self.owner,
+            &bought,
+        ))?);
 
         let mut input_txuos = gtxd.clone().get_inputs();
 
@@ -101,10 +139,11 @@ impl<'a> super::PerformTxb<AtMPBuyParam<'a>> for AtMPBuyBuilder {
         let mut tbb_values = cutils::Value::new(&cutils::to_bignum(0u64));
         let mut acc = cutils::Value::new(&cutils::to_bignum(0u64));
 
-        let mut needed_value = supporting_functions::sum_output_values(&txouts);
+        let mut needed_value = cutils::Value::new(&to_bignum(total_price));
         needed_value.set_coin(&needed_value.coin().checked_add(&fee.clone()).unwrap());
         let security = cutils::to_bignum(
-            cutils::from_bignum(&needed_value.coin()) / 100 * 10 + (2 * models::MIN_ADA),
+            cutils::from_bignum(&needed_value.coin()) / 100 * 10
+                + ((2 + self.listings.len() as u64) * models::MIN_ADA),
         ); // 10% Security for min utxo etc.
         needed_value.set_coin(&needed_value.coin().checked_add(&security).unwrap());
 
@@ -126,7 +165,7 @@ impl<'a> super::PerformTxb<AtMPBuyParam<'a>> for AtMPBuyBuilder {
 
         for utxo in script_utxos.0.iter() {
             txins.add(&utxo.input());
-            input_txuos.add(&utxo);
+            input_txuos.add(utxo);
         }
 
         let vkey_counter =
@@ -150,8 +189,8 @@ impl<'a> super::PerformTxb<AtMPBuyParam<'a>> for AtMPBuyBuilder {
         )?;
         let txouts_fin = combine_wallet_outputs(&txouts_fin);
 
-        let slot = gtxd.get_current_slot() + 3000;
-        let mut txbody = clib::TransactionBody::new_tx_body(&txins, &txouts_fin, fee); //922321
+        let slot = gtxd.get_current_slot() + SlotConfig::for_network(&network)?.slots_in(3000);
+        let mut txbody = clib::TransactionBody::new_tx_body(&txins, &txouts_fin, fee);
         txbody.set_ttl(&cutils::to_bignum(slot));
         trace!("\nTxOutputs: {:?}\n", txbody.outputs());
         trace!("\nTxInouts: {:?}\n", txbody.inputs());
@@ -161,13 +200,7 @@ impl<'a> super::PerformTxb<AtMPBuyParam<'a>> for AtMPBuyBuilder {
         //
         ///////////////////////////////////////////////////////////////////////////////////////////////////////
         let mut req_signers = clib::Ed25519KeyHashes::new();
-        let owner_keyhash = caddr::BaseAddress::from_address(&self.owner)
-            .unwrap()
-            .payment_cred()
-            .to_keyhash()
-            .unwrap();
-        req_signers.add(&owner_keyhash);
-
+        req_signers.add(&supporting_functions::get_payment_keyhash(&self.owner));
         txbody.set_required_signers(&req_signers);
 
         // Collateral Input
@@ -179,75 +212,8 @@ impl<'a> super::PerformTxb<AtMPBuyParam<'a>> for AtMPBuyBuilder {
         if txbody.collateral().is_none() {
             return Err(MurinError::new("Error: No collateral provided"));
         }
-        //////////////////////////////////////////////////////////////////////////////////////////////////////
-        //Redeemer
-        //  Build Redeemer
-        //
-        ///////////////////////////////////////////////////////////////////////////////////////////////////////
-        let mut redeemers = plutus::Redeemers::new();
-
-        let protocol_parameters: ProtocolParameters = ProtocolParameters::read_protocol_parameter(
-            &std::env::var("CARDANO_PROTOCOL_PARAMETER_PATH")
-                .unwrap_or_else(|_| "/odin/protocol_parameters_babbage.json".to_owned()),
-        )
-        .unwrap();
-        let exunits = ExUnits::new(
-            &to_bignum(protocol_parameters.max_tx_execution_units.memory as u64 / 4),
-            &to_bignum(protocol_parameters.max_tx_execution_units.steps as u64 / 4),
-        );
-
-        for utxo in script_utxos.0.iter() {
-            let script_input_index = get_input_position(txins.clone(), utxo.clone());
-
-            let redeemer_data =
-                plutus::PlutusData::new_constr_plutus_data(&plutus::ConstrPlutusData::new(
-                    &cutils::to_bignum(1u64),
-                    &plutus::PlutusList::new(),
-                ));
-
-            let red = plutus::Redeemer::new(
-                &plutus::RedeemerTag::new_spend(),
-                &cutils::to_bignum(script_input_index.0 as u64),
-                &redeemer_data,
-                &exunits,
-            );
-
-            redeemers.add(&red);
-            debug!("Redeemer: {:?} \n", red);
-        }
-
-        debug!("Redeemers: {:?} \n", hex::encode(redeemers.to_bytes()));
-
-        // CostModel
-        let cost_models = protocol_parameters.get_CostMdls().unwrap();
-        let costmodel = cost_models
-            .get(&crate::pparams::mp_plutus::Language::new_plutus_v2())
-            .unwrap();
-        let mut pcm = plutus::CostModel::new();
-        for (i, o) in costmodel.op_costs.iter().enumerate() {
-            pcm.set(i, o)?;
-        }
-        let mut cstmodls = plutus::Costmdls::new();
-        cstmodls.insert(&plutus::Language::new_plutus_v2(), &pcm);
-
-        let costmodel = cost_models
-            .get(&crate::pparams::mp_plutus::Language::new_plutus_v2())
-            .unwrap();
-        let mut cstmodls_ = crate::pparams::mp_plutus::Costmdls::new();
-        cstmodls_.insert(&costmodel);
-
-        let scriptdatahash = crate::pparams::hash::hash_script_data(&redeemers, &cstmodls_, None);
-        log::debug!(
-            "ScriptDataHash: {:?}\n",
-            hex::encode(scriptdatahash.to_bytes())
-        );
-        txbody.set_script_data_hash(&scriptdatahash);
 
-        let mut txwitness = clib::TransactionWitnessSet::new();
-        let mut scripts = PlutusScripts::new();
-        scripts.add(&self.contract);
-        txwitness.set_plutus_scripts(&scripts);
-        txwitness.set_redeemers(&redeemers);
+        let txwitness = script_witness(&mut txbody, &script_utxos, &self.contract, BUY_REDEEMER)?;
 
         info!("--------------------Iteration Ended------------------------------");
         info!("Vkey Counter at End: {:?}", vkey_counter);
@@ -261,3 +227,147 @@ impl<'a> super::PerformTxb<AtMPBuyParam<'a>> for AtMPBuyBuilder {
         ))
     }
 }
+
+/// Builds a purchase of all `listings` in one transaction. If they do not fit
+/// into the protocol limits, the error lists the listings ('txhash#index') which
+/// must be bought in another transaction, the ones before them fit.
+pub async fn build_sweep(
+    txb: &TxBuilder,
+    contract: &PlutusScript,
+    sc_address: &caddr::Address,
+    owner: &caddr::Address,
+    listings: &[MpTxData],
+) -> Result<BuildOutput, MurinError> {
+    sweep(listings, |cart: &[MpTxData]| {
+        let builder = AtMPBuyBuilder::new((contract, sc_address, owner, cart));
+        async move { txb.build(&builder).await }
+    })
+    .await
+}
+
+/// Runs `build` on all listings and, if they exceed the transaction limits,
+/// searches the largest cart from the start of the listings which still fits
+async fn sweep<T, F, Fut>(listings: &[MpTxData], build: F) -> Result<T, MurinError>
+where
+    F: Fn(&[MpTxData]) -> Fut,
+    Fut: std::future::Future<Output = Result<T, MurinError>>,
+{
+    match build(listings).await {
+        Err(MurinError::TxLimitExceeded { .. }) if listings.len() > 1 => {}
+        result => return result,
+    }
+
+    // Largest number of listings from the start of the cart which still fits
+    let (mut fits, mut exceeds) = (0, listings.len());
+    while exceeds - fits > 1 {
+        let cart = (fits + exceeds) / 2;
+        match build(&listings[..cart]).await {
+            Ok(_) => fits = cart,
+            Err(MurinError::TxLimitExceeded { .. }) => exceeds = cart,
+            Err(e) => return Err(e),
+        }
+    }
+
+    let split_off = listings[fits..]
+        .iter()
+        .flat_map(|l| l.get_listing_refs())
+        .collect::<Vec<_>>();
+    Err(MurinError::TxLimitExceeded {
+        message: format!(
+            "only {} of {} listings fit into one transaction, split off: {}",
+            fits,
+            listings.len(),
+            split_off.join(", ")
+        ),
+        split_off,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    fn listing(i: u8) -> MpTxData {
+        let input = clib::TransactionInput::new(
+            &clib::crypto::TransactionHash::from_bytes(vec![i; 32]).unwrap(),
+            1,
+        );
+        let addr = caddr::Address::from_bech32("addr_test1qp8cprhse9pnnv7f4l3n6pj0afq2hjm6f7r2205dz0583egaeu9dhacmtx94652q4ym0v9v2mcra0n28d5lrtjqzsgxqgk5t8s").unwrap();
+        let output =
+            clib::TransactionOutput::new(&addr, &cutils::Value::new(&to_bignum(2_000_000)));
+        let mut utxos = TransactionUnspentOutputs::new();
+        utxos.add(&TransactionUnspentOutput::new(&input, &output));
+        MpTxData::new(vec![], utxos, 10_000_000)
+    }
+
+    // Fails like the transaction builder once the cart holds more than `max` listings
+    async fn fake_build(cart: &[MpTxData], max: usize) -> Result<usize, MurinError> {
+        if cart.len() > max {
+            return Err(MurinError::TxLimitExceeded {
+                message: "too large".to_string(),
+                split_off: vec![],
+            });
+        }
+        Ok(cart.len())
+    }
+
+    #[tokio::test]
+    async fn sweep_fits_at_once() {
+        let listings = (0..5).map(listing).collect::<Vec<_>>();
+        let calls = RefCell::new(0);
+        let result = sweep(&listings, |cart| {
+            *calls.borrow_mut() += 1;
+            fake_build(cart, 5)
+        })
+        .await;
+        assert_eq!(result.unwrap(), 5);
+        assert_eq!(*calls.borrow(), 1);
+    }
+
+    #[tokio::test]
+    async fn sweep_splits_off_what_does_not_fit() {
+        let listings = (0..7).map(listing).collect::<Vec<_>>();
+        for max in 0..7 {
+            match sweep(&listings, |cart| fake_build(cart, max)).await {
+                Err(MurinError::TxLimitExceeded { message, split_off }) => {
+                    let expected = listings[max..]
+                        .iter()
+                        .flat_map(|l| l.get_listing_refs())
+                        .collect::<Vec<_>>();
+                    assert_eq!(split_off, expected);
+                    assert!(message.starts_with(&format!("only {max} of 7 listings fit")));
+                }
+                other => panic!("expected TxLimitExceeded, got {other:?}"),
+            }
+        }
+        assert_eq!(
+            listing(3).get_listing_refs(),
+            vec![format!("{}#1", hex::encode([3u8; 32]))]
+        );
+    }
+
+    #[tokio::test]
+    async fn sweep_single_listing_and_other_errors() {
+        // A single listing which is too large has nothing to split
+        let one = vec![listing(0)];
+        match sweep(&one, |cart| fake_build(cart, 0)).await {
+            Err(MurinError::TxLimitExceeded { split_off, .. }) => assert!(split_off.is_empty()),
+            other => panic!("expected TxLimitExceeded, got {other:?}"),
+        }
+
+        // Errors other than the limits are passed through during the search
+        let listings = (0..4).map(listing).collect::<Vec<_>>();
+        let result = sweep(&listings, |cart| async move {
+            match cart.len() {
+                4 => fake_build(cart, 0).await,
+                _ => Err(MurinError::new("listing is sold")),
+            }
+        })
+        .await;
+        match result {
+            Err(MurinError::Custom(message)) => assert_eq!(message, "listing is sold"),
+            other => panic!("expected the build error, got {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use crate::cardano::{models, SlotConfig};
+use crate::error::MurinError;
+use crate::marketplace::*;
+use crate::modules::txtools::utxo_handling::combine_wallet_outputs;
+use cardano_serialization_lib as clib;
+use cardano_serialization_lib::{address as caddr, utils as cutils};
+use clib::plutus::PlutusScript;
+use models::Tokens;
+
+/// Withdraws an offer or collection bid, the locked value goes back to the buyer.
+#[derive(Debug, Clone)]
+pub struct AtMPCancelOfferBuilder {
+    pub contract: PlutusScript,
+    pub sc_address: caddr::Address,
+    pub owner: caddr::Address,
+    pub offer: MpOfferTxData,
+}
+
+pub type AtMPCancelOfferParam<'a> = (
+    &'a PlutusScript,
+    &'a caddr::Address,
+    &'a caddr::Address,
+    &'a MpOfferTxData,
+);
+
+impl<'a> super::PerformTxb<AtMPCancelOfferParam<'a>> for AtMPCancelOfferBuilder {
+    fn new(t: AtMPCancelOfferParam) -> Self {
+        AtMPCancelOfferBuilder {
+            contract: t.0.clone(),
+            sc_address: t.1.clone(),
+            owner: t.2.clone(),
+            offer: t.3.clone(),
+        }
+    }
+
+    fn script_utxos(&self) -> models::TransactionUnspentOutputs {
+        self.offer.get_offer_utxos()
+    }
+
+    fn perform_txb(
+        &self,
+        fee: &clib::utils::BigNum,
+        gtxd: &TxData,
+        _pvks: &[String],
+        fcrun: bool,
+    ) -> std::result::Result<TxBO, MurinError> {
+        if fcrun {
+            info!("--------------------------------------------------------------------------------------------------------");
+            info!("-----------------------------------------Fee Calculation------------------------------------------------");
+            info!("---------------------------------------------------------------------------------------------------------\n");
+        } else {
+            info!("--------------------------------------------------------------------------------------------------------");
+            info!("-----------------------------------------Build Transaction----------------------------------------------");
+            info!("--------------------------------------------------------------------------------------------------------\n");
+        }
+
+        /////////////////////////////////////////////////////////////////////////////////////////////////////
+        //Restore Datum
+        /////////////////////////////////////////////////////////////////////////////////////////////////////
+        let (offer_utxo, datum) = restore_offer(&self.offer.get_offer_utxos())?;
+        let owner_keyhash = supporting_functions::get_payment_keyhash(&self.owner);
+        if owner_keyhash != datum.buyer {
+            return Err(MurinError::new(
+                "Error: only the buyer can cancel the offer",
+            ));
+        }
+
+        //////////////////////////////////////////////////////////////////////////////////////////////////////
+        //Add Inputs and Outputs
+        ///////////////////////////////////////////////////////////////////////////////////////////////////////
+        let mut txouts = clib::TransactionOutputs::new();
+        txouts.add(&min_ada_for_utxo(&TransactionOutput::new(
+            &self.owner,
+            &offer_utxo.output().amount(),
+        ))?);
+
+        let mut input_txuos = gtxd.clone().get_inputs();
+
+        // Check if some utxos in inputs are in use and remove them
+        if let Some(used_utxos) = crate::utxomngr::usedutxos::check_any_utxo_used(&input_txuos)? {
+            input_txuos.remove_used_utxos(used_utxos);
+        }
+
+        let collateral_input_txuo = gtxd.get_collateral();
+        info!("\nCollateral Input: {:?}", collateral_input_txuo);
+
+        // Balance TX
+        debug!("Before Balance: Transaction Inputs: {:?}", input_txuos);
+        debug!("Before Balance: Transaction Outputs: {:?}", txouts);
+
+        let mut fee_paid = false;
+        let mut first_run = true;
+        let mut txos_paid = false;
+        let mut tbb_values = cutils::Value::new(&cutils::to_bignum(0u64));
+        let mut acc = cutils::Value::new(&cutils::to_bignum(0u64));
+
+        // The offer utxo pays the output, the wallet only needs to cover the fee
+        let mut needed_value = cutils::Value::new(&fee.clone());
+        let security = cutils::to_bignum(2 * models::MIN_ADA);
+        needed_value.set_coin(&needed_value.coin().checked_add(&security).unwrap());
+
+        let (signers_address_utxos, _) =
+            supporting_functions::find_utxos_by_address(self.owner.clone(), &input_txuos);
+
+        if signers_address_utxos.is_empty() {
+            return Err(format!("The Pubkey which signed the offer has no utxos, please send some Ada to it. Address:{:?}",self.owner.to_bech32(None)).into());
+        }
+
+        let (mut txins, mut input_txuos) =
+            input_selection(None, &mut needed_value, &input_txuos, None, None)?;
+        let saved_input_txuos = input_txuos.clone();
+
+        if !input_txuos.contains_any(&signers_address_utxos) {
+            txins.add(&signers_address_utxos.0[0].input());
+            input_txuos.add(&signers_address_utxos.0[0])
+        }
+
+        txins.add(&offer_utxo.input());
+        input_txuos.add(&offer_utxo);
+
+        let vkey_counter =
+            supporting_functions::get_vkey_count(&input_txuos, collateral_input_txuo.as_ref());
+
+        let txouts_fin = supporting_functions::balance_tx(
+            &mut input_txuos,
+            &Tokens::new(),
+            &mut txouts,
+            None,
+            fee,
+            &mut fee_paid,
+            &mut first_run,
+            &mut txos_paid,
+            &mut tbb_values,
+            &self.owner,
+            &self.owner,
+            &mut acc,
+            None,
+            &fcrun,
+        )?;
+        let txouts_fin = combine_wallet_outputs(&txouts_fin);
+
+        let slot =
+            gtxd.get_current_slot() + SlotConfig::for_network(&gtxd.get_network())?.slots_in(3000);
+        let mut txbody = clib::TransactionBody::new_tx_body(&txins, &txouts_fin, fee);
+        txbody.set_ttl(&cutils::to_bignum(slot));
+
+        //////////////////////////////////////////////////////////////////////////////////////////////////////
+        //Required Signer
+        //
+        ///////////////////////////////////////////////////////////////////////////////////////////////////////
+        let mut req_signers = clib::Ed25519KeyHashes::new();
+        req_signers.add(&owner_keyhash);
+        txbody.set_required_signers(&req_signers);
+
+        // Collateral Input
+        let mut col_inputs = clib::TransactionInputs::new();
+        if let Some(collateral) = collateral_input_txuo {
+            col_inputs.add(&collateral.input());
+            txbody.set_collateral(&col_inputs);
+        };
+        if txbody.collateral().is_none() {
+            return Err(MurinError::new("Error: No collateral provided"));
+        }
+
+        let txwitness = spend_script_witness(
+            &mut txbody,
+            &offer_utxo,
+            &self.contract,
+            CANCEL_OFFER_REDEEMER,
+        )?;
+
+        info!("--------------------Iteration Ended------------------------------");
+        info!("Vkey Counter at End: {:?}", vkey_counter);
+        Ok((
+            txbody,
+            txwitness,
+            None,
+            saved_input_txuos,
+            vkey_counter,
+            true,
+        ))
+    }
+}
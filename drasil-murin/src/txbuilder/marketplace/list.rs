@@ -54,7 +54,7 @@ impl<'a> super::PerformTxb<AtMPListParam<'a>> for AtMPListBuilder {
         }
 
         let roy_rate = if let Some(royrate) = self.mptxd.clone().get_royalties_rate() {
-            (royrate * 1000.0) as u64
+            (royrate * 1000.0).round() as u64
         } else {
             0u64
         };
@@ -73,6 +73,8 @@ impl<'a> super::PerformTxb<AtMPListParam<'a>> for AtMPListBuilder {
                 royalties_pkh = None;
                 royalties_address = None;
             }
+        } else if roy_rate > 0u64 {
+            return Err(MurinError::new("royalties declared without an address"));
         } else {
             royalties_pkh = None;
             royalties_address = None;
@@ -173,7 +175,7 @@ impl<'a> super::PerformTxb<AtMPListParam<'a>> for AtMPListBuilder {
         let txouts_fin = combine_wallet_outputs(&txouts_fin);
 
         let slot = gtxd.clone().get_current_slot()
-            + supporting_functions::get_ttl_tx(&gtxd.clone().get_network());
+            + supporting_functions::get_ttl_tx(&gtxd.clone().get_network())?;
         let mut txbody = clib::TransactionBody::new_tx_body(&txins, &txouts_fin, fee);
         txbody.set_ttl(&cutils::to_bignum(slot));
 
@@ -3,19 +3,50 @@ use cardano_serialization_lib::{address as caddr, crypto as ccrypto, utils as cu
 use clib::crypto::Ed25519KeyHash;
 use clib::{plutus, AssetName, PolicyID};
 
+pub mod accept_offer;
 pub mod buy;
 pub mod cancel;
+pub mod cancel_offer;
 pub mod list;
+pub mod offer;
 pub mod update;
 
+use self::minter::cip27::Cip27Royalty;
 use self::models::TxInput;
+use super::scripts::{
+    bytes_field, int_field, key_address, key_field, royalty_terms, script_witness,
+    spend_script_witness,
+};
 
 pub use super::*;
+pub use accept_offer::*;
 pub use buy::*;
 pub use cancel::*;
+pub use cancel_offer::*;
 pub use list::*;
+pub use offer::*;
 pub use update::*;
 
+/// Redeemer of the buyer spending a listing
+pub const BUY_REDEEMER: u64 = 0;
+
+// Offers and collection bids are locked at their own validator, the `MarketplaceOffer`
+// contract, the listing script does not know their datum. The validator has to
+// implement this interface:
+//
+// Datum `Constr 0 [price, buyer, buyer_stake, policy_id, token_name, royalties_rate, royalties_pkh]`,
+// see `encode_offer_datum`. Empty `buyer_stake`, `token_name` and `royalties_pkh` are absent
+// values, an empty token name makes the offer a bid on any token of the policy.
+//
+// `ACCEPT_OFFER_REDEEMER` spends the offer if the transaction pays the offered token to
+// the buyer address and at least `price / 1000 * royalties_rate` to `royalties_pkh`.
+// `CANCEL_OFFER_REDEEMER` spends the offer if the transaction is signed by `buyer`.
+
+/// Redeemer of the holder selling a token into an offer or collection bid
+pub const ACCEPT_OFFER_REDEEMER: u64 = 0;
+/// Redeemer of the buyer withdrawing an offer or collection bid
+pub const CANCEL_OFFER_REDEEMER: u64 = 1;
+
 #[derive(Debug, Clone)]
 pub struct MpTxData {
     tokens: Vec<TokenAsset>,
@@ -154,6 +185,13 @@ impl MpTxData {
         self.royalties_rate = Some(royrate);
     }
 
+    /// Sets the royalties declared by the CIP-27 token of the traded policy
+    pub fn set_royalties(&mut self, royalty: &Cip27Royalty) -> Result<(), MurinError> {
+        self.royalties_addr = Some(royalty.get_addr()?);
+        self.royalties_rate = Some(royalty.rate);
+        Ok(())
+    }
+
     pub fn get_tokens(&self) -> &Vec<TokenAsset> {
         &self.tokens
     }
@@ -173,6 +211,314 @@ impl MpTxData {
     pub fn get_price(&self) -> u64 {
         self.selling_price
     }
+
+    /// Restores a listing from the datum of its marketplace script UTxO
+    pub fn from_listing_utxo(utxo: &TransactionUnspentOutput) -> Result<MpTxData, MurinError> {
+        let datum = utxo
+            .output()
+            .plutus_data()
+            .ok_or_else(|| MurinError::new("Error: listing utxo has no inline datum"))?;
+        let datum = decode_mp_datum(&datum.to_bytes())?;
+        let mut token_utxos = TransactionUnspentOutputs::new();
+        token_utxos.add(utxo);
+        Ok(MpTxData::new(
+            vec![(datum.policy_id, datum.token_name, to_bignum(1))],
+            token_utxos,
+            datum.price,
+        ))
+    }
+
+    /// Reference 'txhash#index' of the listing UTxOs
+    pub fn get_listing_refs(&self) -> Vec<String> {
+        self.token_utxos
+            .0
+            .iter()
+            .map(|u| {
+                format!(
+                    "{}#{}",
+                    hex::encode(u.input().transaction_id().to_bytes()),
+                    u.input().index()
+                )
+            })
+            .collect()
+    }
+}
+
+/// Offer on a single token or, without token name, a bid on any token of a policy
+#[derive(Debug, Clone)]
+pub struct MpOfferTxData {
+    policy_id: PolicyID,
+    token_name: Option<AssetName>,
+    price: u64,
+    offer_utxos: TransactionUnspentOutputs,
+    token: Option<TokenAsset>,
+    royalties_addr: Option<caddr::Address>,
+    royalties_rate: Option<f32>,
+}
+
+impl ToString for MpOfferTxData {
+    fn to_string(&self) -> String {
+        let s_token_name = match &self.token_name {
+            Some(n) => hex::encode(n.to_bytes()),
+            None => "NoData".to_string(),
+        };
+        let s_offer_utxos = match self.offer_utxos.to_hex() {
+            Ok(s) if !self.offer_utxos.is_empty() => s,
+            _ => "NoData".to_string(),
+        };
+        let s_token = match &self.token {
+            Some(t) => {
+                hex::encode(t.0.to_bytes())
+                    + "?"
+                    + &hex::encode(t.1.to_bytes())
+                    + "?"
+                    + &hex::encode(t.2.to_bytes())
+            }
+            None => "NoData".to_string(),
+        };
+        let s_royaddr = match &self.royalties_addr {
+            Some(a) => hex::encode(a.to_bytes()),
+            None => "NoData".to_string(),
+        };
+        let s_royrate = match self.royalties_rate {
+            Some(r) => r.to_string(),
+            None => "NoData".to_string(),
+        };
+
+        [
+            hex::encode(self.policy_id.to_bytes()),
+            s_token_name,
+            self.price.to_string(),
+            s_offer_utxos,
+            s_token,
+            s_royaddr,
+            s_royrate,
+        ]
+        .join("|")
+    }
+}
+
+impl std::str::FromStr for MpOfferTxData {
+    type Err = MurinError;
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let slice: Vec<&str> = src.split('|').collect();
+        if slice.len() != 7 {
+            return Err(MurinError::new(&format!(
+                "Error the provided string '{src}' cannot be parsed into 'MpOfferTxData' "
+            )));
+        }
+        let token_name = match slice[1] {
+            "NoData" => None,
+            _ => Some(AssetName::from_bytes(hex::decode(slice[1])?)?),
+        };
+        let offer_utxos = match slice[3] {
+            "NoData" => TransactionUnspentOutputs::new(),
+            _ => TransactionUnspentOutputs::from_hex(slice[3])?,
+        };
+        let token = match slice[4] {
+            "NoData" => None,
+            _ => {
+                let t: Vec<&str> = slice[4].split('?').collect();
+                if t.len() != 3 {
+                    return Err(MurinError::new("Error: invalid token in offer data"));
+                }
+                Some((
+                    PolicyID::from_bytes(hex::decode(t[0])?)?,
+                    AssetName::from_bytes(hex::decode(t[1])?)?,
+                    cutils::BigNum::from_bytes(hex::decode(t[2])?)?,
+                ))
+            }
+        };
+        let royalties_addr = match slice[5] {
+            "NoData" => None,
+            _ => Some(caddr::Address::from_bytes(hex::decode(slice[5])?)?),
+        };
+        let royalties_rate = match slice[6] {
+            "NoData" => None,
+            _ => Some(slice[6].parse::<f32>()?),
+        };
+        Ok(MpOfferTxData {
+            policy_id: PolicyID::from_bytes(hex::decode(slice[0])?)?,
+            token_name,
+            price: slice[2].parse::<u64>()?,
+            offer_utxos,
+            token,
+            royalties_addr,
+            royalties_rate,
+        })
+    }
+}
+
+impl MpOfferTxData {
+    pub fn new(policy_id: PolicyID, token_name: Option<AssetName>, price: u64) -> MpOfferTxData {
+        MpOfferTxData {
+            policy_id,
+            token_name,
+            price,
+            offer_utxos: TransactionUnspentOutputs::new(),
+            token: None,
+            royalties_addr: None,
+            royalties_rate: None,
+        }
+    }
+
+    /// Restores the offer data from the datum of an offer script UTxO
+    pub fn from_offer_utxo(utxo: &TransactionUnspentOutput) -> Result<MpOfferTxData, MurinError> {
+        let mut offer_utxos = TransactionUnspentOutputs::new();
+        offer_utxos.add(utxo);
+        let (_, datum) = restore_offer(&offer_utxos)?;
+        let mut offer = MpOfferTxData::new(datum.policy_id, datum.token_name, datum.price);
+        offer.set_offer_utxos(offer_utxos);
+        Ok(offer)
+    }
+
+    /// The script UTxO holding the offer, required to accept or cancel it
+    pub fn set_offer_utxos(&mut self, offer_utxos: TransactionUnspentOutputs) {
+        self.offer_utxos = offer_utxos;
+    }
+
+    /// The token the holder sells when accepting
+    pub fn set_token(&mut self, token: TokenAsset) {
+        self.token = Some(token);
+    }
+
+    /// Sets the royalties declared by the CIP-27 token of the policy
+    pub fn set_royalties(&mut self, royalty: &Cip27Royalty) -> Result<(), MurinError> {
+        self.royalties_addr = Some(royalty.get_addr()?);
+        self.royalties_rate = Some(royalty.rate);
+        Ok(())
+    }
+
+    pub fn get_policy_id(&self) -> &PolicyID {
+        &self.policy_id
+    }
+
+    pub fn get_token_name(&self) -> Option<&AssetName> {
+        self.token_name.as_ref()
+    }
+
+    pub fn get_price(&self) -> u64 {
+        self.price
+    }
+
+    pub fn get_offer_utxos(&self) -> TransactionUnspentOutputs {
+        self.offer_utxos.clone()
+    }
+
+    pub fn get_token(&self) -> Option<&TokenAsset> {
+        self.token.as_ref()
+    }
+
+    pub fn get_royalties_address(&self) -> Option<caddr::Address> {
+        self.royalties_addr.clone()
+    }
+
+    pub fn get_royalties_rate(&self) -> Option<f32> {
+        self.royalties_rate
+    }
+
+    /// Royalties in promille and the key hash they are paid to
+    fn royalties(&self) -> Result<(u64, Option<Ed25519KeyHash>), MurinError> {
+        royalty_terms(self.royalties_addr.as_ref(), self.royalties_rate)
+    }
+}
+
+/// Datum of an offer, the token name is empty for collection bids
+pub struct OfferDatum {
+    price: u64,
+    buyer: Ed25519KeyHash,
+    buyer_stake: Option<Ed25519KeyHash>,
+    policy_id: PolicyID,
+    token_name: Option<AssetName>,
+    royalties_rate: u64,
+    royalties_pkh: Option<Ed25519KeyHash>,
+}
+
+impl OfferDatum {
+    /// Address the bought token is paid to
+    pub fn buyer_address(&self, network: &clib::NetworkIdKind) -> caddr::Address {
+        key_address(network, &self.buyer, self.buyer_stake.as_ref())
+    }
+}
+
+/// Datum of the `MarketplaceOffer` validator
+pub fn encode_offer_datum(offer: &OfferDatum) -> plutus::PlutusData {
+    let mut fields = plutus::PlutusList::new();
+    fields.add(&int_field(offer.price));
+    fields.add(&key_field(Some(&offer.buyer)));
+    fields.add(&key_field(offer.buyer_stake.as_ref()));
+    fields.add(&bytes_field(offer.policy_id.to_bytes()));
+    fields.add(&bytes_field(
+        offer
+            .token_name
+            .as_ref()
+            .map(|n| n.name())
+            .unwrap_or_default(),
+    ));
+    fields.add(&int_field(offer.royalties_rate));
+    fields.add(&key_field(offer.royalties_pkh.as_ref()));
+    plutus::PlutusData::new_constr_plutus_data(&plutus::ConstrPlutusData::new(
+        &cutils::to_bignum(0),
+        &fields,
+    ))
+}
+
+pub fn decode_offer_datum(datum: &plutus::PlutusData) -> Result<OfferDatum, MurinError> {
+    let err = || MurinError::new("Error: datum is not a marketplace offer");
+    let constr = datum.as_constr_plutus_data().ok_or_else(err)?;
+    let fields = constr.data();
+    if cutils::from_bignum(&constr.alternative()) != 0 || fields.len() != 7 {
+        return Err(err());
+    }
+    let int = |i: usize| -> Result<u64, MurinError> {
+        fields
+            .get(i)
+            .as_integer()
+            .and_then(|n| n.as_u64())
+            .map(|n| cutils::from_bignum(&n))
+            .ok_or_else(err)
+    };
+    let bytes = |i: usize| fields.get(i).as_bytes().ok_or_else(err);
+    let keyhash = |i: usize| -> Result<Option<Ed25519KeyHash>, MurinError> {
+        let b = bytes(i)?;
+        if b.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(Ed25519KeyHash::from_bytes(b)?))
+        }
+    };
+
+    let token_name = bytes(4)?;
+    Ok(OfferDatum {
+        price: int(0)?,
+        buyer: keyhash(1)?.ok_or_else(err)?,
+        buyer_stake: keyhash(2)?,
+        policy_id: PolicyID::from_bytes(bytes(3)?)?,
+        token_name: if token_name.is_empty() {
+            None
+        } else {
+            Some(AssetName::new(token_name)?)
+        },
+        royalties_rate: int(5)?,
+        royalties_pkh: keyhash(6)?,
+    })
+}
+
+/// The offer datum of the single script UTxO in `offer_utxos`
+fn restore_offer(
+    offer_utxos: &TransactionUnspentOutputs,
+) -> Result<(TransactionUnspentOutput, OfferDatum), MurinError> {
+    if offer_utxos.len() != 1 {
+        return Err(MurinError::new(
+            "Error: exactly one offer can be accepted or cancelled at a time",
+        ));
+    }
+    let utxo = offer_utxos.get(0);
+    let datum = utxo
+        .output()
+        .plutus_data()
+        .ok_or_else(|| MurinError::new("Error: offer utxo has no inline datum"))?;
+    Ok((utxo, decode_offer_datum(&datum)?))
 }
 
 pub struct MarketPlaceDatum {
@@ -184,6 +530,57 @@ pub struct MarketPlaceDatum {
     token_name: AssetName,
 }
 
+impl MarketPlaceDatum {
+    /// Address the seller is paid to, the datum carries no stake key
+    pub fn seller_address(&self, network: &clib::NetworkIdKind) -> caddr::Address {
+        key_address(network, &self.seller, None)
+    }
+
+    pub fn price(&self) -> u64 {
+        self.price
+    }
+
+    pub fn seller(&self) -> Ed25519KeyHash {
+        self.seller.clone()
+    }
+
+    /// Royalties rate in promille
+    pub fn royalties_rate(&self) -> u64 {
+        self.royalties_rate
+    }
+
+    pub fn royalties_pkh(&self) -> Option<Ed25519KeyHash> {
+        self.royalties_pkh.clone()
+    }
+
+    pub fn policy_id(&self) -> PolicyID {
+        self.policy_id.clone()
+    }
+
+    pub fn token_name(&self) -> AssetName {
+        self.token_name.clone()
+    }
+}
+
+/// Fails if the listing `datum` pays less royalties than declared in `mptxd`
+pub fn check_datum_royalties(datum: &MarketPlaceDatum, mptxd: &MpTxData) -> Result<(), MurinError> {
+    let (roy_rate, roy_pkh) = royalty_terms(
+        mptxd.get_royalties_address().as_ref(),
+        mptxd.get_royalties_rate(),
+    )?;
+    if roy_rate == 0 {
+        return Ok(());
+    }
+    if datum.royalties_rate < roy_rate || datum.royalties_pkh != roy_pkh {
+        return Err(MurinError::new(&format!(
+            "listing of '{}' does not pay the declared royalties of {} promille",
+            hex::encode(datum.policy_id.to_bytes()),
+            roy_rate
+        )));
+    }
+    Ok(())
+}
+
 pub fn encode_mp_datum(mp: MarketPlaceDatum) -> (ccrypto::DataHash, plutus::PlutusData) {
     let roy_rate: u64;
     let roy_pkey_hash = if let Some(rpkh) = &mp.royalties_pkh {
@@ -200,9 +597,7 @@ pub fn encode_mp_datum(mp: MarketPlaceDatum) -> (ccrypto::DataHash, plutus::Plut
         &cutils::BigInt::from_str(&mp.price.to_string()).unwrap(),
     ));
     // Sellers PubKeyHash
-    fields_inner.add(&plutus::PlutusData::new_bytes(
-        hex::decode(&mp.seller.to_bytes()).unwrap(),
-    ));
+    fields_inner.add(&plutus::PlutusData::new_bytes(mp.seller.to_bytes()));
     // royalties rate in promille
     fields_inner.add(&plutus::PlutusData::new_integer(
         &cutils::BigInt::from_str(&roy_rate.to_string()).unwrap(),
@@ -210,13 +605,9 @@ pub fn encode_mp_datum(mp: MarketPlaceDatum) -> (ccrypto::DataHash, plutus::Plut
     // Royalties PubKeyHash
     fields_inner.add(&plutus::PlutusData::new_bytes(roy_pkey_hash));
     // PolicyId
-    fields_inner.add(&plutus::PlutusData::new_bytes(
-        hex::decode(&mp.policy_id.to_bytes()).unwrap(),
-    ));
+    fields_inner.add(&plutus::PlutusData::new_bytes(mp.policy_id.to_bytes()));
     // TokenName
-    fields_inner.add(&plutus::PlutusData::new_bytes(
-        hex::decode(&mp.token_name.name()).unwrap(),
-    ));
+    fields_inner.add(&plutus::PlutusData::new_bytes(mp.token_name.name()));
 
     debug!("\nFields Inner: \n{:?}\n", fields_inner);
 
@@ -234,81 +625,175 @@ pub fn encode_mp_datum(mp: MarketPlaceDatum) -> (ccrypto::DataHash, plutus::Plut
 }
 
 pub fn decode_mp_datum(bytes: &[u8]) -> Result<MarketPlaceDatum, MurinError> {
-    let datum = cardano_serialization_lib::plutus::PlutusData::from_bytes(bytes.to_vec())
-        .expect("Could not deserialize PlutusData");
+    let err = || MurinError::new("Error: datum is not a marketplace listing");
+    let datum = plutus::PlutusData::from_bytes(bytes.to_vec())?;
     log::debug!("Restored PlutusData: {:?}", datum);
-    let d_str = datum
-        .to_json(cardano_serialization_lib::plutus::PlutusDatumSchema::DetailedSchema)
-        .expect("Could not transform PlutusData to JSON");
-    log::info!("Restored PlutusData Str: {:?}", d_str);
-    let d_svalue = serde_json::from_str::<serde_json::Value>(&d_str)
-        .expect("Could not transform PlutusDataJson to serde_json::Value");
-    log::debug!("Deserialized Datum: \n{:?}", &d_str);
-    let fields = d_svalue.get("fields").unwrap().as_array().unwrap();
-
-    let price = fields[0].as_u64().unwrap();
-
-    let seller = Ed25519KeyHash::from_bytes(
-        hex::decode(
-            fields[1]
-                .as_object()
-                .unwrap()
-                .get("bytes")
-                .unwrap()
-                .as_str()
-                .unwrap(),
-        )
-        .unwrap(),
-    )
-    .unwrap();
-
-    let royalties_rate = fields[2].as_u64().unwrap();
-
-    let royalties_pkh = Ed25519KeyHash::from_bytes(
-        hex::decode(
-            fields[3]
-                .as_object()
-                .unwrap()
-                .get("bytes")
-                .unwrap()
-                .as_str()
-                .unwrap(),
+    let constr = datum.as_constr_plutus_data().ok_or_else(err)?;
+    let fields = constr.data();
+    if cutils::from_bignum(&constr.alternative()) != 0 || fields.len() != 6 {
+        return Err(err());
+    }
+    let int = |i: usize| -> Result<u64, MurinError> {
+        fields
+            .get(i)
+            .as_integer()
+            .and_then(|n| n.as_u64())
+            .map(|n| cutils::from_bignum(&n))
+            .ok_or_else(err)
+    };
+    let bytes = |i: usize| fields.get(i).as_bytes().ok_or_else(err);
+
+    // listings without royalties carry empty bytes
+    let royalties_pkh = bytes(3)?;
+    Ok(MarketPlaceDatum {
+        price: int(0)?,
+        seller: Ed25519KeyHash::from_bytes(bytes(1)?)?,
+        royalties_rate: int(2)?,
+        royalties_pkh: if royalties_pkh.is_empty() {
+            None
+        } else {
+            Some(Ed25519KeyHash::from_bytes(royalties_pkh)?)
+        },
+        policy_id: PolicyID::from_bytes(bytes(4)?)?,
+        token_name: AssetName::new(bytes(5)?)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn offer_datum_roundtrip() {
+        let addr = caddr::Address::from_bech32("addr_test1qp8cprhse9pnnv7f4l3n6pj0afq2hjm6f7r2205dz0583egaeu9dhacmtx94652q4ym0v9v2mcra0n28d5lrtjqzsgxqgk5t8s").unwrap();
+        let base = caddr::BaseAddress::from_address(&addr).unwrap();
+        let policy_id = PolicyID::from_bytes(
+            hex::decode("5a3f5bb4bf1a22f0a3a9a9d8e6e5b7a1d9d2d1c8b7a6f5e4d3c2b1a0").unwrap(),
         )
-        .unwrap(),
-    )
-    .unwrap();
+        .unwrap();
+        let bid = OfferDatum {
+            price: 25_000_000,
+            buyer: base.payment_cred().to_keyhash().unwrap(),
+            buyer_stake: base.stake_cred().to_keyhash(),
+            policy_id,
+            token_name: None,
+            royalties_rate: 50,
+            royalties_pkh: None,
+        };
 
-    let policy_id = PolicyID::from_hex(
-        fields[4]
-            .as_object()
-            .unwrap()
-            .get("bytes")
+        let decoded = decode_offer_datum(&encode_offer_datum(&bid)).unwrap();
+        assert_eq!(decoded.price, bid.price);
+        assert_eq!(decoded.buyer, bid.buyer);
+        assert_eq!(decoded.token_name, None);
+        assert_eq!(decoded.royalties_pkh, None);
+        assert_eq!(
+            decoded
+                .buyer_address(&clib::NetworkIdKind::Testnet)
+                .to_bytes(),
+            addr.to_bytes()
+        );
+
+        let mut offer = MpOfferTxData::new(
+            bid.policy_id.clone(),
+            Some(AssetName::new(b"Drasil01".to_vec()).unwrap()),
+            bid.price,
+        );
+        offer.royalties_rate = Some(0.05);
+        let restored = MpOfferTxData::from_str(&offer.to_string()).unwrap();
+        assert_eq!(restored.to_string(), offer.to_string());
+        assert!(offer.royalties().is_err());
+    }
+
+    #[test]
+    fn offer_datum_encoding() {
+        let policy_id = PolicyID::from_bytes(vec![3; 28]).unwrap();
+        let offer = OfferDatum {
+            price: 25_000_000,
+            buyer: Ed25519KeyHash::from_bytes(vec![1; 28]).unwrap(),
+            buyer_stake: None,
+            policy_id: policy_id.clone(),
+            token_name: Some(AssetName::new(b"Drasil01".to_vec()).unwrap()),
+            royalties_rate: 50,
+            royalties_pkh: Some(Ed25519KeyHash::from_bytes(vec![2; 28]).unwrap()),
+        };
+        let datum = encode_offer_datum(&offer);
+        let constr = datum.as_constr_plutus_data().unwrap();
+        assert_eq!(cutils::from_bignum(&constr.alternative()), 0);
+        let fields = constr.data();
+        assert_eq!(fields.len(), 7);
+        assert_eq!(fields.get(0).to_bytes(), int_field(25_000_000).to_bytes());
+        assert_eq!(fields.get(1).as_bytes().unwrap(), vec![1; 28]);
+        assert!(fields.get(2).as_bytes().unwrap().is_empty());
+        assert_eq!(fields.get(3).as_bytes().unwrap(), policy_id.to_bytes());
+        assert_eq!(fields.get(4).as_bytes().unwrap(), b"Drasil01".to_vec());
+        assert_eq!(fields.get(5).to_bytes(), int_field(50).to_bytes());
+        assert_eq!(fields.get(6).as_bytes().unwrap(), vec![2; 28]);
+
+        // listings are not offers
+        let (_, listing) = encode_mp_datum(MarketPlaceDatum {
+            price: 25_000_000,
+            seller: offer.buyer.clone(),
+            royalties_rate: 0,
+            royalties_pkh: None,
+            policy_id,
+            token_name: AssetName::new(b"Drasil01".to_vec()).unwrap(),
+        });
+        assert!(decode_offer_datum(&listing).is_err());
+    }
+
+    #[test]
+    fn listing_datum_roundtrip() {
+        let addr = caddr::Address::from_bech32("addr_test1qp8cprhse9pnnv7f4l3n6pj0afq2hjm6f7r2205dz0583egaeu9dhacmtx94652q4ym0v9v2mcra0n28d5lrtjqzsgxqgk5t8s").unwrap();
+        let seller = caddr::BaseAddress::from_address(&addr)
             .unwrap()
-            .as_str()
-            .unwrap(),
-    )
-    .unwrap();
-
-    let token_name = AssetName::new(
-        hex::decode(
-            fields[5]
-                .as_object()
-                .unwrap()
-                .get("bytes")
-                .unwrap()
-                .as_str()
-                .unwrap(),
+            .payment_cred()
+            .to_keyhash()
+            .unwrap();
+        let policy_id = PolicyID::from_bytes(
+            hex::decode("5a3f5bb4bf1a22f0a3a9a9d8e6e5b7a1d9d2d1c8b7a6f5e4d3c2b1a0").unwrap(),
         )
-        .unwrap(),
-    )
-    .unwrap();
+        .unwrap();
+        let listing = |royalties_pkh: Option<Ed25519KeyHash>| MarketPlaceDatum {
+            price: 40_000_000,
+            seller: seller.clone(),
+            royalties_rate: 25,
+            royalties_pkh,
+            policy_id: policy_id.clone(),
+            token_name: AssetName::new(b"Drasil02".to_vec()).unwrap(),
+        };
 
-    Ok(MarketPlaceDatum {
-        price,
-        seller,
-        royalties_rate,
-        royalties_pkh: Some(royalties_pkh),
-        policy_id,
-        token_name,
-    })
+        let royalties = Ed25519KeyHash::from_bytes(vec![2; 28]).unwrap();
+        let (hash, datum) = encode_mp_datum(listing(Some(royalties.clone())));
+        assert_eq!(hash.to_bytes(), cutils::hash_plutus_data(&datum).to_bytes());
+        let decoded = decode_mp_datum(&datum.to_bytes()).unwrap();
+        assert_eq!(decoded.price, 40_000_000);
+        assert_eq!(decoded.seller, seller);
+        assert_eq!(decoded.royalties_rate, 25);
+        assert_eq!(decoded.royalties_pkh, Some(royalties));
+        assert_eq!(decoded.policy_id, policy_id);
+        assert_eq!(decoded.token_name.name(), b"Drasil02".to_vec());
+        assert_eq!(
+            encode_mp_datum(decoded).1.to_bytes(),
+            datum.to_bytes(),
+            "decoding and encoding again gives the same datum"
+        );
+
+        // without royalties address the rate is not encoded
+        let (_, datum) = encode_mp_datum(listing(None));
+        let decoded = decode_mp_datum(&datum.to_bytes()).unwrap();
+        assert_eq!(decoded.royalties_rate, 0);
+        assert_eq!(decoded.royalties_pkh, None);
+        assert_eq!(
+            decoded
+                .seller_address(&clib::NetworkIdKind::Testnet)
+                .network_id()
+                .unwrap(),
+            0
+        );
+
+        // other datums are errors, not panics
+        assert!(decode_mp_datum(&int_field(1).to_bytes()).is_err());
+        assert!(decode_mp_datum(&[0xff]).is_err());
+    }
 }
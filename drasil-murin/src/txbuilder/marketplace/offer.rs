@@ -0,0 +1,158 @@
+use cardano_serialization_lib as clib;
+use cardano_serialization_lib::{address as caddr, utils as cutils};
+use clib::plutus::PlutusScript;
+use clib::TransactionWitnessSet;
+use models::Tokens;
+
+use crate::cardano::{models, supporting_functions};
+use crate::error::MurinError;
+use crate::marketplace::*;
+use crate::modules::txtools::utxo_handling::combine_wallet_outputs;
+
+/// Locks the offered price with an [`OfferDatum`] at the marketplace script.
+/// Without token name in the offer data it is a bid on any token of the policy.
+#[derive(Debug, Clone)]
+pub struct AtMPOfferBuilder {
+    pub contract: PlutusScript,
+    pub sc_address: caddr::Address,
+    pub owner: caddr::Address,
+    pub offer: MpOfferTxData,
+}
+
+pub type AtMPOfferParam<'a> = (
+    &'a PlutusScript,
+    &'a caddr::Address,
+    &'a caddr::Address,
+    &'a MpOfferTxData,
+);
+
+impl<'a> super::PerformTxb<AtMPOfferParam<'a>> for AtMPOfferBuilder {
+    fn new(t: AtMPOfferParam) -> Self {
+        AtMPOfferBuilder {
+            contract: t.0.clone(),
+            sc_address: t.1.clone(),
+            owner: t.2.clone(),
+            offer: t.3.clone(),
+        }
+    }
+
+    fn perform_txb(
+        &self,
+        fee: &clib::utils::BigNum,
+        gtxd: &TxData,
+        _pvks: &[String],
+        fcrun: bool,
+    ) -> std::result::Result<TxBO, MurinError> {
+        if fcrun {
+            info!("--------------------------------------------------------------------------------------------------------");
+            info!("-----------------------------------------Fee Calculation------------------------------------------------");
+            info!("---------------------------------------------------------------------------------------------------------\n");
+        } else {
+            info!("--------------------------------------------------------------------------------------------------------");
+            info!("-----------------------------------------Build Transaction----------------------------------------------");
+            info!("--------------------------------------------------------------------------------------------------------\n");
+        }
+
+        let (roy_rate, royalties_pkh) = self.offer.royalties()?;
+        let owner = caddr::BaseAddress::from_address(&self.owner)
+            .ok_or_else(|| MurinError::new("Error: offers need a base address"))?;
+        let offer_datum = OfferDatum {
+            price: self.offer.get_price(),
+            buyer: owner.payment_cred().to_keyhash().unwrap(),
+            buyer_stake: owner.stake_cred().to_keyhash(),
+            policy_id: self.offer.get_policy_id().clone(),
+            token_name: self.offer.get_token_name().cloned(),
+            royalties_rate: roy_rate,
+            royalties_pkh,
+        };
+
+        //////////////////////////////////////////////////////////////////////////////////////////////////////
+        //Add Inputs and Outputs
+        //
+        //
+        ///////////////////////////////////////////////////////////////////////////////////////////////////////
+        let trade_owner = &gtxd.clone().get_senders_addresses()[0];
+        let mut txouts = clib::TransactionOutputs::new();
+
+        let mut txo = TransactionOutput::new(
+            &self.sc_address,
+            &cutils::Value::new(&to_bignum(self.offer.get_price())),
+        );
+        txo.set_plutus_data(&encode_offer_datum(&offer_datum));
+        let txo = min_ada_for_utxo(&txo)?;
+        if cutils::from_bignum(&txo.amount().coin()) > self.offer.get_price() {
+            return Err(MurinError::new(
+                "Error: the offered price is below the minimum Ada of an output",
+            ));
+        }
+        txouts.add(&txo);
+
+        let mut input_txuos = gtxd.get_inputs().clone();
+
+        // Check if some utxos in inputs are in use and remove them
+        if let Some(used_utxos) = crate::utxomngr::usedutxos::check_any_utxo_used(&input_txuos)? {
+            input_txuos.remove_used_utxos(used_utxos);
+        }
+
+        // Balance TX
+
+        let mut fee_paid = false;
+        let mut first_run = true;
+        let mut txos_paid = false;
+        let mut tbb_values = cutils::Value::new(&cutils::to_bignum(0u64));
+        let mut acc = cutils::Value::new(&cutils::to_bignum(0u64));
+
+        let mut needed_value = supporting_functions::sum_output_values(&txouts);
+        needed_value.set_coin(&needed_value.coin().checked_add(&fee.clone()).unwrap());
+        let security = cutils::to_bignum(
+            cutils::from_bignum(&needed_value.coin()) / 100 * 10 + (2 * models::MIN_ADA),
+        ); // 10% Security for min utxo etc.
+        needed_value.set_coin(&needed_value.coin().checked_add(&security).unwrap());
+
+        debug!("Needed Value: {:?}", needed_value);
+
+        let (txins, mut input_txuos) =
+            input_selection(None, &mut needed_value, &input_txuos, None, None)?;
+        let saved_input_txuos = input_txuos.clone();
+
+        let vkey_counter = supporting_functions::get_vkey_count(&input_txuos, None);
+
+        let txouts_fin = supporting_functions::balance_tx(
+            &mut input_txuos,
+            &Tokens::new(),
+            &mut txouts,
+            None,
+            fee,
+            &mut fee_paid,
+            &mut first_run,
+            &mut txos_paid,
+            &mut tbb_values,
+            trade_owner,
+            trade_owner,
+            &mut acc,
+            None,
+            &fcrun,
+        )?;
+        let txouts_fin = combine_wallet_outputs(&txouts_fin);
+
+        let slot = gtxd.clone().get_current_slot()
+            + supporting_functions::get_ttl_tx(&gtxd.clone().get_network())?;
+        let mut txbody = clib::TransactionBody::new_tx_body(&txins, &txouts_fin, fee);
+        txbody.set_ttl(&cutils::to_bignum(slot));
+
+        let txwitness = TransactionWitnessSet::new();
+
+        debug!("TxWitness: {:?}", hex::encode(txwitness.to_bytes()));
+        debug!("TxBody: {:?}", hex::encode(txbody.to_bytes()));
+        debug!("--------------------Iteration Ended------------------------------");
+        debug!("Vkey Counter at End: {:?}", vkey_counter);
+        Ok((
+            txbody,
+            txwitness,
+            None,
+            saved_input_txuos,
+            vkey_counter,
+            false,
+        ))
+    }
+}
@@ -1,4 +1,4 @@
-use crate::cardano::models;
+use crate::cardano::{models, SlotConfig};
 use crate::error::MurinError;
 use crate::marketplace::*;
 use crate::modules::txtools::utxo_handling::combine_wallet_outputs;
@@ -32,6 +32,10 @@ impl<'a> super::PerformTxb<AtMPUpdateParam<'a>> for AtMPUpdateBuilder {
         }
     }
 
+    fn script_utxos(&self) -> models::TransactionUnspentOutputs {
+        self.mptxd.get_token_utxos()
+    }
+
     fn perform_txb(
         &self,
         fee: &clib::utils::BigNum,
@@ -66,6 +70,8 @@ impl<'a> super::PerformTxb<AtMPUpdateParam<'a>> for AtMPUpdateBuilder {
         if datums.len() != 1 {
             return Err("More than one datum found in contract utxos, currently only one trade at a time is supported".into());
         }
+        // Royalties are resolved from the CIP-27 token of the policy
+        check_datum_royalties(&datums[0], &self.mptxd)?;
 
         //////////////////////////////////////////////////////////////////////////////////////////////////////
         //Add Inputs and Outputs
@@ -150,7 +156,8 @@ impl<'a> super::PerformTxb<AtMPUpdateParam<'a>> for AtMPUpdateBuilder {
         )?;
         let txouts_fin = combine_wallet_outputs(&txouts_fin);
 
-        let slot = gtxd.get_current_slot() + 3000;
+        let slot =
+            gtxd.get_current_slot() + SlotConfig::for_network(&gtxd.get_network())?.slots_in(3000);
         let mut txbody = clib::TransactionBody::new_tx_body(&txins, &txouts_fin, fee); //922321
         txbody.set_ttl(&cutils::to_bignum(slot));
         trace!("\nTxOutputs: {:?}\n", txbody.outputs());
@@ -186,11 +193,7 @@ impl<'a> super::PerformTxb<AtMPUpdateParam<'a>> for AtMPUpdateBuilder {
         ///////////////////////////////////////////////////////////////////////////////////////////////////////
         let mut redeemers = plutus::Redeemers::new();
 
-        let protocol_parameters: ProtocolParameters = ProtocolParameters::read_protocol_parameter(
-            &std::env::var("CARDANO_PROTOCOL_PARAMETER_PATH")
-                .unwrap_or_else(|_| "/odin/protocol_parameters_babbage.json".to_owned()),
-        )
-        .unwrap();
+        let protocol_parameters: ProtocolParameters = crate::pparams::protocol_parameters()?;
         let exunits = ExUnits::new(
             &to_bignum(protocol_parameters.max_tx_execution_units.memory as u64 / 4),
             &to_bignum(protocol_parameters.max_tx_execution_units.steps as u64 / 4),
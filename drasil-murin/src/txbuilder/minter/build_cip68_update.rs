@@ -0,0 +1,168 @@
+use crate::cardano::supporting_functions::{
+    balance_tx, get_ttl_tx, get_vkey_count, sum_output_values,
+};
+use crate::cardano::{self, Tokens};
+use crate::error::MurinError;
+use crate::txbuilder::{harden, input_selection, min_ada_for_utxo, TxBO};
+use crate::{TransactionUnspentOutput, TransactionUnspentOutputs, TxData};
+use cardano_serialization_lib as clib;
+use cardano_serialization_lib::{crypto as ccrypto, utils as cutils};
+use clib::address::Address;
+use clib::plutus::PlutusData;
+
+/// CIP-68 Reference Datum Update Builder Type
+///
+/// Spends the UTxO holding a reference token and locks the same value at the
+/// same address again with the new datum. The reference token must be held by
+/// a key address the first key in `pvks` signs for, e.g. a liquidity wallet.
+#[derive(Debug, Clone)]
+pub struct AtCip68UpdBuilder {
+    pub reference_utxo: TransactionUnspentOutput,
+    pub datum: PlutusData,
+    pub change_addr: Address,
+}
+
+pub type AtCip68UpdParams<'a> = (&'a TransactionUnspentOutput, &'a PlutusData, &'a Address);
+
+impl<'a> super::PerformTxb<AtCip68UpdParams<'a>> for AtCip68UpdBuilder {
+    fn new(t: AtCip68UpdParams) -> Self {
+        AtCip68UpdBuilder {
+            reference_utxo: t.0.clone(),
+            datum: t.1.clone(),
+            change_addr: t.2.clone(),
+        }
+    }
+
+    fn perform_txb(
+        &self,
+        fee: &clib::utils::BigNum,
+        gtxd: &TxData,
+        pvks: &[String],
+        fcrun: bool,
+    ) -> std::result::Result<TxBO, MurinError> {
+        if fcrun {
+            info!("--------------------------------------------------------------------------------------------------------");
+            info!("-----------------------------------------Fee Calculation------------------------------------------------");
+            info!("---------------------------------------------------------------------------------------------------------\n");
+        } else {
+            info!("--------------------------------------------------------------------------------------------------------");
+            info!("-----------------------------------------Build Transaction----------------------------------------------");
+            info!("--------------------------------------------------------------------------------------------------------\n");
+        }
+
+        //////////////////////////////////////////////////////////////////////////////////////////////////////
+        //Add Inputs and Outputs
+        //
+        //
+        ///////////////////////////////////////////////////////////////////////////////////////////////////////
+        let reference_txo = self.reference_utxo.output();
+        let mut txo =
+            clib::TransactionOutput::new(&reference_txo.address(), &reference_txo.amount());
+        txo.set_plutus_data(&self.datum);
+
+        let mut txouts = clib::TransactionOutputs::new();
+        txouts.add(&min_ada_for_utxo(&txo)?);
+
+        // Inputs
+        let mut input_txuos = gtxd.clone().get_inputs();
+        // Check if some utxos in inputs are in use and remove them
+        if let Some(used_utxos) = crate::utxomngr::usedutxos::check_any_utxo_used(&input_txuos)? {
+            input_txuos.remove_used_utxos(used_utxos);
+        }
+        if let Some(i) = input_txuos.find_utxo_index(&self.reference_utxo) {
+            input_txuos.swap_remove(i);
+        }
+
+        // Balance TX
+        let mut fee_paid = false;
+        let mut first_run = true;
+        let mut txos_paid = false;
+        let mut tbb_values = cutils::Value::new(&cutils::to_bignum(0u64));
+        let mut acc = cutils::Value::new(&cutils::to_bignum(0u64));
+
+        let mut needed_value = sum_output_values(&txouts);
+        needed_value.set_coin(&needed_value.coin().checked_add(&fee.clone()).unwrap());
+        let security = cutils::to_bignum(
+            cutils::from_bignum(&needed_value.coin()) / 100 * 10 + (2 * cardano::MIN_ADA),
+        ); // 10% Security for min utxo etc.
+        needed_value.set_coin(&needed_value.coin().checked_add(&security).unwrap());
+        let mut needed_value = cutils::Value::new(&needed_value.coin());
+
+        let mut reference_utxos = TransactionUnspentOutputs::new();
+        reference_utxos.add(&self.reference_utxo);
+        let (txins, mut input_txuos) = input_selection(
+            Some(&reference_utxos),
+            &mut needed_value,
+            &input_txuos,
+            gtxd.clone().get_collateral(),
+            None,
+        )?;
+
+        let saved_input_txuos = input_txuos.clone();
+
+        let vkey_counter = get_vkey_count(&input_txuos, None);
+
+        let txouts_fin = balance_tx(
+            &mut input_txuos,
+            &Tokens::new(),
+            &mut txouts,
+            None,
+            fee,
+            &mut fee_paid,
+            &mut first_run,
+            &mut txos_paid,
+            &mut tbb_values,
+            &self.change_addr,
+            &self.change_addr,
+            &mut acc,
+            None,
+            &fcrun,
+        )?;
+
+        let slot = cutils::to_bignum(
+            gtxd.clone().get_current_slot() + get_ttl_tx(&gtxd.clone().get_network())?,
+        );
+        let mut txbody = clib::TransactionBody::new_tx_body(&txins, &txouts_fin, fee);
+        txbody.set_ttl(&slot);
+
+        // Set network Id
+        if gtxd.get_network() == clib::NetworkIdKind::Testnet {
+            txbody.set_network_id(&clib::NetworkId::testnet());
+        } else {
+            txbody.set_network_id(&clib::NetworkId::mainnet());
+        }
+
+        let mut txwitness = clib::TransactionWitnessSet::new();
+
+        let root_key1 = clib::crypto::Bip32PrivateKey::from_bytes(&hex::decode(&pvks[0])?)?;
+        let account_key1 = root_key1
+            .derive(harden(1852u32))
+            .derive(harden(1815u32))
+            .derive(harden(0u32));
+        let prv1 = account_key1.to_raw_key(); // for signatures
+        let vkwitness_1d1 = cutils::make_vkey_witness(&cutils::hash_transaction(&txbody), &prv1);
+
+        let mut vkeywitnesses = ccrypto::Vkeywitnesses::new();
+        vkeywitnesses.add(&vkwitness_1d1);
+        txwitness.set_vkeys(&vkeywitnesses);
+
+        debug!("TxWitness: {:?}", hex::encode(txwitness.to_bytes()));
+        debug!("TxBody: {:?}", hex::encode(txbody.to_bytes()));
+        debug!("--------------------Iteration Ended------------------------------");
+        debug!("Vkey Counter at End: {:?}", vkey_counter);
+        Ok((
+            txbody,
+            txwitness,
+            None,
+            saved_input_txuos,
+            vkey_counter,
+            false,
+        ))
+    }
+
+    fn script_utxos(&self) -> TransactionUnspentOutputs {
+        let mut utxos = TransactionUnspentOutputs::new();
+        utxos.add(&self.reference_utxo);
+        utxos
+    }
+}
@@ -7,6 +7,7 @@ use crate::minter::*;
 use crate::modules::txtools::utxo_handling::combine_wallet_outputs;
 use crate::ServiceFees;
 
+use crate::minter::cip68::{self, Cip68Config};
 use crate::minter::models::CMintHandle;
 use crate::txbuilder::{calc_min_ada_for_utxo, harden, input_selection, TxBO};
 use crate::TxData;
@@ -23,6 +24,7 @@ pub struct AtCMBuilder {
     pub metadata: Cip25Metadata,
     pub fees: Option<Vec<ServiceFees>>,
     pub stxd: ColMinterTxData,
+    pub cip68: Option<Cip68Config>,
 }
 
 pub type AtCMParams<'a> = (
@@ -31,6 +33,7 @@ pub type AtCMParams<'a> = (
     &'a Cip25Metadata,
     &'a Option<Vec<ServiceFees>>,
     &'a ColMinterTxData,
+    &'a Option<Cip68Config>,
 );
 
 impl<'a> super::PerformTxb<AtCMParams<'a>> for AtCMBuilder {
@@ -41,6 +44,7 @@ impl<'a> super::PerformTxb<AtCMParams<'a>> for AtCMBuilder {
             metadata: t.2.clone(),
             fees: t.3.clone(),
             stxd: t.4.clone(),
+            cip68: t.5.clone(),
         }
     }
 
@@ -70,15 +74,30 @@ impl<'a> super::PerformTxb<AtCMParams<'a>> for AtCMBuilder {
         let native_script = &self.scripts[0];
         let mintpolicy = native_script.hash();
 
+        let cip68 = match &self.cip68 {
+            Some(config) => {
+                let mut nfts = Tokens::new();
+                for handle in &self.stxd.mint_handles {
+                    for name in handle.nft_ids()? {
+                        nfts.push((mintpolicy.clone(), name, cutils::to_bignum(1)));
+                    }
+                }
+                Some(cip68::cip68_mint(&nfts, &self.metadata, config)?)
+            }
+            None => None,
+        };
+
         /////////////////////////////////////////////////////////////////////////////////////////////////////
         //
         //Auxiliary Data
         //  Plutus Script and Metadata
         /////////////////////////////////////////////////////////////////////////////////////////////////////
         let mut aux_data = clib::metadata::AuxiliaryData::new();
-        let metadata = make_mint_metadata(&self.metadata, mintpolicy.clone())?;
-        aux_data.set_metadata(&metadata);
-        let aux_data_hash = cutils::hash_auxiliary_data(&aux_data);
+        // CIP-68 metadata is held in the datum of the reference tokens
+        if cip68.is_none() {
+            let metadata = make_mint_metadata(&self.metadata, mintpolicy.clone())?;
+            aux_data.set_metadata(&metadata);
+        }
 
         //////////////////////////////////////////////////////////////////////////////////////////////////////
         //Add Inputs and Outputs
@@ -89,13 +108,23 @@ impl<'a> super::PerformTxb<AtCMParams<'a>> for AtCMBuilder {
         let mut txouts = clib::TransactionOutputs::new();
         let _zero_val = cutils::Value::new(&cutils::to_bignum(0u64));
 
-        let mut mint_val = CMintHandle::total_value(&self.stxd.mint_handles)?;
+        let mut mint_val = match &cip68 {
+            Some(cip68) => cardano::tokens_to_value(&cip68.user_tokens),
+            None => CMintHandle::total_value(&self.stxd.mint_handles)?,
+        };
         let min_utxo_val = calc_min_ada_for_utxo(&mint_val, None);
         mint_val.set_coin(&min_utxo_val);
         txouts.add(&clib::TransactionOutput::new(
             &gtxd.get_senders_address(None).unwrap(),
             &mint_val,
         ));
+        if let Some(cip68) = &cip68 {
+            for i in 0..cip68.reference_outputs.len() {
+                txouts.add(&cip68.reference_outputs.get(i));
+            }
+            // the reference tokens are minted as well
+            mint_val = cardano::tokens_to_value(&cip68.mint_tokens);
+        }
 
         let receiver = Address::from_bech32(&self.stxd.mint_handles[0].pay_addr)?;
 
@@ -109,6 +138,22 @@ impl<'a> super::PerformTxb<AtCMParams<'a>> for AtCMBuilder {
             }
         }
 
+        // Pay the prices of the minted NFTs
+        if let Some(prices) = &self.prices {
+            for price in prices.iter().filter(|p| !p.price.is_zero()) {
+                let seller = price.seller_address.as_ref().ok_or_else(|| {
+                    MurinError::new(&format!(
+                        "no address receives the price of mint handle {}",
+                        price.handle_id
+                    ))
+                })?;
+                txouts.add(&clib::TransactionOutput::new(
+                    &Address::from_bech32(seller)?,
+                    &cutils::Value::new(&price.price),
+                ));
+            }
+        }
+
         // Inputs
         let mut input_txuos = gtxd.clone().get_inputs();
         // Check if some utxos in inputs are in use and remove them
@@ -179,29 +224,40 @@ impl<'a> super::PerformTxb<AtCMParams<'a>> for AtCMBuilder {
         ////////////////////////////////////////////////////////////////////////////////////////////
         let mut mintasset = clib::MintAssets::new();
 
-        for token in &self.stxd.mint_handles {
-            for t in &token.nft_ids()? {
-                mintasset.insert(t, clib::utils::Int::new_i32(1));
+        match &cip68 {
+            Some(cip68) => {
+                for token in &cip68.mint_tokens {
+                    mintasset.insert(&token.1, clib::utils::Int::new(&token.2));
+                }
+            }
+            None => {
+                for token in &self.stxd.mint_handles {
+                    for t in &token.nft_ids()? {
+                        mintasset.insert(t, clib::utils::Int::new_i32(1));
+                    }
+                }
             }
         }
 
         let mint = clib::Mint::new_from_entry(&mintpolicy, &mintasset);
 
         let slot = cutils::to_bignum(
-            gtxd.clone().get_current_slot() + get_ttl_tx(&gtxd.clone().get_network()),
+            gtxd.clone().get_current_slot() + get_ttl_tx(&gtxd.clone().get_network())?,
         );
         log::info!("Added Slot: {:?}", slot);
         log::info!("Current Slot: {:?}", gtxd.clone().get_current_slot());
         log::info!(
             "Added Slot Time: {:?}",
-            get_ttl_tx(&gtxd.clone().get_network())
+            get_ttl_tx(&gtxd.clone().get_network())?
         );
         let mut txbody = clib::TransactionBody::new_tx_body(&txins, &txouts_fin, fee);
         txbody.set_ttl(&slot);
         info!("\nTxOutputs: {:?}\n", txbody.outputs());
         debug!("\nTxInputs: {:?}\n", txbody.inputs());
 
-        txbody.set_auxiliary_data_hash(&aux_data_hash);
+        if aux_data.metadata().is_some() {
+            txbody.set_auxiliary_data_hash(&cutils::hash_auxiliary_data(&aux_data));
+        }
 
         txbody.set_mint(&mint);
 
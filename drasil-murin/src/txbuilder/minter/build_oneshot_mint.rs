@@ -54,6 +54,14 @@ impl<'a> super::PerformTxb<AtOSMParams<'a>> for AtOSMBuilder {
         let mintpolicy = self.script.hash();
         debug!("Policy ID: {:?}", hex::encode(mintpolicy.to_bytes()));
         let minttokens = mintasset_into_tokenasset(self.stxd.get_mint_tokens(), mintpolicy.clone());
+        let cip68 = match self.stxd.get_cip68() {
+            Some(config) => Some(cip68::cip68_mint(
+                &minttokens,
+                &self.stxd.get_metadata(),
+                &config,
+            )?),
+            None => None,
+        };
 
         /////////////////////////////////////////////////////////////////////////////////////////////////////
         //
@@ -61,13 +69,15 @@ impl<'a> super::PerformTxb<AtOSMParams<'a>> for AtOSMBuilder {
         //  Plutus Script and Metadata
         /////////////////////////////////////////////////////////////////////////////////////////////////////
         let mut aux_data = clib::metadata::AuxiliaryData::new();
-        let metadata = make_mint_metadata_from_json(
-            &self.stxd.get_metadata(),
-            minttokens.clone(),
-            mintpolicy.clone(),
-        )?;
-        aux_data.set_metadata(&metadata);
-        let aux_data_hash = cutils::hash_auxiliary_data(&aux_data);
+        // CIP-68 metadata is held in the datum of the reference tokens
+        if cip68.is_none() {
+            let metadata = make_mint_metadata_from_json(
+                &self.stxd.get_metadata(),
+                minttokens.clone(),
+                mintpolicy.clone(),
+            )?;
+            aux_data.set_metadata(&metadata);
+        }
 
         //////////////////////////////////////////////////////////////////////////////////////////////////////
         //Add Inputs and Outputs
@@ -78,11 +88,25 @@ impl<'a> super::PerformTxb<AtOSMParams<'a>> for AtOSMBuilder {
         let mut txouts = clib::TransactionOutputs::new();
         let _zero_val = cutils::Value::new(&cutils::to_bignum(0u64));
 
-        let mut mint_val = tokens_to_value(&minttokens.clone());
-        let min_utxo_val = calc_min_ada_for_utxo(&mint_val, None);
-        mint_val.set_coin(&min_utxo_val);
-
-        txouts.add(&clib::TransactionOutput::new(&receiving_address, &mint_val));
+        let (minttokens, receiver_tokens) = match &cip68 {
+            Some(cip68) => (cip68.mint_tokens.clone(), cip68.user_tokens.clone()),
+            None => (minttokens.clone(), minttokens),
+        };
+
+        let mut receiver_val = tokens_to_value(&receiver_tokens);
+        let min_utxo_val = calc_min_ada_for_utxo(&receiver_val, None);
+        receiver_val.set_coin(&min_utxo_val);
+
+        txouts.add(&clib::TransactionOutput::new(
+            &receiving_address,
+            &receiver_val,
+        ));
+        if let Some(cip68) = &cip68 {
+            for i in 0..cip68.reference_outputs.len() {
+                txouts.add(&cip68.reference_outputs.get(i));
+            }
+        }
+        let mint_val = tokens_to_value(&minttokens);
 
         // Inputs
         let mut input_txuos = gtxd.clone().get_inputs();
@@ -162,12 +186,14 @@ impl<'a> super::PerformTxb<AtOSMParams<'a>> for AtOSMBuilder {
         let mint = clib::Mint::new_from_entry(&mintpolicy, &mintasset);
 
         let slot = cutils::to_bignum(
-            gtxd.clone().get_current_slot() + get_ttl_tx(&gtxd.clone().get_network()),
+            gtxd.clone().get_current_slot() + get_ttl_tx(&gtxd.clone().get_network())?,
         );
         let mut txbody = clib::TransactionBody::new_tx_body(&txins, &txouts_fin, fee);
         txbody.set_ttl(&slot);
 
-        txbody.set_auxiliary_data_hash(&aux_data_hash);
+        if aux_data.metadata().is_some() {
+            txbody.set_auxiliary_data_hash(&cutils::hash_auxiliary_data(&aux_data));
+        }
 
         txbody.set_mint(&mint);
 
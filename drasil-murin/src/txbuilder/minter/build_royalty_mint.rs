@@ -0,0 +1,193 @@
+use crate::cardano::models::*;
+use crate::cardano::supporting_functions::{
+    balance_tx, get_ttl_tx, get_vkey_count, sum_output_values,
+};
+use crate::error::MurinError;
+use crate::minter::cip27::{royalty_token_name, Cip27Royalty};
+use crate::txbuilder::{calc_min_ada_for_utxo, harden, input_selection, TxBO};
+use crate::TxData;
+use cardano_serialization_lib as clib;
+use cardano_serialization_lib::{crypto as ccrypto, utils as cutils};
+use clib::address::Address;
+
+/// CIP-27 Royalty Token Minter Builder Type
+///
+/// Mints the royalty token of the policy of `script` with the label 777
+/// metadata of `royalty`. The transaction is funded from and the token is
+/// sent to `liquidity_addr`, the first key in `pvks` signs for it. The policy
+/// keys sign in finalize.
+#[derive(Debug, Clone)]
+pub struct AtRoyaltyMintBuilder {
+    pub script: clib::NativeScript,
+    pub royalty: Cip27Royalty,
+    pub liquidity_addr: Address,
+}
+
+pub type AtRoyaltyMintParams<'a> = (&'a clib::NativeScript, &'a Cip27Royalty, &'a Address);
+
+impl<'a> super::PerformTxb<AtRoyaltyMintParams<'a>> for AtRoyaltyMintBuilder {
+    fn new(t: AtRoyaltyMintParams) -> Self {
+        AtRoyaltyMintBuilder {
+            script: t.0.clone(),
+            royalty: t.1.clone(),
+            liquidity_addr: t.2.clone(),
+        }
+    }
+
+    fn perform_txb(
+        &self,
+        fee: &clib::utils::BigNum,
+        gtxd: &TxData,
+        pvks: &[String],
+        fcrun: bool,
+    ) -> std::result::Result<TxBO, MurinError> {
+        if fcrun {
+            info!("--------------------------------------------------------------------------------------------------------");
+            info!("-----------------------------------------Fee Calculation------------------------------------------------");
+            info!("---------------------------------------------------------------------------------------------------------\n");
+        } else {
+            info!("--------------------------------------------------------------------------------------------------------");
+            info!("-----------------------------------------Build Transaction----------------------------------------------");
+            info!("--------------------------------------------------------------------------------------------------------\n");
+        }
+
+        let mintpolicy = self.script.hash();
+        debug!("Policy ID: {:?}", hex::encode(mintpolicy.to_bytes()));
+        let minttokens: Tokens = vec![(
+            mintpolicy.clone(),
+            royalty_token_name(),
+            cutils::to_bignum(1),
+        )];
+
+        /////////////////////////////////////////////////////////////////////////////////////////////////////
+        //
+        //Auxiliary Data
+        //  Royalty Metadata
+        /////////////////////////////////////////////////////////////////////////////////////////////////////
+        let mut aux_data = clib::metadata::AuxiliaryData::new();
+        aux_data.set_metadata(&self.royalty.to_metadata()?);
+
+        //////////////////////////////////////////////////////////////////////////////////////////////////////
+        //Add Inputs and Outputs
+        //
+        //
+        ///////////////////////////////////////////////////////////////////////////////////////////////////////
+        let mut txouts = clib::TransactionOutputs::new();
+        let mut token_val = tokens_to_value(&minttokens);
+        token_val.set_coin(&calc_min_ada_for_utxo(&token_val, None));
+        txouts.add(&clib::TransactionOutput::new(
+            &self.liquidity_addr,
+            &token_val,
+        ));
+
+        // Inputs
+        let mut input_txuos = gtxd.clone().get_inputs();
+
+        // Check if some utxos in inputs are in use and remove them
+        if let Some(used_utxos) = crate::utxomngr::usedutxos::check_any_utxo_used(&input_txuos)? {
+            input_txuos.remove_used_utxos(used_utxos);
+        }
+
+        // Balance TX
+        let mut fee_paid = false;
+        let mut first_run = true;
+        let mut txos_paid = false;
+        let mut tbb_values = cutils::Value::new(&cutils::to_bignum(0u64));
+        let mut acc = cutils::Value::new(&cutils::to_bignum(0u64));
+
+        let mut needed_value = sum_output_values(&txouts);
+        needed_value.set_coin(&needed_value.coin().checked_add(&fee.clone()).unwrap());
+        let security =
+            cutils::to_bignum(cutils::from_bignum(&needed_value.coin()) / 100 * 10 + (2 * MIN_ADA)); // 10% Security for min utxo etc.
+        needed_value.set_coin(&needed_value.coin().checked_add(&security).unwrap());
+        let mut needed_value = cutils::Value::new(&needed_value.coin());
+
+        let (txins, mut input_txuos) = input_selection(
+            None,
+            &mut needed_value,
+            &input_txuos,
+            gtxd.clone().get_collateral(),
+            None,
+        )?;
+
+        let saved_input_txuos = input_txuos.clone();
+
+        // the policy keys sign in finalize
+        let vkey_counter =
+            get_vkey_count(&input_txuos, None) + self.script.get_required_signers().len();
+
+        let mut mint_val_zero_coin = tokens_to_value(&minttokens);
+        mint_val_zero_coin.set_coin(&cutils::to_bignum(0u64));
+
+        let txouts_fin = balance_tx(
+            &mut input_txuos,
+            &minttokens,
+            &mut txouts,
+            Some(mint_val_zero_coin).as_ref(),
+            fee,
+            &mut fee_paid,
+            &mut first_run,
+            &mut txos_paid,
+            &mut tbb_values,
+            &self.liquidity_addr,
+            &self.liquidity_addr,
+            &mut acc,
+            None,
+            &fcrun,
+        )?;
+
+        ////////////////////////////////////////////////////////////////////////////////////////////
+        //
+        // MINT ASSETS
+        //
+        ////////////////////////////////////////////////////////////////////////////////////////////
+        let mut mintasset = clib::MintAssets::new();
+        mintasset.insert(&royalty_token_name(), clib::utils::Int::new_i32(1));
+        let mint = clib::Mint::new_from_entry(&mintpolicy, &mintasset);
+
+        let slot = cutils::to_bignum(
+            gtxd.clone().get_current_slot() + get_ttl_tx(&gtxd.clone().get_network())?,
+        );
+        let mut txbody = clib::TransactionBody::new_tx_body(&txins, &txouts_fin, fee);
+        txbody.set_ttl(&slot);
+        txbody.set_auxiliary_data_hash(&cutils::hash_auxiliary_data(&aux_data));
+        txbody.set_mint(&mint);
+
+        // Set network Id
+        if gtxd.get_network() == clib::NetworkIdKind::Testnet {
+            txbody.set_network_id(&clib::NetworkId::testnet());
+        } else {
+            txbody.set_network_id(&clib::NetworkId::mainnet());
+        }
+
+        let mut txwitness = clib::TransactionWitnessSet::new();
+        let mut native_scripts = clib::NativeScripts::new();
+        native_scripts.add(&self.script);
+        txwitness.set_native_scripts(&native_scripts);
+
+        let root_key1 = clib::crypto::Bip32PrivateKey::from_bytes(&hex::decode(&pvks[0])?)?;
+        let account_key1 = root_key1
+            .derive(harden(1852u32))
+            .derive(harden(1815u32))
+            .derive(harden(0u32));
+        let prv1 = account_key1.to_raw_key(); // for signatures
+        let vkwitness_1d1 = cutils::make_vkey_witness(&cutils::hash_transaction(&txbody), &prv1);
+
+        let mut vkeywitnesses = ccrypto::Vkeywitnesses::new();
+        vkeywitnesses.add(&vkwitness_1d1);
+        txwitness.set_vkeys(&vkeywitnesses);
+
+        debug!("TxWitness: {:?}", hex::encode(txwitness.to_bytes()));
+        debug!("TxBody: {:?}", hex::encode(txbody.to_bytes()));
+        debug!("--------------------Iteration Ended------------------------------");
+        debug!("Vkey Counter at End: {:?}", vkey_counter);
+        Ok((
+            txbody,
+            txwitness,
+            Some(aux_data),
+            saved_input_txuos,
+            vkey_counter,
+            false,
+        ))
+    }
+}
@@ -0,0 +1,168 @@
+//! CIP-27 royalties
+//!
+//! The royalties of a collection are declared once per policy by minting a
+//! token with an empty asset name under that policy. The mint transaction
+//! carries the rate and the payment address under metadata label 777, see
+//! [`super::build_royalty_mint`]. Marketplaces read the declaration back from
+//! the first mint of that token.
+use cardano_serialization_lib as clib;
+use clib::address::{Address, BaseAddress, EnterpriseAddress};
+use clib::metadata::{GeneralTransactionMetadata, MetadataMap, TransactionMetadatum};
+use clib::utils as cutils;
+use clib::AssetName;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{wallet, MurinError};
+
+/// Metadata label of the royalty declaration
+pub const ROYALTY_METADATA_LABEL: u64 = 777;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cip27Royalty {
+    /// Share of the selling price, between 0.0 and 1.0
+    pub rate: f32,
+    /// Address the royalties are paid to
+    pub addr: String,
+}
+
+impl Cip27Royalty {
+    pub fn new(rate: f32, addr: &str) -> Result<Cip27Royalty, MurinError> {
+        if !(0.0..=1.0).contains(&rate) {
+            return Err(MurinError::new(&format!(
+                "royalty rate '{rate}' is not between 0.0 and 1.0"
+            )));
+        }
+        let addr = addr.to_string();
+        // the marketplace datums carry the payment key hash of the royalty address
+        let address = wallet::address_from_string_non_async(&addr)?;
+        let payment_cred = BaseAddress::from_address(&address)
+            .map(|a| a.payment_cred())
+            .or_else(|| EnterpriseAddress::from_address(&address).map(|a| a.payment_cred()));
+        if payment_cred.and_then(|c| c.to_keyhash()).is_none() {
+            return Err(MurinError::new(&format!(
+                "royalty address '{addr}' does not have a key payment credential"
+            )));
+        }
+        Ok(Cip27Royalty { rate, addr })
+    }
+
+    pub fn get_addr(&self) -> Result<Address, MurinError> {
+        wallet::address_from_string_non_async(&self.addr)
+    }
+
+    /// Rate in promille as used in the marketplace datum
+    pub fn rate_promille(&self) -> u64 {
+        (self.rate * 1000.0).round() as u64
+    }
+
+    /// Label 777 metadata of the royalty token mint
+    pub fn to_metadata(&self) -> Result<GeneralTransactionMetadata, MurinError> {
+        let mut metamap = MetadataMap::new();
+        metamap.insert_str(
+            "rate",
+            &TransactionMetadatum::new_text(self.rate.to_string())?,
+        )?;
+        super::chunk_string(&mut metamap, "addr", &self.addr)?;
+
+        let mut metadata = GeneralTransactionMetadata::new();
+        metadata.insert(
+            &cutils::to_bignum(ROYALTY_METADATA_LABEL),
+            &TransactionMetadatum::new_map(&metamap),
+        );
+        Ok(metadata)
+    }
+
+    /// Parses the label 777 metadata of a royalty token mint, accepts the
+    /// deprecated `pct` key and addresses split into 64 byte chunks
+    pub fn from_json(json: &Value) -> Result<Cip27Royalty, MurinError> {
+        let err = || MurinError::new(&format!("invalid CIP-27 royalty metadata: '{json}'"));
+        let rate = match json.get("rate").or_else(|| json.get("pct")) {
+            Some(Value::String(s)) => s.parse::<f32>().map_err(|_| err())?,
+            Some(Value::Number(n)) => n.as_f64().ok_or_else(err)? as f32,
+            _ => return Err(err()),
+        };
+        let addr = match json.get("addr") {
+            Some(Value::String(s)) => s.clone(),
+            Some(Value::Array(a)) => a
+                .iter()
+                .map(|c| c.as_str().ok_or_else(err))
+                .collect::<Result<Vec<&str>, MurinError>>()?
+                .concat(),
+            _ => return Err(err()),
+        };
+        Cip27Royalty::new(rate, &addr)
+    }
+}
+
+/// The royalty token has an empty asset name
+pub fn royalty_token_name() -> AssetName {
+    AssetName::new(vec![]).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cip27_metadata_roundtrip() {
+        let addr = "addr_test1qp8cprhse9pnnv7f4l3n6pj0afq2hjm6f7r2205dz0583egaeu9dhacmtx94652q4ym0v9v2mcra0n28d5lrtjqzsgxqgk5t8s";
+        let royalty = Cip27Royalty::new(0.05, addr).unwrap();
+        assert_eq!(royalty.rate_promille(), 50);
+
+        let metadata = royalty.to_metadata().unwrap();
+        let entry = metadata
+            .get(&cutils::to_bignum(ROYALTY_METADATA_LABEL))
+            .unwrap();
+        let json: Value = serde_json::from_str(
+            &clib::metadata::decode_metadatum_to_json_str(
+                &entry,
+                clib::metadata::MetadataJsonSchema::NoConversions,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(json.get("addr").unwrap().is_array());
+        assert_eq!(Cip27Royalty::from_json(&json).unwrap(), royalty);
+
+        let legacy = serde_json::json!({ "pct": "0.1", "addr": addr });
+        assert_eq!(
+            Cip27Royalty::from_json(&legacy).unwrap().rate_promille(),
+            100
+        );
+        assert!(Cip27Royalty::new(1.5, addr).is_err());
+    }
+
+    #[test]
+    fn royalty_address_needs_payment_key() {
+        use clib::address::{RewardAddress, StakeCredential};
+        use clib::crypto::{Ed25519KeyHash, ScriptHash};
+
+        let key = StakeCredential::from_keyhash(&Ed25519KeyHash::from_bytes(vec![1; 28]).unwrap());
+        let script =
+            StakeCredential::from_scripthash(&ScriptHash::from_bytes(vec![2; 28]).unwrap());
+        let bech32 = |a: Address| a.to_bech32(None).unwrap();
+
+        assert!(
+            Cip27Royalty::new(0.05, &bech32(EnterpriseAddress::new(0, &key).to_address())).is_ok()
+        );
+        assert!(Cip27Royalty::new(
+            0.05,
+            &bech32(BaseAddress::new(0, &key, &script).to_address())
+        )
+        .is_ok());
+        assert!(Cip27Royalty::new(
+            0.05,
+            &bech32(EnterpriseAddress::new(0, &script).to_address())
+        )
+        .is_err());
+        assert!(Cip27Royalty::new(
+            0.05,
+            &bech32(BaseAddress::new(0, &script, &key).to_address())
+        )
+        .is_err());
+        assert!(
+            Cip27Royalty::new(0.05, &bech32(RewardAddress::new(0, &key).to_address())).is_err()
+        );
+    }
+}
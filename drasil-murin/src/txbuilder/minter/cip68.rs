@@ -0,0 +1,343 @@
+//! CIP-68 datum metadata
+//!
+//! A CIP-68 asset is minted as a pair of tokens under the same policy and name,
+//! distinguished by a CIP-67 label prefix. The (100) reference token is locked
+//! at a reference address and carries the metadata as inline datum, the (222)
+//! user NFT or (333) user FT goes to the receiver. Spending the reference token
+//! and locking it again with a new datum updates the metadata of the asset,
+//! see [`super::build_cip68_update`].
+use std::str::FromStr;
+
+use cardano_serialization_lib as clib;
+use clib::address::Address;
+use clib::plutus::{ConstrPlutusData, PlutusData, PlutusList, PlutusMap};
+use clib::utils::{self as cutils, BigInt};
+use clib::{AssetName, TransactionOutput, TransactionOutputs};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::{AssetMetadata, Cip25Metadata};
+use crate::cardano::models::tokens_to_value;
+use crate::txbuilder::min_ada_for_utxo;
+use crate::{wallet, MurinError, TokenAsset};
+
+/// Label prefix (100) of the reference token
+pub const REFERENCE_TOKEN_LABEL: [u8; 4] = [0x00, 0x06, 0x43, 0xb0];
+/// Label prefix (222) of the user NFT
+pub const NFT_TOKEN_LABEL: [u8; 4] = [0x00, 0x0d, 0xe1, 0x40];
+/// Label prefix (333) of the user FT
+pub const FT_TOKEN_LABEL: [u8; 4] = [0x00, 0x14, 0xdf, 0x10];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Cip68TokenKind {
+    #[default]
+    Nft,
+    Ft,
+}
+
+impl Cip68TokenKind {
+    pub fn label(&self) -> [u8; 4] {
+        match self {
+            Cip68TokenKind::Nft => NFT_TOKEN_LABEL,
+            Cip68TokenKind::Ft => FT_TOKEN_LABEL,
+        }
+    }
+
+    /// First datum version defining this token kind
+    pub fn version(&self) -> u64 {
+        match self {
+            Cip68TokenKind::Nft => 1,
+            Cip68TokenKind::Ft => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cip68Config {
+    /// Address the reference tokens are locked at, if empty the minting service uses its own
+    #[serde(default)]
+    pub reference_addr: String,
+    #[serde(default)]
+    pub token_kind: Cip68TokenKind,
+    /// Datum version, defaults to the version of `token_kind`
+    pub version: Option<u64>,
+}
+
+impl Cip68Config {
+    pub fn new(reference_addr: &str, token_kind: Cip68TokenKind) -> Cip68Config {
+        Cip68Config {
+            reference_addr: reference_addr.to_string(),
+            token_kind,
+            version: None,
+        }
+    }
+
+    pub fn get_reference_addr(&self) -> Result<Address, MurinError> {
+        wallet::address_from_string_non_async(&self.reference_addr)
+    }
+
+    pub fn get_version(&self) -> u64 {
+        self.version.unwrap_or_else(|| self.token_kind.version())
+    }
+}
+
+/// Tokens to mint and reference outputs of CIP-68 pairs
+#[derive(Debug, Clone)]
+pub struct Cip68Mint {
+    /// Reference and user tokens
+    pub mint_tokens: Vec<TokenAsset>,
+    /// User tokens, to be sent to the receiver
+    pub user_tokens: Vec<TokenAsset>,
+    /// One output per reference token, locked at the reference address with the datum
+    pub reference_outputs: TransactionOutputs,
+}
+
+fn labeled_asset_name(label: &[u8; 4], name: &AssetName) -> Result<AssetName, MurinError> {
+    let name = name.name();
+    if name.len() > 28 {
+        return Err(MurinError::new(&format!(
+            "asset name '{}' is too long for a CIP-68 token",
+            hex::encode(&name)
+        )));
+    }
+    Ok(AssetName::new([label.as_slice(), &name].concat())?)
+}
+
+pub fn reference_asset_name(name: &AssetName) -> Result<AssetName, MurinError> {
+    labeled_asset_name(&REFERENCE_TOKEN_LABEL, name)
+}
+
+pub fn user_asset_name(name: &AssetName, kind: Cip68TokenKind) -> Result<AssetName, MurinError> {
+    labeled_asset_name(&kind.label(), name)
+}
+
+/// Metadata of the asset `name` in `metadata`, matched by plain or hex encoded token name
+pub fn find_asset_metadata<'a>(
+    metadata: &'a Cip25Metadata,
+    name: &AssetName,
+) -> Result<&'a AssetMetadata, MurinError> {
+    let name = name.name();
+    let hex_name = hex::encode(&name);
+    let str_name = String::from_utf8(name).unwrap_or_else(|_| hex_name.clone());
+    metadata
+        .assets
+        .iter()
+        .find(|a| a.tokenname == str_name || a.tokenname == hex_name)
+        .ok_or_else(|| MurinError::new(&format!("no metadata provided for asset '{str_name}'")))
+}
+
+fn constr(alternative: u64, fields: &PlutusList) -> PlutusData {
+    PlutusData::new_constr_plutus_data(&ConstrPlutusData::new(
+        &cutils::to_bignum(alternative),
+        fields,
+    ))
+}
+
+fn json_to_plutus(v: &Value) -> Result<PlutusData, MurinError> {
+    Ok(match v {
+        Value::Null => PlutusData::new_bytes(vec![]),
+        Value::Bool(b) => constr(*b as u64, &PlutusList::new()),
+        Value::Number(n) => {
+            PlutusData::new_integer(&BigInt::from_str(&n.to_string()).map_err(|_| {
+                MurinError::new(&format!("CIP-68 metadata only supports integers, got {n}"))
+            })?)
+        }
+        Value::String(s) => PlutusData::new_bytes(s.as_bytes().to_vec()),
+        Value::Array(a) => {
+            let mut list = PlutusList::new();
+            for e in a {
+                list.add(&json_to_plutus(e)?);
+            }
+            PlutusData::new_list(&list)
+        }
+        Value::Object(o) => {
+            let mut map = PlutusMap::new();
+            for (k, e) in o {
+                map.insert(
+                    &PlutusData::new_bytes(k.as_bytes().to_vec()),
+                    &json_to_plutus(e)?,
+                );
+            }
+            PlutusData::new_map(&map)
+        }
+    })
+}
+
+fn insert_other(map: &mut Map<String, Value>, other: &Option<Vec<super::MetadataOther>>) {
+    if let Some(other) = other {
+        for o in other {
+            let value =
+                serde_json::from_str(&o.value).unwrap_or_else(|_| Value::String(o.value.clone()));
+            map.insert(o.key.clone(), value);
+        }
+    }
+}
+
+/// The asset metadata in the key / value layout of CIP-25, as used by CIP-68
+fn asset_metadata_json(asset: &AssetMetadata) -> Value {
+    let mut map = Map::new();
+    map.insert(
+        "name".to_string(),
+        Value::String(
+            asset
+                .name
+                .clone()
+                .unwrap_or_else(|| asset.tokenname.clone()),
+        ),
+    );
+    if let Some(image) = &asset.image_url {
+        map.insert("image".to_string(), Value::String(image.clone()));
+    }
+    if let Some(media_type) = &asset.media_type {
+        map.insert("mediaType".to_string(), Value::String(media_type.clone()));
+    }
+    if let Some(description) = &asset.description {
+        map.insert(
+            "description".to_string(),
+            Value::String(description.concat()),
+        );
+    }
+    if let Some(files) = &asset.files {
+        let files = files
+            .iter()
+            .map(|f| {
+                let mut file = Map::new();
+                file.insert("name".to_string(), Value::String(f.name.clone()));
+                file.insert("mediaType".to_string(), Value::String(f.media_type.clone()));
+                file.insert("src".to_string(), f.src.clone());
+                insert_other(&mut file, &f.other);
+                Value::Object(file)
+            })
+            .collect();
+        map.insert("files".to_string(), Value::Array(files));
+    }
+    insert_other(&mut map, &asset.other);
+    Value::Object(map)
+}
+
+/// Reference datum `Constr 0 [metadata, version, extra]` of `asset`
+pub fn cip68_datum(asset: &AssetMetadata, version: u64) -> Result<PlutusData, MurinError> {
+    let mut fields = PlutusList::new();
+    fields.add(&json_to_plutus(&asset_metadata_json(asset))?);
+    fields.add(&PlutusData::new_integer(&BigInt::from_str(
+        &version.to_string(),
+    )?));
+    fields.add(&constr(0, &PlutusList::new()));
+    Ok(constr(0, &fields))
+}
+
+/// Version of the reference datum `datum`
+pub fn datum_version(datum: &PlutusData) -> Option<u64> {
+    let fields = datum.as_constr_plutus_data()?.data();
+    if fields.len() != 3 {
+        return None;
+    }
+    fields
+        .get(1)
+        .as_integer()?
+        .as_u64()
+        .map(|v| cutils::from_bignum(&v))
+}
+
+/// Locks the reference token `reference` at `addr` with `datum`
+pub fn reference_output(
+    addr: &Address,
+    reference: &TokenAsset,
+    datum: &PlutusData,
+) -> Result<TransactionOutput, MurinError> {
+    let mut txo = TransactionOutput::new(addr, &tokens_to_value(&vec![reference.clone()]));
+    txo.set_plutus_data(datum);
+    min_ada_for_utxo(&txo)
+}
+
+/// Turns `tokens` into CIP-68 pairs, each token must have an entry in `metadata`
+pub fn cip68_mint(
+    tokens: &[TokenAsset],
+    metadata: &Cip25Metadata,
+    config: &Cip68Config,
+) -> Result<Cip68Mint, MurinError> {
+    let reference_addr = config.get_reference_addr()?;
+    let mut mint = Cip68Mint {
+        mint_tokens: Vec::<TokenAsset>::new(),
+        user_tokens: Vec::<TokenAsset>::new(),
+        reference_outputs: TransactionOutputs::new(),
+    };
+    for token in tokens {
+        if config.token_kind == Cip68TokenKind::Nft && token.2.compare(&cutils::to_bignum(1)) != 0 {
+            return Err(MurinError::new(&format!(
+                "CIP-68 NFT '{}' must be minted exactly once",
+                hex::encode(token.1.name())
+            )));
+        }
+        let asset = find_asset_metadata(metadata, &token.1)?;
+        let reference = (
+            token.0.clone(),
+            reference_asset_name(&token.1)?,
+            cutils::to_bignum(1),
+        );
+        let user = (
+            token.0.clone(),
+            user_asset_name(&token.1, config.token_kind)?,
+            token.2.clone(),
+        );
+        mint.reference_outputs.add(&reference_output(
+            &reference_addr,
+            &reference,
+            &cip68_datum(asset, config.get_version())?,
+        )?);
+        mint.mint_tokens.push(reference);
+        mint.mint_tokens.push(user.clone());
+        mint.user_tokens.push(user);
+    }
+    Ok(mint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cip68_names_and_datum() {
+        let name = AssetName::new(b"Drasil01".to_vec()).unwrap();
+        assert_eq!(
+            hex::encode(reference_asset_name(&name).unwrap().name()),
+            "000643b0".to_string() + &hex::encode(b"Drasil01")
+        );
+        assert_eq!(
+            hex::encode(user_asset_name(&name, Cip68TokenKind::Nft).unwrap().name()),
+            "000de140".to_string() + &hex::encode(b"Drasil01")
+        );
+        assert_eq!(
+            hex::encode(user_asset_name(&name, Cip68TokenKind::Ft).unwrap().name()),
+            "0014df10".to_string() + &hex::encode(b"Drasil01")
+        );
+        assert!(reference_asset_name(&AssetName::new(vec![1u8; 29]).unwrap()).is_err());
+
+        let asset = AssetMetadata {
+            name: Some("Drasil 01".to_string()),
+            tokenname: "Drasil01".to_string(),
+            media_type: Some("image/png".to_string()),
+            description: None,
+            image_url: Some("ipfs://Qm".to_string()),
+            files: None,
+            other: None,
+        };
+        let datum = cip68_datum(&asset, 1).unwrap();
+        let constr = datum.as_constr_plutus_data().unwrap();
+        assert_eq!(constr.alternative(), cutils::to_bignum(0));
+        let fields = constr.data();
+        assert_eq!(fields.len(), 3);
+        let metadata = fields.get(0).as_map().unwrap();
+        assert_eq!(metadata.len(), 3);
+        assert_eq!(
+            metadata
+                .get(&PlutusData::new_bytes(b"name".to_vec()))
+                .unwrap()
+                .as_bytes()
+                .unwrap(),
+            b"Drasil 01".to_vec()
+        );
+        assert_eq!(datum_version(&datum), Some(1));
+    }
+}
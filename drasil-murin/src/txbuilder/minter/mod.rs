@@ -1,19 +1,26 @@
+pub mod build_cip68_update;
 pub mod build_minttx;
 pub mod build_oneshot_mint;
+pub mod build_reveal_mint;
+pub mod build_royalty_mint;
+pub mod cip27;
+pub mod cip68;
 pub mod models;
+pub mod reveal;
 
 use std::str;
 
 use cardano_serialization_lib as clib;
 use cardano_serialization_lib::{address as caddr, utils as cutils};
-use chrono::{DateTime, Utc};
 use clib::crypto::{Ed25519KeyHash, ScriptHash};
 use clib::metadata::{MetadataList, MetadataMap};
 use clib::{NativeScript, NativeScripts, ScriptAll, ScriptPubkey};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use self::cip68::Cip68Config;
 use super::PerformTxb;
+use crate::cardano::SlotConfig;
 use crate::wallet;
 use crate::MurinError;
 use crate::{MintTokenAsset, TokenAsset};
@@ -28,6 +35,7 @@ pub struct MinterTxData {
     fee_addr: Option<caddr::Address>,
     fee: Option<i64>,
     contract_id: i64,
+    cip68: Option<Cip68Config>,
 }
 
 impl MinterTxData {
@@ -51,6 +59,7 @@ impl MinterTxData {
             fee_addr,
             fee,
             contract_id,
+            cip68: None,
         }
     }
 
@@ -101,6 +110,11 @@ impl MinterTxData {
         self.fee
     }
 
+    /// Mint CIP-68 pairs instead of CIP-25 tokens if set
+    pub fn get_cip68(&self) -> Option<Cip68Config> {
+        self.cip68.clone()
+    }
+
     pub fn set_claim_addr(&mut self, addr: caddr::Address) {
         self.receiver_payment_addr = addr;
     }
@@ -120,6 +134,10 @@ impl MinterTxData {
     pub fn set_fee(&mut self, fee: i64) {
         self.fee = Some(fee);
     }
+
+    pub fn set_cip68(&mut self, cip68: Cip68Config) {
+        self.cip68 = Some(cip68);
+    }
 }
 
 impl ToString for MinterTxData {
@@ -171,7 +189,12 @@ impl ToString for MinterTxData {
         ret.push_str(&(self.get_auto_mint().to_string() + "|"));
         ret.push_str(&(s_fee_addr + "|"));
         ret.push_str(&(s_fee + "|"));
-        ret.push_str(&self.contract_id.to_string());
+        ret.push_str(&(self.contract_id.to_string() + "|"));
+        ret.push_str(&match self.get_cip68() {
+            Some(cip68) => serde_json::to_string(&cip68)
+                .expect("ERROR: Could not serialize cip68 configuration"),
+            None => "NoData".to_string(),
+        });
 
         ret
     }
@@ -181,7 +204,8 @@ impl std::str::FromStr for MinterTxData {
     type Err = MurinError;
     fn from_str(src: &str) -> std::result::Result<Self, Self::Err> {
         let slice: Vec<&str> = src.split('|').collect();
-        if slice.len() == 8 {
+        // a 9th field was added for CIP-68, transactions stored before have 8
+        if slice.len() == 8 || slice.len() == 9 {
             // restore token vector
             let mut tokens = Vec::<MintTokenAsset>::new();
             let tokens_vec: Vec<&str> = slice[0].split('!').collect();
@@ -225,6 +249,11 @@ impl std::str::FromStr for MinterTxData {
 
             let contract_id = slice[7].parse::<i64>()?;
 
+            let cip68 = match slice.get(8) {
+                None | Some(&"NoData") => None,
+                Some(s) => Some(serde_json::from_str::<Cip68Config>(s)?),
+            };
+
             Ok(MinterTxData {
                 mint_tokens: tokens,
                 receiver_stake_addr: stake_address,
@@ -234,6 +263,7 @@ impl std::str::FromStr for MinterTxData {
                 fee_addr,
                 fee,
                 contract_id,
+                cip68,
             })
         } else {
             Err(MurinError::new(&format!(
@@ -802,16 +832,19 @@ pub fn make_721_asset_entry(
     Ok(())
 }
 
+/// Oneshot policy of `pub_key_hash` which locks 9000 seconds after `current_slot`
 pub fn create_onshot_policy(
     pub_key_hash: &Ed25519KeyHash,
     current_slot: u64,
-) -> (NativeScript, ScriptHash) {
+    network: &clib::NetworkIdKind,
+) -> Result<(NativeScript, ScriptHash), MurinError> {
     let mut native_scripts = NativeScripts::new();
     native_scripts.add(&NativeScript::new_script_pubkey(&ScriptPubkey::new(
         pub_key_hash,
     )));
 
-    let slot = cutils::to_bignum(current_slot + 9000);
+    let slot = current_slot + SlotConfig::for_network(network)?.slots_in(9000);
+    let slot = cutils::to_bignum(slot);
     native_scripts.add(&NativeScript::new_timelock_expiry(
         &clib::TimelockExpiry::new_timelockexpiry(&slot),
     ));
@@ -819,11 +852,5 @@ pub fn create_onshot_policy(
     let mint_script = NativeScript::new_script_all(&ScriptAll::new(&native_scripts));
     let policy_id = mint_script.hash(); //policyId
 
-    (mint_script, policy_id)
-}
-
-pub fn calculate_slot_from_date(date: DateTime<Utc>) -> u32 {
-    let ms = date.timestamp();
-    let slot = ms - 1596491091 + 4924800;
-    slot as u32
+    Ok((mint_script, policy_id))
 }
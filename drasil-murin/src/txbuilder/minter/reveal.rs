@@ -0,0 +1,110 @@
+//! Delayed metadata reveal of mint projects
+//!
+//! A reveal project mints its NFTs with placeholder metadata. Once its
+//! metadata pool, the imported metadata ordered by asset name, is final and
+//! before the project is activated, the platform commits to the secret seed
+//! and the [`provenance_hash`] of the pool with [`reveal_commitment`]. The pool
+//! can not change afterwards. On reveal the seed is published and the pool is
+//! assigned to the assets with [`reveal_order`]. Anyone can verify a reveal by
+//! hashing the pool and the seed and repeating the shuffle.
+use cardano_serialization_lib::AssetName;
+
+use super::AssetMetadata;
+use crate::cardano::pparams::hash::blake2b256;
+use crate::MurinError;
+
+/// Hex encoded blake2b-256 hash of the reveal seed followed by the bytes of
+/// the hex encoded provenance hash
+pub fn reveal_commitment(seed: &[u8], provenance: &str) -> Result<String, MurinError> {
+    Ok(hex::encode(blake2b256(
+        &[seed, &hex::decode(provenance)?].concat(),
+    )))
+}
+
+/// Checks a published seed and provenance against the commitment of the project
+pub fn verify_commitment(seed: &[u8], provenance: &str, commitment: &str) -> bool {
+    reveal_commitment(seed, provenance).map_or(false, |c| c == commitment)
+}
+
+/// Hex encoded blake2b-256 hash of the JSON encoded metadata pool
+pub fn provenance_hash(pool: &[AssetMetadata]) -> Result<String, MurinError> {
+    Ok(hex::encode(blake2b256(
+        serde_json::to_string(pool)?.as_bytes(),
+    )))
+}
+
+/// Seeded Fisher-Yates shuffle of `0..n`
+///
+/// Asset `i` receives the pool entry at `reveal_order(seed, n)[i]`. The swap
+/// index of step `i` is the first 8 bytes of blake2b-256(seed || i) as big
+/// endian integer modulo `i + 1`, with `i` as 8 byte big endian integer.
+pub fn reveal_order(seed: &[u8], n: usize) -> Vec<usize> {
+    let mut order = (0..n).collect::<Vec<_>>();
+    for i in (1..n).rev() {
+        let hash = blake2b256(&[seed, &(i as u64).to_be_bytes()].concat());
+        let mut random = [0u8; 8];
+        random.copy_from_slice(&hash[..8]);
+        let j = (u64::from_be_bytes(random) % (i as u64 + 1)) as usize;
+        order.swap(i, j);
+    }
+    order
+}
+
+/// Metadata of a pool entry assigned to the asset `tokenname` named `name`
+pub fn assign_metadata(metadata: &AssetMetadata, tokenname: &str, name: &str) -> AssetMetadata {
+    AssetMetadata {
+        name: Some(name.to_owned()),
+        tokenname: tokenname.to_owned(),
+        ..metadata.clone()
+    }
+}
+
+/// Each CIP-25 reveal re-mint mints one token with this asset name
+pub fn reveal_token_name() -> AssetName {
+    AssetName::new(b"reveal".to_vec()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reveal_order_is_a_seeded_permutation() {
+        let seed = [7u8; 32];
+        let order = reveal_order(&seed, 100);
+        assert_eq!(order, reveal_order(&seed, 100));
+        assert_ne!(order, reveal_order(&[8u8; 32], 100));
+        let mut sorted = order.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..100).collect::<Vec<_>>());
+        assert!(reveal_order(&seed, 0).is_empty());
+        assert_eq!(reveal_order(&seed, 1), vec![0]);
+    }
+
+    #[test]
+    fn commitment_binds_seed_and_pool() {
+        let seed = [7u8; 32];
+        let pool = |image: &str| {
+            vec![AssetMetadata {
+                tokenname: "4e465431".to_string(),
+                name: Some("NFT1".to_string()),
+                media_type: None,
+                description: None,
+                image_url: Some(image.to_string()),
+                files: None,
+                other: None,
+            }]
+        };
+        let provenance = provenance_hash(&pool("ipfs://a")).unwrap();
+        let commitment = reveal_commitment(&seed, &provenance).unwrap();
+        assert_eq!(commitment.len(), 64);
+        assert!(verify_commitment(&seed, &provenance, &commitment));
+        assert!(!verify_commitment(&[8u8; 32], &provenance, &commitment));
+
+        // an edited pool no longer matches the commitment
+        let edited = provenance_hash(&pool("ipfs://b")).unwrap();
+        assert_ne!(edited, provenance);
+        assert!(!verify_commitment(&seed, &edited, &commitment));
+        assert!(!verify_commitment(&seed, "not hex", &commitment));
+    }
+}
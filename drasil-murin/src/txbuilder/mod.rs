@@ -1,16 +1,22 @@
+pub mod auction;
+pub mod exunits;
 pub mod finalize;
 pub mod marketplace;
 pub mod minter;
 pub mod modules;
 pub mod rwdist;
+mod scripts;
 pub mod stdtx;
+pub mod submit;
 pub use marketplace::*;
 pub use rwdist::*;
+pub use submit::*;
 
+use crate::cardano::conway::ConwayBodyExtension;
 use crate::cardano::{models, supporting_functions, BuildOutput};
 use crate::cardano::{TransactionUnspentOutput, TransactionUnspentOutputs};
 use crate::error::MurinError;
-use crate::pparams::ProtocolParameters;
+use crate::utxomngr::txstatus::{advance_tx_state, set_tx_error, TxState};
 use crate::wallet;
 use cardano_serialization_lib as clib;
 use cardano_serialization_lib::{address as caddr, crypto as ccrypto, utils as cutils};
@@ -43,6 +49,18 @@ pub trait PerformTxb<T> {
         pvks: &[String],
         fcrun: bool,
     ) -> std::result::Result<TxBO, MurinError>;
+
+    /// UTxOs spent or referenced by the transaction which are not part of the `TxData` inputs,
+    /// e.g. script outputs. They are needed to resolve all inputs for the script evaluation.
+    fn script_utxos(&self) -> TransactionUnspentOutputs {
+        TransactionUnspentOutputs::new()
+    }
+
+    /// Conway certificates and votes which are added to the body after it was built,
+    /// cardano-serialization-lib does not support them yet.
+    fn conway_extension(&self) -> Result<Option<ConwayBodyExtension>, MurinError> {
+        Ok(None)
+    }
 }
 
 /// TxBuilder is the general transactionbuilder and performs the steps common for all specfic transaction types.
@@ -64,17 +82,9 @@ impl TxBuilder {
         &self,
         app_type: &A,
     ) -> Result<BuildOutput, MurinError> {
-        let protocol_parameters = ProtocolParameters::read_protocol_parameter(
-            &std::env::var("CARDANO_PROTOCOL_PARAMETER_PATH")
-                .unwrap_or_else(|_| "/odin/protocol_parameters_babbage.json".to_owned()),
-        )
-        .unwrap();
-        // We define a standard budget which will fit most of the caseses.
-        // We should use AIKEN here to calculate the needed budget for the particular transaction after the dummy build step and feed the calculated values into
-        // the second transaction building step.
-
-        let mem = cutils::to_bignum(protocol_parameters.max_tx_execution_units.memory as u64 - 2);
-        let steps = cutils::to_bignum(protocol_parameters.max_tx_execution_units.steps as u64 - 2);
+        let protocol_parameters = crate::pparams::protocol_parameters()?;
+        let max_mem = protocol_parameters.max_tx_execution_units.memory as u64 - 2;
+        let max_steps = protocol_parameters.max_tx_execution_units.steps as u64 - 2;
 
         let ex_unit_price = models::ExUnitPrice {
             priceSteps: protocol_parameters.execution_unit_prices.priceSteps,
@@ -84,6 +94,13 @@ impl TxBuilder {
         let a = cutils::to_bignum(protocol_parameters.tx_fee_per_byte);
         let b = cutils::to_bignum(protocol_parameters.tx_fee_fixed);
 
+        // The Conway extension is not part of the body yet, its size has to be paid additionally
+        let conway = app_type.conway_extension()?;
+        let extension_fee = match &conway {
+            Some(ext) => a.checked_mul(&cutils::to_bignum(ext.size()? as u64))?,
+            None => cutils::to_bignum(0),
+        };
+
         //Create first Transaction for fee calculation with a fixed fee of 2 Ada
         let mut tx_ =
             app_type.perform_txb(&cutils::to_bignum(2000000), &self.gtxd, &self.pvks, true)?;
@@ -93,6 +110,43 @@ impl TxBuilder {
         // Assemble the dummy transaction
         let transaction_ = clib::Transaction::new(&tx_.0, &tx_.1, tx_.2);
 
+        // Evaluate the scripts of the dummy transaction, a failing script fails the build
+        let evaluated = if tx_.5 {
+            match exunits::evaluate_ex_units(
+                &transaction_,
+                &self.evaluation_utxos(app_type, &tx_.3),
+                &protocol_parameters,
+                &self.gtxd.get_network(),
+            ) {
+                Ok(redeemers) => Some(redeemers),
+                Err(e) => {
+                    warn!(
+                        "Could not evaluate execution units for redeemers {:?}: {}",
+                        tx_.1.redeemers().and_then(|r| r.to_json().ok()),
+                        e
+                    );
+                    return Err(e);
+                }
+            }
+        } else {
+            None
+        };
+        let (mem, steps) = match &evaluated {
+            Some(redeemers) => {
+                let (mem, steps) = exunits::total_ex_units(redeemers);
+                debug!("Evaluated execution units: mem: {}, steps: {}", mem, steps);
+                (cutils::to_bignum(mem), cutils::to_bignum(steps))
+            }
+            None => (cutils::to_bignum(max_mem), cutils::to_bignum(max_steps)),
+        };
+
+        // Conway charges the reference scripts of spent and referenced outputs by size
+        let ref_script_size =
+            Self::ref_script_size(&tx_.0, &self.evaluation_utxos(app_type, &tx_.3));
+        let extension_fee = extension_fee.checked_add(&cutils::to_bignum(
+            protocol_parameters.ref_script_fee(ref_script_size),
+        ))?;
+
         // Calculate the actual fee
         let calculated_fee = supporting_functions::calc_txfee(
             &transaction_,
@@ -102,9 +156,13 @@ impl TxBuilder {
             &steps,
             &mem,
             true,
-        );
+        )
+        .checked_add(&extension_fee)?;
         // Perform another transaction building with the calculated fee
-        let tx = app_type.perform_txb(&calculated_fee, &self.gtxd, &self.pvks, false)?;
+        let mut tx = app_type.perform_txb(&calculated_fee, &self.gtxd, &self.pvks, false)?;
+        if let Some(redeemers) = &evaluated {
+            exunits::apply_ex_units(&mut tx, redeemers, &protocol_parameters)?;
+        }
 
         // Assemble the second transaction
         let transaction2 = clib::Transaction::new(&tx.0, &tx_.1, tx.2.clone());
@@ -122,28 +180,125 @@ impl TxBuilder {
                 &steps,
                 &mem,
                 true,
-            );
-            let tx = app_type.perform_txb(&calculated_fee, &self.gtxd, &self.pvks, false)?;
+            )
+            .checked_add(&extension_fee)?;
+            let mut tx = app_type.perform_txb(&calculated_fee, &self.gtxd, &self.pvks, false)?;
+            if let Some(redeemers) = &evaluated {
+                exunits::apply_ex_units(&mut tx, redeemers, &protocol_parameters)?;
+            }
             info!("Fee: {:?}", calculated_fee);
-            Ok(supporting_functions::tx_output_data(
-                tx.0,
-                tx.1,
-                tx.2,
-                tx.3.to_hex()?,
-                0u64,
-                false,
-            )?)
+            Self::check_limits(&tx, &conway, &protocol_parameters)?;
+            Self::output_data(&conway, tx)
         } else {
             info!("Fee: {:?}", calculated_fee);
-            Ok(supporting_functions::tx_output_data(
+            Self::check_limits(&tx, &conway, &protocol_parameters)?;
+            Self::output_data(&conway, tx)
+        }
+    }
+
+    /// Fails with `MurinError::TxLimitExceeded` if the signed transaction would exceed
+    /// the maximum transaction size or the execution unit limits of the protocol
+    fn check_limits(
+        tx: &TxBO,
+        conway: &Option<ConwayBodyExtension>,
+        protocol_parameters: &crate::pparams::ProtocolParameters,
+    ) -> Result<(), MurinError> {
+        let mut witness = tx.1.clone();
+        witness.set_vkeys(&supporting_functions::make_dummy_vkeywitnesses(tx.4));
+        let mut size = clib::Transaction::new(&tx.0, &witness, tx.2.clone())
+            .to_bytes()
+            .len() as u64;
+        if let Some(ext) = conway {
+            size += ext.size()? as u64;
+        }
+        if size > protocol_parameters.max_tx_size {
+            return Err(MurinError::TxLimitExceeded {
+                message: format!(
+                    "transaction size of {} bytes exceeds the maximum of {} bytes",
+                    size, protocol_parameters.max_tx_size
+                ),
+                split_off: vec![],
+            });
+        }
+
+        if let Some(redeemers) = tx.1.redeemers() {
+            let (mem, steps) = exunits::total_ex_units(&redeemers);
+            let max = &protocol_parameters.max_tx_execution_units;
+            if mem > max.memory as u64 || steps > max.steps as u64 {
+                return Err(MurinError::TxLimitExceeded {
+                    message: format!(
+                        "execution units (mem: {}, steps: {}) exceed the maximum (mem: {}, steps: {})",
+                        mem, steps, max.memory, max.steps
+                    ),
+                    split_off: vec![],
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn output_data(
+        conway: &Option<ConwayBodyExtension>,
+        tx: TxBO,
+    ) -> Result<BuildOutput, MurinError> {
+        match conway {
+            Some(ext) => ext.tx_output_data(tx.0, tx.1, tx.2, tx.3.to_hex()?, 0u64, false),
+            None => Ok(supporting_functions::tx_output_data(
                 tx.0,
                 tx.1,
                 tx.2,
                 tx.3.to_hex()?,
                 0u64,
                 false,
-            )?)
+            )?),
+        }
+    }
+
+    /// Collects all UTxOs needed to resolve the inputs of a transaction for the script evaluation
+    /// Size of the reference scripts of all inputs and reference inputs of the transaction
+    fn ref_script_size(body: &clib::TransactionBody, utxos: &TransactionUnspentOutputs) -> usize {
+        let mut inputs = (0..body.inputs().len())
+            .map(|i| body.inputs().get(i))
+            .collect::<Vec<_>>();
+        if let Some(refs) = body.reference_inputs() {
+            inputs.extend((0..refs.len()).map(|i| refs.get(i)));
+        }
+        inputs
+            .iter()
+            .filter_map(|input| {
+                utxos
+                    .0
+                    .iter()
+                    .find(|u| u.input() == *input)
+                    .and_then(|u| u.output().script_ref())
+            })
+            .map(|script_ref| match script_ref.plutus_script() {
+                Some(script) => script.bytes().len(),
+                None => script_ref
+                    .native_script()
+                    .map(|script| script.to_bytes().len())
+                    .unwrap_or(0),
+            })
+            .sum()
+    }
+
+    fn evaluation_utxos<P, A: PerformTxb<P>>(
+        &self,
+        app_type: &A,
+        used: &TransactionUnspentOutputs,
+    ) -> TransactionUnspentOutputs {
+        let mut utxos = used.clone();
+        let mut others = self.gtxd.get_inputs();
+        others.merge(app_type.script_utxos());
+        if let Some(collateral) = self.gtxd.get_collateral() {
+            others.add(&collateral);
         }
+        for u in others.0.iter() {
+            if !utxos.contains_tx(u) {
+                utxos.add(u);
+            }
+        }
+        utxos
     }
 }
 
@@ -995,8 +1150,7 @@ pub fn calc_min_ada_for_utxo(
 /// This method must be prefered for new implementations as it gives better results and is compatible with PlutusV2.
 pub fn min_ada_for_utxo(output_: &TransactionOutput) -> Result<TransactionOutput, MurinError> {
     let mut output: TransactionOutput = output_.clone();
-    let pppath = std::env::var("CARDANO_PROTOCOL_PARAMETER_PATH")?;
-    let ppp = crate::pparams::ProtocolParameters::read_protocol_parameter(&pppath)?;
+    let ppp = crate::pparams::protocol_parameters()?;
     for _ in 0..3 {
         let required_coin = to_bignum(output.to_bytes().len() as u64)
             .checked_add(&to_bignum(160))?
@@ -1083,101 +1237,26 @@ pub async fn create_and_submit_cbor_tx(tx: String, tx_hash: String) -> Result<St
         cbor_hex: tx,
     };
     debug!("{:?}", cli_tx);
-    let node_tx_hash = submit_tx(&cli_tx, &tx_hash).await?;
-
-    Ok(node_tx_hash)
-}
-
-/// Submit a Cardano transaction to a specific submit-api-endpoint.
-pub async fn submit_endpoint(
-    tx: &[u8],
-    endpoint: String,
-    own_tx_hash: &String,
-    client: &reqwest::Client,
-) -> Result<(String, String, bool), MurinError> {
-    use futures::pin_mut;
-
-    let response = client
-        .post(endpoint.clone())
-        .header("Content-Type", "application/cbor")
-        .body(tx.to_owned())
-        .send();
-    pin_mut!(response);
-
-    match tokio::time::timeout(std::time::Duration::from_secs(5), &mut response).await {
-        Err(_) => {
-            debug!("Taking more than five seconds");
-            Ok((
-                "".to_string(),
-                format!("ERROR: '{endpoint:?}' is not available"),
-                false,
-            ))
-        }
-        Ok(no_timeout) => match no_timeout {
-            Ok(resp) => {
-                let mut err = String::new();
-                let mut txhash = String::new();
-                info!("Response: {:?}", resp);
-                let r_status = resp.status();
-                let resp_text = resp.text().await?;
-                if r_status != http::StatusCode::ACCEPTED {
-                    err = format!("ERROR on tx submission: {resp_text:?}");
-                    debug!("Error, Endpoint: {} : {:?}", endpoint, err);
-                } else {
-                    txhash = resp_text.replace('\"', "");
-                }
-                let assert = *own_tx_hash == txhash;
-
-                Ok((txhash, err, assert))
-            }
-            Err(e) => Ok((
-                "".to_string(),
-                format!("ERROR: '{e:?}' is not available"),
-                false,
-            )),
-        },
-    }
-}
-
-/// Submits a CBOR Transaction to the Cardano Network, it is possible to send the transaction to three endpoints simultaneously.
-/// TODO: Make it generic for an arbitrary amount of submit endpoints.
-pub async fn submit_tx(tx: &CBORTransaction, own_tx_hash: &String) -> Result<String, MurinError> {
-    let submit1 = std::env::var("CARDANO_TX_SUBMIT_ENDPOINT1")?;
-    let submit2 = std::env::var("CARDANO_TX_SUBMIT_ENDPOINT2")?;
-    let submit3 = std::env::var("CARDANO_TX_SUBMIT_ENDPOINT3")?;
-
-    let client = reqwest::Client::new();
-    let tx = hex::decode(tx.cbor_hex.clone())?;
-    let mut response1 = (String::new(), String::new(), false);
-    match submit_endpoint(&tx, submit1, own_tx_hash, &client).await {
-        Ok(x) => response1 = x,
-        Err(e) => {
-            error!("Error: '{}'", e.to_string())
-        }
-    };
-
-    let mut response2 = (String::new(), String::new(), false);
-    match submit_endpoint(&tx, submit2, own_tx_hash, &client).await {
-        Ok(x) => response2 = x,
+    track_tx_state(&tx_hash, TxState::Submitted);
+    let node_tx_hash = match submit_tx(&cli_tx, &tx_hash).await {
+        Ok(hash) => hash,
         Err(e) => {
-            error!("Error: '{}'", e.to_string())
+            if let Err(err) = set_tx_error(&tx_hash, &e.to_string()) {
+                warn!("Could not store submission error of {}: {}", tx_hash, err);
+            }
+            return Err(e);
         }
     };
+    track_tx_state(&tx_hash, TxState::InMempool);
 
-    let mut response3 = (String::new(), String::new(), false);
-    match submit_endpoint(&tx, submit3, own_tx_hash, &client).await {
-        Ok(x) => response3 = x,
-        Err(e) => {
-            error!("Error: '{}'", e.to_string())
-        }
-    };
+    Ok(node_tx_hash)
+}
 
-    if response1.2 || response2.2 || response3.2 {
-        Ok(own_tx_hash.clone())
-    } else {
-        Err(MurinError::new(
-            &(response1.1 + &response2.1 + &response3.1),
-        ))
+/// Moves a transaction forward in the status registry, failures only get logged
+/// as the registry must not break building or submission.
+pub fn track_tx_state(tx_hash: &str, state: TxState) {
+    if let Err(e) = advance_tx_state(tx_hash, state) {
+        warn!("Could not update status of transaction {}: {}", tx_hash, e);
     }
 }
 
@@ -1291,3 +1370,92 @@ pub struct ServiceFees {
     pub fee: BigNum,
     pub fee_addr: Address,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clib::plutus::{ExUnits, PlutusData, PlutusList, Redeemer, RedeemerTag, Redeemers};
+
+    fn tx(redeemers: &[(u64, u64)]) -> TxBO {
+        let mut inputs = clib::TransactionInputs::new();
+        inputs.add(&clib::TransactionInput::new(
+            &ccrypto::TransactionHash::from_bytes(vec![1; 32]).unwrap(),
+            0,
+        ));
+        let addr = Address::from_bech32("addr_test1qp8cprhse9pnnv7f4l3n6pj0afq2hjm6f7r2205dz0583egaeu9dhacmtx94652q4ym0v9v2mcra0n28d5lrtjqzsgxqgk5t8s").unwrap();
+        let mut outputs = clib::TransactionOutputs::new();
+        outputs.add(&TransactionOutput::new(
+            &addr,
+            &cutils::Value::new(&to_bignum(2_000_000)),
+        ));
+        let body = clib::TransactionBody::new_tx_body(&inputs, &outputs, &to_bignum(200_000));
+
+        let mut witness = clib::TransactionWitnessSet::new();
+        if !redeemers.is_empty() {
+            let mut rdms = Redeemers::new();
+            for (i, (mem, steps)) in redeemers.iter().enumerate() {
+                rdms.add(&Redeemer::new(
+                    &RedeemerTag::new_spend(),
+                    &to_bignum(i as u64),
+                    &PlutusData::new_list(&PlutusList::new()),
+                    &ExUnits::new(&to_bignum(*mem), &to_bignum(*steps)),
+                ));
+            }
+            witness.set_redeemers(&rdms);
+        }
+        (
+            body,
+            witness,
+            None,
+            TransactionUnspentOutputs::new(),
+            1,
+            !redeemers.is_empty(),
+        )
+    }
+
+    fn params() -> crate::pparams::ProtocolParameters {
+        crate::pparams::ProtocolParameters::from_json(crate::pparams::tests::BABBAGE_PARAMS)
+            .unwrap()
+    }
+
+    fn assert_limit_exceeded(result: Result<(), MurinError>) {
+        match result {
+            Err(MurinError::TxLimitExceeded { split_off, .. }) => assert!(split_off.is_empty()),
+            other => panic!("expected TxLimitExceeded, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn check_limits_within_limits() {
+        let pp = params();
+        assert!(TxBuilder::check_limits(&tx(&[]), &None, &pp).is_ok());
+        // The execution units of all redeemers may use up the whole budget
+        assert!(TxBuilder::check_limits(&tx(&[(7_000_000, 5_000_000_000); 2]), &None, &pp).is_ok());
+    }
+
+    #[test]
+    fn check_limits_size_includes_signatures() {
+        let mut pp = params();
+        let tx = tx(&[]);
+        let unsigned = clib::Transaction::new(&tx.0, &tx.1, None).to_bytes().len() as u64;
+        pp.max_tx_size = unsigned + 1;
+        assert_limit_exceeded(TxBuilder::check_limits(&tx, &None, &pp));
+        pp.max_tx_size = unsigned + 200;
+        assert!(TxBuilder::check_limits(&tx, &None, &pp).is_ok());
+    }
+
+    #[test]
+    fn check_limits_execution_units() {
+        let pp = params();
+        assert_limit_exceeded(TxBuilder::check_limits(
+            &tx(&[(7_000_000, 1_000), (7_000_001, 1_000)]),
+            &None,
+            &pp,
+        ));
+        assert_limit_exceeded(TxBuilder::check_limits(
+            &tx(&[(1_000, 10_000_000_001)]),
+            &None,
+            &pp,
+        ));
+    }
+}
@@ -209,7 +209,7 @@ impl<'a> super::PerformTxb<AtRWDParams<'a>> for AtRWDBuilder {
                 + rwd_contract_ids.len(); // +1 due to signature in finalize
         let slot = cutils::to_bignum(
             gtxd.clone().get_current_slot()
-                + supporting_functions::get_ttl_tx(&gtxd.clone().get_network()),
+                + supporting_functions::get_ttl_tx(&gtxd.clone().get_network())?,
         );
         let mut txbody = clib::TransactionBody::new_tx_body(
             &builder.tx.as_ref().unwrap().1,
@@ -62,5 +62,6 @@ pub async fn finalize_rwd(
     let fin_tx =
         hex::encode(clib::Transaction::new(&tx_body, &tx_witness_stored, aux_data).to_bytes());
 
+    track_tx_state(&tx_hash, crate::utxomngr::TxState::Signed);
     create_and_submit_cbor_tx(fin_tx, tx_hash).await
 }
@@ -38,5 +38,6 @@ pub async fn finalize_utxopti(raw_tx: RawTx, pvks: Vec<String>) -> Result<String
 
     let fin_tx = hex::encode(clib::Transaction::new(&tx_body, &tx_witness, None).to_bytes());
 
+    track_tx_state(&tx_hash, crate::utxomngr::TxState::Signed);
     create_and_submit_cbor_tx(fin_tx, tx_hash).await
 }
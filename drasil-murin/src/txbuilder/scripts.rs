@@ -0,0 +1,154 @@
+//! Datum fields and script witnesses shared by the marketplace and auction builders
+use super::*;
+use clib::crypto::Ed25519KeyHash;
+use clib::plutus;
+use std::str::FromStr;
+
+pub(crate) fn int_field(i: u64) -> plutus::PlutusData {
+    plutus::PlutusData::new_integer(&cutils::BigInt::from_str(&i.to_string()).unwrap())
+}
+
+pub(crate) fn bytes_field(bytes: Vec<u8>) -> plutus::PlutusData {
+    plutus::PlutusData::new_bytes(bytes)
+}
+
+/// Key hash datum field, empty bytes for an absent key
+pub(crate) fn key_field(key: Option<&Ed25519KeyHash>) -> plutus::PlutusData {
+    bytes_field(key.map(|k| k.to_bytes()).unwrap_or_default())
+}
+
+/// Base address of the key hashes, or enterprise address without stake key
+pub(crate) fn key_address(
+    network: &clib::NetworkIdKind,
+    payment: &Ed25519KeyHash,
+    stake: Option<&Ed25519KeyHash>,
+) -> caddr::Address {
+    let net = match network {
+        clib::NetworkIdKind::Testnet => 0u8,
+        clib::NetworkIdKind::Mainnet => 1u8,
+    };
+    let payment = caddr::StakeCredential::from_keyhash(payment);
+    match stake {
+        Some(stake) => {
+            caddr::BaseAddress::new(net, &payment, &caddr::StakeCredential::from_keyhash(stake))
+                .to_address()
+        }
+        None => caddr::EnterpriseAddress::new(net, &payment).to_address(),
+    }
+}
+
+/// Royalties in promille and the key hash they are paid to
+pub(crate) fn royalty_terms(
+    addr: Option<&caddr::Address>,
+    rate: Option<f32>,
+) -> Result<(u64, Option<Ed25519KeyHash>), MurinError> {
+    let roy_rate = match rate {
+        Some(rate) => (rate * 1000.0).round() as u64,
+        None => 0,
+    };
+    match (addr, roy_rate) {
+        (_, 0) => Ok((0, None)),
+        (Some(addr), rate) => Ok((rate, Some(supporting_functions::get_payment_keyhash(addr)))),
+        (None, _) => Err(MurinError::new("royalties declared without an address")),
+    }
+}
+
+/// Witnesses spending `script_utxo` from `contract` with `redeemer`,
+/// sets the script data hash on `txbody`
+pub(crate) fn spend_script_witness(
+    txbody: &mut clib::TransactionBody,
+    script_utxo: &TransactionUnspentOutput,
+    contract: &plutus::PlutusScript,
+    redeemer: u64,
+) -> Result<clib::TransactionWitnessSet, MurinError> {
+    let mut script_utxos = TransactionUnspentOutputs::new();
+    script_utxos.add(script_utxo);
+    script_witness(txbody, &script_utxos, contract, redeemer)
+}
+
+/// Witnesses spending all `script_utxos` from `contract` with `redeemer`,
+/// sets the script data hash on `txbody`
+pub(crate) fn script_witness(
+    txbody: &mut clib::TransactionBody,
+    script_utxos: &TransactionUnspentOutputs,
+    contract: &plutus::PlutusScript,
+    redeemer: u64,
+) -> Result<clib::TransactionWitnessSet, MurinError> {
+    // Placeholder budget until the scripts are evaluated, it must not exceed the transaction limit in sum
+    let protocol_parameters = crate::pparams::protocol_parameters()?;
+    let share = std::cmp::max(4, script_utxos.len()) as u64;
+    let exunits = plutus::ExUnits::new(
+        &to_bignum(protocol_parameters.max_tx_execution_units.memory as u64 / share),
+        &to_bignum(protocol_parameters.max_tx_execution_units.steps as u64 / share),
+    );
+
+    let redeemer_data = plutus::PlutusData::new_constr_plutus_data(&plutus::ConstrPlutusData::new(
+        &cutils::to_bignum(redeemer),
+        &plutus::PlutusList::new(),
+    ));
+    let mut redeemers = plutus::Redeemers::new();
+    for script_utxo in script_utxos.0.iter() {
+        let script_input_index = get_input_position(txbody.inputs(), script_utxo.clone());
+        redeemers.add(&plutus::Redeemer::new(
+            &plutus::RedeemerTag::new_spend(),
+            &cutils::to_bignum(script_input_index.0 as u64),
+            &redeemer_data,
+            &exunits,
+        ));
+    }
+    debug!("Redeemers: {:?} \n", hex::encode(redeemers.to_bytes()));
+
+    let cost_models = protocol_parameters.get_CostMdls().unwrap();
+    let costmodel = cost_models
+        .get(&crate::pparams::mp_plutus::Language::new_plutus_v2())
+        .unwrap();
+    let mut cstmodls = crate::pparams::mp_plutus::Costmdls::new();
+    cstmodls.insert(&costmodel);
+    txbody.set_script_data_hash(&crate::pparams::hash::hash_script_data(
+        &redeemers, &cstmodls, None,
+    ));
+
+    let mut txwitness = clib::TransactionWitnessSet::new();
+    let mut scripts = plutus::PlutusScripts::new();
+    scripts.add(contract);
+    txwitness.set_plutus_scripts(&scripts);
+    txwitness.set_redeemers(&redeemers);
+    Ok(txwitness)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn royalty_terms_need_an_address() {
+        let addr = caddr::Address::from_bech32("addr_test1qp8cprhse9pnnv7f4l3n6pj0afq2hjm6f7r2205dz0583egaeu9dhacmtx94652q4ym0v9v2mcra0n28d5lrtjqzsgxqgk5t8s").unwrap();
+        let (rate, pkh) = royalty_terms(Some(&addr), Some(0.05)).unwrap();
+        assert_eq!(rate, 50);
+        assert_eq!(pkh, Some(supporting_functions::get_payment_keyhash(&addr)));
+        assert_eq!(royalty_terms(None, None).unwrap(), (0, None));
+        assert_eq!(royalty_terms(Some(&addr), Some(0.0)).unwrap(), (0, None));
+        assert!(royalty_terms(None, Some(0.05)).is_err());
+    }
+
+    #[test]
+    fn key_address_with_and_without_stake_key() {
+        let addr = caddr::Address::from_bech32("addr_test1qp8cprhse9pnnv7f4l3n6pj0afq2hjm6f7r2205dz0583egaeu9dhacmtx94652q4ym0v9v2mcra0n28d5lrtjqzsgxqgk5t8s").unwrap();
+        let base = caddr::BaseAddress::from_address(&addr).unwrap();
+        let payment = base.payment_cred().to_keyhash().unwrap();
+        let stake = base.stake_cred().to_keyhash();
+        assert_eq!(
+            key_address(&clib::NetworkIdKind::Testnet, &payment, stake.as_ref()).to_bytes(),
+            addr.to_bytes()
+        );
+        let enterprise = key_address(&clib::NetworkIdKind::Mainnet, &payment, None);
+        assert_eq!(
+            caddr::EnterpriseAddress::from_address(&enterprise)
+                .unwrap()
+                .payment_cred()
+                .to_keyhash(),
+            Some(payment)
+        );
+        assert_eq!(enterprise.network_id().unwrap(), 1);
+    }
+}
@@ -146,7 +146,7 @@ impl<'a> PerformTxb<AtDelegParams<'a>> for AtDelegBuilder {
             &fcrun,
         )?;
 
-        let slot = gtxd.clone().get_current_slot() + get_ttl_tx(&gtxd.clone().get_network());
+        let slot = gtxd.clone().get_current_slot() + get_ttl_tx(&gtxd.clone().get_network())?;
         let mut txbody = clib::TransactionBody::new_tx_body(&txins, &txouts_fin, fee);
         txbody.set_ttl(&cutils::to_bignum(slot));
         txbody.set_certs(&certs);
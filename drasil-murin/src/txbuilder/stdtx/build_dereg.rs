@@ -136,7 +136,7 @@ impl<'a> PerformTxb<AtDeregParams<'a>> for AtDeregBuilder {
             &fcrun,
         )?;
   
-        let deadline_slot = gtxd.clone().get_current_slot() + get_ttl_tx(&gtxd.get_network());
+        let deadline_slot = gtxd.clone().get_current_slot() + get_ttl_tx(&gtxd.get_network())?;
         let mut txbody = clib::TransactionBody::new_tx_body(&txins, &txouts_fin, fee);
         txbody.set_ttl(&cutils::to_bignum(deadline_slot));
         txbody.set_certs(&certs);
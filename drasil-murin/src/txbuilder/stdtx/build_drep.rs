@@ -0,0 +1,189 @@
+use crate::cardano::conway::{ConwayBodyExtension, ConwayCertificate};
+use crate::cardano::models::*;
+use crate::cardano::supporting_functions::{
+    balance_tx, get_ttl_tx, get_vkey_count, sum_output_values,
+};
+use crate::error::MurinError;
+use crate::txbuilder::stdtx::{DRepAction, DRepTxData};
+use crate::txbuilder::{input_selection, TxBO};
+use crate::PerformTxb;
+use crate::TxData;
+use cardano_serialization_lib as clib;
+use cardano_serialization_lib::{address as caddr, utils as cutils};
+
+// DRep Registration, Update and Retirement Builder Type
+#[derive(Debug, Clone)]
+pub struct AtDRepBuilder {
+    pub stxd: DRepTxData,
+}
+
+pub type AtDRepParams<'a> = &'a DRepTxData;
+
+impl AtDRepBuilder {
+    fn deposit(&self) -> Result<u64, MurinError> {
+        match self.stxd.get_deposit() {
+            Some(d) => Ok(d),
+            None => crate::pparams::protocol_parameters()?
+                .drep_deposit
+                .ok_or_else(|| {
+                    MurinError::new("The protocol parameters do not contain a DRep deposit")
+                }),
+        }
+    }
+}
+
+impl<'a> PerformTxb<AtDRepParams<'a>> for AtDRepBuilder {
+    fn new(t: AtDRepParams) -> Self {
+        AtDRepBuilder { stxd: t.clone() }
+    }
+
+    fn conway_extension(&self) -> Result<Option<ConwayBodyExtension>, MurinError> {
+        let drep_credential = caddr::StakeCredential::from_keyhash(&self.stxd.get_drep_keyhash());
+        let cert = match self.stxd.get_action() {
+            DRepAction::Register => ConwayCertificate::DRepRegistration {
+                drep_credential,
+                deposit: self.deposit()?,
+                anchor: self.stxd.get_anchor(),
+            },
+            DRepAction::Update => ConwayCertificate::DRepUpdate {
+                drep_credential,
+                anchor: self.stxd.get_anchor(),
+            },
+            DRepAction::Retire => ConwayCertificate::DRepDeregistration {
+                drep_credential,
+                refund: self.deposit()?,
+            },
+        };
+        let mut ext = ConwayBodyExtension::new();
+        ext.add_certificate(cert);
+        Ok(Some(ext))
+    }
+
+    fn perform_txb(
+        &self,
+        fee: &clib::utils::BigNum,
+        gtxd: &TxData,
+        _pvks: &[String],
+        fcrun: bool,
+    ) -> std::result::Result<TxBO, MurinError> {
+        if fcrun {
+            info!("--------------------------------------------------------------------------------------------------------");
+            info!("-----------------------------------------Fee Calculation------------------------------------------------");
+            info!("---------------------------------------------------------------------------------------------------------\n");
+        } else {
+            info!("--------------------------------------------------------------------------------------------------------");
+            info!("-----------------------------------------Build Transaction----------------------------------------------");
+            info!("--------------------------------------------------------------------------------------------------------\n");
+        }
+
+        let action = self.stxd.get_action();
+        info!(
+            "DRep {:?}: {}",
+            action,
+            hex::encode(self.stxd.get_drep_keyhash().to_bytes())
+        );
+
+        let owner_address = match gtxd.get_senders_address(None) {
+            Some(a) => a,
+            None => {
+                return Err(MurinError::new(
+                    "Address of Wallet owner could not be found",
+                ))
+            }
+        };
+
+        let aux_data = None;
+        //////////////////////////////////////////////////////////////////////////////////////////////////////
+        //Add Inputs and Outputs
+        //
+        //
+        ///////////////////////////////////////////////////////////////////////////////////////////////////////
+
+        let mut txouts = clib::TransactionOutputs::new();
+        let deposit_val = match action {
+            DRepAction::Update => cutils::Value::zero(),
+            _ => cutils::Value::new(&cutils::to_bignum(self.deposit()?)),
+        };
+
+        // The refund of a retirement is paid back to the owner
+        if action == DRepAction::Retire {
+            txouts.add(&clib::TransactionOutput::new(&owner_address, &deposit_val));
+        }
+
+        // Inputs
+        let mut input_txuos = gtxd.clone().get_inputs();
+
+        // Check if some utxos in inputs are in use and remove them
+        if let Some(used_utxos) = crate::utxomngr::usedutxos::check_any_utxo_used(&input_txuos)? {
+            info!("\n\n");
+            info!("USED UTXOS: {:?}", used_utxos);
+            info!("\n\n");
+            input_txuos.remove_used_utxos(used_utxos);
+        }
+
+        // Balance TX
+        let mut fee_paid = false;
+        let mut first_run = true;
+        let mut txos_paid = false;
+        let (already_paid, mut tbb_values) = match action {
+            DRepAction::Register => (None, deposit_val.clone()),
+            DRepAction::Update => (None, cutils::Value::zero()),
+            DRepAction::Retire => (Some(&deposit_val), cutils::Value::zero()),
+        };
+        let mut acc = cutils::Value::zero();
+        let change_address = owner_address.clone();
+
+        let mut needed_value = sum_output_values(&txouts);
+        needed_value.set_coin(&needed_value.coin().checked_add(&fee.clone()).unwrap());
+        if action == DRepAction::Register {
+            needed_value = needed_value.checked_add(&deposit_val)?;
+        }
+        let security =
+            cutils::to_bignum(cutils::from_bignum(&needed_value.coin()) / 100 * 10 + MIN_ADA); // 10% Security for min utxo etc.
+        needed_value.set_coin(&needed_value.coin().checked_add(&security).unwrap());
+        let mut needed_value = cutils::Value::new(&needed_value.coin());
+
+        let (txins, mut input_txuos) =
+            input_selection(None, &mut needed_value, &input_txuos, None, None)?;
+
+        let saved_input_txuos = input_txuos.clone();
+        let vkey_counter = get_vkey_count(&input_txuos, None) + 1; // +1 due to the DRep key signature in finalize
+
+        let txouts_fin = balance_tx(
+            &mut input_txuos,
+            &Tokens::new(),
+            &mut txouts,
+            already_paid,
+            fee,
+            &mut fee_paid,
+            &mut first_run,
+            &mut txos_paid,
+            &mut tbb_values,
+            &owner_address,
+            &change_address,
+            &mut acc,
+            None,
+            &fcrun,
+        )?;
+
+        let slot = gtxd.clone().get_current_slot() + get_ttl_tx(&gtxd.clone().get_network())?;
+        let mut txbody = clib::TransactionBody::new_tx_body(&txins, &txouts_fin, fee);
+        txbody.set_ttl(&cutils::to_bignum(slot));
+        // The DRep certificate is added by the Conway extension
+
+        let txwitness = clib::TransactionWitnessSet::new();
+
+        debug!("TxWitness: {:?}", hex::encode(txwitness.to_bytes()));
+        debug!("TxBody: {:?}", hex::encode(txbody.to_bytes()));
+        debug!("--------------------Iteration Ended------------------------------");
+        debug!("Vkey Counter at End: {:?}", vkey_counter);
+        Ok((
+            txbody,
+            txwitness,
+            aux_data,
+            saved_input_txuos,
+            vkey_counter,
+            false,
+        ))
+    }
+}
@@ -94,7 +94,7 @@ impl<'a> PerformTxb<AtAWParams<'a>> for AtAWBuilder {
         );
         txbody.set_withdrawals(&withdrawals);
         txbody.set_ttl(&cutils::to_bignum(
-            gtxd.clone().get_current_slot() + get_ttl_tx(&gtxd.clone().get_network()),
+            gtxd.clone().get_current_slot() + get_ttl_tx(&gtxd.clone().get_network())?,
         ));
 
         // Stake address must be registered
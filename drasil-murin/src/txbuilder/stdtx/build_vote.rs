@@ -0,0 +1,148 @@
+use crate::cardano::conway::ConwayBodyExtension;
+use crate::cardano::models::*;
+use crate::cardano::supporting_functions::{
+    balance_tx, get_ttl_tx, get_vkey_count, sum_output_values,
+};
+use crate::error::MurinError;
+use crate::txbuilder::{input_selection, stdtx::VoteTxData, TxBO};
+use crate::PerformTxb;
+use crate::TxData;
+use cardano_serialization_lib as clib;
+use cardano_serialization_lib::utils as cutils;
+
+// Governance Vote Builder Type
+#[derive(Debug, Clone)]
+pub struct AtVoteBuilder {
+    pub stxd: VoteTxData,
+}
+
+pub type AtVoteParams<'a> = &'a VoteTxData;
+
+impl<'a> PerformTxb<AtVoteParams<'a>> for AtVoteBuilder {
+    fn new(t: AtVoteParams) -> Self {
+        AtVoteBuilder { stxd: t.clone() }
+    }
+
+    fn conway_extension(&self) -> Result<Option<ConwayBodyExtension>, MurinError> {
+        let votes = self.stxd.get_votes();
+        if votes.is_empty() {
+            return Err(MurinError::new("No votes to cast"));
+        }
+        let mut ext = ConwayBodyExtension::new();
+        for (action, procedure) in votes {
+            ext.add_vote(self.stxd.get_voter(), action, procedure);
+        }
+        Ok(Some(ext))
+    }
+
+    fn perform_txb(
+        &self,
+        fee: &clib::utils::BigNum,
+        gtxd: &TxData,
+        _pvks: &[String],
+        fcrun: bool,
+    ) -> std::result::Result<TxBO, MurinError> {
+        if fcrun {
+            info!("--------------------------------------------------------------------------------------------------------");
+            info!("-----------------------------------------Fee Calculation------------------------------------------------");
+            info!("---------------------------------------------------------------------------------------------------------\n");
+        } else {
+            info!("--------------------------------------------------------------------------------------------------------");
+            info!("-----------------------------------------Build Transaction----------------------------------------------");
+            info!("--------------------------------------------------------------------------------------------------------\n");
+        }
+
+        info!(
+            "Voter {:?} casts {} vote(s)",
+            self.stxd.get_voter(),
+            self.stxd.get_votes().len()
+        );
+
+        let owner_address = match gtxd.get_senders_address(None) {
+            Some(a) => a,
+            None => {
+                return Err(MurinError::new(
+                    "Address of Wallet owner could not be found",
+                ))
+            }
+        };
+
+        let aux_data = None;
+        //////////////////////////////////////////////////////////////////////////////////////////////////////
+        //Add Inputs and Outputs
+        //
+        //
+        ///////////////////////////////////////////////////////////////////////////////////////////////////////
+
+        let mut txouts = clib::TransactionOutputs::new();
+
+        // Inputs
+        let mut input_txuos = gtxd.clone().get_inputs();
+
+        // Check if some utxos in inputs are in use and remove them
+        if let Some(used_utxos) = crate::utxomngr::usedutxos::check_any_utxo_used(&input_txuos)? {
+            info!("\n\n");
+            info!("USED UTXOS: {:?}", used_utxos);
+            info!("\n\n");
+            input_txuos.remove_used_utxos(used_utxos);
+        }
+
+        // Balance TX
+        let mut fee_paid = false;
+        let mut first_run = true;
+        let mut txos_paid = false;
+        let mut tbb_values = cutils::Value::zero();
+        let mut acc = cutils::Value::zero();
+        let change_address = owner_address.clone();
+
+        let mut needed_value = sum_output_values(&txouts);
+        needed_value.set_coin(&needed_value.coin().checked_add(&fee.clone()).unwrap());
+        let security =
+            cutils::to_bignum(cutils::from_bignum(&needed_value.coin()) / 100 * 10 + MIN_ADA); // 10% Security for min utxo etc.
+        needed_value.set_coin(&needed_value.coin().checked_add(&security).unwrap());
+        let mut needed_value = cutils::Value::new(&needed_value.coin());
+
+        let (txins, mut input_txuos) =
+            input_selection(None, &mut needed_value, &input_txuos, None, None)?;
+
+        let saved_input_txuos = input_txuos.clone();
+        let vkey_counter = get_vkey_count(&input_txuos, None) + 1; // +1 due to the voter signature in finalize
+
+        let txouts_fin = balance_tx(
+            &mut input_txuos,
+            &Tokens::new(),
+            &mut txouts,
+            None,
+            fee,
+            &mut fee_paid,
+            &mut first_run,
+            &mut txos_paid,
+            &mut tbb_values,
+            &owner_address,
+            &change_address,
+            &mut acc,
+            None,
+            &fcrun,
+        )?;
+
+        let slot = gtxd.clone().get_current_slot() + get_ttl_tx(&gtxd.clone().get_network())?;
+        let mut txbody = clib::TransactionBody::new_tx_body(&txins, &txouts_fin, fee);
+        txbody.set_ttl(&cutils::to_bignum(slot));
+        // The voting procedures are added by the Conway extension
+
+        let txwitness = clib::TransactionWitnessSet::new();
+
+        debug!("TxWitness: {:?}", hex::encode(txwitness.to_bytes()));
+        debug!("TxBody: {:?}", hex::encode(txbody.to_bytes()));
+        debug!("--------------------Iteration Ended------------------------------");
+        debug!("Vkey Counter at End: {:?}", vkey_counter);
+        Ok((
+            txbody,
+            txwitness,
+            aux_data,
+            saved_input_txuos,
+            vkey_counter,
+            false,
+        ))
+    }
+}
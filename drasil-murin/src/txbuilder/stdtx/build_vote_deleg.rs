@@ -0,0 +1,248 @@
+use crate::cardano::conway::{ConwayBodyExtension, ConwayCertificate};
+use crate::cardano::models::*;
+use crate::cardano::supporting_functions::{
+    balance_tx, get_ttl_tx, get_vkey_count, sum_output_values,
+};
+use crate::error::MurinError;
+use crate::txbuilder::{input_selection, stdtx::VoteDelegTxData, TxBO};
+use crate::PerformTxb;
+use crate::TxData;
+use cardano_serialization_lib as clib;
+use cardano_serialization_lib::{address as caddr, utils as cutils};
+
+// Vote Delegation Builder Type
+#[derive(Debug, Clone)]
+pub struct AtVoteDelegBuilder {
+    pub stxd: VoteDelegTxData,
+}
+
+pub type AtVoteDelegParams<'a> = &'a VoteDelegTxData;
+
+impl AtVoteDelegBuilder {
+    fn stake_credential(&self) -> Result<caddr::StakeCredential, MurinError> {
+        let stake_address = self
+            .stxd
+            .get_stake_address()
+            .ok_or_else(|| MurinError::new("The delegating stake address is not set"))?;
+        Ok(caddr::RewardAddress::from_address(&stake_address)
+            .ok_or_else(|| MurinError::new("The delegating address is not a stake address"))?
+            .payment_cred())
+    }
+}
+
+impl<'a> PerformTxb<AtVoteDelegParams<'a>> for AtVoteDelegBuilder {
+    fn new(t: AtVoteDelegParams) -> Self {
+        AtVoteDelegBuilder { stxd: t.clone() }
+    }
+
+    fn conway_extension(&self) -> Result<Option<ConwayBodyExtension>, MurinError> {
+        let stake_credential = self.stake_credential()?;
+        let cert = if self.stxd.get_registered() {
+            ConwayCertificate::VoteDelegation {
+                stake_credential,
+                drep: self.stxd.get_drep(),
+            }
+        } else {
+            ConwayCertificate::VoteRegistrationDelegation {
+                stake_credential,
+                drep: self.stxd.get_drep(),
+                deposit: crate::pparams::protocol_parameters()?.stake_address_deposit,
+            }
+        };
+        let mut ext = ConwayBodyExtension::new();
+        ext.add_certificate(cert);
+        Ok(Some(ext))
+    }
+
+    fn perform_txb(
+        &self,
+        fee: &clib::utils::BigNum,
+        gtxd: &TxData,
+        _pvks: &[String],
+        fcrun: bool,
+    ) -> std::result::Result<TxBO, MurinError> {
+        if fcrun {
+            info!("--------------------------------------------------------------------------------------------------------");
+            info!("-----------------------------------------Fee Calculation------------------------------------------------");
+            info!("---------------------------------------------------------------------------------------------------------\n");
+        } else {
+            info!("--------------------------------------------------------------------------------------------------------");
+            info!("-----------------------------------------Build Transaction----------------------------------------------");
+            info!("--------------------------------------------------------------------------------------------------------\n");
+        }
+
+        let registered = self.stxd.get_registered();
+        log::info!("\nThis user is registered: {}\n", registered);
+        let owner_address = match gtxd.get_senders_address(None) {
+            Some(a) => a,
+            None => {
+                return Err(MurinError::new(
+                    "Address of Wallet owner could not be found",
+                ))
+            }
+        };
+
+        let owner_base_addr = caddr::BaseAddress::from_address(&owner_address).ok_or_else(|| {
+            MurinError::new("The given address isn't a base address and doesn't contain staking information")
+        })?;
+        let deleg_stake_creds = self.stake_credential()?;
+        let gtxd_stake_creds = caddr::RewardAddress::from_address(&gtxd.get_stake_address())
+            .ok_or_else(|| MurinError::new("The delegating address is not a stake address"))?
+            .payment_cred();
+        if owner_base_addr.stake_cred().to_bytes() != deleg_stake_creds.to_bytes()
+            || gtxd_stake_creds.to_bytes() != deleg_stake_creds.to_bytes()
+        {
+            return Err(MurinError::new("Inconsistent Stake Key Data, forbidden!"));
+        }
+
+        let aux_data = None;
+        //////////////////////////////////////////////////////////////////////////////////////////////////////
+        //Add Inputs and Outputs
+        //
+        //
+        ///////////////////////////////////////////////////////////////////////////////////////////////////////
+
+        let mut txouts = clib::TransactionOutputs::new();
+        let deposit_val = cutils::Value::new(&cutils::to_bignum(
+            crate::pparams::protocol_parameters()?.stake_address_deposit,
+        ));
+
+        // Inputs
+        let mut input_txuos = gtxd.clone().get_inputs();
+
+        // Check if some utxos in inputs are in use and remove them
+        if let Some(used_utxos) = crate::utxomngr::usedutxos::check_any_utxo_used(&input_txuos)? {
+            info!("\n\n");
+            info!("USED UTXOS: {:?}", used_utxos);
+            info!("\n\n");
+            input_txuos.remove_used_utxos(used_utxos);
+        }
+
+        // Balance TX
+        let mut fee_paid = false;
+        let mut first_run = true;
+        let mut txos_paid = false;
+        let mut tbb_values = cutils::Value::zero();
+        if !registered {
+            tbb_values = deposit_val.clone();
+        }
+        let mut acc = cutils::Value::zero();
+        let change_address = owner_address.clone();
+
+        let mut needed_value = sum_output_values(&txouts);
+        needed_value.set_coin(&needed_value.coin().checked_add(&fee.clone()).unwrap());
+        if !registered {
+            needed_value = needed_value.checked_add(&deposit_val)?;
+        }
+        let security =
+            cutils::to_bignum(cutils::from_bignum(&needed_value.coin()) / 100 * 10 + MIN_ADA); // 10% Security for min utxo etc.
+        needed_value.set_coin(&needed_value.coin().checked_add(&security).unwrap());
+        let mut needed_value = cutils::Value::new(&needed_value.coin());
+
+        let (txins, mut input_txuos) =
+            input_selection(None, &mut needed_value, &input_txuos, None, None)?;
+
+        let saved_input_txuos = input_txuos.clone();
+        let vkey_counter = get_vkey_count(&input_txuos, None) + 1; // +1 due to signature in finalize
+
+        let txouts_fin = balance_tx(
+            &mut input_txuos,
+            &Tokens::new(),
+            &mut txouts,
+            None,
+            fee,
+            &mut fee_paid,
+            &mut first_run,
+            &mut txos_paid,
+            &mut tbb_values,
+            &owner_address,
+            &change_address,
+            &mut acc,
+            None,
+            &fcrun,
+        )?;
+
+        let slot = gtxd.clone().get_current_slot() + get_ttl_tx(&gtxd.clone().get_network())?;
+        let mut txbody = clib::TransactionBody::new_tx_body(&txins, &txouts_fin, fee);
+        txbody.set_ttl(&cutils::to_bignum(slot));
+        // The vote delegation certificate is added by the Conway extension
+
+        let txwitness = clib::TransactionWitnessSet::new();
+
+        debug!("TxWitness: {:?}", hex::encode(txwitness.to_bytes()));
+        debug!("TxBody: {:?}", hex::encode(txbody.to_bytes()));
+        debug!("--------------------Iteration Ended------------------------------");
+        debug!("Vkey Counter at End: {:?}", vkey_counter);
+        Ok((
+            txbody,
+            txwitness,
+            aux_data,
+            saved_input_txuos,
+            vkey_counter,
+            false,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pparams::{ProtocolParameterSource, ProtocolParameters};
+    use std::sync::Arc;
+
+    struct TestSource;
+
+    impl ProtocolParameterSource for TestSource {
+        fn protocol_parameters(&self) -> Result<ProtocolParameters, MurinError> {
+            ProtocolParameters::from_json(crate::pparams::tests::BABBAGE_PARAMS)
+        }
+    }
+
+    fn builder(registered: bool) -> AtVoteDelegBuilder {
+        let stake_address = caddr::RewardAddress::new(
+            0,
+            &caddr::StakeCredential::from_keyhash(
+                &clib::crypto::Ed25519KeyHash::from_bytes(vec![5; 28]).unwrap(),
+            ),
+        )
+        .to_address();
+        let mut stxd = VoteDelegTxData::new("always_abstain").unwrap();
+        stxd.set_stake_address(&stake_address);
+        stxd.set_registered(Some(registered));
+        AtVoteDelegBuilder::new(&stxd)
+    }
+
+    fn certificate_bytes(ext: &ConwayBodyExtension) -> Vec<u8> {
+        let body = clib::TransactionBody::new_tx_body(
+            &clib::TransactionInputs::new(),
+            &clib::TransactionOutputs::new(),
+            &cutils::to_bignum(200000),
+        );
+        let original = body.to_bytes();
+        let extended = ext.extend_body(&body).unwrap();
+        // body key 4 followed by an array with one certificate
+        assert_eq!(&extended[original.len()..original.len() + 2], &[0x04, 0x81]);
+        extended[original.len() + 2..].to_vec()
+    }
+
+    #[test]
+    fn vote_delegation_certificates() {
+        crate::pparams::set_protocol_parameter_source(Arc::new(TestSource));
+        let mut credential = vec![0x82, 0x00, 0x58, 0x1c];
+        credential.extend([5; 28]);
+
+        // vote_reg_deleg_cert = (12, stake_credential, drep, coin)
+        let ext = builder(false).conway_extension().unwrap().unwrap();
+        let mut expected = vec![0x84, 0x0c];
+        expected.extend(&credential);
+        expected.extend([0x81, 0x02, 0x1a, 0x00, 0x1e, 0x84, 0x80]);
+        assert_eq!(certificate_bytes(&ext), expected);
+
+        // vote_deleg_cert = (9, stake_credential, drep)
+        let ext = builder(true).conway_extension().unwrap().unwrap();
+        let mut expected = vec![0x83, 0x09];
+        expected.extend(&credential);
+        expected.extend([0x81, 0x02]);
+        assert_eq!(certificate_bytes(&ext), expected);
+    }
+}
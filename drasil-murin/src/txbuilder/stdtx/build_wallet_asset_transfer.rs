@@ -190,7 +190,7 @@ impl<'a> PerformTxb<AtSATParams<'a>> for AtSATBuilder {
             supporting_functions::get_vkey_count(&builder.tx.as_ref().unwrap().0, None);
         let slot = to_bignum(
             gtxd.clone().get_current_slot()
-                + supporting_functions::get_ttl_tx(&gtxd.clone().get_network()),
+                + supporting_functions::get_ttl_tx(&gtxd.clone().get_network())?,
         );
         let mut txbody = clib::TransactionBody::new_tx_body(
             &builder.tx.as_ref().unwrap().1,
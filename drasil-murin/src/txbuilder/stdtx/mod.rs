@@ -6,12 +6,19 @@ pub mod build_dereg;
 pub use build_dereg::{AtDeregBuilder, AtDeregParams};
 pub mod build_reward_withdrawal;
 pub use build_reward_withdrawal::{AtAWBuilder, AtAWParams};
+pub mod build_drep;
+pub use build_drep::{AtDRepBuilder, AtDRepParams};
+pub mod build_vote_deleg;
+pub use build_vote_deleg::{AtVoteDelegBuilder, AtVoteDelegParams};
+pub mod build_vote;
+pub use build_vote::{AtVoteBuilder, AtVoteParams};
 
 use cardano_serialization_lib::{crypto as ccrypto, utils};
 use cardano_serialization_lib::{address::Address, utils::BigNum, AssetName, PolicyID};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
+use crate::conway::{drep_key_hash, Anchor, DRep, GovActionId, Vote, Voter, VotingProcedure};
 use crate::MurinError;
 pub mod build_cpo;
 pub mod build_wallet_asset_transfer;
@@ -30,6 +37,12 @@ pub mod build_wallet_asset_transfer;
 /// 
 /// - Build Wallet Asset Transfer Transaction (build_wallet_asset_transfer.rs)
 /// 
+/// - Build DRep Registration, Update and Retirement Transaction (build_drep.rs)
+/// 
+/// - Build Vote Delegation Transaction (build_vote_deleg.rs)
+/// 
+/// - Build Governance Vote Transaction (build_vote.rs)
+/// 
  
 
 /// A StdAssetHandle is a handle to an Asset on the Cardano blockchain.
@@ -167,6 +180,161 @@ impl DeregTxData {
     }
 }
 
+/// Certificate a DRep transaction carries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DRepAction {
+    Register,
+    Update,
+    Retire,
+}
+
+/// DRepTxData is the 'DRep Registration / Update / Retirement Transaction' specific type and holds
+/// the key hash of the DRep credential, the optional metadata anchor and the deposit.
+/// The deposit is taken from the protocol parameters if it is not set, for a retirement it must
+/// be the deposit paid on registration.
+#[derive(Debug, Clone)]
+pub struct DRepTxData {
+    action: DRepAction,
+    drep_keyhash: ccrypto::Ed25519KeyHash,
+    anchor: Option<Anchor>,
+    deposit: Option<u64>,
+}
+
+impl DRepTxData {
+    /// `drep_key` is a DRep id, a hex encoded key hash or the hex encoded public DRep key
+    pub fn new(
+        action: DRepAction,
+        drep_key: &str,
+        anchor: Option<Anchor>,
+    ) -> Result<DRepTxData, MurinError> {
+        if action == DRepAction::Retire && anchor.is_some() {
+            return Err(MurinError::new("a DRep retirement has no anchor"));
+        }
+        Ok(DRepTxData {
+            action,
+            drep_keyhash: drep_key_hash(drep_key)?,
+            anchor,
+            deposit: None,
+        })
+    }
+
+    pub fn get_action(&self) -> DRepAction {
+        self.action
+    }
+
+    pub fn get_drep_keyhash(&self) -> ccrypto::Ed25519KeyHash {
+        self.drep_keyhash.clone()
+    }
+
+    pub fn get_anchor(&self) -> Option<Anchor> {
+        self.anchor.clone()
+    }
+
+    pub fn get_deposit(&self) -> Option<u64> {
+        self.deposit
+    }
+
+    pub fn set_deposit(&mut self, deposit: u64) {
+        self.deposit = Some(deposit);
+    }
+}
+
+/// VoteDelegTxData is the 'Vote Delegation Transaction' specific type and holds the DRep
+/// the voting power of the stake address is delegated to, the delegating stake address
+/// and if the stake address is already registered.
+#[derive(Debug, Clone)]
+pub struct VoteDelegTxData {
+    drep: DRep,
+    stake_address: Option<Address>,
+    registered: Option<bool>,
+}
+
+impl VoteDelegTxData {
+    /// `drep` is a DRep id, `always_abstain` or `always_no_confidence`
+    pub fn new(drep: &str) -> Result<VoteDelegTxData, MurinError> {
+        Ok(VoteDelegTxData {
+            drep: DRep::from_str(drep)?,
+            stake_address: None,
+            registered: None,
+        })
+    }
+
+    pub fn get_drep(&self) -> DRep {
+        self.drep.clone()
+    }
+
+    pub fn get_stake_address(&self) -> Option<Address> {
+        self.stake_address.clone()
+    }
+
+    pub fn set_stake_address(&mut self, stake_address: &Address) {
+        self.stake_address = Some(stake_address.clone());
+    }
+
+    pub fn get_registered(&self) -> bool {
+        self.registered.unwrap_or(false)
+    }
+
+    pub fn set_registered(&mut self, r: Option<bool>) {
+        self.registered = r;
+    }
+}
+
+impl std::str::FromStr for VoteDelegTxData {
+    type Err = MurinError;
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        VoteDelegTxData::new(src)
+    }
+}
+
+/// VoteTxData is the 'Governance Vote Transaction' specific type and holds the voter
+/// and its votes on governance actions
+#[derive(Debug, Clone)]
+pub struct VoteTxData {
+    voter: Voter,
+    votes: Vec<(GovActionId, VotingProcedure)>,
+}
+
+impl VoteTxData {
+    pub fn new(voter: Voter) -> VoteTxData {
+        VoteTxData {
+            voter,
+            votes: Vec::new(),
+        }
+    }
+
+    /// Adds a vote on `gov_action_id` ('<txhash>#<index>')
+    pub fn add_vote(
+        &mut self,
+        gov_action_id: &str,
+        vote: &str,
+        anchor: Option<Anchor>,
+    ) -> Result<(), MurinError> {
+        let action = GovActionId::from_str(gov_action_id)?;
+        if self.votes.iter().any(|(a, _)| *a == action) {
+            return Err(MurinError::new(&format!(
+                "more than one vote on governance action {gov_action_id}"
+            )));
+        }
+        self.votes.push((
+            action,
+            VotingProcedure {
+                vote: Vote::from_str(vote)?,
+                anchor,
+            },
+        ));
+        Ok(())
+    }
+
+    pub fn get_voter(&self) -> Voter {
+        self.voter.clone()
+    }
+
+    pub fn get_votes(&self) -> Vec<(GovActionId, VotingProcedure)> {
+        self.votes.clone()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::MurinError;
@@ -235,4 +403,22 @@ mod tests {
         let get_registered_none = dereg_tx_data.get_registered();
         assert!(!get_registered_none);
     }
+
+    #[test]
+    fn vote_tx_data() -> Result<(), MurinError> {
+        let voter = crate::conway::Voter::DRepKeyHash(Ed25519KeyHash::from_bytes(vec![3; 28])?);
+        let mut vote_tx_data = super::VoteTxData::new(voter);
+        let action = format!("{}#0", hex::encode([9; 32]));
+
+        vote_tx_data.add_vote(&action, "yes", None)?;
+        assert!(vote_tx_data.add_vote(&action, "no", None).is_err());
+        let other_action = format!("{}#1", hex::encode([9; 32]));
+        assert!(vote_tx_data.add_vote(&other_action, "maybe", None).is_err());
+        assert_eq!(vote_tx_data.get_votes().len(), 1);
+
+        let vote_deleg = super::VoteDelegTxData::from_str("always_no_confidence")?;
+        assert_eq!(vote_deleg.get_drep(), crate::conway::DRep::AlwaysNoConfidence);
+        assert!(!vote_deleg.get_registered());
+        Ok(())
+    }
 }
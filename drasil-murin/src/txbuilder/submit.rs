@@ -0,0 +1,413 @@
+//! Transaction submission to an arbitrary set of submit-api endpoints.
+//!
+//! The endpoints are contacted in parallel, each with its own timeout and
+//! backoff retries. Depending on the [`SubmitMode`] the submission is
+//! successful as soon as the first endpoint accepted the transaction or once a
+//! quorum of endpoints accepted it.
+use std::time::Duration;
+
+use futures::stream::{FuturesUnordered, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::error::MurinError;
+
+/// Endpoint list, comma separated
+pub const SUBMIT_ENDPOINTS_ENV: &str = "CARDANO_TX_SUBMIT_ENDPOINTS";
+/// `first` or `quorum:<n>`
+pub const SUBMIT_MODE_ENV: &str = "CARDANO_TX_SUBMIT_MODE";
+/// Retries per endpoint after the first attempt
+pub const SUBMIT_RETRIES_ENV: &str = "CARDANO_TX_SUBMIT_RETRIES";
+/// Timeout per attempt in milliseconds
+pub const SUBMIT_TIMEOUT_ENV: &str = "CARDANO_TX_SUBMIT_TIMEOUT_MS";
+/// Initial backoff between attempts in milliseconds, doubled on every retry
+pub const SUBMIT_BACKOFF_ENV: &str = "CARDANO_TX_SUBMIT_BACKOFF_MS";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SubmitMode {
+    /// Successful when any endpoint accepted the transaction
+    FirstSuccess,
+    /// Successful when at least `n` endpoints accepted the transaction
+    Quorum(usize),
+}
+
+impl std::str::FromStr for SubmitMode {
+    type Err = MurinError;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let src = src.trim().to_lowercase();
+        match src.split_once(':') {
+            None if src == "first" || src.is_empty() => Ok(SubmitMode::FirstSuccess),
+            Some(("quorum", n)) => {
+                let n = n.parse::<usize>()?;
+                if n == 0 {
+                    return Err(MurinError::new("submit quorum must be at least 1"));
+                }
+                Ok(SubmitMode::Quorum(n))
+            }
+            _ => Err(MurinError::new(&format!("unknown submit mode: '{src}'"))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EndpointStatus {
+    /// The endpoint accepted the transaction and returned the expected hash
+    Accepted,
+    /// The endpoint answered but refused the transaction
+    Rejected(String),
+    /// The endpoint could not be reached or answered with a server error
+    Unavailable(String),
+    /// The submission was still running when the policy was satisfied
+    Pending,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EndpointResult {
+    pub endpoint: String,
+    pub attempts: u32,
+    pub status: EndpointStatus,
+}
+
+impl EndpointResult {
+    pub fn is_accepted(&self) -> bool {
+        self.status == EndpointStatus::Accepted
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubmitReport {
+    pub tx_hash: String,
+    pub mode: SubmitMode,
+    pub results: Vec<EndpointResult>,
+}
+
+impl SubmitReport {
+    pub fn accepted(&self) -> usize {
+        self.results.iter().filter(|r| r.is_accepted()).count()
+    }
+
+    pub fn is_success(&self) -> bool {
+        match self.mode {
+            SubmitMode::FirstSuccess => self.accepted() >= 1,
+            SubmitMode::Quorum(n) => self.accepted() >= n,
+        }
+    }
+}
+
+impl std::fmt::Display for SubmitReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "tx '{}' accepted by {}/{} endpoints ({:?})",
+            self.tx_hash,
+            self.accepted(),
+            self.results.len(),
+            self.mode
+        )?;
+        for r in &self.results {
+            write!(
+                f,
+                "; {} after {} attempt(s): {:?}",
+                r.endpoint, r.attempts, r.status
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubmitPolicy {
+    pub endpoints: Vec<String>,
+    pub mode: SubmitMode,
+    pub retries: u32,
+    pub timeout: Duration,
+    pub backoff: Duration,
+}
+
+impl SubmitPolicy {
+    pub fn new(endpoints: Vec<String>, mode: SubmitMode) -> Self {
+        SubmitPolicy {
+            endpoints: endpoints
+                .into_iter()
+                .map(|e| e.trim().to_string())
+                .filter(|e| !e.is_empty())
+                .collect(),
+            mode,
+            retries: 2,
+            timeout: Duration::from_secs(5),
+            backoff: Duration::from_millis(250),
+        }
+    }
+
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Reads the policy from the environment.
+    ///
+    /// The endpoints are taken from `CARDANO_TX_SUBMIT_ENDPOINTS`, if it is not set
+    /// the legacy variables `CARDANO_TX_SUBMIT_ENDPOINT1..3` are used.
+    pub fn from_env() -> Result<Self, MurinError> {
+        let endpoints = match std::env::var(SUBMIT_ENDPOINTS_ENV) {
+            Ok(list) => list.split(',').map(|e| e.to_string()).collect(),
+            Err(_) => (1..=3)
+                .filter_map(|i| std::env::var(format!("CARDANO_TX_SUBMIT_ENDPOINT{i}")).ok())
+                .collect(),
+        };
+        let mode = match std::env::var(SUBMIT_MODE_ENV) {
+            Ok(m) => m.parse::<SubmitMode>()?,
+            Err(_) => SubmitMode::FirstSuccess,
+        };
+        let mut policy = SubmitPolicy::new(endpoints, mode);
+        if let Ok(r) = std::env::var(SUBMIT_RETRIES_ENV) {
+            policy.retries = r.parse::<u32>()?;
+        }
+        if let Ok(t) = std::env::var(SUBMIT_TIMEOUT_ENV) {
+            policy.timeout = Duration::from_millis(t.parse::<u64>()?);
+        }
+        if let Ok(b) = std::env::var(SUBMIT_BACKOFF_ENV) {
+            policy.backoff = Duration::from_millis(b.parse::<u64>()?);
+        }
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    fn validate(&self) -> Result<(), MurinError> {
+        if self.endpoints.is_empty() {
+            return Err(MurinError::new("no transaction submit endpoint configured"));
+        }
+        if let SubmitMode::Quorum(n) = self.mode {
+            if n > self.endpoints.len() {
+                return Err(MurinError::new(&format!(
+                    "submit quorum of {} cannot be reached with {} endpoints",
+                    n,
+                    self.endpoints.len()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Submits the transaction to all endpoints in parallel and returns as soon as
+    /// the policy is satisfied or every endpoint finished.
+    ///
+    /// Submissions which are still running when the policy is satisfied keep
+    /// running in the background and are reported as [`EndpointStatus::Pending`].
+    pub async fn submit(&self, tx: &[u8], own_tx_hash: &str) -> Result<SubmitReport, MurinError> {
+        self.validate()?;
+        let client = reqwest::Client::new();
+        let mut running = self
+            .endpoints
+            .iter()
+            .map(|endpoint| {
+                tokio::spawn(submit_with_retries(
+                    client.clone(),
+                    tx.to_vec(),
+                    endpoint.clone(),
+                    own_tx_hash.to_string(),
+                    self.timeout,
+                    self.retries,
+                    self.backoff,
+                ))
+            })
+            .collect::<FuturesUnordered<_>>();
+
+        let mut report = SubmitReport {
+            tx_hash: own_tx_hash.to_string(),
+            mode: self.mode,
+            results: Vec::new(),
+        };
+        while let Some(result) = running.next().await {
+            let result = result.map_err(|e| MurinError::new(&e.to_string()))?;
+            match &result.status {
+                EndpointStatus::Accepted => {
+                    info!("tx {} accepted by {}", own_tx_hash, result.endpoint)
+                }
+                status => error!("tx {} on {}: {:?}", own_tx_hash, result.endpoint, status),
+            }
+            report.results.push(result);
+            if report.is_success() {
+                break;
+            }
+        }
+
+        for endpoint in &self.endpoints {
+            if !report.results.iter().any(|r| &r.endpoint == endpoint) {
+                report.results.push(EndpointResult {
+                    endpoint: endpoint.clone(),
+                    attempts: 0,
+                    status: EndpointStatus::Pending,
+                });
+            }
+        }
+        Ok(report)
+    }
+}
+
+async fn submit_with_retries(
+    client: reqwest::Client,
+    tx: Vec<u8>,
+    endpoint: String,
+    own_tx_hash: String,
+    timeout: Duration,
+    retries: u32,
+    backoff: Duration,
+) -> EndpointResult {
+    let mut attempts = 0;
+    let mut delay = backoff;
+    loop {
+        attempts += 1;
+        let status = submit_endpoint(&tx, &endpoint, &own_tx_hash, &client, timeout).await;
+        match status {
+            EndpointStatus::Unavailable(_) if attempts <= retries => {
+                debug!(
+                    "Endpoint {} unavailable, retry in {:?} ({}/{})",
+                    endpoint, delay, attempts, retries
+                );
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            status => {
+                return EndpointResult {
+                    endpoint,
+                    attempts,
+                    status,
+                }
+            }
+        }
+    }
+}
+
+/// Submit a Cardano transaction to a specific submit-api-endpoint.
+pub async fn submit_endpoint(
+    tx: &[u8],
+    endpoint: &str,
+    own_tx_hash: &str,
+    client: &reqwest::Client,
+    timeout: Duration,
+) -> EndpointStatus {
+    let response = client
+        .post(endpoint)
+        .header("Content-Type", "application/cbor")
+        .body(tx.to_owned())
+        .send();
+
+    match tokio::time::timeout(timeout, response).await {
+        Err(_) => {
+            debug!("Endpoint {} takes more than {:?}", endpoint, timeout);
+            EndpointStatus::Unavailable(format!("timeout after {timeout:?}"))
+        }
+        Ok(Err(e)) => EndpointStatus::Unavailable(e.to_string()),
+        Ok(Ok(resp)) => {
+            info!("Response: {:?}", resp);
+            let r_status = resp.status();
+            let resp_text = match resp.text().await {
+                Ok(t) => t,
+                Err(e) => return EndpointStatus::Unavailable(e.to_string()),
+            };
+            if r_status.is_server_error() {
+                EndpointStatus::Unavailable(format!("{r_status}: {resp_text}"))
+            } else if r_status != http::StatusCode::ACCEPTED {
+                debug!("Error, Endpoint: {} : {:?}", endpoint, resp_text);
+                EndpointStatus::Rejected(resp_text)
+            } else if resp_text.replace('\"', "") != own_tx_hash {
+                EndpointStatus::Rejected(format!(
+                    "endpoint returned tx hash {resp_text}, expected {own_tx_hash}"
+                ))
+            } else {
+                EndpointStatus::Accepted
+            }
+        }
+    }
+}
+
+/// Submits a CBOR Transaction to the Cardano Network using the submit policy configured in the environment.
+pub async fn submit_tx(
+    tx: &super::CBORTransaction,
+    own_tx_hash: &String,
+) -> Result<String, MurinError> {
+    let report = submit_tx_with_report(tx, own_tx_hash).await?;
+    if report.is_success() {
+        Ok(own_tx_hash.clone())
+    } else {
+        Err(MurinError::TxSubmission(report))
+    }
+}
+
+/// Submits a CBOR Transaction and returns the result of each endpoint.
+pub async fn submit_tx_with_report(
+    tx: &super::CBORTransaction,
+    own_tx_hash: &str,
+) -> Result<SubmitReport, MurinError> {
+    let policy = SubmitPolicy::from_env()?;
+    let tx = hex::decode(&tx.cbor_hex)?;
+    policy.submit(&tx, own_tx_hash).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(endpoint: &str, status: EndpointStatus) -> EndpointResult {
+        EndpointResult {
+            endpoint: endpoint.to_string(),
+            attempts: 1,
+            status,
+        }
+    }
+
+    #[test]
+    fn parse_submit_mode() {
+        assert_eq!(
+            "first".parse::<SubmitMode>().unwrap(),
+            SubmitMode::FirstSuccess
+        );
+        assert_eq!(
+            "Quorum:2".parse::<SubmitMode>().unwrap(),
+            SubmitMode::Quorum(2)
+        );
+        assert!("quorum:0".parse::<SubmitMode>().is_err());
+        assert!("all".parse::<SubmitMode>().is_err());
+    }
+
+    #[test]
+    fn report_success_per_mode() {
+        let mut report = SubmitReport {
+            tx_hash: "abc".to_string(),
+            mode: SubmitMode::Quorum(2),
+            results: vec![
+                result("http://a", EndpointStatus::Accepted),
+                result("http://b", EndpointStatus::Rejected("bad".to_string())),
+            ],
+        };
+        assert!(!report.is_success());
+        report.mode = SubmitMode::FirstSuccess;
+        assert!(report.is_success());
+        report.mode = SubmitMode::Quorum(2);
+        report
+            .results
+            .push(result("http://c", EndpointStatus::Accepted));
+        assert!(report.is_success());
+    }
+
+    #[test]
+    fn policy_drops_empty_endpoints() {
+        let policy = SubmitPolicy::new(
+            vec!["http://a".to_string(), "".to_string(), " ".to_string()],
+            SubmitMode::Quorum(2),
+        );
+        assert_eq!(policy.endpoints, vec!["http://a".to_string()]);
+        assert!(policy.validate().is_err());
+    }
+}
@@ -6,9 +6,31 @@ pub use txmind::*;
 pub mod usedutxos;
 pub use usedutxos::*;
 
+pub mod txstatus;
+pub use txstatus::*;
+
 use crate::MurinError;
 use std::env;
 
+pub(crate) type RedisCon = (
+    Option<redis::cluster::ClusterConnection>,
+    Option<redis::Connection>,
+);
+
+/// Runs `cmd` on whichever connection is established
+pub(crate) fn query<T: redis::FromRedisValue>(
+    con: &mut RedisCon,
+    cmd: &redis::Cmd,
+) -> Result<T, MurinError> {
+    match con {
+        (Some(ref mut c), None) => Ok(cmd.query(c)?),
+        (None, Some(ref mut c)) => Ok(cmd.query(c)?),
+        _ => Err(MurinError::new(
+            "Could not establish single nor cluster redis connection",
+        )),
+    }
+}
+
 pub fn redis_txmind_connection() -> Result<
     (
         Option<redis::cluster::ClusterConnection>,
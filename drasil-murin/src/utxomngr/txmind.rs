@@ -201,6 +201,10 @@ pub fn store_raw_tx(payload: &RawTx) -> Result<String, MurinError> {
         }
     };
 
+    if let Err(e) = register_built_tx(payload) {
+        log::warn!("Could not register transaction in the status registry: {}", e);
+    }
+
     Ok(key.id)
 }
 
@@ -0,0 +1,305 @@
+//! Lifecycle registry of the transactions built by drasil
+//!
+//! Every transaction gets a record keyed by its hash when it is built. Finalize
+//! and submission move it to `Signed`, `Submitted` and `InMempool`, geri moves
+//! it on-chain to `Confirmed`, `RolledBack` or `Expired` from the Oura events.
+//! Transactions which did not reach the confirmation depth yet are kept in a
+//! tracking set, geri only looks at those on each block.
+use crate::cardano::conway::hash_body;
+use crate::utxomngr::{query, redis_txmind_connection, RawTx, RedisCon};
+use crate::MurinError;
+use cardano_serialization_lib as clib;
+use cardano_serialization_lib::utils as cutils;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumString};
+
+const KEY_PREFIX: &str = "txstatus:";
+const TRACKED_KEY: &str = "txstatus_tracked";
+// Records are kept for 30 days after their last update
+const RETENTION_SECS: u64 = 30 * 24 * 3600;
+/// Blocks on top of the including block after which a transaction is final
+pub const DEFAULT_CONFIRMATION_DEPTH: u64 = 10;
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Display, EnumString,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum TxState {
+    Built,
+    Signed,
+    Submitted,
+    /// Accepted into the mempool of at least one node by the submit API
+    InMempool,
+    Confirmed,
+    RolledBack,
+    Expired,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TxStatus {
+    pub tx_hash: String,
+    pub state: TxState,
+    pub user_id: Option<i64>,
+    pub ttl: Option<u64>,
+    pub block_slot: Option<u64>,
+    pub block_hash: Option<String>,
+    pub block_number: Option<u64>,
+    /// Number of blocks including the one containing the transaction
+    pub depth: u64,
+    /// Last submission error
+    pub error: Option<String>,
+    pub updated_at: i64,
+}
+
+impl TxStatus {
+    pub fn new(tx_hash: &str, user_id: Option<i64>, ttl: Option<u64>) -> TxStatus {
+        TxStatus {
+            tx_hash: tx_hash.to_owned(),
+            state: TxState::Built,
+            user_id,
+            ttl,
+            block_slot: None,
+            block_hash: None,
+            block_number: None,
+            depth: 0,
+            error: None,
+            updated_at: chrono::Utc::now().timestamp(),
+        }
+    }
+
+    /// Moves an off-chain transaction forward, late updates of an earlier
+    /// state are ignored. Returns true if the state changed.
+    pub fn advance(&mut self, state: TxState) -> bool {
+        if state > TxState::InMempool || self.state >= state {
+            return false;
+        }
+        self.state = state;
+        true
+    }
+
+    pub fn confirm(&mut self, slot: u64, block_hash: &str, block_number: u64) {
+        self.state = TxState::Confirmed;
+        self.block_slot = Some(slot);
+        self.block_hash = Some(block_hash.to_owned());
+        self.block_number = Some(block_number);
+        self.depth = 1;
+        self.error = None;
+    }
+
+    /// Updates the depth or expires the transaction for a new block. Returns
+    /// true if the transaction reached a final state.
+    pub fn on_block(&mut self, slot: u64, block_number: u64, confirmation_depth: u64) -> bool {
+        match (self.state, self.block_number) {
+            (TxState::Confirmed, Some(number)) => {
+                self.depth = block_number.saturating_sub(number) + 1;
+                self.depth >= confirmation_depth
+            }
+            (TxState::Expired, _) => true,
+            _ => match self.ttl {
+                Some(ttl) if slot > ttl => {
+                    self.state = TxState::Expired;
+                    true
+                }
+                _ => false,
+            },
+        }
+    }
+
+    /// Marks the transaction rolled back if its block is after the rollback
+    /// point. Returns true if the state changed.
+    pub fn on_rollback(&mut self, slot: u64) -> bool {
+        match (self.state, self.block_slot) {
+            (TxState::Confirmed, Some(block_slot)) if block_slot > slot => {
+                self.state = TxState::RolledBack;
+                self.depth = 0;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_final(&self, confirmation_depth: u64) -> bool {
+        match self.state {
+            TxState::Expired => true,
+            TxState::Confirmed => self.depth >= confirmation_depth,
+            _ => false,
+        }
+    }
+}
+
+/// Depth from `TX_CONFIRMATION_DEPTH` or the default
+pub fn confirmation_depth() -> u64 {
+    std::env::var("TX_CONFIRMATION_DEPTH")
+        .ok()
+        .and_then(|d| d.parse().ok())
+        .unwrap_or(DEFAULT_CONFIRMATION_DEPTH)
+}
+
+fn read(con: &mut RedisCon, tx_hash: &str) -> Result<Option<TxStatus>, MurinError> {
+    let value: Option<String> = query(con, redis::cmd("GET").arg(KEY_PREFIX.to_owned() + tx_hash))?;
+    Ok(match value {
+        Some(v) => Some(serde_json::from_str(&v)?),
+        None => None,
+    })
+}
+
+fn write(con: &mut RedisCon, status: &mut TxStatus) -> Result<(), MurinError> {
+    status.updated_at = chrono::Utc::now().timestamp();
+    query::<()>(
+        con,
+        redis::cmd("SET")
+            .arg(KEY_PREFIX.to_owned() + &status.tx_hash)
+            .arg(serde_json::to_string(status)?)
+            .arg("EX")
+            .arg(RETENTION_SECS),
+    )?;
+    if status.is_final(confirmation_depth()) {
+        query::<()>(
+            con,
+            redis::cmd("SREM").arg(TRACKED_KEY).arg(&status.tx_hash),
+        )?;
+    } else {
+        query::<()>(
+            con,
+            redis::cmd("SADD").arg(TRACKED_KEY).arg(&status.tx_hash),
+        )?;
+    }
+    Ok(())
+}
+
+/// Registers a freshly built transaction
+pub fn register_built_tx(raw_tx: &RawTx) -> Result<TxStatus, MurinError> {
+    let body = hex::decode(raw_tx.get_txbody())?;
+    let tx_hash = hex::encode(hash_body(&body).to_bytes());
+    let ttl = clib::TransactionBody::from_bytes(body)
+        .ok()
+        .and_then(|b| b.ttl_bignum())
+        .map(|t| cutils::from_bignum(&t));
+    let mut status = TxStatus::new(&tx_hash, raw_tx.get_user_id().ok(), ttl);
+    write(&mut redis_txmind_connection()?, &mut status)?;
+    Ok(status)
+}
+
+pub fn get_tx_status(tx_hash: &str) -> Result<Option<TxStatus>, MurinError> {
+    read(&mut redis_txmind_connection()?, tx_hash)
+}
+
+/// Moves a registered transaction forward to an off-chain state, unknown
+/// transactions are ignored
+pub fn advance_tx_state(tx_hash: &str, state: TxState) -> Result<Option<TxStatus>, MurinError> {
+    let mut con = redis_txmind_connection()?;
+    let mut status = match read(&mut con, tx_hash)? {
+        Some(s) => s,
+        None => return Ok(None),
+    };
+    if status.advance(state) {
+        write(&mut con, &mut status)?;
+    }
+    Ok(Some(status))
+}
+
+/// Stores a submission error, the state is kept
+pub fn set_tx_error(tx_hash: &str, error: &str) -> Result<(), MurinError> {
+    let mut con = redis_txmind_connection()?;
+    if let Some(mut status) = read(&mut con, tx_hash)? {
+        status.error = Some(error.to_owned());
+        write(&mut con, &mut status)?;
+    }
+    Ok(())
+}
+
+/// Records the block a registered transaction was included in
+pub fn confirm_tx(
+    tx_hash: &str,
+    slot: u64,
+    block_hash: &str,
+    block_number: u64,
+) -> Result<Option<TxStatus>, MurinError> {
+    let mut con = redis_txmind_connection()?;
+    let mut status = match read(&mut con, tx_hash)? {
+        Some(s) => s,
+        None => return Ok(None),
+    };
+    status.confirm(slot, block_hash, block_number);
+    write(&mut con, &mut status)?;
+    Ok(Some(status))
+}
+
+fn tracked(con: &mut RedisCon) -> Result<Vec<TxStatus>, MurinError> {
+    let hashes: Vec<String> = query(con, redis::cmd("SMEMBERS").arg(TRACKED_KEY))?;
+    let mut out = Vec::with_capacity(hashes.len());
+    for hash in hashes {
+        match read(con, &hash)? {
+            Some(status) => out.push(status),
+            // record expired
+            None => query::<()>(con, redis::cmd("SREM").arg(TRACKED_KEY).arg(&hash))?,
+        }
+    }
+    Ok(out)
+}
+
+/// Updates the confirmation depth and TTL expiry of all tracked transactions
+/// for a new block, returns the transactions which reached a final state
+pub fn process_block(slot: u64, block_number: u64) -> Result<Vec<TxStatus>, MurinError> {
+    let mut con = redis_txmind_connection()?;
+    let depth = confirmation_depth();
+    let mut finished = Vec::new();
+    for mut status in tracked(&mut con)? {
+        let before = status.clone();
+        let is_final = status.on_block(slot, block_number, depth);
+        if status != before {
+            write(&mut con, &mut status)?;
+        }
+        if is_final {
+            finished.push(status);
+        }
+    }
+    Ok(finished)
+}
+
+/// Marks tracked transactions included after `slot` as rolled back and
+/// returns them
+pub fn rollback_txs(slot: u64) -> Result<Vec<TxStatus>, MurinError> {
+    let mut con = redis_txmind_connection()?;
+    let mut rolled_back = Vec::new();
+    for mut status in tracked(&mut con)? {
+        if status.on_rollback(slot) {
+            write(&mut con, &mut status)?;
+            rolled_back.push(status);
+        }
+    }
+    Ok(rolled_back)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lifecycle() {
+        let mut status = TxStatus::new("ab", Some(1), Some(100));
+        assert!(status.advance(TxState::Submitted));
+        assert!(!status.advance(TxState::Signed));
+        assert!(!status.advance(TxState::Confirmed));
+        assert!(status.advance(TxState::InMempool));
+
+        status.confirm(90, "blk", 1000);
+        assert!(!status.advance(TxState::InMempool));
+        assert!(!status.on_block(95, 1005, 10));
+        assert_eq!(status.depth, 6);
+        assert!(status.on_rollback(80));
+        assert_eq!(status.state, TxState::RolledBack);
+
+        // not included again before the TTL
+        assert!(!status.on_block(100, 1010, 10));
+        assert!(status.on_block(101, 1011, 10));
+        assert_eq!(status.state, TxState::Expired);
+
+        let mut status = TxStatus::new("cd", None, None);
+        status.confirm(90, "blk", 1000);
+        assert!(status.on_block(120, 1009, 10));
+        assert!(status.is_final(10));
+        assert_eq!(TxState::InMempool.to_string(), "in_mempool");
+    }
+}
@@ -1,7 +1,14 @@
 use crate::cardano::{TransactionUnspentOutput, TransactionUnspentOutputs};
+use crate::utxomngr::{query, RedisCon};
 use crate::{redis_usedutxos_connection, MurinError};
 use std::collections::HashMap;
 
+// Journal of the used utxos released by confirmed transactions, scored by slot
+const CONFIRMED_TX_KEY: &str = "usedutxos_confirmed";
+const CONFIRMED_UTXOS_PREFIX: &str = "usedutxos_confirmed:";
+/// Slots after which a block can not be rolled back anymore (3k/f on mainnet)
+pub const ROLLBACK_WINDOW_SLOTS: u64 = 129600;
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct UsedUtxo {
     txhash: String,
@@ -477,6 +484,104 @@ pub fn delete_used_utxo(txhash: &String) -> Result<(), MurinError> {
     Ok(())
 }
 
+fn get_tx_used_utxos(con: &mut RedisCon, txhash: &String) -> Result<Vec<String>, MurinError> {
+    let tx_map: HashMap<String, String> = query(con, redis::cmd("HGETALL").arg(txhash))?;
+    Ok(match tx_map.get("utxos") {
+        Some(utxos) => utxostring_to_utxovec(utxos),
+        None => Vec::new(),
+    })
+}
+
+/// Releases the used utxos of a transaction included in the block at `slot`,
+/// they are kept in a journal to be restored if the block is rolled back
+pub fn confirm_used_utxos(txhash: &String, slot: u64) -> Result<(), MurinError> {
+    let mut con = redis_usedutxos_connection()?;
+    let members = get_tx_used_utxos(&mut con, txhash)?;
+    if !members.is_empty() {
+        query::<()>(
+            &mut con,
+            redis::cmd("HSET")
+                .arg(CONFIRMED_UTXOS_PREFIX.to_owned() + txhash)
+                .arg("utxos")
+                .arg(utxovec_to_utxostring(&members)),
+        )?;
+        query::<()>(
+            &mut con,
+            redis::cmd("ZADD")
+                .arg(CONFIRMED_TX_KEY)
+                .arg(slot)
+                .arg(txhash),
+        )?;
+    }
+    delete_used_utxo(txhash)
+}
+
+/// Locks the used utxos of transactions included after `slot` again, returns
+/// the restored transactions
+pub fn restore_used_utxos(slot: u64) -> Result<Vec<String>, MurinError> {
+    let mut con = redis_usedutxos_connection()?;
+    let txhashes: Vec<String> = query(
+        &mut con,
+        redis::cmd("ZRANGEBYSCORE")
+            .arg(CONFIRMED_TX_KEY)
+            .arg(format!("({slot}"))
+            .arg("+inf"),
+    )?;
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+        .to_string();
+    for txhash in &txhashes {
+        let journal = CONFIRMED_UTXOS_PREFIX.to_owned() + txhash;
+        let utxos: Option<String> = query(&mut con, redis::cmd("HGET").arg(&journal).arg("utxos"))?;
+        if let Some(utxos) = utxos {
+            let members = utxostring_to_utxovec(&utxos);
+            info!(
+                "restoring used utxos of rolled back tx {}: {:?}",
+                txhash, members
+            );
+            let key = select_used_utxo_datastore(members.len(), None)?;
+            let items: [(&str, &str); 2] = [("utxos", &utxos), ("timestamp", &timestamp)];
+            query::<()>(&mut con, redis::cmd("SADD").arg(&key.0).arg(&members))?;
+            query::<()>(&mut con, redis::cmd("HMSET").arg(txhash).arg(&items))?;
+        }
+        query::<()>(&mut con, redis::cmd("DEL").arg(&journal))?;
+        query::<()>(
+            &mut con,
+            redis::cmd("ZREM").arg(CONFIRMED_TX_KEY).arg(txhash),
+        )?;
+    }
+    Ok(txhashes)
+}
+
+/// Forgets the released utxos of blocks which can not be rolled back anymore
+pub fn prune_used_utxo_journal(slot: u64) -> Result<(), MurinError> {
+    let until = slot.saturating_sub(ROLLBACK_WINDOW_SLOTS);
+    if until == 0 {
+        return Ok(());
+    }
+    let mut con = redis_usedutxos_connection()?;
+    let txhashes: Vec<String> = query(
+        &mut con,
+        redis::cmd("ZRANGEBYSCORE")
+            .arg(CONFIRMED_TX_KEY)
+            .arg("-inf")
+            .arg(format!("({until}")),
+    )?;
+    for txhash in &txhashes {
+        query::<()>(
+            &mut con,
+            redis::cmd("DEL").arg(CONFIRMED_UTXOS_PREFIX.to_owned() + txhash),
+        )?;
+        query::<()>(
+            &mut con,
+            redis::cmd("ZREM").arg(CONFIRMED_TX_KEY).arg(txhash),
+        )?;
+    }
+    Ok(())
+}
+
 /// deletes a used utxo
 pub async fn delete_used_utxo_async(
     redis_con: &mut redis::aio::Connection,
@@ -205,8 +205,7 @@ pub async fn create_airdrop(
         }
     }
 
-    let mut mconn = drasil_mimir::establish_connection()?;
-    let current_epoch = drasil_mimir::get_epoch(&mut mconn)? as i64;
+    let current_epoch = drasil_mimir::chain_data().epoch()? as i64;
     if start_epoch < current_epoch {
         return Err(SleipnirError::new(&format!(
             "Start epoch: {start_epoch} cannot be smaller than the current epoch: : {current_epoch:?}"
@@ -388,10 +387,8 @@ pub fn airdrop_whitelist_selection(
                         )?
                     };
 
-                    let whitelist = drasil_mimir::lookup_token_holders(
-                        &fingerprint,
-                        adparam.min_holding.as_ref(),
-                    )?;
+                    let whitelist = drasil_mimir::chain_data()
+                        .token_holders(&fingerprint, adparam.min_holding.as_ref())?;
                 }
                 ADTokenType::NonFungibleToken => {
                     let adparam = match args2 {
@@ -405,9 +402,8 @@ pub fn airdrop_whitelist_selection(
 
                     // ToDo: Traits
 
-                    let whitelist = drasil_mimir::lookup_nft_token_holders(&hex::encode(
-                        adparam.policy_id.to_bytes(),
-                    ))?;
+                    let whitelist = drasil_mimir::chain_data()
+                        .nft_token_holders(&hex::encode(adparam.policy_id.to_bytes()))?;
                 }
             }
         }
@@ -1,47 +1,11 @@
 use crate::error::SleipnirError;
 use chrono::prelude::*;
+pub use drasil_hugin::authentication::apikey::{ApiClaims, ApiScope, Permissions};
 use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
 use serde::{Deserialize, Serialize};
-use std::fmt;
-
-#[derive(Debug, Deserialize, Serialize)]
-pub enum Permissions {
-    RewardClaimRequest,
-    RewardClaimCreateContract,
-    StandardTransactionsDeleigate,
-    Marketplace,
-    Standard,
-}
-
-impl Permissions {
-    pub fn str_to_role(role: &str) -> Permissions {
-        match role {
-            "1" => Permissions::RewardClaimRequest,
-            "2" => Permissions::RewardClaimCreateContract,
-            "3" => Permissions::StandardTransactionsDeleigate,
-            "4" => Permissions::Marketplace,
-            _ => Permissions::Standard,
-        }
-    }
-}
-
-impl fmt::Display for Permissions {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Permissions::RewardClaimRequest => write!(f, "1"),
-            Permissions::RewardClaimCreateContract => write!(f, "2"),
-            Permissions::StandardTransactionsDeleigate => write!(f, "3"),
-            Permissions::Marketplace => write!(f, "3"),
-            Permissions::Standard => write!(f, "0"),
-        }
-    }
-}
 
-#[derive(Debug, Deserialize, Serialize)]
-pub struct ApiClaims {
-    sub: String,
-    exp: usize,
-}
+/// Default and longest validity of API tokens in seconds
+const MAX_KEY_DURATION: i64 = 317125598072;
 
 pub fn create_jwt(uid: &i64, duration: Option<i64>) -> Result<String, SleipnirError> {
     let user = drasil_hugin::database::TBDrasilUser::get_user_by_user_id(uid)?;
@@ -50,7 +14,7 @@ pub fn create_jwt(uid: &i64, duration: Option<i64>) -> Result<String, SleipnirEr
         return Err(SleipnirError::new("invalid user"));
     }
 
-    let mut dur = 317125598072; // 15552000;
+    let mut dur = MAX_KEY_DURATION; // 15552000;
     if let Some(t) = duration {
         dur = t
     };
@@ -63,14 +27,101 @@ pub fn create_jwt(uid: &i64, duration: Option<i64>) -> Result<String, SleipnirEr
     let claims = ApiClaims {
         sub: uid.to_string(),
         exp: expiration as usize,
+        jti: None,
+        scope: None,
     };
+    let token = encode_claims(&claims)?;
+
+    drasil_hugin::database::TBDrasilUser::update_api_key(&user.id, &token)?;
+
+    Ok(token)
+}
+
+fn encode_claims(claims: &ApiClaims) -> Result<String, SleipnirError> {
     let header = Header::new(Algorithm::ES256);
     let key = std::env::var("JWT_KEY").map_err(|_| SleipnirError::new("jwt key error"))?;
     let key = key.into_bytes();
-    let token = encode(&header, &claims, &EncodingKey::from_ec_pem(&key).unwrap())
-        .map_err(|_| SleipnirError::new("JWT Token could not been created"))?;
+    encode(&header, claims, &EncodingKey::from_ec_pem(&key).unwrap())
+        .map_err(|_| SleipnirError::new("JWT Token could not been created"))
+}
 
-    drasil_hugin::database::TBDrasilUser::update_api_key(&user.id, &token)?;
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NewApiKey {
+    pub name: String,
+    #[serde(default)]
+    pub scope: ApiScope,
+    /// Validity of the key in seconds
+    pub duration: Option<i64>,
+}
 
-    Ok(token)
+#[derive(Debug, Serialize)]
+pub struct CreatedApiKey {
+    /// The key itself, it is only returned once
+    pub token: String,
+    pub key: drasil_hugin::TBApiKey,
+}
+
+/// Creates an API key restricted to the scope, the user keeps all other keys
+pub fn create_api_key(uid: &i64, new: &NewApiKey) -> Result<CreatedApiKey, SleipnirError> {
+    let user = drasil_hugin::database::TBDrasilUser::get_user_by_user_id(uid)?;
+
+    if !user.email_verified {
+        return Err(SleipnirError::new("invalid user"));
+    }
+    if new.name.is_empty() {
+        return Err(SleipnirError::new("API key needs a name"));
+    }
+    if let Some(ip) = new
+        .scope
+        .ips
+        .iter()
+        .find(|ip| ip.parse::<std::net::IpAddr>().is_err())
+    {
+        return Err(SleipnirError::new(&format!("invalid ip address: {ip}")));
+    }
+
+    let duration = new.duration.unwrap_or(MAX_KEY_DURATION);
+    if !(1..=MAX_KEY_DURATION).contains(&duration) {
+        return Err(SleipnirError::new("invalid API key duration"));
+    }
+    let expires_at = Utc::now()
+        .checked_add_signed(chrono::Duration::seconds(duration))
+        .ok_or_else(|| SleipnirError::new("invalid API key duration"))?;
+    let key_id = hex::encode(rand::random::<[u8; 16]>());
+
+    let claims = ApiClaims {
+        sub: uid.to_string(),
+        exp: expires_at.timestamp() as usize,
+        jti: Some(key_id.clone()),
+        scope: Some(new.scope.clone()),
+    };
+    let token = encode_claims(&claims)?;
+
+    let key = drasil_hugin::TBApiKey::create(&drasil_hugin::TBApiKeyNew {
+        user_id: uid,
+        key_id: &key_id,
+        token_hash: &drasil_hugin::TBApiKey::hash_token(&token),
+        name: &new.name,
+        contract_ids: &new.scope.contracts,
+        permissions: &new
+            .scope
+            .permissions
+            .iter()
+            .map(|p| format!("{p:?}"))
+            .collect(),
+        networks: &new.scope.networks.iter().map(|n| *n as i64).collect(),
+        origins: &new.scope.origins,
+        ips: &new.scope.ips,
+        expires_at: &expires_at,
+    })?;
+
+    Ok(CreatedApiKey { token, key })
+}
+
+pub fn get_api_keys(uid: &i64) -> Result<Vec<drasil_hugin::TBApiKey>, SleipnirError> {
+    Ok(drasil_hugin::TBApiKey::get_user_keys(uid)?)
+}
+
+pub fn revoke_api_key(uid: &i64, key_id: &str) -> Result<drasil_hugin::TBApiKey, SleipnirError> {
+    Ok(drasil_hugin::TBApiKey::revoke(uid, key_id)?)
 }
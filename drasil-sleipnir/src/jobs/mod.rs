@@ -15,4 +15,5 @@ pub enum JobTypes {
     RandomAllocateWhitelistToMintProject(Job),
     CalculateReoccuringRewards(Job),
     OptimizeRewardUTxOs(Job),
+    ExpireRewards(Job),
 }
@@ -1,10 +1,25 @@
 use chrono::{DateTime, NaiveDateTime, Utc};
 use drasil_gungnir::minting::models::*;
-use drasil_murin::{cardano, wallet};
+use drasil_hugin::database::{TBContracts, TBMultiSigLoc};
+use drasil_hugin::encryption::{decrypt_pkvs, mident};
+use drasil_murin::minter::build_cip68_update::{AtCip68UpdBuilder, AtCip68UpdParams};
+use drasil_murin::minter::build_reveal_mint::{AtRevealMintBuilder, AtRevealMintParams};
+use drasil_murin::minter::build_royalty_mint::{AtRoyaltyMintBuilder, AtRoyaltyMintParams};
+use drasil_murin::minter::cip27::Cip27Royalty;
+use drasil_murin::minter::cip68::{cip68_datum, datum_version, reference_asset_name};
+use drasil_murin::minter::reveal::{
+    assign_metadata, provenance_hash, reveal_commitment, reveal_order, verify_commitment,
+};
+use drasil_murin::minter::{AssetMetadata, Cip25Metadata};
+use drasil_murin::{cardano, wallet, MurinError, PerformTxb};
 
 use super::models::*;
 use crate::SleipnirError;
 
+/// Revealed assets per CIP-25 re-mint, keeps the metadata within the
+/// transaction size limit
+const REVEAL_ASSETS_PER_TX: usize = 20;
+
 pub async fn create_mintproject(data: &CreateMintProj) -> Result<MintProject, SleipnirError> {
     let time_constraint = if let Some(date) = &data.time_constraint {
         Some(DateTime::from_naive_utc_and_offset(
@@ -15,6 +30,16 @@ pub async fn create_mintproject(data: &CreateMintProj) -> Result<MintProject, Sl
         None
     };
 
+    if let Some(addr) = &data.cip68_ref_addr {
+        wallet::address_from_string(addr).await?;
+    }
+    let reveal_placeholder = match &data.reveal_placeholder {
+        Some(placeholder) => Some(serde_json::from_value::<AssetMetadata>(
+            placeholder.clone(),
+        )?),
+        None => None,
+    };
+
     let policy_script_id = super::create_policy_script(
         wallet::get_network_kind(data.network).await?,
         data.user_id.unwrap(),
@@ -59,11 +84,478 @@ pub async fn create_mintproject(data: &CreateMintProj) -> Result<MintProject, Sl
         data.max_mint_p_addr.as_ref(),
         &tablename,
         &false,
+        data.cip68_ref_addr.as_ref(),
+        data.max_mint_p_stake.as_ref(),
+        data.max_mint_p_wl_entry.as_ref(),
     )?;
+    // the seed stays secret until the reveal, it is committed together with
+    // the metadata pool by `commit_mintproject_reveal`
+    let m = match reveal_placeholder {
+        Some(placeholder) => {
+            let seed: [u8; 32] = rand::random();
+            m.enable_reveal(&serde_json::to_string(&placeholder)?, &hex::encode(seed))?
+        }
+        None => m,
+    };
     log::debug!("...finish create mintproject");
     Ok(m)
 }
 
+/// Mints the CIP-27 royalty token under the policy of a mint project, funded
+/// by the liquidity wallet of the user which also keeps the token
+pub async fn mint_royalty_token(
+    user_id: i64,
+    data: &CreateRoyaltyToken,
+) -> Result<String, SleipnirError> {
+    let royalty = Cip27Royalty::new(data.rate, &data.addr)?;
+    let mint_project = MintProject::get_mintproject_by_id(data.project_id)?;
+    if mint_project.user_id != user_id {
+        return Err(SleipnirError::new("mint project not found"));
+    }
+    let mint_contract = TBContracts::get_contract_uid_cid(user_id, mint_project.mint_contract_id)?;
+    let policy_id = mint_contract
+        .policy_id
+        .clone()
+        .ok_or_else(|| SleipnirError::new("mint contract has no policy"))?;
+
+    let chain = drasil_mimir::chain_data();
+    if chain.royalty_metadata(&policy_id)?.is_some() {
+        return Err(SleipnirError::new(&format!(
+            "royalties of policy '{policy_id}' are already declared"
+        )));
+    }
+
+    log::debug!("try to determine keys...");
+    let (script, policy_pvks) = policy_keys(&mint_contract).await?;
+    let (liquidity, liquidity_pvks) = liquidity_wallet(user_id).await?;
+
+    log::debug!("try to build royalty token mint...");
+    let liquidity_addr = wallet::address_from_string(&liquidity.address).await?;
+    let gtxd = liquidity_txdata(&liquidity, mint_contract.contract_id).await?;
+    let txb_param: AtRoyaltyMintParams = (&script, &royalty, &liquidity_addr);
+    let minter = AtRoyaltyMintBuilder::new(txb_param);
+    let bld_tx = drasil_murin::TxBuilder::new(&gtxd, &liquidity_pvks)
+        .build(&minter)
+        .await?;
+
+    let txh = finalize_liquidity_tx(
+        &bld_tx,
+        &gtxd,
+        &serde_json::json!(royalty).to_string(),
+        user_id,
+        mint_contract.contract_id,
+        policy_pvks,
+    )
+    .await?;
+    log::debug!("royalty token minted in {}", txh);
+    Ok(txh)
+}
+
+/// Native script of a mint contract and the keys signing for it
+async fn policy_keys(
+    mint_contract: &TBContracts,
+) -> Result<(drasil_murin::NativeScript, Vec<String>), SleipnirError> {
+    let script = drasil_murin::NativeScript::from_bytes(hex::decode(&mint_contract.plutus)?)
+        .map_err(MurinError::from)?;
+    let keyloc = TBMultiSigLoc::get_multisig_keyloc(
+        &mint_contract.contract_id,
+        &mint_contract.user_id,
+        &mint_contract.version,
+    )?;
+    let pvks = decrypt_pkvs(
+        keyloc.pvks,
+        &mident(
+            &mint_contract.user_id,
+            &mint_contract.contract_id,
+            &mint_contract.version,
+            &mint_contract.address,
+        ),
+    )
+    .await?;
+    Ok((script, pvks))
+}
+
+/// Liquidity wallet of a user and its keys
+async fn liquidity_wallet(user_id: i64) -> Result<(TBContracts, Vec<String>), SleipnirError> {
+    let liquidity = TBContracts::get_liquidity_wallet(&user_id)?;
+    let keyloc = TBMultiSigLoc::get_multisig_keyloc(
+        &liquidity.contract_id,
+        &liquidity.user_id,
+        &liquidity.version,
+    )?;
+    let pvks = decrypt_pkvs(
+        keyloc.pvks,
+        &mident(
+            &liquidity.user_id,
+            &liquidity.contract_id,
+            &liquidity.version,
+            &liquidity.address,
+        ),
+    )
+    .await?;
+    Ok((liquidity, pvks))
+}
+
+/// Transaction data of a transaction funded by the liquidity wallet
+async fn liquidity_txdata(
+    liquidity: &TBContracts,
+    contract_id: i64,
+) -> Result<drasil_murin::TxData, SleipnirError> {
+    let chain = drasil_mimir::chain_data();
+    Ok(drasil_murin::TxData::new(
+        Some(vec![contract_id]),
+        vec![wallet::address_from_string(&liquidity.address).await?],
+        None,
+        chain.address_utxos(&liquidity.address)?,
+        cardano::get_network_from_address(&liquidity.address)?,
+        chain.slot()? as u64,
+    )?)
+}
+
+/// Signs a transaction funded by the liquidity wallet with `pvks`, submits it
+/// and locks its inputs until it is on chain
+async fn finalize_liquidity_tx(
+    bld_tx: &cardano::BuildOutput,
+    gtxd: &drasil_murin::TxData,
+    data: &str,
+    user_id: i64,
+    contract_id: i64,
+    pvks: Vec<String>,
+) -> Result<String, SleipnirError> {
+    let tx = drasil_murin::utxomngr::RawTx::new(
+        &bld_tx.get_tx_body(),
+        &bld_tx.get_txwitness(),
+        &bld_tx.get_tx_unsigned(),
+        &bld_tx.get_metadata(),
+        &gtxd.to_string(),
+        &data.to_string(),
+        &bld_tx.get_used_utxos(),
+        &hex::encode(gtxd.get_stake_address().to_bytes()),
+        &user_id,
+        &[contract_id],
+    );
+    let used_utxos = tx.get_usedutxos().clone();
+    let txh = drasil_murin::finalize_rwd(
+        &hex::encode(drasil_murin::clib::TransactionWitnessSet::new().to_bytes()),
+        tx,
+        pvks,
+    )
+    .await?;
+    drasil_murin::utxomngr::usedutxos::store_used_utxos(
+        &txh,
+        &drasil_murin::TransactionUnspentOutputs::from_hex(&used_utxos)?,
+    )?;
+    Ok(txh)
+}
+
+/// Adds a sale phase to a mint project of the user
+pub async fn create_mint_phase(
+    user_id: i64,
+    data: &CreateMintPhase,
+) -> Result<MintPhase, SleipnirError> {
+    let mint_project = MintProject::get_mintproject_by_id(data.project_id)?;
+    if mint_project.user_id != user_id {
+        return Err(SleipnirError::new("mint project not found"));
+    }
+    if let Some(addr) = &data.pay_addr {
+        wallet::address_from_string(addr).await?;
+    }
+    if let Some(whitelist) = &data.whitelist_id {
+        drasil_gungnir::Whitelist::get_whitelist(&user_id, whitelist)?;
+    }
+    if let Some(policy) = &data.gate_policy {
+        drasil_murin::PolicyID::from_hex(policy)?;
+    }
+    let phase = MintPhase::create_phase(
+        &data.project_id,
+        &data.phase_name,
+        &data.start_slot,
+        data.end_slot.as_ref(),
+        &data.price,
+        data.pay_addr.as_ref(),
+        data.max_per_wallet.as_ref(),
+        data.whitelist_id.as_ref(),
+        data.gate_policy.as_ref(),
+    )?;
+    Ok(phase)
+}
+
+pub fn remove_mint_phase(user_id: i64, data: &RemoveMintPhase) -> Result<usize, SleipnirError> {
+    let mint_project = MintProject::get_mintproject_by_id(data.project_id)?;
+    if mint_project.user_id != user_id {
+        return Err(SleipnirError::new("mint project not found"));
+    }
+    Ok(MintPhase::remove_phase(data.project_id, data.phase_id)?)
+}
+
+/// Commits to the seed and the final metadata pool of a reveal project, it
+/// must be done before the project is activated and fixes the pool
+pub fn commit_mintproject_reveal(
+    user_id: i64,
+    data: &RevealMintProj,
+) -> Result<MintProject, SleipnirError> {
+    let mint_project = MintProject::get_mintproject_by_id(data.project_id)?;
+    if mint_project.user_id != user_id {
+        return Err(SleipnirError::new("mint project not found"));
+    }
+    if !mint_project.reveal_uncommitted() {
+        return Err(SleipnirError::new(
+            "mint project has no reveal waiting for a commitment",
+        ));
+    }
+    let seed = reveal_seed(&mint_project)?;
+    let (_, pool) = metadata_pool(&mint_project)?;
+    if pool.is_empty() {
+        return Err(SleipnirError::new("mint project has no NFTs to commit"));
+    }
+    let provenance = provenance_hash(&pool)?;
+    let commitment = reveal_commitment(&seed, &provenance)?;
+    Ok(mint_project.commit_reveal(&provenance, &commitment)?)
+}
+
+/// Assigns the imported metadata to the NFTs of a reveal project by the
+/// seeded shuffle the project committed to before its activation
+pub fn reveal_mintproject(
+    user_id: i64,
+    data: &RevealMintProj,
+) -> Result<RevealProof, SleipnirError> {
+    let mint_project = MintProject::get_mintproject_by_id(data.project_id)?;
+    if mint_project.user_id != user_id {
+        return Err(SleipnirError::new("mint project not found"));
+    }
+    if !mint_project.reveal_pending() {
+        return Err(SleipnirError::new("mint project has no pending reveal"));
+    }
+    let (provenance, commitment) = match (
+        &mint_project.reveal_provenance,
+        &mint_project.reveal_commitment,
+    ) {
+        (Some(provenance), Some(commitment)) => (provenance, commitment),
+        _ => return Err(SleipnirError::new("mint project has no reveal commitment")),
+    };
+    let seed = reveal_seed(&mint_project)?;
+
+    let (nfts, pool) = metadata_pool(&mint_project)?;
+    if provenance_hash(&pool)? != *provenance || !verify_commitment(&seed, provenance, commitment) {
+        return Err(SleipnirError::new(
+            "the metadata pool does not match the reveal commitment",
+        ));
+    }
+    let order = reveal_order(&seed, pool.len());
+    let reveals = nfts
+        .iter()
+        .zip(order)
+        .map(|(nft, i)| {
+            let metadata =
+                assign_metadata(&pool[i], &hex::encode(&nft.asset_name_b), &nft.asset_name);
+            Ok(NftRevealNew {
+                project_id: mint_project.id,
+                asset_name_b: nft.asset_name_b.clone(),
+                pool_index: i as i32,
+                metadata: serde_json::to_string(&metadata)?,
+                needs_update: nft.claim_addr.is_some() || nft.minted,
+            })
+        })
+        .collect::<Result<Vec<_>, SleipnirError>>()?;
+    let mint_project = mint_project.reveal(&reveals)?;
+    log::debug!(
+        "revealed {} NFTs of project {}",
+        reveals.len(),
+        mint_project.id
+    );
+    reveal_proof(&mint_project, &nfts, pool)
+}
+
+pub fn get_reveal_proof(user_id: i64, data: &RevealMintProj) -> Result<RevealProof, SleipnirError> {
+    let mint_project = MintProject::get_mintproject_by_id(data.project_id)?;
+    if mint_project.user_id != user_id {
+        return Err(SleipnirError::new("mint project not found"));
+    }
+    if mint_project.revealed_at.is_none() {
+        return Err(SleipnirError::new("mint project is not revealed"));
+    }
+    let (nfts, pool) = metadata_pool(&mint_project)?;
+    reveal_proof(&mint_project, &nfts, pool)
+}
+
+fn reveal_seed(mint_project: &MintProject) -> Result<Vec<u8>, SleipnirError> {
+    Ok(hex::decode(mint_project.reveal_seed.as_ref().ok_or_else(
+        || SleipnirError::new("mint project has no reveal seed"),
+    )?)?)
+}
+
+/// NFTs of a project ordered by asset name with their imported metadata
+fn metadata_pool(
+    mint_project: &MintProject,
+) -> Result<(Vec<Nft>, Vec<AssetMetadata>), SleipnirError> {
+    let mut nfts = Nft::get_nfts_by_pid(
+        &mut drasil_gungnir::establish_connection()?,
+        mint_project.id,
+        &mint_project.nft_table_name,
+    )?;
+    nfts.sort_by(|a, b| a.asset_name_b.cmp(&b.asset_name_b));
+    let pool = nfts
+        .iter()
+        .map(|nft| {
+            let metadata = nft.metadata.as_ref().ok_or_else(|| {
+                SleipnirError::new(&format!("NFT '{}' has no metadata", nft.asset_name))
+            })?;
+            Ok(serde_json::from_str(metadata)?)
+        })
+        .collect::<Result<Vec<AssetMetadata>, SleipnirError>>()?;
+    Ok((nfts, pool))
+}
+
+fn reveal_proof(
+    mint_project: &MintProject,
+    nfts: &[Nft],
+    pool: Vec<AssetMetadata>,
+) -> Result<RevealProof, SleipnirError> {
+    match (
+        &mint_project.reveal_commitment,
+        &mint_project.reveal_seed,
+        &mint_project.reveal_provenance,
+    ) {
+        (Some(commitment), Some(seed), Some(provenance)) => Ok(RevealProof {
+            project_id: mint_project.id,
+            commitment: commitment.clone(),
+            seed: seed.clone(),
+            provenance: provenance.clone(),
+            asset_names: nfts.iter().map(|n| hex::encode(&n.asset_name_b)).collect(),
+            pool,
+        }),
+        _ => Err(SleipnirError::new("mint project is not revealed")),
+    }
+}
+
+/// Updates the metadata of the NFTs minted with the placeholder, through their
+/// CIP-68 reference datum or by a CIP-25 re-mint under the project policy.
+/// Updated NFTs are recorded, so an interrupted update continues on the next
+/// call. Returns the submitted transactions.
+pub async fn update_revealed_metadata(
+    user_id: i64,
+    data: &RevealMintProj,
+) -> Result<Vec<String>, SleipnirError> {
+    let mint_project = MintProject::get_mintproject_by_id(data.project_id)?;
+    if mint_project.user_id != user_id {
+        return Err(SleipnirError::new("mint project not found"));
+    }
+    if mint_project.revealed_at.is_none() {
+        return Err(SleipnirError::new("mint project is not revealed"));
+    }
+    let mut pending = Vec::<NftReveal>::new();
+    for reveal in NftReveal::get_pending_updates(mint_project.id)? {
+        let nft = Nft::get_nft_by_assetnameb(
+            mint_project.id,
+            &mint_project.nft_table_name,
+            &reveal.asset_name_b,
+        )?;
+        if nft.minted {
+            pending.push(reveal);
+        }
+    }
+    if pending.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mint_contract = TBContracts::get_contract_uid_cid(user_id, mint_project.mint_contract_id)?;
+    let (liquidity, liquidity_pvks) = liquidity_wallet(user_id).await?;
+    let liquidity_addr = wallet::address_from_string(&liquidity.address).await?;
+    let mut txhashes = Vec::<String>::new();
+    if mint_project.cip68_ref_addr.is_some() {
+        let policy = drasil_murin::PolicyID::from_hex(
+            mint_contract
+                .policy_id
+                .as_ref()
+                .ok_or_else(|| SleipnirError::new("mint contract has no policy"))?,
+        )?;
+        for reveal in pending {
+            let reference = reference_asset_name(&drasil_murin::clib::AssetName::new(
+                reveal.asset_name_b.clone(),
+            )?)?;
+            let gtxd = liquidity_txdata(&liquidity, liquidity.contract_id).await?;
+            let reference_utxos = gtxd
+                .get_inputs()
+                .find_utxos_containing_asset(&policy, &reference)?;
+            if reference_utxos.is_empty() {
+                return Err(SleipnirError::new(&format!(
+                    "reference token of '{}' is not held by the liquidity wallet",
+                    hex::encode(&reveal.asset_name_b)
+                )));
+            }
+            let used = drasil_murin::utxomngr::usedutxos::check_any_utxo_used(&reference_utxos)?;
+            if used.is_some() {
+                return Err(SleipnirError::new(&format!(
+                    "reference token of '{}' is locked by a pending transaction",
+                    hex::encode(&reveal.asset_name_b)
+                )));
+            }
+            let reference_utxo = reference_utxos.get(0);
+            let version = reference_utxo
+                .output()
+                .plutus_data()
+                .and_then(|d| datum_version(&d))
+                .unwrap_or(1);
+            let datum = cip68_datum(&serde_json::from_str(&reveal.metadata)?, version)?;
+
+            let txb_param: AtCip68UpdParams = (&reference_utxo, &datum, &liquidity_addr);
+            let updater = AtCip68UpdBuilder::new(txb_param);
+            let bld_tx = drasil_murin::TxBuilder::new(&gtxd, &liquidity_pvks)
+                .build(&updater)
+                .await?;
+            let txh = finalize_liquidity_tx(
+                &bld_tx,
+                &gtxd,
+                &hex::encode(datum.to_bytes()),
+                user_id,
+                liquidity.contract_id,
+                vec![],
+            )
+            .await?;
+            reveal.set_update_tx(&txh)?;
+            log::debug!(
+                "reference datum of '{}' updated in {}",
+                hex::encode(&reveal.asset_name_b),
+                txh
+            );
+            txhashes.push(txh);
+        }
+    } else {
+        let (script, policy_pvks) = policy_keys(&mint_contract).await?;
+        for chunk in pending.chunks(REVEAL_ASSETS_PER_TX) {
+            let metadata = Cip25Metadata {
+                assets: chunk
+                    .iter()
+                    .map(|r| serde_json::from_str(&r.metadata))
+                    .collect::<Result<Vec<_>, _>>()?,
+                other: None,
+                version: "1.0".to_string(),
+            };
+            let gtxd = liquidity_txdata(&liquidity, mint_contract.contract_id).await?;
+            let txb_param: AtRevealMintParams = (&script, &metadata, &liquidity_addr);
+            let minter = AtRevealMintBuilder::new(txb_param);
+            let bld_tx = drasil_murin::TxBuilder::new(&gtxd, &liquidity_pvks)
+                .build(&minter)
+                .await?;
+            let txh = finalize_liquidity_tx(
+                &bld_tx,
+                &gtxd,
+                &serde_json::json!(metadata).to_string(),
+                user_id,
+                mint_contract.contract_id,
+                policy_pvks.clone(),
+            )
+            .await?;
+            for reveal in chunk {
+                reveal.set_update_tx(&txh)?;
+            }
+            log::debug!("metadata of {} NFTs re-minted in {}", chunk.len(), txh);
+            txhashes.push(txh);
+        }
+    }
+    Ok(txhashes)
+}
+
 pub fn make_table_name(
     user_id: i64,
     policy_script_id: i64,
@@ -124,6 +616,11 @@ pub async fn import_from_asset_metadata(
     meta: Vec<drasil_murin::minter::AssetMetadata>,
 ) -> Result<Vec<drasil_gungnir::minting::models::Nft>, SleipnirError> {
     let mint_project = MintProject::get_mintproject_by_id(mpid)?;
+    if mint_project.reveal_commitment.is_some() {
+        return Err(SleipnirError::new(
+            "the metadata pool of a reveal project is fixed by its commitment",
+        ));
+    }
     println!("Try to find contract...");
     let mint_contract =
         drasil_hugin::TBContracts::get_contract_uid_cid(user_id, mint_project.mint_contract_id)?;
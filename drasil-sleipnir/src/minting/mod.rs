@@ -81,7 +81,7 @@ pub async fn create_policy_script(
     };
 
     if let Some(date) = time_constraint {
-        let slot = drasil_murin::minter::calculate_slot_from_date(date) as u64;
+        let slot = drasil_murin::cardano::SlotConfig::for_network(&network)?.time_to_slot(&date);
         native_scripts.add(&NativeScript::new_timelock_expiry(
             &clib::TimelockExpiry::new_timelockexpiry(&drasil_murin::clib::utils::to_bignum(slot)),
         ));
@@ -15,8 +15,70 @@ pub struct CreateMintProj {
     pub meta_description: String,
     pub meta_common_nft_name: Option<String>,
     pub max_mint_p_addr: Option<i32>,
+    /// Maximum NFTs all addresses of one stake key can claim
+    pub max_mint_p_stake: Option<i32>,
+    /// Maximum NFTs one whitelist entry can claim
+    pub max_mint_p_wl_entry: Option<i32>,
     pub network: u64,
     pub time_constraint: Option<String>,
+    /// Mint CIP-68 pairs and lock the reference tokens at this address
+    pub cip68_ref_addr: Option<String>,
+    /// Asset metadata all NFTs are minted with until the reveal, enables
+    /// reveal mode
+    pub reveal_placeholder: Option<serde_json::Value>,
+}
+
+/// Declares the CIP-27 royalties of a mint project
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub struct CreateRoyaltyToken {
+    pub project_id: i64,
+    /// Share of the selling price, between 0.0 and 1.0
+    pub rate: f32,
+    pub addr: String,
+}
+
+/// Sale phase of a mint project, phases without end slot stay open
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+pub struct CreateMintPhase {
+    pub project_id: i64,
+    pub phase_name: String,
+    pub start_slot: i64,
+    pub end_slot: Option<i64>,
+    /// Price per NFT in lovelace
+    pub price: i64,
+    pub pay_addr: Option<String>,
+    pub max_per_wallet: Option<i32>,
+    pub whitelist_id: Option<i64>,
+    pub gate_policy: Option<String>,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+pub struct RemoveMintPhase {
+    pub project_id: i64,
+    pub phase_id: i64,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+pub struct RevealMintProj {
+    pub project_id: i64,
+}
+
+/// Everything needed to verify the reveal of a mint project
+///
+/// The pool hashes to the provenance, the seed and the provenance hash to the
+/// commitment published before the project was activated, and
+/// the asset at position `i` of `asset_names` received the pool entry at
+/// position `reveal_order(seed, n)[i]`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevealProof {
+    pub project_id: i64,
+    pub commitment: String,
+    pub seed: String,
+    pub provenance: String,
+    /// Hex encoded asset names in ascending order
+    pub asset_names: Vec<String>,
+    /// Imported metadata ordered by asset name
+    pub pool: Vec<drasil_murin::minter::AssetMetadata>,
 }
 
 pub enum NftImportType {
@@ -0,0 +1,610 @@
+//! Reward formulas of the custom calculation mode
+//!
+//! The `equation` of a custom token whitelist entry names a [`RewardFormula`],
+//! its `modificator_equ` holds the JSON parameters of the formula. The reward
+//! calculation looks the formula up in the registry returned by [`formulas`]
+//! and calls it with the [`StakeSnapshot`] of each delegator or whitelisted
+//! address. Additional formulas are added with [`register_formula`].
+use std::sync::{Arc, OnceLock, RwLock};
+
+use drasil_gungnir::{BigDecimal, FromPrimitive, ToPrimitive};
+use drasil_mimir::ChainDataProvider;
+use serde::de::DeserializeOwned;
+
+use super::models::*;
+use crate::error::SleipnirError;
+
+/// Active stake and performance of a participating pool in the calculation epoch
+#[derive(Debug, Clone)]
+pub struct PoolSnapshot {
+    pub pool_id: String,
+    /// Active stake of the pool in lovelace
+    pub active_stake: u64,
+    /// Minted blocks relative to the blocks expected for the active stake, at most 1
+    pub performance: f64,
+}
+
+/// Blocks expected per epoch on the network of the contract of `rwd_token`,
+/// `None` if its formula does not read the pool performance
+pub fn expected_blocks(
+    rwd_token: &drasil_gungnir::TokenWhitelist,
+) -> Result<Option<f64>, SleipnirError> {
+    if rwd_token.mode != drasil_gungnir::Calculationmode::Custom
+        || !formulas()
+            .get(&rwd_token.equation)?
+            .needs_pool_performance()
+    {
+        return Ok(None);
+    }
+    let contract =
+        drasil_hugin::TBContracts::get_contract_uid_cid(rwd_token.user_id, rwd_token.contract_id)?;
+    let network = drasil_murin::cardano::get_network_from_address(&contract.address)?;
+    Ok(Some(
+        drasil_murin::cardano::SlotConfig::for_network(&network)?.blocks_per_epoch(),
+    ))
+}
+
+/// Active stake of the participating pools and their performance weighted total
+///
+/// The performance is the ratio of minted to `expected_blocks` scaled by the
+/// share of the pool in the active stake, it is 1 without `expected_blocks`.
+pub fn pool_snapshots(
+    pools: &[drasil_gungnir::GPools],
+    epoch: i64,
+    expected_blocks: Option<f64>,
+    provider: &dyn ChainDataProvider,
+) -> Result<(Vec<PoolSnapshot>, f64), SleipnirError> {
+    let network_stake = match expected_blocks {
+        Some(_) => provider.active_stake(epoch as i32)? as f64,
+        None => 0.0,
+    };
+    let mut snapshots = Vec::<PoolSnapshot>::new();
+    let mut weighted_stake = 0.0;
+    for pool in pools {
+        let active_stake = provider.pool_total_stake(&pool.pool_id, epoch as i32)?;
+        let mut performance = 1.0;
+        if let Some(blocks_per_epoch) = expected_blocks {
+            if network_stake > 0.0 && active_stake > 0 {
+                let expected = blocks_per_epoch * active_stake as f64 / network_stake;
+                let blocks = provider.pool_blocks(&pool.pool_id, epoch as i32)?;
+                performance = (blocks as f64 / expected).min(1.0);
+            }
+        }
+        weighted_stake += active_stake as f64 * performance;
+        snapshots.push(PoolSnapshot {
+            pool_id: pool.pool_id.clone(),
+            active_stake,
+            performance,
+        });
+    }
+    Ok((snapshots, weighted_stake))
+}
+
+/// Input of a reward formula for one stake address in the calculation epoch
+#[derive(Debug, Clone)]
+pub struct StakeSnapshot {
+    pub stake_addr: String,
+    pub epoch: i64,
+    /// Delegated lovelace, zero for whitelisted addresses
+    pub amount: BigDecimal,
+    /// Pool the stake is delegated to, `None` for whitelisted addresses
+    pub pool: Option<PoolSnapshot>,
+    /// Consecutive epochs up to `epoch` the stake address is delegated to the pool,
+    /// from the delegation history in gungnir
+    pub streak: i64,
+    /// Performance weighted active stake of all participating pools in lovelace
+    pub weighted_stake: f64,
+}
+
+impl StakeSnapshot {
+    /// Snapshot of a whitelisted address which earns independent of its stake
+    pub fn whitelisted(stake_addr: &str, epoch: i64) -> StakeSnapshot {
+        StakeSnapshot {
+            stake_addr: stake_addr.to_owned(),
+            epoch,
+            amount: BigDecimal::from_i32(0).unwrap(),
+            pool: None,
+            streak: 0,
+            weighted_stake: 0.0,
+        }
+    }
+
+    /// Delegated stake in Ada
+    pub fn ada(&self) -> f64 {
+        self.amount.to_f64().unwrap() / 1000000.0
+    }
+}
+
+pub trait RewardFormula: Send + Sync {
+    /// Name of the formula, the `equation` of the token whitelist entries using it
+    fn name(&self) -> &str;
+
+    /// Checks the parameters of a token whitelist entry
+    fn validate(&self, params: &str) -> Result<(), SleipnirError>;
+
+    /// Earned tokens in their smallest unit, `None` if the stake address does not qualify
+    fn calculate(
+        &self,
+        stake: &StakeSnapshot,
+        params: &str,
+    ) -> Result<Option<BigDecimal>, SleipnirError>;
+
+    /// `false` if new rewards are only earned once the previous ones were claimed
+    fn accumulates(&self) -> bool {
+        true
+    }
+
+    /// `true` if whitelisted addresses can earn with this formula
+    fn whitelists(&self) -> bool {
+        false
+    }
+
+    /// `true` if the formula reads the pool performance and [`StakeSnapshot::weighted_stake`]
+    fn needs_pool_performance(&self) -> bool {
+        false
+    }
+
+    /// `true` if the formula multiplies by a loyalty schedule of its own, the
+    /// loyalty schedule of the token is not applied on top
+    fn applies_loyalty(&self) -> bool {
+        false
+    }
+}
+
+fn parse<T: DeserializeOwned>(params: &str) -> Result<T, SleipnirError> {
+    Ok(serde_json::from_str::<T>(params)?)
+}
+
+fn tokens(amount: i128) -> BigDecimal {
+    BigDecimal::from_i128(amount * 1000000).unwrap()
+}
+
+pub struct Freeloaderz;
+
+impl RewardFormula for Freeloaderz {
+    fn name(&self) -> &str {
+        "Freeloaderz"
+    }
+
+    fn validate(&self, params: &str) -> Result<(), SleipnirError> {
+        parse::<FreeloaderzType>(params).map(|_| ())
+    }
+
+    fn calculate(
+        &self,
+        stake: &StakeSnapshot,
+        params: &str,
+    ) -> Result<Option<BigDecimal>, SleipnirError> {
+        let param = parse::<FreeloaderzType>(params)?;
+        let adastake = stake.ada();
+        if adastake <= param.min_stake as f64 {
+            log::debug!("delegator below min stake");
+            return Ok(None);
+        }
+        Ok(Some(
+            BigDecimal::from_f64(((adastake.powf(param.flatten)) + param.min_earned) * 1000000.0)
+                .unwrap()
+                .round(0),
+        ))
+    }
+}
+
+/// Fixed amount per epoch for each delegator above `min_stake` and each whitelisted address
+pub struct FixedAmountPerEpoch;
+
+impl RewardFormula for FixedAmountPerEpoch {
+    fn name(&self) -> &str {
+        "FixedAmountPerEpoch"
+    }
+
+    fn validate(&self, params: &str) -> Result<(), SleipnirError> {
+        parse::<FixedAmountPerEpochType>(params).map(|_| ())
+    }
+
+    fn calculate(
+        &self,
+        stake: &StakeSnapshot,
+        params: &str,
+    ) -> Result<Option<BigDecimal>, SleipnirError> {
+        fixed_amount(stake, params)
+    }
+
+    fn whitelists(&self) -> bool {
+        true
+    }
+}
+
+/// Like [`FixedAmountPerEpoch`] but nothing is earned while rewards are unclaimed
+pub struct FixedAmountPerEpochNonAcc;
+
+impl RewardFormula for FixedAmountPerEpochNonAcc {
+    fn name(&self) -> &str {
+        "FixedAmountPerEpochNonAcc"
+    }
+
+    fn validate(&self, params: &str) -> Result<(), SleipnirError> {
+        parse::<FixedAmountPerEpochType>(params).map(|_| ())
+    }
+
+    fn calculate(
+        &self,
+        stake: &StakeSnapshot,
+        params: &str,
+    ) -> Result<Option<BigDecimal>, SleipnirError> {
+        fixed_amount(stake, params)
+    }
+
+    fn accumulates(&self) -> bool {
+        false
+    }
+
+    fn whitelists(&self) -> bool {
+        true
+    }
+}
+
+fn fixed_amount(stake: &StakeSnapshot, params: &str) -> Result<Option<BigDecimal>, SleipnirError> {
+    let param = parse::<FixedAmountPerEpochType>(params)?;
+    if let (Some(min), Some(_)) = (param.min_stake, &stake.pool) {
+        if stake.ada() <= min {
+            return Ok(None);
+        }
+    }
+    Ok(Some(tokens(param.amount)))
+}
+
+/// `rwd` tokens for each full `cap_value` Ada staked
+pub struct FixedAmountPerEpochCaped;
+
+impl RewardFormula for FixedAmountPerEpochCaped {
+    fn name(&self) -> &str {
+        "FixedAmountPerEpochCaped"
+    }
+
+    fn validate(&self, params: &str) -> Result<(), SleipnirError> {
+        let param = parse::<CapedType>(params)?;
+        if param.cap_value <= 0 {
+            return Err(SleipnirError::new("cap_value needs to be positive"));
+        }
+        Ok(())
+    }
+
+    fn calculate(
+        &self,
+        stake: &StakeSnapshot,
+        params: &str,
+    ) -> Result<Option<BigDecimal>, SleipnirError> {
+        let param = parse::<CapedType>(params)?;
+        let adastake = stake.amount.to_i128().unwrap() / 1000000;
+        let mul = adastake / param.cap_value;
+        Ok(Some(tokens(mul * param.rwd)))
+    }
+}
+
+/// `upper_rwd` tokens from `stake_threshold` Ada on, `lower_rwd` below
+pub struct Threshold;
+
+impl RewardFormula for Threshold {
+    fn name(&self) -> &str {
+        "Threshold"
+    }
+
+    fn validate(&self, params: &str) -> Result<(), SleipnirError> {
+        parse::<ThresholdType>(params).map(|_| ())
+    }
+
+    fn calculate(
+        &self,
+        stake: &StakeSnapshot,
+        params: &str,
+    ) -> Result<Option<BigDecimal>, SleipnirError> {
+        let param = parse::<ThresholdType>(params)?;
+        if stake.ada() >= param.stake_threshold {
+            Ok(Some(tokens(param.upper_rwd)))
+        } else {
+            Ok(Some(tokens(param.lower_rwd)))
+        }
+    }
+}
+
+/// Airdrops are distributed by the airdrop job, nothing is earned per epoch
+pub struct Airdrop;
+
+impl RewardFormula for Airdrop {
+    fn name(&self) -> &str {
+        "Airdrop"
+    }
+
+    fn validate(&self, _params: &str) -> Result<(), SleipnirError> {
+        Ok(())
+    }
+
+    fn calculate(
+        &self,
+        _stake: &StakeSnapshot,
+        _params: &str,
+    ) -> Result<Option<BigDecimal>, SleipnirError> {
+        Ok(None)
+    }
+}
+
+/// `rate` tokens per Ada staked, multiplied by the highest multiplier of the
+/// schedule whose `epochs` the delegation streak reached
+pub struct LoyaltyMultiplier;
+
+impl RewardFormula for LoyaltyMultiplier {
+    fn name(&self) -> &str {
+        "LoyaltyMultiplier"
+    }
+
+    fn validate(&self, params: &str) -> Result<(), SleipnirError> {
+        let param = parse::<LoyaltyType>(params)?;
+        Ok(drasil_gungnir::validate_loyalty_schedule(&param.schedule)?)
+    }
+
+    fn applies_loyalty(&self) -> bool {
+        true
+    }
+
+    fn calculate(
+        &self,
+        stake: &StakeSnapshot,
+        params: &str,
+    ) -> Result<Option<BigDecimal>, SleipnirError> {
+        let param = parse::<LoyaltyType>(params)?;
+        let adastake = stake.ada();
+        if let Some(min) = param.min_stake {
+            if adastake <= min {
+                return Ok(None);
+            }
+        }
+        let multiplier = param.multiplier(stake.streak);
+        Ok(
+            BigDecimal::from_f64(adastake * param.rate * multiplier * 1000000.0)
+                .map(|e| e.round(0)),
+        )
+    }
+}
+
+/// Splits `amount` tokens per epoch between all delegators of the participating
+/// pools in proportion to their stake weighted by the performance of their pool
+pub struct PoolPerformanceShare;
+
+impl RewardFormula for PoolPerformanceShare {
+    fn name(&self) -> &str {
+        "PoolPerformanceShare"
+    }
+
+    fn validate(&self, params: &str) -> Result<(), SleipnirError> {
+        parse::<PerformanceShareType>(params).map(|_| ())
+    }
+
+    fn calculate(
+        &self,
+        stake: &StakeSnapshot,
+        params: &str,
+    ) -> Result<Option<BigDecimal>, SleipnirError> {
+        let param = parse::<PerformanceShareType>(params)?;
+        let pool = match &stake.pool {
+            Some(p) => p,
+            None => return Ok(None),
+        };
+        if let Some(min) = param.min_stake {
+            if stake.ada() <= min {
+                return Ok(None);
+            }
+        }
+        if stake.weighted_stake <= 0.0 {
+            return Ok(None);
+        }
+        let share = stake.amount.to_f64().unwrap() * pool.performance / stake.weighted_stake;
+        Ok(BigDecimal::from_f64(share * param.amount as f64 * 1000000.0).map(|e| e.round(0)))
+    }
+
+    fn needs_pool_performance(&self) -> bool {
+        true
+    }
+}
+
+/// Formulas available to the reward calculation, looked up by name
+pub struct FormulaRegistry {
+    formulas: Vec<Arc<dyn RewardFormula>>,
+}
+
+impl FormulaRegistry {
+    /// Registry without any formula
+    pub fn empty() -> FormulaRegistry {
+        FormulaRegistry {
+            formulas: Vec::new(),
+        }
+    }
+
+    /// Adds a formula, replacing a registered formula of the same name
+    pub fn register(&mut self, formula: Arc<dyn RewardFormula>) {
+        self.formulas.retain(|f| f.name() != formula.name());
+        self.formulas.push(formula);
+    }
+
+    pub fn get(&self, name: &str) -> Result<Arc<dyn RewardFormula>, SleipnirError> {
+        self.formulas
+            .iter()
+            .find(|f| f.name() == name)
+            .cloned()
+            .ok_or_else(|| SleipnirError::new(&format!("Reward formula not found: {name}")))
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.formulas.iter().map(|f| f.name()).collect()
+    }
+
+    /// Checks that `name` is a registered formula accepting `params`
+    pub fn validate(&self, name: &str, params: Option<&str>) -> Result<(), SleipnirError> {
+        let formula = self.get(name)?;
+        match params {
+            Some(p) => formula.validate(p),
+            None => Err(SleipnirError::new(&format!(
+                "Reward formula {name} requires parameters"
+            ))),
+        }
+    }
+}
+
+impl Default for FormulaRegistry {
+    /// Registry with the built in formulas of [`CustomCalculationTypes`]
+    fn default() -> FormulaRegistry {
+        let mut registry = FormulaRegistry::empty();
+        registry.register(Arc::new(Freeloaderz));
+        registry.register(Arc::new(FixedAmountPerEpoch));
+        registry.register(Arc::new(FixedAmountPerEpochNonAcc));
+        registry.register(Arc::new(FixedAmountPerEpochCaped));
+        registry.register(Arc::new(Threshold));
+        registry.register(Arc::new(Airdrop));
+        registry.register(Arc::new(LoyaltyMultiplier));
+        registry.register(Arc::new(PoolPerformanceShare));
+        registry
+    }
+}
+
+static REGISTRY: OnceLock<RwLock<Arc<FormulaRegistry>>> = OnceLock::new();
+
+fn registry_slot() -> &'static RwLock<Arc<FormulaRegistry>> {
+    REGISTRY.get_or_init(|| RwLock::new(Arc::new(FormulaRegistry::default())))
+}
+
+/// Returns the formula registry used by this process, the built in formulas if
+/// nothing was registered.
+pub fn formulas() -> Arc<FormulaRegistry> {
+    registry_slot()
+        .read()
+        .expect("formula registry lock poisoned")
+        .clone()
+}
+
+/// Adds a formula to the registry used by this process.
+pub fn register_formula(formula: Arc<dyn RewardFormula>) {
+    let mut slot = registry_slot()
+        .write()
+        .expect("formula registry lock poisoned");
+    let mut registry = FormulaRegistry {
+        formulas: slot.formulas.clone(),
+    };
+    registry.register(formula);
+    *slot = Arc::new(registry);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delegator(ada: i64, streak: i64) -> StakeSnapshot {
+        StakeSnapshot {
+            stake_addr: "stake_test1".to_string(),
+            epoch: 300,
+            amount: BigDecimal::from_i64(ada * 1000000).unwrap(),
+            pool: Some(PoolSnapshot {
+                pool_id: "pool1".to_string(),
+                active_stake: 1000000000000,
+                performance: 0.5,
+            }),
+            streak,
+            weighted_stake: 500000000000.0,
+        }
+    }
+
+    #[test]
+    fn builtin_formulas_match_the_calculation_types() {
+        let registry = FormulaRegistry::default();
+        for name in [
+            CustomCalculationTypes::Freeloaderz,
+            CustomCalculationTypes::FixedAmountPerEpoch,
+            CustomCalculationTypes::FixedAmountPerEpochNonAcc,
+            CustomCalculationTypes::FixedAmountPerEpochCaped,
+            CustomCalculationTypes::Threshold,
+            CustomCalculationTypes::Airdrop,
+            CustomCalculationTypes::LoyaltyMultiplier,
+            CustomCalculationTypes::PoolPerformanceShare,
+        ] {
+            assert!(registry.get(&name.to_string()).is_ok());
+        }
+        assert!(registry.get("Unknown").is_err());
+
+        let caped = r#"{"cap_value":100,"rwd":2}"#;
+        assert_eq!(
+            registry
+                .get("FixedAmountPerEpochCaped")
+                .unwrap()
+                .calculate(&delegator(250, 0), caped)
+                .unwrap(),
+            Some(BigDecimal::from_i64(4000000).unwrap())
+        );
+
+        let fixed = r#"{"min_stake":100.0,"amount":5}"#;
+        let formula = registry.get("FixedAmountPerEpoch").unwrap();
+        assert_eq!(formula.calculate(&delegator(50, 0), fixed).unwrap(), None);
+        assert_eq!(
+            formula
+                .calculate(&StakeSnapshot::whitelisted("stake_test1", 300), fixed)
+                .unwrap(),
+            Some(BigDecimal::from_i64(5000000).unwrap())
+        );
+    }
+
+    #[test]
+    fn loyalty_and_performance_formulas() {
+        let registry = FormulaRegistry::default();
+        let loyalty = registry.get("LoyaltyMultiplier").unwrap();
+        let params = r#"{"rate":0.01,"schedule":[{"epochs":10,"multiplier":1.25},{"epochs":20,"multiplier":1.5}]}"#;
+        loyalty.validate(params).unwrap();
+        assert!(loyalty.applies_loyalty());
+        assert!(loyalty
+            .validate(r#"{"rate":0.01,"schedule":[{"epochs":0,"multiplier":1.25}]}"#)
+            .is_err());
+        let earned = |streak| {
+            loyalty
+                .calculate(&delegator(1000, streak), params)
+                .unwrap()
+                .unwrap()
+        };
+        assert_eq!(earned(3), BigDecimal::from_i64(10000000).unwrap());
+        assert_eq!(earned(10), BigDecimal::from_i64(12500000).unwrap());
+        assert_eq!(earned(25), BigDecimal::from_i64(15000000).unwrap());
+
+        // 1000 Ada at half performance are 500 of 500000 weighted Ada, 1/1000 of the budget
+        let share = registry.get("PoolPerformanceShare").unwrap();
+        assert_eq!(
+            share
+                .calculate(&delegator(1000, 0), r#"{"amount":50000}"#)
+                .unwrap(),
+            Some(BigDecimal::from_i64(50000000).unwrap())
+        );
+    }
+
+    #[test]
+    fn pool_snapshots_weigh_pools_by_performance() {
+        let stake = |amount: i64| {
+            vec![drasil_mimir::EpochStakeView {
+                stake_addr: "stake_test1".to_string(),
+                amount: BigDecimal::from_i64(amount).unwrap(),
+            }]
+        };
+        let provider = drasil_mimir::provider::FixtureProvider::new()
+            .with_pool_stake("pool1", 300, stake(1000000))
+            .with_pool_stake("pool2", 300, stake(3000000))
+            .with_pool_blocks("pool1", 300, 20)
+            .with_pool_blocks("pool2", 300, 90);
+        let pools = ["pool1", "pool2"].map(|p| drasil_gungnir::GPools {
+            pool_id: p.to_string(),
+            first_valid_epoch: 0,
+        });
+
+        // 100 blocks expected in the epoch, 25 of them from pool1 and 75 from pool2
+        let (snapshots, weighted) = pool_snapshots(&pools, 300, Some(100.0), &provider).unwrap();
+        assert_eq!(snapshots[0].performance, 0.8);
+        assert_eq!(snapshots[1].performance, 1.0);
+        assert_eq!(weighted, 3800000.0);
+
+        let (snapshots, weighted) = pool_snapshots(&pools, 300, None, &provider).unwrap();
+        assert!(snapshots.iter().all(|s| s.performance == 1.0));
+        assert_eq!(weighted, 4000000.0);
+    }
+}
@@ -1,7 +1,9 @@
 pub use crate::error::SleipnirError;
+pub mod formulas;
 pub mod models;
 
 use chrono::{NaiveDateTime, Utc};
+use drasil_gungnir::{FromPrimitive, ToPrimitive};
 use drasil_hugin::database::*;
 use drasil_murin::*;
 use models::NewTWL;
@@ -200,11 +202,11 @@ pub fn create_token_whitelisting(twl: NewTWL) -> Result<serde_json::Value, Sleip
         );
     }
     log::debug!("Retrieve token information...");
-    let mut mconn = drasil_mimir::establish_connection()?;
-    let ti = drasil_mimir::get_token_info(&mut mconn, &twl.fingerprint)?;
+    let chain = drasil_mimir::chain_data();
+    let ti = chain.token_info(&twl.fingerprint)?;
 
     log::debug!("Process epochs...");
-    let current_epoch = drasil_mimir::get_epoch(&mut mconn)? as i64;
+    let current_epoch = chain.epoch()? as i64;
     let mut start_epoch = twl.start_epoch_in;
     if start_epoch < current_epoch - 2 {
         start_epoch = current_epoch - 2;
@@ -225,7 +227,7 @@ pub fn create_token_whitelisting(twl: NewTWL) -> Result<serde_json::Value, Sleip
     let mut spools = Vec::<drasil_gungnir::GPools>::new();
     if let Some(ps) = twl.pools {
         for pool in &ps {
-            if !drasil_mimir::find_avail_pool(pool)? || models::WhitelistLink::is_wl_link(pool) {
+            if !chain.pool_available(pool)? || models::WhitelistLink::is_wl_link(pool) {
                 return Err(SleipnirError::new(&format!(
                     "One of the pools is not existing or retired: {pool}"
                 )));
@@ -239,14 +241,17 @@ pub fn create_token_whitelisting(twl: NewTWL) -> Result<serde_json::Value, Sleip
 
     //Mode
     log::debug!("Process calculation mode...");
-    match drasil_gungnir::Calculationmode::from_str(&twl.mode)? {
+    let mode = drasil_gungnir::Calculationmode::from_str(&twl.mode)?;
+    match mode {
         drasil_gungnir::Calculationmode::FixedEndEpoch => {
             twl.equation.parse::<u64>()?;
         }
         drasil_gungnir::Calculationmode::RelationalToADAStake => {
             twl.equation.parse::<f32>()?;
         }
-        drasil_gungnir::Calculationmode::Custom => {}
+        drasil_gungnir::Calculationmode::Custom => {
+            formulas::formulas().validate(&twl.equation, twl.modificator_equ.as_deref())?;
+        }
         drasil_gungnir::Calculationmode::AirDrop => {}
         _ => {
             return Err(SleipnirError::new(&format!(
@@ -257,7 +262,9 @@ pub fn create_token_whitelisting(twl: NewTWL) -> Result<serde_json::Value, Sleip
     }
 
     log::debug!("Process modificator equiation...");
-    if let Some(m) = &twl.modificator_equ {
+    // parameters of custom formulas were checked by the formula
+    let custom = mode == drasil_gungnir::Calculationmode::Custom;
+    if let (Some(m), false) = (&twl.modificator_equ, custom) {
         log::debug!("Modificator EQU found: {}", *m);
         match serde_json::from_str(m) {
             Ok(models::FreeloaderzType { .. }) => (),
@@ -275,6 +282,22 @@ pub fn create_token_whitelisting(twl: NewTWL) -> Result<serde_json::Value, Sleip
         };
     }
 
+    if let Some(schedule) = &twl.loyalty_schedule {
+        drasil_gungnir::validate_loyalty_schedule(schedule)?;
+        if custom && formulas::formulas().get(&twl.equation)?.applies_loyalty() {
+            return Err(SleipnirError::new(&format!(
+                "Reward formula {} applies its own loyalty schedule",
+                twl.equation
+            )));
+        }
+    }
+
+    if let Some(e) = twl.expiry_epochs {
+        if e < 1 {
+            return Err(SleipnirError::new("Reward expiry needs at least one epoch"));
+        }
+    }
+
     log::debug!("Establish connection to rwd database...");
     let mut gconn = drasil_gungnir::establish_connection()?;
     log::debug!("Try to create twl...");
@@ -292,7 +315,21 @@ pub fn create_token_whitelisting(twl: NewTWL) -> Result<serde_json::Value, Sleip
         &start_epoch,
         twl.end_epoch.as_ref(),
         twl.modificator_equ.as_ref(),
-    ) {
+    )
+    .and_then(|o| match &twl.loyalty_schedule {
+        Some(schedule) => drasil_gungnir::TokenWhitelist::set_loyalty_schedule(
+            &mut gconn,
+            o.id,
+            Some(schedule.as_slice()),
+        ),
+        None => Ok(o),
+    })
+    .and_then(|o| match twl.expiry_epochs {
+        Some(_) => {
+            drasil_gungnir::TokenWhitelist::set_expiry_epochs(&mut gconn, o.id, twl.expiry_epochs)
+        }
+        None => Ok(o),
+    }) {
         Ok(o) => {
             json!(o)
         }
@@ -332,8 +369,7 @@ pub async fn add_pools(
     pools: Vec<String>,
 ) -> Result<serde_json::Value, SleipnirError> {
     // Pools
-    let mut mconn = drasil_mimir::establish_connection()?;
-    let current_epoch = drasil_mimir::get_epoch(&mut mconn)? as i64;
+    let current_epoch = drasil_mimir::chain_data().epoch()? as i64;
     let mut spools = Vec::<drasil_gungnir::GPools>::new();
     spools.extend(pools.iter().map(|p| drasil_gungnir::GPools {
         pool_id: p.clone(),
@@ -416,3 +452,130 @@ pub async fn get_tokens_from_contract(
 
     Ok(json!(resp))
 }
+
+/// Tokens on a reward contract which are not owed to delegators, per whitelisted token
+pub fn reclaimable_tokens(
+    user_id: i64,
+    contract_id: i64,
+) -> Result<Vec<models::ReclaimableToken>, SleipnirError> {
+    let contract = drasil_hugin::TBContracts::get_contract_uid_cid(user_id, contract_id)?;
+    let total_value = drasil_mimir::chain_data()
+        .address_utxos(&contract.address)?
+        .calc_total_value()?;
+    let mut gconn = drasil_gungnir::establish_connection()?;
+
+    let mut out = Vec::<models::ReclaimableToken>::new();
+    for twl in drasil_gungnir::TokenWhitelist::get_rwd_contract_tokens(contract_id, user_id)? {
+        let (fingerprint, tokenname) = match (&twl.fingerprint, &twl.tokenname) {
+            (Some(f), Some(t)) => (f.clone(), t.clone()),
+            _ => continue,
+        };
+        let on_contract = match total_value.multiasset() {
+            Some(ma) => drasil_murin::utils::from_bignum(&ma.get_asset(
+                &drasil_murin::cardano::string_to_policy(&twl.policy_id)?,
+                &drasil_murin::cardano::string_to_assetname(&tokenname)?,
+            )),
+            None => 0,
+        };
+        let rwds = drasil_gungnir::Rewards::get_token_rewards(
+            &mut gconn,
+            contract_id,
+            user_id,
+            &fingerprint,
+        )?;
+        let (owed, expired) = owed_and_expired(&rwds);
+        out.push(models::ReclaimableToken {
+            fingerprint,
+            policy_id: twl.policy_id,
+            tokenname,
+            expiry_epochs: twl.expiry_epochs,
+            on_contract,
+            owed,
+            expired,
+            reclaimable: on_contract.saturating_sub(owed),
+        });
+    }
+    Ok(out)
+}
+
+/// Whole tokens still owed to and expired from the rewards of a token
+fn owed_and_expired(rwds: &[drasil_gungnir::Rewards]) -> (u64, u64) {
+    let lovelace = drasil_gungnir::BigDecimal::from_i32(1000000).unwrap();
+    let owed = rwds
+        .iter()
+        .map(|r| r.unclaimed())
+        .sum::<drasil_gungnir::BigDecimal>()
+        .to_u64()
+        .unwrap_or(0);
+    let expired = rwds
+        .iter()
+        .map(|r| &r.tot_expired / &lovelace)
+        .sum::<drasil_gungnir::BigDecimal>()
+        .to_u64()
+        .unwrap_or(0);
+    (owed, expired)
+}
+
+pub async fn get_reclaimable_rewards(
+    user_id: i64,
+    contract_id: i64,
+) -> Result<serde_json::Value, SleipnirError> {
+    Ok(json!(reclaimable_tokens(user_id, contract_id)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use drasil_gungnir::{BigDecimal, Rewards};
+
+    fn rewards(stake_addr: &str, earned: i64, claimed: i64, last_calc_epoch: i64) -> Rewards {
+        Rewards {
+            id: 1,
+            stake_addr: stake_addr.to_string(),
+            payment_addr: format!("addr_{stake_addr}"),
+            fingerprint: "asset1test".to_string(),
+            contract_id: 1,
+            user_id: 1,
+            tot_earned: BigDecimal::from_i64(earned * 1000000).unwrap(),
+            tot_claimed: BigDecimal::from_i64(claimed).unwrap(),
+            oneshot: false,
+            last_calc_epoch,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            tot_expired: BigDecimal::from_i32(0).unwrap(),
+        }
+    }
+
+    /// Expires the rewards like the expiry job does for rewards without reward runs
+    fn expire(r: &mut Rewards, cutoff: i64) {
+        if let Some(e) = r.expiry(cutoff, None) {
+            r.tot_expired = e;
+        }
+    }
+
+    #[test]
+    fn reclaimable_after_expiry() {
+        // a claimed everything before the expiry, b half of it, c nothing and
+        // d is a legacy reward calculated after the cutoff
+        let mut rwds = vec![
+            rewards("stake_test_a", 10, 10, 300),
+            rewards("stake_test_b", 10, 5, 300),
+            rewards("stake_test_c", 10, 0, 300),
+            rewards("stake_test_d", 10, 0, 310),
+        ];
+        assert_eq!(owed_and_expired(&rwds), (25, 0));
+
+        rwds.iter_mut().for_each(|r| expire(r, 305));
+        assert_eq!(owed_and_expired(&rwds), (10, 15));
+
+        // claims after the expiry only take from what is still owed
+        rwds[3].tot_claimed = BigDecimal::from_i32(4).unwrap();
+        rwds.iter_mut().for_each(|r| expire(r, 305));
+        assert_eq!(owed_and_expired(&rwds), (6, 15));
+
+        // the expired amount never shrinks
+        rwds[1].tot_claimed = BigDecimal::from_i32(7).unwrap();
+        rwds.iter_mut().for_each(|r| expire(r, 305));
+        assert_eq!(owed_and_expired(&rwds).1, 15);
+    }
+}
@@ -1,8 +1,10 @@
+pub use drasil_gungnir::LoyaltyStep;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::str::FromStr;
 use strum_macros::{Display, EnumString};
 
+/// Names of the built in reward formulas, see [`super::formulas`]
 #[derive(EnumString, Display, Serialize, Deserialize, Debug, Clone)]
 pub enum CustomCalculationTypes {
     Freeloaderz,
@@ -11,6 +13,8 @@ pub enum CustomCalculationTypes {
     FixedAmountPerEpochCaped,
     Threshold,
     Airdrop,
+    LoyaltyMultiplier,
+    PoolPerformanceShare,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -39,6 +43,26 @@ pub struct FixedAmountPerEpochType {
     pub amount: i128,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LoyaltyType {
+    pub min_stake: Option<f64>,
+    pub rate: f64,
+    pub schedule: Vec<LoyaltyStep>,
+}
+
+impl LoyaltyType {
+    /// Multiplier of the longest step reached by `streak` epochs, 1 if none
+    pub fn multiplier(&self, streak: i64) -> f64 {
+        drasil_gungnir::loyalty_multiplier(&self.schedule, streak)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PerformanceShareType {
+    pub min_stake: Option<f64>,
+    pub amount: i128,
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct WhitelistLink {
     pub id: i64,
@@ -84,4 +108,25 @@ pub struct NewTWL {
     pub start_epoch_in: i64,
     pub end_epoch: Option<i64>,
     pub modificator_equ: Option<String>,
+    /// Reward multipliers by consecutive epochs delegated to a whitelisted pool
+    pub loyalty_schedule: Option<Vec<LoyaltyStep>>,
+    /// Epochs after which unclaimed rewards expire
+    pub expiry_epochs: Option<i64>,
+}
+
+/// Liquidity of a whitelisted token on a reward contract, amounts in token units
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ReclaimableToken {
+    pub fingerprint: String,
+    pub policy_id: String,
+    pub tokenname: String,
+    pub expiry_epochs: Option<i64>,
+    /// Tokens locked on the contract
+    pub on_contract: u64,
+    /// Rewards which did not expire and are not claimed yet
+    pub owed: u64,
+    /// Rewards which expired unclaimed
+    pub expired: u64,
+    /// Tokens on the contract which are not owed to any delegator
+    pub reclaimable: u64,
 }
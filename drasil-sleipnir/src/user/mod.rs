@@ -9,7 +9,7 @@ pub async fn create_rev_payout(
     pw: String,
 ) -> Result<drasil_hugin::TBCaPayment, SleipnirError> {
     let contract = drasil_hugin::TBContracts::get_contract_uid_cid(user_id, contract_id)?;
-    let address_utxos = drasil_mimir::get_address_utxos(&contract.address)?;
+    let address_utxos = drasil_mimir::chain_data().address_utxos(&contract.address)?;
 
     let total_value = address_utxos.calc_total_value()?;
     let contract_lqdty = contract.get_contract_liquidity();
@@ -30,7 +30,7 @@ pub async fn create_custom_payout(
     log::debug!("Try to get contract...");
     let contract = drasil_hugin::TBContracts::get_contract_uid_cid(user_id, contract_id)?;
     log::debug!("Try to find contract utxos...");
-    let address_utxos = drasil_mimir::get_address_utxos(&contract.address)?;
+    let address_utxos = drasil_mimir::chain_data().address_utxos(&contract.address)?;
     log::debug!("Try to calculate total value...");
     let total_value = address_utxos.calc_total_value()?;
     log::debug!("Try to get contract liquidity...");
@@ -43,6 +43,22 @@ pub async fn create_custom_payout(
             "Requested Ada is not available on this contract",
         ));
     }
+    log::debug!("Check payout of reward tokens...");
+    let reclaimable = crate::rewards::reclaimable_tokens(user_id, contract_id)?;
+    for token in &tokens {
+        let (policy, name, amount) = token.into_asset()?;
+        for r in &reclaimable {
+            if drasil_murin::cardano::string_to_policy(&r.policy_id)? == policy
+                && drasil_murin::cardano::string_to_assetname(&r.tokenname)? == name
+                && from_bignum(&amount) > r.reclaimable
+            {
+                return Err(SleipnirError::new(&format!(
+                    "Requested tokens are owed to delegators, only {} of {} are reclaimable",
+                    r.reclaimable, r.fingerprint
+                )));
+            }
+        }
+    }
     log::debug!("Create payout value...");
 
     let payout_value = drasil_hugin::CaValue::new(ada_value as u64, tokens);
@@ -135,7 +135,7 @@ pub async fn random_allocation_whitelist_to_mintproject(
         drasil_hugin::database::TBContracts::get_contract_uid_cid(mp.user_id, mp.mint_contract_id)?;
 
     'a: for entry in wl_entry {
-        let payaddr = match drasil_mimir::select_addr_of_first_transaction(
+        let payaddr = match drasil_mimir::chain_data().first_address_of_stake_address(
             match &wallet::bech32_stake_address_from_str(&entry.payment_address) {
                 Ok(o) => o,
                 Err(_) => {
@@ -220,7 +220,7 @@ pub async fn allocate_specific_assets_to_mintproject(
             log::debug!("{:?}", anb);
 
             if anb.project_id == mp.id {
-                let payaddr = match drasil_mimir::select_addr_of_first_transaction(
+                let payaddr = match drasil_mimir::chain_data().first_address_of_stake_address(
                     match &wallet::bech32_stake_address_from_str(&entry.payment_address) {
                         Ok(o) => o,
                         Err(_) => {
@@ -5,7 +5,7 @@ mod whitelists;
 
 use csv::WriterBuilder;
 use models::*;
-use rwd_handling::{check_contract_is_active, get_token_whitelist, handle_lists};
+use rwd_handling::{check_contract_is_active, get_token_whitelist, handle_lists, record_runs};
 use structopt::StructOpt;
 
 use s3::bucket::Bucket;
@@ -26,6 +26,12 @@ struct Opt {
         about = "calc from the given epoch up to the latest possible one"
     )]
     from: Option<bool>,
+
+    #[structopt(long, about = "print the changes to the rewards without writing them")]
+    dry_run: bool,
+
+    #[structopt(long, about = "reverse and replace already applied reward runs")]
+    recalculate: bool,
 }
 
 #[tokio::main]
@@ -33,7 +39,8 @@ pub async fn main() -> Result<()> {
     let opt = Opt::from_args();
     pretty_env_logger::init();
 
-    let current_epoch = drasil_mimir::get_epoch(&mut drasil_mimir::establish_connection()?)? as i64;
+    let provider = drasil_mimir::chain_data();
+    let current_epoch = provider.epoch()? as i64;
     let calc_epoch = current_epoch - 2;
     log::debug!("Current Epoch: {}", current_epoch);
     log::debug!("Calculation Epoch: {}", calc_epoch);
@@ -43,12 +50,20 @@ pub async fn main() -> Result<()> {
         )
         .into());
     }
+    // later epochs are calculated from the rewards the earlier ones wrote
+    if opt.dry_run && opt.from == Some(true) {
+        return Err(drasil_gungnir::RWDError::new(
+            "A dry run cannot calculate several epochs, run it for one epoch",
+        )
+        .into());
+    }
 
     let mut i = calc_epoch;
     if opt.epoch.is_some() {
         i = opt.epoch.unwrap();
     };
     let mut table = Vec::<RewardTable>::new();
+    let mut protocol = Vec::<(RewardTable, drasil_gungnir::RewardDiff)>::new();
     if let Some(b) = opt.from {
         while i < calc_epoch && b {
             let mut whitelist = get_token_whitelist(calc_epoch).await?;
@@ -57,7 +72,15 @@ pub async fn main() -> Result<()> {
             for mut entry in whitelist {
                 if check_contract_is_active(&entry)? {
                     log::debug!("Entered: {:?}", entry);
-                    handle_lists(&mut entry, i, &mut table).await?
+                    handle_lists(
+                        &mut entry,
+                        i,
+                        &mut table,
+                        opt.recalculate,
+                        opt.dry_run,
+                        provider.as_ref(),
+                    )
+                    .await?
                     //   tokio::spawn(async move {
                     //       if let Err(err) = handle_pools(&mut entry,i).await {
                     //error!(cause = ?err, "calculation error for whitelist entry");
@@ -66,6 +89,9 @@ pub async fn main() -> Result<()> {
                     //   });
                 }
             }
+            // later epochs build on the rewards of this one
+            protocol.extend(record(&table, &opt)?);
+            table.clear();
             i += 1;
         }
         log::debug!(
@@ -79,26 +105,42 @@ pub async fn main() -> Result<()> {
         log::debug!("Whitelist: {:?}", whitelist);
         for mut entry in whitelist {
             if check_contract_is_active(&entry)? {
-                handle_lists(&mut entry, i, &mut table).await?
+                handle_lists(
+                    &mut entry,
+                    i,
+                    &mut table,
+                    opt.recalculate,
+                    opt.dry_run,
+                    provider.as_ref(),
+                )
+                .await?
             }
         }
+        protocol.extend(record(&table, &opt)?);
         log::debug!("Rewards successfully calucalted for epoch: {:?}", i);
     }
 
+    if opt.dry_run {
+        let diff = protocol.iter().map(|(_, d)| d).collect::<Vec<_>>();
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+        return Ok(());
+    }
+
     let mut bpath = "/".to_string();
 
     bpath.push_str(&(calc_epoch.to_string() + "_"));
     bpath.push_str(&chrono::offset::Utc::now().to_string());
     bpath.push_str(".csv");
     let mut wtr2 = WriterBuilder::new().from_writer(vec![]);
-    for entry in table {
+    for (entry, diff) in protocol {
         let mut e = entry.twldata.to_str_vec();
         e.extend(
             &mut vec![
                 entry.calc_date.to_string(),
                 entry.current_epoch.to_string(),
-                entry.earned_epoch.to_string(),
-                entry.total_earned_epoch.to_string(),
+                entry.stake_addr,
+                diff.earned.to_string(),
+                diff.tot_earned.to_string(),
             ]
             .into_iter(),
         );
@@ -115,3 +157,24 @@ pub async fn main() -> Result<()> {
 
     Ok(())
 }
+
+/// Records the reward runs of `table` and pairs each change with its calculation
+fn record(
+    table: &[RewardTable],
+    opt: &Opt,
+) -> Result<Vec<(RewardTable, drasil_gungnir::RewardDiff)>> {
+    let mut protocol = Vec::new();
+    for (key, diff) in record_runs(table, opt.dry_run, opt.recalculate)? {
+        for d in diff {
+            let entry = table
+                .iter()
+                .find(|t| t.run_key() == key && t.stake_addr == d.stake_addr);
+            if let Some(entry) = entry {
+                protocol.push((entry.clone(), d));
+            } else {
+                log::info!("Reversed rewards of {} for {:?}", d.stake_addr, key);
+            }
+        }
+    }
+    Ok(protocol)
+}
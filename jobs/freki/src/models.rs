@@ -2,7 +2,7 @@ use std::fmt;
 
 use bigdecimal::BigDecimal;
 use chrono::{DateTime, Utc};
-use drasil_gungnir::Calculationmode;
+use drasil_gungnir::{Calculationmode, RewardEarned, RewardRunInput, RewardRunKey};
 use drasil_sleipnir::rewards::models::WhitelistLink;
 
 #[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug, Clone)]
@@ -38,6 +38,11 @@ pub(crate) struct TwlData {
     pub end_epoch: Option<i64>,
     pub modificator_equ: Option<String>,
     pub calc_epoch: i64,
+    pub loyalty_schedule: Vec<drasil_gungnir::LoyaltyStep>,
+    #[serde(skip)]
+    pub recalculate: bool,
+    #[serde(skip)]
+    pub dry_run: bool,
 }
 
 impl TwlData {
@@ -56,6 +61,9 @@ impl TwlData {
         end_epoch: Option<i64>,
         modificator_equ: Option<String>,
         calc_epoch: i64,
+        loyalty_schedule: Vec<drasil_gungnir::LoyaltyStep>,
+        recalculate: bool,
+        dry_run: bool,
     ) -> TwlData {
         TwlData {
             fingerprint,
@@ -71,6 +79,9 @@ impl TwlData {
             end_epoch,
             modificator_equ,
             calc_epoch,
+            loyalty_schedule,
+            recalculate,
+            dry_run,
         }
     }
 
@@ -93,6 +104,15 @@ impl TwlData {
             self.calc_epoch.to_string(),
         ]
     }
+
+    pub fn run_key(&self) -> RewardRunKey {
+        RewardRunKey {
+            contract_id: self.contract_id,
+            user_id: self.user_id,
+            fingerprint: self.fingerprint.clone(),
+            epoch: self.calc_epoch,
+        }
+    }
 }
 impl fmt::Display for TwlData {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -118,11 +138,56 @@ impl fmt::Display for TwlData {
 #[derive(serde::Serialize, serde::Deserialize, PartialEq, Debug, Clone)]
 pub(crate) struct RewardTable {
     pub twldata: TwlData,
+    pub stake_addr: String,
+    pub payment_addr: String,
     pub calc_date: DateTime<Utc>,
     pub calc_epoch: i64,
     pub current_epoch: i64,
     pub earned_epoch: BigDecimal,
-    pub total_earned_epoch: BigDecimal,
+}
+
+impl RewardTable {
+    pub fn run_key(&self) -> RewardRunKey {
+        self.twldata.run_key()
+    }
+
+    /// The rewards of `table` grouped into one reward run per token and epoch
+    ///
+    /// The inputs hash of a run is the hex encoded blake2b-256 hash of its calculation data.
+    pub fn run_inputs(table: &[RewardTable]) -> Result<Vec<RewardRunInput>> {
+        let mut runs = Vec::<(RewardRunKey, Vec<&RewardTable>)>::new();
+        for entry in table {
+            let key = entry.run_key();
+            match runs.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, entries)) => entries.push(entry),
+                None => runs.push((key, vec![entry])),
+            }
+        }
+
+        let mut inputs = Vec::new();
+        for (key, mut entries) in runs {
+            entries.sort_by(|a, b| a.stake_addr.cmp(&b.stake_addr));
+            let data = entries
+                .iter()
+                .map(|e| (&e.twldata, &e.stake_addr, &e.earned_epoch))
+                .collect::<Vec<_>>();
+            inputs.push(RewardRunInput {
+                key,
+                inputs_hash: hex::encode(drasil_murin::cardano::blake2b256(
+                    serde_json::to_string(&data)?.as_bytes(),
+                )),
+                earned: entries
+                    .iter()
+                    .map(|e| RewardEarned {
+                        stake_addr: e.stake_addr.clone(),
+                        payment_addr: e.payment_addr.clone(),
+                        earned: e.earned_epoch.clone(),
+                    })
+                    .collect(),
+            });
+        }
+        Ok(inputs)
+    }
 }
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
@@ -19,7 +19,7 @@ pub async fn main() -> Result<()> {
     println!("Checking contracts...");
     'outer: for contract in contracts {
         let _lq = contract.get_contract_liquidity();
-        let utxos = drasil_mimir::get_address_utxos(&contract.address)?;
+        let utxos = drasil_mimir::chain_data().address_utxos(&contract.address)?;
 
         let twl = drasil_gungnir::TokenWhitelist::get_whitelist()?;
 
@@ -11,7 +11,7 @@ pub type Result<T> = std::result::Result<T, UOError>;
 
 pub async fn optimize(addr: String, uid: i64, cid: i64) -> Result<()> {
     log::debug!("Try to connect to dbsync...");
-    let contract_utxos = drasil_mimir::get_address_utxos(&addr)?;
+    let contract_utxos = drasil_mimir::chain_data().address_utxos(&addr)?;
     log::debug!("Calculate thresholds...");
     let ada_utxos = contract_utxos.get_coin_only();
     let mut t_utxos = contract_utxos.get_token_only();
@@ -382,7 +382,7 @@ fn finalize_tx(
     let a = drasil_murin::clib::utils::to_bignum(44u64);
     let b = drasil_murin::clib::utils::to_bignum(155381u64);
 
-    let slot = drasil_mimir::get_slot(&mut drasil_mimir::establish_connection()?)? as u64 + 3600;
+    let slot = drasil_mimir::chain_data().slot()? as u64 + 3600;
     let network = match addr.network_id()? {
         1 => drasil_murin::clib::NetworkId::mainnet(),
         _ => drasil_murin::clib::NetworkId::testnet(),
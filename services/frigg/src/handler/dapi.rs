@@ -18,3 +18,59 @@ pub async fn enterprise_create_apikey_post_handler(uid: String) -> WebResult<imp
         warp::http::StatusCode::CREATED,
     ))
 }
+
+/// Creates an API key restricted to contracts, operations, networks, origins and ips
+pub async fn enterprise_create_scoped_apikey_handler(
+    uid: String,
+    new: drasil_sleipnir::apiauth::NewApiKey,
+) -> WebResult<impl Reply> {
+    log::debug!("Create scoped API key for user: {:?}", uid);
+    let user = match uid.parse::<i64>() {
+        Ok(u) => u,
+        Err(_) => return Err(reject::custom(Error::Custom("invalid user".to_string()))),
+    };
+
+    let key = drasil_sleipnir::apiauth::create_api_key(&user, &new)?;
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&key),
+        warp::http::StatusCode::CREATED,
+    ))
+}
+
+pub async fn enterprise_list_apikeys_handler(uid: String) -> WebResult<impl Reply> {
+    let user = match uid.parse::<i64>() {
+        Ok(u) => u,
+        Err(_) => return Err(reject::custom(Error::Custom("invalid user".to_string()))),
+    };
+
+    let keys = drasil_sleipnir::apiauth::get_api_keys(&user)?;
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&keys),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+#[derive(serde::Deserialize, Debug, Clone)]
+pub struct RevokeApiKey {
+    key_id: String,
+}
+
+pub async fn enterprise_revoke_apikey_handler(
+    uid: String,
+    cparam: RevokeApiKey,
+) -> WebResult<impl Reply> {
+    log::debug!("Revoke API key {:?} of user: {:?}", cparam.key_id, uid);
+    let user = match uid.parse::<i64>() {
+        Ok(u) => u,
+        Err(_) => return Err(reject::custom(Error::Custom("invalid user".to_string()))),
+    };
+
+    let key = drasil_sleipnir::apiauth::revoke_api_key(&user, &cparam.key_id)?;
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&key),
+        warp::http::StatusCode::OK,
+    ))
+}
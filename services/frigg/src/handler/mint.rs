@@ -5,7 +5,10 @@ use crate::{
     WebResult,
 };
 use deadpool_lapin::Pool;
-use drasil_sleipnir::models::{CreateMintProj, ImportNFTsfromCSV};
+use drasil_sleipnir::models::{
+    CreateMintPhase, CreateMintProj, CreateRoyaltyToken, ImportNFTsfromCSV, RemoveMintPhase,
+    RevealMintProj,
+};
 use serde_json::json;
 use warp::Reply;
 
@@ -21,6 +24,89 @@ pub async fn entrp_create_mint_proj(uid: String, param: CreateMintProj) -> WebRe
     ))
 }
 
+pub async fn entrp_mint_royalty_token(
+    uid: String,
+    param: CreateRoyaltyToken,
+) -> WebResult<impl Reply> {
+    let user = get_user_from_string(&uid).await?;
+
+    let txhash = drasil_sleipnir::minting::api::mint_royalty_token(user, &param).await?;
+    Ok(warp::reply::with_status(
+        warp::reply::json(&json!({ "txhash": txhash })),
+        warp::http::StatusCode::CREATED,
+    ))
+}
+
+pub async fn entrp_create_mint_phase(uid: String, param: CreateMintPhase) -> WebResult<impl Reply> {
+    let user = get_user_from_string(&uid).await?;
+
+    let phase = drasil_sleipnir::minting::api::create_mint_phase(user, &param).await?;
+    Ok(warp::reply::with_status(
+        warp::reply::json(&phase),
+        warp::http::StatusCode::CREATED,
+    ))
+}
+
+pub async fn entrp_remove_mint_phase(uid: String, param: RemoveMintPhase) -> WebResult<impl Reply> {
+    let user = get_user_from_string(&uid).await?;
+
+    let removed = drasil_sleipnir::minting::api::remove_mint_phase(user, &param)?;
+    Ok(warp::reply::with_status(
+        warp::reply::json(&json!({ "removed": removed })),
+        warp::http::StatusCode::OK,
+    ))
+}
+
+pub async fn entrp_commit_mint_proj_reveal(
+    uid: String,
+    param: RevealMintProj,
+) -> WebResult<impl Reply> {
+    let user = get_user_from_string(&uid).await?;
+
+    let project = drasil_sleipnir::minting::api::commit_mintproject_reveal(user, &param)?;
+    Ok(warp::reply::with_status(
+        warp::reply::json(&json!({
+            "project_id": project.id,
+            "commitment": project.reveal_commitment,
+            "provenance": project.reveal_provenance,
+        })),
+        warp::http::StatusCode::CREATED,
+    ))
+}
+
+pub async fn entrp_reveal_mint_proj(uid: String, param: RevealMintProj) -> WebResult<impl Reply> {
+    let user = get_user_from_string(&uid).await?;
+
+    let proof = drasil_sleipnir::minting::api::reveal_mintproject(user, &param)?;
+    Ok(warp::reply::with_status(
+        warp::reply::json(&proof),
+        warp::http::StatusCode::CREATED,
+    ))
+}
+
+pub async fn entrp_update_revealed_metadata(
+    uid: String,
+    param: RevealMintProj,
+) -> WebResult<impl Reply> {
+    let user = get_user_from_string(&uid).await?;
+
+    let txhashes = drasil_sleipnir::minting::api::update_revealed_metadata(user, &param).await?;
+    Ok(warp::reply::with_status(
+        warp::reply::json(&json!({ "txhashes": txhashes })),
+        warp::http::StatusCode::CREATED,
+    ))
+}
+
+pub async fn entrp_reveal_proof(uid: String, param: RevealMintProj) -> WebResult<impl Reply> {
+    let user = get_user_from_string(&uid).await?;
+
+    let proof = drasil_sleipnir::minting::api::get_reveal_proof(user, &param)?;
+    Ok(warp::reply::with_status(
+        warp::reply::json(&proof),
+        warp::http::StatusCode::OK,
+    ))
+}
+
 pub async fn entrp_create_nfts_from_csv(
     uid: String,
     pool: Pool,
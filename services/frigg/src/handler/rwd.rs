@@ -76,6 +76,17 @@ pub async fn get_contract_tokens(uid: String, cparam: Contract) -> WebResult<imp
     ))
 }
 
+pub async fn get_reclaimable_rewards(uid: String, cparam: Contract) -> WebResult<impl Reply> {
+    let user = get_user_from_string(&uid).await?;
+    let reclaimable =
+        drasil_sleipnir::rewards::get_reclaimable_rewards(user, cparam.contract_id).await?;
+
+    Ok(warp::reply::with_status(
+        warp::reply::json(&reclaimable),
+        warp::http::StatusCode::OK,
+    ))
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct AddTokenWhitelisitng {
     contract_id: i64,
@@ -87,6 +98,8 @@ pub struct AddTokenWhitelisitng {
     start_epoch: i64,
     end_epoch: Option<i64>,
     modificator_equ: Option<String>,
+    loyalty_schedule: Option<Vec<drasil_sleipnir::rewards::models::LoyaltyStep>>,
+    expiry_epochs: Option<i64>,
 }
 
 pub async fn entrp_add_token_sporwc(
@@ -119,6 +132,8 @@ pub async fn entrp_add_token_sporwc(
         start_epoch_in: cparam.start_epoch,
         end_epoch: cparam.end_epoch,
         modificator_equ: cparam.modificator_equ,
+        loyalty_schedule: cparam.loyalty_schedule,
+        expiry_epochs: cparam.expiry_epochs,
     };
     log::debug!("Try to create TokenWhitelisting...");
     let token_listing = drasil_sleipnir::rewards::create_token_whitelisting(arg)?;
@@ -114,6 +114,14 @@ fn endpoints(
         .and(warp::path("cr"))
         .and_then(handler::dapi::enterprise_create_apikey_post_handler);
 
+    // list the scoped API keys of the user
+    let enterprise_get_api_keys = enterprise_get
+        .clone()
+        .and(warp::path("api"))
+        .and(warp::path("keys"))
+        .and(warp::path::end())
+        .and_then(handler::dapi::enterprise_list_apikeys_handler);
+
     // get set pool in a contract
     let enterprise_get_user_tx = enterprise_get
         .clone()
@@ -165,12 +173,23 @@ fn endpoints(
         //.and(warp::body::content_length_limit(100 * 1024).and(warp::body::json()))
         .and_then(handler::rwd::get_contract_tokens);
 
+    // get reward tokens on a contract which are not owed to delegators
+    let enterprise_get_reclaimable_rewards = enterprise_get
+        .clone()
+        .and(warp::path("sprwc"))
+        .and(warp::path("reclaim"))
+        .and(warp::path::end())
+        .and(warp::query::<Contract>())
+        .and_then(handler::rwd::get_reclaimable_rewards);
+
     let ent_get = enterprise_create_api_token
+        .or(enterprise_get_api_keys)
         .or(enterprise_get_user_tx)
         .or(enterprise_get_user_tx_timed)
         .or(enterprise_get_contracts)
         .or(enterprise_get_pools)
-        .or(enterprise_get_contract_tokens);
+        .or(enterprise_get_contract_tokens)
+        .or(enterprise_get_reclaimable_rewards);
 
     // Enterprise POST
 
@@ -246,6 +265,68 @@ fn endpoints(
         .and(warp::body::content_length_limit(100 * 1024).and(warp::body::json()))
         .and_then(handler::mint::entrp_create_mint_proj);
 
+    // Mint the CIP-27 royalty token of a mint project
+    let enterprise_post_mint_royalty_token = enterprise_post
+        .clone()
+        .and(warp::path("mint"))
+        .and(warp::path("royalty"))
+        .and(warp::body::content_length_limit(100 * 1024).and(warp::body::json()))
+        .and_then(handler::mint::entrp_mint_royalty_token);
+
+    // Add a sale phase to a mint project
+    let enterprise_post_create_mint_phase = enterprise_post
+        .clone()
+        .and(warp::path("mint"))
+        .and(warp::path("phase"))
+        .and(warp::path("cr"))
+        .and(warp::body::content_length_limit(100 * 1024).and(warp::body::json()))
+        .and_then(handler::mint::entrp_create_mint_phase);
+
+    // Remove a sale phase from a mint project
+    let enterprise_post_remove_mint_phase = enterprise_post
+        .clone()
+        .and(warp::path("mint"))
+        .and(warp::path("phase"))
+        .and(warp::path("rm"))
+        .and(warp::body::content_length_limit(100 * 1024).and(warp::body::json()))
+        .and_then(handler::mint::entrp_remove_mint_phase);
+
+    // Commit to the seed and metadata pool of a mint project in reveal mode
+    let enterprise_post_commit_mint_project_reveal = enterprise_post
+        .clone()
+        .and(warp::path("mint"))
+        .and(warp::path("reveal"))
+        .and(warp::path("commit"))
+        .and(warp::body::content_length_limit(100 * 1024).and(warp::body::json()))
+        .and_then(handler::mint::entrp_commit_mint_proj_reveal);
+
+    // Reveal the metadata of a mint project in reveal mode
+    let enterprise_post_reveal_mint_project = enterprise_post
+        .clone()
+        .and(warp::path("mint"))
+        .and(warp::path("reveal"))
+        .and(warp::path("cr"))
+        .and(warp::body::content_length_limit(100 * 1024).and(warp::body::json()))
+        .and_then(handler::mint::entrp_reveal_mint_proj);
+
+    // Update the metadata of NFTs minted before the reveal
+    let enterprise_post_update_revealed_metadata = enterprise_post
+        .clone()
+        .and(warp::path("mint"))
+        .and(warp::path("reveal"))
+        .and(warp::path("upd"))
+        .and(warp::body::content_length_limit(100 * 1024).and(warp::body::json()))
+        .and_then(handler::mint::entrp_update_revealed_metadata);
+
+    // Proof to verify the reveal of a mint project
+    let enterprise_post_reveal_proof = enterprise_post
+        .clone()
+        .and(warp::path("mint"))
+        .and(warp::path("reveal"))
+        .and(warp::path("proof"))
+        .and(warp::body::content_length_limit(100 * 1024).and(warp::body::json()))
+        .and_then(handler::mint::entrp_reveal_proof);
+
     // Create a new reward contract
     let enterprise_post_create_reward_contract = enterprise_post
         .clone()
@@ -330,6 +411,22 @@ fn endpoints(
         .and(warp::body::content_length_limit(10000 * 1024).and(warp::body::json()))
         .and_then(handler::whitelist::import_whitelist_from_csv);
 
+    // Create an API key with scopes and allowlists
+    let enterprise_post_create_api_key = enterprise_post
+        .clone()
+        .and(warp::path("api"))
+        .and(warp::path("key"))
+        .and(warp::body::content_length_limit(100 * 1024).and(warp::body::json()))
+        .and_then(handler::dapi::enterprise_create_scoped_apikey_handler);
+
+    // Revoke a single API key
+    let enterprise_post_revoke_api_key = enterprise_post
+        .clone()
+        .and(warp::path("api"))
+        .and(warp::path("revoke"))
+        .and(warp::body::content_length_limit(100 * 1024).and(warp::body::json()))
+        .and_then(handler::dapi::enterprise_revoke_apikey_handler);
+
     let ent_post = enterprise_post_create_discount
         .or(enterprise_post_remove_discount)
         .or(enterprise_post_rnd_alloc_nfts_to_mp)
@@ -338,6 +435,13 @@ fn endpoints(
         .or(enterprise_post_import_nfts_csv_meta_2)
         .or(enterprise_post_reactivate_reward_contract)
         .or(enterprise_post_create_mint_project)
+        .or(enterprise_post_mint_royalty_token)
+        .or(enterprise_post_create_mint_phase)
+        .or(enterprise_post_remove_mint_phase)
+        .or(enterprise_post_commit_mint_project_reveal)
+        .or(enterprise_post_reveal_mint_project)
+        .or(enterprise_post_update_revealed_metadata)
+        .or(enterprise_post_reveal_proof)
         .or(enterprise_post_create_reward_contract)
         .or(enterprise_post_deprecate_reward_contract)
         .or(enterprise_post_add_pools)
@@ -347,7 +451,9 @@ fn endpoints(
         .or(enterprise_post_create_lqdt_wallet)
         .or(enterprise_post_create_whitelist)
         .or(enterprise_post_delete_whitelist)
-        .or(enterprise_post_import_whitelist);
+        .or(enterprise_post_import_whitelist)
+        .or(enterprise_post_create_api_key)
+        .or(enterprise_post_revoke_api_key);
 
     // Endpoint Accumulators
     let enterprise = ent_get.or(ent_post);
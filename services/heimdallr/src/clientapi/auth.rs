@@ -1,7 +1,10 @@
 use std::str;
 
 use drasil_hugin::Signature;
-use drasil_hugin::{OneShotMintPayload, TXPWrapper, TransactionPattern, WalletTransactionPattern};
+use drasil_hugin::{
+    Cip68UpdatePayload, OneShotMintPayload, TXPWrapper, TransactionPattern,
+    WalletTransactionPattern,
+};
 use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
 use serde::{Deserialize, Serialize};
 use warp::http::header::{HeaderMap, HeaderValue, AUTHORIZATION};
@@ -33,6 +36,8 @@ pub(crate) async fn authorize(
         TXPWrapper::Signature(s)
     } else if let Ok(wal) = serde_json::from_str::<WalletTransactionPattern>(str_slice) {
         TXPWrapper::TransactionPattern(Box::new(wal.into_txp()))
+    } else if let Ok(upd) = serde_json::from_str::<Cip68UpdatePayload>(str_slice) {
+        TXPWrapper::Cip68Update(upd)
     } else {
         TXPWrapper::OneShotMinter(serde_json::from_str::<OneShotMintPayload>(str_slice).unwrap())
     };
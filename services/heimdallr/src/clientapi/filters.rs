@@ -5,7 +5,9 @@ use drasil_hugin::datamodel::models::{ContractType, MultiSigType, StdTxType, TXP
 use warp::Filter;
 
 fn api_endpoints() -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
-    resp_option().or(oneshot_minter_api())
+    resp_option()
+        .or(oneshot_minter_api())
+        .or(cip68_update_api())
 }
 
 fn oneshot_minter_api() -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone
@@ -18,6 +20,16 @@ fn oneshot_minter_api() -> impl Filter<Extract = impl warp::Reply, Error = warp:
         .and_then(handlers::hnd_oneshot_minter_api)
 }
 
+fn cip68_update_api() -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path("api")
+        .and(warp::path("mint"))
+        .and(warp::path("cip68"))
+        .and(warp::path("update"))
+        .and(warp::post())
+        .and(auth())
+        .and_then(handlers::hnd_cip68_update_api)
+}
+
 pub fn endpoints() -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
     list_contracts()
         .or(exec_build_multisig())
@@ -27,6 +39,7 @@ pub fn endpoints() -> impl Filter<Extract = impl warp::Reply, Error = warp::Reje
         .or(exec_finalize_contract())
         .or(exec_finalize_multisig())
         .or(exec_finalize_stdtx())
+        .or(tx_status())
         .or(api_endpoints())
         .or(warp::get().and(warp::any().map(warp::reply)))
 }
@@ -55,6 +68,9 @@ fn list_contracts() -> impl Filter<Extract = impl warp::Reply, Error = warp::Rej
 }
 
 /// Build a Smart Contract transaction
+///
+/// Marketplace actions: list, buy, cancel, update, offer, bid, acceptoffer, canceloffer
+/// Auction actions: startauction, bidauction, settleauction, cancelauction
 fn exec_build_contract() -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone
 {
     warp::path("cn")
@@ -120,6 +136,21 @@ fn exec_finalize_stdtx() -> impl Filter<Extract = impl warp::Reply, Error = warp
         .and_then(handlers::stdtx_exec_finalize)
 }
 
+/// GET the lifecycle status of a transaction
+fn tx_status() -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path!("tx" / "status" / String)
+        .and(warp::get())
+        .and(header_auth())
+        .and_then(handlers::tx_status)
+}
+
+fn header_auth() -> impl Filter<Extract = (u64,), Error = warp::Rejection> + Clone {
+    use drasil_hugin::authentication::auth::authorize_header;
+    warp::filters::header::headers_cloned()
+        .and(warp::addr::remote())
+        .and_then(authorize_header)
+}
+
 fn auth() -> impl Filter<Extract = ((u64, TXPWrapper),), Error = warp::Rejection> + Clone {
     use drasil_hugin::authentication::auth::authorize;
     use warp::{
@@ -129,6 +160,7 @@ fn auth() -> impl Filter<Extract = ((u64, TXPWrapper),), Error = warp::Rejection
     };
     headers_cloned()
         .map(move |headers: HeaderMap<HeaderValue>| (headers))
+        .and(warp::addr::remote())
         .and(bytes().map(move |body: bytes::Bytes| (body)))
         .and_then(authorize)
 }
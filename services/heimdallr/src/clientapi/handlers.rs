@@ -1,21 +1,52 @@
 use std::convert::Infallible;
 use std::env;
 use std::str::FromStr;
+use std::sync::OnceLock;
 
-use drasil_hugin::client::{connect, Client};
+use drasil_hugin::client::{Client, ClientPool};
 use drasil_hugin::datamodel::models::{
     ContractAction, ContractType, MultiSigType, OneShotReturn, ReturnError, StdTxType,
     TransactionPattern, TxHash, UnsignedTransaction,
 };
 use drasil_hugin::{
-    BuildContract, BuildMultiSig, BuildStdTx, FinalizeContract, FinalizeMultiSig, FinalizeStdTx,
-    TXPWrapper,
+    BuildContract, BuildMultiSig, BuildStdTx, ErrorCode, FinalizeContract, FinalizeMultiSig,
+    FinalizeStdTx, GetTxStatus, TXPWrapper,
 };
+use warp::http::StatusCode;
 
 use strum::VariantNames;
 
+// Connections to odin shared by all requests
+static ODIN: OnceLock<ClientPool> = OnceLock::new();
+const DEFAULT_ODIN_CONNECTIONS: usize = 4;
+
 async fn connect_odin() -> Client {
-    connect(env::var("ODIN_URL").unwrap()).await.unwrap()
+    ODIN.get_or_init(|| {
+        let size = env::var("ODIN_CONNECTIONS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(DEFAULT_ODIN_CONNECTIONS);
+        ClientPool::new(env::var("ODIN_URL").unwrap(), size)
+    })
+    .get()
+    .await
+    .unwrap()
+}
+
+/// HTTP status of a typed odin error, `default` for untyped errors
+fn error_status(err: &drasil_hugin::Error, default: StatusCode) -> StatusCode {
+    match ErrorCode::of(err) {
+        Some(ErrorCode::InvalidData | ErrorCode::InvalidCommand) => StatusCode::BAD_REQUEST,
+        Some(ErrorCode::Unauthorized) => StatusCode::UNAUTHORIZED,
+        Some(ErrorCode::NotFound) => StatusCode::NOT_FOUND,
+        Some(ErrorCode::BuildFailed) => StatusCode::CONFLICT,
+        Some(ErrorCode::TxLimitExceeded) => StatusCode::PAYLOAD_TOO_LARGE,
+        Some(ErrorCode::FinalizeFailed | ErrorCode::SubmissionFailed) => {
+            StatusCode::PRECONDITION_FAILED
+        }
+        Some(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        None => default,
+    }
 }
 
 pub async fn contracts_list() -> Result<impl warp::Reply, Infallible> {
@@ -39,6 +70,7 @@ pub async fn contract_exec_build(
         ContractType::MarketPlace => {}
         ContractType::WmEnRegistration => {}
         ContractType::WmtStaking => {}
+        ContractType::Auction => {}
         _ => {
             // Wrong Parameter
             return Ok(badreq);
@@ -48,8 +80,11 @@ pub async fn contract_exec_build(
         TXPWrapper::TransactionPattern(txp) => txp,
         _ => return Ok(badreq),
     };
-    let mut client = connect_odin().await;
-    let action = ContractAction::from_str(&action).unwrap();
+    let action = match ContractAction::from_str(&action) {
+        Ok(action) => action,
+        Err(_) => return Ok(badreq),
+    };
+    let client = connect_odin().await;
     let cmd = BuildContract::new(customer_id, contract.clone(), action, *payload.clone());
     match client.build_cmd::<BuildContract>(cmd).await {
         Ok(ok) => match UnsignedTransaction::from_str(&ok) {
@@ -79,7 +114,7 @@ pub async fn contract_exec_build(
         },
         Err(otherwise) => Ok(warp::reply::with_status(
             warp::reply::json(&ReturnError::new(&otherwise.to_string())),
-            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+            error_status(&otherwise, StatusCode::INTERNAL_SERVER_ERROR),
         )),
     }
 }
@@ -107,7 +142,7 @@ pub async fn multisig_exec_build(
         TXPWrapper::TransactionPattern(txp) => txp,
         _ => return Ok(badreq),
     };
-    let mut client = connect_odin().await;
+    let client = connect_odin().await;
     let cmd = BuildMultiSig::new(customer_id, multisig_type.clone(), *payload.clone());
     match client.build_cmd::<BuildMultiSig>(cmd).await {
         Ok(ok) => match UnsignedTransaction::from_str(&ok) {
@@ -137,7 +172,7 @@ pub async fn multisig_exec_build(
         },
         Err(otherwise) => Ok(warp::reply::with_status(
             warp::reply::json(&ReturnError::new(&otherwise.to_string())),
-            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+            error_status(&otherwise, StatusCode::INTERNAL_SERVER_ERROR),
         )),
     }
 }
@@ -154,6 +189,11 @@ pub async fn stdtx_exec_build(
         StdTxType::DeregisterStake => {}
         StdTxType::StandardTx => {}
         StdTxType::RewardWithdrawal => {}
+        StdTxType::RegisterDRep => {}
+        StdTxType::UpdateDRep => {}
+        StdTxType::RetireDRep => {}
+        StdTxType::DelegateVote => {}
+        StdTxType::CastVote => {}
     }
     let payload = match payload {
         TXPWrapper::TransactionPattern(txp) => txp,
@@ -163,7 +203,7 @@ pub async fn stdtx_exec_build(
     let cmd = BuildStdTx::new(customer_id, tx_type.clone(), *payload.clone());
     log::debug!("Command: {:?}", cmd);
     log::debug!("Try to connect to odin...");
-    let mut client = connect_odin().await;
+    let client = connect_odin().await;
     match client.build_cmd::<BuildStdTx>(cmd).await {
         Ok(ok) => match UnsignedTransaction::from_str(&ok) {
             Ok(resp) => Ok(warp::reply::with_status(
@@ -192,7 +232,7 @@ pub async fn stdtx_exec_build(
         },
         Err(otherwise) => Ok(warp::reply::with_status(
             warp::reply::json(&ReturnError::new(&otherwise.to_string())),
-            warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+            error_status(&otherwise, StatusCode::INTERNAL_SERVER_ERROR),
         )),
     }
 }
@@ -208,7 +248,7 @@ pub async fn contract_exec_finalize(
         TXPWrapper::Signature(txp) => txp,
         _ => return Ok(badreq),
     };
-    let mut client = connect_odin().await;
+    let client = connect_odin().await;
     let cmd = FinalizeContract::new(
         customer_id,
         contract.clone(),
@@ -223,7 +263,7 @@ pub async fn contract_exec_finalize(
 
         Err(e) => warp::reply::with_status(
             warp::reply::json(&ReturnError::new(&e.to_string())),
-            warp::http::StatusCode::PRECONDITION_FAILED,
+            error_status(&e, StatusCode::PRECONDITION_FAILED),
         ),
     };
 
@@ -242,7 +282,7 @@ pub async fn multisig_exec_finalize(
         TXPWrapper::Signature(txp) => txp,
         _ => return Ok(badreq),
     };
-    let mut client = connect_odin().await;
+    let client = connect_odin().await;
     let cmd = FinalizeMultiSig::new(
         customer_id,
         multisig_type.clone(),
@@ -257,7 +297,7 @@ pub async fn multisig_exec_finalize(
 
         Err(e) => warp::reply::with_status(
             warp::reply::json(&ReturnError::new(&e.to_string())),
-            warp::http::StatusCode::PRECONDITION_FAILED,
+            error_status(&e, StatusCode::PRECONDITION_FAILED),
         ),
     };
 
@@ -275,7 +315,7 @@ pub async fn stdtx_exec_finalize(
         TXPWrapper::Signature(txp) => txp,
         _ => return Ok(badreq),
     };
-    let mut client = connect_odin().await;
+    let client = connect_odin().await;
     let cmd = FinalizeStdTx::new(customer_id, txtype.clone(), tx_id, payload.get_signature());
     let response = match client.build_cmd(cmd).await {
         Ok(res) => warp::reply::with_status(
@@ -285,7 +325,7 @@ pub async fn stdtx_exec_finalize(
 
         Err(e) => warp::reply::with_status(
             warp::reply::json(&ReturnError::new(&e.to_string())),
-            warp::http::StatusCode::PRECONDITION_FAILED,
+            error_status(&e, StatusCode::PRECONDITION_FAILED),
         ),
     };
 
@@ -311,7 +351,7 @@ pub async fn hnd_oneshot_minter_api(
     let transaction_pattern =
         TransactionPattern::new_empty(customer_id, &payload.into_script_spec(), payload.network());
 
-    let mut client = connect_odin().await;
+    let client = connect_odin().await;
     let cmd = BuildMultiSig::new(customer_id, multisig_type.clone(), transaction_pattern);
     let response = match client.build_cmd::<BuildMultiSig>(cmd).await {
         Ok(ok) => match serde_json::from_str::<OneShotReturn>(&ok) {
@@ -325,3 +365,54 @@ pub async fn hnd_oneshot_minter_api(
         warp::http::StatusCode::OK,
     ))
 }
+
+pub async fn hnd_cip68_update_api(
+    (customer_id, payload): (u64, TXPWrapper),
+) -> Result<impl warp::Reply, Infallible> {
+    let badreq =
+        warp::reply::with_status(warp::reply::json(&()), warp::http::StatusCode::BAD_REQUEST);
+    log::info!("Build CIP-68 Update Transaction....");
+    let payload = match payload {
+        TXPWrapper::Cip68Update(p) => p,
+        _ => return Ok(badreq),
+    };
+
+    let multisig_type = MultiSigType::ClAPICip68Update;
+    let transaction_pattern =
+        TransactionPattern::new_empty(customer_id, &payload.into_script_spec(), payload.network());
+
+    let client = connect_odin().await;
+    let cmd = BuildMultiSig::new(customer_id, multisig_type, transaction_pattern);
+    let response = match client.build_cmd::<BuildMultiSig>(cmd).await {
+        Ok(ok) => warp::reply::with_status(
+            warp::reply::json(&TxHash::new(&ok)),
+            warp::http::StatusCode::OK,
+        ),
+        Err(e) => warp::reply::with_status(
+            warp::reply::json(&ReturnError::new(&e.to_string())),
+            error_status(&e, StatusCode::PRECONDITION_FAILED),
+        ),
+    };
+    Ok(response)
+}
+
+pub async fn tx_status(tx_hash: String, customer_id: u64) -> Result<impl warp::Reply, Infallible> {
+    let client = connect_odin().await;
+    let cmd = GetTxStatus::new(customer_id, tx_hash);
+    match client.build_cmd::<GetTxStatus>(cmd).await {
+        Ok(ok) => match serde_json::from_str::<serde_json::Value>(&ok) {
+            Ok(status) => Ok(warp::reply::with_status(
+                warp::reply::json(&status),
+                StatusCode::OK,
+            )),
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&ReturnError::new(&e.to_string())),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )),
+        },
+        Err(otherwise) => Ok(warp::reply::with_status(
+            warp::reply::json(&ReturnError::new(&otherwise.to_string())),
+            error_status(&otherwise, StatusCode::INTERNAL_SERVER_ERROR),
+        )),
+    }
+}
@@ -1,8 +1,9 @@
 use crate::error::{self, Error};
+use drasil_hugin::authentication::apikey::{ApiClaims, ApiScope, Permissions};
 use drasil_hugin::client::connect;
 use drasil_hugin::VerifyUser;
 use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation}; //encode , EncodingKey, Header
-use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
 
 use warp::{
     http::header::{HeaderMap, HeaderValue, AUTHORIZATION},
@@ -11,13 +12,12 @@ use warp::{
 
 const BEARER: &str = "Bearer ";
 
-#[derive(Debug, Deserialize, Serialize)]
-struct ApiClaims {
-    sub: String,
-    exp: usize,
-}
-
-pub(crate) async fn authorize(headers: HeaderMap<HeaderValue>) -> Result<u64, Rejection> {
+/// Checks the bearer token and returns the user id and the scope of the API key,
+/// account tokens get an unrestricted scope
+pub(crate) async fn authorize(
+    headers: HeaderMap<HeaderValue>,
+    remote: Option<SocketAddr>,
+) -> Result<(u64, ApiScope), Rejection> {
     let publ = std::env::var("JWT_PUB_KEY")
         .map_err(|_| Error::Custom("env jwt pub not existing".to_string()))?;
     let publ = publ.into_bytes();
@@ -35,7 +35,16 @@ pub(crate) async fn authorize(headers: HeaderMap<HeaderValue>) -> Result<u64, Re
             let user_id = decoded.claims.sub.parse::<u64>().map_err(|_| {
                 reject::custom(Error::Custom("Could not parse customer id".to_string()))
             })?;
-            let mut client = connect(std::env::var("ODIN_URL").unwrap()).await.unwrap();
+            decoded
+                .claims
+                .check_request(&headers, remote)
+                .map_err(|e| reject::custom(Error::Forbidden(e)))?;
+            let scope = decoded.claims.scope.unwrap_or_default();
+            // loki only serves mint claims
+            scope
+                .allows(&Permissions::Minting, &[], None)
+                .map_err(|e| reject::custom(Error::Forbidden(e)))?;
+            let client = connect(std::env::var("ODIN_URL").unwrap()).await.unwrap();
             let cmd = VerifyUser::new(user_id, jwt);
             log::info!("try to verify user ...");
             match client.build_cmd::<VerifyUser>(cmd).await {
@@ -45,7 +54,7 @@ pub(crate) async fn authorize(headers: HeaderMap<HeaderValue>) -> Result<u64, Re
                 }
             };
             log::info!("Authentication successful: User_id: {:?}", user_id);
-            Ok(user_id)
+            Ok((user_id, scope))
         }
 
         Err(e) => {
@@ -10,6 +10,8 @@ pub enum Error {
     NoAuthHeaderError,
     #[error("invalid auth header")]
     InvalidAuthHeaderError,
+    #[error("forbidden: {0}")]
+    Forbidden(String),
     #[error("internal error: {:?}", self)]
     Custom(String),
     #[error("rmq error: {0}")]
@@ -2,6 +2,7 @@ use super::error::Error;
 use super::handlers;
 use super::models::{Clients, ErrorResult};
 use deadpool_lapin::Pool;
+use drasil_hugin::authentication::apikey::ApiScope;
 use ratelimit_meter::{DirectRateLimiter, LeakyBucket};
 use std::convert::Infallible;
 use warp::{hyper::StatusCode, Filter};
@@ -67,7 +68,7 @@ pub fn ok() -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection>
         .and(warp::any().map(warp::reply))
 }
 
-pub(crate) fn auth() -> impl Filter<Extract = (u64,), Error = warp::Rejection> + Clone {
+pub(crate) fn auth() -> impl Filter<Extract = ((u64, ApiScope),), Error = warp::Rejection> + Clone {
     use super::auth::authorize;
     use warp::{
         filters::header::headers_cloned,
@@ -75,6 +76,7 @@ pub(crate) fn auth() -> impl Filter<Extract = (u64,), Error = warp::Rejection> +
     };
     headers_cloned()
         .map(move |headers: HeaderMap<HeaderValue>| (headers))
+        .and(warp::addr::remote())
         //.and(bytes().map(move |body: bytes::Bytes| (body)))
         .and_then(authorize)
 }
@@ -108,6 +110,10 @@ pub(crate) async fn handle_rejection(
                 code = StatusCode::BAD_REQUEST;
                 message = "Invalid authentication";
             }
+            Error::Forbidden(_) => {
+                code = StatusCode::FORBIDDEN;
+                message = "API key not permitted";
+            }
             Error::Custom(_) => {
                 code = StatusCode::INTERNAL_SERVER_ERROR;
                 message = "Internal Error";
@@ -1,5 +1,6 @@
 use crate::models::{Client, Clients, WSCom};
 use deadpool_lapin::Pool;
+use drasil_hugin::authentication::apikey::ApiScope;
 use futures::{FutureExt, StreamExt};
 use ratelimit_meter::{DirectRateLimiter, LeakyBucket};
 use std::convert::Infallible;
@@ -29,23 +30,84 @@ pub async fn main_worker(clients: Clients) {
     }
 }
 
+/// Forwards the claim results of the workers to the websocket of the client
+/// which requested the claim, reconnects if the queue is lost
+pub async fn result_worker(clients: Clients, pool: Pool) {
+    let mut retry_interval = tokio::time::interval(Duration::from_secs(5));
+    loop {
+        retry_interval.tick().await;
+        if let Err(e) = forward_results(&clients, pool.clone()).await {
+            log::error!("claim result listener failed: {}", e);
+        }
+    }
+}
+
+async fn forward_results(clients: &Clients, pool: Pool) -> Result<(), crate::error::Error> {
+    let rmq_con = super::get_rmq_con(pool).await?;
+    let channel = rmq_con.create_channel().await?;
+    channel
+        .queue_declare(
+            super::reply_queue(),
+            lapin::options::QueueDeclareOptions {
+                exclusive: true,
+                auto_delete: true,
+                ..Default::default()
+            },
+            lapin::types::FieldTable::default(),
+        )
+        .await?;
+    let mut consumer = channel
+        .basic_consume(
+            super::reply_queue(),
+            "loki",
+            lapin::options::BasicConsumeOptions {
+                no_ack: true,
+                ..Default::default()
+            },
+            lapin::types::FieldTable::default(),
+        )
+        .await?;
+
+    while let Some(delivery) = consumer.next().await {
+        let delivery = delivery?;
+        let client_id = match delivery.properties.correlation_id() {
+            Some(id) => id.as_str().to_string(),
+            None => continue,
+        };
+        let result = String::from_utf8_lossy(&delivery.data).to_string();
+        match clients.lock().await.get(&client_id) {
+            Some(Client {
+                sender: Some(sender),
+                ..
+            }) => {
+                let _ = sender.send(Ok(Message::binary(result)));
+            }
+            _ => log::info!(
+                "client {} left before its claim result: {}",
+                client_id,
+                result
+            ),
+        }
+    }
+    Ok(())
+}
+
 pub(crate) async fn handle_ws_client(
-    user_id: u64,
+    (user_id, scope): (u64, ApiScope),
     ws: warp::ws::Ws,
     clients: Clients,
     pool: Pool,
     rate_limiter: DirectRateLimiter<LeakyBucket>,
 ) -> Result<impl warp::Reply, Infallible> {
     println!("ws_handler");
-    Ok(
-        ws.on_upgrade(move |socket| {
-            client_connection(user_id, socket, clients, pool, rate_limiter)
-        }),
-    )
+    Ok(ws.on_upgrade(move |socket| {
+        client_connection(user_id, scope, socket, clients, pool, rate_limiter)
+    }))
 }
 
 async fn client_connection(
     user_id: u64,
+    scope: ApiScope,
     ws: WebSocket,
     clients: Clients,
     pool: Pool,
@@ -82,6 +144,7 @@ async fn client_connection(
         client_msg(
             uuid.clone(),
             user_id as i64,
+            &scope,
             msg,
             &clients,
             pool.clone(),
@@ -96,6 +159,7 @@ async fn client_connection(
 async fn client_msg(
     client_id: String,
     user_id: i64,
+    scope: &ApiScope,
     msg: Message,
     clients: &Clients,
     pool: Pool,
@@ -125,7 +189,14 @@ async fn client_msg(
                     log::info!("Try to claim mint reward...");
                     // Send Requst into Queue and respond with waiting time
                     cmr.user_id = Some(user_id);
-                    match super::add_msg_handler(pool, &cmr, rate_limiter).await {
+                    if let Err(e) = super::check_scope(&cmr, scope) {
+                        let _ = sender.send(Ok(Message::binary(
+                            serde_json::json!({ "status": "not permitted", "error": e })
+                                .to_string(),
+                        )));
+                        return;
+                    }
+                    match super::add_msg_handler(pool, &client_id, &cmr, rate_limiter).await {
                         Ok(o) => {
                             let _ = sender.send(Ok(Message::binary(o)));
                         }
@@ -8,9 +8,13 @@ mod handlers;
 mod models;
 
 use deadpool_lapin::Pool;
+use drasil_gungnir::minting::models::MintProject;
+use drasil_hugin::authentication::apikey::{ApiScope, Permissions};
+use drasil_murin::cardano;
 use lapin::ConnectionProperties;
 use models::Clients;
 use std::env;
+use std::sync::OnceLock;
 use std::{collections::HashMap, str, sync::Arc};
 use tokio::sync::Mutex;
 use warp::{Filter, Rejection};
@@ -21,6 +25,13 @@ use ratelimit_meter::{DirectRateLimiter, LeakyBucket};
 const DEFAULT_HOST: &str = "127.0.0.1";
 const DEFAULT_PORT: &str = "40001";
 
+static REPLY_QUEUE: OnceLock<String> = OnceLock::new();
+
+/// Queue of this instance receiving the claim results of the workers
+fn reply_queue() -> &'static str {
+    REPLY_QUEUE.get_or_init(|| format!("mint_result_{}", uuid::Uuid::new_v4().as_simple()))
+}
+
 #[tokio::main]
 async fn main() -> Result<(), error::Error> {
     if env::var_os("RUST_LOG").is_none() {
@@ -89,9 +100,15 @@ async fn main() -> Result<(), error::Error> {
         .recover(filters::handle_rejection)
         .with(warp::log("loki"));
     log::info!("Starting update loop");
+    let result_clients = clients.clone();
     tokio::task::spawn(async move {
         handlers::main_worker(clients.clone()).await;
     });
+    log::info!("Starting claim result listener");
+    let result_pool = pool.clone();
+    tokio::task::spawn(async move {
+        handlers::result_worker(result_clients, result_pool).await;
+    });
     log::info!("Starting server");
 
     let server = host.clone() + ":" + &port;
@@ -106,8 +123,26 @@ async fn get_rmq_con(pool: Pool) -> Result<models::Connection, deadpool_lapin::P
     Ok(connection)
 }
 
+/// Checks the mint contract and network of a claim against the scope of the API key
+fn check_scope(payload: &models::ClaimMintRewards, scope: &ApiScope) -> Result<(), String> {
+    let project = MintProject::get_mintproject_by_id(payload.mpid).map_err(|e| e.to_string())?;
+    let network =
+        match cardano::get_network_from_address(&payload.claim_addr).map_err(|e| e.to_string())? {
+            drasil_murin::clib::NetworkIdKind::Mainnet => 1,
+            drasil_murin::clib::NetworkIdKind::Testnet => 0,
+        };
+    scope.allows(
+        &Permissions::Minting,
+        &[project.mint_contract_id],
+        Some(network),
+    )
+}
+
+/// Queues the claim, the worker sends its result to the reply queue with the
+/// client id as correlation id
 async fn add_msg_handler(
     pool: Pool,
+    client_id: &str,
     payload: &models::ClaimMintRewards,
     rate_limiter: &mut DirectRateLimiter<LeakyBucket>,
 ) -> Result<String, Rejection> {
@@ -144,7 +179,9 @@ async fn add_msg_handler(
             "mint_response",
             lapin::options::BasicPublishOptions::default(),
             payload.as_bytes(),
-            lapin::BasicProperties::default(),
+            lapin::BasicProperties::default()
+                .with_reply_to(reply_queue().into())
+                .with_correlation_id(client_id.into()),
         )
         .await
         .map_err(|e| {
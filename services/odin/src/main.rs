@@ -1,6 +1,7 @@
 extern crate pretty_env_logger;
-use drasil_hugin::protocol::{connection::Connection, Shutdown};
-use drasil_hugin::Command;
+use drasil_hugin::protocol::tls::TlsAcceptor;
+use drasil_hugin::protocol::{connection::Connection, Shutdown, TlsConfig};
+use drasil_hugin::{Command, ErrorCode};
 
 use std::future::Future;
 use std::sync::Arc;
@@ -13,6 +14,7 @@ use std::str;
 
 struct Listener {
     listener: TcpListener,
+    tls: Option<TlsAcceptor>,
     limit_connections: Arc<Semaphore>,
 
     notify_shutdown: broadcast::Sender<()>,
@@ -31,12 +33,19 @@ struct Handler {
 const DEFAULT_HOST: &str = "127.0.0.1";
 const DEFAULT_PORT: &str = "6142";
 const MAX_CONNECTIONS: usize = 1000;
+// tagged requests of one connection which are processed at the same time
+const MAX_IN_FLIGHT_REQUESTS: usize = 32;
 
-pub async fn run(listener: TcpListener, shutdown: impl Future) -> crate::Result<()> {
+pub async fn run(
+    listener: TcpListener,
+    tls: Option<TlsAcceptor>,
+    shutdown: impl Future,
+) -> crate::Result<()> {
     let (notify_shutdown, _) = broadcast::channel(1);
     let (shutdown_complete_tx, shutdown_complete_rx) = mpsc::channel(1);
     let mut server = Listener {
         listener,
+        tls,
         limit_connections: Arc::new(Semaphore::new(MAX_CONNECTIONS)),
         notify_shutdown,
         shutdown_complete_tx,
@@ -78,15 +87,32 @@ impl Listener {
         loop {
             self.limit_connections.acquire().await?.forget();
             let socket = self.accept().await?;
-            log::debug!("From peer address: {:?}", socket.peer_addr().unwrap());
-            let mut handler = Handler {
-                connection: Connection::new(socket),
-                limit_connections: self.limit_connections.clone(),
-                shutdown: Shutdown::new(self.notify_shutdown.subscribe()),
-                _shutdown_complete: self.shutdown_complete_tx.clone(),
-            };
+            let peer = socket.peer_addr()?;
+            log::debug!("From peer address: {:?}", peer);
+            let tls = self.tls.clone();
+            let limit_connections = self.limit_connections.clone();
+            let shutdown = Shutdown::new(self.notify_shutdown.subscribe());
+            let shutdown_complete = self.shutdown_complete_tx.clone();
 
             tokio::spawn(async move {
+                // peers without a certificate of the configured CA fail the TLS handshake
+                let connection = match tls {
+                    Some(acceptor) => match acceptor.accept(socket).await {
+                        Ok(stream) => Connection::from_stream(stream),
+                        Err(err) => {
+                            log::warn!("rejected peer {:?}: {:?}", peer, err);
+                            limit_connections.add_permits(1);
+                            return;
+                        }
+                    },
+                    None => Connection::new(socket),
+                };
+                let mut handler = Handler {
+                    connection,
+                    limit_connections,
+                    shutdown,
+                    _shutdown_complete: shutdown_complete,
+                };
                 if let Err(err) = handler.run().await {
                     log::error!("connection error: {:?}", err);
                 }
@@ -115,12 +141,19 @@ impl Listener {
 impl Handler {
     async fn run(&mut self) -> crate::Result<()> {
         log::debug!("started new handler");
+        let (responses_tx, mut responses) = mpsc::unbounded_channel();
+        let in_flight = Arc::new(Semaphore::new(MAX_IN_FLIGHT_REQUESTS));
         while !self.shutdown.is_shutdown() {
+            // handshakes are answered before any tagged request is in flight, no response can interrupt them
             let maybe_frame = tokio::select! {
-                res = self.connection.read_frame() => {
+                res = self.connection.read_request() => {
                     log::debug!("odin received something, res: {:?}", &res);
                     res?
                 },
+                Some(response) = responses.recv() => {
+                    self.connection.write_frame(&response).await?;
+                    continue;
+                },
                 _ = self.shutdown.recv() => {
                     return Ok(());
                 }
@@ -129,19 +162,53 @@ impl Handler {
                 Some(frame) => {
                     log::debug!("odin received frame: {:?}", frame);
                     frame
-                },
+                }
                 None => return Ok(()),
             };
             log::debug!("try to parse from frame");
-            let cmd = Command::from_frame(frame);
+            let cmd = match Command::from_frame(frame, self.connection.encoding()) {
+                Ok(cmd) => cmd,
+                Err(err) => {
+                    log::error!("could not parse command: {:?}", err);
+                    self.connection
+                        .write_error(ErrorCode::InvalidCommand, &err.to_string())
+                        .await?;
+                    continue;
+                }
+            };
             log::debug!("CMD: {:?}", cmd);
-            cmd?.apply(&mut self.connection, &mut self.shutdown).await?;
+            if self.connection.request_id().is_some() {
+                // tagged requests are answered in the order they complete
+                let permit = in_flight.clone().acquire_owned().await?;
+                let mut responder = self.connection.responder(responses_tx.clone());
+                let mut shutdown = self.shutdown.subscribe();
+                tokio::spawn(async move {
+                    if let Err(err) = apply(cmd, &mut responder, &mut shutdown).await {
+                        log::error!("could not answer request: {:?}", err);
+                    }
+                    drop(permit);
+                });
+            } else {
+                apply(cmd, &mut self.connection, &mut self.shutdown).await?;
+            }
         }
 
         Ok(())
     }
 }
 
+async fn apply(cmd: Command, dst: &mut Connection, shutdown: &mut Shutdown) -> crate::Result<()> {
+    let failure = cmd.failure_code();
+    if let Err(err) = cmd.apply(dst, shutdown).await {
+        log::error!("command failed: {:?}", err);
+        if !dst.responded() {
+            let code = ErrorCode::of(&err).unwrap_or(failure);
+            dst.write_error(code, &err.to_string()).await?;
+        }
+    }
+    Ok(())
+}
+
 impl Drop for Handler {
     fn drop(&mut self) {
         self.limit_connections.add_permits(1);
@@ -152,11 +219,22 @@ use tokio::signal;
 #[tokio::main]
 pub async fn main() -> crate::Result<()> {
     pretty_env_logger::init();
+    drasil_mimir::init_protocol_parameter_source();
     let host: String = env::var("POD_HOST").unwrap_or_else(|_| DEFAULT_HOST.to_string());
     let port = env::var("POD_PORT").unwrap_or_else(|_| DEFAULT_PORT.to_string());
     let listener = TcpListener::bind(&format!("{host}:{port}")).await?;
+    let tls = match TlsConfig::from_env()? {
+        Some(config) => {
+            log::info!("TLS enabled, clients need a certificate of {:?}", config.ca);
+            Some(config.acceptor()?)
+        }
+        None => {
+            log::warn!("TLS is not configured, accepting plain TCP connections");
+            None
+        }
+    };
 
-    run(listener, signal::ctrl_c()).await
+    run(listener, tls, signal::ctrl_c()).await
 }
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
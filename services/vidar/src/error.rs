@@ -22,6 +22,8 @@ pub enum VError {
     OdinConError,
     #[error("API token unknown" )]
     UserDoesNotExists,
+    #[error("forbidden: {0}")]
+    Forbidden(String),
 }
 
 impl From<std::string::String> for VError {
@@ -44,6 +46,7 @@ pub async fn _handle_rejection(err: Rejection) -> std::result::Result<impl Reply
     } else if let Some(e) = err.find::<VError>() {
         match e {
             VError::JWTTokenError => (StatusCode::UNAUTHORIZED, e.to_string()),
+            VError::Forbidden(_) => (StatusCode::FORBIDDEN, e.to_string()),
             _ => (StatusCode::BAD_REQUEST, e.to_string()),
         }
     } else if err.find::<warp::reject::MethodNotAllowed>().is_some() {
@@ -67,10 +67,11 @@ async fn main() {
 }
 
 mod auth {
+    use drasil_hugin::authentication::apikey::{ApiClaims, Permissions};
     use drasil_hugin::client::connect;
     use drasil_hugin::VerifyUser;
     use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
-    use serde::{Deserialize, Serialize};
+    use std::net::SocketAddr;
     use warp::http::header::{HeaderMap, HeaderValue, AUTHORIZATION};
     use warp::{reject, Rejection};
 
@@ -78,13 +79,14 @@ mod auth {
 
     const BEARER: &str = "Bearer ";
 
-    #[derive(Debug, Deserialize, Serialize)]
-    struct ApiClaims {
-        sub: String,
-        exp: usize,
-    }
-
-    pub(crate) async fn authorize(headers: HeaderMap<HeaderValue>) -> Result<u64, Rejection> {
+    /// Verifies the token and checks the scope of API keys, `contracts` is `None`
+    /// if the route reads the data of all contracts of the user
+    pub(crate) async fn authorize(
+        headers: HeaderMap<HeaderValue>,
+        remote: Option<SocketAddr>,
+        permission: Permissions,
+        contracts: Option<Vec<i64>>,
+    ) -> Result<u64, Rejection> {
         let publ = std::env::var("JWT_PUB_KEY")
             .map_err(|_| VError::Custom("env jwt pub not existing".to_string()))?;
         let publ = publ.into_bytes();
@@ -101,7 +103,15 @@ mod auth {
                 let user_id = decoded.claims.sub.parse::<u64>().map_err(|_| {
                     reject::custom(VError::Custom("Could not parse customer id".to_string()))
                 })?;
-                let mut client = connect(std::env::var("ODIN_URL").unwrap()).await.map_err(|_| reject::custom(VError::OdinConError))?;
+                decoded
+                    .claims
+                    .check_request(&headers, remote)
+                    .map_err(|e| reject::custom(VError::Forbidden(e)))?;
+                decoded
+                    .claims
+                    .check_access(&permission, contracts.as_deref())
+                    .map_err(|e| reject::custom(VError::Forbidden(e)))?;
+                let client = connect(std::env::var("ODIN_URL").unwrap()).await.map_err(|_| reject::custom(VError::OdinConError))?;
                 let cmd = VerifyUser::new(user_id, jwt);
                 log::info!("try to verify user ...");
                 match client.build_cmd::<VerifyUser>(cmd).await {
@@ -134,9 +144,10 @@ mod auth {
 
 ///Filters
 mod filters {
-    use crate::models::{QAddresses, QStakeAddress};
+    use crate::models::{QAddresses, QListings, QSales, QStakeAddress};
 
     use super::handlers;
+    use drasil_hugin::authentication::apikey::Permissions;
     use warp::Filter;
 
     pub fn endpoints() -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
@@ -153,6 +164,11 @@ mod filters {
             .or(get_assethandles())
             .or(get_assethandles_stakeaddress())
             .or(get_avail_mintrewards_user())
+            .or(get_tx_status())
+            .or(get_live_auctions())
+            .or(get_mp_listings())
+            .or(get_mp_listing_history())
+            .or(get_mp_sales())
             .or(resp_option())
         // .or(warp::get().and(warp::any().map(warp::reply)))
     }
@@ -179,7 +195,7 @@ mod filters {
         warp::path("rwd")
             .and(warp::path("all"))
             .and(warp::get())
-            .and(auth())
+            .and(auth_all_contracts(Permissions::RewardClaimRequest))
             .and(warp::path::param::<String>())
             .and_then(handlers::handle_all_rewards_for_stake_addr)
     }
@@ -190,7 +206,7 @@ mod filters {
         warp::path("rwd")
             .and(warp::path("cl"))
             .and(warp::get())
-            .and(auth())
+            .and(auth_all_contracts(Permissions::RewardClaimRequest))
             .and(warp::path::param::<String>())
             .and_then(handlers::handle_rewards_for_client_stake_addr)
     }
@@ -201,8 +217,7 @@ mod filters {
         warp::path("rwd")
             .and(warp::path("one"))
             .and(warp::get())
-            .and(auth())
-            .and(warp::path::param::<u64>()) // contract-id
+            .and(auth_contract(Permissions::RewardClaimRequest))
             .and(warp::path::param::<String>()) //Stake_addr
             .and_then(handlers::handle_rewards_for_stake_addr)
     }
@@ -213,8 +228,7 @@ mod filters {
         warp::path("rwd")
             .and(warp::path("history"))
             .and(warp::get())
-            .and(auth())
-            .and(warp::path::param::<u64>()) // contract-id
+            .and(auth_contract(Permissions::RewardClaimRequest))
             .and(warp::path::param::<String>()) //Stake_addr
             .and_then(handlers::handle_claim_history_for_stake_addr_contr)
     }
@@ -225,7 +239,7 @@ mod filters {
         warp::path("rwd")
             .and(warp::path("history"))
             .and(warp::get())
-            .and(auth())
+            .and(auth_all_contracts(Permissions::RewardClaimRequest))
             .and(warp::path::param::<String>()) //Stake_addr
             .and_then(handlers::handle_claim_history_for_stake_addr)
     }
@@ -235,7 +249,7 @@ mod filters {
         warp::path("token")
             .and(warp::path("info"))
             .and(warp::get())
-            .and(auth())
+            .and(auth(Permissions::Standard))
             .and(warp::path::param::<String>()) //fingerprint
             .and_then(handlers::handle_token_info)
     }
@@ -244,7 +258,7 @@ mod filters {
     ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
         warp::path("tokens")
             .and(warp::get())
-            .and(auth())
+            .and(auth_all_contracts(Permissions::RewardClaimRequest))
             .and_then(handlers::handle_tokens)
     }
 
@@ -253,16 +267,69 @@ mod filters {
         warp::get()
             .and(warp::path("tokens"))
             .and(warp::path("rwd"))
-            .and(auth())
+            .and(auth_all_contracts(Permissions::RewardClaimRequest))
             .and_then(handlers::handle_total_rewards)
     }
 
+    pub fn get_tx_status(
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("tx" / "status" / String)
+            .and(warp::get())
+            .and(auth(Permissions::Standard))
+            .and_then(handlers::handle_tx_status)
+    }
+
+    pub fn get_live_auctions(
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("auction" / "live")
+            .and(warp::get())
+            .and(auth(Permissions::Marketplace))
+            .and_then(handlers::handle_live_auctions)
+    }
+
+    /// Listings of a marketplace contract filtered by policy, seller, price range and status
+    pub fn get_mp_listings(
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path("marketplace")
+            .and(warp::get())
+            .and(auth_contract(Permissions::Marketplace))
+            .and(warp::path("listings"))
+            .and(warp::path::end())
+            .and(warp::query::<QListings>())
+            .and_then(handlers::handle_mp_listings)
+    }
+
+    /// Events of a listing of a marketplace contract
+    pub fn get_mp_listing_history(
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path("marketplace")
+            .and(warp::get())
+            .and(auth_contract(Permissions::Marketplace))
+            .and(warp::path("listings"))
+            .and(warp::path::param::<i64>()) // listing-id
+            .and(warp::path("history"))
+            .and(warp::path::end())
+            .and_then(handlers::handle_mp_listing_history)
+    }
+
+    /// Sold listings of a marketplace contract, the latest first
+    pub fn get_mp_sales() -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone
+    {
+        warp::path("marketplace")
+            .and(warp::get())
+            .and(auth_contract(Permissions::Marketplace))
+            .and(warp::path("sales"))
+            .and(warp::path::end())
+            .and(warp::query::<QSales>())
+            .and_then(handlers::handle_mp_sales)
+    }
+
     pub fn get_avail_mintrewards(
     ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
         warp::path("mird")
             .and(warp::path("all"))
             .and(warp::get())
-            .and(auth())
+            .and(auth_all_contracts(Permissions::Minting))
             .and(warp::path::param::<String>())
             .and_then(handlers::handle_all_mint_rewards_for_stake_addr)
     }
@@ -272,7 +339,7 @@ mod filters {
         warp::path("mird")
             .and(warp::path("cl"))
             .and(warp::get())
-            .and(auth())
+            .and(auth_all_contracts(Permissions::Minting))
             .and(warp::path::param::<String>())
             .and_then(handlers::handle_cl_mint_rewards_for_stake_addr)
     }
@@ -283,7 +350,7 @@ mod filters {
             .and(warp::path("assets"))
             .and(warp::path("addresses"))
             .and(warp::post())
-            .and(auth())
+            .and(auth(Permissions::Standard))
             .and(warp::body::content_length_limit(10000 * 1024).and(warp::body::json()))
             .and_then(handlers::handle_post_asset_for_addresses)
     }
@@ -294,7 +361,7 @@ mod filters {
             .and(warp::path("assets"))
             .and(warp::path("addresses"))
             .and(warp::get())
-            .and(auth())
+            .and(auth(Permissions::Standard))
             .and(warp::query::<QAddresses>())
             .and_then(handlers::handle_get_asset_for_addresses)
     }
@@ -305,28 +372,77 @@ mod filters {
             .and(warp::path("assets"))
             .and(warp::path("stake_address"))
             .and(warp::get())
-            .and(auth())
+            .and(auth(Permissions::Standard))
             .and(warp::query::<QStakeAddress>())
             .and_then(handlers::handle_asset_for_stake_address)
     }
 
-    fn auth() -> impl Filter<Extract = (u64,), Error = warp::Rejection> + Clone {
+    /// Authorization for routes not reading contract data
+    fn auth(
+        permission: Permissions,
+    ) -> impl Filter<Extract = (u64,), Error = warp::Rejection> + Clone {
+        scoped_auth(permission, Some(vec![]))
+    }
+
+    /// Authorization for routes reading the data of all contracts of the user
+    fn auth_all_contracts(
+        permission: Permissions,
+    ) -> impl Filter<Extract = (u64,), Error = warp::Rejection> + Clone {
+        scoped_auth(permission, None)
+    }
+
+    /// Authorization for routes reading the contract in the next path segment,
+    /// extracts the user and the contract id
+    fn auth_contract(
+        permission: Permissions,
+    ) -> impl Filter<Extract = (u64, u64), Error = warp::Rejection> + Clone {
         use super::auth::authorize;
+        use std::net::SocketAddr;
         use warp::{
             filters::header::headers_cloned,
             http::header::{HeaderMap, HeaderValue},
         };
-        headers_cloned()
-            .map(move |headers: HeaderMap<HeaderValue>| (headers))
-            .and_then(authorize)
+        warp::path::param::<u64>() // contract-id
+            .and(headers_cloned())
+            .and(warp::addr::remote())
+            .and_then(
+                move |contract_id: u64,
+                      headers: HeaderMap<HeaderValue>,
+                      remote: Option<SocketAddr>| {
+                    let permission = permission.clone();
+                    async move {
+                        let contracts = Some(vec![contract_id as i64]);
+                        let user_id = authorize(headers, remote, permission, contracts).await?;
+                        Ok::<_, warp::Rejection>((user_id, contract_id))
+                    }
+                },
+            )
+            .untuple_one()
+    }
+
+    fn scoped_auth(
+        permission: Permissions,
+        contracts: Option<Vec<i64>>,
+    ) -> impl Filter<Extract = (u64,), Error = warp::Rejection> + Clone {
+        use super::auth::authorize;
+        use std::net::SocketAddr;
+        use warp::{
+            filters::header::headers_cloned,
+            http::header::{HeaderMap, HeaderValue},
+        };
+        headers_cloned().and(warp::addr::remote()).and_then(
+            move |headers: HeaderMap<HeaderValue>, remote: Option<SocketAddr>| {
+                authorize(headers, remote, permission.clone(), contracts.clone())
+            },
+        )
     }
 }
 
-///Handlers
 mod handlers {
     use std::{convert::Infallible, str::from_utf8};
 
     use cardano_serialization_lib::{address::Address, utils::from_bignum};
+    use drasil_gungnir::marketplace::models::{MpListing, MpListingEvent, MpListingFilter};
     use drasil_gungnir::minting::models::{MintProject, MintReward};
     use drasil_hugin::{
         datamodel::{ClaimedHandle, MintProjectHandle, RewardHandle},
@@ -334,7 +450,7 @@ mod handlers {
     };
     use drasil_murin::{cardano, wallet};
 
-    use crate::models::{QAddresses, QStakeAddress};
+    use crate::models::{QAddresses, QListings, QSales, QStakeAddress};
 
     #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
     pub struct ReturnError {
@@ -632,7 +748,7 @@ mod handlers {
         _: u64,
         fingerprint: String,
     ) -> Result<impl warp::Reply, Infallible> {
-        let response = match drasil_mimir::get_mint_metadata(&fingerprint) {
+        let response = match drasil_mimir::chain_data().mint_metadata(&fingerprint) {
             Ok(t) => t,
             Err(e) => {
                 log::info!(
@@ -687,6 +803,164 @@ mod handlers {
         }
     }
 
+    pub async fn handle_tx_status(
+        tx_hash: String,
+        user_id: u64,
+    ) -> Result<impl warp::Reply, Infallible> {
+        match drasil_murin::get_tx_status(&tx_hash) {
+            Ok(Some(status)) if status.user_id == Some(user_id as i64) => Ok(
+                warp::reply::with_status(warp::reply::json(&status), warp::http::StatusCode::OK),
+            ),
+            Ok(_) => Ok(warp::reply::with_status(
+                warp::reply::json(&ReturnError::new("unknown transaction")),
+                warp::http::StatusCode::NOT_FOUND,
+            )),
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&ReturnError::new(&e.to_string())),
+                warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+            )),
+        }
+    }
+
+    pub async fn handle_live_auctions(user_id: u64) -> Result<impl warp::Reply, Infallible> {
+        match drasil_hugin::TBAuction::get_live(&(user_id as i64)) {
+            Ok(auctions) => Ok(warp::reply::with_status(
+                warp::reply::json(&auctions),
+                warp::http::StatusCode::OK,
+            )),
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&ReturnError::new(&e.to_string())),
+                warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+            )),
+        }
+    }
+
+    pub async fn handle_mp_listings(
+        user_id: u64,
+        contract_id: u64,
+        query: QListings,
+    ) -> Result<impl warp::Reply, Infallible> {
+        let contract_addr = match marketplace_address(user_id, contract_id) {
+            Ok(a) => a,
+            Err(e) => {
+                return make_error(e);
+            }
+        };
+        let seller_pkh = match query.seller.as_deref().map(seller_keyhash).transpose() {
+            Ok(s) => s,
+            Err(e) => {
+                return make_error(e);
+            }
+        };
+        let filter = MpListingFilter {
+            contract_addr: Some(contract_addr),
+            policy_id: query.policy_id,
+            seller_pkh,
+            min_price: query.min_price,
+            max_price: query.max_price,
+            status: query.status,
+            page: query.page,
+            per_page: query.per_page,
+        };
+        match MpListing::query(&filter) {
+            Ok(listings) => Ok(warp::reply::with_status(
+                warp::reply::json(&listings),
+                warp::http::StatusCode::OK,
+            )),
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&ReturnError::new(&e.to_string())),
+                warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+            )),
+        }
+    }
+
+    pub async fn handle_mp_listing_history(
+        user_id: u64,
+        contract_id: u64,
+        listing_id: i64,
+    ) -> Result<impl warp::Reply, Infallible> {
+        let contract_addr = match marketplace_address(user_id, contract_id) {
+            Ok(a) => a,
+            Err(e) => {
+                return make_error(e);
+            }
+        };
+        match MpListingEvent::history(listing_id, &contract_addr) {
+            Ok(events) if events.is_empty() => Ok(warp::reply::with_status(
+                warp::reply::json(&ReturnError::new("listing not found")),
+                warp::http::StatusCode::NOT_FOUND,
+            )),
+            Ok(events) => Ok(warp::reply::with_status(
+                warp::reply::json(&events),
+                warp::http::StatusCode::OK,
+            )),
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&ReturnError::new(&e.to_string())),
+                warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+            )),
+        }
+    }
+
+    pub async fn handle_mp_sales(
+        user_id: u64,
+        contract_id: u64,
+        query: QSales,
+    ) -> Result<impl warp::Reply, Infallible> {
+        let contract_addr = match marketplace_address(user_id, contract_id) {
+            Ok(a) => a,
+            Err(e) => {
+                return make_error(e);
+            }
+        };
+        match MpListingEvent::sales(
+            &contract_addr,
+            query.policy_id.as_ref(),
+            query.page,
+            query.per_page,
+        ) {
+            Ok(sales) => Ok(warp::reply::with_status(
+                warp::reply::json(&sales),
+                warp::http::StatusCode::OK,
+            )),
+            Err(e) => Ok(warp::reply::with_status(
+                warp::reply::json(&ReturnError::new(&e.to_string())),
+                warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+            )),
+        }
+    }
+
+    /// Address of a marketplace contract of the user
+    fn marketplace_address(user_id: u64, contract_id: u64) -> Result<String, String> {
+        let contract =
+            drasil_hugin::TBContracts::get_contract_uid_cid(user_id as i64, contract_id as i64)
+                .map_err(|_| "unknown contract".to_string())?;
+        if contract.contract_type != drasil_hugin::ContractType::MarketPlace.to_string() {
+            return Err("not a marketplace contract".to_string());
+        }
+        Ok(contract.address)
+    }
+
+    /// Payment key hash of a bech32 address, a hex key hash is taken as is
+    fn seller_keyhash(seller: &str) -> Result<String, String> {
+        use cardano_serialization_lib::address::{BaseAddress, EnterpriseAddress};
+        let address = match Address::from_bech32(seller) {
+            Ok(a) => a,
+            Err(_) if seller.len() == 56 && hex::decode(seller).is_ok() => {
+                return Ok(seller.to_string())
+            }
+            Err(_) => return Err(format!("'{seller}' is no address or key hash")),
+        };
+        let cred = match BaseAddress::from_address(&address) {
+            Some(base) => base.payment_cred(),
+            None => EnterpriseAddress::from_address(&address)
+                .ok_or_else(|| format!("'{seller}' has no payment key"))?
+                .payment_cred(),
+        };
+        cred.to_keyhash()
+            .map(|pkh| hex::encode(pkh.to_bytes()))
+            .ok_or_else(|| format!("'{seller}' has no payment key"))
+    }
+
     pub fn make_error(e: String) -> Result<warp::reply::WithStatus<warp::reply::Json>, Infallible> {
         Ok(warp::reply::with_status(
             warp::reply::json(&ReturnError::new(&e)),
@@ -719,7 +993,7 @@ mod handlers {
             }
         };
 
-        let payaddr = match drasil_mimir::select_addr_of_first_transaction(&bech32addr) {
+        let payaddr = match drasil_mimir::chain_data().first_address_of_stake_address(&bech32addr) {
             Ok(a) => a,
             Err(e) => {
                 return Ok(warp::reply::with_status(
@@ -786,7 +1060,7 @@ mod handlers {
             }
         };
 
-        let payaddr = match drasil_mimir::select_addr_of_first_transaction(&bech32addr) {
+        let payaddr = match drasil_mimir::chain_data().first_address_of_stake_address(&bech32addr) {
             Ok(a) => a,
             Err(e) => {
                 return Ok(warp::reply::with_status(
@@ -850,9 +1124,14 @@ mod handlers {
     ) -> Result<impl warp::Reply, Infallible> {
         let mut utxos = drasil_murin::TransactionUnspentOutputs::new();
 
+        let chain = drasil_mimir::chain_data();
         for a in &addresses {
-            let us = drasil_mimir::get_address_utxos(a).unwrap();
-            utxos.merge(us);
+            match chain.address_utxos(a) {
+                Ok(us) => utxos.merge(us),
+                Err(e) => {
+                    return make_error(e.to_string());
+                }
+            }
         }
 
         let mut handles = Vec::<drasil_hugin::AssetHandle>::new();
@@ -880,7 +1159,12 @@ mod handlers {
                                 &hex::encode(asset.name()),
                             )
                             .unwrap();
-                            let metadata = drasil_mimir::get_mint_metadata(&fingerprint).unwrap();
+                            let metadata = match chain.mint_metadata(&fingerprint) {
+                                Ok(m) => m,
+                                Err(e) => {
+                                    return make_error(e.to_string());
+                                }
+                            };
                             handles.push(drasil_hugin::AssetHandle {
                                 fingerprint: Some(fingerprint),
                                 policy: Some(policy.to_hex()),
@@ -953,9 +1237,14 @@ mod handlers {
 
         let mut utxos = drasil_murin::TransactionUnspentOutputs::new();
 
+        let chain = drasil_mimir::chain_data();
         for a in &addresses {
-            let us = drasil_mimir::get_address_utxos(a).unwrap();
-            utxos.merge(us);
+            match chain.address_utxos(a) {
+                Ok(us) => utxos.merge(us),
+                Err(e) => {
+                    return make_error(e.to_string());
+                }
+            }
         }
 
         let mut handles = Vec::<drasil_hugin::AssetHandle>::new();
@@ -983,7 +1272,12 @@ mod handlers {
                                 &hex::encode(asset.name()),
                             )
                             .unwrap();
-                            let metadata = drasil_mimir::get_mint_metadata(&fingerprint).unwrap();
+                            let metadata = match chain.mint_metadata(&fingerprint) {
+                                Ok(m) => m,
+                                Err(e) => {
+                                    return make_error(e.to_string());
+                                }
+                            };
                             handles.push(drasil_hugin::AssetHandle {
                                 fingerprint: Some(fingerprint),
                                 policy: Some(policy.to_hex()),
@@ -1058,10 +1352,14 @@ mod handlers {
             }
         };
 
-        let utxos = match drasil_mimir::get_stake_address_utxos(
-            &mut drasil_mimir::establish_connection().unwrap(),
-            &reward_address.to_bech32(None).unwrap(),
-        ) {
+        let bech32_reward_address = match reward_address.to_bech32(None) {
+            Ok(r) => r,
+            Err(e) => {
+                return make_error(e.to_string());
+            }
+        };
+        let chain = drasil_mimir::chain_data();
+        let utxos = match chain.stake_address_utxos(&bech32_reward_address) {
             Ok(u) => u,
             Err(e) => {
                 return make_error(e.to_string());
@@ -1093,7 +1391,12 @@ mod handlers {
                                 &hex::encode(asset.name()),
                             )
                             .unwrap();
-                            let metadata = drasil_mimir::get_mint_metadata(&fingerprint).unwrap();
+                            let metadata = match chain.mint_metadata(&fingerprint) {
+                                Ok(m) => m,
+                                Err(e) => {
+                                    return make_error(e.to_string());
+                                }
+                            };
                             handles.push(drasil_hugin::AssetHandle {
                                 fingerprint: Some(fingerprint),
                                 policy: Some(policy.to_hex()),
@@ -8,3 +8,22 @@ pub struct QAddresses {
 pub struct QStakeAddress {
     pub stake_address: String,
 }
+
+/// Filter of the marketplace listing query, `seller` is an address or a payment key hash
+#[derive(Deserialize, Debug, Clone)]
+pub struct QListings {
+    pub policy_id: Option<String>,
+    pub seller: Option<String>,
+    pub min_price: Option<i64>,
+    pub max_price: Option<i64>,
+    pub status: Option<String>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct QSales {
+    pub policy_id: Option<String>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
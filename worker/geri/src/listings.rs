@@ -0,0 +1,161 @@
+//! Index of the NFT marketplace listings
+//!
+//! Outputs at one of the marketplace addresses in `MARKETPLACE_ADDRESSES`
+//! carrying a listing datum are stored as listings. Spending a listing either
+//! relists the token (update), returns it to the seller (cancel) or sends it
+//! to someone else (buy). Every change is recorded as an event with its slot
+//! so rollbacks can restore the previous state.
+use crate::model::{TransactionRecord, TxOutputRecord};
+use crate::Result;
+use drasil_gungnir::marketplace::models::{MpListing, EVENT_BUY, EVENT_CANCEL};
+use drasil_murin::clib::address::{Address, BaseAddress, EnterpriseAddress};
+use drasil_murin::clib::plutus::{PlutusData, PlutusDatumSchema};
+use drasil_murin::marketplace::{decode_mp_datum, MarketPlaceDatum};
+
+struct NewListing<'a> {
+    utxo: String,
+    address: &'a String,
+    datum: MarketPlaceDatum,
+    policy_id: String,
+    tokenname: String,
+}
+
+/// Marketplace contract addresses to index, indexing is disabled if unset
+pub fn marketplace_addresses() -> Option<Vec<String>> {
+    let addresses = std::env::var("MARKETPLACE_ADDRESSES").ok()?;
+    let addresses = addresses
+        .split('|')
+        .filter(|a| !a.is_empty())
+        .map(|a| a.to_string())
+        .collect::<Vec<_>>();
+    (!addresses.is_empty()).then_some(addresses)
+}
+
+/// Records the listing events of a transaction included at `slot`
+pub fn index_tx(tx: &TransactionRecord, slot: u64) -> Result<()> {
+    let addresses = match marketplace_addresses() {
+        Some(a) => a,
+        None => return Ok(()),
+    };
+    let slot = slot as i64;
+    let outputs = tx.outputs.clone().unwrap_or_default();
+
+    let mut new_listings = outputs
+        .iter()
+        .enumerate()
+        .filter(|(_, o)| addresses.contains(&o.address))
+        .filter_map(|(i, o)| {
+            let datum = listing_datum(tx, o)?;
+            Some(NewListing {
+                utxo: format!("{}#{}", tx.hash, i),
+                address: &o.address,
+                policy_id: hex::encode(datum.policy_id().to_bytes()),
+                tokenname: hex::encode(datum.token_name().name()),
+                datum,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for input in tx.inputs.iter().flatten() {
+        let utxo = format!("{}#{}", input.tx_id, input.index);
+        let listing = match MpListing::find_open_by_utxo(&utxo)? {
+            Some(l) => l,
+            None => continue,
+        };
+
+        // The token was locked again at the marketplace, e.g. with a new price
+        if let Some(pos) = new_listings
+            .iter()
+            .position(|n| n.policy_id == listing.policy_id && n.tokenname == listing.tokenname)
+        {
+            let relisted = new_listings.remove(pos);
+            listing.update_listing(
+                &relisted.utxo,
+                &(relisted.datum.price() as i64),
+                &tx.hash,
+                &slot,
+            )?;
+            log::info!("Listing {} updated in {}", listing.id, tx.hash);
+            continue;
+        }
+
+        let receiver = outputs.iter().find(|o| {
+            o.assets.iter().flatten().any(|a| {
+                a.policy == listing.policy_id && a.asset == listing.tokenname && a.amount > 0
+            })
+        });
+        match receiver {
+            Some(o) if payment_keyhash(&o.address).as_ref() == Some(&listing.seller_pkh) => {
+                listing.close_listing(EVENT_CANCEL, None, &tx.hash, &slot)?;
+                log::info!("Listing {} cancelled in {}", listing.id, tx.hash);
+            }
+            _ => {
+                let buyer = receiver.map(|o| o.address.clone());
+                listing.close_listing(EVENT_BUY, buyer.as_ref(), &tx.hash, &slot)?;
+                log::info!("Listing {} bought in {}", listing.id, tx.hash);
+            }
+        }
+    }
+
+    for new in new_listings {
+        let listing = MpListing::create_listing(
+            new.address,
+            &new.utxo,
+            &new.policy_id,
+            &new.tokenname,
+            &hex::encode(new.datum.seller().to_bytes()),
+            &(new.datum.price() as i64),
+            &(new.datum.royalties_rate() as i64),
+            new.datum
+                .royalties_pkh()
+                .map(|pkh| hex::encode(pkh.to_bytes()))
+                .as_ref(),
+            &tx.hash,
+            &slot,
+        )?;
+        log::info!("Listing {} created in {}", listing.id, tx.hash);
+    }
+    Ok(())
+}
+
+/// Reverts the listing events after `slot`
+pub fn rollback(slot: u64) -> Result<()> {
+    if marketplace_addresses().is_none() {
+        return Ok(());
+    }
+    let reverted = MpListing::rollback(slot as i64)?;
+    log::info!("Reverted {} listing events after slot {}", reverted, slot);
+    Ok(())
+}
+
+/// Listing datum of an output, inline or attached to the transaction
+fn listing_datum(tx: &TransactionRecord, output: &TxOutputRecord) -> Option<MarketPlaceDatum> {
+    let json = match (&output.inline_datum, &output.datum_hash) {
+        (Some(inline), _) => inline.plutus_data.clone(),
+        (None, Some(hash)) => tx
+            .plutus_data
+            .iter()
+            .flatten()
+            .find(|d| &d.datum_hash == hash)?
+            .plutus_data
+            .clone(),
+        (None, None) => return None,
+    };
+    let data = PlutusData::from_json(&json.to_string(), PlutusDatumSchema::DetailedSchema).ok()?;
+    match decode_mp_datum(&data.to_bytes()) {
+        Ok(datum) => Some(datum),
+        Err(e) => {
+            log::debug!("Output at marketplace address is no listing: {}", e);
+            None
+        }
+    }
+}
+
+fn payment_keyhash(address: &str) -> Option<String> {
+    let address = Address::from_bech32(address).ok()?;
+    let cred = match BaseAddress::from_address(&address) {
+        Some(base) => base.payment_cred(),
+        None => EnterpriseAddress::from_address(&address)?.payment_cred(),
+    };
+    cred.to_keyhash().map(|pkh| hex::encode(pkh.to_bytes()))
+}
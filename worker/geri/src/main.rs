@@ -1,4 +1,6 @@
+mod listings;
 mod model;
+mod rollback;
 extern crate pretty_env_logger;
 
 use drasil_murin::utxomngr::*;
@@ -87,10 +89,71 @@ pub fn run_worker(stream: String, worker_number: usize, id: String) -> Result<u8
         let event_type = data[0].clone();
         log::info!("Event Type: {}", event_type);
         let tx_data: Event = serde_json::from_str(&data[1])?;
+        if let Some(point) = rollback::block_point()? {
+            if point.covers(&tx_data) {
+                log::info!(
+                    "Already processed up to slot {}, skipping {}",
+                    point.slot,
+                    id
+                );
+                return Ok(0);
+            }
+        }
         match tx_data.data {
             EventData::Transaction(tx) => {
-                delete_used_utxo(&tx.hash)?;
+                let ctx = &tx_data.context;
+                match ctx.slot {
+                    Some(slot) => confirm_used_utxos(&tx.hash, slot)?,
+                    None => delete_used_utxo(&tx.hash)?,
+                }
                 log::info!("Delete: {}", tx.hash);
+                if let (Some(slot), Some(block_hash), Some(block_number)) =
+                    (ctx.slot, &ctx.block_hash, ctx.block_number)
+                {
+                    if let Some(status) = confirm_tx(&tx.hash, slot, block_hash, block_number)? {
+                        log::info!("Confirmed: {} in block {}", status.tx_hash, block_number);
+                        if let Err(e) = rollback::confirm_tx_states(&tx.hash) {
+                            log::error!("Could not confirm states of {}: {}", tx.hash, e);
+                        }
+                    }
+                }
+                if let Some(slot) = ctx.slot {
+                    if let Err(e) = listings::index_tx(&tx, slot) {
+                        log::error!("Could not index marketplace listings of {}: {}", tx.hash, e);
+                    }
+                }
+            }
+            EventData::Block(b) => {
+                rollback::store_block_point(b.slot, &b.hash, id)?;
+                prune_used_utxo_journal(b.slot)?;
+                for status in process_block(b.slot, b.number)? {
+                    log::info!("Transaction {} is final: {}", status.tx_hash, status.state);
+                    if status.state == TxState::Expired {
+                        delete_used_utxo(&status.tx_hash)?;
+                        if let Err(e) = rollback::release_tx_states(&status.tx_hash) {
+                            log::error!("Could not release states of {}: {}", status.tx_hash, e);
+                        }
+                    }
+                }
+            }
+            EventData::RollBack {
+                block_slot,
+                block_hash,
+            } => {
+                log::warn!("Rollback to slot {} ({})", block_slot, block_hash);
+                rollback::store_block_point(block_slot, &block_hash, id)?;
+                for txhash in restore_used_utxos(block_slot)? {
+                    log::info!("Restored used utxos of: {}", txhash);
+                }
+                for status in rollback_txs(block_slot)? {
+                    log::info!("Rolled back: {}", status.tx_hash);
+                    if let Err(e) = rollback::revert_tx_states(&status.tx_hash) {
+                        log::error!("Could not revert states of {}: {}", status.tx_hash, e);
+                    }
+                }
+                if let Err(e) = listings::rollback(block_slot) {
+                    log::error!("Could not roll back marketplace listings: {}", e);
+                }
             }
             _ => {
                 log::info!("Event data is not a transaction");
@@ -136,6 +199,19 @@ pub fn main() -> Result<()> {
 
     log::debug!("Trying to establish first connection....");
     let timeout = std::env::var("TIMEOUT")?;
+    // Blocks, transactions and rollbacks must be consumed from one stream to be processed in chain order
+    if streams.len() > 1 {
+        log::warn!(
+            "Events of different streams are not processed in chain order: {:?}",
+            streams
+        );
+    }
+    let point = rollback::block_point()?;
+    match &point {
+        Some(p) => log::info!("Resuming after block point: {} ({})", p.slot, p.hash),
+        None => log::info!("No block point processed yet"),
+    }
+    let group_start = rollback::BlockPoint::group_start(point.as_ref());
     let mut last_id: String;
     let mut read_backlog = true;
 
@@ -160,7 +236,7 @@ pub fn main() -> Result<()> {
                                 .arg("CREATE")
                                 .arg(streams[i].clone())
                                 .arg(streams[i].clone() + "_grp")
-                                .arg("$")
+                                .arg(&group_start)
                                 .query(&mut c)?;
                             groups = redis::cmd("XINFO")
                                 .arg("GROUPS")
@@ -182,7 +258,7 @@ pub fn main() -> Result<()> {
                             .arg("CREATE")
                             .arg(streams[i].clone())
                             .arg(streams[i].clone() + "_grp")
-                            .arg("$")
+                            .arg(&group_start)
                             .query(&mut c)?;
                         log::info!("Create Group: {:?}", resp);
                     }
@@ -200,7 +276,7 @@ pub fn main() -> Result<()> {
                                 .arg("CREATE")
                                 .arg(streams[i].clone())
                                 .arg(streams[i].clone() + "_grp")
-                                .arg("$")
+                                .arg(&group_start)
                                 .query(&mut c)?;
                             groups = redis::cmd("XINFO")
                                 .arg("GROUPS")
@@ -222,7 +298,7 @@ pub fn main() -> Result<()> {
                             .arg("CREATE")
                             .arg(streams[i].clone())
                             .arg(streams[i].clone() + "_grp")
-                            .arg("$")
+                            .arg(&group_start)
                             .query(&mut c)?;
                         log::info!("Create Group: {:?}", resp);
                     }
@@ -278,6 +354,7 @@ pub fn main() -> Result<()> {
                 if use_stream_trimmer {
                     for stream in streams.clone() {
                         let maxlen = match &stream.clone()[..] {
+                            "chain" => 30000,
                             "transaction" => 25000,
                             "block" => 5000,
                             "rollback" => 1000,
@@ -0,0 +1,195 @@
+//! Chain state geri keeps consistent across rollbacks
+//!
+//! Geri remembers the last block point it processed and resumes from it after
+//! a restart. Transactions included in
+//! a rolled back block get their used utxos locked again and their minted NFTs,
+//! reward claims and auction changes reverted until they are included again or
+//! expire.
+use crate::model::{Event, EventData};
+use crate::{connect, Connection, Result};
+use drasil_gungnir::minting::models::Nft;
+use drasil_gungnir::{Claimed, CLAIM_EXPIRED, CLAIM_ROLLED_BACK};
+use drasil_hugin::TBAuction;
+
+const BLOCK_POINT_KEY: &str = "geri_block_point";
+
+/// Point of the last processed block or rollback and the id of its stream entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockPoint {
+    pub slot: u64,
+    pub hash: String,
+    pub id: Option<String>,
+}
+
+impl BlockPoint {
+    /// Stream id a new consumer group starts after, without a block point only new entries are read
+    pub fn group_start(point: Option<&BlockPoint>) -> String {
+        point
+            .and_then(|p| p.id.clone())
+            .unwrap_or_else(|| "$".to_string())
+    }
+
+    /// True for blocks and transactions up to this point, they were processed
+    /// before geri restarted. Rollbacks are always processed.
+    pub fn covers(&self, event: &Event) -> bool {
+        match &event.data {
+            EventData::Block(b) => {
+                b.slot < self.slot || (b.slot == self.slot && b.hash == self.hash)
+            }
+            EventData::Transaction(_) => event.context.slot.map_or(false, |s| s < self.slot),
+            _ => false,
+        }
+    }
+}
+
+/// Stores the point of the last processed block or rollback
+pub fn store_block_point(slot: u64, hash: &str, id: &str) -> Result<()> {
+    let items: [(&str, String); 3] = [
+        ("slot", slot.to_string()),
+        ("hash", hash.to_string()),
+        ("id", id.to_string()),
+    ];
+    match connect()? {
+        Connection::Cluster(mut c) => redis::cmd("HSET")
+            .arg(BLOCK_POINT_KEY)
+            .arg(&items)
+            .query(&mut c)?,
+        Connection::Single(mut c) => redis::cmd("HSET")
+            .arg(BLOCK_POINT_KEY)
+            .arg(&items)
+            .query(&mut c)?,
+    }
+    Ok(())
+}
+
+/// The last processed block or rollback
+pub fn block_point() -> Result<Option<BlockPoint>> {
+    let (slot, hash, id): (Option<u64>, Option<String>, Option<String>) = match connect()? {
+        Connection::Cluster(mut c) => redis::cmd("HMGET")
+            .arg(BLOCK_POINT_KEY)
+            .arg("slot")
+            .arg("hash")
+            .arg("id")
+            .query(&mut c)?,
+        Connection::Single(mut c) => redis::cmd("HMGET")
+            .arg(BLOCK_POINT_KEY)
+            .arg("slot")
+            .arg("hash")
+            .arg("id")
+            .query(&mut c)?,
+    };
+    Ok(slot
+        .zip(hash)
+        .map(|(slot, hash)| BlockPoint { slot, hash, id }))
+}
+
+/// Confirms the NFTs minted and the auction changes of a transaction and
+/// restores its claims if it was rolled back before
+pub fn confirm_tx_states(txhash: &String) -> Result<()> {
+    let nfts = Nft::set_tx_confirmed(txhash, true)?;
+    let mut gconn = drasil_gungnir::establish_connection()?;
+    let claims = Claimed::restore_tx_claims(&mut gconn, txhash)?;
+    let auctions = TBAuction::confirm_tx(txhash)?;
+    log::info!(
+        "Confirmed {} NFTs and {} auction events and restored {} claims of {}",
+        nfts,
+        auctions,
+        claims.len(),
+        txhash
+    );
+    Ok(())
+}
+
+/// Sets the NFTs and auction changes of a rolled back transaction unconfirmed
+/// and invalidates its claims until the transaction is included again
+pub fn revert_tx_states(txhash: &String) -> Result<()> {
+    let nfts = Nft::set_tx_confirmed(txhash, false)?;
+    let mut gconn = drasil_gungnir::establish_connection()?;
+    let claims = Claimed::invalidate_tx_claims(&mut gconn, txhash, CLAIM_ROLLED_BACK)?;
+    let auctions = TBAuction::revert_tx(txhash)?;
+    log::info!(
+        "Unconfirmed {} NFTs and {} auction events and invalidated {} claims of {}",
+        nfts,
+        auctions,
+        claims.len(),
+        txhash
+    );
+    Ok(())
+}
+
+/// Makes the NFTs of an expired transaction mintable again, invalidates its
+/// claims and drops its auction changes
+pub fn release_tx_states(txhash: &String) -> Result<()> {
+    let nfts = Nft::release_tx(txhash)?;
+    let mut gconn = drasil_gungnir::establish_connection()?;
+    let claims = Claimed::invalidate_tx_claims(&mut gconn, txhash, CLAIM_EXPIRED)?;
+    let auctions = TBAuction::release_tx(txhash)?;
+    log::info!(
+        "Released {} NFTs and {} auction events and invalidated {} claims of {}",
+        nfts,
+        auctions,
+        claims.len(),
+        txhash
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(json: &str) -> Event {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn block(slot: u64, hash: &str) -> Event {
+        event(&format!(
+            r#"{{"context":{{"slot":{slot},"block_hash":"{hash}"}},"block":{{"era":"Babbage","body_size":4,"issuer_vkey":"aa","tx_count":1,"slot":{slot},"hash":"{hash}","number":7,"previous_hash":"bb"}}}}"#
+        ))
+    }
+
+    fn transaction(slot: u64) -> Event {
+        event(&format!(
+            r#"{{"context":{{"slot":{slot},"tx_hash":"dd"}},"transaction":{{"hash":"dd","fee":170000,"input_count":1,"output_count":1,"mint_count":0,"total_output":1000000}}}}"#
+        ))
+    }
+
+    fn rollback(slot: u64) -> Event {
+        event(&format!(
+            r#"{{"context":{{}},"roll_back":{{"block_slot":{slot},"block_hash":"ee"}}}}"#
+        ))
+    }
+
+    #[test]
+    fn resume_skips_processed_blocks_and_transactions() {
+        let point = BlockPoint {
+            slot: 100,
+            hash: "cc".to_string(),
+            id: Some("1700000000000-3".to_string()),
+        };
+        assert!(point.covers(&block(99, "aa")));
+        assert!(point.covers(&block(100, "cc")));
+        // a different block at the same slot belongs to another fork
+        assert!(!point.covers(&block(100, "ff")));
+        assert!(!point.covers(&block(101, "aa")));
+
+        assert!(point.covers(&transaction(99)));
+        // transactions of the block of the point might not have been processed yet
+        assert!(!point.covers(&transaction(100)));
+
+        assert!(!point.covers(&rollback(50)));
+    }
+
+    #[test]
+    fn consumer_group_starts_at_block_point() {
+        assert_eq!(BlockPoint::group_start(None), "$");
+        let mut point = BlockPoint {
+            slot: 100,
+            hash: "cc".to_string(),
+            id: None,
+        };
+        assert_eq!(BlockPoint::group_start(Some(&point)), "$");
+        point.id = Some("1700000000000-3".to_string());
+        assert_eq!(BlockPoint::group_start(Some(&point)), "1700000000000-3");
+    }
+}
@@ -1,4 +1,5 @@
 pub mod reward_calculation;
+pub mod reward_expiry;
 pub mod utxo_multiplication;
 extern crate pretty_env_logger;
 
@@ -9,9 +10,11 @@ use drasil_sleipnir::whitelist::AllocateSpecificAssetsToMintProject;
 use drasil_sleipnir::whitelist::ImportWhitelistFromCSV;
 
 use crate::handlers::reward_calculation::models::CalculateReoccuringRewards;
+use crate::handlers::reward_expiry::models::ExpireRewards;
 use crate::handlers::utxo_multiplication::models::OptimizeRewardUTxOs;
 
 use self::reward_calculation::reward_calculation;
+use self::reward_expiry::expire_rewards;
 use self::utxo_multiplication::run_optimize;
 
 pub async fn handle_job(job_type: &JobTypes) -> Result<(), MurinError> {
@@ -70,10 +73,16 @@ pub async fn handle_job(job_type: &JobTypes) -> Result<(), MurinError> {
         JobTypes::CalculateReoccuringRewards(job) => {
             let data = serde_json::from_value::<CalculateReoccuringRewards>(job.data.clone())?;
             log::debug!("CalculateReoccuringRewards Data {:?}", data);
-            reward_calculation(data.epoch, data.from)
+            reward_calculation(data.epoch, data.from, data.dry_run, data.recalculate)
                 .await
                 .map_err(|e| e.to_string())?;
         }
+        // Expire unclaimed rewards past the expiry window of their token
+        JobTypes::ExpireRewards(job) => {
+            let data = serde_json::from_value::<ExpireRewards>(job.data.clone())?;
+            log::debug!("ExpireRewards Data {:?}", data);
+            expire_rewards(data.epoch, data.dry_run).await?;
+        }
         // Multiply the UTxOs on a multi signature native script address
         JobTypes::OptimizeRewardUTxOs(job) => {
             let data = serde_json::from_value::<OptimizeRewardUTxOs>(job.data.clone())?;
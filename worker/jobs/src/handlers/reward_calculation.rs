@@ -3,20 +3,22 @@ pub mod reward_handling;
 pub mod stake;
 pub mod whitelists;
 
-use csv::WriterBuilder;
 use drasil_murin::MurinError;
 
-use reward_handling::{check_contract_is_active, get_token_whitelist, handle_lists};
+use reward_handling::{check_contract_is_active, get_token_whitelist, handle_lists, record_runs};
 
 use crate::handlers::reward_calculation::models::RewardTable;
 
-pub async fn reward_calculation(epoch: Option<i64>, from: Option<bool>) -> Result<(), MurinError> {
+pub async fn reward_calculation(
+    epoch: Option<i64>,
+    from: Option<bool>,
+    dry_run: bool,
+    recalculate: bool,
+) -> Result<(), MurinError> {
     pretty_env_logger::init();
 
-    let current_epoch = drasil_mimir::get_epoch(
-        &mut drasil_mimir::establish_connection().map_err(|e| e.to_string())?,
-    )
-    .map_err(|e| e.to_string())? as i64;
+    let provider = drasil_mimir::chain_data();
+    let current_epoch = provider.epoch().map_err(|e| e.to_string())? as i64;
     let calc_epoch = current_epoch - 2;
     log::debug!("Current Epoch: {}", current_epoch);
     log::debug!("Calculation Epoch: {}", calc_epoch);
@@ -25,6 +27,12 @@ pub async fn reward_calculation(epoch: Option<i64>, from: Option<bool>) -> Resul
             "It is not possible to calculate rewards for the current or future epochs",
         ));
     }
+    // later epochs are calculated from the rewards the earlier ones wrote
+    if dry_run && from == Some(true) {
+        return Err(MurinError::new(
+            "A dry run cannot calculate several epochs, run it for one epoch",
+        ));
+    }
 
     let mut i = calc_epoch;
     if epoch.is_some() {
@@ -39,7 +47,15 @@ pub async fn reward_calculation(epoch: Option<i64>, from: Option<bool>) -> Resul
             for mut entry in whitelist {
                 if check_contract_is_active(&entry)? {
                     log::debug!("Entered: {:?}", entry);
-                    handle_lists(&mut entry, i, &mut table).await?
+                    handle_lists(
+                        &mut entry,
+                        i,
+                        &mut table,
+                        recalculate,
+                        dry_run,
+                        provider.as_ref(),
+                    )
+                    .await?
                     //   tokio::spawn(async move {
                     //       if let Err(err) = handle_pools(&mut entry,i).await {
                     //error!(cause = ?err, "calculation error for whitelist entry");
@@ -48,6 +64,9 @@ pub async fn reward_calculation(epoch: Option<i64>, from: Option<bool>) -> Resul
                     //   });
                 }
             }
+            // later epochs build on the rewards of this one
+            report_runs(record_runs(&table, dry_run, recalculate)?, dry_run)?;
+            table.clear();
             i += 1;
         }
         log::debug!(
@@ -61,35 +80,38 @@ pub async fn reward_calculation(epoch: Option<i64>, from: Option<bool>) -> Resul
         log::debug!("Whitelist: {:?}", whitelist);
         for mut entry in whitelist {
             if check_contract_is_active(&entry)? {
-                handle_lists(&mut entry, i, &mut table).await?
+                handle_lists(
+                    &mut entry,
+                    i,
+                    &mut table,
+                    recalculate,
+                    dry_run,
+                    provider.as_ref(),
+                )
+                .await?
             }
         }
+        report_runs(record_runs(&table, dry_run, recalculate)?, dry_run)?;
         log::debug!("Rewards successfully calucalted for epoch: {:?}", i);
     }
 
-    let mut bpath = "/".to_string();
-
-    bpath.push_str(&(calc_epoch.to_string() + "_"));
-    bpath.push_str(&chrono::offset::Utc::now().to_string());
-    bpath.push_str(".csv");
-    let mut wtr2 = WriterBuilder::new().from_writer(vec![]);
-    for entry in table {
-        let mut e = entry.twldata.to_str_vec();
-        e.extend(
-            &mut vec![
-                entry.calc_date.to_string(),
-                entry.current_epoch.to_string(),
-                entry.earned_epoch.to_string(),
-                entry.total_earned_epoch.to_string(),
-            ]
-            .into_iter(),
-        );
+    Ok(())
+}
 
-        wtr2.write_record(&e).map_err(|e| e.to_string())?;
+fn report_runs(
+    runs: Vec<(
+        drasil_gungnir::RewardRunKey,
+        Vec<drasil_gungnir::RewardDiff>,
+    )>,
+    dry_run: bool,
+) -> Result<(), MurinError> {
+    for (key, diff) in runs {
+        if dry_run {
+            log::info!("Dry run for {:?}: {}", key, serde_json::to_string(&diff)?);
+        } else {
+            log::debug!("Reward run for {:?} changed {} rewards", key, diff.len());
+        }
     }
-    // ToDo:
-    // Store calculation Protocol into database
-    let _data = String::from_utf8(wtr2.into_inner().map_err(|e| e.to_string())?)?;
 
     Ok(())
 }
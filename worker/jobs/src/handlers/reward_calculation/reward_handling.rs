@@ -2,8 +2,11 @@ use std::str::FromStr;
 
 use crate::handlers::reward_calculation::stake::handle_pool;
 use crate::handlers::reward_calculation::whitelists::handle_whitelist;
-use bigdecimal::{BigDecimal, FromPrimitive, ToPrimitive};
+use bigdecimal::BigDecimal;
+use drasil_gungnir::{RewardDiff, RewardRun, RewardRunKey};
+use drasil_mimir::ChainDataProvider;
 use drasil_murin::MurinError;
+use drasil_sleipnir::rewards::formulas::{expected_blocks, pool_snapshots};
 use drasil_sleipnir::rewards::models::*;
 
 use super::models::{AddrSrc, RewardTable, TwlData};
@@ -37,106 +40,73 @@ pub(crate) fn handle_rewards(
     token_earned: &BigDecimal,
     table: &mut Vec<RewardTable>,
     no_acc: bool,
+    provider: &dyn ChainDataProvider,
 ) -> Result<(), MurinError> {
+    // A stake address earns only once per token and epoch, the first list it is found on wins
+    let key = twd.run_key();
+    if table
+        .iter()
+        .any(|t| t.stake_addr == *stake_addr && t.run_key() == key)
+    {
+        return Ok(());
+    }
     let mut gconn = drasil_gungnir::establish_connection().map_err(|e| e.to_string())?;
-    log::debug!("Try to find rewards...");
-    let rewards = drasil_gungnir::Rewards::get_rewards_per_token(
+    let (payment_addr, earned) = match RewardRun::earned_by(
         &mut gconn,
+        &key,
         stake_addr,
-        twd.contract_id,
-        twd.user_id,
-        &twd.fingerprint.clone(),
+        token_earned,
+        no_acc,
+        twd.recalculate,
     )
-    .map_err(|e| e.to_string())?;
-    log::debug!("Check rewards...");
-    let mut tot_earned = BigDecimal::from_i32(0).unwrap();
-    match rewards.len() {
-        1 => {
-            if rewards[0].last_calc_epoch < twd.calc_epoch {
-                if no_acc
-                    && drasil_gungnir::Rewards::get_available_rewards(
-                        &mut gconn,
-                        &rewards[0].stake_addr,
-                        &rewards[0].payment_addr,
-                        &rewards[0].fingerprint,
-                        rewards[0].contract_id,
-                        rewards[0].user_id,
-                        token_earned.to_i128().unwrap(),
-                    )
-                    .map_err(|e| e.to_string())?
-                        != -token_earned.to_i128().unwrap()
-                {
-                    drasil_gungnir::Rewards::update_rewards(
-                        &mut gconn,
-                        &rewards[0].stake_addr,
-                        &rewards[0].fingerprint,
-                        &rewards[0].contract_id,
-                        &rewards[0].user_id,
-                        &rewards[0].tot_earned,
-                        &twd.calc_epoch,
-                    )
-                    .map_err(|e| e.to_string())?;
-                    return Ok(());
-                }
-                tot_earned = rewards[0].tot_earned.clone() + token_earned.clone();
-                log::debug!("Earned add: {:?}", tot_earned);
-                let stake_rwd = drasil_gungnir::Rewards::update_rewards(
-                    &mut gconn,
-                    &rewards[0].stake_addr,
-                    &rewards[0].fingerprint,
-                    &rewards[0].contract_id,
-                    &rewards[0].user_id,
-                    &tot_earned,
-                    &twd.calc_epoch,
-                )
-                .map_err(|e| e.to_string())?;
-                log::debug!("Stake Rewards Added : {:?}", stake_rwd);
-            }
-        }
-        0 => {
-            let payment_addr = drasil_mimir::api::select_addr_of_first_transaction(stake_addr)
-                .map_err(|e| e.to_string())?;
-
-            tot_earned = token_earned.to_owned();
-            log::debug!("Earned new: {:?}", tot_earned);
-            let stake_rwd = drasil_gungnir::Rewards::create_rewards(
-                &mut gconn,
-                stake_addr,
-                &payment_addr,
-                &twd.fingerprint,
-                &twd.contract_id,
-                &twd.user_id,
-                &tot_earned,
-                &BigDecimal::from_i32(0).unwrap(),
-                &false,
-                &twd.calc_epoch,
-            );
-            log::debug!("Stake Rewards New: {:?}", stake_rwd);
-        }
-        _ => {
-            return Err(drasil_murin::MurinError::new(
-                "More than one reward entry found on the same contract for the same token",
-            )
-            .into());
-        }
-    }
-    // Store reward calculation to csv
+    .map_err(|e| e.to_string())?
+    {
+        Some(earned) => earned,
+        None => return Ok(()),
+    };
+    let payment_addr = match payment_addr {
+        Some(payment_addr) => payment_addr,
+        None => provider
+            .first_address_of_stake_address(stake_addr)
+            .map_err(|e| e.to_string())?,
+    };
+    log::debug!("Earned: {:?}", earned);
     let table_entry = RewardTable {
         twldata: twd.clone(),
+        stake_addr: stake_addr.clone(),
+        payment_addr,
         calc_date: chrono::offset::Utc::now(),
         calc_epoch: twd.calc_epoch,
         current_epoch: twd.calc_epoch + 2,
-        earned_epoch: token_earned.clone(),
-        total_earned_epoch: tot_earned,
+        earned_epoch: earned,
     };
     table.push(table_entry);
     Ok(())
 }
 
+/// Records the calculated rewards as one reward run per token and epoch
+pub(crate) fn record_runs(
+    table: &[RewardTable],
+    dry_run: bool,
+    recalculate: bool,
+) -> Result<Vec<(RewardRunKey, Vec<RewardDiff>)>, MurinError> {
+    let mut gconn = drasil_gungnir::establish_connection().map_err(|e| e.to_string())?;
+    Ok(RewardRun::record(
+        &mut gconn,
+        &RewardTable::run_inputs(table)?,
+        dry_run,
+        recalculate,
+    )
+    .map_err(|e| e.to_string())?)
+}
+
 pub(crate) async fn handle_lists(
     rwd_token: &mut drasil_gungnir::TokenWhitelist,
     epoch: i64,
     table: &mut Vec<RewardTable>,
+    recalculate: bool,
+    dry_run: bool,
+    provider: &dyn ChainDataProvider,
 ) -> Result<(), MurinError> {
     let spools = rwd_token.pools.clone();
     let mut pools = Vec::<drasil_gungnir::GPools>::new();
@@ -153,19 +123,17 @@ pub(crate) async fn handle_lists(
     whitelists
         .iter()
         .for_each(|n| wlists.push(WhitelistLink::from_str(&n.pool_id).unwrap()));
+    pools.retain(|p| !WhitelistLink::is_wl_link(&p.pool_id));
+    let loyalty_schedule = rwd_token.loyalty_schedule().map_err(|e| e.to_string())?;
 
-    let mut conn = drasil_mimir::establish_connection().map_err(|e| e.to_string())?;
+    let blocks = expected_blocks(rwd_token).map_err(|e| e.to_string())?;
+    let (snapshots, weighted_stake) =
+        pool_snapshots(&pools, epoch, blocks, provider).map_err(|e| e.to_string())?;
 
     // Get total Ada staked from all participating pools
     match rwd_token.mode.clone() {
         drasil_gungnir::Calculationmode::FixedEndEpoch => {
-            let mut total_pools_stake = 0;
-            for pool in pools.clone() {
-                total_pools_stake +=
-                    drasil_mimir::get_pool_total_stake(&mut conn, &pool.pool_id, epoch as i32)
-                        .map_err(|e| e.to_string())?
-                        / 1000000
-            }
+            let total_pools_stake: u64 = snapshots.iter().map(|p| p.active_stake / 1000000).sum();
             rwd_token.modificator_equ = Some(total_pools_stake.to_string());
         }
         drasil_gungnir::Calculationmode::AirDrop => {
@@ -191,12 +159,15 @@ pub(crate) async fn handle_lists(
             rwd_token.end_epoch,
             rwd_token.modificator_equ.clone(),
             epoch,
+            loyalty_schedule.clone(),
+            recalculate,
+            dry_run,
         );
-        handle_whitelist(whitelist, &mut twd, table).await?;
+        handle_whitelist(whitelist, &mut twd, table, provider).await?;
     }
 
     //Hanlde Stakepools
-    for pool in pools {
+    for (pool, snapshot) in pools.into_iter().zip(snapshots.iter()) {
         let mut twd = TwlData::new(
             rwd_token.fingerprint.clone().unwrap(),
             rwd_token.policy_id.clone(),
@@ -211,8 +182,11 @@ pub(crate) async fn handle_lists(
             rwd_token.end_epoch,
             rwd_token.modificator_equ.clone(),
             epoch,
+            loyalty_schedule.clone(),
+            recalculate,
+            dry_run,
         );
-        handle_pool(pool, epoch, &mut twd, table).await?;
+        handle_pool(snapshot, weighted_stake, epoch, &mut twd, table, provider).await?;
     }
 
     Ok(())
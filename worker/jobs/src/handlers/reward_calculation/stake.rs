@@ -1,15 +1,18 @@
 use crate::handlers::reward_calculation::reward_handling::handle_rewards;
 use bigdecimal::{self, BigDecimal, FromPrimitive, ToPrimitive};
+use drasil_mimir::ChainDataProvider;
 use drasil_murin::MurinError;
-use drasil_sleipnir::rewards::models::*;
+use drasil_sleipnir::rewards::formulas::{formulas, PoolSnapshot, StakeSnapshot};
+use std::collections::HashMap;
 use std::str::*;
 
 use super::models::{RewardTable, TwlData};
 
 pub(crate) async fn handle_stake(
-    stake: drasil_mimir::EpochStakeView,
+    stake: StakeSnapshot,
     twd: &TwlData,
     table: &mut Vec<RewardTable>,
+    provider: &dyn ChainDataProvider,
 ) -> Result<(), MurinError> {
     log::debug!("Handle Stake Address: {:?}", stake.stake_addr);
     if stake.amount.to_f64().unwrap() / 1000000.0 < 1.0 {
@@ -27,7 +30,14 @@ pub(crate) async fn handle_stake(
                 };
             }
 
-            handle_rewards(&stake.stake_addr, twd, &token_earned, table, false)?;
+            handle_rewards(
+                &stake.stake_addr,
+                twd,
+                &with_loyalty(twd, stake.streak, token_earned),
+                table,
+                false,
+                provider,
+            )?;
         }
 
         drasil_gungnir::Calculationmode::FixedEndEpoch => {
@@ -40,147 +50,44 @@ pub(crate) async fn handle_stake(
             log::debug!("X: {:?}", x);
             let y = BigDecimal::from_str(&twd.equation).map_err(|e| e.to_string())?;
             log::debug!("Y: {:?}", y);
-            let token_earned = y / x * stake.amount;
-            handle_rewards(&stake.stake_addr, twd, &token_earned, table, false)?;
+            let token_earned = y / x * stake.amount.clone();
+            handle_rewards(
+                &stake.stake_addr,
+                twd,
+                &with_loyalty(twd, stake.streak, token_earned),
+                table,
+                false,
+                provider,
+            )?;
         }
 
         drasil_gungnir::Calculationmode::Custom => {
-            match CustomCalculationTypes::from_str(&twd.equation).unwrap() {
-                CustomCalculationTypes::Freeloaderz => {
-                    log::debug!("Calculating Freeloaderz");
-                    let adastake = stake.amount.to_f64().unwrap() / 1000000.0;
-                    log::debug!(
-                        "Ada Staked: {}, for addr: {} in epoch: {}",
-                        adastake,
-                        stake.stake_addr,
-                        twd.calc_epoch
-                    );
-                    let param: FreeloaderzType =
-                        serde_json::from_str(&twd.modificator_equ.clone().unwrap())?;
-                    if adastake > param.min_stake as f64 {
-                        let token_earned = BigDecimal::from_f64(
-                            ((adastake.powf(param.flatten)) + param.min_earned) * 1000000.0,
-                        )
-                        .unwrap()
-                        .round(0);
-                        log::debug!("Token earned before reward handle: {}", token_earned);
-                        handle_rewards(&stake.stake_addr, twd, &token_earned, table, false)?;
-                        log::debug!("Token earned after reward handle: {}", token_earned);
-                    } else {
-                        log::debug!("delegator below min stake");
-                    }
-                }
-                CustomCalculationTypes::FixedAmountPerEpoch => {
-                    log::debug!("Calcualte with: FixedAmountPerEpoch");
-                    let adastake = stake.amount.to_f64().unwrap() / 1000000.0;
-                    log::debug!(
-                        "Ada Staked: {}, for addr: {} in epoch: {}",
-                        adastake,
-                        stake.stake_addr,
-                        twd.calc_epoch
-                    );
-                    let param: FixedAmountPerEpochType =
-                        serde_json::from_str(&twd.modificator_equ.clone().unwrap())?;
-                    if let Some(min) = param.min_stake {
-                        if adastake > min {
-                            handle_rewards(
-                                &stake.stake_addr,
-                                twd,
-                                &BigDecimal::from_i128(param.amount * 1000000).unwrap(),
-                                table,
-                                false,
-                            )?;
-                        }
-                    } else {
-                        handle_rewards(
-                            &stake.stake_addr,
-                            twd,
-                            &BigDecimal::from_i128(param.amount * 1000000).unwrap(),
-                            table,
-                            false,
-                        )?;
-                    }
-                }
-                CustomCalculationTypes::FixedAmountPerEpochNonAcc => {
-                    log::debug!("Calcualte with: FixedAmountPerEpochNonAcc");
-
-                    let adastake = stake.amount.to_f64().unwrap() / 1000000.0;
-                    log::debug!(
-                        "Ada Staked: {}, for addr: {} in epoch: {}",
-                        adastake,
-                        stake.stake_addr,
-                        twd.calc_epoch
-                    );
-                    let param: FixedAmountPerEpochType =
-                        serde_json::from_str(&twd.modificator_equ.clone().unwrap())?;
-                    if let Some(min) = param.min_stake {
-                        if adastake > min {
-                            handle_rewards(
-                                &stake.stake_addr,
-                                twd,
-                                &BigDecimal::from_i128(param.amount * 1000000).unwrap(),
-                                table,
-                                true,
-                            )?;
-                        }
-                    } else {
-                        handle_rewards(
-                            &stake.stake_addr,
-                            twd,
-                            &BigDecimal::from_i128(param.amount * 1000000).unwrap(),
-                            table,
-                            true,
-                        )?;
-                    }
-                }
-                CustomCalculationTypes::Threshold => {
-                    log::debug!("Calcualte with: Threshold");
-
-                    let adastake = stake.amount.to_f64().unwrap() / 1000000.0;
-                    log::debug!(
-                        "Ada Staked: {}, for addr: {} in epoch: {}",
-                        adastake,
-                        stake.stake_addr,
-                        twd.calc_epoch
-                    );
-                    let param: ThresholdType =
-                        serde_json::from_str(&twd.modificator_equ.clone().unwrap())?;
-                    let token_earned = if adastake >= param.stake_threshold {
-                        param.upper_rwd * 1000000
-                    } else {
-                        param.lower_rwd * 1000000
-                    };
-                    handle_rewards(
-                        &stake.stake_addr,
-                        twd,
-                        &BigDecimal::from_i128(token_earned).unwrap(),
-                        table,
-                        false,
-                    )?;
-                }
-                CustomCalculationTypes::FixedAmountPerEpochCaped => {
-                    log::debug!("Calcualte with: FixedAmountPerEpochCaped");
-                    let adastake = stake.amount.to_i128().unwrap() / 1000000;
-                    log::debug!(
-                        "Ada Staked: {}, for addr: {} in epoch: {}",
-                        adastake,
-                        stake.stake_addr,
-                        twd.calc_epoch
-                    );
-
-                    let param: CapedType =
-                        serde_json::from_str(&twd.modificator_equ.clone().unwrap())?;
-                    let mul = adastake / param.cap_value;
-                    let token_earned = mul * param.rwd * 1000000;
-                    handle_rewards(
-                        &stake.stake_addr,
-                        twd,
-                        &BigDecimal::from_i128(token_earned).unwrap(),
-                        table,
-                        false,
-                    )?;
-                }
-                CustomCalculationTypes::Airdrop => {}
+            let formula = formulas().get(&twd.equation).map_err(|e| e.to_string())?;
+            let params = twd.modificator_equ.clone().unwrap_or_default();
+            log::debug!(
+                "Calculate with: {}, Ada Staked: {}, for addr: {} in epoch: {}",
+                formula.name(),
+                stake.ada(),
+                stake.stake_addr,
+                twd.calc_epoch
+            );
+            if let Some(token_earned) = formula
+                .calculate(&stake, &params)
+                .map_err(|e| e.to_string())?
+            {
+                let earned = if formula.applies_loyalty() {
+                    token_earned
+                } else {
+                    with_loyalty(twd, stake.streak, token_earned)
+                };
+                handle_rewards(
+                    &stake.stake_addr,
+                    twd,
+                    &earned,
+                    table,
+                    !formula.accumulates(),
+                    provider,
+                )?;
             }
         }
         _ => {
@@ -191,18 +98,78 @@ pub(crate) async fn handle_stake(
     Ok(())
 }
 
+/// Applies the loyalty schedule of the token to the tokens a delegator earned
+fn with_loyalty(twd: &TwlData, streak: i64, token_earned: BigDecimal) -> BigDecimal {
+    let multiplier = drasil_gungnir::loyalty_multiplier(&twd.loyalty_schedule, streak);
+    if multiplier == 1.0 {
+        return token_earned;
+    }
+    (token_earned * BigDecimal::from_f64(multiplier).unwrap()).round(0)
+}
+
+/// Consecutive epochs up to `epoch` each delegator of the pool is delegated to it
+/// according to the chain
+fn delegation_streaks(
+    pool: &PoolSnapshot,
+    epoch: i64,
+    provider: &dyn ChainDataProvider,
+) -> Result<HashMap<String, i64>, MurinError> {
+    let mut streaks = HashMap::<String, i64>::new();
+    for deleg in provider
+        .delegations_per_pool(&pool.pool_id, 0, epoch)
+        .map_err(|e| e.to_string())?
+    {
+        let streak = epoch - deleg.active_epoch_no + 1;
+        let entry = streaks.entry(deleg.stake_addr).or_insert(streak);
+        *entry = (*entry).min(streak);
+    }
+    Ok(streaks)
+}
+
 pub(crate) async fn handle_pool(
-    pool: drasil_gungnir::GPools,
+    pool: &PoolSnapshot,
+    weighted_stake: f64,
     epoch: i64,
     twd: &mut TwlData,
     table: &mut Vec<RewardTable>,
+    provider: &dyn ChainDataProvider,
 ) -> Result<(), MurinError> {
     log::debug!("Handle pool: {:?}", pool);
-    let mut conn = drasil_mimir::establish_connection().map_err(|e| e.to_string())?;
-    let pool_stake = drasil_mimir::get_tot_stake_per_pool(&mut conn, &pool.pool_id, epoch as i32)
+    let pool_stake = provider
+        .stake_per_pool(&pool.pool_id, epoch as i32)
+        .map_err(|e| e.to_string())?;
+    let mut gconn = drasil_gungnir::establish_connection().map_err(|e| e.to_string())?;
+    let tracked = drasil_gungnir::DelegationHistory::is_tracked(&mut gconn, &pool.pool_id)
         .map_err(|e| e.to_string())?;
+    // delegators of a pool seen for the first time keep their streak from the chain
+    let seeds = if tracked {
+        HashMap::new()
+    } else {
+        delegation_streaks(pool, epoch, provider)?
+    };
+    let delegators = pool_stake
+        .iter()
+        .map(|s| s.stake_addr.clone())
+        .collect::<Vec<_>>();
+    let streaks = drasil_gungnir::DelegationHistory::track_epoch(
+        &mut gconn,
+        &pool.pool_id,
+        epoch,
+        &delegators,
+        &seeds,
+        twd.dry_run,
+    )
+    .map_err(|e| e.to_string())?;
     for stake in pool_stake {
-        handle_stake(stake, twd, table).await?;
+        let snapshot = StakeSnapshot {
+            streak: streaks.get(&stake.stake_addr).copied().unwrap_or(0),
+            stake_addr: stake.stake_addr,
+            epoch,
+            amount: stake.amount,
+            pool: Some(pool.clone()),
+            weighted_stake,
+        };
+        handle_stake(snapshot, twd, table, provider).await?;
     }
 
     Ok(())
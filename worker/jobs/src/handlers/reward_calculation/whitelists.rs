@@ -1,7 +1,7 @@
-use bigdecimal::{BigDecimal, FromPrimitive};
+use drasil_mimir::ChainDataProvider;
 use drasil_murin::{wallet, MurinError};
+use drasil_sleipnir::rewards::formulas::{formulas, StakeSnapshot};
 use drasil_sleipnir::rewards::models::*;
-use std::str::FromStr;
 
 use crate::handlers::reward_calculation::reward_handling::handle_rewards;
 
@@ -11,6 +11,7 @@ pub(crate) async fn handle_whitelist_address(
     addr: &String,
     twd: &TwlData,
     table: &mut Vec<RewardTable>,
+    provider: &dyn ChainDataProvider,
 ) -> Result<(), MurinError> {
     let stake_addr =
         wallet::reward_address_from_address(&wallet::address_from_string(addr).await?)?
@@ -31,36 +32,26 @@ pub(crate) async fn handle_whitelist_address(
             //            serde_json::from_str(&twd.modificator_equ.clone().unwrap())?;
         }
         drasil_gungnir::Calculationmode::Custom => {
-            match CustomCalculationTypes::from_str(&twd.equation).unwrap() {
-                CustomCalculationTypes::FixedAmountPerEpoch => {
-                    log::debug!("Whitelist calcualte with: FixedAmountPerEpoch");
-
-                    let param: FixedAmountPerEpochType =
-                        serde_json::from_str(&twd.modificator_equ.clone().unwrap())?;
-
-                    handle_rewards(
-                        &stake_addr,
-                        twd,
-                        &BigDecimal::from_i128(param.amount * 1000000).unwrap(),
-                        table,
-                        false,
-                    )?;
-                }
-                CustomCalculationTypes::FixedAmountPerEpochNonAcc => {
-                    log::debug!("Whitelist calcualte with: FixedAmountPerEpochNonAcc");
-                    let param: FixedAmountPerEpochType =
-                        serde_json::from_str(&twd.modificator_equ.clone().unwrap())?;
-                    handle_rewards(
-                        &stake_addr,
-                        twd,
-                        &BigDecimal::from_i128(param.amount * 1000000).unwrap(),
-                        table,
-                        true,
-                    )?;
-                }
-                _ => {
-                    // stake related calculation modes are not supported for whitelists
-                }
+            let formula = formulas().get(&twd.equation).map_err(|e| e.to_string())?;
+            if !formula.whitelists() {
+                // stake related calculation modes are not supported for whitelists
+                return Ok(());
+            }
+            log::debug!("Whitelist calculate with: {}", formula.name());
+            let params = twd.modificator_equ.clone().unwrap_or_default();
+            let stake = StakeSnapshot::whitelisted(&stake_addr, twd.calc_epoch);
+            if let Some(token_earned) = formula
+                .calculate(&stake, &params)
+                .map_err(|e| e.to_string())?
+            {
+                handle_rewards(
+                    &stake_addr,
+                    twd,
+                    &token_earned,
+                    table,
+                    !formula.accumulates(),
+                    provider,
+                )?;
             }
         }
         _ => {
@@ -74,12 +65,13 @@ pub(crate) async fn handle_whitelist(
     wl_link: WhitelistLink,
     twd: &mut TwlData,
     table: &mut Vec<RewardTable>,
+    provider: &dyn ChainDataProvider,
 ) -> Result<(), MurinError> {
     log::debug!("Handle whitelist: {:?}", wl_link);
     let addr_list = drasil_gungnir::WlAlloc::get_whitelist(&wl_link.id)
         .map_err(|_| MurinError::new("Error while trying to handle the whitelist"))?;
     for addr in addr_list {
-        handle_whitelist_address(&addr, twd, table).await?;
+        handle_whitelist_address(&addr, twd, table, provider).await?;
     }
 
     Ok(())
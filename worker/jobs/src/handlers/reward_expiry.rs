@@ -0,0 +1,40 @@
+pub mod models;
+
+use drasil_murin::MurinError;
+
+pub async fn expire_rewards(epoch: Option<i64>, dry_run: bool) -> Result<(), MurinError> {
+    let current_epoch = drasil_mimir::chain_data()
+        .epoch()
+        .map_err(|e| e.to_string())? as i64;
+    let epoch = epoch.unwrap_or(current_epoch);
+    if epoch > current_epoch {
+        return Err(MurinError::new(
+            "It is not possible to expire rewards for future epochs",
+        ));
+    }
+
+    let mut gconn = drasil_gungnir::establish_connection().map_err(|e| e.to_string())?;
+    let mut whitelist =
+        drasil_gungnir::TokenWhitelist::get_whitelist().map_err(|e| e.to_string())?;
+    whitelist.retain(|w| w.expiry_epochs.is_some());
+    for twl in whitelist {
+        let expired = drasil_gungnir::Rewards::expire_rewards(&mut gconn, &twl, epoch, dry_run)
+            .map_err(|e| e.to_string())?;
+        if dry_run {
+            log::info!(
+                "Dry run expiry for whitelist entry {} in epoch {}: {}",
+                twl.id,
+                epoch,
+                serde_json::to_string(&expired)?
+            );
+        } else {
+            log::debug!(
+                "Expired rewards of {} stake addresses for whitelist entry {}",
+                expired.len(),
+                twl.id
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug, Serialize)]
+pub struct ExpireRewards {
+    /// Epoch to expire the rewards in, the current epoch if not set
+    pub epoch: Option<i64>,
+    /// Report the expired rewards without writing them
+    #[serde(default)]
+    pub dry_run: bool,
+}
@@ -8,7 +8,9 @@ pub type Result<T> = std::result::Result<T, MurinError>;
 
 pub async fn optimize(addr: String, uid: i64, cid: i64) -> Result<()> {
     log::debug!("Try to connect to dbsync...");
-    let contract_utxos = drasil_mimir::get_address_utxos(&addr).map_err(|e| e.to_string())?;
+    let contract_utxos = drasil_mimir::chain_data()
+        .address_utxos(&addr)
+        .map_err(|e| e.to_string())?;
     log::debug!("Calculate thresholds...");
     let ada_utxos = contract_utxos.get_coin_only();
     let mut t_utxos = contract_utxos.get_token_only();
@@ -410,10 +412,9 @@ fn finalize_tx(
     let a = drasil_murin::clib::utils::to_bignum(44u64);
     let b = drasil_murin::clib::utils::to_bignum(155381u64);
 
-    let slot = drasil_mimir::get_slot(
-        &mut drasil_mimir::establish_connection().map_err(|e| e.to_string())?,
-    )
-    .map_err(|e| e.to_string())? as u64
+    let slot = drasil_mimir::chain_data()
+        .slot()
+        .map_err(|e| e.to_string())? as u64
         + 3600;
     let network = match addr.network_id()? {
         1 => drasil_murin::clib::NetworkId::mainnet(),
@@ -473,8 +474,9 @@ pub async fn run_optimize(contract_id: i64) -> Result<()> {
     'outer: for contract in contracts {
         // ToDo: Use Liquidity
         //let _lq = contract.get_contract_liquidity();
-        let utxos =
-            drasil_mimir::get_address_utxos(&contract.address).map_err(|e| e.to_string())?;
+        let utxos = drasil_mimir::chain_data()
+            .address_utxos(&contract.address)
+            .map_err(|e| e.to_string())?;
 
         let twl = drasil_gungnir::TokenWhitelist::get_whitelist().map_err(|e| e.to_string())?;
 
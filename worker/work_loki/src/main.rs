@@ -6,7 +6,7 @@ mod models;
 use std::env;
 
 use deadpool_lapin::Pool;
-use drasil_gungnir::minting::models::MintReward;
+use drasil_gungnir::minting::models::{ClaimResult, MintPhase, Nft, NftClaim};
 use drasil_murin::{clib::Assets, utils::to_bignum, wallet, AssetName, MultiAsset, PolicyID};
 use lapin::ConnectionProperties;
 use lazy_static::lazy_static;
@@ -109,7 +109,8 @@ async fn init_rmq_listen(pool: Pool) -> Result<(), error::Error> {
                 }
             };
 
-            if !mp.active {
+            // a reveal project mints only once its metadata pool is committed
+            if !mp.active || mp.reveal_uncommitted() {
                 log::error!("requesed to mint on an inactive project");
                 channel
                     .basic_ack(
@@ -178,12 +179,56 @@ async fn init_rmq_listen(pool: Pool) -> Result<(), error::Error> {
                 }
             };
 
+            // projects with sale phases only accept claims in an open phase
+            let phases = MintPhase::get_phases(mp.id)?;
+            let has_phases = !phases.is_empty();
+            let phase = MintPhase::active_phase(phases, drasil_mimir::chain_data().slot()?);
+            if phase.is_none() && has_phases {
+                log::error!("no sale phase of mint project {} is open", mp.id);
+                channel
+                    .basic_ack(
+                        deliv.delivery_tag,
+                        lapin::options::BasicAckOptions::default(),
+                    )
+                    .await?;
+                return Err(crate::error::Error::Custom(
+                    "no sale phase is open, request discarded".to_owned(),
+                ));
+            }
+
+            // token gated phases require a token of the gate policy in the wallet
+            if let Some(policy) = phase.as_ref().and_then(|p| p.gate_policy.as_ref()) {
+                let policy = PolicyID::from_hex(policy)?;
+                let utxos = drasil_mimir::chain_data().stake_address_utxos(&stake_address)?;
+                let holds_token = (0..utxos.len()).any(|i| {
+                    utxos
+                        .get(i)
+                        .output()
+                        .amount()
+                        .multiasset()
+                        .and_then(|ma| ma.get(&policy))
+                        .is_some()
+                });
+                if !holds_token {
+                    log::error!("requesting wallet holds no token of the gate policy");
+                    return Err(crate::error::Error::Custom(
+                        "requesting wallet holds no token of the gate policy".to_owned(),
+                    ));
+                }
+            }
+
             // get first payment address
             let mut payment_addr =
-                drasil_mimir::api::select_addr_of_first_transaction(&stake_address)?;
+                drasil_mimir::chain_data().first_address_of_stake_address(&stake_address)?;
+
+            // a phase replaces the whitelists of the project with its own
+            let whitelists = match &phase {
+                Some(p) => p.whitelist_id.map(|w| vec![w]),
+                None => mp.whitelists.clone(),
+            };
 
             // check whitelists
-            let valid_addresses = if let Some(wl) = mp.whitelists.clone() {
+            let valid_addresses = if let Some(wl) = whitelists.clone() {
                 let mut va = Vec::<(drasil_gungnir::WlEntry, i64)>::new();
 
                 for w in wl {
@@ -218,82 +263,76 @@ async fn init_rmq_listen(pool: Pool) -> Result<(), error::Error> {
             } else {
                 None
             };
-            if valid_addresses.is_none() && mp.whitelists.is_some() {
+            if valid_addresses.is_none() && whitelists.is_some() {
                 log::error!("requesting wallet is not whitelisted");
                 return Err(crate::error::Error::Custom(
                     "requesting wallet is not whitelisted".to_owned(),
                 ));
             }
 
-            if let Some(i) = mp.max_mint_p_addr {
-                let nfts = drasil_gungnir::minting::models::Nft::get_nft_by_claim_addr(
-                    mp.id,
-                    &payment_addr,
-                    &mp.nft_table_name,
-                )?;
-                log::debug!("already minted NFTs: {:?}", nfts);
-                if nfts.len() >= i as usize {
-                    log::error!("reached maximum allowed mints: {}", payment_addr);
+            let mint_contract = drasil_hugin::database::TBContracts::get_contract_uid_cid(
+                mp.user_id,
+                mp.mint_contract_id,
+            )?;
+            let policy = PolicyID::from_hex(&mint_contract.policy_id.unwrap())?;
+            let value_of = |asset_name_b: &[u8]| -> Result<Vec<u8>, drasil_gungnir::RWDError> {
+                let mut assets = Assets::new();
+                let name = AssetName::new(asset_name_b.to_vec())
+                    .map_err(|e| drasil_gungnir::RWDError::new(&format!("{e:?}")))?;
+                assets.insert(&name, &to_bignum(1));
+                let mut ma = MultiAsset::new();
+                ma.insert(&policy, &assets);
+                let mut mint_value = drasil_murin::clib::utils::Value::zero();
+                mint_value.set_multiasset(&ma);
+                Ok(mint_value.to_bytes())
+            };
+
+            // the limits of the project and phase are enforced with the claim
+            let claim = NftClaim {
+                pay_addr: payment_addr.clone(),
+                stake_addr: Some(stake_address.clone()),
+                wl_entry: valid_addresses
+                    .as_ref()
+                    .and_then(|va| va.first())
+                    .map(|(entry, _)| entry.id),
+                phase,
+            };
+            log::debug!("try to claim nft ...");
+            let result = Nft::claim_nft_within_limits(&mp, &claim, value_of).await;
+            reply(&channel, &deliv, &result).await?;
+            match result {
+                Ok(ClaimResult::Claimed(reward)) => {
+                    log::debug!("claimed: {:?}", reward);
+                }
+                Ok(ClaimResult::LimitReached(limit)) => {
+                    log::error!(
+                        "reached maximum allowed mints per {} ({}/{}): {}",
+                        limit.limit,
+                        limit.claimed,
+                        limit.max,
+                        payment_addr
+                    );
                     channel
                         .basic_reject(
                             deliv.delivery_tag,
                             lapin::options::BasicRejectOptions::default(),
                         )
                         .await?;
-                    return Err(crate::error::Error::Custom(
-                        "reached maximum allowed mints".to_owned(),
-                    ));
+                    return Err(crate::error::Error::Custom(format!(
+                        "reached maximum allowed mints per {}",
+                        limit.limit
+                    )));
                 }
-            }
-
-            log::debug!("try to claim nft ...");
-            let nft = match drasil_gungnir::minting::models::Nft::claim_random_unminted_nft(
-                mp.id,
-                &mp.nft_table_name,
-                &payment_addr,
-                0,
-            )
-            .await
-            {
-                Ok(o) => o,
-                Err(_) => {
+                result => {
+                    if let Err(e) = result {
+                        log::error!("could not claim nft: {}", e);
+                    }
                     channel
                         .basic_reject(
                             deliv.delivery_tag,
                             lapin::options::BasicRejectOptions::default(),
                         )
                         .await?;
-                    None
-                }
-            };
-
-            let mint_contract = drasil_hugin::database::TBContracts::get_contract_uid_cid(
-                mp.user_id,
-                mp.mint_contract_id,
-            )?;
-
-            match nft {
-                Some(n) => {
-                    log::debug!("nft: {:?}", n);
-                    let mut mint_value = drasil_murin::clib::utils::Value::zero();
-                    let mut assets = Assets::new();
-                    assets.insert(&AssetName::new(n.asset_name_b.clone())?, &to_bignum(1));
-                    let mut ma = MultiAsset::new();
-                    ma.insert(
-                        &PolicyID::from_hex(&mint_contract.policy_id.unwrap()).unwrap(),
-                        &assets,
-                    );
-                    mint_value.set_multiasset(&ma);
-
-                    MintReward::create_mintreward(
-                        mp.user_id,
-                        mp.mint_contract_id,
-                        &payment_addr,
-                        vec![&n.asset_name_b],
-                        vec![&mint_value.to_bytes()],
-                    )?;
-                }
-                None => {
                     return Err(crate::error::Error::Custom(
                         "Could not reserve NFT".to_owned(),
                     ));
@@ -310,3 +349,39 @@ async fn init_rmq_listen(pool: Pool) -> Result<(), error::Error> {
     }
     Ok(())
 }
+
+/// Sends the result of a claim to the reply queue of the loki instance which
+/// queued it, loki forwards it to the websocket of the client
+async fn reply(
+    channel: &lapin::Channel,
+    deliv: &lapin::message::Delivery,
+    result: &Result<ClaimResult, drasil_gungnir::RWDError>,
+) -> Result<(), error::Error> {
+    let (Some(reply_to), Some(correlation_id)) = (
+        deliv.properties.reply_to(),
+        deliv.properties.correlation_id(),
+    ) else {
+        return Ok(());
+    };
+    let response = match result {
+        Ok(ClaimResult::Claimed(reward)) => {
+            serde_json::json!({ "status": "claimed", "mint_reward": reward.id })
+        }
+        Ok(ClaimResult::LimitReached(limit)) => {
+            serde_json::json!({ "status": "limit reached", "limits": [limit] })
+        }
+        Ok(ClaimResult::SoldOut) => serde_json::json!({ "status": "sold out" }),
+        Err(_) => serde_json::json!({ "status": "failed", "error": "could not reserve NFT" }),
+    };
+    channel
+        .basic_publish(
+            "",
+            reply_to.as_str(),
+            lapin::options::BasicPublishOptions::default(),
+            response.to_string().as_bytes(),
+            lapin::BasicProperties::default().with_correlation_id(correlation_id.clone()),
+        )
+        .await?
+        .await?;
+    Ok(())
+}